@@ -11,40 +11,29 @@ enum PrefixSetting {
     NoPrefix,
 }
 
-fn build_dfa_with_setting(query: &str, setting: PrefixSetting) -> DFA {
+fn build_dfa_with_setting(query: &str, typos: u8, setting: PrefixSetting) -> DFA {
     use PrefixSetting::{NoPrefix, Prefix};
 
-    match query.len() {
-        0..=4 => {
-            let builder = LEVDIST0.get_or_init(|| LevBuilder::new(0, true));
-            match setting {
-                Prefix => builder.build_prefix_dfa(query),
-                NoPrefix => builder.build_dfa(query),
-            }
-        }
-        5..=8 => {
-            let builder = LEVDIST1.get_or_init(|| LevBuilder::new(1, true));
-            match setting {
-                Prefix => builder.build_prefix_dfa(query),
-                NoPrefix => builder.build_dfa(query),
-            }
-        }
-        _ => {
-            let builder = LEVDIST2.get_or_init(|| LevBuilder::new(2, true));
-            match setting {
-                Prefix => builder.build_prefix_dfa(query),
-                NoPrefix => builder.build_dfa(query),
-            }
-        }
+    let builder = match typos {
+        0 => LEVDIST0.get_or_init(|| LevBuilder::new(0, true)),
+        1 => LEVDIST1.get_or_init(|| LevBuilder::new(1, true)),
+        _ => LEVDIST2.get_or_init(|| LevBuilder::new(2, true)),
+    };
+
+    match setting {
+        Prefix => builder.build_prefix_dfa(query),
+        NoPrefix => builder.build_dfa(query),
     }
 }
 
-pub fn build_prefix_dfa(query: &str) -> DFA {
-    build_dfa_with_setting(query, PrefixSetting::Prefix)
+/// Builds a DFA accepting the words starting with the query with at most `typos` typos.
+pub fn build_prefix_dfa(query: &str, typos: u8) -> DFA {
+    build_dfa_with_setting(query, typos, PrefixSetting::Prefix)
 }
 
-pub fn build_dfa(query: &str) -> DFA {
-    build_dfa_with_setting(query, PrefixSetting::NoPrefix)
+/// Builds a DFA accepting the query with at most `typos` typos.
+pub fn build_dfa(query: &str, typos: u8) -> DFA {
+    build_dfa_with_setting(query, typos, PrefixSetting::NoPrefix)
 }
 
 pub fn build_exact_dfa(query: &str) -> DFA {
@@ -1,13 +1,13 @@
 mod dfa;
 
-use meilisearch_tokenizer::is_cjk;
+use meilisearch_tokenizer::is_unspaced;
 
 pub use self::dfa::{build_dfa, build_prefix_dfa, build_exact_dfa};
 
 pub fn normalize_str(string: &str) -> String {
     let mut string = string.to_lowercase();
 
-    if !string.contains(is_cjk) {
+    if !string.contains(is_unspaced) {
         string = deunicode::deunicode_with_tofu(&string, "");
     }
 
@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::cmp;
 use std::collections::HashMap;
 use std::mem;
 use std::ops::Deref;
@@ -11,17 +12,20 @@ use std::fmt;
 use compact_arena::{SmallArena, Idx32, mk_arena};
 use log::debug;
 use meilisearch_types::DocIndex;
+use serde::Serialize;
 use sdset::{Set, SetBuf, exponential_search, SetOperation, Counter, duo::OpBuilder};
 use slice_group_by::{GroupBy, GroupByMut};
 
 use crate::error::Error;
-use crate::criterion::{Criteria, Context, ContextMut};
+use crate::facets::FacetStats;
+use crate::criterion::{AttributeWeights, Criteria, CriterionDetails, Context, ContextMut};
 use crate::distinct_map::{BufferedDistinctMap, DistinctMap};
 use crate::raw_document::RawDocument;
+use crate::settings::{TypoTolerance, DEFAULT_FOLD_DIACRITICS, DEFAULT_STOP_WORDS_IN_PHRASES};
 use crate::{database::MainT, reordered_attrs::ReorderedAttrs};
 use crate::{store, Document, DocumentId, MResult};
 use crate::query_tree::{create_query_tree, traverse_query_tree};
-use crate::query_tree::{Operation, QueryResult, QueryKind, QueryId, PostingsKey};
+use crate::query_tree::{Operation, Query, QueryResult, QueryKind, QueryId, Postings, PostingsKey};
 use crate::query_tree::Context as QTContext;
 
 #[derive(Debug, Default)]
@@ -30,20 +34,86 @@ pub struct SortResult {
     pub nb_hits: usize,
     pub exhaustive_nb_hit: bool,
     pub facets: Option<HashMap<String, HashMap<String, usize>>>,
+    pub facets_stats: Option<HashMap<String, FacetStats>>,
     pub exhaustive_facet_count: Option<bool>,
+    /// The time budget was spent before the documents were completely sorted,
+    /// the documents returned are the best ones found so far.
+    pub degraded: bool,
+    pub profile: SearchProfile,
+}
+
+/// The time spent in each stage of a search, in milliseconds,
+/// along with the number of candidates handled by these stages.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchProfile {
+    pub query_tree_ms: f64,
+    pub postings_fetch_ms: f64,
+    pub candidates: usize,
+    pub facet_filtered_candidates: usize,
+    pub matches_cleanup_ms: f64,
+    pub raw_documents_ms: f64,
+    pub criteria: Vec<CriterionProfile>,
+    pub documents_building_ms: f64,
+}
+
+/// The time spent by a criterion over all the groups it sorted.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CriterionProfile {
+    pub name: String,
+    pub preparation_ms: f64,
+    pub evaluation_ms: f64,
+    pub sorted_documents: usize,
+    pub groups: usize,
+}
+
+fn elapsed_ms(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
+
+/// Whether the time budget of the search is spent.
+fn deadline_reached(deadline: Option<Instant>) -> bool {
+    deadline.map_or(false, |deadline| Instant::now() >= deadline)
+}
+
+/// The typo tolerance of the index along with the sorted indexed positions
+/// of the attributes in which typos are not allowed.
+fn typo_tolerance(reader: &heed::RoTxn<MainT>, main_store: store::Main) -> MResult<(TypoTolerance, Vec<u16>)> {
+    let typo_tolerance = main_store.typo_tolerance(reader)?.unwrap_or_default();
+
+    let mut attributes = Vec::new();
+    if !typo_tolerance.disable_on_attributes.is_empty() {
+        if let Some(schema) = main_store.schema(reader)? {
+            for name in &typo_tolerance.disable_on_attributes {
+                if let Some(pos) = schema.id(name).and_then(|id| schema.is_indexed(id)) {
+                    attributes.push(pos.0);
+                }
+            }
+        }
+    }
+    attributes.sort_unstable();
+
+    Ok((typo_tolerance, attributes))
 }
 
 pub fn bucket_sort<'c, FI>(
     reader: &heed::RoTxn<MainT>,
     query: &str,
+    language: Option<String>,
     range: Range<usize>,
     facets_docids: Option<SetBuf<DocumentId>>,
     facet_count_docids: Option<HashMap<String, HashMap<String, Cow<Set<DocumentId>>>>>,
     filter: Option<FI>,
-    criteria: Criteria<'c>,
+    criteria: &Criteria<'c>,
     searchable_attrs: Option<ReorderedAttrs>,
+    attribute_weights: Option<&AttributeWeights>,
+    exhaustive_nb_hits: bool,
+    deadline: Option<Instant>,
+    ranking_score_details: bool,
     main_store: store::Main,
     postings_lists_store: store::PostingsLists,
+    documents_fields_store: store::DocumentsFields,
     documents_fields_counts_store: store::DocumentsFieldsCounts,
     synonyms_store: store::Synonyms,
     prefix_documents_cache_store: store::PrefixDocumentsCache,
@@ -60,6 +130,7 @@ where
         return bucket_sort_with_distinct(
             reader,
             query,
+            language,
             range,
             facets_docids,
             facet_count_docids,
@@ -68,8 +139,13 @@ where
             distinct_size,
             criteria,
             searchable_attrs,
+            attribute_weights,
+            exhaustive_nb_hits,
+            deadline,
+            ranking_score_details,
             main_store,
             postings_lists_store,
+            documents_fields_store,
             documents_fields_counts_store,
             synonyms_store,
             prefix_documents_cache_store,
@@ -79,22 +155,38 @@ where
 
     let mut result = SortResult::default();
 
-    let words_set = match unsafe { main_store.static_words_fst(reader)? } {
+    let words_set = match main_store.words_fst(reader)? {
         Some(words) => words,
         None => return Ok(SortResult::default()),
     };
 
+    let words_delta = main_store.words_delta_fst(reader)?.unwrap_or_default();
     let stop_words = main_store.stop_words_fst(reader)?.unwrap_or_default();
+    let (typo_tolerance, typo_disabled_attributes) = typo_tolerance(reader, main_store)?;
+    let fold_diacritics = main_store.fold_diacritics(reader)?.unwrap_or(DEFAULT_FOLD_DIACRITICS);
+    let prefix_search = main_store.prefix_search(reader)?.unwrap_or_default();
+    let stop_words_in_phrases = main_store.stop_words_in_phrases(reader)?.unwrap_or(DEFAULT_STOP_WORDS_IN_PHRASES);
+    let token_policy = main_store.token_policy(reader)?.unwrap_or_default();
 
     let context = QTContext {
         words_set,
+        words_delta,
         stop_words,
         synonyms: synonyms_store,
+        language,
         postings_lists: postings_lists_store,
         prefix_postings_lists: prefix_postings_lists_cache_store,
+        typo_tolerance,
+        prefix_search,
+        fold_diacritics,
+        stop_words_in_phrases,
+        token_policy,
+        typo_disabled_attributes,
     };
 
+    let before_query_tree = Instant::now();
     let (operation, mapping) = create_query_tree(reader, &context, query)?;
+    result.profile.query_tree_ms = elapsed_ms(before_query_tree);
     debug!("operation:\n{:?}", operation);
     debug!("mapping:\n{:?}", mapping);
 
@@ -109,10 +201,30 @@ where
     let mut queries_kinds = HashMap::new();
     recurs_operation(&mut queries_kinds, &operation);
 
-    let QueryResult { mut docids, queries } = traverse_query_tree(reader, &context, &operation)?;
+    let before_postings_fetch = Instant::now();
+    let placeholder = mapping.is_empty();
+    let QueryResult { mut docids, queries } = if placeholder {
+        placeholder_candidates(reader, documents_fields_store)?
+    } else {
+        traverse_query_tree(reader, &context, &operation)?
+    };
+    if let Some(searchable_attrs) = searchable_attrs.as_ref().filter(|_| !placeholder) {
+        let restricted = restricted_docids(&operation, &queries, searchable_attrs);
+        docids = Cow::Owned(OpBuilder::new(docids.as_ref(), restricted.as_set()).intersection().into_set_buf());
+    }
+    result.profile.postings_fetch_ms = elapsed_ms(before_postings_fetch);
+    result.profile.candidates = docids.len();
     debug!("found {} documents", docids.len());
     debug!("number of postings {:?}", queries.len());
 
+    // the facet counts are computed before the facet filter is applied, the facet documents ids
+    // are already restricted by the facet filter clauses that do not concern their own facet.
+    if let Some(f) = facet_count_docids {
+        // hardcoded value, until approximation optimization
+        result.exhaustive_facet_count = Some(true);
+        result.facets = Some(facet_count(f, &docids));
+    }
+
     if let Some(facets_docids) = facets_docids {
         let intersection = sdset::duo::OpBuilder::new(docids.as_ref(), facets_docids.as_set())
             .intersection()
@@ -120,15 +232,12 @@ where
         docids = Cow::Owned(intersection);
     }
 
-    if let Some(f) = facet_count_docids {
-        // hardcoded value, until approximation optimization
-        result.exhaustive_facet_count = Some(true);
-        result.facets = Some(facet_count(f, &docids));
-    }
+    result.profile.facet_filtered_candidates = docids.len();
 
     let before = Instant::now();
     mk_arena!(arena);
     let mut bare_matches = cleanup_bare_matches(&mut arena, &docids, queries);
+    result.profile.matches_cleanup_ms = elapsed_ms(before);
     debug!("matches cleaned in {:.02?}", before.elapsed());
 
     let before_bucket_sort = Instant::now();
@@ -139,6 +248,10 @@ where
         let raw_document = RawDocument::new(bare_matches, &mut arena, searchable_attrs.as_ref());
         raw_documents.push(raw_document);
     }
+    if placeholder {
+        raw_documents.extend(docids.iter().map(|id| RawDocument::without_matches(*id)));
+    }
+    result.profile.raw_documents_ms = elapsed_ms(before_raw_documents_building);
     debug!("creating {} candidates documents took {:.02?}",
         raw_documents.len(),
         before_raw_documents_building.elapsed(),
@@ -150,10 +263,19 @@ where
     let mut groups = vec![raw_documents.as_mut_slice()];
 
     'criteria: for criterion in criteria.as_ref() {
+        result.profile.criteria.push(CriterionProfile { name: criterion.name().to_string(), ..Default::default() });
+        let criterion_profile = result.profile.criteria.last_mut().unwrap();
         let tmp_groups = mem::replace(&mut groups, Vec::new());
         let mut documents_seen = 0;
 
         for mut group in tmp_groups {
+            // the groups left are kept in the order of the previous criteria
+            if result.degraded || deadline_reached(deadline) {
+                result.degraded = true;
+                groups.push(group);
+                continue;
+            }
+
             let before_criterion_preparation = Instant::now();
 
             let ctx = ContextMut {
@@ -164,18 +286,23 @@ where
             };
 
             criterion.prepare(ctx, &mut group)?;
+            criterion_profile.preparation_ms += elapsed_ms(before_criterion_preparation);
             debug!("{:?} preparation took {:.02?}", criterion.name(), before_criterion_preparation.elapsed());
 
             let ctx = Context {
                 postings_lists: &arena,
                 query_mapping: &mapping,
+                attribute_weights,
             };
 
             let before_criterion_sort = Instant::now();
             group.sort_unstable_by(|a, b| criterion.evaluate(&ctx, a, b));
+            criterion_profile.evaluation_ms += elapsed_ms(before_criterion_sort);
+            criterion_profile.sorted_documents += group.len();
             debug!("{:?} evaluation took {:.02?}", criterion.name(), before_criterion_sort.elapsed());
 
             for group in group.binary_group_by_mut(|a, b| criterion.eq(&ctx, a, b)) {
+                criterion_profile.groups += 1;
                 debug!("{:?} produced a group of size {}", criterion.name(), group.len());
 
                 documents_seen += group.len();
@@ -193,15 +320,38 @@ where
     debug!("criterion loop took {:.02?}", before_criterion_loop.elapsed());
     debug!("proximity evaluation called {} times", proximity_count.load(Ordering::Relaxed));
 
+    let before_documents_building = Instant::now();
     let schema = main_store.schema(reader)?.ok_or(Error::SchemaMissing)?;
-    let iter = raw_documents.into_iter().skip(range.start).take(range.len());
-    let iter = iter.map(|rd| Document::from_raw(rd, &queries_kinds, &arena, searchable_attrs.as_ref(), &schema));
-    let documents = iter.collect();
+    let mut documents = Vec::with_capacity(range.len());
+    for mut raw_document in raw_documents.into_iter().skip(range.start).take(range.len()) {
+        let details = if ranking_score_details {
+            let details = criteria_details(
+                reader,
+                criteria,
+                &mut arena,
+                &mapping,
+                attribute_weights,
+                documents_fields_counts_store,
+                &mut raw_document,
+            )?;
+            Some(details)
+        } else {
+            None
+        };
+        let score = normalized_score(&raw_document, &arena, &mapping, attribute_weights);
+        let mut document = Document::from_raw(raw_document, &queries_kinds, &arena, searchable_attrs.as_ref(), &schema);
+        document.score = score;
+        document.ranking_score_details = details;
+        documents.push(document);
+    }
+    result.profile.documents_building_ms = elapsed_ms(before_documents_building);
 
     debug!("bucket sort took {:.02?}", before_bucket_sort.elapsed());
 
     result.documents = documents;
+    // without filter nor distinct rule all the candidates are hits
     result.nb_hits = docids.len();
+    result.exhaustive_nb_hit = true;
 
     Ok(result)
 }
@@ -209,16 +359,22 @@ where
 pub fn bucket_sort_with_distinct<'c, FI, FD>(
     reader: &heed::RoTxn<MainT>,
     query: &str,
+    language: Option<String>,
     range: Range<usize>,
     facets_docids: Option<SetBuf<DocumentId>>,
     facet_count_docids: Option<HashMap<String, HashMap<String, Cow<Set<DocumentId>>>>>,
     filter: Option<FI>,
     distinct: FD,
     distinct_size: usize,
-    criteria: Criteria<'c>,
+    criteria: &Criteria<'c>,
     searchable_attrs: Option<ReorderedAttrs>,
+    attribute_weights: Option<&AttributeWeights>,
+    exhaustive_nb_hits: bool,
+    deadline: Option<Instant>,
+    ranking_score_details: bool,
     main_store: store::Main,
     postings_lists_store: store::PostingsLists,
+    documents_fields_store: store::DocumentsFields,
     documents_fields_counts_store: store::DocumentsFieldsCounts,
     synonyms_store: store::Synonyms,
     _prefix_documents_cache_store: store::PrefixDocumentsCache,
@@ -230,22 +386,38 @@ where
 {
     let mut result = SortResult::default();
 
-    let words_set = match unsafe { main_store.static_words_fst(reader)? } {
+    let words_set = match main_store.words_fst(reader)? {
         Some(words) => words,
         None => return Ok(SortResult::default()),
     };
 
+    let words_delta = main_store.words_delta_fst(reader)?.unwrap_or_default();
     let stop_words = main_store.stop_words_fst(reader)?.unwrap_or_default();
+    let (typo_tolerance, typo_disabled_attributes) = typo_tolerance(reader, main_store)?;
+    let fold_diacritics = main_store.fold_diacritics(reader)?.unwrap_or(DEFAULT_FOLD_DIACRITICS);
+    let prefix_search = main_store.prefix_search(reader)?.unwrap_or_default();
+    let stop_words_in_phrases = main_store.stop_words_in_phrases(reader)?.unwrap_or(DEFAULT_STOP_WORDS_IN_PHRASES);
+    let token_policy = main_store.token_policy(reader)?.unwrap_or_default();
 
     let context = QTContext {
         words_set,
+        words_delta,
         stop_words,
         synonyms: synonyms_store,
+        language,
         postings_lists: postings_lists_store,
         prefix_postings_lists: prefix_postings_lists_cache_store,
+        typo_tolerance,
+        prefix_search,
+        fold_diacritics,
+        stop_words_in_phrases,
+        token_policy,
+        typo_disabled_attributes,
     };
 
+    let before_query_tree = Instant::now();
     let (operation, mapping) = create_query_tree(reader, &context, query)?;
+    result.profile.query_tree_ms = elapsed_ms(before_query_tree);
     debug!("operation:\n{:?}", operation);
     debug!("mapping:\n{:?}", mapping);
 
@@ -260,10 +432,40 @@ where
     let mut queries_kinds = HashMap::new();
     recurs_operation(&mut queries_kinds, &operation);
 
-    let QueryResult { mut docids, queries } = traverse_query_tree(reader, &context, &operation)?;
+    let before_postings_fetch = Instant::now();
+    let placeholder = mapping.is_empty();
+    let QueryResult { mut docids, queries } = if placeholder {
+        placeholder_candidates(reader, documents_fields_store)?
+    } else {
+        traverse_query_tree(reader, &context, &operation)?
+    };
+    if let Some(searchable_attrs) = searchable_attrs.as_ref().filter(|_| !placeholder) {
+        let restricted = restricted_docids(&operation, &queries, searchable_attrs);
+        docids = Cow::Owned(OpBuilder::new(docids.as_ref(), restricted.as_set()).intersection().into_set_buf());
+    }
+    result.profile.postings_fetch_ms = elapsed_ms(before_postings_fetch);
+    result.profile.candidates = docids.len();
     debug!("found {} documents", docids.len());
     debug!("number of postings {:?}", queries.len());
 
+    // the facet counts are computed before the facet filter is applied, the facet documents ids
+    // are already restricted by the facet filter clauses that do not concern their own facet.
+    if let Some(f) = facet_count_docids {
+        // the filter is lazily applied to the documents being ranked, the counts
+        // require a pass over all the candidates to only keep the matching ones
+        let candidates = match &filter {
+            Some(filter) => {
+                let filtered: Vec<_> = docids.iter().filter(|id| filter(**id)).cloned().collect();
+                Cow::Owned(SetBuf::new_unchecked(filtered))
+            }
+            None => Cow::Borrowed(docids.as_ref()),
+        };
+
+        // hardcoded value, until approximation optimization
+        result.exhaustive_facet_count = Some(true);
+        result.facets = Some(facet_count(f, &candidates));
+    }
+
     if let Some(facets_docids) = facets_docids {
         let intersection = OpBuilder::new(docids.as_ref(), facets_docids.as_set())
             .intersection()
@@ -271,15 +473,12 @@ where
         docids = Cow::Owned(intersection);
     }
 
-    if let Some(f) = facet_count_docids {
-        // hardcoded value, until approximation optimization
-        result.exhaustive_facet_count = Some(true);
-        result.facets = Some(facet_count(f, &docids));
-    }
+    result.profile.facet_filtered_candidates = docids.len();
 
     let before = Instant::now();
     mk_arena!(arena);
     let mut bare_matches = cleanup_bare_matches(&mut arena, &docids, queries);
+    result.profile.matches_cleanup_ms = elapsed_ms(before);
     debug!("matches cleaned in {:.02?}", before.elapsed());
 
     let before_raw_documents_building = Instant::now();
@@ -288,6 +487,10 @@ where
         let raw_document = RawDocument::new(bare_matches, &mut arena, searchable_attrs.as_ref());
         raw_documents.push(raw_document);
     }
+    if placeholder {
+        raw_documents.extend(docids.iter().map(|id| RawDocument::without_matches(*id)));
+    }
+    result.profile.raw_documents_ms = elapsed_ms(before_raw_documents_building);
     debug!("creating {} candidates documents took {:.02?}",
         raw_documents.len(),
         before_raw_documents_building.elapsed(),
@@ -304,6 +507,8 @@ where
     let mut distinct_raw_offset = 0;
 
     'criteria: for criterion in criteria.as_ref() {
+        result.profile.criteria.push(CriterionProfile { name: criterion.name().to_string(), ..Default::default() });
+        let criterion_profile = result.profile.criteria.last_mut().unwrap();
         let tmp_groups = mem::replace(&mut groups, Vec::new());
         let mut buf_distinct = BufferedDistinctMap::new(&mut distinct_map);
         let mut documents_seen = 0;
@@ -317,6 +522,14 @@ where
                 continue;
             }
 
+            // the groups left are kept in the order of the previous criteria
+            if result.degraded || deadline_reached(deadline) {
+                result.degraded = true;
+                documents_seen += group.len();
+                groups.push(group);
+                continue;
+            }
+
             let ctx = ContextMut {
                 reader,
                 postings_lists: &mut arena,
@@ -326,18 +539,23 @@ where
 
             let before_criterion_preparation = Instant::now();
             criterion.prepare(ctx, &mut group)?;
+            criterion_profile.preparation_ms += elapsed_ms(before_criterion_preparation);
             debug!("{:?} preparation took {:.02?}", criterion.name(), before_criterion_preparation.elapsed());
 
             let ctx = Context {
                 postings_lists: &arena,
                 query_mapping: &mapping,
+                attribute_weights,
             };
 
             let before_criterion_sort = Instant::now();
             group.sort_unstable_by(|a, b| criterion.evaluate(&ctx, a, b));
+            criterion_profile.evaluation_ms += elapsed_ms(before_criterion_sort);
+            criterion_profile.sorted_documents += group.len();
             debug!("{:?} evaluation took {:.02?}", criterion.name(), before_criterion_sort.elapsed());
 
             for group in group.binary_group_by_mut(|a, b| criterion.eq(&ctx, a, b)) {
+                criterion_profile.groups += 1;
                 // we must compute the real distinguished len of this sub-group
                 for document in group.iter() {
                     let filter_accepted = match &filter {
@@ -383,39 +601,175 @@ where
         }
     }
 
+    // the filter and the distinct rule are lazily applied to the ranked documents,
+    // an exact count requires to apply them to all the candidates
+    let mut distinct_counts = HashMap::new();
+    if exhaustive_nb_hits && !result.degraded {
+        let mut distinct_map = DistinctMap::new(distinct_size);
+        let mut counted = BufferedDistinctMap::new(&mut distinct_map);
+        for document in raw_documents.iter() {
+            let filter_accepted = match &filter {
+                Some(filter) => *filter_map.entry(document.id).or_insert_with(|| (filter)(document.id)),
+                None => true,
+            };
+
+            if filter_accepted {
+                let key = key_cache.entry(document.id).or_insert_with(|| (distinct)(document.id).map(Rc::new));
+                match key.clone() {
+                    Some(key) => {
+                        *distinct_counts.entry(*key).or_insert(0) += 1;
+                        counted.register(key)
+                    },
+                    None => counted.register_without_key(),
+                };
+            }
+        }
+        result.nb_hits = counted.len();
+        result.exhaustive_nb_hit = true;
+    } else {
+        result.nb_hits = docids.len();
+    }
+
     // once we classified the documents related to the current
     // automatons we save that as the next valid result
+    let before_documents_building = Instant::now();
     let mut seen = BufferedDistinctMap::new(&mut distinct_map);
     let schema = main_store.schema(reader)?.ok_or(Error::SchemaMissing)?;
 
     let mut documents = Vec::with_capacity(range.len());
-    for raw_document in raw_documents.into_iter().skip(distinct_raw_offset) {
+    for mut raw_document in raw_documents.into_iter().skip(distinct_raw_offset) {
+        // the documents of the groups left unsorted by a degraded search were not evaluated
         let filter_accepted = match &filter {
-            Some(_) => filter_map.remove(&raw_document.id).unwrap(),
+            Some(filter) => filter_map.remove(&raw_document.id).unwrap_or_else(|| (filter)(raw_document.id)),
             None => true,
         };
 
         if filter_accepted {
-            let key = key_cache.remove(&raw_document.id).unwrap();
+            let key = match key_cache.remove(&raw_document.id) {
+                Some(key) => key,
+                None => (distinct)(raw_document.id).map(Rc::new),
+            };
+            let collapsed = key.as_ref().and_then(|key| distinct_counts.get(&**key)).map_or(0, |count| {
+                count - cmp::min(*count, distinct_size)
+            });
             let distinct_accepted = match key {
                 Some(key) => seen.register(key),
                 None => seen.register_without_key(),
             };
 
             if distinct_accepted && seen.len() > range.start {
-                documents.push(Document::from_raw(raw_document, &queries_kinds, &arena, searchable_attrs.as_ref(), &schema));
+                let details = if ranking_score_details {
+                    let details = criteria_details(
+                        reader,
+                        criteria,
+                        &mut arena,
+                        &mapping,
+                        attribute_weights,
+                        documents_fields_counts_store,
+                        &mut raw_document,
+                    )?;
+                    Some(details)
+                } else {
+                    None
+                };
+                let score = normalized_score(&raw_document, &arena, &mapping, attribute_weights);
+                let mut document = Document::from_raw(raw_document, &queries_kinds, &arena, searchable_attrs.as_ref(), &schema);
+                document.collapsed = collapsed;
+                document.score = score;
+                document.ranking_score_details = details;
+                documents.push(document);
                 if documents.len() == range.len() {
                     break;
                 }
             }
         }
     }
+    result.profile.documents_building_ms = elapsed_ms(before_documents_building);
     result.documents = documents;
-    result.nb_hits = docids.len();
 
     Ok(result)
 }
 
+/// The candidates of a placeholder search, a query without any word: all the documents
+/// of the index, without matches, they are only ordered by the ranking rules on attributes.
+fn placeholder_candidates<'o, 'txn>(
+    reader: &'txn heed::RoTxn<MainT>,
+    documents_fields_store: store::DocumentsFields,
+) -> MResult<QueryResult<'o, 'txn>> {
+    let docids = documents_fields_store.documents_ids(reader)?.collect::<Result<Vec<_>, _>>()?;
+    // the documents ids are the prefix of the keys, they are iterated in order
+    let docids = Cow::Owned(SetBuf::new_unchecked(docids));
+    Ok(QueryResult { docids, queries: HashMap::new() })
+}
+
+/// The values compared by each criterion for the document. The document is prepared
+/// by all the criteria first, the sort may have stopped before reaching the last ones.
+fn criteria_details<'r, 'tag, 'txn>(
+    reader: &heed::RoTxn<MainT>,
+    criteria: &Criteria,
+    arena: &mut SmallArena<'tag, PostingsListView<'txn>>,
+    mapping: &HashMap<QueryId, Range<usize>>,
+    attribute_weights: Option<&AttributeWeights>,
+    documents_fields_counts_store: store::DocumentsFieldsCounts,
+    raw_document: &mut RawDocument<'r, 'tag>,
+) -> MResult<Vec<CriterionDetails>> {
+    for criterion in criteria.as_ref() {
+        let ctx = ContextMut {
+            reader,
+            postings_lists: &mut *arena,
+            query_mapping: mapping,
+            documents_fields_counts_store,
+        };
+        criterion.prepare(ctx, std::slice::from_mut(raw_document))?;
+    }
+
+    let ctx = Context {
+        postings_lists: &*arena,
+        query_mapping: mapping,
+        attribute_weights,
+    };
+
+    let details = criteria.as_ref().iter().map(|criterion| {
+        CriterionDetails { name: criterion.name().to_string(), value: criterion.details(&ctx, raw_document) }
+    });
+
+    Ok(details.collect())
+}
+
+/// The proportion of the query words matched by the document, each word counting for
+/// less when it is matched with typos or in an attribute lighter than the heaviest one,
+/// the query words are the ones of the mapping.
+fn normalized_score<'tag, 'txn>(
+    raw_document: &RawDocument<'_, 'tag>,
+    arena: &SmallArena<'tag, PostingsListView<'txn>>,
+    mapping: &HashMap<QueryId, Range<usize>>,
+    attribute_weights: Option<&AttributeWeights>,
+) -> f64 {
+    let nb_words = mapping.values().map(|range| range.end).max().unwrap_or(0);
+    if nb_words == 0 {
+        return 0.0;
+    }
+
+    let mut words = vec![0.0; nb_words];
+    for bm in raw_document.bare_matches.iter() {
+        let mut weight = 1.0 / (1.0 + f64::from(bm.distance));
+        if let Some(weights) = attribute_weights {
+            let postings_list = &arena[bm.postings_list];
+            let heaviest = postings_list.iter().map(|di| weights.get(di.attribute)).fold(0.0, f64::max);
+            weight *= heaviest / weights.max();
+        }
+        if let Some(range) = mapping.get(&bm.query_index) {
+            for word in &mut words[range.clone()] {
+                if weight > *word {
+                    *word = weight;
+                }
+            }
+        }
+    }
+
+    words.iter().sum::<f64>() / nb_words as f64
+}
+
 fn cleanup_bare_matches<'tag, 'txn>(
     arena: &mut SmallArena<'tag, PostingsListView<'txn>>,
     docids: &Set<DocumentId>,
@@ -610,6 +964,42 @@ impl Deref for PostingsListView<'_> {
 }
 
 /// For each entry in facet_docids, calculates the number of documents in the intersection with candidate_docids.
+/// Evaluates the query tree with the matches of the queries that are in the searchable
+/// attributes, the documents must match the query in these attributes only.
+fn restricted_docids(
+    operation: &Operation,
+    queries: &Postings,
+    searchable_attrs: &ReorderedAttrs,
+) -> SetBuf<DocumentId>
+{
+    fn recurs_operation(operation: &Operation, docids: &HashMap<&Query, SetBuf<DocumentId>>) -> SetBuf<DocumentId> {
+        match operation {
+            Operation::And(ops) => {
+                let sets: Vec<_> = ops.iter().map(|op| recurs_operation(op, docids)).collect();
+                sdset::multi::Intersection::new(sets.iter().map(|s| s.as_set()).collect()).into_set_buf()
+            },
+            Operation::Or(ops) => {
+                let sets: Vec<_> = ops.iter().map(|op| recurs_operation(op, docids)).collect();
+                sdset::multi::Union::new(sets.iter().map(|s| s.as_set()).collect()).into_set_buf()
+            },
+            Operation::Query(query) => docids.get(query).cloned().unwrap_or_default(),
+        }
+    }
+
+    let mut queries_docids: HashMap<&Query, Vec<DocumentId>> = HashMap::new();
+    for (key, matches) in queries {
+        let matching = matches.iter().filter(|di| searchable_attrs.get(di.attribute).is_some());
+        queries_docids.entry(key.query).or_default().extend(matching.map(|di| di.document_id));
+    }
+
+    let queries_docids = queries_docids
+        .into_iter()
+        .map(|(id, docids)| (id, SetBuf::from_dirty(docids)))
+        .collect();
+
+    recurs_operation(operation, &queries_docids)
+}
+
 fn facet_count(
     facet_docids: HashMap<String, HashMap<String, Cow<Set<DocumentId>>>>,
     candidate_docids: &Set<DocumentId>,
@@ -1,9 +1,51 @@
 use std::cmp::Ordering;
+use std::collections::HashMap;
+use serde_json::{json, Value};
 use slice_group_by::GroupBy;
 use crate::{RawDocument, MResult};
 use crate::bucket_sort::SimpleMatch;
+use crate::reordered_attrs::ReorderedAttrs;
 use super::{Criterion, Context, ContextMut, prepare_bare_matches};
 
+/// The weights given at query time to the searchable attributes, keyed by
+/// their indexed position, the attributes without a weight weigh 1.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AttributeWeights {
+    weights: HashMap<u16, f64>,
+}
+
+impl AttributeWeights {
+    pub fn new() -> AttributeWeights {
+        AttributeWeights::default()
+    }
+
+    pub fn insert(&mut self, attribute: u16, weight: f64) {
+        self.weights.insert(attribute, weight);
+    }
+
+    pub fn get(&self, attribute: u16) -> f64 {
+        self.weights.get(&attribute).copied().unwrap_or(1.0)
+    }
+
+    /// The greatest weight, never less than the default one.
+    pub fn max(&self) -> f64 {
+        self.weights.values().copied().fold(1.0, f64::max)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+
+    /// Rewrites the weights with the attributes positions of the restricted searchable
+    /// attributes, the weights of the attributes that are not searched are dropped.
+    pub(crate) fn reordered(&self, reordered_attrs: &ReorderedAttrs) -> AttributeWeights {
+        let weights = self.weights.iter()
+            .filter_map(|(attr, weight)| reordered_attrs.get(*attr).map(|attr| (attr, *weight)))
+            .collect();
+        AttributeWeights { weights }
+    }
+}
+
 pub struct Attribute;
 
 impl Criterion for Attribute {
@@ -19,14 +61,15 @@ impl Criterion for Attribute {
         Ok(())
     }
 
-    fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
-        #[inline]
-        fn sum_of_attribute(matches: &[SimpleMatch]) -> usize {
-            let mut sum_of_attribute = 0;
-            for group in matches.linear_group_by_key(|bm| bm.query_index) {
-                sum_of_attribute += group[0].attribute as usize;
+    fn evaluate(&self, ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
+        // the documents matching the query words in the heaviest attributes come first
+        if let Some(weights) = ctx.attribute_weights {
+            let lhs = sum_of_weights(&lhs.processed_matches, weights);
+            let rhs = sum_of_weights(&rhs.processed_matches, weights);
+            match rhs.partial_cmp(&lhs) {
+                Some(Ordering::Equal) | None => (),
+                Some(ordering) => return ordering,
             }
-            sum_of_attribute
         }
 
         let lhs = sum_of_attribute(&lhs.processed_matches);
@@ -34,4 +77,33 @@ impl Criterion for Attribute {
 
         lhs.cmp(&rhs)
     }
+
+    fn details(&self, ctx: &Context, document: &RawDocument) -> Value {
+        let sum_of_attribute = sum_of_attribute(&document.processed_matches);
+        match ctx.attribute_weights {
+            Some(weights) => json!({
+                "weights": sum_of_weights(&document.processed_matches, weights),
+                "attributes": sum_of_attribute,
+            }),
+            None => Value::from(sum_of_attribute),
+        }
+    }
+}
+
+#[inline]
+fn sum_of_weights(matches: &[SimpleMatch], weights: &AttributeWeights) -> f64 {
+    let mut sum_of_weights = 0.0;
+    for group in matches.linear_group_by_key(|bm| bm.query_index) {
+        sum_of_weights += group.iter().map(|bm| weights.get(bm.attribute)).fold(0.0, f64::max);
+    }
+    sum_of_weights
+}
+
+#[inline]
+fn sum_of_attribute(matches: &[SimpleMatch]) -> usize {
+    let mut sum_of_attribute = 0;
+    for group in matches.linear_group_by_key(|bm| bm.query_index) {
+        sum_of_attribute += group[0].attribute as usize;
+    }
+    sum_of_attribute
 }
@@ -1,4 +1,5 @@
 use std::cmp::Ordering;
+use serde_json::Value;
 use crate::RawDocument;
 use super::{Criterion, Context};
 
@@ -13,4 +14,8 @@ impl Criterion for DocumentId {
 
         lhs.cmp(rhs)
     }
+
+    fn details(&self, _ctx: &Context, document: &RawDocument) -> Value {
+        Value::from(document.id.0)
+    }
 }
@@ -1,6 +1,7 @@
 use std::cmp::{Ordering, Reverse};
 use std::collections::hash_map::{HashMap, Entry};
 use meilisearch_schema::IndexedPos;
+use serde_json::{json, Value};
 use slice_group_by::GroupBy;
 use crate::{RawDocument, MResult};
 use crate::bucket_sort::BareMatch;
@@ -55,17 +56,6 @@ impl Criterion for Exactness {
     }
 
     fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
-        #[inline]
-        fn sum_exact_query_words(matches: &[BareMatch]) -> usize {
-            let mut sum_exact_query_words = 0;
-
-            for group in matches.linear_group_by_key(|bm| bm.query_index) {
-                sum_exact_query_words += group[0].is_exact as usize;
-            }
-
-            sum_exact_query_words
-        }
-
         // does it contains a "one word field"
         lhs.contains_one_word_field.cmp(&rhs.contains_one_word_field).reverse()
         // if not, with document contains the more exact words
@@ -75,4 +65,22 @@ impl Criterion for Exactness {
             lhs.cmp(&rhs).reverse()
         })
     }
+
+    fn details(&self, _ctx: &Context, document: &RawDocument) -> Value {
+        json!({
+            "oneWordField": document.contains_one_word_field,
+            "exactWords": sum_exact_query_words(&document.bare_matches),
+        })
+    }
+}
+
+#[inline]
+fn sum_exact_query_words(matches: &[BareMatch]) -> usize {
+    let mut sum_exact_query_words = 0;
+
+    for group in matches.linear_group_by_key(|bm| bm.query_index) {
+        sum_exact_query_words += group[0].is_exact as usize;
+    }
+
+    sum_exact_query_words
 }
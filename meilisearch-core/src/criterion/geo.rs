@@ -0,0 +1,60 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::geo::GeoPoint;
+use crate::{store, DocumentId, MResult, RawDocument};
+use super::{Criterion, Context, ContextMut};
+
+/// Sorts the documents by their distance to a point, the closest first.
+///
+/// The documents without coordinates are placed after the others.
+pub struct GeoDistance {
+    store: store::Geo,
+    origin: GeoPoint,
+    distances: RefCell<HashMap<DocumentId, Option<f64>>>,
+}
+
+impl GeoDistance {
+    pub fn new(store: store::Geo, origin: GeoPoint) -> GeoDistance {
+        GeoDistance { store, origin, distances: RefCell::new(HashMap::new()) }
+    }
+
+    fn distance(&self, document_id: DocumentId) -> Option<f64> {
+        self.distances.borrow().get(&document_id).copied().flatten()
+    }
+}
+
+impl Criterion for GeoDistance {
+    fn name(&self) -> &str { "geo distance" }
+
+    fn prepare<'h, 'p, 'tag, 'txn, 'q, 'r>(
+        &self,
+        ctx: ContextMut<'h, 'p, 'tag, 'txn, 'q>,
+        documents: &mut [RawDocument<'r, 'tag>],
+    ) -> MResult<()>
+    {
+        let mut distances = self.distances.borrow_mut();
+        for document in documents {
+            if distances.contains_key(&document.id) { continue }
+            let point = self.store.document_point(ctx.reader, document.id)?;
+            distances.insert(document.id, point.map(|point| self.origin.distance(&point)));
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
+        match (self.distance(lhs.id), self.distance(rhs.id)) {
+            (Some(lhs), Some(rhs)) => lhs.partial_cmp(&rhs).unwrap_or(Ordering::Equal),
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (None, None) => Ordering::Equal,
+        }
+    }
+
+    fn details(&self, _ctx: &Context, document: &RawDocument) -> Value {
+        self.distance(document.id).map_or(Value::Null, Value::from)
+    }
+}
@@ -4,6 +4,8 @@ use std::ops::Range;
 
 use compact_arena::SmallArena;
 use sdset::SetBuf;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use slice_group_by::GroupBy;
 
 use crate::bucket_sort::{SimpleMatch, PostingsListView};
@@ -19,15 +21,27 @@ mod words_position;
 mod exactness;
 mod document_id;
 mod sort_by_attr;
+mod geo;
+mod sort_by;
 
 pub use self::typo::Typo;
 pub use self::words::Words;
 pub use self::proximity::Proximity;
-pub use self::attribute::Attribute;
+pub use self::attribute::{Attribute, AttributeWeights};
 pub use self::words_position::WordsPosition;
 pub use self::exactness::Exactness;
 pub use self::document_id::DocumentId;
 pub use self::sort_by_attr::SortByAttr;
+pub use self::geo::GeoDistance;
+pub use self::sort_by::{SortBy, SortByError, SortRule};
+
+/// The value compared by a criterion for a document, reported along the
+/// hits to explain why a document is ranked before another one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CriterionDetails {
+    pub name: String,
+    pub value: Value,
+}
 
 pub trait Criterion {
     fn name(&self) -> &str;
@@ -58,6 +72,17 @@ pub trait Criterion {
     {
         self.evaluate(ctx, lhs, rhs) == Ordering::Equal
     }
+
+    /// The value this criterion compares for the prepared document,
+    /// the criteria that do not report it return null.
+    fn details<'p, 'tag, 'txn, 'q, 'r>(
+        &self,
+        _ctx: &Context<'p, 'tag, 'txn, 'q>,
+        _document: &RawDocument<'r, 'tag>,
+    ) -> Value
+    {
+        Value::Null
+    }
 }
 
 pub struct ContextMut<'h, 'p, 'tag, 'txn, 'q> {
@@ -70,6 +95,7 @@ pub struct ContextMut<'h, 'p, 'tag, 'txn, 'q> {
 pub struct Context<'p, 'tag, 'txn, 'q> {
     pub postings_lists: &'p SmallArena<'tag, PostingsListView<'txn>>,
     pub query_mapping: &'q HashMap<QueryId, Range<usize>>,
+    pub attribute_weights: Option<&'q AttributeWeights>,
 }
 
 #[derive(Default)]
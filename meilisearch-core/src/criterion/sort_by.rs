@@ -0,0 +1,169 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use meilisearch_schema::{FieldId, Schema};
+use sdset::Set;
+use serde_json::Value;
+
+use crate::store::{self, SortableValue};
+use crate::{DocumentId, MResult, RawDocument};
+use super::{Criterion, Context, ContextMut};
+
+/// A sort requested with a search, the attribute followed by `:asc` or `:desc`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortRule {
+    pub attribute: String,
+    pub descending: bool,
+}
+
+impl FromStr for SortRule {
+    type Err = SortByError;
+
+    fn from_str(s: &str) -> Result<SortRule, SortByError> {
+        let (attribute, order) = match s.rfind(':') {
+            Some(pos) => (&s[..pos], &s[pos + 1..]),
+            None => return Err(SortByError::InvalidSyntax(s.to_string())),
+        };
+        let descending = match order {
+            "asc" => false,
+            "desc" => true,
+            _ => return Err(SortByError::InvalidSyntax(s.to_string())),
+        };
+        if attribute.is_empty() {
+            return Err(SortByError::InvalidSyntax(s.to_string()));
+        }
+        Ok(SortRule { attribute: attribute.to_string(), descending })
+    }
+}
+
+impl fmt::Display for SortRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let order = if self.descending { "desc" } else { "asc" };
+        write!(f, "{}:{}", self.attribute, order)
+    }
+}
+
+/// Sorts the documents by the value of one of the sortable attributes of the index.
+///
+/// The documents without a sortable value for this attribute are placed after the others.
+pub struct SortBy {
+    store: store::Sortables,
+    field_id: FieldId,
+    descending: bool,
+    values: RefCell<HashMap<DocumentId, Option<SortableValue>>>,
+}
+
+impl SortBy {
+    pub fn new(
+        store: store::Sortables,
+        schema: &Schema,
+        sortable_attributes: &Set<FieldId>,
+        rule: &SortRule,
+    ) -> Result<SortBy, SortByError> {
+        let field_id = match schema.id(&rule.attribute) {
+            Some(field_id) => field_id,
+            None => return Err(SortByError::AttributeNotFound(rule.attribute.clone())),
+        };
+
+        if !sortable_attributes.contains(&field_id) {
+            return Err(SortByError::AttributeNotSortable(rule.attribute.clone()));
+        }
+
+        Ok(SortBy {
+            store,
+            field_id,
+            descending: rule.descending,
+            values: RefCell::new(HashMap::new()),
+        })
+    }
+}
+
+impl Criterion for SortBy {
+    fn name(&self) -> &str { "sort" }
+
+    fn prepare<'h, 'p, 'tag, 'txn, 'q, 'r>(
+        &self,
+        ctx: ContextMut<'h, 'p, 'tag, 'txn, 'q>,
+        documents: &mut [RawDocument<'r, 'tag>],
+    ) -> MResult<()>
+    {
+        let mut values = self.values.borrow_mut();
+        for document in documents {
+            if values.contains_key(&document.id) { continue }
+            let value = self.store.document_value(ctx.reader, document.id, self.field_id)?;
+            values.insert(document.id, value);
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
+        let values = self.values.borrow();
+        let lhs = values.get(&lhs.id).and_then(Option::as_ref);
+        let rhs = values.get(&rhs.id).and_then(Option::as_ref);
+
+        match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) => {
+                let order = lhs.cmp(rhs);
+                if self.descending {
+                    order.reverse()
+                } else {
+                    order
+                }
+            }
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (None, None) => Ordering::Equal,
+        }
+    }
+
+    fn details(&self, _ctx: &Context, document: &RawDocument) -> Value {
+        match self.values.borrow().get(&document.id) {
+            Some(Some(SortableValue::Number(number))) => Value::from(*number),
+            Some(Some(SortableValue::String(string))) => Value::from(string.as_str()),
+            Some(None) | None => Value::Null,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortByError {
+    InvalidSyntax(String),
+    AttributeNotFound(String),
+    AttributeNotSortable(String),
+}
+
+impl fmt::Display for SortByError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use SortByError::*;
+        match self {
+            InvalidSyntax(rule) => write!(f, "invalid sort `{}`, expected `attribute:asc` or `attribute:desc`", rule),
+            AttributeNotFound(attribute) => write!(f, "attribute `{}` not found in the schema", attribute),
+            AttributeNotSortable(attribute) => write!(f, "attribute `{}` is not sortable", attribute),
+        }
+    }
+}
+
+impl Error for SortByError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sort_rules() {
+        let rule: SortRule = "price:asc".parse().unwrap();
+        assert_eq!(rule, SortRule { attribute: "price".to_string(), descending: false });
+
+        let rule: SortRule = "release:date:desc".parse().unwrap();
+        assert_eq!(rule, SortRule { attribute: "release:date".to_string(), descending: true });
+        assert_eq!(rule.to_string(), "release:date:desc");
+
+        assert_matches!("price".parse::<SortRule>(), Err(SortByError::InvalidSyntax(_)));
+        assert_matches!("price:up".parse::<SortRule>(), Err(SortByError::InvalidSyntax(_)));
+        assert_matches!(":asc".parse::<SortRule>(), Err(SortByError::InvalidSyntax(_)));
+    }
+}
@@ -2,6 +2,7 @@ use std::cmp::Ordering;
 use std::error::Error;
 use std::fmt;
 use meilisearch_schema::{Schema, FieldId};
+use serde_json::Value;
 use crate::{RankedMap, RawDocument};
 use super::{Criterion, Context};
 
@@ -108,6 +109,10 @@ impl Criterion for SortByAttr<'_> {
             (None, None) => Ordering::Equal,
         }
     }
+
+    fn details(&self, _ctx: &Context, document: &RawDocument) -> Value {
+        self.ranked_map.get(document.id, self.field_id).map_or(Value::Null, Value::from)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
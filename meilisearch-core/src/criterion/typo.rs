@@ -1,4 +1,5 @@
 use std::cmp::Ordering;
+use serde_json::Value;
 use crate::{RawDocument, MResult};
 use super::{Criterion, Context, ContextMut, prepare_query_distances};
 
@@ -52,4 +53,9 @@ impl Criterion for Typo {
 
         lhs.cmp(&rhs).reverse()
     }
+
+    fn details(&self, _ctx: &Context, document: &RawDocument) -> Value {
+        let typos: u64 = document.processed_distances.iter().flatten().map(|d| u64::from(*d)).sum();
+        Value::from(typos)
+    }
 }
@@ -1,4 +1,5 @@
 use std::cmp::Ordering;
+use serde_json::Value;
 use crate::{RawDocument, MResult};
 use super::{Criterion, Context, ContextMut, prepare_query_distances};
 
@@ -18,14 +19,18 @@ impl Criterion for Words {
     }
 
     fn evaluate(&self, _ctx: &Context, lhs: &RawDocument, rhs: &RawDocument) -> Ordering {
-        #[inline]
-        fn number_of_query_words(distances: &[Option<u8>]) -> usize {
-            distances.iter().cloned().filter(Option::is_some).count()
-        }
-
         let lhs = number_of_query_words(&lhs.processed_distances);
         let rhs = number_of_query_words(&rhs.processed_distances);
 
         lhs.cmp(&rhs).reverse()
     }
+
+    fn details(&self, _ctx: &Context, document: &RawDocument) -> Value {
+        Value::from(number_of_query_words(&document.processed_distances))
+    }
+}
+
+#[inline]
+fn number_of_query_words(distances: &[Option<u8>]) -> usize {
+    distances.iter().cloned().filter(Option::is_some).count()
 }
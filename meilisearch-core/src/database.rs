@@ -1,35 +1,338 @@
 use std::collections::hash_map::{Entry, HashMap};
+use std::collections::VecDeque;
 use std::fs::File;
-use std::path::Path;
-use std::sync::{Arc, RwLock};
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::time::{Duration, Instant};
 use std::{fs, thread};
 
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
 use heed::types::{Str, Unit};
+use heed::flags::Flags;
 use heed::{CompactionOption, Result as ZResult};
-use log::debug;
+use log::{debug, info};
 use meilisearch_schema::Schema;
+use rayon::prelude::*;
+use serde::Serialize;
 
-use crate::{store, update, Index, MResult};
+use crate::{map_growth, store, update, Document, Index, MResult};
+
+/// The file whose presence requests the compaction of the environments at the next opening.
+const COMPACTION_MARKER: &str = "compaction-scheduled";
+
+/// How many times the transactions using a full map are awaited before giving up on growing it.
+const MAP_GROWTH_ATTEMPTS: usize = 50;
+const MAP_GROWTH_RETRY_DELAY: Duration = Duration::from_millis(100);
 
 pub type BoxUpdateFn = Box<dyn Fn(&str, update::ProcessedUpdateResult) + Send + Sync + 'static>;
 type ArcSwapFn = arc_swap::ArcSwapOption<BoxUpdateFn>;
 
+pub type BoxUpdateLogFn = Box<dyn Fn(&update::UpdateLogEntry) + Send + Sync + 'static>;
+type ArcSwapLogFn = arc_swap::ArcSwapOption<BoxUpdateLogFn>;
+
+pub type BoxUpdateListenerFn = Box<dyn Fn(&UpdateNotification) + Send + Sync + 'static>;
+
+/// What is notified to the listeners once an update has been processed.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateNotification {
+    pub index_uid: String,
+    pub update_id: u64,
+    #[serde(rename = "type")]
+    pub update_type: update::UpdateType,
+    /// Either `processed`, `failed` or `aborted`, like the status of the update.
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration: f64, // in seconds
+    pub enqueued_at: chrono::DateTime<chrono::Utc>,
+    pub processed_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl UpdateNotification {
+    fn new(index_uid: &str, result: &update::ProcessedUpdateResult) -> UpdateNotification {
+        let status = match result.interruption {
+            Some(update::Interruption::Canceled) => "canceled",
+            Some(update::Interruption::Aborted) => "aborted",
+            None if result.error.is_some() => "failed",
+            None => "processed",
+        };
+
+        UpdateNotification {
+            index_uid: index_uid.to_owned(),
+            update_id: result.update_id,
+            update_type: result.update_type.clone(),
+            status,
+            error: result.error.clone(),
+            duration: result.duration,
+            enqueued_at: result.enqueued_at,
+            processed_at: result.processed_at,
+        }
+    }
+}
+
+/// Where the update notifications are delivered, the channels
+/// are forgotten once their receiver is dropped.
+enum UpdateListener {
+    Callback(BoxUpdateListenerFn),
+    Channel(Sender<UpdateNotification>),
+}
+
+/// What the update loops share with the database.
+#[derive(Clone)]
+struct UpdateHooks {
+    update_fn: Arc<ArcSwapFn>,
+    update_log_fn: Arc<ArcSwapLogFn>,
+    listeners: Arc<RwLock<Vec<UpdateListener>>>,
+    // held for reading while an update is processed, for writing while a snapshot is taken
+    processing: Arc<RwLock<()>>,
+    running: Arc<Mutex<RunningUpdates>>,
+    turns: Arc<UpdateTurns>,
+    // held for reading while a transaction begins, for writing while an environment is resized
+    txn_gate: Arc<RwLock<()>>,
+    retention: UpdatesRetention,
+    expiration_purge_interval: Duration,
+    max_main_map_size: Option<usize>,
+    max_update_map_size: Option<usize>,
+}
+
+/// The updates being processed by the update loops.
+#[derive(Default)]
+struct RunningUpdates {
+    // once set, no new update is processed and the running ones are aborted
+    shutdown: bool,
+    updates: HashMap<String, u64>,
+}
+
+/// Gives the turn to process updates to the update loops of the indexes, in the order they
+/// asked for it. A loop gives the turn back after each batch of updates and asks for it
+/// again for the next batch, the indexes with a long queue of updates therefore take turns
+/// with the other ones instead of draining their queue first.
+///
+/// The updates of different indexes are not processed concurrently: all the indexes share
+/// the main environment and LMDB allows a single write transaction per environment. A batch
+/// is applied in one transaction to stay atomic, a long one, like a reindexation of all the
+/// documents, still holds the turn until it is done and delays the updates of the other indexes.
+struct UpdateTurns {
+    state: Mutex<TurnsState>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct TurnsState {
+    taken: bool,
+    next_ticket: u64,
+    // the tickets of the loops waiting for the turn, in the order they will get it
+    waiting: VecDeque<u64>,
+}
+
+/// The turn to process a batch of updates, it is given back when dropped.
+struct UpdateTurn<'a> {
+    turns: &'a UpdateTurns,
+}
+
+impl UpdateTurns {
+    fn new() -> UpdateTurns {
+        UpdateTurns { state: Mutex::new(TurnsState::default()), changed: Condvar::new() }
+    }
+
+    fn acquire(&self) -> UpdateTurn {
+        let mut state = self.state.lock().unwrap();
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        state.waiting.push_back(ticket);
+        self.changed.notify_all();
+
+        while state.taken || state.waiting.front() != Some(&ticket) {
+            state = self.changed.wait(state).unwrap();
+        }
+
+        state.waiting.pop_front();
+        state.taken = true;
+
+        UpdateTurn { turns: self }
+    }
+
+    /// Blocks until the turn is taken and `count` loops are waiting for it.
+    #[cfg(test)]
+    fn wait_for_waiting_loops(&self, count: usize) {
+        let mut state = self.state.lock().unwrap();
+        while !state.taken || state.waiting.len() < count {
+            state = self.changed.wait(state).unwrap();
+        }
+    }
+}
+
+impl Drop for UpdateTurn<'_> {
+    fn drop(&mut self) {
+        self.turns.state.lock().unwrap().taken = false;
+        self.turns.changed.notify_all();
+    }
+}
+
+impl UpdateHooks {
+    fn new(options: &DatabaseOptions) -> UpdateHooks {
+        UpdateHooks {
+            update_fn: Arc::new(ArcSwapFn::empty()),
+            update_log_fn: Arc::new(ArcSwapLogFn::empty()),
+            listeners: Arc::new(RwLock::new(Vec::new())),
+            processing: Arc::new(RwLock::new(())),
+            running: Arc::new(Mutex::new(RunningUpdates::default())),
+            turns: Arc::new(UpdateTurns::new()),
+            txn_gate: Arc::new(RwLock::new(())),
+            retention: options.updates_retention,
+            expiration_purge_interval: options.expiration_purge_interval,
+            max_main_map_size: options.max_main_map_size,
+            max_update_map_size: options.max_update_map_size,
+        }
+    }
+
+    fn read_txn<T>(&self, env: &heed::Env) -> ZResult<heed::RoTxn<T>> {
+        let _gate = self.txn_gate.read().unwrap();
+        env.typed_read_txn::<T>()
+    }
+
+    fn write_txn<T>(&self, env: &heed::Env) -> ZResult<heed::RwTxn<T>> {
+        let _gate = self.txn_gate.read().unwrap();
+        env.typed_write_txn::<T>()
+    }
+
+    /// Grows the map of the environment once its transactions are done, returns `false` if
+    /// the map cannot grow, either because it has reached its maximum size or is still in use.
+    ///
+    /// The caller must not hold any transaction nor the processing lock.
+    fn grow_map(&self, env: &heed::Env, max_map_size: Option<usize>, name: &str) -> bool {
+        let max_map_size = match max_map_size {
+            Some(max_map_size) => max_map_size,
+            None => return false,
+        };
+
+        // the other update loops finish the update they are processing
+        let _processing = self.processing.write().unwrap();
+
+        for _ in 0..MAP_GROWTH_ATTEMPTS {
+            match map_growth::grow(env, &self.txn_gate, max_map_size) {
+                Ok(map_growth::Growth::Grown(map_size)) => {
+                    info!("the map of the {} environment has grown to {} bytes", name, map_size);
+                    return true;
+                }
+                Ok(map_growth::Growth::MaximumReached) => {
+                    log::error!("the map of the {} environment is full and has reached its maximum size", name);
+                    return false;
+                }
+                Ok(map_growth::Growth::Busy) => thread::sleep(MAP_GROWTH_RETRY_DELAY),
+                Err(e) => {
+                    log::error!("impossible to grow the map of the {} environment; {}", name, e);
+                    return false;
+                }
+            }
+        }
+
+        log::error!("the map of the {} environment is full and still in use, it cannot grow", name);
+        false
+    }
+
+    fn log(&self, entry: update::UpdateLogEntry) {
+        if let Some(ref log_fn) = *self.update_log_fn.load() {
+            (log_fn)(&entry);
+        }
+    }
+
+    fn notify(&self, index_uid: &str, result: &update::ProcessedUpdateResult) {
+        let mut listeners = self.listeners.write().unwrap();
+        if listeners.is_empty() {
+            return;
+        }
+
+        let notification = UpdateNotification::new(index_uid, result);
+        listeners.retain(|listener| match listener {
+            UpdateListener::Callback(callback) => {
+                (callback)(&notification);
+                true
+            }
+            UpdateListener::Channel(sender) => sender.send(notification.clone()).is_ok(),
+        });
+    }
+}
+
 pub struct MainT;
 pub struct UpdateT;
 
 pub struct Database {
     env: heed::Env,
     update_env: heed::Env,
+    path: PathBuf,
+    main_path: PathBuf,
+    update_path: PathBuf,
+    options: DatabaseOptions,
     common_store: heed::PolyDatabase,
     indexes_store: heed::Database<Str, Unit>,
-    indexes: RwLock<HashMap<String, (Index, thread::JoinHandle<MResult<()>>)>>,
-    update_fn: Arc<ArcSwapFn>,
+    indexes_stores_names: heed::Database<Str, Str>,
+    aliases_store: heed::Database<Str, Str>,
+    indexes: RwLock<HashMap<String, OpenedIndex>>,
+    aliases: RwLock<HashMap<String, String>>,
+    hooks: UpdateHooks,
 }
 
+#[derive(Debug, Clone, Copy)]
 pub struct DatabaseOptions {
     pub main_map_size: usize,
     pub update_map_size: usize,
+    pub updates_retention: UpdatesRetention,
+    pub update_queue_limits: UpdateQueueLimits,
+    /// The maps are doubled when an update cannot be applied because they are full, up to
+    /// these sizes. The maps keep their initial size when they are not set.
+    pub max_main_map_size: Option<usize>,
+    pub max_update_map_size: Option<usize>,
+    /// How often the update loops look for the expired documents to delete them.
+    pub expiration_purge_interval: Duration,
+    /// Opens the environments strictly read-only, no update is processed and nothing can
+    /// be written, another process can keep updating them or they can be a copied snapshot.
+    pub read_only: bool,
+}
+
+/// How many processed updates results are kept, the oldest ones are deleted
+/// after each processed update. Everything is kept by default.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UpdatesRetention {
+    pub max_entries: Option<usize>,
+    pub max_age: Option<chrono::Duration>,
+}
+
+/// How many updates each index can have waiting to be processed, new updates are
+/// refused with an `UpdateQueueFull` error once a limit is reached. There is no limit by default.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UpdateQueueLimits {
+    pub max_pending_updates: Option<u64>,
+    /// The maximum size of the serialized payloads of the pending updates.
+    pub max_pending_bytes: Option<u64>,
+}
+
+/// The space used by an LMDB environment, its file grows page by page until the map size
+/// is reached and never shrinks, even after deletions. The map itself grows up to its
+/// maximum size when it is full, if there is one.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentUsage {
+    pub size: u64,
+    pub map_size: u64,
+    pub max_map_size: Option<u64>,
+}
+
+/// A document found by a multi search, along with the index it comes from.
+#[derive(Debug, Clone)]
+pub struct MultiSearchHit {
+    pub index_uid: String,
+    pub document: Document,
+}
+
+#[derive(Debug, Default)]
+pub struct MultiSearchResult {
+    pub hits: Vec<MultiSearchHit>,
+    pub nb_hits: usize,
+    pub exhaustive_nb_hits: bool,
 }
 
 impl Default for DatabaseOptions {
@@ -37,6 +340,12 @@ impl Default for DatabaseOptions {
         DatabaseOptions {
             main_map_size: 100 * 1024 * 1024 * 1024, //100Gb
             update_map_size: 100 * 1024 * 1024 * 1024, //100Gb
+            updates_retention: UpdatesRetention::default(),
+            update_queue_limits: UpdateQueueLimits::default(),
+            max_main_map_size: None,
+            max_update_map_size: None,
+            expiration_purge_interval: Duration::from_secs(60),
+            read_only: false,
         }
     }
 }
@@ -61,85 +370,245 @@ pub enum UpdateEvent {
 pub type UpdateEvents = Receiver<UpdateEvent>;
 pub type UpdateEventsEmitter = Sender<UpdateEvent>;
 
+/// The uid of an index shared with its update loop.
+type IndexUid = Arc<RwLock<String>>;
+
+/// An index along with the name of its stores, which is the uid it was created with,
+/// and the update loop processing its updates.
+struct OpenedIndex {
+    index: Index,
+    uid: IndexUid,
+    store_name: String,
+    // there is no update loop when the database is read-only
+    handle: Option<thread::JoinHandle<MResult<()>>>,
+}
+
 fn update_awaiter(
     receiver: UpdateEvents,
     env: heed::Env,
     update_env: heed::Env,
-    index_uid: &str,
-    update_fn: Arc<ArcSwapFn>,
+    uid: IndexUid,
+    hooks: UpdateHooks,
     index: Index,
 ) -> MResult<()> {
-    let mut receiver = receiver.into_iter();
-    while let Some(event) = receiver.next() {
+    let mut next_purge = Instant::now() + hooks.expiration_purge_interval;
+    loop {
+        let timeout = next_purge.saturating_duration_since(Instant::now());
+        let event = match receiver.recv_timeout(timeout) {
+            Ok(event) => event,
+            // the deletion of the expired documents is enqueued like any other update
+            Err(RecvTimeoutError::Timeout) => {
+                next_purge = Instant::now() + hooks.expiration_purge_interval;
+                if let Err(e) = enqueue_expired_documents_deletion(&env, &update_env, &hooks, &index) {
+                    log::error!("impossible to delete the expired documents of {}; {}", uid.read().unwrap(), e);
+                }
+                continue;
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
 
         // if we receive a *MustClear* event, clear the index and break the loop
         if let UpdateEvent::MustClear = event {
-            let mut writer = env.typed_write_txn::<MainT>()?;
-            let mut update_writer = update_env.typed_write_txn::<UpdateT>()?;
+            let mut writer = hooks.write_txn::<MainT>(&env)?;
+            let mut update_writer = hooks.write_txn::<UpdateT>(&update_env)?;
 
             store::clear(&mut writer, &mut update_writer, &index)?;
 
             writer.commit()?;
             update_writer.commit()?;
 
-            debug!("store {} cleared", index_uid);
+            debug!("store {} cleared", uid.read().unwrap());
 
             break
         }
 
+        // the additions of a failed coalesced application are then processed one by one
+        let mut uncoalesced_updates = 0;
+
         loop {
+            // each batch waits for its turn, it is given back to the other indexes once processed
+            let _turn = hooks.turns.acquire();
+
+            // the uid of the index changes when it is swapped with another one
+            let index_uid = uid.read().unwrap().clone();
+            let index_uid = index_uid.as_str();
+
             // We instantiate a *write* transaction to *block* the thread
             // until the *other*, notifiying, thread commits
-            let result = update_env.typed_write_txn::<UpdateT>();
+            let result = hooks.write_txn::<UpdateT>(&update_env);
             let update_reader = break_try!(result, "LMDB read transaction (update) begin failed");
 
-            // retrieve the update that needs to be processed
-            let result = index.updates.first_update(&update_reader);
-            let (update_id, update) = match break_try!(result, "pop front update failed") {
-                Some(value) => value,
+            // retrieve the update that needs to be processed, along with the
+            // consecutive additions that can be applied at the same time
+            let result = update::next_updates(&update_reader, index.updates, uncoalesced_updates == 0);
+            let updates = break_try!(result, "pop front update failed");
+            let update_id = match updates.first() {
+                Some((update_id, _)) => *update_id,
                 None => {
                     debug!("no more updates");
                     break;
                 }
             };
+            let coalesced = updates.len();
 
             // do not keep the reader for too long
             update_reader.abort();
 
+            {
+                let mut running = hooks.running.lock().unwrap();
+                if running.shutdown {
+                    debug!("shutting down, the update {} stays enqueued", update_id);
+                    return Ok(());
+                }
+                running.updates.insert(index_uid.to_owned(), update_id);
+            }
+
+            // a snapshot must not see the update applied to only one of the environments
+            let processing = hooks.processing.read().unwrap();
+
+            // the updates are consumed by the task, keep them for the update log if it is shipped
+            let logged_updates: Option<Vec<_>> = if hooks.update_log_fn.load().is_some() {
+                Some(updates.iter().map(|(_, update)| update.clone()).collect())
+            } else {
+                None
+            };
+
             // instantiate a transaction to touch to the main env
-            let result = env.typed_write_txn::<MainT>();
+            let result = hooks.write_txn::<MainT>(&env);
             let mut main_writer = break_try!(result, "LMDB nested write transaction failed");
 
-            // try to apply the update to the database using the main transaction
-            let result = update::update_task(&mut main_writer, &index, update_id, update);
-            let status = break_try!(result, "update task failed");
+            // try to apply the updates to the database using the main transaction
+            index.progress.start(update_id);
+            let result = update::coalesced_update_task(&mut main_writer, &index, updates);
+            index.progress.finish();
+
+            // the updates are still enqueued, they are applied again once the map has grown
+            if let Err(ref e) = result {
+                if e.is_map_full() {
+                    main_writer.abort();
+                    hooks.running.lock().unwrap().updates.remove(index_uid);
+                    drop(processing);
+                    if hooks.grow_map(&env, hooks.max_main_map_size, "main") {
+                        continue;
+                    }
+                    log::error!("the update {} of {} cannot be applied, the map is full", update_id, index_uid);
+                    break;
+                }
+            }
+            let mut statuses = break_try!(result, "update task failed");
+
+            // a failing addition must not make the other ones fail, they are applied again one by one
+            let failed = statuses.iter().any(|status| status.error.is_some());
+            if failed && coalesced > 1 {
+                debug!("the {} coalesced updates from {} failed, applying them one by one", coalesced, update_id);
+                main_writer.abort();
+                hooks.running.lock().unwrap().updates.remove(index_uid);
+                uncoalesced_updates = coalesced;
+                continue;
+            }
+
+            // the shutdown cannot be requested while the main transaction is committed
+            let mut running = hooks.running.lock().unwrap();
+            running.updates.remove(index_uid);
+
+            // the results are written by a transaction opened before the main one is committed,
+            // the updates coalesced with the first one can be canceled while they are applied
+            let result = hooks.write_txn::<UpdateT>(&update_env);
+            let mut update_writer = break_try!(result, "LMDB write transaction begin failed");
+
+            let mut canceled = false;
+            for status in &statuses {
+                // definitely remove the update from the updates store
+                canceled |= !index.updates.del_update(&mut update_writer, status.update_id)?;
+            }
+
+            // the update is applied again without the canceled ones, the one aborted
+            // by a shutdown is already recorded and the loop stops at the next iteration
+            if canceled {
+                debug!("an update coalesced with the update {} was canceled, applying them again", update_id);
+                update_writer.abort();
+                main_writer.abort();
+                continue;
+            }
+            uncoalesced_updates = uncoalesced_updates.saturating_sub(1);
+
+            if running.shutdown {
+                statuses = statuses
+                    .into_iter()
+                    .map(|status| status.interrupt(update::Interruption::Aborted))
+                    .collect();
+            }
 
-            // commit the main transaction if the update was successful, abort it otherwise
-            if status.error.is_none() {
-                break_try!(main_writer.commit(), "commit nested transaction failed");
+            // commit the main transaction if the updates were successful, abort it otherwise
+            if statuses.iter().all(|status| status.error.is_none()) {
+                if let Err(e) = main_writer.commit() {
+                    update_writer.abort();
+                    drop(running);
+                    drop(processing);
+                    if let heed::Error::Mdb(heed::MdbError::MapFull) = e {
+                        if hooks.grow_map(&env, hooks.max_main_map_size, "main") {
+                            continue;
+                        }
+                    }
+                    log::error!("commit nested transaction failed: {}", e);
+                    break;
+                }
             } else {
                 main_writer.abort()
             }
+            drop(running);
 
-            // now that the update has been processed we can instantiate
-            // a transaction to move the result to the updates-results store
-            let result = update_env.typed_write_txn::<UpdateT>();
-            let mut update_writer = break_try!(result, "LMDB write transaction begin failed");
-
-            // definitely remove the update from the updates store
-            index.updates.del_update(&mut update_writer, update_id)?;
+            // now that the updates have been processed we can move
+            // the results to the updates-results store
+            let mut result = write_updates_results(update_writer, &index, &statuses, hooks.retention);
 
-            // write the result of the updates-results store
-            let updates_results = index.updates_results;
-            let result = updates_results.put_update_result(&mut update_writer, update_id, &status);
+            // the map can only grow once the other update loops release the processing lock,
+            // they may be waiting for this one to give its turn back
+            if result.as_ref().err().map_or(false, |e| e.is_map_full()) {
+                drop(processing);
+                drop(_turn);
+            }
 
-            // always commit the main transaction, even if the update was unsuccessful
-            break_try!(result, "update result store commit failed");
-            break_try!(update_writer.commit(), "update transaction commit failed");
+            // the main transaction is committed, only the results are written again
+            while let Err(ref e) = result {
+                if !e.is_map_full() || !hooks.grow_map(&update_env, hooks.max_update_map_size, "update") {
+                    break;
+                }
+                result = hooks.write_txn::<UpdateT>(&update_env).map_err(Into::into).and_then(|mut update_writer| {
+                    for status in &statuses {
+                        index.updates.del_update(&mut update_writer, status.update_id)?;
+                    }
+                    write_updates_results(update_writer, &index, &statuses, hooks.retention)
+                });
+            }
+            break_try!(result, "update transaction commit failed");
+
+            // an aborted update has not been applied, it must not be replayed
+            if let Some(logged_updates) = logged_updates {
+                let primary_key = hooks
+                    .read_txn::<MainT>(&env)
+                    .ok()
+                    .and_then(|reader| index.main.schema(&reader).ok().flatten())
+                    .and_then(|schema| schema.primary_key().map(str::to_owned));
+
+                for (status, update) in statuses.iter().zip(logged_updates) {
+                    if status.interruption.is_none() {
+                        hooks.log(update::UpdateLogEntry::Update {
+                            index_uid: index_uid.to_owned(),
+                            update_id: status.update_id,
+                            primary_key: primary_key.clone(),
+                            update,
+                        });
+                    }
+                }
+            }
 
-            // call the user callback when the update and the result are written consistently
-            if let Some(ref callback) = *update_fn.load() {
-                (callback)(index_uid, status);
+            // call the user callback when the updates and the results are written consistently
+            for status in statuses {
+                hooks.notify(index_uid, &status);
+                if let Some(ref callback) = *hooks.update_fn.load() {
+                    (callback)(index_uid, status);
+                }
             }
         }
     }
@@ -149,42 +618,104 @@ fn update_awaiter(
     Ok(())
 }
 
+/// Writes the results of the processed updates and commits the transaction, the oldest
+/// results that are not retained anymore are deleted.
+fn write_updates_results(
+    mut update_writer: heed::RwTxn<UpdateT>,
+    index: &Index,
+    statuses: &[update::ProcessedUpdateResult],
+    retention: UpdatesRetention,
+) -> MResult<()> {
+    for status in statuses {
+        index.updates_results.put_update_result(&mut update_writer, status.update_id, status)?;
+    }
+
+    if let Err(e) = index.apply_updates_retention(&mut update_writer, retention) {
+        log::error!("impossible to delete the oldest updates results; {}", e);
+    }
+
+    Ok(update_writer.commit()?)
+}
+
+/// Enqueues the deletion of the documents of which the expiration date is passed, if any.
+fn enqueue_expired_documents_deletion(
+    env: &heed::Env,
+    update_env: &heed::Env,
+    hooks: &UpdateHooks,
+    index: &Index,
+) -> MResult<()> {
+    let reader = hooks.read_txn::<MainT>(env)?;
+    let now = chrono::Utc::now().timestamp().max(0) as u64;
+    let expired = index.expirations.expired_documents(&reader, now)?;
+    reader.abort();
+
+    if expired.is_empty() {
+        return Ok(());
+    }
+
+    let mut update_writer = hooks.write_txn::<UpdateT>(update_env)?;
+    let mut deletion = index.documents_deletion();
+    for document_id in expired {
+        deletion.delete_document_by_id(document_id);
+    }
+    let update_id = deletion.finalize(&mut update_writer)?;
+    update_writer.commit()?;
+
+    debug!("update {} deletes the expired documents", update_id);
+
+    Ok(())
+}
+
 impl Database {
     pub fn open_or_create(path: impl AsRef<Path>, options: DatabaseOptions) -> MResult<Database> {
-        let main_path = path.as_ref().join("main");
-        let update_path = path.as_ref().join("update");
-
-        fs::create_dir_all(&main_path)?;
-        let env = heed::EnvOpenOptions::new()
-            .map_size(options.main_map_size)
-            .max_dbs(3000)
-            .open(main_path)?;
+        let path = path.as_ref().to_path_buf();
+        let main_path = path.join("main");
+        let update_path = path.join("update");
+
+        // the environments can only be compacted while nothing uses them
+        let compaction_marker = path.join(COMPACTION_MARKER);
+        if compaction_marker.exists() && !options.read_only {
+            compact_environment(&main_path, options.main_map_size)?;
+            compact_environment(&update_path, options.update_map_size)?;
+            fs::remove_file(compaction_marker)?;
+        }
 
-        fs::create_dir_all(&update_path)?;
-        let update_env = heed::EnvOpenOptions::new()
-            .map_size(options.update_map_size)
-            .max_dbs(3000)
-            .open(update_path)?;
+        let env = open_environment(&main_path, options.main_map_size, options.read_only)?;
+        let update_env = open_environment(&update_path, options.update_map_size, options.read_only)?;
 
-        let common_store = env.create_poly_database(Some("common"))?;
-        let indexes_store = env.create_database::<Str, Unit>(Some("indexes"))?;
-        let update_fn = Arc::new(ArcSwapFn::empty());
+        let common_store = if options.read_only {
+            env.open_poly_database(Some("common"))?.ok_or_else(|| missing_store("common"))?
+        } else {
+            env.create_poly_database(Some("common"))?
+        };
+        let indexes_store = open_or_create_database::<Str, Unit>(&env, "indexes", options.read_only)?;
+        let indexes_stores_names = open_or_create_database::<Str, Str>(&env, "indexes-stores-names", options.read_only)?;
+        let aliases_store = open_or_create_database::<Str, Str>(&env, "aliases", options.read_only)?;
+        let hooks = UpdateHooks::new(&options);
 
-        // list all indexes that needs to be opened
+        // list all indexes that needs to be opened, the stores of the
+        // swapped indexes are not named after the uid of the index
         let mut must_open = Vec::new();
+        let mut aliases = HashMap::new();
         let reader = env.read_txn()?;
         for result in indexes_store.iter(&reader)? {
             let (index_uid, _) = result?;
-            must_open.push(index_uid.to_owned());
+            let store_name = indexes_stores_names.get(&reader, index_uid)?.unwrap_or(index_uid);
+            must_open.push((index_uid.to_owned(), store_name.to_owned()));
+        }
+
+        for result in aliases_store.iter(&reader)? {
+            let (alias, index_uid) = result?;
+            aliases.insert(alias.to_owned(), index_uid.to_owned());
         }
 
         reader.abort();
 
         // open the previously aggregated indexes
         let mut indexes = HashMap::new();
-        for index_uid in must_open {
+        for (index_uid, store_name) in must_open {
             let (sender, receiver) = crossbeam_channel::unbounded();
-            let index = match store::open(&env, &update_env, &index_uid, sender.clone())? {
+            let index = match store::open(&env, &update_env, &store_name, sender.clone(), options.update_queue_limits)? {
                 Some(index) => index,
                 None => {
                     log::warn!(
@@ -195,28 +726,36 @@ impl Database {
                 }
             };
 
-            let env_clone = env.clone();
-            let update_env_clone = update_env.clone();
-            let index_clone = index.clone();
-            let name_clone = index_uid.clone();
-            let update_fn_clone = update_fn.clone();
-
-            let handle = thread::spawn(move || {
-                update_awaiter(
-                    receiver,
-                    env_clone,
-                    update_env_clone,
-                    &name_clone,
-                    update_fn_clone,
-                    index_clone,
-                )
-            });
-
-            // send an update notification to make sure that
-            // possible pre-boot updates are consumed
-            sender.send(UpdateEvent::NewUpdate).unwrap();
-
-            let result = indexes.insert(index_uid, (index, handle));
+            let uid = Arc::new(RwLock::new(index_uid.clone()));
+            let handle = if options.read_only {
+                None
+            } else {
+                let env_clone = env.clone();
+                let update_env_clone = update_env.clone();
+                let index_clone = index.clone();
+                let uid_clone = uid.clone();
+                let hooks_clone = hooks.clone();
+
+                let handle = thread::spawn(move || {
+                    update_awaiter(
+                        receiver,
+                        env_clone,
+                        update_env_clone,
+                        uid_clone,
+                        hooks_clone,
+                        index_clone,
+                    )
+                });
+
+                // send an update notification to make sure that
+                // possible pre-boot updates are consumed
+                sender.send(UpdateEvent::NewUpdate).unwrap();
+
+                Some(handle)
+            };
+
+            let opened = OpenedIndex { index, uid, store_name, handle };
+            let result = indexes.insert(index_uid, opened);
             assert!(
                 result.is_none(),
                 "The index should not have been already open"
@@ -226,33 +765,63 @@ impl Database {
         Ok(Database {
             env,
             update_env,
+            path,
+            main_path,
+            update_path,
+            options,
             common_store,
             indexes_store,
+            indexes_stores_names,
+            aliases_store,
             indexes: RwLock::new(indexes),
-            update_fn,
+            aliases: RwLock::new(aliases),
+            hooks,
         })
     }
 
+    /// Opens an index by its uid or by one of its aliases.
     pub fn open_index(&self, name: impl AsRef<str>) -> Option<Index> {
+        let name = name.as_ref();
         let indexes_lock = self.indexes.read().unwrap();
-        match indexes_lock.get(name.as_ref()) {
-            Some((index, ..)) => Some(index.clone()),
-            None => None,
+        match indexes_lock.get(name) {
+            Some(opened) => Some(opened.index.clone()),
+            None => {
+                let aliases_lock = self.aliases.read().unwrap();
+                let index_uid = aliases_lock.get(name)?;
+                indexes_lock.get(index_uid).map(|opened| opened.index.clone())
+            }
         }
     }
 
     pub fn create_index(&self, name: impl AsRef<str>) -> MResult<Index> {
+        self.check_writable()?;
         let name = name.as_ref();
         let mut indexes_lock = self.indexes.write().unwrap();
 
+        if self.aliases.read().unwrap().contains_key(name) {
+            return Err(crate::Error::AliasConflict(name.to_owned()));
+        }
+
+        // the stores of a swapped index keep the name of the uid it was created with,
+        // another name must be found when this uid is now used by another index
+        let mut store_name = name.to_owned();
+        let mut suffix = 0;
+        while indexes_lock.values().any(|opened| opened.store_name == store_name) {
+            suffix += 1;
+            store_name = format!("{}-{}", name, suffix);
+        }
+
         match indexes_lock.entry(name.to_owned()) {
             Entry::Occupied(_) => Err(crate::Error::IndexAlreadyExists),
             Entry::Vacant(entry) => {
                 let (sender, receiver) = crossbeam_channel::unbounded();
-                let index = store::create(&self.env, &self.update_env, name, sender)?;
+                let index = store::create(&self.env, &self.update_env, &store_name, sender, self.options.update_queue_limits)?;
 
-                let mut writer = self.env.typed_write_txn::<MainT>()?;
+                let mut writer = self.main_write_txn()?;
                 self.indexes_store.put(&mut writer, name, &())?;
+                if store_name != name {
+                    self.indexes_stores_names.put(&mut writer, name, &store_name)?;
+                }
 
                 index.main.put_name(&mut writer, name)?;
                 index.main.put_created_at(&mut writer)?;
@@ -262,22 +831,25 @@ impl Database {
                 let env_clone = self.env.clone();
                 let update_env_clone = self.update_env.clone();
                 let index_clone = index.clone();
-                let name_clone = name.to_owned();
-                let update_fn_clone = self.update_fn.clone();
+                let uid = Arc::new(RwLock::new(name.to_owned()));
+                let uid_clone = uid.clone();
+                let hooks_clone = self.hooks.clone();
 
                 let handle = thread::spawn(move || {
                     update_awaiter(
                         receiver,
                         env_clone,
                         update_env_clone,
-                        &name_clone,
-                        update_fn_clone,
+                        uid_clone,
+                        hooks_clone,
                         index_clone,
                     )
                 });
 
                 writer.commit()?;
-                entry.insert((index.clone(), handle));
+                entry.insert(OpenedIndex { index: index.clone(), uid, store_name, handle: Some(handle) });
+
+                self.hooks.log(update::UpdateLogEntry::IndexCreation { index_uid: name.to_owned() });
 
                 Ok(index)
             }
@@ -285,16 +857,24 @@ impl Database {
     }
 
     pub fn delete_index(&self, name: impl AsRef<str>) -> MResult<bool> {
+        self.check_writable()?;
         let name = name.as_ref();
         let mut indexes_lock = self.indexes.write().unwrap();
 
         match indexes_lock.remove_entry(name) {
-            Some((name, (index, handle))) => {
-                // remove the index name from the list of indexes
+            Some((name, OpenedIndex { index, handle, .. })) => {
+                // remove the index name from the list of indexes along with its aliases
                 // and clear all the LMDB dbi
-                let mut writer = self.env.write_txn()?;
+                let mut aliases_lock = self.aliases.write().unwrap();
+                let mut writer = self.main_write_txn()?;
                 self.indexes_store.delete(&mut writer, &name)?;
+                self.indexes_stores_names.delete(&mut writer, &name)?;
+                for (alias, _) in aliases_lock.iter().filter(|(_, uid)| **uid == name) {
+                    self.aliases_store.delete(&mut writer, alias)?;
+                }
                 writer.commit()?;
+                aliases_lock.retain(|_, uid| *uid != name);
+                drop(aliases_lock);
 
                 // send a stop event to the update loop of the index
                 index.updates_notifier.send(UpdateEvent::MustClear).unwrap();
@@ -302,7 +882,11 @@ impl Database {
                 drop(indexes_lock);
 
                 // join the update loop thread to ensure it is stopped
-                handle.join().unwrap()?;
+                if let Some(handle) = handle {
+                    handle.join().unwrap()?;
+                }
+
+                self.hooks.log(update::UpdateLogEntry::IndexDeletion { index_uid: name });
 
                 Ok(true)
             }
@@ -310,29 +894,93 @@ impl Database {
         }
     }
 
+    /// Stops processing the updates, the updates being processed are not applied and are
+    /// recorded as aborted, the other ones stay enqueued until the database is reopened.
+    pub fn shutdown(&self) -> MResult<()> {
+        let mut running = self.hooks.running.lock().unwrap();
+        running.shutdown = true;
+
+        // no update is ever processed by a read-only database
+        if self.options.read_only {
+            return Ok(());
+        }
+
+        let mut update_writer = self.update_write_txn()?;
+        for (index_uid, &update_id) in &running.updates {
+            let index = match self.open_index(index_uid) {
+                Some(index) => index,
+                None => continue,
+            };
+
+            if let Some(update) = index.updates.get(&update_writer, update_id)? {
+                let interruption = update::Interruption::Aborted;
+                let status = update::ProcessedUpdateResult::interrupted(update_id, &update, interruption);
+                index.updates.del_update(&mut update_writer, update_id)?;
+                index.updates_results.put_update_result(&mut update_writer, update_id, &status)?;
+            }
+        }
+        update_writer.commit()?;
+
+        Ok(())
+    }
+
     pub fn set_update_callback(&self, update_fn: BoxUpdateFn) {
         let update_fn = Some(Arc::new(update_fn));
-        self.update_fn.swap(update_fn);
+        self.hooks.update_fn.swap(update_fn);
     }
 
     pub fn unset_update_callback(&self) {
-        self.update_fn.swap(None);
+        self.hooks.update_fn.swap(None);
+    }
+
+    /// Registers a function called with a notification of every update processed,
+    /// the functions are called by the update loops and must not block them.
+    pub fn add_update_listener(&self, listener: BoxUpdateListenerFn) {
+        self.hooks.listeners.write().unwrap().push(UpdateListener::Callback(listener));
+    }
+
+    /// Returns a channel receiving a notification of every update processed from now on,
+    /// the notifications are no longer sent once the receiver is dropped.
+    pub fn subscribe_updates(&self) -> Receiver<UpdateNotification> {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        self.hooks.listeners.write().unwrap().push(UpdateListener::Channel(sender));
+        receiver
+    }
+
+    /// Sets the function receiving the indexes creations and deletions and the processed
+    /// updates, in the order they are applied, to replay them on another database.
+    pub fn set_update_log_callback(&self, update_log_fn: BoxUpdateLogFn) {
+        let update_log_fn = Some(Arc::new(update_log_fn));
+        self.hooks.update_log_fn.swap(update_log_fn);
     }
 
     pub fn main_read_txn(&self) -> heed::Result<heed::RoTxn<MainT>> {
-        self.env.typed_read_txn::<MainT>()
+        self.hooks.read_txn::<MainT>(&self.env)
     }
 
-    pub fn main_write_txn(&self) -> heed::Result<heed::RwTxn<MainT>> {
-        self.env.typed_write_txn::<MainT>()
+    pub fn main_write_txn(&self) -> MResult<heed::RwTxn<MainT>> {
+        self.check_writable()?;
+        Ok(self.hooks.write_txn::<MainT>(&self.env)?)
     }
 
     pub fn update_read_txn(&self) -> heed::Result<heed::RoTxn<UpdateT>> {
-        self.update_env.typed_read_txn::<UpdateT>()
+        self.hooks.read_txn::<UpdateT>(&self.update_env)
+    }
+
+    pub fn update_write_txn(&self) -> MResult<heed::RwTxn<UpdateT>> {
+        self.check_writable()?;
+        Ok(self.hooks.write_txn::<UpdateT>(&self.update_env)?)
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.options.read_only
     }
 
-    pub fn update_write_txn(&self) -> heed::Result<heed::RwTxn<UpdateT>> {
-        self.update_env.typed_write_txn::<UpdateT>()
+    fn check_writable(&self) -> MResult<()> {
+        if self.options.read_only {
+            return Err(crate::Error::ReadOnly);
+        }
+        Ok(())
     }
 
     pub fn copy_and_compact_to_path<P: AsRef<Path>>(&self, path: P) -> ZResult<(File, File)> {
@@ -357,68 +1005,471 @@ impl Database {
         }
     }
 
-    pub fn indexes_uids(&self) -> Vec<String> {
-        let indexes = self.indexes.read().unwrap();
-        indexes.keys().cloned().collect()
+    /// Copies and compacts both environments to the given directory, no update is
+    /// processed during the copy so that the two copies are consistent with each other.
+    /// The updates can still be enqueued and the indexes searched in the meantime.
+    pub fn snapshot<P: AsRef<Path>>(&self, path: P) -> MResult<()> {
+        let _processing = self.hooks.processing.write().unwrap();
+        let (env_file, update_env_file) = self.copy_and_compact_to_path(path)?;
+        env_file.sync_all()?;
+        update_env_file.sync_all()?;
+        Ok(())
     }
 
-    pub fn common_store(&self) -> heed::PolyDatabase {
-        self.common_store
-    }
-}
+    /// Replaces the environments of the database directory by the ones of a snapshot,
+    /// it must be done before the database is opened.
+    pub fn restore_snapshot<P: AsRef<Path>, Q: AsRef<Path>>(snapshot_path: P, path: Q) -> MResult<()> {
+        let snapshot_path = snapshot_path.as_ref();
+        let path = path.as_ref();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        for env in &["main", "update"] {
+            let env_path = path.join(env);
+            if env_path.exists() {
+                fs::remove_dir_all(&env_path)?;
+            }
+            fs::create_dir_all(&env_path)?;
+            fs::copy(snapshot_path.join(env).join("data.mdb"), env_path.join("data.mdb"))?;
+        }
 
-    use crate::bucket_sort::SortResult;
-    use crate::criterion::{self, CriteriaBuilder};
-    use crate::update::{ProcessedUpdateResult, UpdateStatus};
-    use crate::settings::Settings;
-    use crate::{Document, DocumentId};
-    use serde::de::IgnoredAny;
-    use std::sync::mpsc;
+        Ok(())
+    }
 
-    #[test]
-    fn valid_updates() {
-        let dir = tempfile::tempdir().unwrap();
+    /// Opens a read transaction on each environment while no update is processed,
+    /// an update is therefore either applied and removed from the queue or still enqueued.
+    pub fn consistent_read_txns(&self) -> MResult<(heed::RoTxn<MainT>, heed::RoTxn<UpdateT>)> {
+        let _processing = self.hooks.processing.write().unwrap();
+        let reader = self.main_read_txn()?;
+        let update_reader = self.update_read_txn()?;
+        Ok((reader, update_reader))
+    }
 
-        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
-        let db = &database;
+    pub fn main_environment_usage(&self) -> MResult<EnvironmentUsage> {
+        environment_usage(&self.main_path, &self.env, self.options.max_main_map_size)
+    }
 
-        let (sender, receiver) = mpsc::sync_channel(100);
-        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
-            sender.send(update.update_id).unwrap()
-        };
-        let index = database.create_index("test").unwrap();
+    pub fn update_environment_usage(&self) -> MResult<EnvironmentUsage> {
+        environment_usage(&self.update_path, &self.update_env, self.options.max_update_map_size)
+    }
 
-        database.set_update_callback(Box::new(update_fn));
+    /// Requests the compaction of the environments, it is done the next time the
+    /// database is opened as the files can't be replaced while they are in use.
+    pub fn schedule_compaction(&self) -> MResult<()> {
+        self.check_writable()?;
+        File::create(self.path.join(COMPACTION_MARKER))?;
+        Ok(())
+    }
 
-        let mut writer = db.main_write_txn().unwrap();
-        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
-        writer.commit().unwrap();
+    pub fn is_compaction_scheduled(&self) -> bool {
+        self.path.join(COMPACTION_MARKER).exists()
+    }
 
-        // block until the transaction is processed
+    pub fn indexes_uids(&self) -> Vec<String> {
+        let indexes = self.indexes.read().unwrap();
+        indexes.keys().cloned().collect()
+    }
 
-        let settings = {
-            let data = r#"
-                {
-                    "searchableAttributes": ["name", "description"],
-                    "displayedAttributes": ["name", "description"]
-                }
-            "#;
-            let settings: Settings = serde_json::from_str(data).unwrap();
-            settings.into_update().unwrap()
-        };
+    /// Atomically exchanges two indexes, their documents, settings and updates, the clients
+    /// of an index uid instantly see the other index. The names stay with the uids.
+    pub fn swap_indexes(&self, lhs: &str, rhs: &str) -> MResult<()> {
+        let mut indexes_lock = self.indexes.write().unwrap();
+        for uid in &[lhs, rhs] {
+            if !indexes_lock.contains_key(*uid) {
+                return Err(crate::Error::IndexNotFound(uid.to_string()));
+            }
+        }
 
-        let mut update_writer = db.update_write_txn().unwrap();
-        let _update_id = index.settings_update(&mut update_writer, settings).unwrap();
-        update_writer.commit().unwrap();
+        if lhs == rhs {
+            return Ok(());
+        }
 
-        let mut additions = index.documents_addition();
+        let lhs_opened = &indexes_lock[lhs];
+        let rhs_opened = &indexes_lock[rhs];
 
-        let doc1 = serde_json::json!({
-            "id": 123,
+        let mut writer = self.main_write_txn()?;
+        let lhs_name = lhs_opened.index.main.name(&writer)?;
+        let rhs_name = rhs_opened.index.main.name(&writer)?;
+        if let Some(name) = lhs_name {
+            rhs_opened.index.main.put_name(&mut writer, &name)?;
+        }
+        if let Some(name) = rhs_name {
+            lhs_opened.index.main.put_name(&mut writer, &name)?;
+        }
+
+        // each uid now refers to the stores of the other index
+        for (uid, store_name) in &[(lhs, &rhs_opened.store_name), (rhs, &lhs_opened.store_name)] {
+            if uid == store_name {
+                self.indexes_stores_names.delete(&mut writer, uid)?;
+            } else {
+                self.indexes_stores_names.put(&mut writer, uid, store_name)?;
+            }
+        }
+        writer.commit()?;
+
+        let lhs_opened = indexes_lock.remove(lhs).unwrap();
+        let rhs_opened = indexes_lock.remove(rhs).unwrap();
+        *lhs_opened.uid.write().unwrap() = rhs.to_owned();
+        *rhs_opened.uid.write().unwrap() = lhs.to_owned();
+        indexes_lock.insert(lhs.to_owned(), rhs_opened);
+        indexes_lock.insert(rhs.to_owned(), lhs_opened);
+
+        self.hooks.log(update::UpdateLogEntry::IndexesSwap { lhs: lhs.to_owned(), rhs: rhs.to_owned() });
+
+        Ok(())
+    }
+
+    /// Changes the uid of an index, its stores keep their names and nothing is copied. The
+    /// aliases of the index are moved to the new uid, like its name if it was the old uid.
+    pub fn rename_index(&self, old_uid: &str, new_uid: &str) -> MResult<()> {
+        self.check_writable()?;
+        let mut indexes_lock = self.indexes.write().unwrap();
+        if !indexes_lock.contains_key(old_uid) {
+            return Err(crate::Error::IndexNotFound(old_uid.to_owned()));
+        }
+
+        if old_uid == new_uid {
+            return Ok(());
+        }
+
+        if indexes_lock.contains_key(new_uid) {
+            return Err(crate::Error::IndexAlreadyExists);
+        }
+
+        let mut aliases_lock = self.aliases.write().unwrap();
+        if aliases_lock.contains_key(new_uid) {
+            return Err(crate::Error::AliasConflict(new_uid.to_owned()));
+        }
+
+        let opened = &indexes_lock[old_uid];
+
+        let mut writer = self.main_write_txn()?;
+        self.indexes_store.delete(&mut writer, old_uid)?;
+        self.indexes_store.put(&mut writer, new_uid, &())?;
+        self.indexes_stores_names.delete(&mut writer, old_uid)?;
+        if opened.store_name != new_uid {
+            self.indexes_stores_names.put(&mut writer, new_uid, &opened.store_name)?;
+        }
+        if opened.index.main.name(&writer)?.as_deref() == Some(old_uid) {
+            opened.index.main.put_name(&mut writer, new_uid)?;
+        }
+        for (alias, _) in aliases_lock.iter().filter(|(_, uid)| *uid == old_uid) {
+            self.aliases_store.put(&mut writer, alias, new_uid)?;
+        }
+        writer.commit()?;
+
+        for uid in aliases_lock.values_mut().filter(|uid| *uid == old_uid) {
+            *uid = new_uid.to_owned();
+        }
+        drop(aliases_lock);
+
+        // the enqueued updates are in the stores of the index, the update
+        // loop reads the uid of the index before processing each of them
+        let opened = indexes_lock.remove(old_uid).unwrap();
+        *opened.uid.write().unwrap() = new_uid.to_owned();
+        indexes_lock.insert(new_uid.to_owned(), opened);
+
+        self.hooks.log(update::UpdateLogEntry::IndexRename { old_uid: old_uid.to_owned(), new_uid: new_uid.to_owned() });
+
+        Ok(())
+    }
+
+    /// Creates an index with the documents and the settings of another one, its stores are
+    /// copied in a single transaction. The updates of the index are only copied when asked to,
+    /// the copy otherwise starts with an empty updates history.
+    pub fn copy_index(&self, source: &str, destination: &str, with_updates: bool) -> MResult<Index> {
+        let source_store_name = match self.indexes.read().unwrap().get(source) {
+            Some(opened) => opened.store_name.clone(),
+            None => return Err(crate::Error::IndexNotFound(source.to_owned())),
+        };
+
+        let index = self.create_index(destination)?;
+        let destination_store_name = self.indexes.read().unwrap()[destination].store_name.clone();
+
+        let result = self.copy_index_stores(&index, &source_store_name, &destination_store_name, destination, with_updates);
+        if let Err(e) = result {
+            self.delete_index(destination)?;
+            return Err(e);
+        }
+
+        self.hooks.log(update::UpdateLogEntry::IndexCopy {
+            source: source.to_owned(),
+            destination: destination.to_owned(),
+            with_updates,
+        });
+
+        // the copied updates that are still enqueued must be processed by the copy
+        if with_updates {
+            let _ = index.updates_notifier.send(UpdateEvent::NewUpdate);
+        }
+
+        Ok(index)
+    }
+
+    fn copy_index_stores(
+        &self,
+        index: &Index,
+        source_store_name: &str,
+        destination_store_name: &str,
+        destination: &str,
+        with_updates: bool,
+    ) -> MResult<()> {
+        let copy = store::StoresCopy::open(
+            &self.env,
+            &self.update_env,
+            source_store_name,
+            destination_store_name,
+            with_updates,
+        )?;
+
+        // the updates being processed are either entirely copied or not at all
+        let _processing = self.hooks.processing.write().unwrap();
+        let mut writer = self.main_write_txn()?;
+        let mut update_writer = self.update_write_txn()?;
+
+        copy.apply(&mut writer, &mut update_writer)?;
+
+        // the copy is a new index that only shares the content of the source
+        index.main.put_name(&mut writer, destination)?;
+        index.main.put_created_at(&mut writer)?;
+        index.main.put_updated_at(&mut writer)?;
+
+        writer.commit()?;
+        update_writer.commit()?;
+
+        Ok(())
+    }
+
+    /// Makes an index reachable by another uid, an existing alias is moved to the index.
+    pub fn put_alias(&self, alias: &str, index_uid: &str) -> MResult<()> {
+        let indexes_lock = self.indexes.read().unwrap();
+        if indexes_lock.contains_key(alias) {
+            return Err(crate::Error::AliasConflict(alias.to_owned()));
+        }
+        if !indexes_lock.contains_key(index_uid) {
+            return Err(crate::Error::IndexNotFound(index_uid.to_owned()));
+        }
+
+        let mut aliases_lock = self.aliases.write().unwrap();
+        let mut writer = self.main_write_txn()?;
+        self.aliases_store.put(&mut writer, alias, index_uid)?;
+        writer.commit()?;
+        aliases_lock.insert(alias.to_owned(), index_uid.to_owned());
+
+        self.hooks.log(update::UpdateLogEntry::AliasUpdate { alias: alias.to_owned(), index_uid: index_uid.to_owned() });
+
+        Ok(())
+    }
+
+    pub fn delete_alias(&self, alias: &str) -> MResult<bool> {
+        let mut aliases_lock = self.aliases.write().unwrap();
+        if !aliases_lock.contains_key(alias) {
+            return Ok(false);
+        }
+
+        let mut writer = self.main_write_txn()?;
+        self.aliases_store.delete(&mut writer, alias)?;
+        writer.commit()?;
+        aliases_lock.remove(alias);
+
+        self.hooks.log(update::UpdateLogEntry::AliasDeletion { alias: alias.to_owned() });
+
+        Ok(true)
+    }
+
+    /// Returns the aliases along with the uid of their index, ordered by alias.
+    pub fn aliases(&self) -> Vec<(String, String)> {
+        let aliases = self.aliases.read().unwrap();
+        let mut aliases: Vec<_> = aliases.iter().map(|(a, u)| (a.clone(), u.clone())).collect();
+        aliases.sort_unstable();
+        aliases
+    }
+
+    pub fn common_store(&self) -> heed::PolyDatabase {
+        self.common_store
+    }
+
+    /// Runs the same query against the given indexes in parallel and merges their hits by
+    /// their normalized score, the hits of each index keep their ranking order. The caller
+    /// must not hold a main read transaction as one is opened on each of the threads.
+    pub fn multi_search(
+        &self,
+        indexes_uids: &[&str],
+        query: &str,
+        range: Range<usize>,
+    ) -> MResult<MultiSearchResult> {
+        let mut indexes = Vec::with_capacity(indexes_uids.len());
+        for uid in indexes_uids {
+            match self.open_index(uid) {
+                Some(index) => indexes.push((uid.to_string(), index)),
+                None => return Err(crate::Error::IndexNotFound(uid.to_string())),
+            }
+        }
+
+        // every index must return enough hits to fill the range on its own
+        let env = &self.env;
+        let hooks = &self.hooks;
+        let results = indexes
+            .into_par_iter()
+            .map(|(uid, index)| {
+                let reader = hooks.read_txn::<MainT>(env)?;
+                let result = index.query_builder().query(&reader, query, 0..range.end)?;
+                Ok((uid, result))
+            })
+            .collect::<MResult<Vec<_>>>()?;
+
+        let mut result = MultiSearchResult { exhaustive_nb_hits: true, ..MultiSearchResult::default() };
+        let mut ranked = Vec::with_capacity(results.len());
+        for (uid, sort_result) in results {
+            result.nb_hits += sort_result.nb_hits;
+            result.exhaustive_nb_hits &= sort_result.exhaustive_nb_hit;
+            ranked.push((uid, sort_result.documents.into_iter().peekable()));
+        }
+
+        // picks the best head of the indexes results, the first index wins the ties
+        let mut hits = Vec::with_capacity(range.end);
+        while hits.len() < range.end {
+            let mut best: Option<(usize, f64)> = None;
+            for (i, (_, documents)) in ranked.iter_mut().enumerate() {
+                if let Some(document) = documents.peek() {
+                    if best.map_or(true, |(_, score)| document.score > score) {
+                        best = Some((i, document.score));
+                    }
+                }
+            }
+
+            match best {
+                Some((i, _)) => {
+                    let (uid, documents) = &mut ranked[i];
+                    let document = documents.next().unwrap();
+                    hits.push(MultiSearchHit { index_uid: uid.clone(), document });
+                }
+                None => break,
+            }
+        }
+
+        result.hits = hits.into_iter().skip(range.start).collect();
+        Ok(result)
+    }
+}
+
+/// Copies the environment to a new file without its free pages and replaces the
+/// original file with it, the rename being atomic the environment is never left half copied.
+/// Opens an environment, a read-only environment must already exist and is never written.
+fn open_environment(path: &Path, map_size: usize, read_only: bool) -> MResult<heed::Env> {
+    let mut options = heed::EnvOpenOptions::new();
+    options.map_size(map_size).max_dbs(3000);
+    if read_only {
+        // the environment can only be read, the write transactions would fail
+        unsafe { options.flag(Flags::MdbRdOnly) };
+    } else {
+        fs::create_dir_all(path)?;
+    }
+    Ok(options.open(path)?)
+}
+
+fn open_or_create_database<KC: 'static, DC: 'static>(
+    env: &heed::Env,
+    name: &str,
+    read_only: bool,
+) -> MResult<heed::Database<KC, DC>> {
+    if read_only {
+        env.open_database(Some(name))?.ok_or_else(|| missing_store(name))
+    } else {
+        Ok(env.create_database(Some(name))?)
+    }
+}
+
+fn missing_store(name: &str) -> crate::Error {
+    let message = format!("the {} store doesn't exist, the database can't be opened read-only", name);
+    io::Error::new(io::ErrorKind::NotFound, message).into()
+}
+
+fn compact_environment(path: &Path, map_size: usize) -> MResult<()> {
+    let data_path = path.join("data.mdb");
+    if !data_path.exists() {
+        return Ok(());
+    }
+
+    let compacted_path = path.join("data.mdb.compacted");
+    if compacted_path.exists() {
+        fs::remove_file(&compacted_path)?;
+    }
+
+    let size_before = fs::metadata(&data_path)?.len();
+    {
+        let env = heed::EnvOpenOptions::new()
+            .map_size(map_size)
+            .max_dbs(3000)
+            .open(path)?;
+        let file = env.copy_to_path(&compacted_path, CompactionOption::Enabled)?;
+        file.sync_all()?;
+    }
+    fs::rename(&compacted_path, &data_path)?;
+
+    let size_after = fs::metadata(&data_path)?.len();
+    info!("compacted {:?} from {} to {} bytes", path, size_before, size_after);
+
+    Ok(())
+}
+
+fn environment_usage(path: &Path, env: &heed::Env, max_map_size: Option<usize>) -> MResult<EnvironmentUsage> {
+    let size = fs::metadata(path.join("data.mdb"))?.len();
+    let map_size = map_growth::map_size(env) as u64;
+    Ok(EnvironmentUsage { size, map_size, max_map_size: max_map_size.map(|size| size as u64) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::bucket_sort::SortResult;
+    use crate::criterion::{self, CriteriaBuilder};
+    use crate::facets::{FacetFilter, FacetKey};
+    use crate::update::{AdditionOptions, MetadataUpdate, ProcessedUpdateResult, UpdateStatus, UpdateType};
+    use crate::settings::Settings;
+    use crate::{Document, DocumentId, Error, Filter, GeoPoint, QueueLimit, UpdateQueueSize};
+    use serde::de::IgnoredAny;
+    use std::sync::mpsc;
+
+    #[test]
+    fn valid_updates() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        // block until the transaction is processed
+
+        let settings = {
+            let data = r#"
+                {
+                    "searchableAttributes": ["name", "description"],
+                    "displayedAttributes": ["name", "description"]
+                }
+            "#;
+            let settings: Settings = serde_json::from_str(data).unwrap();
+            settings.into_update().unwrap()
+        };
+
+        let mut update_writer = db.update_write_txn().unwrap();
+        let _update_id = index.settings_update(&mut update_writer, settings).unwrap();
+        update_writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+
+        let doc1 = serde_json::json!({
+            "id": 123,
             "name": "Marvin",
             "description": "My name is Marvin",
         });
@@ -444,6 +1495,56 @@ mod tests {
         assert_matches!(result, Some(UpdateStatus::Processed { content }) if content.error.is_none());
     }
 
+    #[test]
+    fn infer_primary_key_on_first_addition() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        // no field ends with the suffix, the addition fails and the index keeps no primary key
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "identifier": 1, "title": "hello" }));
+
+        let mut update_writer = db.update_write_txn().unwrap();
+        let first_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "title": "hello", "movie_Id": 1 }));
+        additions.update_document(serde_json::json!({ "title": "world", "movie_Id": 2 }));
+
+        let mut update_writer = db.update_write_txn().unwrap();
+        let second_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+
+        // block until the transaction is processed
+        let _ = receiver.iter().find(|id| *id == second_id);
+
+        let update_reader = db.update_read_txn().unwrap();
+        let result = index.update_status(&update_reader, first_id).unwrap();
+        assert_matches!(result, Some(UpdateStatus::Failed { content }) if content.inferred_primary_key.is_none());
+        let result = index.update_status(&update_reader, second_id).unwrap();
+        assert_matches!(
+            result,
+            Some(UpdateStatus::Processed { content })
+                if content.inferred_primary_key.as_deref() == Some("movie_Id")
+        );
+
+        let reader = db.main_read_txn().unwrap();
+        let schema = index.main.schema(&reader).unwrap().unwrap();
+        assert_eq!(schema.primary_key(), Some("movie_Id"));
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 2);
+    }
+
     #[test]
     fn invalid_updates() {
         let dir = tempfile::tempdir().unwrap();
@@ -501,9 +1602,19 @@ mod tests {
         // block until the transaction is processed
         let _ = receiver.into_iter().find(|id| *id == update_id);
 
+        // the document without id is skipped and reported, the other one is added
         let update_reader = db.update_read_txn().unwrap();
         let result = index.update_status(&update_reader, update_id).unwrap();
-        assert_matches!(result, Some(UpdateStatus::Failed { content }) if content.error.is_some());
+        let failed_documents = match result {
+            Some(UpdateStatus::Processed { content }) => content.failed_documents.unwrap(),
+            status => panic!("unexpected update status {:?}", status),
+        };
+        assert_eq!(failed_documents.len(), 1);
+        assert_eq!(failed_documents[0].position, 1);
+        assert_eq!(failed_documents[0].document_id, None);
+
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 1);
     }
 
     #[test]
@@ -991,6 +2102,48 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn update_queue_size_counts_the_entries() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+        let index = database.create_index("test").unwrap();
+
+        // the updates are not processed before the transaction is committed
+        let mut writer = db.update_write_txn().unwrap();
+        assert_eq!(index.update_queue_size(&writer).unwrap(), UpdateQueueSize::default());
+
+        let mut update_ids = Vec::new();
+        for id in 0..3 {
+            let mut additions = index.documents_addition();
+            additions.update_document(serde_json::json!({ "id": id, "title": "hello" }));
+            update_ids.push(additions.finalize(&mut writer).unwrap());
+        }
+
+        let size = index.update_queue_size(&writer).unwrap();
+        assert_eq!(size.pending_updates, 3);
+        assert!(size.pending_bytes > 0);
+
+        // the ids of the updates left in the queue are no more contiguous
+        index.updates.del_update(&mut writer, update_ids[1]).unwrap();
+        let new_size = index.update_queue_size(&writer).unwrap();
+        assert_eq!(new_size.pending_updates, 2);
+        assert!(new_size.pending_bytes < size.pending_bytes);
+
+        // the queues of the indexes created before the size was stored are counted from the entries
+        index.updates.queue_size.clear(&mut writer).unwrap();
+        assert_eq!(index.update_queue_size(&writer).unwrap(), new_size);
+
+        index.updates.pop_front(&mut writer).unwrap();
+        let last_size = index.update_queue_size(&writer).unwrap();
+        assert_eq!(last_size.pending_updates, 1);
+        assert!(last_size.pending_bytes < new_size.pending_bytes);
+
+        index.updates.clear(&mut writer).unwrap();
+        assert_eq!(index.update_queue_size(&writer).unwrap(), UpdateQueueSize::default());
+    }
+
     #[test]
     fn check_number_ordering() {
         let dir = tempfile::tempdir().unwrap();
@@ -1093,4 +2246,1555 @@ mod tests {
         );
         assert_matches!(iter.next(), None);
     }
+
+    #[test]
+    fn geo_filter_and_ordering() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({
+            "id": 1,
+            "name": "restaurant in Lyon",
+            "_geo": { "lat": 45.7640, "lng": 4.8357 },
+        }));
+        additions.update_document(serde_json::json!({
+            "id": 2,
+            "name": "restaurant in Paris",
+            "_geo": { "lat": 48.8566, "lng": 2.3522 },
+        }));
+        additions.update_document(serde_json::json!({
+            "id": 3,
+            "name": "restaurant in Versailles",
+            "_geo": { "lat": "48.8049", "lng": "2.1204" },
+        }));
+        additions.update_document(serde_json::json!({
+            "id": 4,
+            "name": "restaurant somewhere",
+        }));
+
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // block until the transaction is processed
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        let schema = index.main.schema(&reader).unwrap().unwrap();
+        let names = |documents: Vec<Document>| -> Vec<String> {
+            documents
+                .into_iter()
+                .map(|document| {
+                    let document: serde_json::Value = index.document(&reader, None, document.id).unwrap().unwrap();
+                    document["name"].as_str().unwrap().to_string()
+                })
+                .collect()
+        };
+
+        // the documents sorted by their distance to the Eiffel Tower
+        let eiffel_tower = GeoPoint::new(48.8584, 2.2945).unwrap();
+        let criteria = CriteriaBuilder::new()
+            .add(criterion::GeoDistance::new(index.geo, eiffel_tower))
+            .add(criterion::DocumentId)
+            .build();
+        let builder = index.query_builder_with_criteria(criteria);
+        let SortResult { documents, .. } = builder.query(&reader, "restaurant", 0..20).unwrap();
+        assert_eq!(
+            names(documents),
+            vec!["restaurant in Paris", "restaurant in Versailles", "restaurant in Lyon", "restaurant somewhere"],
+        );
+
+        // the documents at most 20km away from the Eiffel Tower
+        let filter = Filter::parse("_geoRadius(48.8584, 2.2945, 20000)", &schema).unwrap();
+        let mut builder = index.query_builder();
+        builder.with_filter(|id| filter.test(&reader, &index, id).unwrap());
+        let SortResult { documents, .. } = builder.query(&reader, "restaurant", 0..20).unwrap();
+        let mut names = names(documents);
+        names.sort();
+        assert_eq!(names, vec!["restaurant in Paris", "restaurant in Versailles"]);
+        drop(reader);
+
+        // the coordinates are removed with the documents
+        let mut deletion = index.documents_deletion();
+        deletion.delete_document_by_id(update::compute_document_id("2", Default::default()).unwrap());
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = deletion.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        let filter = Filter::parse("_geoRadius(48.8584, 2.2945, 20000)", &schema).unwrap();
+        let mut builder = index.query_builder();
+        builder.with_filter(|id| filter.test(&reader, &index, id).unwrap());
+        let SortResult { documents, .. } = builder.query(&reader, "restaurant", 0..20).unwrap();
+        assert_eq!(documents.len(), 1);
+        drop(reader);
+
+        // a document with an invalid `_geo` field is skipped and reported
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 5, "name": "nowhere", "_geo": [48.8, 2.3] }));
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let update_reader = db.update_read_txn().unwrap();
+        let result = index.update_status(&update_reader, update_id).unwrap();
+        assert_matches!(
+            result,
+            Some(UpdateStatus::Processed { content })
+                if content.failed_documents.map_or(false, |failed| failed[0].document_id.as_deref() == Some("5"))
+        );
+    }
+
+    #[test]
+    fn sort_by_sortable_attributes() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "name": "shoe Bravo", "price": 30 }));
+        additions.update_document(serde_json::json!({ "id": 2, "name": "shoe alpha", "price": 10.5 }));
+        additions.update_document(serde_json::json!({ "id": 3, "name": "shoe Charlie" }));
+        additions.update_document(serde_json::json!({ "id": 4, "name": "shoe delta", "price": 20 }));
+
+        let mut writer = db.update_write_txn().unwrap();
+        additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // the documents already indexed are reindexed when the setting changes
+        let settings: Settings = serde_json::from_str(r#"{ "sortableAttributes": ["price", "name"] }"#).unwrap();
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = index.settings_update(&mut writer, settings.into_update().unwrap()).unwrap();
+        writer.commit().unwrap();
+
+        // block until the transaction is processed
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        let schema = index.main.schema(&reader).unwrap().unwrap();
+        let sortable_attributes = index.main.sortable_attributes(&reader).unwrap().unwrap();
+        let ids = |rules: &[&str]| -> Vec<u64> {
+            let mut builder = CriteriaBuilder::new();
+            for rule in rules {
+                let rule: criterion::SortRule = rule.parse().unwrap();
+                builder.push(criterion::SortBy::new(index.sortables, &schema, &sortable_attributes, &rule).unwrap());
+            }
+            builder.push(criterion::DocumentId);
+
+            let builder = index.query_builder_with_criteria(builder.build());
+            let SortResult { documents, .. } = builder.query(&reader, "shoe", 0..20).unwrap();
+            documents
+                .into_iter()
+                .map(|document| {
+                    let document: serde_json::Value = index.document(&reader, None, document.id).unwrap().unwrap();
+                    document["id"].as_u64().unwrap()
+                })
+                .collect()
+        };
+
+        assert_eq!(ids(&["price:asc"]), vec![2, 4, 1, 3]);
+        assert_eq!(ids(&["price:desc"]), vec![1, 4, 2, 3]);
+        assert_eq!(ids(&["name:asc"]), vec![2, 1, 3, 4]);
+
+        let rule: criterion::SortRule = "id:asc".parse().unwrap();
+        let result = criterion::SortBy::new(index.sortables, &schema, &sortable_attributes, &rule);
+        assert_matches!(result, Err(criterion::SortByError::AttributeNotSortable(_)));
+    }
+
+    #[test]
+    fn filter_with_facets_and_ranked_values() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let settings: Settings = serde_json::from_str(r#"{
+            "rankingRules": ["typo", "words", "proximity", "attribute", "wordsPosition", "exactness", "asc(price)"],
+            "attributesForFaceting": ["genre"]
+        }"#).unwrap();
+        let mut writer = db.update_write_txn().unwrap();
+        index.settings_update(&mut writer, settings.into_update().unwrap()).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "title": "movie", "genre": "Sci-Fi", "price": 10, "archived": false }));
+        additions.update_document(serde_json::json!({ "id": 2, "title": "movie", "genre": "horror", "price": 25, "archived": false }));
+        additions.update_document(serde_json::json!({ "id": 3, "title": "movie", "genre": ["drama", "horror"], "price": 15, "archived": true }));
+        additions.update_document(serde_json::json!({ "id": 4, "title": "movie", "genre": "sci-fi", "price": 12.5 }));
+        additions.update_document(serde_json::json!({ "id": 5, "title": "movie", "genre": "comedy", "price": 5 }));
+
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // block until the transaction is processed
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        let schema = index.main.schema(&reader).unwrap().unwrap();
+        let ids = |expression: &str| -> Vec<u64> {
+            let filter = Filter::parse(expression, &schema).unwrap();
+            let mut builder = index.query_builder();
+            builder.with_filter(|id| filter.test(&reader, &index, id).unwrap());
+            let SortResult { documents, .. } = builder.query(&reader, "movie", 0..20).unwrap();
+            let mut ids: Vec<_> = documents
+                .into_iter()
+                .map(|document| {
+                    let document: serde_json::Value = index.document(&reader, None, document.id).unwrap().unwrap();
+                    document["id"].as_u64().unwrap()
+                })
+                .collect();
+            ids.sort_unstable();
+
+            // the candidates read from the stores are the documents the filter accepts
+            let candidates = filter.candidates(&reader, &index).unwrap();
+            assert_eq!(candidates.len(), ids.len(), "{}", expression);
+
+            ids
+        };
+
+        let expression = r#"(genre = "sci-fi" OR genre = horror) AND price < 20 AND NOT archived = true"#;
+        assert_eq!(ids(expression), vec![1, 4]);
+        assert_eq!(ids("price >= 15"), vec![2, 3]);
+        assert_eq!(ids("price = 12.5 OR genre = comedy"), vec![4, 5]);
+        // one of the genres of the third document is not horror
+        assert_eq!(ids("genre != horror"), vec![1, 3, 4, 5]);
+
+        assert_matches!(Filter::parse("price < cheap", &schema), Err(Error::FilterParseError(_)));
+        assert_matches!(Filter::parse("(genre = horror", &schema), Err(Error::FilterParseError(_)));
+    }
+
+    #[test]
+    fn filter_with_numeric_ranges() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "title": "movie", "price": 10 }));
+        additions.update_document(serde_json::json!({ "id": 2, "title": "movie", "price": 50.5 }));
+        additions.update_document(serde_json::json!({ "id": 3, "title": "movie", "price": [5, 30] }));
+        additions.update_document(serde_json::json!({ "id": 4, "title": "movie", "price": -12 }));
+        additions.update_document(serde_json::json!({ "id": 5, "title": "movie", "price": "20" }));
+
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // block until the transaction is processed
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        let schema = index.main.schema(&reader).unwrap().unwrap();
+        let price = schema.id("price").unwrap();
+        let ids = |builder: crate::query_builder::QueryBuilder| -> Vec<u64> {
+            let SortResult { documents, .. } = builder.query(&reader, "movie", 0..20).unwrap();
+            let mut ids: Vec<_> = documents
+                .into_iter()
+                .map(|document| {
+                    let document: serde_json::Value = index.document(&reader, None, document.id).unwrap().unwrap();
+                    document["id"].as_u64().unwrap()
+                })
+                .collect();
+            ids.sort_unstable();
+            ids
+        };
+
+        let mut builder = index.query_builder();
+        builder.add_numeric_range(price, 10.0, 50.0);
+        assert_eq!(ids(builder), vec![1, 3]);
+
+        let mut builder = index.query_builder();
+        builder.add_numeric_range(price, f64::NEG_INFINITY, 0.0);
+        assert_eq!(ids(builder), vec![4]);
+
+        let mut builder = index.query_builder();
+        builder.add_numeric_range(price, 0.0, 20.0);
+        builder.add_numeric_range(price, 25.0, 60.0);
+        assert_eq!(ids(builder), vec![3]);
+
+        let filter = Filter::parse("price >= 10 AND price <= 50", &schema).unwrap();
+        let mut builder = index.query_builder();
+        builder.with_filter(|id| filter.test(&reader, &index, id).unwrap());
+        assert_eq!(ids(builder), vec![1, 3]);
+
+        let filter = Filter::parse("price > 10 AND price < 50.5", &schema).unwrap();
+        let mut builder = index.query_builder();
+        builder.with_filter(|id| filter.test(&reader, &index, id).unwrap());
+        assert_eq!(ids(builder), vec![3]);
+        drop(reader);
+
+        let mut deletion = index.documents_deletion();
+        deletion.delete_document_by_id(update::compute_document_id("3", Default::default()).unwrap());
+
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = deletion.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // block until the transaction is processed
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        // the numbers of the deleted documents are removed when they are purged
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = index.purge_deleted_documents(&mut writer).unwrap();
+        writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        let document_ids = index.numbers.range_document_ids(&reader, price, f64::NEG_INFINITY, f64::INFINITY).unwrap();
+        assert_eq!(document_ids.len(), 3);
+    }
+
+    #[test]
+    fn words_delta_is_searched_and_compacted() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        // the first addition writes the words set directly
+        let mut additions = index.documents_addition();
+        for i in 0..40 {
+            additions.update_document(serde_json::json!({ "id": i, "title": format!("word{}", i) }));
+        }
+        let mut update_writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        assert!(index.main.words_delta_fst(&reader).unwrap().is_none());
+        drop(reader);
+
+        // a small addition only writes the words delta
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 100, "title": "zebra" }));
+        let mut update_writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        let words_delta = index.main.words_delta_fst(&reader).unwrap().unwrap();
+        assert!(words_delta.contains("zebra"));
+        assert!(!index.main.words_fst(&reader).unwrap().unwrap().contains("zebra"));
+
+        let builder = index.query_builder();
+        let SortResult { documents, .. } = builder.query(&reader, "zebra", 0..20).unwrap();
+        let document_id = update::compute_document_id("100", Default::default()).unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].id, document_id);
+        drop(reader);
+
+        // the purge of the deleted documents merges the words delta into the words set
+        let mut deletion = index.documents_deletion();
+        deletion.delete_document_by_id(document_id);
+        let mut update_writer = db.update_write_txn().unwrap();
+        deletion.finalize(&mut update_writer).unwrap();
+        let update_id = index.purge_deleted_documents(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        assert!(index.main.words_delta_fst(&reader).unwrap().is_none());
+        let words = index.main.words_fst(&reader).unwrap().unwrap();
+        assert!(!words.contains("zebra"));
+        assert!(words.contains("word39"));
+    }
+
+    #[test]
+    fn integrity_check_and_repair() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "title": "hello world" }));
+        additions.update_document(serde_json::json!({ "id": 2, "title": "hello kevin" }));
+        let mut update_writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        let report = index.check_integrity(&reader).unwrap();
+        assert!(report.is_sound());
+        assert_eq!(report.number_of_documents, 2);
+        drop(reader);
+
+        // simulate the drift an interrupted update leaves behind
+        let mut writer = db.main_write_txn().unwrap();
+        index.postings_lists.del_postings_list(&mut writer, b"kevin").unwrap();
+        index.main.put_number_of_documents(&mut writer, |n| n + 3).unwrap();
+        let document_id = update::compute_document_id("2", Default::default()).unwrap();
+        index.documents_fields.del_all_document_fields(&mut writer, document_id).unwrap();
+        writer.commit().unwrap();
+
+        let reader = db.main_read_txn().unwrap();
+        let report = index.check_integrity(&reader).unwrap();
+        assert!(!report.is_sound());
+        assert_eq!(report.words_without_postings_list, 1);
+        assert_eq!(report.documents_without_fields, 1);
+        assert_eq!(report.number_of_documents, 1);
+        assert_eq!(report.stored_number_of_documents, 5);
+        drop(reader);
+
+        let mut update_writer = db.update_write_txn().unwrap();
+        let update_id = index.repair(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        let report = index.check_integrity(&reader).unwrap();
+        assert!(report.is_sound());
+        assert_eq!(report.stored_number_of_documents, 1);
+    }
+
+    #[test]
+    fn expired_documents_are_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let options = DatabaseOptions {
+            expiration_purge_interval: Duration::from_millis(50),
+            ..DatabaseOptions::default()
+        };
+        let database = Database::open_or_create(dir.path(), options).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "title": "expired", "_expiresAt": 1_600_000_000 }));
+        additions.update_document(serde_json::json!({ "id": 2, "title": "forever" }));
+        additions.update_document(serde_json::json!({ "id": 3, "title": "later", "_expiresAt": "2200-01-01T00:00:00Z" }));
+        let mut update_writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+        let _ = receiver.iter().find(|update| update.update_id == update_id);
+
+        // the next update is the deletion enqueued by the update loop
+        let update = receiver.recv().unwrap();
+        assert_eq!(update.update_id, update_id + 1);
+        assert_matches!(update.update_type, UpdateType::DocumentsDeletion { number: 1 });
+        assert!(update.error.is_none());
+
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 2);
+        let document_id = update::compute_document_id("1", Default::default()).unwrap();
+        assert!(index.document::<IgnoredAny>(&reader, None, document_id).unwrap().is_none());
+        assert_eq!(index.expirations.expired_documents(&reader, u64::max_value()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn read_only_database() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "title": "read only" }));
+        let mut update_writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+        let _ = receiver.iter().find(|update| update.update_id == update_id);
+
+        let snapshot_dir = tempfile::tempdir().unwrap();
+        database.snapshot(snapshot_dir.path()).unwrap();
+
+        let options = DatabaseOptions { read_only: true, ..DatabaseOptions::default() };
+        let replica = Database::open_or_create(snapshot_dir.path(), options).unwrap();
+        assert!(replica.is_read_only());
+
+        // the documents are searchable but nothing can be written
+        let index = replica.open_index("test").unwrap();
+        let reader = replica.main_read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 1);
+        let document_id = update::compute_document_id("1", Default::default()).unwrap();
+        assert!(index.document::<IgnoredAny>(&reader, None, document_id).unwrap().is_some());
+
+        assert_matches!(replica.main_write_txn(), Err(Error::ReadOnly));
+        assert_matches!(replica.update_write_txn(), Err(Error::ReadOnly));
+        assert_matches!(replica.create_index("other"), Err(Error::ReadOnly));
+        assert_matches!(replica.delete_index("test"), Err(Error::ReadOnly));
+        assert_matches!(replica.put_alias("alias", "test"), Err(Error::ReadOnly));
+        assert!(replica.open_index("test").is_some());
+        replica.shutdown().unwrap();
+    }
+
+    #[test]
+    fn iterate_over_all_documents() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        for i in 0..10 {
+            additions.update_document(serde_json::json!({ "id": i, "title": format!("title {}", i), "rank": i * 2 }));
+        }
+        let mut update_writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        let mut documents: Vec<_> = index.all_documents_iter(&reader).unwrap().map(Result::unwrap).collect();
+        assert_eq!(documents.len(), 10);
+
+        documents.sort_by_key(|(user_id, _)| user_id.parse::<u64>().unwrap());
+        for (i, (user_id, document)) in documents.into_iter().enumerate() {
+            assert_eq!(user_id, i.to_string());
+            assert_eq!(document["id"], i);
+            assert_eq!(document["title"], format!("title {}", i));
+            assert_eq!(document["rank"], i * 2);
+        }
+    }
+
+    #[test]
+    fn ndjson_addition_in_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let ndjson = r#"{ "id": 1, "title": "hello" }
+            { "id": 2, "title": "world" }
+
+            { "id": 3, "title": "hello world" }
+            { "id": 4, "title": "bye" }
+            { "id": 5, "title": "bye world" }
+        "#;
+
+        let mut additions = index.documents_addition();
+        additions.set_chunk_size(2);
+
+        let mut writer = db.update_write_txn().unwrap();
+        let mut update_ids = additions.extend_from_ndjson(&mut writer, ndjson.as_bytes()).unwrap();
+        update_ids.push(additions.finalize(&mut writer).unwrap());
+        writer.commit().unwrap();
+
+        assert_eq!(update_ids.len(), 3);
+
+        // block until the transactions are processed
+        let last_update_id = *update_ids.last().unwrap();
+        let _ = receiver.iter().find(|id| *id == last_update_id);
+
+        let update_reader = db.update_read_txn().unwrap();
+        for update_id in update_ids {
+            let result = index.update_status(&update_reader, update_id).unwrap();
+            assert_matches!(result, Some(UpdateStatus::Processed { content }) if content.error.is_none());
+        }
+
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 5);
+    }
+
+    #[test]
+    fn cancel_enqueued_update() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut first_addition = index.documents_addition();
+        first_addition.update_document(serde_json::json!({ "id": 1, "title": "hello" }));
+        let mut second_addition = index.documents_addition();
+        second_addition.update_document(serde_json::json!({ "id": 2, "title": "world" }));
+
+        // the updates are not processed before the transaction is committed
+        let mut update_writer = db.update_write_txn().unwrap();
+        let first_id = first_addition.finalize(&mut update_writer).unwrap();
+        let second_id = second_addition.finalize(&mut update_writer).unwrap();
+
+        let outcome = index.cancel_update(&mut update_writer, first_id).unwrap();
+        assert_eq!(outcome, update::CancelOutcome::Processing);
+        let outcome = index.cancel_update(&mut update_writer, second_id).unwrap();
+        assert_eq!(outcome, update::CancelOutcome::Canceled);
+        let outcome = index.cancel_update(&mut update_writer, second_id).unwrap();
+        assert_eq!(outcome, update::CancelOutcome::NotEnqueued);
+        update_writer.commit().unwrap();
+
+        // block until the transaction is processed
+        let _ = receiver.iter().find(|id| *id == first_id);
+
+        let update_reader = db.update_read_txn().unwrap();
+        let result = index.update_status(&update_reader, first_id).unwrap();
+        assert_matches!(result, Some(UpdateStatus::Processed { .. }));
+        let result = index.update_status(&update_reader, second_id).unwrap();
+        assert_matches!(result, Some(UpdateStatus::Canceled { .. }));
+
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 1);
+    }
+
+    #[test]
+    fn updates_results_retention() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let options = DatabaseOptions {
+            updates_retention: UpdatesRetention { max_entries: Some(2), max_age: None },
+            ..DatabaseOptions::default()
+        };
+        let database = Database::open_or_create(dir.path(), options).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut update_ids = Vec::new();
+        for id in 0..4 {
+            let mut additions = index.documents_addition();
+            additions.update_document(serde_json::json!({ "id": id, "title": "hello" }));
+
+            let mut writer = db.update_write_txn().unwrap();
+            update_ids.push(additions.finalize(&mut writer).unwrap());
+            writer.commit().unwrap();
+        }
+
+        // block until the transactions are processed
+        let last_update_id = *update_ids.last().unwrap();
+        let _ = receiver.iter().find(|id| *id == last_update_id);
+
+        let update_reader = db.update_read_txn().unwrap();
+        assert_eq!(index.updates_results.update_ids(&update_reader).unwrap(), &update_ids[2..]);
+        drop(update_reader);
+
+        let mut update_writer = db.update_write_txn().unwrap();
+        let cleared = index.clear_processed_updates(&mut update_writer, last_update_id + 1).unwrap();
+        update_writer.commit().unwrap();
+
+        // the last update result is always kept
+        assert_eq!(cleared, 1);
+    }
+
+    #[test]
+    fn hidden_fields_are_kept_but_not_retrieved() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let settings = {
+            let data = r#"
+                {
+                    "searchableAttributes": ["name", "email"],
+                    "displayedAttributes": ["id", "name"]
+                }
+            "#;
+            let settings: Settings = serde_json::from_str(data).unwrap();
+            settings.into_update().unwrap()
+        };
+
+        let mut writer = db.update_write_txn().unwrap();
+        let _update_id = index.settings_update(&mut writer, settings).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "name": "Marvin", "email": "marvin@example.com" }));
+
+        let mut writer = db.update_write_txn().unwrap();
+        let _update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // a partial update must not lose the fields that are not displayed
+        let mut additions = index.documents_partial_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "name": "Kevin" }));
+
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // block until the transaction is processed
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        let document_id = update::compute_document_id("1", Default::default()).unwrap();
+
+        let document: Option<serde_json::Value> = index.document(&reader, None, document_id).unwrap();
+        assert_eq!(document, Some(serde_json::json!({ "id": 1, "name": "Kevin" })));
+
+        let attributes = ["name"].iter().cloned().collect();
+        let document: Option<serde_json::Value> = index.document(&reader, Some(&attributes), document_id).unwrap();
+        assert_eq!(document, Some(serde_json::json!({ "name": "Kevin" })));
+
+        let document: Option<serde_json::Value> = index.full_document(&reader, document_id).unwrap();
+        assert_eq!(document, Some(serde_json::json!({ "id": 1, "name": "Kevin", "email": "marvin@example.com" })));
+    }
+
+    #[test]
+    fn attribute_weights_at_query_time() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let settings: Settings = serde_json::from_str(r#"{ "searchableAttributes": ["title", "overview"] }"#).unwrap();
+        let mut writer = db.update_write_txn().unwrap();
+        let _update_id = index.settings_update(&mut writer, settings.into_update().unwrap()).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "title": "shoe", "overview": "a red one" }));
+        additions.update_document(serde_json::json!({ "id": 2, "title": "a red one", "overview": "shoe" }));
+
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // block until the transaction is processed
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        let schema = index.main.schema(&reader).unwrap().unwrap();
+        let ids = |searchable: &[&str], weights: &[(&str, f64)]| -> Vec<u64> {
+            let mut builder = CriteriaBuilder::new();
+            builder.push(criterion::Attribute);
+            builder.push(criterion::DocumentId);
+
+            let mut builder = index.query_builder_with_criteria(builder.build());
+            if !searchable.is_empty() {
+                builder.restrict_searchable_attributes(&schema, searchable);
+            }
+            builder.set_attribute_weights(&schema, weights);
+            let SortResult { documents, .. } = builder.query(&reader, "shoe", 0..20).unwrap();
+            documents
+                .into_iter()
+                .map(|document| {
+                    let document: serde_json::Value = index.document(&reader, None, document.id).unwrap().unwrap();
+                    document["id"].as_u64().unwrap()
+                })
+                .collect()
+        };
+
+        assert_eq!(ids(&[], &[]), vec![1, 2]);
+        assert_eq!(ids(&[], &[("overview", 3.0)]), vec![2, 1]);
+        // the weights follow the attributes when the searchable attributes are reordered
+        assert_eq!(ids(&["overview", "title"], &[]), vec![2, 1]);
+        assert_eq!(ids(&["overview", "title"], &[("title", 3.0)]), vec![1, 2]);
+    }
+
+    #[test]
+    fn update_notifications() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let notifications = db.subscribe_updates();
+        let (sender, receiver) = mpsc::sync_channel(100);
+        db.add_update_listener(Box::new(move |notification| {
+            sender.send(notification.update_id).unwrap()
+        }));
+
+        let index = database.create_index("test").unwrap();
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "name": "Marvin" }));
+        let mut update_writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+
+        let notification = notifications.recv().unwrap();
+        assert_eq!(notification.index_uid, "test");
+        assert_eq!(notification.update_id, update_id);
+        assert_eq!(notification.status, "processed");
+        assert!(notification.error.is_none());
+        assert_eq!(receiver.recv().unwrap(), update_id);
+
+        // the dropped receivers are not notified anymore
+        drop(notifications);
+        let mut deletion = index.documents_deletion();
+        deletion.delete_document_by_id(DocumentId(42));
+        let mut update_writer = db.update_write_txn().unwrap();
+        let update_id = deletion.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+
+        assert_eq!(receiver.recv().unwrap(), update_id);
+        assert_eq!(db.hooks.listeners.read().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn copy_index() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("source").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "name": "Marvin" }));
+        additions.update_document(serde_json::json!({ "id": 2, "name": "Kevin" }));
+
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // block until the transaction is processed
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let copy = database.copy_index("source", "copy", false).unwrap();
+        assert_matches!(database.copy_index("source", "copy", false), Err(Error::IndexAlreadyExists));
+        assert_matches!(database.copy_index("missing", "other", false), Err(Error::IndexNotFound(_)));
+
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(copy.main.name(&reader).unwrap().as_deref(), Some("copy"));
+        assert_eq!(copy.main.number_of_documents(&reader).unwrap(), 2);
+        let SortResult { documents, .. } = copy.query_builder().query(&reader, "marvin", 0..20).unwrap();
+        assert_eq!(documents.len(), 1);
+        reader.abort();
+
+        let update_reader = db.update_read_txn().unwrap();
+        assert!(copy.all_updates_status(&update_reader).unwrap().is_empty());
+        update_reader.abort();
+
+        // the copy is independent from the source
+        let mut deletion = copy.documents_deletion();
+        deletion.delete_document_by_id(documents[0].id);
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = deletion.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(copy.main.number_of_documents(&reader).unwrap(), 1);
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 2);
+        reader.abort();
+
+        let with_updates = database.copy_index("source", "with-updates", true).unwrap();
+        let update_reader = db.update_read_txn().unwrap();
+        assert_eq!(with_updates.all_updates_status(&update_reader).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rename_index() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("source").unwrap();
+        database.create_index("other").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "name": "Marvin" }));
+        additions.update_document(serde_json::json!({ "id": 2, "name": "Kevin" }));
+
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // block until the transaction is processed
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        database.put_alias("people", "source").unwrap();
+
+        assert_matches!(database.rename_index("source", "other"), Err(Error::IndexAlreadyExists));
+        assert_matches!(database.rename_index("missing", "renamed"), Err(Error::IndexNotFound(_)));
+        database.rename_index("source", "renamed").unwrap();
+
+        assert!(database.open_index("source").is_none());
+        let renamed = database.open_index("renamed").unwrap();
+        assert_eq!(database.aliases(), vec![("people".to_string(), "renamed".to_string())]);
+
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(renamed.main.name(&reader).unwrap().as_deref(), Some("renamed"));
+        assert_eq!(renamed.main.number_of_documents(&reader).unwrap(), 2);
+        let SortResult { documents, .. } = renamed.query_builder().query(&reader, "marvin", 0..20).unwrap();
+        assert_eq!(documents.len(), 1);
+        reader.abort();
+
+        let update_reader = db.update_read_txn().unwrap();
+        assert_eq!(renamed.all_updates_status(&update_reader).unwrap().len(), 1);
+        update_reader.abort();
+
+        // the old uid is free again and gets its own stores
+        let source = database.create_index("source").unwrap();
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(source.main.number_of_documents(&reader).unwrap(), 0);
+        assert_eq!(renamed.main.number_of_documents(&reader).unwrap(), 2);
+    }
+
+    #[test]
+    fn metadata_namespaces() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_metadata(&mut writer, "app", "owner", &serde_json::json!("kevin")).unwrap();
+        index.main.put_metadata(&mut writer, "app", "tags", &serde_json::json!(["a", "b"])).unwrap();
+        index.main.put_metadata(&mut writer, "ap", "p/owner", &serde_json::json!("marvin")).unwrap();
+        index.main.put_metadata(&mut writer, "app2", "owner", &serde_json::json!("bob")).unwrap();
+        writer.commit().unwrap();
+
+        let mut update = MetadataUpdate::new("app");
+        update.put("version", serde_json::json!(2));
+        update.delete("tags");
+
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = index.metadata_update(&mut writer, update).unwrap();
+        writer.commit().unwrap();
+
+        // block until the transaction is processed
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        let entries = index.main.metadata_entries(&reader, "app").unwrap();
+        assert_eq!(entries, vec![
+            ("owner".to_string(), serde_json::json!("kevin")),
+            ("version".to_string(), serde_json::json!(2)),
+        ]);
+        assert_eq!(index.main.metadata(&reader, "app", "tags").unwrap(), None);
+        assert_eq!(index.main.metadata(&reader, "ap", "p/owner").unwrap(), Some(serde_json::json!("marvin")));
+        assert_eq!(index.main.metadata_entries(&reader, "app2").unwrap().len(), 1);
+        assert!(index.main.metadata_entries(&reader, "unknown").unwrap().is_empty());
+
+        let update_reader = db.update_read_txn().unwrap();
+        let status = index.update_status(&update_reader, update_id).unwrap();
+        assert_matches!(status, Some(UpdateStatus::Processed { content }) if content.error.is_none());
+    }
+
+    #[test]
+    fn soft_deleted_documents_are_purged() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "title": "walrus" }));
+        additions.update_document(serde_json::json!({ "id": 2, "title": "penguin" }));
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        // the deleted document is hidden but its postings are kept
+        let document_id = update::compute_document_id("1", Default::default()).unwrap();
+        let mut deletion = index.documents_deletion();
+        deletion.delete_document_by_id(document_id);
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = deletion.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 1);
+        assert!(index.is_deleted(&reader, document_id).unwrap());
+        assert!(index.document::<IgnoredAny>(&reader, None, document_id).unwrap().is_none());
+        assert!(index.postings_lists.postings_list(&reader, b"walrus").unwrap().is_some());
+
+        let builder = index.query_builder();
+        let SortResult { documents, .. } = builder.query(&reader, "walrus", 0..20).unwrap();
+        assert!(documents.is_empty());
+
+        let report = index.check_integrity(&reader).unwrap();
+        assert_eq!(report.deleted_documents, 1);
+        assert!(report.is_sound());
+        drop(reader);
+
+        // the purge removes the deleted documents from the stores
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = index.purge_deleted_documents(&mut writer).unwrap();
+        writer.commit().unwrap();
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 1);
+        assert!(index.main.deleted_documents(&reader).unwrap().is_none());
+        assert!(index.postings_lists.postings_list(&reader, b"walrus").unwrap().is_none());
+        assert!(index.postings_lists.postings_list(&reader, b"penguin").unwrap().is_some());
+
+        let report = index.check_integrity(&reader).unwrap();
+        assert_eq!(report.deleted_documents, 0);
+        assert!(report.is_sound());
+
+        let update_reader = db.update_read_txn().unwrap();
+        let status = index.update_status(&update_reader, update_id).unwrap();
+        assert_matches!(status, Some(UpdateStatus::Processed { content }) if content.error.is_none());
+    }
+
+    #[test]
+    fn consecutive_additions_are_coalesced() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        // the updates are enqueued before the update loop processes the first one
+        let mut update_writer = db.update_write_txn().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "title": "first" }));
+        additions.update_document(serde_json::json!({ "id": 1, "title": "first again" }));
+        let first_id = additions.finalize(&mut update_writer).unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "title": "replaced" }));
+        additions.update_document(serde_json::json!({ "id": 2, "title": "second" }));
+        additions.update_document(serde_json::json!({ "id": [3], "title": "invalid" }));
+        let second_id = additions.finalize(&mut update_writer).unwrap();
+
+        let mut partial = index.documents_partial_addition();
+        partial.update_document(serde_json::json!({ "id": 2, "kind": "partial" }));
+        let partial_id = partial.finalize(&mut update_writer).unwrap();
+
+        update_writer.commit().unwrap();
+
+        let first = receiver.recv().unwrap();
+        let second = receiver.recv().unwrap();
+        let third = receiver.recv().unwrap();
+
+        // the additions are applied at once but every update has its own result
+        assert_eq!(first.update_id, first_id);
+        assert_matches!(first.update_type, UpdateType::DocumentsAddition { number: 2 });
+        assert_eq!(first.duplicates.unwrap().ids, vec!["1".to_string()]);
+        assert!(first.failed_documents.is_none());
+
+        assert_eq!(second.update_id, second_id);
+        assert_matches!(second.update_type, UpdateType::DocumentsAddition { number: 3 });
+        assert!(second.duplicates.is_none());
+        assert_eq!(second.failed_documents.unwrap()[0].position, 2);
+        assert_eq!(first.processed_at, second.processed_at);
+
+        // a partial addition is not coalesced with the complete ones
+        assert_eq!(third.update_id, partial_id);
+        assert_matches!(third.update_type, UpdateType::DocumentsPartial { number: 1 });
+        assert!(third.error.is_none());
+
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 2);
+
+        let document_id = update::compute_document_id("1", Default::default()).unwrap();
+        let document: Option<serde_json::Value> = index.document(&reader, None, document_id).unwrap();
+        assert_eq!(document.unwrap()["title"], "replaced");
+
+        let document_id = update::compute_document_id("2", Default::default()).unwrap();
+        let document: Option<serde_json::Value> = index.document(&reader, None, document_id).unwrap();
+        assert_eq!(document.unwrap(), serde_json::json!({ "id": 2, "title": "second", "kind": "partial" }));
+
+        let update_reader = db.update_read_txn().unwrap();
+        for update_id in &[first_id, second_id, partial_id] {
+            let status = index.update_status(&update_reader, *update_id).unwrap();
+            assert_matches!(status, Some(UpdateStatus::Processed { .. }));
+        }
+    }
+
+    #[test]
+    fn update_queue_limits() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let options = DatabaseOptions {
+            update_queue_limits: UpdateQueueLimits { max_pending_updates: Some(2), max_pending_bytes: Some(1024) },
+            ..DatabaseOptions::default()
+        };
+        let database = Database::open_or_create(dir.path(), options).unwrap();
+        let db = &database;
+        let index = database.create_index("test").unwrap();
+
+        // the updates are not processed before the transaction is committed
+        let mut update_writer = db.update_write_txn().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "title": "hello" }));
+        additions.finalize(&mut update_writer).unwrap();
+
+        let size = index.update_queue_size(&update_writer).unwrap();
+        assert_eq!(size.pending_updates, 1);
+        assert!(size.pending_bytes > 0);
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 2, "title": "a".repeat(2048) }));
+        let result = additions.finalize(&mut update_writer);
+        assert_matches!(result, Err(Error::UpdateQueueFull(QueueLimit::PendingBytes(1024))));
+
+        // the refused update is not enqueued
+        assert_eq!(index.update_queue_size(&update_writer).unwrap(), size);
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 3, "title": "world" }));
+        additions.finalize(&mut update_writer).unwrap();
+
+        let mut deletion = index.documents_deletion();
+        deletion.delete_document_by_id(update::compute_document_id("1", Default::default()).unwrap());
+        let result = deletion.finalize(&mut update_writer);
+        assert_matches!(result, Err(Error::UpdateQueueFull(QueueLimit::PendingUpdates(2))));
+
+        let result = index.check_update_queue(&update_writer);
+        assert_matches!(result, Err(Error::UpdateQueueFull(QueueLimit::PendingUpdates(2))));
+        assert_eq!(index.update_queue_size(&update_writer).unwrap().pending_updates, 2);
+    }
+
+    #[test]
+    fn full_map_grows() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let options = DatabaseOptions {
+            main_map_size: 1024 * 1024,
+            max_main_map_size: Some(64 * 1024 * 1024),
+            ..DatabaseOptions::default()
+        };
+        let database = Database::open_or_create(dir.path(), options).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        // the documents do not fit in the initial map
+        let mut additions = index.documents_addition();
+        for id in 0..100 {
+            let words: Vec<_> = (0..2000).map(|i| format!("word{}x{}", id, i)).collect();
+            additions.update_document(serde_json::json!({ "id": id, "text": words.join(" ") }));
+        }
+
+        let mut update_writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+
+        let result = receiver.recv().unwrap();
+        assert_eq!(result.update_id, update_id);
+        assert!(result.error.is_none());
+
+        let usage = database.main_environment_usage().unwrap();
+        assert!(usage.map_size > 1024 * 1024);
+        assert_eq!(usage.max_map_size, Some(64 * 1024 * 1024));
+
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 100);
+    }
+
+    #[test]
+    fn full_update_map_grows() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let options = DatabaseOptions {
+            update_map_size: 1024 * 1024,
+            max_update_map_size: Some(64 * 1024 * 1024),
+            ..DatabaseOptions::default()
+        };
+        let database = Database::open_or_create(dir.path(), options).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        // the documents fit in the initial map but not the ids generated for them
+        let mut additions = index.documents_addition();
+        additions.set_options(AdditionOptions { generate_ids: true, ..AdditionOptions::default() });
+        for _ in 0..50_000 {
+            additions.update_document(serde_json::json!({}));
+        }
+
+        let mut update_writer = db.update_write_txn().unwrap();
+        let last_update_id = additions.finalize(&mut update_writer).unwrap();
+        update_writer.commit().unwrap();
+
+        // the results are written once the map has grown, the loop must not wait for itself
+        let mut generated = 0;
+        loop {
+            let result = receiver.recv_timeout(Duration::from_secs(60)).unwrap();
+            assert!(result.error.is_none());
+            generated += result.generated_ids.map_or(0, |ids| ids.len());
+            if result.update_id == last_update_id {
+                break;
+            }
+        }
+        assert_eq!(generated, 50_000);
+
+        let usage = database.update_environment_usage().unwrap();
+        assert!(usage.map_size > 1024 * 1024);
+
+        let reader = db.main_read_txn().unwrap();
+        assert_eq!(index.main.number_of_documents(&reader).unwrap(), 50_000);
+    }
+
+    #[test]
+    fn indexes_take_turns_to_process_their_updates() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |name: &str, _update: ProcessedUpdateResult| {
+            sender.send(name.to_owned()).unwrap()
+        };
+        let large = database.create_index("large").unwrap();
+        let small = database.create_index("small").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        large.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        small.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        // the additions and the partial additions are not coalesced, each one is a batch
+        let mut writer = db.update_write_txn().unwrap();
+        for i in 0..4 {
+            let mut additions = large.documents_addition();
+            additions.update_document(serde_json::json!({ "id": i, "name": "Marvin" }));
+            additions.finalize(&mut writer).unwrap();
+
+            let mut partial = large.documents_partial_addition();
+            partial.update_document(serde_json::json!({ "id": i, "age": 42 }));
+            partial.finalize(&mut writer).unwrap();
+        }
+
+        let mut additions = small.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "name": "Kevin" }));
+        additions.finalize(&mut writer).unwrap();
+
+        // one update loop waits for this transaction, the other one is in line for the turn
+        database.hooks.turns.wait_for_waiting_loops(1);
+        writer.commit().unwrap();
+
+        let processed: Vec<_> = receiver.iter().take(9).collect();
+        let position = processed.iter().position(|name| name == "small").unwrap();
+        assert!(position <= 1, "the small index waited for the large one: {:?}", processed);
+    }
+
+    #[test]
+    fn facets_of_arrays_across_updates() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let database = Database::open_or_create(dir.path(), DatabaseOptions::default()).unwrap();
+        let db = &database;
+
+        let (sender, receiver) = mpsc::sync_channel(100);
+        let update_fn = move |_name: &str, update: ProcessedUpdateResult| {
+            sender.send(update.update_id).unwrap()
+        };
+        let index = database.create_index("test").unwrap();
+
+        database.set_update_callback(Box::new(update_fn));
+
+        let mut writer = db.main_write_txn().unwrap();
+        index.main.put_schema(&mut writer, &Schema::with_primary_key("id")).unwrap();
+        writer.commit().unwrap();
+
+        let settings: Settings = serde_json::from_str(r#"{ "attributesForFaceting": ["tags"] }"#).unwrap();
+        let mut writer = db.update_write_txn().unwrap();
+        index.settings_update(&mut writer, settings.into_update().unwrap()).unwrap();
+        writer.commit().unwrap();
+
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 1, "title": "movie", "tags": ["a", "b"] }));
+        additions.update_document(serde_json::json!({ "id": 2, "title": "movie", "tags": "b" }));
+        let mut writer = db.update_write_txn().unwrap();
+        additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // the values of the second update are shared with the documents of the first one
+        let mut additions = index.documents_addition();
+        additions.update_document(serde_json::json!({ "id": 3, "title": "movie", "tags": ["b", "c", "c"] }));
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = additions.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        // block until the transaction is processed
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        let search = |filter: &str| -> (Vec<u64>, HashMap<String, usize>) {
+            let reader = db.main_read_txn().unwrap();
+            let schema = index.main.schema(&reader).unwrap().unwrap();
+            let attributes_for_faceting = index.main.attributes_for_faceting(&reader).unwrap().unwrap();
+            let normalization = index.main.facet_normalization(&reader).unwrap().unwrap_or_default();
+            let tags = schema.id("tags").unwrap();
+
+            let filter = FacetFilter::from_str(filter, &schema, &attributes_for_faceting, &normalization).unwrap();
+            let mut builder = index.query_builder();
+            builder.set_facet_filter(Some(filter));
+            builder.set_facets(Some(vec![(tags, "tags".to_string())]));
+            let SortResult { documents, facets, .. } = builder.query(&reader, "movie", 0..20).unwrap();
+
+            let mut ids: Vec<_> = documents
+                .into_iter()
+                .map(|document| {
+                    let document: serde_json::Value = index.document(&reader, None, document.id).unwrap().unwrap();
+                    document["id"].as_u64().unwrap()
+                })
+                .collect();
+            ids.sort_unstable();
+
+            let mut counts = facets.unwrap().remove("tags").unwrap();
+            counts.retain(|_, count| *count > 0);
+            (ids, counts)
+        };
+
+        let counts = |pairs: &[(&str, usize)]| -> HashMap<String, usize> {
+            pairs.iter().map(|(value, count)| (value.to_string(), *count)).collect()
+        };
+
+        assert_eq!(search(r#"["tags:a"]"#).0, vec![1]);
+        assert_eq!(search(r#"["tags:b"]"#).0, vec![1, 2, 3]);
+        assert_eq!(search(r#"["tags:c"]"#).0, vec![3]);
+        assert_eq!(search(r#"[["tags:a", "tags:c"]]"#).1, counts(&[("a", 1), ("b", 3), ("c", 1)]));
+
+        // each value of the deleted document loses it, the ones it held alone are removed
+        let mut deletion = index.documents_deletion();
+        deletion.delete_document_by_id(update::compute_document_id("1", Default::default()).unwrap());
+        let mut writer = db.update_write_txn().unwrap();
+        let update_id = deletion.finalize(&mut writer).unwrap();
+        writer.commit().unwrap();
+
+        let _ = receiver.iter().find(|id| *id == update_id);
+
+        assert_eq!(search(r#"["tags:a"]"#).0, Vec::<u64>::new());
+        assert_eq!(search(r#"["tags:b"]"#).0, vec![2, 3]);
+        assert_eq!(search(r#"["tags:b"]"#).1, counts(&[("b", 2), ("c", 1)]));
+
+        let reader = db.main_read_txn().unwrap();
+        let schema = index.main.schema(&reader).unwrap().unwrap();
+        let key = FacetKey::new(schema.id("tags").unwrap(), "a".to_string());
+        assert!(index.facets.facet_document_ids(&reader, &key).unwrap().is_none());
+    }
 }
@@ -2,6 +2,7 @@ use crate::serde::{DeserializerError, SerializerError};
 use serde_json::Error as SerdeJsonError;
 use pest::error::Error as PestError;
 use crate::filters::Rule;
+use meilisearch_schema::FieldType;
 use std::{error, fmt, io};
 
 pub use bincode::Error as BincodeError;
@@ -15,11 +16,17 @@ pub type MResult<T> = Result<T, Error>;
 pub enum Error {
     Io(io::Error),
     IndexAlreadyExists,
+    IndexNotFound(String),
+    AliasConflict(String),
     MissingPrimaryKey,
     SchemaMissing,
     WordIndexMissing,
     MissingDocumentId,
+    DuplicateDocumentIds(Vec<String>),
     MaxFieldsLimitExceeded,
+    InvalidGeoField(String),
+    InvalidExpiresAtField(String),
+    InvalidFieldType { field: String, expected: FieldType },
     Schema(meilisearch_schema::Error),
     Zlmdb(heed::Error),
     Fst(fst::Error),
@@ -30,6 +37,9 @@ pub enum Error {
     UnsupportedOperation(UnsupportedOperation),
     FilterParseError(PestError<Rule>),
     FacetError(FacetError),
+    BatchUpdate { position: usize, error: String },
+    ReadOnly,
+    UpdateQueueFull(QueueLimit),
 }
 
 impl From<io::Error> for Error {
@@ -52,6 +62,8 @@ impl From<PestError<Rule>> for Error {
                 Rule::eq => "field = value",
                 Rule::leq => "field <= value",
                 Rule::geq => "field >= value",
+                Rule::has => "HAS field",
+                Rule::geo_radius => "_geoRadius(lat, lng, meters)",
                 Rule::key => "key",
                 _ => "other",
             };
@@ -120,11 +132,17 @@ impl fmt::Display for Error {
         match self {
             Io(e) => write!(f, "{}", e),
             IndexAlreadyExists => write!(f, "index already exists"),
+            IndexNotFound(uid) => write!(f, "index {} not found", uid),
+            AliasConflict(uid) => write!(f, "{} is already the uid of an index or an alias", uid),
             MissingPrimaryKey => write!(f, "schema cannot be built without a primary key"),
             SchemaMissing => write!(f, "this index does not have a schema"),
             WordIndexMissing => write!(f, "this index does not have a word index"),
             MissingDocumentId => write!(f, "document id is missing"),
+            DuplicateDocumentIds(ids) => write!(f, "documents ids appear multiple times in the update: {}", ids.join(", ")),
             MaxFieldsLimitExceeded => write!(f, "maximum number of fields in a document exceeded"),
+            InvalidGeoField(e) => write!(f, "invalid `_geo` field; {}", e),
+            InvalidExpiresAtField(e) => write!(f, "invalid `_expiresAt` field; {}", e),
+            InvalidFieldType { field, expected } => write!(f, "invalid `{}` field; the value must be of type {}", field, expected),
             Schema(e) => write!(f, "schema error; {}", e),
             Zlmdb(e) => write!(f, "heed error; {}", e),
             Fst(e) => write!(f, "fst error; {}", e),
@@ -135,12 +153,41 @@ impl fmt::Display for Error {
             UnsupportedOperation(op) => write!(f, "unsupported operation; {}", op),
             FilterParseError(e) => write!(f, "error parsing filter; {}", e),
             FacetError(e) => write!(f, "error processing facet filter: {}", e),
+            BatchUpdate { position, error } => write!(f, "update {} of the batch failed; {}", position, error),
+            ReadOnly => write!(f, "the database is opened in read-only mode"),
+            UpdateQueueFull(limit) => write!(f, "the update queue is full; the limit of {} is reached", limit),
+        }
+    }
+}
+
+impl Error {
+    /// Whether the error comes from an LMDB environment of which the map is full.
+    pub fn is_map_full(&self) -> bool {
+        match self {
+            Error::Zlmdb(heed::Error::Mdb(heed::MdbError::MapFull)) => true,
+            _ => false,
         }
     }
 }
 
 impl error::Error for Error {}
 
+/// The limit of the update queue that refused an update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueLimit {
+    PendingUpdates(u64),
+    PendingBytes(u64),
+}
+
+impl fmt::Display for QueueLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueueLimit::PendingUpdates(max) => write!(f, "{} pending updates", max),
+            QueueLimit::PendingBytes(max) => write!(f, "{} pending bytes", max),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum UnsupportedOperation {
     SchemaAlreadyExists,
@@ -197,7 +244,7 @@ impl fmt::Display for FacetError {
             InvalidFormat(found) => write!(f, "invalid facet: {}, facets should be \"facetName:facetValue\"", found),
             AttributeNotFound(attr) => write!(f, "unknown {:?} attribute", attr),
             AttributeNotSet { found, expected } => write!(f, "`{}` is not set as a faceted attribute. available facet attributes: {}", found, expected.join(", ")),
-            InvalidDocumentAttribute(attr) => write!(f, "invalid document attribute {}, accepted types: String and [String]", attr),
+            InvalidDocumentAttribute(attr) => write!(f, "invalid document attribute {}, accepted types: String, Number and arrays of them", attr),
         }
     }
 }
@@ -0,0 +1,42 @@
+use chrono::DateTime;
+use serde_json::Value;
+
+use crate::error::Error;
+
+/// The reserved attribute holding the date after which a document is deleted, a number
+/// of seconds since the Unix epoch or an RFC 3339 date.
+pub const EXPIRES_AT_FIELD: &str = "_expiresAt";
+
+/// Extracts the expiration date from the value of the `_expiresAt` attribute, in seconds
+/// since the Unix epoch, the dates before the epoch are already expired.
+pub fn expiration_from_value(value: &Value) -> Result<u64, Error> {
+    let timestamp = match value {
+        Value::Number(number) => number
+            .as_i64()
+            .ok_or_else(|| Error::InvalidExpiresAtField("must be an integer number of seconds".to_string()))?,
+        Value::String(string) => DateTime::parse_from_rfc3339(string)
+            .map_err(|e| Error::InvalidExpiresAtField(e.to_string()))?
+            .timestamp(),
+        _ => return Err(Error::InvalidExpiresAtField("must be a number or an RFC 3339 date".to_string())),
+    };
+
+    Ok(timestamp.max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn expiration_from_values() {
+        assert_eq!(expiration_from_value(&json!(1_600_000_000)).unwrap(), 1_600_000_000);
+        assert_eq!(expiration_from_value(&json!("2020-09-13T12:26:40Z")).unwrap(), 1_600_000_000);
+        assert_eq!(expiration_from_value(&json!("2020-09-13T14:26:40+02:00")).unwrap(), 1_600_000_000);
+        assert_eq!(expiration_from_value(&json!(-12)).unwrap(), 0);
+
+        assert_matches!(expiration_from_value(&json!(12.5)), Err(Error::InvalidExpiresAtField(_)));
+        assert_matches!(expiration_from_value(&json!("tomorrow")), Err(Error::InvalidExpiresAtField(_)));
+        assert_matches!(expiration_from_value(&json!(true)), Err(Error::InvalidExpiresAtField(_)));
+    }
+}
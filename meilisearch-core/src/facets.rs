@@ -1,12 +1,13 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::convert::TryInto;
 use std::hash::Hash;
 use std::ops::Deref;
 
-use cow_utils::CowUtils;
 use either::Either;
 use heed::types::{Str, OwnedType};
 use indexmap::IndexMap;
+use serde::Serialize;
 use serde_json::Value;
 
 use meilisearch_schema::{FieldId, Schema};
@@ -14,15 +15,17 @@ use meilisearch_types::DocumentId;
 
 use crate::database::MainT;
 use crate::error::{FacetError, Error};
+use crate::settings::FacetNormalization;
 use crate::store::BEU16;
+use crate::update::{nested_fields, nested_path, nested_value};
 
 /// Data structure used to represent a boolean expression in the form of nested arrays.
 /// Values in the outer array are and-ed together, values in the inner arrays are or-ed together.
 #[derive(Debug, PartialEq)]
-pub struct FacetFilter(Vec<Either<Vec<FacetKey>, FacetKey>>);
+pub struct FacetFilter(Vec<Either<Vec<FacetCondition>, FacetCondition>>);
 
 impl Deref for FacetFilter {
-    type Target = Vec<Either<Vec<FacetKey>, FacetKey>>;
+    type Target = Vec<Either<Vec<FacetCondition>, FacetCondition>>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -34,6 +37,7 @@ impl FacetFilter {
         s: &str,
         schema: &Schema,
         attributes_for_faceting: &[FieldId],
+        normalization: &FacetNormalization,
     ) -> Result<Self, FacetError> {
 
         let parsed = serde_json::from_str::<Value>(s).map_err(|e| FacetError::ParsingError(e.to_string()))?;
@@ -46,8 +50,8 @@ impl FacetFilter {
                 for expr in and_exprs {
                     match expr {
                         Value::String(s) => {
-                            let key = FacetKey::from_str( &s, schema, attributes_for_faceting)?;
-                            filter.push(Either::Right(key));
+                            let condition = FacetCondition::from_str(&s, schema, attributes_for_faceting, normalization)?;
+                            filter.push(Either::Right(condition));
                         }
                         Value::Array(or_exprs) => {
                             if or_exprs.is_empty() {
@@ -57,8 +61,8 @@ impl FacetFilter {
                             for expr in or_exprs {
                                 match expr {
                                     Value::String(s) => {
-                                        let key = FacetKey::from_str( &s, schema, attributes_for_faceting)?;
-                                        inner.push(key);
+                                        let condition = FacetCondition::from_str(&s, schema, attributes_for_faceting, normalization)?;
+                                        inner.push(condition);
                                     }
                                     bad_value => return Err(FacetError::unexpected_token(&["String"], bad_value)),
                                 }
@@ -75,19 +79,63 @@ impl FacetFilter {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Hash)]
+/// A clause of a facet filter.
+#[derive(Debug, PartialEq)]
+pub enum FacetCondition {
+    /// The facet must be equal to this value, once both are normalized.
+    Equal(FacetKey),
+    /// The numeric facet must be in this range, bounds included: `price:10 TO 50`.
+    Range { field_id: FieldId, from: f64, to: f64 },
+}
+
+impl FacetCondition {
+    pub fn field_id(&self) -> FieldId {
+        match self {
+            FacetCondition::Equal(key) => key.key(),
+            FacetCondition::Range { field_id, .. } => *field_id,
+        }
+    }
+
+    fn from_str(
+        s: &str,
+        schema: &Schema,
+        attributes_for_faceting: &[FieldId],
+        normalization: &FacetNormalization,
+    ) -> Result<Self, FacetError> {
+        let key = FacetKey::from_str(s, schema, attributes_for_faceting, normalization)?;
+
+        // the value is parsed before being unquoted and normalized by the `FacetKey`,
+        // a quoted value is therefore always compared as a string
+        let value = s.splitn(2, ':').nth(1).unwrap_or_default();
+        match parse_range(value) {
+            Some((from, to)) => Ok(FacetCondition::Range { field_id: key.key(), from, to }),
+            None => Ok(FacetCondition::Equal(key)),
+        }
+    }
+}
+
+fn parse_range(value: &str) -> Option<(f64, f64)> {
+    let parse = |s: &str| s.trim().parse::<f64>().ok().filter(|n| n.is_finite());
+    let mut split = value.splitn(2, " TO ");
+    let from = parse(split.next()?)?;
+    let to = parse(split.next()?)?;
+    Some((from, to))
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 #[repr(C)]
 pub struct FacetKey(FieldId, String);
 
 impl FacetKey {
     pub fn new(field_id: FieldId, value: String) -> Self {
-        let value = match value.cow_to_lowercase() {
-                Cow::Borrowed(_) => value,
-                Cow::Owned(s) => s,
-        };
         Self(field_id, value)
     }
 
+    /// Creates a key whose value is normalized with the options of the index.
+    pub fn normalized(field_id: FieldId, value: &str, normalization: &FacetNormalization) -> Self {
+        Self(field_id, normalization.normalize(value))
+    }
+
     pub fn key(&self) -> FieldId {
         self.0
     }
@@ -101,6 +149,7 @@ impl FacetKey {
         s: &str,
         schema: &Schema,
         attributes_for_faceting: &[FieldId],
+        normalization: &FacetNormalization,
     ) -> Result<Self, FacetError> {
         let mut split = s.splitn(2, ':');
         let key = split
@@ -128,10 +177,10 @@ impl FacetKey {
         let mut indices = value.char_indices();
         let value =  match (indices.next(), indices.last()) {
             (Some((s, '\'')), Some((e, '\''))) |
-            (Some((s, '\"')), Some((e, '\"'))) => value[s + 1..e].to_string(),
-            _ => value.to_string(),
+            (Some((s, '\"')), Some((e, '\"'))) => &value[s + 1..e],
+            _ => value,
         };
-        Ok(Self::new(field_id, value))
+        Ok(Self::normalized(field_id, value, normalization))
     }
 }
 
@@ -161,20 +210,127 @@ impl<'a> heed::BytesDecode<'a> for FacetKey {
     }
 }
 
+/// A numeric facet value, encoded in a way that its bytes are ordered like the numbers,
+/// the numeric facets can therefore be filtered by range.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct NumericFacetKey(FieldId, u64);
+
+/// Returns the bits of a number ordered like the number itself once written in big endian.
+pub(crate) fn ordered_f64_bits(value: f64) -> u64 {
+    // -0.0 and 0.0 must be the same key
+    let bits = if value == 0.0 { 0.0f64.to_bits() } else { value.to_bits() };
+    // the sign bit is flipped for positive numbers and all the bits for negative ones
+    if bits >> 63 == 1 { !bits } else { bits | 1 << 63 }
+}
+
+impl NumericFacetKey {
+    pub fn new(field_id: FieldId, value: f64) -> Self {
+        Self(field_id, ordered_f64_bits(value))
+    }
+
+    pub fn key(&self) -> FieldId {
+        self.0
+    }
+
+    pub fn value(&self) -> f64 {
+        let bits = self.1;
+        let bits = if bits >> 63 == 1 { bits & !(1 << 63) } else { !bits };
+        f64::from_bits(bits)
+    }
+}
+
+impl<'a> heed::BytesEncode<'a> for NumericFacetKey {
+    type EItem = NumericFacetKey;
+
+    fn bytes_encode(item: &'a Self::EItem) -> Option<Cow<'a, [u8]>> {
+        let mut buffer = Vec::with_capacity(2 + 8);
+        buffer.extend_from_slice(&item.key().0.to_be_bytes());
+        buffer.extend_from_slice(&item.1.to_be_bytes());
+        Some(Cow::Owned(buffer))
+    }
+}
+
+impl<'a> heed::BytesDecode<'a> for NumericFacetKey {
+    type DItem = NumericFacetKey;
+
+    fn bytes_decode(bytes: &'a [u8]) -> Option<Self::DItem> {
+        if bytes.len() != 2 + 8 {
+            return None;
+        }
+        let (id_bytes, value_bytes) = bytes.split_at(2);
+        let id = u16::from_be_bytes(id_bytes.try_into().ok()?);
+        let bits = u64::from_be_bytes(value_bytes.try_into().ok()?);
+        Some(NumericFacetKey(FieldId(id), bits))
+    }
+}
+
+/// The statistics of the numeric values of a faceted attribute over the matching documents,
+/// a value is counted once for each document that holds it.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+pub struct FacetStats {
+    pub min: f64,
+    pub max: f64,
+    pub sum: f64,
+    pub avg: f64,
+}
+
+/// Computes the statistics of the facet values that are numbers from their documents counts,
+/// returns `None` when no matching document holds a number for this facet.
+pub fn facet_stats(counts: &HashMap<String, usize>) -> Option<FacetStats> {
+    let mut stats: Option<FacetStats> = None;
+    let mut total = 0;
+
+    for (value, &count) in counts {
+        let number = match value.parse::<f64>() {
+            Ok(number) if count > 0 && number.is_finite() => number,
+            _ => continue,
+        };
+
+        total += count;
+        let stats = stats.get_or_insert(FacetStats { min: number, max: number, sum: 0.0, avg: 0.0 });
+        stats.min = stats.min.min(number);
+        stats.max = stats.max.max(number);
+        stats.sum += number * count as f64;
+    }
+
+    stats.map(|stats| FacetStats { avg: stats.sum / total as f64, ..stats })
+}
+
+/// The documents associated to each facet value, the numbers are also
+/// registered in an ordered way to be filtered by range. The original
+/// values are kept, for display, when they differ from their normalized form.
+#[derive(Debug, Default)]
+pub struct FacetsMap {
+    pub strings: HashMap<FacetKey, Vec<DocumentId>>,
+    pub numbers: HashMap<NumericFacetKey, Vec<DocumentId>>,
+    pub originals: HashMap<FacetKey, String>,
+}
+
 pub fn add_to_facet_map(
-    facet_map: &mut HashMap<FacetKey, Vec<DocumentId>>,
+    facet_map: &mut FacetsMap,
     field_id: FieldId,
     value: Value,
     document_id: DocumentId,
+    normalization: &FacetNormalization,
 ) -> Result<(), FacetError> {
     let value = match value {
         Value::String(s) => s,
+        Value::Number(number) => {
+            if let Some(n) = number.as_f64() {
+                let key = NumericFacetKey::new(field_id, n);
+                facet_map.numbers.entry(key).or_insert_with(Vec::new).push(document_id);
+            }
+            number.to_string()
+        }
         // ignore null
         Value::Null => return Ok(()),
         value => return Err(FacetError::InvalidDocumentAttribute(value.to_string())),
     };
-    let key = FacetKey::new(field_id, value);
-    facet_map.entry(key).or_insert_with(Vec::new).push(document_id);
+    let key = FacetKey::normalized(field_id, &value, normalization);
+    if key.value() != value {
+        facet_map.originals.entry(key.clone()).or_insert(value);
+    }
+    facet_map.strings.entry(key).or_insert_with(Vec::new).push(document_id);
     Ok(())
 }
 
@@ -183,22 +339,42 @@ pub fn facet_map_from_docids(
     index: &crate::Index,
     document_ids: &[DocumentId],
     attributes_for_facetting: &[FieldId],
-) -> Result<HashMap<FacetKey, Vec<DocumentId>>, Error> {
-    let mut facet_map = HashMap::new();
+) -> Result<FacetsMap, Error> {
+    let schema = index.main.schema(rtxn)?.ok_or(Error::SchemaMissing)?;
+    let normalization = index.main.facet_normalization(rtxn)?.unwrap_or_default();
+    let mut facet_map = FacetsMap::default();
     for document_id in document_ids {
         for result in index
             .documents_fields
             .document_fields(rtxn, *document_id)?
         {
             let (field_id, bytes) = result?;
+            let nested_fields: Vec<_> = nested_fields(&schema, field_id)
+                .into_iter()
+                .filter(|(id, _)| attributes_for_facetting.contains(id))
+                .collect();
+
+            if !attributes_for_facetting.contains(&field_id) && nested_fields.is_empty() {
+                continue;
+            }
+
+            let value: Value = serde_json::from_slice(&bytes)?;
+            let mut values: Vec<_> = nested_fields
+                .into_iter()
+                .filter_map(|(id, path)| nested_value(&value, path).map(|value| (id, value)))
+                .collect();
             if attributes_for_facetting.contains(&field_id) {
-                match serde_json::from_slice(bytes)? {
+                values.push((field_id, value));
+            }
+
+            for (field_id, value) in values {
+                match value {
                     Value::Array(values) => {
                         for v in values {
-                            add_to_facet_map(&mut facet_map, field_id, v, *document_id)?;
+                            add_to_facet_map(&mut facet_map, field_id, v, *document_id, &normalization)?;
                         }
                     }
-                    v => add_to_facet_map(&mut facet_map, field_id, v, *document_id)?,
+                    v => add_to_facet_map(&mut facet_map, field_id, v, *document_id, &normalization)?,
                 };
             }
         }
@@ -210,8 +386,9 @@ pub fn facet_map_from_docs(
     schema: &Schema,
     documents: &HashMap<DocumentId, IndexMap<String, Value>>,
     attributes_for_facetting: &[FieldId],
-) -> Result<HashMap<FacetKey, Vec<DocumentId>>, Error> {
-    let mut facet_map = HashMap::new();
+    normalization: &FacetNormalization,
+) -> Result<FacetsMap, Error> {
+    let mut facet_map = FacetsMap::default();
     let attributes_for_facetting = attributes_for_facetting
         .iter()
         .filter_map(|&id| schema.name(id).map(|name| (id, name)))
@@ -219,14 +396,14 @@ pub fn facet_map_from_docs(
 
     for (id, document) in documents {
         for (field_id, name) in &attributes_for_facetting {
-            if let Some(value) = document.get(*name) {
-                match value {
+            if let Some(value) = document_value(document, name) {
+                match value.into_owned() {
                     Value::Array(values) => {
                         for v in values {
-                            add_to_facet_map(&mut facet_map, *field_id, v.clone(), *id)?;
+                            add_to_facet_map(&mut facet_map, *field_id, v, *id, normalization)?;
                         }
                     }
-                    v => add_to_facet_map(&mut facet_map, *field_id, v.clone(), *id)?,
+                    v => add_to_facet_map(&mut facet_map, *field_id, v, *id, normalization)?,
                 }
             }
         }
@@ -234,6 +411,44 @@ pub fn facet_map_from_docs(
     Ok(facet_map)
 }
 
+/// Checks that the faceted attributes of the document are strings, numbers, null
+/// or arrays of those, the values that `facet_map_from_docs` can index.
+pub fn check_document_facets(
+    schema: &Schema,
+    document: &IndexMap<String, Value>,
+    attributes_for_facetting: &[FieldId],
+) -> Result<(), FacetError> {
+    fn check_value(value: &Value) -> Result<(), FacetError> {
+        match value {
+            Value::String(_) | Value::Number(_) | Value::Null => Ok(()),
+            value => Err(FacetError::InvalidDocumentAttribute(value.to_string())),
+        }
+    }
+
+    for name in attributes_for_facetting.iter().filter_map(|&id| schema.name(id)) {
+        if let Some(value) = document_value(document, name) {
+            match value.as_ref() {
+                Value::Array(values) => values.iter().try_for_each(check_value)?,
+                value => check_value(value)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the value of an attribute of the document, the nested
+/// attributes, e.g. `author.name`, are read from their top-level field.
+fn document_value<'a>(document: &'a IndexMap<String, Value>, name: &str) -> Option<Cow<'a, Value>> {
+    if let Some(value) = document.get(name) {
+        return Some(Cow::Borrowed(value));
+    }
+
+    document.iter().find_map(|(parent, value)| {
+        let path = nested_path(name, parent)?;
+        nested_value(value, path).map(Cow::Owned)
+    })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -244,82 +459,86 @@ mod test {
         let mut schema = Schema::new();
         let id = schema.insert_and_index("hello").unwrap();
         let facet_list = [schema.id("hello").unwrap()];
+        let normalization = FacetNormalization::default();
         assert_eq!(
-            FacetKey::from_str("hello:12", &schema, &facet_list).unwrap(),
+            FacetKey::from_str("hello:12", &schema, &facet_list, &normalization).unwrap(),
             FacetKey::new(id, "12".to_string())
         );
         assert_eq!(
-            FacetKey::from_str("hello:\"foo bar\"", &schema, &facet_list).unwrap(),
+            FacetKey::from_str("hello:\"foo bar\"", &schema, &facet_list, &normalization).unwrap(),
             FacetKey::new(id, "foo bar".to_string())
         );
         assert_eq!(
-            FacetKey::from_str("hello:'foo bar'", &schema, &facet_list).unwrap(),
+            FacetKey::from_str("hello:'foo bar'", &schema, &facet_list, &normalization).unwrap(),
             FacetKey::new(id, "foo bar".to_string())
         );
         // weird case
         assert_eq!(
-            FacetKey::from_str("hello:blabla:machin", &schema, &facet_list).unwrap(),
+            FacetKey::from_str("hello:blabla:machin", &schema, &facet_list, &normalization).unwrap(),
             FacetKey::new(id, "blabla:machin".to_string())
         );
 
         assert_eq!(
-            FacetKey::from_str("hello:\"\"", &schema, &facet_list).unwrap(),
+            FacetKey::from_str("hello:\"\"", &schema, &facet_list, &normalization).unwrap(),
             FacetKey::new(id, "".to_string())
         );
 
         assert_eq!(
-            FacetKey::from_str("hello:'", &schema, &facet_list).unwrap(),
+            FacetKey::from_str("hello:'", &schema, &facet_list, &normalization).unwrap(),
             FacetKey::new(id, "'".to_string())
         );
         assert_eq!(
-            FacetKey::from_str("hello:''", &schema, &facet_list).unwrap(),
+            FacetKey::from_str("hello:''", &schema, &facet_list, &normalization).unwrap(),
             FacetKey::new(id, "".to_string())
         );
-        assert!(FacetKey::from_str("hello", &schema, &facet_list).is_err());
-        assert!(FacetKey::from_str("toto:12", &schema, &facet_list).is_err());
+        assert!(FacetKey::from_str("hello", &schema, &facet_list, &normalization).is_err());
+        assert!(FacetKey::from_str("toto:12", &schema, &facet_list, &normalization).is_err());
     }
 
     #[test]
     fn test_parse_facet_array() {
         use either::Either::{Left, Right};
+        use FacetCondition::Equal;
         let mut schema = Schema::new();
         let _id = schema.insert_and_index("hello").unwrap();
         let facet_list = [schema.id("hello").unwrap()];
+        let normalization = FacetNormalization::default();
         assert_eq!(
-            FacetFilter::from_str("[[\"hello:12\"]]", &schema, &facet_list).unwrap(),
-            FacetFilter(vec![Left(vec![FacetKey(FieldId(0), "12".to_string())])])
+            FacetFilter::from_str("[[\"hello:12\"]]", &schema, &facet_list, &normalization).unwrap(),
+            FacetFilter(vec![Left(vec![Equal(FacetKey(FieldId(0), "12".to_string()))])])
         );
         assert_eq!(
-            FacetFilter::from_str("[\"hello:12\"]", &schema, &facet_list).unwrap(),
-            FacetFilter(vec![Right(FacetKey(FieldId(0), "12".to_string()))])
+            FacetFilter::from_str("[\"hello:12\"]", &schema, &facet_list, &normalization).unwrap(),
+            FacetFilter(vec![Right(Equal(FacetKey(FieldId(0), "12".to_string())))])
         );
         assert_eq!(
-            FacetFilter::from_str("[\"hello:12\", \"hello:13\"]", &schema, &facet_list).unwrap(),
+            FacetFilter::from_str("[\"hello:12\", \"hello:13\"]", &schema, &facet_list, &normalization).unwrap(),
             FacetFilter(vec![
-                Right(FacetKey(FieldId(0), "12".to_string())),
-                Right(FacetKey(FieldId(0), "13".to_string()))
+                Right(Equal(FacetKey(FieldId(0), "12".to_string()))),
+                Right(Equal(FacetKey(FieldId(0), "13".to_string())))
             ])
         );
         assert_eq!(
-            FacetFilter::from_str("[[\"hello:12\", \"hello:13\"]]", &schema, &facet_list).unwrap(),
+            FacetFilter::from_str("[[\"hello:12\", \"hello:13\"]]", &schema, &facet_list, &normalization).unwrap(),
             FacetFilter(vec![Left(vec![
-                FacetKey(FieldId(0), "12".to_string()),
-                FacetKey(FieldId(0), "13".to_string())
+                Equal(FacetKey(FieldId(0), "12".to_string())),
+                Equal(FacetKey(FieldId(0), "13".to_string()))
             ])])
         );
         assert_eq!(
             FacetFilter::from_str(
                 "[[\"hello:12\", \"hello:13\"], \"hello:14\"]",
                 &schema,
-                &facet_list
+                &facet_list,
+                &normalization,
             )
             .unwrap(),
             FacetFilter(vec![
                 Left(vec![
-                    FacetKey(FieldId(0), "12".to_string()),
-                    FacetKey(FieldId(0), "13".to_string())
+                    Equal(FacetKey(FieldId(0), "12".to_string())),
+                    Equal(FacetKey(FieldId(0), "13".to_string()))
                 ]),
-                Right(FacetKey(FieldId(0), "14".to_string()))
+                Right(Equal(FacetKey(FieldId(0), "14".to_string())))
             ])
         );
 
@@ -327,27 +546,117 @@ mod test {
         assert!(FacetFilter::from_str(
             "[[[\"hello:12\", \"hello:13\"], \"hello:14\"]]",
             &schema,
-            &facet_list
+            &facet_list,
+            &normalization,
         )
         .is_err());
         assert!(FacetFilter::from_str(
             "[[[\"hello:12\", \"hello:13\"]], \"hello:14\"]]",
             &schema,
-            &facet_list
+            &facet_list,
+            &normalization,
         )
         .is_err());
-        assert!(FacetFilter::from_str("\"hello:14\"", &schema, &facet_list).is_err());
+        assert!(FacetFilter::from_str("\"hello:14\"", &schema, &facet_list, &normalization).is_err());
 
         // unexisting key
-        assert!(FacetFilter::from_str("[\"foo:12\"]", &schema, &facet_list).is_err());
+        assert!(FacetFilter::from_str("[\"foo:12\"]", &schema, &facet_list, &normalization).is_err());
 
         // invalid facet key
-        assert!(FacetFilter::from_str("[\"foo=12\"]", &schema, &facet_list).is_err());
-        assert!(FacetFilter::from_str("[\"foo12\"]", &schema, &facet_list).is_err());
-        assert!(FacetFilter::from_str("[\"\"]", &schema, &facet_list).is_err());
+        assert!(FacetFilter::from_str("[\"foo=12\"]", &schema, &facet_list, &normalization).is_err());
+        assert!(FacetFilter::from_str("[\"foo12\"]", &schema, &facet_list, &normalization).is_err());
+        assert!(FacetFilter::from_str("[\"\"]", &schema, &facet_list, &normalization).is_err());
 
         // empty array error
-        assert!(FacetFilter::from_str("[]", &schema, &facet_list).is_err());
-        assert!(FacetFilter::from_str("[\"hello:12\", []]", &schema, &facet_list).is_err());
+        assert!(FacetFilter::from_str("[]", &schema, &facet_list, &normalization).is_err());
+        assert!(FacetFilter::from_str("[\"hello:12\", []]", &schema, &facet_list, &normalization).is_err());
+    }
+
+    #[test]
+    fn test_parse_facet_range() {
+        let mut schema = Schema::new();
+        let id = schema.insert_and_index("price").unwrap();
+        let facet_list = [id];
+        let normalization = FacetNormalization::default();
+
+        assert_eq!(
+            FacetCondition::from_str("price:10 TO 50.5", &schema, &facet_list, &normalization).unwrap(),
+            FacetCondition::Range { field_id: id, from: 10.0, to: 50.5 }
+        );
+        assert_eq!(
+            FacetCondition::from_str("price: -5 TO 0", &schema, &facet_list, &normalization).unwrap(),
+            FacetCondition::Range { field_id: id, from: -5.0, to: 0.0 }
+        );
+        // quoted or non numeric values are compared as strings
+        assert_eq!(
+            FacetCondition::from_str("price:\"10 TO 50\"", &schema, &facet_list, &normalization).unwrap(),
+            FacetCondition::Equal(FacetKey::new(id, "10 TO 50".to_string()))
+        );
+        assert_eq!(
+            FacetCondition::from_str("price:10 TO cheap", &schema, &facet_list, &normalization).unwrap(),
+            FacetCondition::Equal(FacetKey::new(id, "10 TO cheap".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_normalized_facet_key() {
+        let mut schema = Schema::new();
+        let id = schema.insert_and_index("genre").unwrap();
+        let facet_list = [id];
+
+        let normalization = FacetNormalization { lowercase: true, trim: true, strip_diacritics: true };
+        assert_eq!(
+            FacetKey::from_str("genre:\" Comédie \"", &schema, &facet_list, &normalization).unwrap(),
+            FacetKey::new(id, "comedie".to_string())
+        );
+
+        let normalization = FacetNormalization { lowercase: false, trim: false, strip_diacritics: false };
+        assert_eq!(
+            FacetKey::from_str("genre:Comédie", &schema, &facet_list, &normalization).unwrap(),
+            FacetKey::new(id, "Comédie".to_string())
+        );
+
+        let mut facet_map = FacetsMap::default();
+        let normalization = FacetNormalization { trim: true, ..FacetNormalization::default() };
+        add_to_facet_map(&mut facet_map, id, Value::from("Horror "), DocumentId(1), &normalization).unwrap();
+        add_to_facet_map(&mut facet_map, id, Value::from("drama"), DocumentId(2), &normalization).unwrap();
+        let key = FacetKey::new(id, "horror".to_string());
+        assert_eq!(facet_map.strings[&key], vec![DocumentId(1)]);
+        assert_eq!(facet_map.originals[&key], "Horror ");
+        assert!(!facet_map.originals.contains_key(&FacetKey::new(id, "drama".to_string())));
+    }
+
+    #[test]
+    fn numeric_facet_stats() {
+        let counts: HashMap<_, _> = vec![
+            ("10".to_string(), 2),
+            ("25.5".to_string(), 1),
+            ("40".to_string(), 0),
+            ("blue".to_string(), 3),
+        ].into_iter().collect();
+
+        let stats = facet_stats(&counts).unwrap();
+        assert_eq!(stats, FacetStats { min: 10.0, max: 25.5, sum: 45.5, avg: 45.5 / 3.0 });
+
+        let counts: HashMap<_, _> = vec![("blue".to_string(), 3)].into_iter().collect();
+        assert_eq!(facet_stats(&counts), None);
+    }
+
+    #[test]
+    fn numeric_facet_key_order() {
+        let field_id = FieldId(3);
+        let values = [-1e300, -12.5, -1.0, -0.0, 0.0, 0.5, 1.0, 42.0, 1e300];
+        let keys: Vec<_> = values.iter().map(|&v| NumericFacetKey::new(field_id, v)).collect();
+
+        for (key, value) in keys.iter().zip(&values) {
+            assert_eq!(key.value(), *value);
+            let bytes = <NumericFacetKey as heed::BytesEncode>::bytes_encode(key).unwrap();
+            assert_eq!(<NumericFacetKey as heed::BytesDecode>::bytes_decode(&bytes), Some(*key));
+        }
+        for pair in keys.windows(2) {
+            let left = <NumericFacetKey as heed::BytesEncode>::bytes_encode(&pair[0]).unwrap();
+            let right = <NumericFacetKey as heed::BytesEncode>::bytes_encode(&pair[1]).unwrap();
+            assert!(left <= right);
+        }
     }
 }
@@ -1,12 +1,17 @@
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::cmp::Ordering;
 
 use crate::error::Error;
+use crate::facets::FacetKey;
+use crate::update::{date_to_timestamp, nested_path, nested_value};
 use crate::{store::Index, DocumentId, MainT};
 use heed::RoTxn;
-use meilisearch_schema::{FieldId, Schema};
+use meilisearch_schema::{FieldId, FieldType, Schema};
+use once_cell::sync::OnceCell;
 use pest::error::{Error as PestError, ErrorVariant};
 use pest::iterators::Pair;
+use sdset::{SetBuf, SetOperation};
 use serde_json::{Value, Number};
 use super::parser::Rule;
 
@@ -47,6 +52,14 @@ impl<'a> ConditionValue<'a> {
         value
     }
 
+    /// The dates compared to a field declared as a date are compared as timestamps.
+    fn with_field_type(mut self, field_type: Option<FieldType>) -> Self {
+        if field_type == Some(FieldType::Date) && self.number.is_none() {
+            self.number = date_to_timestamp(self.string).map(Number::from);
+        }
+        self
+    }
+
     pub fn as_str(&self) -> &str {
         self.string.as_ref()
     }
@@ -60,17 +73,30 @@ impl<'a> ConditionValue<'a> {
     }
 }
 
+/// The outcome of a condition read once from the stores of the index,
+/// to avoid deserializing the documents.
+#[derive(Debug)]
+enum IndexedOutcome {
+    /// These are all the documents matching the condition, read from
+    /// the numbers store or from the facets when the attribute is faceted.
+    Candidates(SetBuf<DocumentId>),
+    /// The attribute is ranked, the outcome for the documents with a numeric value.
+    Ranked(HashMap<DocumentId, bool>),
+    Unindexed,
+}
+
 #[derive(Debug)]
 pub struct Condition<'a> {
     field: FieldId,
     condition: ConditionType,
-    value: ConditionValue<'a>
+    value: ConditionValue<'a>,
+    ranked: bool,
+    /// The top-level field and the path of the value when the field is nested, e.g. `author.name`.
+    parent: Option<(FieldId, String)>,
+    indexed: OnceCell<IndexedOutcome>,
 }
 
-fn get_field_value<'a>(schema: &Schema, pair: Pair<'a, Rule>) -> Result<(FieldId, ConditionValue<'a>), Error> {
-    let mut items = pair.into_inner();
-    // lexing ensures that we at least have a key
-    let key = items.next().unwrap();
+pub(super) fn get_field(schema: &Schema, key: &Pair<Rule>) -> Result<FieldId, Error> {
     let field = schema
         .id(key.as_str())
         .ok_or::<PestError<Rule>>(PestError::new_from_span(
@@ -82,7 +108,26 @@ fn get_field_value<'a>(schema: &Schema, pair: Pair<'a, Rule>) -> Result<(FieldId
                              ),
                 },
                 key.as_span()))?;
-    let value = ConditionValue::new(&items.next().unwrap());
+    Ok(field)
+}
+
+fn get_field_value<'a>(schema: &Schema, pair: Pair<'a, Rule>) -> Result<(FieldId, ConditionValue<'a>), Error> {
+    let mut items = pair.into_inner();
+    // lexing ensures that we at least have a key
+    let key = items.next().unwrap();
+    let field = get_field(schema, &key)?;
+    let value = ConditionValue::new(&items.next().unwrap()).with_field_type(schema.field_type(field));
+    Ok((field, value))
+}
+
+/// Like `get_field_value` but only accepts numbers, the values that can be ordered.
+fn get_field_number<'a>(schema: &Schema, pair: Pair<'a, Rule>, operator: &str) -> Result<(FieldId, ConditionValue<'a>), Error> {
+    let span = pair.as_span();
+    let (field, value) = get_field_value(schema, pair)?;
+    if value.as_number().is_none() {
+        let message = format!("`{}` can only be used with a number, found `{}`", operator, value.as_str());
+        return Err(PestError::new_from_span(ErrorVariant::CustomError { message }, span).into());
+    }
     Ok((field, value))
 }
 
@@ -99,22 +144,31 @@ fn compare_numbers(lhs: &Number, rhs: &Number) -> Option<Ordering> {
 }
 
 impl<'a> Condition<'a> {
+    fn new(schema: &Schema, field: FieldId, condition: ConditionType, value: ConditionValue<'a>) -> Self {
+        let ranked = schema.is_ranked(field);
+        let parent = schema.name(field).and_then(|name| {
+            schema.names().find_map(|parent| {
+                let path = nested_path(name, parent)?;
+                Some((schema.id(parent)?, path.to_string()))
+            })
+        });
+        Condition { field, condition, value, ranked, parent, indexed: OnceCell::new() }
+    }
+
     pub fn less(
         item: Pair<'a, Rule>,
         schema: &'a Schema,
     ) -> Result<Self, Error> {
-        let (field, value) = get_field_value(schema, item)?;
-        let condition = ConditionType::Less;
-        Ok(Self { field, condition, value })
+        let (field, value) = get_field_number(schema, item, "<")?;
+        Ok(Self::new(schema, field, ConditionType::Less, value))
     }
 
     pub fn greater(
         item: Pair<'a, Rule>,
         schema: &'a Schema,
     ) -> Result<Self, Error> {
-        let (field, value) = get_field_value(schema, item)?;
-        let condition = ConditionType::Greater;
-        Ok(Self { field, condition, value })
+        let (field, value) = get_field_number(schema, item, ">")?;
+        Ok(Self::new(schema, field, ConditionType::Greater, value))
     }
 
     pub fn neq(
@@ -122,26 +176,23 @@ impl<'a> Condition<'a> {
         schema: &'a Schema,
     ) -> Result<Self, Error> {
         let (field, value) = get_field_value(schema, item)?;
-        let condition = ConditionType::NotEqual;
-        Ok(Self { field, condition, value })
+        Ok(Self::new(schema, field, ConditionType::NotEqual, value))
     }
 
     pub fn geq(
         item: Pair<'a, Rule>,
         schema: &'a Schema,
     ) -> Result<Self, Error> {
-        let (field, value) = get_field_value(schema, item)?;
-        let condition = ConditionType::GreaterEqual;
-        Ok(Self { field, condition, value })
+        let (field, value) = get_field_number(schema, item, ">=")?;
+        Ok(Self::new(schema, field, ConditionType::GreaterEqual, value))
     }
 
     pub fn leq(
         item: Pair<'a, Rule>,
         schema: &'a Schema,
     ) -> Result<Self, Error> {
-        let (field, value) = get_field_value(schema, item)?;
-        let condition = ConditionType::LessEqual;
-        Ok(Self { field, condition, value })
+        let (field, value) = get_field_number(schema, item, "<=")?;
+        Ok(Self::new(schema, field, ConditionType::LessEqual, value))
     }
 
     pub fn eq(
@@ -149,8 +200,23 @@ impl<'a> Condition<'a> {
         schema: &'a Schema,
     ) -> Result<Self, Error> {
         let (field, value) = get_field_value(schema, item)?;
-        let condition = ConditionType::Equal;
-        Ok(Self { field, condition, value })
+        Ok(Self::new(schema, field, ConditionType::Equal, value))
+    }
+
+    /// Returns an equality condition for each of the listed values.
+    pub fn is_in(
+        item: Pair<'a, Rule>,
+        schema: &'a Schema,
+    ) -> Result<Vec<Self>, Error> {
+        let mut items = item.into_inner();
+        // lexing ensures that we have a key and at least one value
+        let key = items.next().unwrap();
+        let field = get_field(schema, &key)?;
+        let conditions = items
+            .map(|value| ConditionValue::new(&value).with_field_type(schema.field_type(field)))
+            .map(|value| Self::new(schema, field, ConditionType::Equal, value))
+            .collect();
+        Ok(conditions)
     }
 
     pub fn test(
@@ -159,12 +225,112 @@ impl<'a> Condition<'a> {
         index: &Index,
         document_id: DocumentId,
     ) -> Result<bool, Error> {
-        match index.document_attribute::<Value>(reader, document_id, self.field)? {
+        let indexed = self.indexed.get_or_try_init(|| self.indexed_outcome(reader, index))?;
+        match indexed {
+            IndexedOutcome::Candidates(document_ids) => return Ok(document_ids.contains(&document_id)),
+            IndexedOutcome::Ranked(outcomes) => {
+                if let Some(outcome) = outcomes.get(&document_id) {
+                    return Ok(*outcome);
+                }
+            },
+            IndexedOutcome::Unindexed => (),
+        }
+
+        // the value is not indexed in a usable way, it is read from the document
+        let value = match (index.document_attribute::<Value>(reader, document_id, self.field)?, &self.parent) {
+            (None, Some((parent, path))) => index
+                .document_attribute::<Value>(reader, document_id, *parent)?
+                .and_then(|value| nested_value(&value, path)),
+            (value, _) => value,
+        };
+
+        match value {
             Some(Value::Array(values)) => Ok(values.iter().any(|v| self.match_value(Some(v)))),
             other => Ok(self.match_value(other.as_ref())),
         }
     }
 
+    /// Returns the documents matching the condition when they can be read from the stores.
+    pub(super) fn indexed_candidates(&self, reader: &RoTxn<MainT>, index: &Index) -> Result<Option<SetBuf<DocumentId>>, Error> {
+        let indexed = self.indexed.get_or_try_init(|| self.indexed_outcome(reader, index))?;
+        match indexed {
+            IndexedOutcome::Candidates(document_ids) => Ok(Some(document_ids.clone())),
+            IndexedOutcome::Ranked(_) | IndexedOutcome::Unindexed => Ok(None),
+        }
+    }
+
+    fn indexed_outcome(&self, reader: &RoTxn<MainT>, index: &Index) -> Result<IndexedOutcome, Error> {
+        // the ordering conditions always compare numbers, all of them are in the numbers store
+        let number = self.value.as_number().and_then(Number::as_f64);
+        if let Some(number) = number {
+            let range = |from, to| index.numbers.range_document_ids(reader, self.field, from, to);
+            let strict = |range: SetBuf<DocumentId>| -> Result<SetBuf<DocumentId>, Error> {
+                let equal = index.numbers.range_document_ids(reader, self.field, number, number)?;
+                Ok(sdset::duo::Difference::new(&range, &equal).into_set_buf())
+            };
+
+            let document_ids = match self.condition {
+                ConditionType::GreaterEqual => Some(range(number, f64::INFINITY)?),
+                ConditionType::LessEqual => Some(range(f64::NEG_INFINITY, number)?),
+                ConditionType::Greater => Some(strict(range(number, f64::INFINITY)?)?),
+                ConditionType::Less => Some(strict(range(f64::NEG_INFINITY, number)?)?),
+                ConditionType::Equal | ConditionType::NotEqual => None,
+            };
+
+            if let Some(document_ids) = document_ids {
+                return Ok(IndexedOutcome::Candidates(document_ids));
+            }
+        }
+
+        let is_faceted = index
+            .main
+            .attributes_for_faceting(reader)?
+            .map_or(false, |attributes| attributes.contains(&self.field));
+
+        // a document with several values doesn't match != as soon as one of them is equal
+        if is_faceted && self.condition == ConditionType::Equal {
+            let normalization = index.main.facet_normalization(reader)?.unwrap_or_default();
+            let key = FacetKey::normalized(self.field, self.value.as_str(), &normalization);
+            let strings = index.facets.facet_document_ids(reader, &key)?.unwrap_or_default();
+            let document_ids = match number {
+                Some(number) => {
+                    let numbers = index.facets.numeric_range_document_ids(reader, self.field, number, number)?;
+                    sdset::duo::Union::new(&strings, &numbers).into_set_buf()
+                },
+                None => strings.into_owned(),
+            };
+
+            return Ok(IndexedOutcome::Candidates(document_ids));
+        }
+
+        // the ranked map only contains the values of the documents that are not arrays
+        if self.ranked {
+            if let Ok(number) = self.value.as_str().parse::<crate::Number>() {
+                let ranked_map = index.main.ranked_map(reader)?.unwrap_or_default();
+                let outcomes = ranked_map
+                    .field_numbers(self.field)
+                    .filter(|(_, value)| *value != crate::Number::Null)
+                    .map(|(document_id, value)| (document_id, self.match_ordering(value.cmp(&number))))
+                    .collect();
+
+                return Ok(IndexedOutcome::Ranked(outcomes));
+            }
+        }
+
+        Ok(IndexedOutcome::Unindexed)
+    }
+
+    fn match_ordering(&self, ord: Ordering) -> bool {
+        match self.condition {
+            ConditionType::Equal => ord == Ordering::Equal,
+            ConditionType::NotEqual => ord != Ordering::Equal,
+            ConditionType::GreaterEqual => ord != Ordering::Less,
+            ConditionType::LessEqual => ord != Ordering::Greater,
+            ConditionType::Greater => ord == Ordering::Greater,
+            ConditionType::Less => ord == Ordering::Less,
+        }
+    }
+
     fn match_value(&self, value: Option<&Value>) -> bool {
         match value {
             Some(Value::String(s)) => {
@@ -178,15 +344,7 @@ impl<'a> Condition<'a> {
             Some(Value::Number(n)) => { 
                 if let Some(value) = self.value.as_number() {
                     if let Some(ord) = compare_numbers(&n, value) {
-                        let res =  match self.condition {
-                            ConditionType::Equal => ord == Ordering::Equal,
-                            ConditionType::NotEqual => ord != Ordering::Equal,
-                            ConditionType::GreaterEqual => ord != Ordering::Less,
-                            ConditionType::LessEqual => ord != Ordering::Greater,
-                            ConditionType::Greater => ord == Ordering::Greater,
-                            ConditionType::Less => ord == Ordering::Less,
-                        };
-                        return res
+                        return self.match_ordering(ord)
                     } 
                 } 
                 false
@@ -5,20 +5,33 @@ pub(crate) use parser::Rule;
 
 use std::ops::Not;
 
-use condition::Condition;
+use condition::{get_field, Condition};
 use crate::error::Error;
+use crate::geo::GeoPoint;
 use crate::{DocumentId, MainT, store::Index};
 use heed::RoTxn;
-use meilisearch_schema::Schema;
+use meilisearch_schema::{FieldId, Schema};
+use once_cell::sync::OnceCell;
 use parser::{PREC_CLIMBER, FilterParser};
+use pest::error::{Error as PestError, ErrorVariant};
 use pest::iterators::{Pair, Pairs};
 use pest::Parser;
+use sdset::{Set, SetBuf, SetOperation};
 
 type FilterResult<'a> = Result<Filter<'a>, Error>;
 
 #[derive(Debug)]
 pub enum Filter<'a> {
     Condition(Condition<'a>),
+    /// The document has a non null value for this field.
+    Has(FieldId),
+    /// The document contains this field, whatever its value, the documents
+    /// containing it are read once from the presences store.
+    Exists(FieldId, OnceCell<SetBuf<DocumentId>>),
+    /// The document contains this field with a null value.
+    IsNull(FieldId),
+    /// The document coordinates are at most this many meters away from the point.
+    GeoRadius(GeoPoint, f64),
     Or(Box<Self>, Box<Self>),
     And(Box<Self>, Box<Self>),
     Not(Box<Self>),
@@ -39,6 +52,22 @@ impl<'a> Filter<'a> {
         use Filter::*;
         match self {
             Condition(c) => c.test(reader, index, document_id),
+            Has(field) => {
+                let bytes = index.document_attribute_bytes(reader, document_id, *field)?;
+                Ok(bytes.map_or(false, |bytes| &*bytes != b"null"))
+            },
+            Exists(field, document_ids) => {
+                let document_ids = document_ids.get_or_try_init(|| index.presences.field_document_ids(reader, *field))?;
+                Ok(document_ids.binary_search(&document_id).is_ok())
+            },
+            IsNull(field) => {
+                let bytes = index.document_attribute_bytes(reader, document_id, *field)?;
+                Ok(bytes.map_or(false, |bytes| &*bytes == b"null"))
+            },
+            GeoRadius(center, radius) => {
+                let point = index.geo.document_point(reader, document_id)?;
+                Ok(point.map_or(false, |point| center.distance(&point) <= *radius))
+            },
             Or(lhs, rhs) => Ok(
                 lhs.test(reader, index, document_id)? || rhs.test(reader, index, document_id)?
             ),
@@ -49,10 +78,90 @@ impl<'a> Filter<'a> {
         }
     }
 
+    /// Returns the documents matching the filter, the deleted documents that are not purged yet
+    /// included. The conditions are read from the numbers, facets and presences stores, only the
+    /// conditions that are not indexed test the documents one by one, among the documents matching
+    /// the other side of an `AND` when it is indexed.
+    pub fn candidates(&self, reader: &RoTxn<MainT>, index: &Index) -> Result<SetBuf<DocumentId>, Error> {
+        match self.indexed_candidates(reader, index)? {
+            Some(candidates) => Ok(candidates),
+            None => {
+                let all = all_documents_ids(reader, index)?;
+                self.test_among(reader, index, &all)
+            }
+        }
+    }
+
+    /// Returns `None` when the filter can't be answered from the stores of the index.
+    fn indexed_candidates(&self, reader: &RoTxn<MainT>, index: &Index) -> Result<Option<SetBuf<DocumentId>>, Error> {
+        use Filter::*;
+        match self {
+            Condition(c) => c.indexed_candidates(reader, index),
+            Exists(field, document_ids) => {
+                let document_ids = document_ids.get_or_try_init(|| index.presences.field_document_ids(reader, *field))?;
+                Ok(Some(document_ids.clone()))
+            },
+            Has(_) | IsNull(_) | GeoRadius(..) => Ok(None),
+            And(lhs, rhs) => {
+                match (lhs.indexed_candidates(reader, index)?, rhs.indexed_candidates(reader, index)?) {
+                    (Some(lhs), Some(rhs)) => Ok(Some(sdset::duo::Intersection::new(&lhs, &rhs).into_set_buf())),
+                    (Some(lhs), None) => rhs.test_among(reader, index, &lhs).map(Some),
+                    (None, Some(rhs)) => lhs.test_among(reader, index, &rhs).map(Some),
+                    (None, None) => Ok(None),
+                }
+            },
+            Or(lhs, rhs) => {
+                match (lhs.indexed_candidates(reader, index)?, rhs.indexed_candidates(reader, index)?) {
+                    (Some(lhs), Some(rhs)) => Ok(Some(sdset::duo::Union::new(&lhs, &rhs).into_set_buf())),
+                    _ => Ok(None),
+                }
+            },
+            Not(op) => match op.indexed_candidates(reader, index)? {
+                Some(matching) => {
+                    let all = all_documents_ids(reader, index)?;
+                    Ok(Some(sdset::duo::Difference::new(&all, &matching).into_set_buf()))
+                },
+                None => Ok(None),
+            },
+        }
+    }
+
+    fn test_among(&self, reader: &RoTxn<MainT>, index: &Index, documents_ids: &Set<DocumentId>) -> Result<SetBuf<DocumentId>, Error> {
+        let mut matching = Vec::new();
+        for &document_id in documents_ids.as_slice() {
+            if self.test(reader, index, document_id)? {
+                matching.push(document_id);
+            }
+        }
+        Ok(SetBuf::new_unchecked(matching))
+    }
+
     fn build(expression: Pairs<'a, Rule>, schema: &'a Schema) -> FilterResult<'a> {
         PREC_CLIMBER.climb(
             expression,
             |pair: Pair<Rule>| match pair.as_rule() {
+                Rule::has => {
+                    // lexing ensures that we have a key
+                    let key = pair.into_inner().next().unwrap();
+                    Ok(Filter::Has(get_field(schema, &key)?))
+                },
+                Rule::exists => {
+                    // lexing ensures that we have a key
+                    let key = pair.into_inner().next().unwrap();
+                    Ok(Filter::Exists(get_field(schema, &key)?, OnceCell::new()))
+                },
+                Rule::is_null => {
+                    // lexing ensures that we have a key
+                    let key = pair.into_inner().next().unwrap();
+                    Ok(Filter::IsNull(get_field(schema, &key)?))
+                },
+                Rule::is_in => {
+                    let mut conditions = Condition::is_in(pair, schema)?.into_iter().map(Filter::Condition);
+                    // lexing ensures that we have at least one value
+                    let first = conditions.next().unwrap();
+                    Ok(conditions.fold(first, |lhs, rhs| Filter::Or(Box::new(lhs), Box::new(rhs))))
+                },
+                Rule::geo_radius => geo_radius(pair),
                 Rule::eq => Ok(Filter::Condition(Condition::eq(pair, schema)?)),
                 Rule::greater => Ok(Filter::Condition(Condition::greater(pair, schema)?)),
                 Rule::less => Ok(Filter::Condition(Condition::less(pair, schema)?)),
@@ -76,6 +185,31 @@ impl<'a> Filter<'a> {
     }
 }
 
+/// The ids of the documents stored by the index, in ascending order.
+fn all_documents_ids(reader: &RoTxn<MainT>, index: &Index) -> Result<SetBuf<DocumentId>, Error> {
+    let mut documents_ids = Vec::new();
+    for result in index.documents_fields_counts.documents_ids(reader)? {
+        documents_ids.push(result?);
+    }
+    Ok(SetBuf::new_unchecked(documents_ids))
+}
+
+fn geo_radius<'a>(pair: Pair<Rule>) -> FilterResult<'a> {
+    let span = pair.as_span();
+    // lexing ensures that we have three numbers
+    let mut numbers = pair.into_inner().map(|number| number.as_str().parse::<f64>().unwrap());
+    let (lat, lng, radius) = (numbers.next().unwrap(), numbers.next().unwrap(), numbers.next().unwrap());
+
+    let center = GeoPoint::new(lat, lng)
+        .map_err(|message| PestError::new_from_span(ErrorVariant::CustomError { message }, span.clone()))?;
+    if radius < 0.0 {
+        let message = "the radius must be a positive number of meters".to_string();
+        return Err(PestError::new_from_span(ErrorVariant::CustomError { message }, span).into());
+    }
+
+    Ok(Filter::GeoRadius(center, radius))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -97,6 +231,15 @@ mod test {
         assert!(FilterParser::parse(Rule::prgm, "hello world=1").is_err());
         assert!(FilterParser::parse(Rule::prgm, "").is_err());
         assert!(FilterParser::parse(Rule::prgm, r#"((((((hello=world)))))"#).is_err());
+        assert!(FilterParser::parse(Rule::prgm, "HAS").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "HASfield").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "HAS field=1").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "field IN []").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "field IN [1, 2").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "field EXISTS = 1").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "field IS").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "_geoRadius(48.85, 2.35)").is_err());
+        assert!(FilterParser::parse(Rule::prgm, "_geoRadius(48.85, east, 2000)").is_err());
     }
 
     #[test]
@@ -123,5 +266,15 @@ mod test {
         assert!(FilterParser::parse(Rule::prgm, r#"'foo bar' <= 10"#).is_ok());
         assert!(FilterParser::parse(Rule::prgm, r#"'foo bar' != 10"#).is_ok());
         assert!(FilterParser::parse(Rule::prgm, r#"bar != 10"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"HAS field"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"HAS 'foo bar' AND NOT HAS field"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"HAS = 10"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"id IN [1, 2, 3]"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"color IN ['dark blue', red] AND NOT id IN [2]"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"tags EXISTS"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"'foo bar' EXISTS AND NOT release_date IS NULL"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"_geoRadius(48.85, 2.35, 2000)"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"_geoRadius(-33.8688,151.2093,1500.5) AND NOT field=5"#).is_ok());
+        assert!(FilterParser::parse(Rule::prgm, r#"_geoRadius = 10"#).is_ok());
     }
 }
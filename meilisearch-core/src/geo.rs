@@ -0,0 +1,87 @@
+use serde_json::Value;
+use zerocopy::{AsBytes, FromBytes};
+
+use crate::error::Error;
+
+/// The reserved attribute holding the coordinates of a document, an object with
+/// the `lat` and `lng` fields expressed in degrees.
+pub const GEO_FIELD: &str = "_geo";
+
+/// The mean radius of the earth, in meters.
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+#[derive(Debug, Copy, Clone, PartialEq, AsBytes, FromBytes)]
+#[repr(C)]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lng: f64,
+}
+
+impl GeoPoint {
+    pub fn new(lat: f64, lng: f64) -> Result<GeoPoint, String> {
+        if !(-90.0..=90.0).contains(&lat) {
+            return Err(format!("latitude {} is not between -90 and 90", lat));
+        }
+        if !(-180.0..=180.0).contains(&lng) {
+            return Err(format!("longitude {} is not between -180 and 180", lng));
+        }
+        Ok(GeoPoint { lat, lng })
+    }
+
+    /// Extracts the point from the value of the `_geo` attribute, the coordinates
+    /// can be numbers or strings containing numbers.
+    pub fn from_value(value: &Value) -> Result<GeoPoint, Error> {
+        fn coordinate(object: &serde_json::Map<String, Value>, name: &str) -> Result<f64, Error> {
+            let coordinate = match object.get(name) {
+                Some(Value::Number(number)) => number.as_f64(),
+                Some(Value::String(string)) => string.trim().parse().ok(),
+                _ => None,
+            };
+            coordinate.ok_or_else(|| Error::InvalidGeoField(format!("`{}` must be a number", name)))
+        }
+
+        let object = value
+            .as_object()
+            .ok_or_else(|| Error::InvalidGeoField("must be an object with `lat` and `lng` fields".to_string()))?;
+        let lat = coordinate(object, "lat")?;
+        let lng = coordinate(object, "lng")?;
+        GeoPoint::new(lat, lng).map_err(Error::InvalidGeoField)
+    }
+
+    /// The great-circle distance between two points, in meters, computed with
+    /// the haversine formula.
+    pub fn distance(&self, other: &GeoPoint) -> f64 {
+        let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+        let delta_lat = lat2 - lat1;
+        let delta_lng = (other.lng - self.lng).to_radians();
+
+        let a = (delta_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (delta_lng / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS * a.sqrt().min(1.0).asin()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn point_from_value() {
+        let point = GeoPoint::from_value(&json!({ "lat": 48.8566, "lng": "2.3522" })).unwrap();
+        assert_eq!(point, GeoPoint { lat: 48.8566, lng: 2.3522 });
+
+        assert_matches!(GeoPoint::from_value(&json!([48.8566, 2.3522])), Err(Error::InvalidGeoField(_)));
+        assert_matches!(GeoPoint::from_value(&json!({ "lat": 48.8566 })), Err(Error::InvalidGeoField(_)));
+        assert_matches!(GeoPoint::from_value(&json!({ "lat": 98.0, "lng": 2.0 })), Err(Error::InvalidGeoField(_)));
+    }
+
+    #[test]
+    fn haversine_distance() {
+        let paris = GeoPoint { lat: 48.8566, lng: 2.3522 };
+        let london = GeoPoint { lat: 51.5074, lng: -0.1278 };
+
+        let distance = paris.distance(&london);
+        assert!((distance - 343_500.0).abs() < 1_000.0, "{}", distance);
+        assert_eq!(paris.distance(&paris), 0.0);
+    }
+}
@@ -9,8 +9,12 @@ mod bucket_sort;
 mod database;
 mod distinct_map;
 mod error;
+mod expiration;
 mod filters;
+mod geo;
 mod levenshtein;
+mod map_growth;
+mod normalization;
 mod number;
 mod query_builder;
 mod query_tree;
@@ -18,22 +22,32 @@ mod query_words_mapper;
 mod ranked_map;
 mod raw_document;
 mod reordered_attrs;
+mod suggest;
 pub mod criterion;
 pub mod facets;
+pub mod query_rules;
 pub mod raw_indexer;
 pub mod serde;
 pub mod settings;
 pub mod store;
 pub mod update;
 
-pub use self::database::{BoxUpdateFn, Database, DatabaseOptions, MainT, UpdateT};
-pub use self::error::{Error, HeedError, FstError, MResult, pest_error, FacetError};
+pub use self::bucket_sort::{CriterionProfile, SearchProfile};
+pub use self::criterion::CriterionDetails;
+pub use self::database::{BoxUpdateFn, BoxUpdateListenerFn, BoxUpdateLogFn, Database, DatabaseOptions, EnvironmentUsage, MainT, UpdateQueueLimits, UpdateT, UpdatesRetention};
+pub use self::database::UpdateNotification;
+pub use self::database::{MultiSearchHit, MultiSearchResult};
+pub use self::error::{Error, HeedError, FstError, MResult, pest_error, FacetError, QueueLimit};
+pub use self::expiration::EXPIRES_AT_FIELD;
 pub use self::filters::Filter;
+pub use self::geo::{GeoPoint, GEO_FIELD};
 pub use self::number::{Number, ParseNumberError};
+pub use self::query_builder::MatchingStrategy;
 pub use self::ranked_map::RankedMap;
 pub use self::raw_document::RawDocument;
-pub use self::store::Index;
-pub use self::update::{EnqueuedUpdateResult, ProcessedUpdateResult, UpdateStatus, UpdateType};
+pub use self::store::{Index, UpdateQueueSize};
+pub use self::suggest::Suggestion;
+pub use self::update::{AdditionOptions, DocumentError, DuplicatesPolicy, DuplicatesReport, EnqueuedUpdateResult, Interruption, MetadataOperation, MetadataUpdate, ProcessedUpdateResult, TruncationReport, UpdateLogEntry, UpdatePhase, UpdateProgress, UpdateStatus, UpdateType};
 pub use meilisearch_types::{DocIndex, DocumentId, Highlight};
 pub use meilisearch_schema::Schema;
 pub use query_words_mapper::QueryWordsMapper;
@@ -48,10 +62,19 @@ use crate::levenshtein::prefix_damerau_levenshtein;
 use crate::query_tree::{QueryId, QueryKind};
 use crate::reordered_attrs::ReorderedAttrs;
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Document {
     pub id: DocumentId,
     pub highlights: Vec<Highlight>,
+    /// The number of documents sharing the distinct value of this
+    /// one that were left out of the results by the distinct rule.
+    pub collapsed: usize,
+    /// The proportion of the query words the document matches, weighted by their typos,
+    /// unlike the ranking it can be compared between the documents of different indexes.
+    pub score: f64,
+    /// The values compared by each criterion to rank the document,
+    /// only computed when requested.
+    pub ranking_score_details: Option<Vec<CriterionDetails>>,
 
     #[cfg(test)]
     pub matches: Vec<crate::bucket_sort::SimpleMatch>,
@@ -114,12 +137,12 @@ fn highlights_from_raw_document<'a, 'tag, 'txn>(
 impl Document {
     #[cfg(not(test))]
     pub fn from_highlights(id: DocumentId, highlights: &[Highlight]) -> Document {
-        Document { id, highlights: highlights.to_owned() }
+        Document { id, highlights: highlights.to_owned(), collapsed: 0, score: 0.0, ranking_score_details: None }
     }
 
     #[cfg(test)]
     pub fn from_highlights(id: DocumentId, highlights: &[Highlight]) -> Document {
-        Document { id, highlights: highlights.to_owned(), matches: Vec::new() }
+        Document { id, highlights: highlights.to_owned(), collapsed: 0, score: 0.0, ranking_score_details: None, matches: Vec::new() }
     }
 
     #[cfg(not(test))]
@@ -139,7 +162,7 @@ impl Document {
             schema,
         );
 
-        Document { id: raw_document.id, highlights }
+        Document { id: raw_document.id, highlights, collapsed: 0, score: 0.0, ranking_score_details: None }
     }
 
     #[cfg(test)]
@@ -180,7 +203,7 @@ impl Document {
         }
         matches.sort_unstable();
 
-        Document { id: raw_document.id, highlights, matches }
+        Document { id: raw_document.id, highlights, collapsed: 0, score: 0.0, ranking_score_details: None, matches }
     }
 }
 
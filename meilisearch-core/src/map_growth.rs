@@ -0,0 +1,78 @@
+use std::ffi::CStr;
+use std::io;
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::RwLock;
+
+use lmdb_rkv_sys as ffi;
+
+use crate::MResult;
+
+/// What happened when the map of an environment was asked to grow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Growth {
+    Grown(usize),
+    /// The map already has the maximum size.
+    MaximumReached,
+    /// A transaction is using the map, it cannot be remapped for now.
+    Busy,
+}
+
+/// Returns the current size of the map of the environment.
+pub(crate) fn map_size(env: &heed::Env) -> usize {
+    let mut info = std::mem::MaybeUninit::<ffi::MDB_envinfo>::uninit();
+    unsafe {
+        ffi::mdb_env_info(env.env_mut_ptr(), info.as_mut_ptr());
+        info.assume_init().me_mapsize
+    }
+}
+
+unsafe extern "C" fn count_active_reader(msg: *const c_char, ctx: *mut c_void) -> c_int {
+    // the lines list the pid, the thread and the transaction id of the readers slots,
+    // the id is a dash when the slot is not used by a transaction
+    let msg = CStr::from_ptr(msg).to_string_lossy();
+    let count = &mut *(ctx as *mut usize);
+    for line in msg.lines() {
+        if line.split_whitespace().nth(2).map_or(false, |txnid| txnid.parse::<u64>().is_ok()) {
+            *count += 1;
+        }
+    }
+    0
+}
+
+/// Returns the number of read transactions currently opened on the environment,
+/// by this process or by any other one.
+fn active_readers(env: &heed::Env) -> MResult<usize> {
+    let mut count = 0usize;
+    let ctx = &mut count as *mut usize as *mut c_void;
+    let rc = unsafe { ffi::mdb_reader_list(env.env_mut_ptr(), Some(count_active_reader), ctx) };
+    if rc < 0 {
+        return Err(io::Error::new(io::ErrorKind::Other, "impossible to list the readers").into());
+    }
+    Ok(count)
+}
+
+/// Doubles the map of the environment without exceeding `max_map_size`.
+///
+/// The map can only be remapped while no transaction uses it, the gate must be held for
+/// reading while a transaction begins. It is held for writing here, no new transaction can
+/// begin while the readers are counted and the map is remapped.
+pub(crate) fn grow(env: &heed::Env, gate: &RwLock<()>, max_map_size: usize) -> MResult<Growth> {
+    let _gate = gate.write().unwrap();
+
+    let current = map_size(env);
+    if current >= max_map_size {
+        return Ok(Growth::MaximumReached);
+    }
+
+    if active_readers(env)? > 0 {
+        return Ok(Growth::Busy);
+    }
+
+    let new_size = current.saturating_mul(2).min(max_map_size);
+    match unsafe { ffi::mdb_env_set_mapsize(env.env_mut_ptr(), new_size) } {
+        0 => Ok(Growth::Grown(new_size)),
+        // the map cannot be resized while there is a write transaction
+        rc if io::Error::from_raw_os_error(rc).kind() == io::ErrorKind::InvalidInput => Ok(Growth::Busy),
+        rc => Err(io::Error::from_raw_os_error(rc).into()),
+    }
+}
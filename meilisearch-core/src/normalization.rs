@@ -0,0 +1,46 @@
+use deunicode::deunicode_with_tofu;
+use meilisearch_tokenizer::is_unspaced;
+use unicode_normalization::UnicodeNormalization;
+
+/// Normalizes a word the same way at indexing and at search time, the compatibility
+/// characters are decomposed and the canonical ones recomposed (NFKC) then lowercased.
+pub fn normalize_word(word: &str) -> String {
+    word.nfkc().collect::<String>().to_lowercase()
+}
+
+/// Returns the word without its diacritics, if it has some,
+/// the scripts segmented by characters are never folded.
+pub fn fold_diacritics(word: &str) -> Option<String> {
+    if word.contains(is_unspaced) {
+        return None;
+    }
+
+    let folded = deunicode_with_tofu(word, "");
+    if folded != word && !folded.is_empty() {
+        Some(folded)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compatibility_and_canonical_forms() {
+        // the composed and decomposed forms of é are the same word
+        assert_eq!(normalize_word("D\u{e9}J\u{e0}"), "déjà");
+        assert_eq!(normalize_word("De\u{301}ja\u{300}"), "déjà");
+        // the ligatures and the full-width characters are decomposed
+        assert_eq!(normalize_word("\u{fb01}n"), "fin");
+        assert_eq!(normalize_word("\u{ff21}\u{ff22}"), "ab");
+    }
+
+    #[test]
+    fn folded_diacritics() {
+        assert_eq!(fold_diacritics("déjà").as_deref(), Some("deja"));
+        assert_eq!(fold_diacritics("deja"), None);
+        assert_eq!(fold_diacritics("東京"), None);
+    }
+}
@@ -5,6 +5,7 @@ use std::str::FromStr;
 
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, Hash)]
 pub enum Number {
@@ -94,6 +95,17 @@ impl Ord for Number {
     }
 }
 
+impl From<Number> for Value {
+    fn from(number: Number) -> Value {
+        match number {
+            Number::Unsigned(n) => Value::from(n),
+            Number::Signed(n) => Value::from(n),
+            Number::Float(n) => Value::from(n.into_inner()),
+            Number::Null => Value::Null,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParseNumberError {
     uint_error: ParseIntError,
@@ -1,28 +1,64 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::mem;
 use std::ops::{Range, Deref};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use either::Either;
-use sdset::SetOperation;
+use sdset::duo::Union;
+use sdset::{SetBuf, SetOperation};
+use serde::Deserialize;
 
-use meilisearch_schema::FieldId;
+use meilisearch_schema::{FieldId, Schema};
+use meilisearch_tokenizer::split_query_string;
 
 use crate::database::MainT;
 use crate::bucket_sort::{bucket_sort, bucket_sort_with_distinct, SortResult};
-use crate::{criterion::Criteria, DocumentId};
+use crate::criterion::{AttributeWeights, Criteria};
+use crate::{Document, DocumentId};
 use crate::{reordered_attrs::ReorderedAttrs, store, MResult};
-use crate::facets::FacetFilter;
+use crate::facets::{facet_stats, FacetCondition, FacetFilter};
+use crate::settings::DEFAULT_MAX_TOTAL_HITS;
+use crate::normalization::normalize_word;
+
+/// Defines which query words a document must contain to be part of the results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatchingStrategy {
+    /// all the query words are required
+    All,
+    /// the trailing query words are dropped one by one until there are enough results
+    Last,
+    /// the most frequent query words, the least discriminating ones,
+    /// are dropped one by one until there are enough results
+    Frequency,
+}
+
+impl Default for MatchingStrategy {
+    fn default() -> MatchingStrategy {
+        MatchingStrategy::All
+    }
+}
 
 pub struct QueryBuilder<'c, 'f, 'd, 'i> {
     criteria: Criteria<'c>,
     searchable_attrs: Option<ReorderedAttrs>,
+    attribute_weights: Option<AttributeWeights>,
     filter: Option<Box<dyn Fn(DocumentId) -> bool + 'f>>,
     distinct: Option<(Box<dyn Fn(DocumentId) -> Option<u64> + 'd>, usize)>,
-    timeout: Option<Duration>,
+    time_budget: Option<Duration>,
     index: &'i store::Index,
     facet_filter: Option<FacetFilter>,
+    numeric_ranges: Vec<(FieldId, f64, f64)>,
     facets: Option<Vec<(FieldId, String)>>,
+    global_facet_count: bool,
+    pinned: Vec<DocumentId>,
+    hidden: Vec<DocumentId>,
+    language: Option<String>,
+    matching_strategy: MatchingStrategy,
+    exhaustive_nb_hits: bool,
+    ranking_score_details: bool,
 }
 
 impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
@@ -38,11 +74,23 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
         self.facet_filter = facets;
     }
 
+    /// restricts the results to the documents with a number of the field
+    /// between `from` and `to`, both included, the ranges are intersected
+    pub fn add_numeric_range(&mut self, field_id: FieldId, from: f64, to: f64) {
+        self.numeric_ranges.push((field_id, from, to));
+    }
+
     /// sets facet attributes for which to return the count
     pub fn set_facets(&mut self, facets: Option<Vec<(FieldId, String)>>) {
         self.facets = facets;
     }
 
+    /// sets whether the facet counts are computed over the whole index
+    /// instead of the documents matching the query
+    pub fn set_global_facet_count(&mut self, global: bool) {
+        self.global_facet_count = global;
+    }
+
     pub fn with_criteria(
         index: &'i store::Index,
         criteria: Criteria<'c>,
@@ -50,15 +98,56 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
         QueryBuilder {
             criteria,
             searchable_attrs: None,
+            attribute_weights: None,
             filter: None,
             distinct: None,
-            timeout: None,
+            time_budget: None,
             index,
             facet_filter: None,
+            numeric_ranges: Vec::new(),
             facets: None,
+            global_facet_count: false,
+            pinned: Vec::new(),
+            hidden: Vec::new(),
+            language: None,
+            matching_strategy: MatchingStrategy::default(),
+            exhaustive_nb_hits: false,
+            ranking_score_details: false,
         }
     }
 
+    /// sets the documents to place at the top of the results, in this order
+    pub fn set_pinned_documents(&mut self, documents_ids: Vec<DocumentId>) {
+        self.pinned = documents_ids;
+    }
+
+    /// sets the documents that must never be part of the results
+    pub fn set_hidden_documents(&mut self, documents_ids: Vec<DocumentId>) {
+        self.hidden = documents_ids;
+    }
+
+    /// sets the language of the query, only the synonyms of this language are used
+    /// along the global ones, the language of the index is used when not set
+    pub fn set_language(&mut self, language: Option<String>) {
+        self.language = language;
+    }
+
+    /// sets whether the trailing query words can be dropped when there are not enough results
+    pub fn set_matching_strategy(&mut self, strategy: MatchingStrategy) {
+        self.matching_strategy = strategy;
+    }
+
+    /// sets whether the filter and the distinct rule must be applied to all the candidates
+    /// to return the exact number of hits instead of an estimation
+    pub fn set_exhaustive_nb_hits(&mut self, exhaustive: bool) {
+        self.exhaustive_nb_hits = exhaustive;
+    }
+
+    /// sets whether the values compared by each criterion are returned along the documents
+    pub fn set_ranking_score_details(&mut self, details: bool) {
+        self.ranking_score_details = details;
+    }
+
     pub fn with_filter<F>(&mut self, function: F)
     where
         F: Fn(DocumentId) -> bool + 'f,
@@ -66,8 +155,11 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
         self.filter = Some(Box::new(function))
     }
 
-    pub fn with_fetch_timeout(&mut self, timeout: Duration) {
-        self.timeout = Some(timeout)
+    /// bounds the time spent sorting the documents, once it is spent the best documents
+    /// found so far are returned and the result is marked as degraded, the search cutoff
+    /// of the index is used by default
+    pub fn with_time_budget(&mut self, budget: Duration) {
+        self.time_budget = Some(budget)
     }
 
     pub fn with_distinct<F>(&mut self, size: usize, function: F)
@@ -82,55 +174,142 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
         reorders.insert_attribute(attribute);
     }
 
+    /// restricts the search to these searchable attributes, the documents only matching
+    /// the query in the other attributes are not part of the results, the attributes that
+    /// are unknown or not searchable are ignored
+    pub fn restrict_searchable_attributes(&mut self, schema: &Schema, attributes: &[&str]) {
+        let reorders = self.searchable_attrs.get_or_insert_with(ReorderedAttrs::new);
+        for name in attributes {
+            if let Some(pos) = schema.id(name).and_then(|id| schema.is_indexed(id)) {
+                reorders.insert_attribute(pos.0);
+            }
+        }
+    }
+
+    /// weights the matches of the query words by the attribute they are found in, the
+    /// documents matching in the heaviest attributes are ranked first by the attribute
+    /// criterion, the attributes that are unknown or not searchable are ignored
+    pub fn set_attribute_weights(&mut self, schema: &Schema, weights: &[(&str, f64)]) {
+        let attribute_weights = self.attribute_weights.get_or_insert_with(AttributeWeights::new);
+        for (name, weight) in weights {
+            if let Some(pos) = schema.id(name).and_then(|id| schema.is_indexed(id)) {
+                attribute_weights.insert(pos.0, *weight);
+            }
+        }
+    }
+
     pub fn query(
-        self,
+        mut self,
         reader: &heed::RoTxn<MainT>,
         query: &str,
         range: Range<usize>,
     ) -> MResult<SortResult> {
-        let facets_docids = match self.facet_filter {
-            Some(facets) => {
-                let mut ands = Vec::with_capacity(facets.len());
-                let mut ors = Vec::new();
-                for f in facets.deref() {
-                    match f {
-                        Either::Left(keys) => {
-                            ors.reserve(keys.len());
-                            for key in keys {
-                                let docids = self.index.facets.facet_document_ids(reader, &key)?.unwrap_or_default();
-                                ors.push(docids);
-                            }
-                            let sets: Vec<_> = ors.iter().map(Cow::deref).collect();
-                            let or_result = sdset::multi::OpBuilder::from_vec(sets).union().into_set_buf();
-                            ands.push(Cow::Owned(or_result));
-                            ors.clear();
-                        }
-                        Either::Right(key) =>{
-                            match self.index.facets.facet_document_ids(reader, &key)? {
-                                Some(docids) => ands.push(docids),
-                                // no candidates for search, early return.
-                                None => return Ok(SortResult::default()),
-                            }
-                        }
-                    };
-                }
-                let ands: Vec<_> = ands.iter().map(Cow::deref).collect();
-                Some(sdset::multi::OpBuilder::from_vec(ands).intersection().into_set_buf())
-            }
+        // the time budget starts with the search, it covers all the relaxed passes
+        let time_budget = match self.time_budget {
+            Some(budget) => Some(budget),
+            None => self.index.main.search_cutoff_ms(reader)?.map(Duration::from_millis),
+        };
+        let deadline = time_budget.map(|budget| Instant::now() + budget);
+
+        // the deleted documents stay in the postings lists until they are purged,
+        // they are stored sorted and only need to be merged with the hidden ones
+        let deleted = self.index.deleted_documents(reader)?;
+        let hidden = if self.hidden.is_empty() {
+            deleted
+        } else {
+            let hidden = SetBuf::from_dirty(mem::take(&mut self.hidden));
+            Union::new(&hidden, &deleted).into_set_buf()
+        };
+
+        if !hidden.is_empty() {
+            let filter = self.filter.take();
+            self.filter = Some(Box::new(move |id: DocumentId| {
+                hidden.binary_search(&id).is_err() && filter.as_ref().map_or(true, |f| f(id))
+            }));
+        }
+
+        // the deeper the requested range the more documents must be sorted,
+        // the hits after the maximum number of hits can't be reached
+        let max_total_hits = self.index.main.max_total_hits(reader)?.unwrap_or(DEFAULT_MAX_TOTAL_HITS);
+        let range = cmp::min(range.start, max_total_hits)..cmp::min(range.end, max_total_hits);
+
+        let language = match self.language.take() {
+            Some(language) => Some(language),
+            None => self.index.main.language(reader)?,
+        };
+
+        // the disjunctive facet counts do not depend on the whole filter,
+        // they must be computed even when no document can match it
+        let no_facet_count = self.facets.is_none();
+
+        let mut facets_docids = match &self.facet_filter {
+            Some(facets) => match facet_filter_docids(reader, self.index, facets, None)? {
+                // no candidates for search, early return.
+                Some(docids) if docids.is_empty() && no_facet_count => return Ok(SortResult::default()),
+                docids => docids,
+            },
             None => None
         };
 
+        for (field_id, from, to) in mem::take(&mut self.numeric_ranges) {
+            let docids = self.index.numbers.range_document_ids(reader, field_id, from, to)?;
+            let docids = match facets_docids {
+                Some(facets_docids) => sdset::duo::OpBuilder::new(facets_docids.as_set(), docids.as_set())
+                    .intersection()
+                    .into_set_buf(),
+                None => docids,
+            };
+            // no candidates for search, early return.
+            if docids.is_empty() && no_facet_count {
+                return Ok(SortResult::default());
+            }
+            facets_docids = Some(docids);
+        }
+
+        // the pinned documents shift the ranked ones, we must retrieve enough of them
+        // to be able to fill the requested range once the pinned ones are placed.
+        // The relaxed passes are merged together, they must also start from the first document.
+        let pinned = self.pinned_documents(reader, facets_docids.as_ref())?;
+        let relaxed = self.matching_strategy != MatchingStrategy::All;
+        let requested_range = range.clone();
+        let range = if pinned.is_empty() && !relaxed { range } else { 0..range.end + pinned.len() };
+
         // for each field to retrieve the count for, create an HashMap associating the attribute
         // value to a set of matching documents. The HashMaps are them collected in another
         // HashMap, associating each HashMap to it's field.
-        let facet_count_docids = match self.facets {
+        //
+        // The facet counts are disjunctive: the documents ids of each field are restricted by
+        // the facet filter clauses that do not concern this field, this way selecting a value
+        // still shows the counts of the other values of the same field.
+        let facet_count_docids = match self.facets.take() {
             Some(field_ids) => {
                 let mut facet_count_map = HashMap::new();
                 for (field_id, field_name) in field_ids {
+                    let candidates = match &self.facet_filter {
+                        Some(facets) if !self.global_facet_count => {
+                            facet_filter_docids(reader, self.index, facets, Some(field_id))?
+                        }
+                        _ => None,
+                    };
+
                     let mut key_map = HashMap::new();
                     for pair in self.index.facets.field_document_ids(reader, field_id)? {
                         let (facet_key, document_ids) = pair?;
-                        let value = facet_key.value();
+                        let value = match self.index.facets.original_value(reader, &facet_key)? {
+                            Some(original) => original,
+                            None => facet_key.value(),
+                        };
+                        let mut document_ids = match &candidates {
+                            Some(candidates) => {
+                                let op = sdset::duo::OpBuilder::new(document_ids.as_ref(), candidates.as_set());
+                                Cow::Owned(op.intersection().into_set_buf())
+                            }
+                            None => document_ids,
+                        };
+                        if !deleted.is_empty() {
+                            let op = sdset::duo::OpBuilder::new(document_ids.as_ref(), deleted.as_set());
+                            document_ids = Cow::Owned(op.difference().into_set_buf());
+                        }
                         key_map.insert(value.to_string(), document_ids);
                     }
                     facet_count_map.insert(field_name, key_map);
@@ -140,20 +319,123 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
             None => None,
         };
 
-        match self.distinct {
+        // when the counts must be global, they do not depend on the query
+        // and are directly computed from the facet documents ids.
+        let (facet_count_docids, global_facets) = if self.global_facet_count {
+            (None, facet_count_docids.map(global_facet_count))
+        } else {
+            (facet_count_docids, None)
+        };
+
+        let mut result = self.sort(
+            reader,
+            query,
+            language.clone(),
+            range.clone(),
+            facets_docids.clone(),
+            facet_count_docids.clone(),
+            deadline,
+        )?;
+
+        // the query words are dropped one by one while there are not enough results,
+        // the documents matching more words stay in front of the ones found by a relaxed query.
+        let mut counted_query = Cow::Borrowed(query);
+        if relaxed {
+            let words: Vec<_> = split_query_string(query).collect();
+            let mut kept = vec![true; words.len()];
+            for index in self.dropping_order(reader, &words)? {
+                if result.nb_hits >= range.end || result.degraded {
+                    break;
+                }
+
+                kept[index] = false;
+                let relaxed_query = words
+                    .iter()
+                    .zip(&kept)
+                    .filter_map(|(word, kept)| if *kept { Some(*word) } else { None })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let relaxed_result = self.sort(
+                    reader,
+                    &relaxed_query,
+                    language.clone(),
+                    range.clone(),
+                    facets_docids.clone(),
+                    facet_count_docids.clone(),
+                    deadline,
+                )?;
+                result = merge_relaxed_result(result, relaxed_result, range.end);
+                counted_query = Cow::Owned(relaxed_query);
+            }
+        }
+
+        if let Some(facets) = global_facets {
+            result.exhaustive_facet_count = Some(true);
+            result.facets = Some(facets);
+        }
+
+        // the stats of the numeric facets are computed from the counts of their values
+        result.facets_stats = result.facets.as_ref().map(|facets| {
+            facets.iter().filter_map(|(name, counts)| Some((name.clone(), facet_stats(counts)?))).collect()
+        });
+
+        if !pinned.is_empty() {
+            // the pinned documents that were not sorted may still match the query and be
+            // part of the number of hits, only the ones that do not match are added to it
+            let unsorted: Vec<_> = pinned.iter().filter(|&&id| result.documents.iter().all(|d| d.id != id)).copied().collect();
+            if !unsorted.is_empty() {
+                let count = unsorted.len();
+                let candidates = SetBuf::from_dirty(unsorted);
+                let matching = self.sort(reader, &counted_query, language, 0..count, Some(candidates), None, None)?;
+                result.nb_hits += count - matching.documents.len();
+            }
+            pin_documents(&mut result, &pinned, requested_range);
+        } else if relaxed {
+            let documents = mem::take(&mut result.documents);
+            result.documents = documents.into_iter().skip(requested_range.start).take(requested_range.len()).collect();
+        }
+
+        Ok(result)
+    }
+
+    fn sort(
+        &self,
+        reader: &heed::RoTxn<MainT>,
+        query: &str,
+        language: Option<String>,
+        range: Range<usize>,
+        facets_docids: Option<SetBuf<DocumentId>>,
+        facet_count_docids: Option<HashMap<String, HashMap<String, Cow<sdset::Set<DocumentId>>>>>,
+        deadline: Option<Instant>,
+    ) -> MResult<SortResult> {
+        // the matches attributes are rewritten when the searchable attributes are restricted
+        let attribute_weights = match (&self.attribute_weights, &self.searchable_attrs) {
+            (Some(weights), Some(reordered)) => Some(weights.reordered(reordered)),
+            (weights, _) => weights.clone(),
+        };
+
+        match &self.distinct {
             Some((distinct, distinct_size)) => bucket_sort_with_distinct(
                 reader,
                 query,
+                language,
                 range,
                 facets_docids,
                 facet_count_docids,
-                self.filter,
+                self.filter.as_ref(),
                 distinct,
-                distinct_size,
-                self.criteria,
-                self.searchable_attrs,
+                *distinct_size,
+                &self.criteria,
+                self.searchable_attrs.clone(),
+                attribute_weights.as_ref(),
+                // the collapsed documents are counted over all the candidates,
+                // which also gives the exact number of hits
+                true,
+                deadline,
+                self.ranking_score_details,
                 self.index.main,
                 self.index.postings_lists,
+                self.index.documents_fields,
                 self.index.documents_fields_counts,
                 self.index.synonyms,
                 self.index.prefix_documents_cache,
@@ -162,14 +444,20 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
             None => bucket_sort(
                 reader,
                 query,
+                language,
                 range,
                 facets_docids,
                 facet_count_docids,
-                self.filter,
-                self.criteria,
-                self.searchable_attrs,
+                self.filter.as_ref(),
+                &self.criteria,
+                self.searchable_attrs.clone(),
+                attribute_weights.as_ref(),
+                self.exhaustive_nb_hits,
+                deadline,
+                self.ranking_score_details,
                 self.index.main,
                 self.index.postings_lists,
+                self.index.documents_fields,
                 self.index.documents_fields_counts,
                 self.index.synonyms,
                 self.index.prefix_documents_cache,
@@ -177,6 +465,155 @@ impl<'c, 'f, 'd, 'i> QueryBuilder<'c, 'f, 'd, 'i> {
             ),
         }
     }
+
+    /// returns the positions of the query words in the order they are dropped by the
+    /// matching strategy, there is always one word left that is never dropped
+    fn dropping_order(&self, reader: &heed::RoTxn<MainT>, words: &[&str]) -> MResult<Vec<usize>> {
+        let mut order: Vec<_> = (1..words.len()).rev().collect();
+
+        if self.matching_strategy == MatchingStrategy::Frequency {
+            let mut frequencies = Vec::with_capacity(words.len());
+            for word in words {
+                let word = normalize_word(word);
+                let postings_list = self.index.postings_lists.postings_list(reader, word.as_bytes())?;
+                frequencies.push(postings_list.map_or(0, |postings| postings.docids.len()));
+            }
+
+            // the trailing words are dropped first when they are as frequent
+            order = (0..words.len()).collect();
+            order.sort_by_key(|&index| (cmp::Reverse(frequencies[index]), cmp::Reverse(index)));
+            order.truncate(words.len().saturating_sub(1));
+        }
+
+        Ok(order)
+    }
+
+    /// returns the pinned documents that exist in the index and pass the filters,
+    /// the hidden and deleted documents are already rejected by the filter
+    fn pinned_documents(
+        &self,
+        reader: &heed::RoTxn<MainT>,
+        facets_docids: Option<&SetBuf<DocumentId>>,
+    ) -> MResult<Vec<DocumentId>> {
+        let mut pinned = Vec::with_capacity(self.pinned.len());
+        for &id in &self.pinned {
+            if self.filter.as_ref().map_or(false, |f| !f(id)) {
+                continue;
+            }
+            if facets_docids.map_or(false, |docids| docids.binary_search(&id).is_err()) {
+                continue;
+            }
+            let mut fields_counts = self.index.documents_fields_counts.document_fields_counts(reader, id)?;
+            if fields_counts.next().is_some() && !pinned.contains(&id) {
+                pinned.push(id);
+            }
+        }
+        Ok(pinned)
+    }
+}
+
+/// Appends the documents found by a relaxed query that are not already part of the
+/// stricter results, the counts of the relaxed query are kept as they include the others.
+fn merge_relaxed_result(strict: SortResult, mut relaxed: SortResult, limit: usize) -> SortResult {
+    let seen: HashSet<_> = strict.documents.iter().map(|d| d.id).collect();
+    let mut documents = strict.documents;
+    documents.extend(relaxed.documents.into_iter().filter(|d| !seen.contains(&d.id)));
+    documents.truncate(limit);
+    relaxed.documents = documents;
+    relaxed.degraded |= strict.degraded;
+    relaxed
+}
+
+/// Places the pinned documents at the top of the results, removing them from
+/// their ranked positions, and only keeps the documents in the requested range.
+fn pin_documents(result: &mut SortResult, pinned: &[DocumentId], range: Range<usize>) {
+    let mut ranked = mem::take(&mut result.documents);
+
+    let mut documents = Vec::with_capacity(ranked.len() + pinned.len());
+    for &id in pinned {
+        match ranked.iter().position(|d| d.id == id) {
+            Some(pos) => documents.push(ranked.remove(pos)),
+            None => documents.push(Document::from_highlights(id, &[])),
+        }
+    }
+    documents.extend(ranked);
+
+    result.documents = documents.into_iter().skip(range.start).take(range.len()).collect();
+}
+
+/// Computes the documents ids matching the facet filter, ignoring the clauses that only concern
+/// the `excluded` field. Returns `None` when no clause restricts the documents.
+fn facet_filter_docids(
+    reader: &heed::RoTxn<MainT>,
+    index: &store::Index,
+    facet_filter: &FacetFilter,
+    excluded: Option<FieldId>,
+) -> MResult<Option<SetBuf<DocumentId>>> {
+    let is_excluded = |condition: &FacetCondition| Some(condition.field_id()) == excluded;
+
+    let mut ands = Vec::with_capacity(facet_filter.len());
+    let mut ors = Vec::new();
+    for f in facet_filter.deref() {
+        match f {
+            Either::Left(conditions) => {
+                if conditions.iter().all(is_excluded) {
+                    continue;
+                }
+                ors.reserve(conditions.len());
+                for condition in conditions {
+                    let docids = condition_docids(reader, index, condition)?.unwrap_or_default();
+                    ors.push(docids);
+                }
+                let sets: Vec<_> = ors.iter().map(Cow::deref).collect();
+                let or_result = sdset::multi::OpBuilder::from_vec(sets).union().into_set_buf();
+                ands.push(Cow::Owned(or_result));
+                ors.clear();
+            }
+            Either::Right(condition) => {
+                if is_excluded(condition) {
+                    continue;
+                }
+                match condition_docids(reader, index, condition)? {
+                    Some(docids) => ands.push(docids),
+                    // no document can match this filter.
+                    None => return Ok(Some(SetBuf::default())),
+                }
+            }
+        };
+    }
+
+    if ands.is_empty() {
+        return Ok(None);
+    }
+
+    let ands: Vec<_> = ands.iter().map(Cow::deref).collect();
+    Ok(Some(sdset::multi::OpBuilder::from_vec(ands).intersection().into_set_buf()))
+}
+
+fn condition_docids<'txn>(
+    reader: &'txn heed::RoTxn<MainT>,
+    index: &store::Index,
+    condition: &FacetCondition,
+) -> MResult<Option<Cow<'txn, sdset::Set<DocumentId>>>> {
+    match condition {
+        FacetCondition::Equal(key) => Ok(index.facets.facet_document_ids(reader, key)?),
+        FacetCondition::Range { field_id, from, to } => {
+            let docids = index.facets.numeric_range_document_ids(reader, *field_id, *from, *to)?;
+            Ok(Some(Cow::Owned(docids)))
+        }
+    }
+}
+
+fn global_facet_count(
+    facet_docids: HashMap<String, HashMap<String, Cow<sdset::Set<DocumentId>>>>,
+) -> HashMap<String, HashMap<String, usize>> {
+    facet_docids
+        .into_iter()
+        .map(|(key, doc_map)| {
+            let count_map = doc_map.into_iter().map(|(value, docids)| (value, docids.len())).collect();
+            (key, count_map)
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -283,10 +720,7 @@ mod tests {
                 .put_synonyms(&mut writer, word.as_bytes(), &new_alternatives)
                 .unwrap();
 
-            let synonyms = match self.index.main.synonyms_fst(&writer).unwrap() {
-                Some(synonyms) => synonyms,
-                None => fst::Set::default(),
-            };
+            let synonyms = self.index.main.synonyms_fst(&writer).unwrap().unwrap_or_default();
 
             let synonyms_fst = insert_key(&synonyms, word.as_bytes());
             self.index
@@ -1167,21 +1601,15 @@ mod tests {
         });
         assert_matches!(iter.next(), None);
 
+        // the query word is also searched without its diacritics, the
+        // unidecoded word of the first document matches without typo
         let builder = store.query_builder();
         let SortResult {documents, .. } = builder.query(&reader, "télephone", 0..20).unwrap();
-        let mut iter = documents.into_iter();
+        assert_eq!(documents.len(), 2);
 
-        assert_matches!(iter.next(), Some(Document { id: DocumentId(1), matches, .. }) => {
-            let mut iter = matches.into_iter();
-            assert_matches!(iter.next(), Some(SimpleMatch { query_index: 0, .. }));
-            assert_matches!(iter.next(), None);
-        });
-        assert_matches!(iter.next(), Some(Document { id: DocumentId(0), matches, .. }) => {
-            let mut iter = matches.into_iter();
-            assert_matches!(iter.next(), Some(SimpleMatch { query_index: 0, distance: 1, word_index: 0, is_exact: false, .. })); // iphone | telephone
-            assert_matches!(iter.next(), None);
-        });
-        assert_matches!(iter.next(), None);
+        let document = documents.iter().find(|d| d.id == DocumentId(0)).unwrap();
+        assert!(document.matches.iter().any(|m| m.query_index == 0 && m.distance == 0));
+        assert!(documents.iter().any(|d| d.id == DocumentId(1)));
     }
 
     #[test]
@@ -1313,4 +1741,46 @@ mod tests {
         });
         assert_matches!(iter.next(), None);
     }
+
+    #[test]
+    fn time_budget_degrades_the_results() {
+        let store = TempDatabase::from_iter(vec![
+            ("hallo", &[doc_index(0, 0)][..]),
+            ("hello", &[doc_index(1, 0)][..]),
+        ]);
+
+        let db = &store.database;
+        let reader = db.main_read_txn().unwrap();
+
+        let builder = store.query_builder();
+        let SortResult { documents, degraded, .. } = builder.query(&reader, "hello", 0..20).unwrap();
+        let ids: Vec<_> = documents.iter().map(|d| d.id).collect();
+        assert_eq!(ids, vec![DocumentId(1), DocumentId(0)]);
+        assert!(!degraded);
+
+        // the budget is spent before the first criterion, the documents are not sorted
+        let mut builder = store.query_builder();
+        builder.with_time_budget(Duration::from_millis(0));
+        let SortResult { documents, degraded, .. } = builder.query(&reader, "hello", 0..20).unwrap();
+        let ids: Vec<_> = documents.iter().map(|d| d.id).collect();
+        assert_eq!(ids, vec![DocumentId(0), DocumentId(1)]);
+        assert!(degraded);
+        drop(reader);
+
+        // the search cutoff of the index is the default budget
+        let mut writer = db.main_write_txn().unwrap();
+        store.index.main.put_search_cutoff_ms(&mut writer, 0).unwrap();
+        writer.commit().unwrap();
+
+        let reader = db.main_read_txn().unwrap();
+        let builder = store.query_builder();
+        let SortResult { degraded, .. } = builder.query(&reader, "hello", 0..20).unwrap();
+        assert!(degraded);
+
+        let mut builder = store.query_builder();
+        builder.with_time_budget(Duration::from_secs(60));
+        let SortResult { documents, degraded, .. } = builder.query(&reader, "hello", 0..20).unwrap();
+        assert_eq!(documents[0].id, DocumentId(1));
+        assert!(!degraded);
+    }
 }
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+
+/// The condition a query must fulfill for a rule to be applied.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QueryCondition {
+    /// The query must be equal to this string, case insensitively.
+    Exact(String),
+    /// The query must match this pattern, where `*` matches any sequence of characters.
+    Pattern(String),
+}
+
+impl QueryCondition {
+    pub fn matches(&self, query: &str) -> bool {
+        let query = normalize_query(query);
+        match self {
+            QueryCondition::Exact(exact) => normalize_query(exact) == query,
+            QueryCondition::Pattern(pattern) => wildcard_match(&normalize_query(pattern), &query),
+        }
+    }
+}
+
+/// A rule applied to the queries matching its condition, it can pin documents to the top of
+/// the results, hide documents from the results and force a filter expression to be applied.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct QueryRule {
+    pub condition: QueryCondition,
+    #[serde(default)]
+    pub pinned: Vec<String>,
+    #[serde(default)]
+    pub hidden: Vec<String>,
+    #[serde(default)]
+    pub filters: Option<String>,
+}
+
+/// The actions to apply to a query, merged from all the rules that match it.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct QueryActions {
+    pub pinned: Vec<String>,
+    pub hidden: Vec<String>,
+    pub filters: Vec<String>,
+}
+
+impl QueryActions {
+    pub fn is_empty(&self) -> bool {
+        self.pinned.is_empty() && self.hidden.is_empty() && self.filters.is_empty()
+    }
+}
+
+/// Returns the actions of all the rules matching the query, in the order of the rules.
+pub fn matching_actions(rules: &[QueryRule], query: &str) -> QueryActions {
+    let mut actions = QueryActions::default();
+
+    for rule in rules.iter().filter(|r| r.condition.matches(query)) {
+        for id in &rule.pinned {
+            if !actions.pinned.contains(id) {
+                actions.pinned.push(id.clone());
+            }
+        }
+        actions.hidden.extend(rule.hidden.iter().cloned());
+        actions.filters.extend(rule.filters.iter().cloned());
+    }
+
+    actions
+}
+
+fn normalize_query(query: &str) -> String {
+    query.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // split always returns at least one element
+    let first = parts.next().unwrap();
+    if !text.starts_with(first) {
+        return false;
+    }
+    let mut text = &text[first.len()..];
+
+    let parts: Vec<_> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some((last, middle)) => (last, middle),
+        None => return text.is_empty(),
+    };
+
+    for part in middle {
+        match text.find(part) {
+            Some(pos) => text = &text[pos + part.len()..],
+            None => return false,
+        }
+    }
+
+    text.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_condition() {
+        let condition = QueryCondition::Exact("Apple  Phone".to_string());
+        assert!(condition.matches("apple phone"));
+        assert!(condition.matches(" APPLE phone "));
+        assert!(!condition.matches("apple phones"));
+    }
+
+    #[test]
+    fn pattern_condition() {
+        let condition = QueryCondition::Pattern("apple *".to_string());
+        assert!(condition.matches("apple phone"));
+        assert!(!condition.matches("pineapple phone"));
+
+        let condition = QueryCondition::Pattern("*phone*case".to_string());
+        assert!(condition.matches("iphone red case"));
+        assert!(condition.matches("phonecase"));
+        assert!(!condition.matches("iphone case red"));
+
+        let condition = QueryCondition::Pattern("*".to_string());
+        assert!(condition.matches("anything"));
+    }
+
+    #[test]
+    fn merge_actions() {
+        let rules = vec![
+            QueryRule {
+                condition: QueryCondition::Pattern("iphone*".to_string()),
+                pinned: vec!["1".to_string(), "2".to_string()],
+                hidden: vec!["3".to_string()],
+                filters: None,
+            },
+            QueryRule {
+                condition: QueryCondition::Exact("iphone".to_string()),
+                pinned: vec!["2".to_string(), "4".to_string()],
+                hidden: Vec::new(),
+                filters: Some("brand = apple".to_string()),
+            },
+        ];
+
+        let actions = matching_actions(&rules, "iphone");
+        assert_eq!(actions.pinned, vec!["1", "2", "4"]);
+        assert_eq!(actions.hidden, vec!["3"]);
+        assert_eq!(actions.filters, vec!["brand = apple"]);
+
+        let actions = matching_actions(&rules, "samsung");
+        assert!(actions.is_empty());
+    }
+}
@@ -5,13 +5,16 @@ use std::ops::Range;
 use std::time::Instant;
 use std::{cmp, fmt, iter::once};
 
-use fst::{IntoStreamer, Streamer};
+use fst::{set::OpBuilder, Streamer};
 use itertools::{EitherOrBoth, merge_join_by};
-use meilisearch_tokenizer::split_query_string;
+use meilisearch_tokenizer::{group_compounds, TokenGroup};
 use sdset::{Set, SetBuf, SetOperation};
+use levenshtein_automata::DFA;
 use log::debug;
 
 use crate::database::MainT;
+use crate::normalization::{fold_diacritics, normalize_word};
+use crate::settings::{PrefixSearch, TokenMode, TokenPolicy, TypoTolerance};
 use crate::{store, DocumentId, DocIndex, MResult};
 use crate::automaton::{normalize_str, build_dfa, build_prefix_dfa, build_exact_dfa};
 use crate::QueryWordsMapper;
@@ -45,16 +48,20 @@ impl fmt::Debug for Operation {
 
 impl Operation {
     fn tolerant(id: QueryId, prefix: bool, s: &str) -> Operation {
-        Operation::Query(Query { id, prefix, exact: true, kind: QueryKind::Tolerant(s.to_string()) })
+        let kind = QueryKind::Tolerant(s.to_string());
+        Operation::Query(Query { id, prefix, exact: true, derived: false, kind })
     }
 
-    fn non_tolerant(id: QueryId, prefix: bool, s: &str) -> Operation {
-        Operation::Query(Query { id, prefix, exact: true, kind: QueryKind::NonTolerant(s.to_string()) })
+    /// The query words concatenated, for the words that were typed with an extra space.
+    fn concatenation(id: QueryId, prefix: bool, s: &str) -> Operation {
+        let kind = QueryKind::NonTolerant(s.to_string());
+        Operation::Query(Query { id, prefix, exact: false, derived: true, kind })
     }
 
-    fn phrase2(id: QueryId, prefix: bool, (left, right): (&str, &str)) -> Operation {
+    /// A query word split in two, for the words that were typed without a space.
+    fn split(id: QueryId, prefix: bool, (left, right): (&str, &str)) -> Operation {
         let kind = QueryKind::Phrase(vec![left.to_owned(), right.to_owned()]);
-        Operation::Query(Query { id, prefix, exact: true, kind })
+        Operation::Query(Query { id, prefix, exact: false, derived: true, kind })
     }
 }
 
@@ -65,18 +72,22 @@ pub struct Query {
     pub id: QueryId,
     pub prefix: bool,
     pub exact: bool,
+    /// The query was derived by splitting or concatenating the query words,
+    /// its matches are penalized as if they contained one more typo.
+    pub derived: bool,
     pub kind: QueryKind,
 }
 
 impl PartialEq for Query {
     fn eq(&self, other: &Self) -> bool {
-        self.prefix == other.prefix && self.kind == other.kind
+        self.prefix == other.prefix && self.derived == other.derived && self.kind == other.kind
     }
 }
 
 impl Hash for Query {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.prefix.hash(state);
+        self.derived.hash(state);
         self.kind.hash(state);
     }
 }
@@ -112,12 +123,66 @@ pub struct PostingsList {
     matches: SetBuf<DocIndex>,
 }
 
-pub struct Context {
-    pub words_set: fst::Set,
-    pub stop_words: fst::Set,
+/// What the query tree is built from, the sets are read in place with the transaction.
+pub struct Context<'txn> {
+    pub words_set: store::FstSet<'txn>,
+    /// The words added since the words set was last rewritten.
+    pub words_delta: store::FstSet<'txn>,
+    pub stop_words: store::FstSet<'txn>,
     pub synonyms: store::Synonyms,
+    /// The language of the query, its synonyms are used along the global ones.
+    pub language: Option<String>,
     pub postings_lists: store::PostingsLists,
     pub prefix_postings_lists: store::PrefixPostingsListsCache,
+    pub typo_tolerance: TypoTolerance,
+    pub prefix_search: PrefixSearch,
+    /// Whether the query words are also searched without their diacritics.
+    pub fold_diacritics: bool,
+    /// Whether the stop words are indexed and searched in the phrases.
+    pub stop_words_in_phrases: bool,
+    /// How the compounds of the query are split, the same way as in the documents.
+    pub token_policy: TokenPolicy,
+    /// The sorted indexed positions of the attributes in which typos are not allowed.
+    pub typo_disabled_attributes: Vec<u16>,
+}
+
+/// Returns the words accepted by the automaton that start with the given byte,
+/// the ones of the words set and of the words delta.
+fn search_words<'s>(ctx: &'s Context, dfa: &'s DFA, byte: u8) -> fst::set::Union<'s> {
+    fn search<'s>(set: &'s fst::Set, dfa: &'s DFA, byte: u8) -> fst::set::StreamBuilder<'s, &'s DFA> {
+        let builder = set.search(dfa).ge(&[byte]);
+        if byte == u8::max_value() { builder } else { builder.lt(&[byte + 1]) }
+    }
+
+    OpBuilder::new()
+        .add(search(&ctx.words_set, dfa, byte))
+        .add(search(&ctx.words_delta, dfa, byte))
+        .union()
+}
+
+/// Removes the matches found in the given sorted attributes, returns `None` if none remain.
+fn remove_attributes_matches<'a>(postings: store::Postings<'a>, attributes: &[u16]) -> Option<store::Postings<'a>> {
+    let matches: Vec<_> = postings.matches
+        .iter()
+        .filter(|m| attributes.binary_search(&m.attribute).is_err())
+        .cloned()
+        .collect();
+
+    if matches.is_empty() {
+        return None;
+    }
+
+    let mut docids: Vec<_> = matches.iter().map(|m| m.document_id).collect();
+    docids.dedup();
+
+    let docids = Cow::Owned(SetBuf::new_unchecked(docids));
+    let matches = Cow::Owned(SetBuf::new_unchecked(matches));
+    Some(store::Postings { docids, matches })
+}
+
+/// Returns `true` if the word is indexed, either in the words set or in the words delta.
+fn contains_word(ctx: &Context, word: &str) -> bool {
+    ctx.words_set.contains(word) || ctx.words_delta.contains(word)
 }
 
 fn split_best_frequency<'a>(reader: &heed::RoTxn<MainT>, ctx: &Context, word: &'a str) -> MResult<Option<(&'a str, &'a str)>> {
@@ -127,6 +192,11 @@ fn split_best_frequency<'a>(reader: &heed::RoTxn<MainT>, ctx: &Context, word: &'
     for (i, _) in chars {
         let (left, right) = word.split_at(i);
 
+        // the words set is cheaper to search than the postings lists
+        if !contains_word(ctx, left) || !contains_word(ctx, right) {
+            continue;
+        }
+
         let left_freq = ctx.postings_lists
             .postings_list(reader, left.as_bytes())?
             .map(|p| p.docids.len())
@@ -148,9 +218,13 @@ fn split_best_frequency<'a>(reader: &heed::RoTxn<MainT>, ctx: &Context, word: &'
 fn fetch_synonyms(reader: &heed::RoTxn<MainT>, ctx: &Context, words: &[&str]) -> MResult<Vec<Vec<String>>> {
     let words = normalize_str(&words.join(" "));
     let set = ctx.synonyms.synonyms(reader, words.as_bytes())?.unwrap_or_default();
+    let localized_set = match &ctx.language {
+        Some(language) => ctx.synonyms.localized_synonyms(reader, language, words.as_bytes())?.unwrap_or_default(),
+        None => fst::Set::default(),
+    };
 
     let mut strings = Vec::new();
-    let mut stream = set.stream();
+    let mut stream = OpBuilder::new().add(&set).add(&localized_set).union();
     while let Some(input) = stream.next() {
         if let Ok(input) = std::str::from_utf8(input) {
             let alts = input.split_ascii_whitespace().map(ToOwned::to_owned).collect();
@@ -174,15 +248,95 @@ where I: IntoIterator<Item=Operation>,
 
 const MAX_NGRAM: usize = 3;
 
+/// A word of the query or a sequence of words written between double quotes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum QueryTerm {
+    Word(String),
+    Phrase(Vec<String>),
+    /// A compound kept as a single word, like `iso-9001`, with its
+    /// parts when they are also indexed at consecutive positions.
+    Compound(String, Vec<String>),
+}
+
+impl QueryTerm {
+    fn as_word(&self) -> Option<&str> {
+        match self {
+            QueryTerm::Word(word) => Some(word),
+            QueryTerm::Phrase(_) | QueryTerm::Compound(..) => None,
+        }
+    }
+}
+
+impl fmt::Display for QueryTerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryTerm::Word(word) => f.write_str(word),
+            QueryTerm::Phrase(words) => f.write_str(&words.join(" ")),
+            QueryTerm::Compound(word, _) => f.write_str(word),
+        }
+    }
+}
+
+/// Splits a part of the query into normalized words, the compounds follow the token policy.
+fn split_query_part(part: &str, policy: &TokenPolicy) -> Vec<QueryTerm> {
+    let mut terms = Vec::new();
+
+    for group in group_compounds(part) {
+        match group {
+            TokenGroup::Token(token) => terms.push(QueryTerm::Word(normalize_word(token.word))),
+            TokenGroup::Compound(compound) => {
+                let parts = compound.tokens.iter().map(|t| normalize_word(t.word));
+                match policy.mode(&compound) {
+                    TokenMode::Split => terms.extend(parts.map(QueryTerm::Word)),
+                    TokenMode::Keep => terms.push(QueryTerm::Compound(normalize_word(compound.word), Vec::new())),
+                    TokenMode::KeepAndSplit => terms.push(QueryTerm::Compound(normalize_word(compound.word), parts.collect())),
+                }
+            },
+        }
+    }
+
+    terms
+}
+
+/// Splits the query into normalized words and double-quoted phrases, an unclosed quote
+/// ends with the query. The stop words are kept in the phrases where they take a position,
+/// a phrase made of stop words is only kept when they are searched in the phrases.
+fn split_query_terms(query: &str, policy: &TokenPolicy, stop_words: &fst::Set, stop_words_in_phrases: bool) -> Vec<QueryTerm> {
+    let mut terms = Vec::new();
+
+    for (i, part) in query.split('"').enumerate() {
+        let part_terms = split_query_part(part, policy);
+        if i % 2 == 0 {
+            let searched = |t: &QueryTerm| t.as_word().map_or(true, |w| !stop_words.contains(w));
+            terms.extend(part_terms.into_iter().filter(searched));
+        } else {
+            // the words of a compound take the positions they have in the documents
+            let words: Vec<_> = part_terms
+                .into_iter()
+                .flat_map(|term| match term {
+                    QueryTerm::Word(word) => vec![word],
+                    QueryTerm::Compound(word, parts) => if parts.is_empty() { vec![word] } else { parts },
+                    QueryTerm::Phrase(words) => words,
+                })
+                .collect();
+            let searched = |w: &String| stop_words_in_phrases || !stop_words.contains(w);
+            if words.iter().any(searched) {
+                terms.push(QueryTerm::Phrase(words));
+            }
+        }
+    }
+
+    terms
+}
+
 pub fn create_query_tree(
     reader: &heed::RoTxn<MainT>,
     ctx: &Context,
     query: &str,
 ) -> MResult<(Operation, HashMap<QueryId, Range<usize>>)>
 {
-    let words = split_query_string(query).map(str::to_lowercase);
-    let words = words.filter(|w| !ctx.stop_words.contains(w));
-    let words: Vec<_> = words.enumerate().collect();
+    let words = split_query_terms(query, &ctx.token_policy, &ctx.stop_words, ctx.stop_words_in_phrases);
+    let words: Vec<_> = words.into_iter().enumerate().collect();
 
     let mut mapper = QueryWordsMapper::new(words.iter().map(|(_, w)| w));
 
@@ -190,21 +344,42 @@ pub fn create_query_tree(
         reader: &heed::RoTxn<MainT>,
         ctx: &Context,
         mapper: &mut QueryWordsMapper,
-        words: &[(usize, String)],
+        words: &[(usize, QueryTerm)],
     ) -> MResult<Vec<Operation>>
     {
         let mut alts = Vec::new();
 
         for ngram in 1..=MAX_NGRAM {
             if let Some(group) = words.get(..ngram) {
+                // the words of a phrase can't be concatenated with the other words
+                if ngram > 1 && group.iter().any(|(_, term)| term.as_word().is_none()) {
+                    break;
+                }
+
                 let mut group_ops = Vec::new();
 
                 let tail = &words[ngram..];
-                let is_last = tail.is_empty();
+                let is_last = tail.is_empty() && ctx.prefix_search.enabled;
 
                 let mut group_alts = Vec::new();
                 match group {
-                    [(id, word)] => {
+                    [(id, QueryTerm::Phrase(words))] => {
+                        let kind = QueryKind::Phrase(words.clone());
+                        group_alts.push(Operation::Query(Query { id: *id, prefix: false, exact: true, derived: false, kind }));
+                    },
+                    [(id, QueryTerm::Compound(word, parts))] => {
+                        let kind = QueryKind::NonTolerant(word.clone());
+                        group_alts.push(Operation::Query(Query { id: *id, prefix: false, exact: true, derived: false, kind }));
+
+                        // the parts are searched as a phrase, they are indexed at consecutive positions
+                        if !parts.is_empty() {
+                            let phrase_id = (id + 1) * 100;
+                            mapper.declare((*id)..id+1, phrase_id, parts);
+                            let kind = QueryKind::Phrase(parts.clone());
+                            group_alts.push(Operation::Query(Query { id: phrase_id, prefix: false, exact: true, derived: false, kind }));
+                        }
+                    },
+                    [(id, QueryTerm::Word(word))] => {
                         let mut idgen = ((id + 1) * 100)..;
                         let range = (*id)..id+1;
 
@@ -213,9 +388,16 @@ pub fn create_query_tree(
                                 let id = idgen.next().unwrap();
                                 idgen.next().unwrap();
                                 mapper.declare(range.clone(), id, &[ws.0, ws.1]);
-                                Operation::phrase2(id, is_last, ws)
+                                Operation::split(id, is_last, ws)
                             });
 
+                        let folded = if ctx.fold_diacritics { fold_diacritics(word) } else { None };
+                        let folded = folded.map(|folded| {
+                            let id = idgen.next().unwrap();
+                            mapper.declare(range.clone(), id, &[&folded]);
+                            Operation::tolerant(id, is_last, &folded)
+                        });
+
                         let synonyms = fetch_synonyms(reader, ctx, &[word])?
                             .into_iter()
                             .map(|alts| {
@@ -227,7 +409,7 @@ pub fn create_query_tree(
                                 let iter = alts.into_iter().map(|w| {
                                     let id = idgen.next().unwrap();
                                     let kind = QueryKind::NonTolerant(w);
-                                    Operation::Query(Query { id, prefix: false, exact, kind })
+                                    Operation::Query(Query { id, prefix: false, exact, derived: false, kind })
                                 });
 
                                 create_operation(iter, Operation::And)
@@ -236,6 +418,7 @@ pub fn create_query_tree(
                         let original = Operation::tolerant(*id, is_last, word);
 
                         group_alts.push(original);
+                        group_alts.extend(folded);
                         group_alts.extend(synonyms.chain(phrase));
                     },
                     words => {
@@ -243,7 +426,7 @@ pub fn create_query_tree(
                         let mut idgen = ((id + 1) * 100_usize.pow(ngram as u32))..;
                         let range = id..id+ngram;
 
-                        let words: Vec<_> = words.iter().map(|(_, s)| s.as_str()).collect();
+                        let words: Vec<_> = words.iter().filter_map(|(_, t)| t.as_word()).collect();
 
                         for synonym in fetch_synonyms(reader, ctx, &words)? {
                             let exact = synonym.len() == 1;
@@ -254,7 +437,7 @@ pub fn create_query_tree(
                             let synonym = synonym.into_iter().map(|s| {
                                 let id = idgen.next().unwrap();
                                 let kind = QueryKind::NonTolerant(s);
-                                Operation::Query(Query { id, prefix: false, exact, kind })
+                                Operation::Query(Query { id, prefix: false, exact, derived: false, kind })
                             });
                             group_alts.push(create_operation(synonym, Operation::And));
                         }
@@ -262,7 +445,7 @@ pub fn create_query_tree(
                         let id = idgen.next().unwrap();
                         let concat = words.concat();
                         mapper.declare(range.clone(), id, &[&concat]);
-                        group_alts.push(Operation::non_tolerant(id, is_last, &concat));
+                        group_alts.push(Operation::concatenation(id, is_last, &concat));
                     }
                 }
 
@@ -397,20 +580,30 @@ pub fn traverse_query_tree<'o, 'txn>(
     {
         let before = Instant::now();
 
-        let Query { prefix, kind, exact, .. } = query;
+        let Query { prefix, kind, exact, derived, .. } = query;
+        let penalty = if *derived { 1 } else { 0 };
         let docids: Cow<Set<_>> = match kind {
             QueryKind::Tolerant(word) => {
-                if *prefix && word.len() <= 2 {
+                // the exact words are never matched as prefixes
+                let prefix = *prefix && !ctx.typo_tolerance.is_exact_word(word);
+
+                // the short prefixes that are not frequent enough are not cached,
+                // the words they are a prefix of are searched like the longer ones
+                let cached = if prefix && word.len() <= ctx.prefix_search.cached_prefix_length() {
                     let prefix = {
                         let mut array = [0; 4];
                         let bytes = word.as_bytes();
                         array[..bytes.len()].copy_from_slice(bytes);
                         array
                     };
+                    ctx.prefix_postings_lists.prefix_postings_list(reader, prefix)?
+                } else {
+                    None
+                };
 
+                if let Some(result) = cached {
                     // We retrieve the cached postings lists for all
                     // the words that starts with this short prefix.
-                    let result = ctx.prefix_postings_lists.prefix_postings_list(reader, prefix)?.unwrap_or_default();
                     let key = PostingsKey { query, input: word.clone().into_bytes(), distance: 0, is_exact: false };
                     postings.insert(key, result.matches);
                     let prefix_docids = &result.docids;
@@ -430,20 +623,28 @@ pub fn traverse_query_tree<'o, 'txn>(
                     Cow::Owned(docids)
 
                 } else {
-                    let dfa = if *prefix { build_prefix_dfa(word) } else { build_dfa(word) };
+                    let typos = ctx.typo_tolerance.allowed_typos(word);
+                    let dfa = if prefix { build_prefix_dfa(word, typos) } else { build_dfa(word, typos) };
 
                     let byte = word.as_bytes()[0];
-                    let mut stream = if byte == u8::max_value() {
-                        ctx.words_set.search(&dfa).ge(&[byte]).into_stream()
-                    } else {
-                        ctx.words_set.search(&dfa).ge(&[byte]).lt(&[byte + 1]).into_stream()
-                    };
+                    let mut stream = search_words(ctx, &dfa, byte);
 
                     let before = Instant::now();
                     let mut results = Vec::new();
                     while let Some(input) = stream.next() {
                         if let Some(result) = ctx.postings_lists.postings_list(reader, input)? {
                             let distance = dfa.eval(input).to_u8();
+
+                            // the words with typos must not match in the typo disabled attributes
+                            let result = if distance != 0 && !ctx.typo_disabled_attributes.is_empty() {
+                                match remove_attributes_matches(result, &ctx.typo_disabled_attributes) {
+                                    Some(result) => result,
+                                    None => continue,
+                                }
+                            } else {
+                                result
+                            };
+
                             let is_exact = *exact && distance == 0 && input.len() == word.len();
                             results.push(result.docids);
                             let key = PostingsKey { query, input: input.to_owned(), distance, is_exact };
@@ -474,17 +675,13 @@ pub fn traverse_query_tree<'o, 'txn>(
                 let dfa = build_exact_dfa(word);
 
                 let byte = word.as_bytes()[0];
-                let mut stream = if byte == u8::max_value() {
-                    ctx.words_set.search(&dfa).ge(&[byte]).into_stream()
-                } else {
-                    ctx.words_set.search(&dfa).ge(&[byte]).lt(&[byte + 1]).into_stream()
-                };
+                let mut stream = search_words(ctx, &dfa, byte);
 
                 let before = Instant::now();
                 let mut results = Vec::new();
                 while let Some(input) = stream.next() {
                     if let Some(result) = ctx.postings_lists.postings_list(reader, input)? {
-                        let distance = dfa.eval(input).to_u8();
+                        let distance = dfa.eval(input).to_u8() + penalty;
                         results.push(result.docids);
                         let key = PostingsKey { query, input: input.to_owned(), distance, is_exact: *exact };
                         postings.insert(key, result.matches);
@@ -510,20 +707,39 @@ pub fn traverse_query_tree<'o, 'txn>(
             },
             QueryKind::Phrase(words) => {
                 // TODO support prefix and non-prefix exact DFA
-                if let [first, second] = words.as_slice() {
-                    let first = ctx.postings_lists.postings_list(reader, first.as_bytes())?.unwrap_or_default();
-                    let second = ctx.postings_lists.postings_list(reader, second.as_bytes())?.unwrap_or_default();
+                // the stop words are not indexed but they still take a position,
+                // unless they are indexed to be searched in the phrases
+                let mut postings_lists = Vec::with_capacity(words.len());
+                let mut gap = 0;
+                for word in words {
+                    if !ctx.stop_words_in_phrases && ctx.stop_words.contains(word) {
+                        gap += 1;
+                    } else {
+                        let postings_list = ctx.postings_lists.postings_list(reader, word.as_bytes())?.unwrap_or_default();
+                        postings_lists.push((gap, postings_list));
+                        gap = 0;
+                    }
+                }
 
-                    let iter = merge_join_by(first.matches.as_slice(), second.matches.as_slice(), |a, b| {
-                        let x = (a.document_id, a.attribute, (a.word_index as u32) + 1);
-                        let y = (b.document_id, b.attribute, b.word_index as u32);
-                        x.cmp(&y)
-                    });
+                let mut postings_lists = postings_lists.into_iter();
+                if let Some((_, first)) = postings_lists.next() {
+                    // each chain is a sequence of matches of the words at consecutive positions
+                    let mut chains: Vec<Vec<DocIndex>> = first.matches.iter().map(|m| vec![*m]).collect();
+                    for (gap, postings_list) in postings_lists {
+                        let iter = merge_join_by(chains, postings_list.matches.iter(), |chain, b| {
+                            let a = chain[chain.len() - 1];
+                            let x = (a.document_id, a.attribute, (a.word_index as u32) + 1 + gap);
+                            let y = (b.document_id, b.attribute, b.word_index as u32);
+                            x.cmp(&y)
+                        });
+
+                        chains = iter
+                            .filter_map(EitherOrBoth::both)
+                            .map(|(mut chain, b)| { chain.push(*b); chain })
+                            .collect();
+                    }
 
-                    let matches: Vec<_> = iter
-                        .filter_map(EitherOrBoth::both)
-                        .flat_map(|(a, b)| once(*a).chain(Some(*b)))
-                        .collect();
+                    let matches: Vec<_> = chains.into_iter().flatten().collect();
 
                     let before = Instant::now();
                     let mut docids: Vec<_> = matches.iter().map(|m| m.document_id).collect();
@@ -532,7 +748,7 @@ pub fn traverse_query_tree<'o, 'txn>(
                     debug!("{:2$}docids construction took {:.02?}", "", before.elapsed(), depth * 2);
 
                     let matches = Cow::Owned(SetBuf::from_dirty(matches));
-                    let key = PostingsKey { query, input: vec![], distance: 0, is_exact: true };
+                    let key = PostingsKey { query, input: vec![], distance: penalty, is_exact: *exact };
                     postings.insert(key, matches);
 
                     Cow::Owned(docids)
@@ -31,6 +31,11 @@ impl RankedMap {
         self.0.get(&(document, field)).cloned()
     }
 
+    /// The numbers of the documents for the given field.
+    pub fn field_numbers(&self, field: FieldId) -> impl Iterator<Item = (DocumentId, Number)> + '_ {
+        self.0.iter().filter(move |((_, f), _)| *f == field).map(|((document, _), number)| (*document, *number))
+    }
+
     pub fn read_from_bin<R: Read>(reader: R) -> bincode::Result<RankedMap> {
         bincode::deserialize_from(reader).map(RankedMap)
     }
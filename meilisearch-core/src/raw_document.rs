@@ -48,4 +48,15 @@ impl<'a, 'tag> RawDocument<'a, 'tag> {
             contains_one_word_field: false,
         }
     }
+
+    /// A document matching none of the query words, returned by a placeholder search.
+    pub fn without_matches(id: crate::DocumentId) -> RawDocument<'a, 'tag> {
+        RawDocument {
+            id,
+            bare_matches: &mut [],
+            processed_matches: Vec::new(),
+            processed_distances: Vec::new(),
+            contains_one_word_field: false,
+        }
+    }
 }
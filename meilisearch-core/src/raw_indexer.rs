@@ -1,21 +1,38 @@
 use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 
+use crate::normalization::{fold_diacritics, normalize_word};
+use crate::settings::{TokenMode, TokenPolicy, DEFAULT_FOLD_DIACRITICS, DEFAULT_MAX_POSITIONS_PER_ATTRIBUTE, DEFAULT_MAX_TOKEN_LENGTH};
 use crate::{DocIndex, DocumentId};
-use deunicode::deunicode_with_tofu;
 use meilisearch_schema::IndexedPos;
-use meilisearch_tokenizer::{is_cjk, SeqTokenizer, Token, Tokenizer};
+use meilisearch_tokenizer::{group_compounds, Token, TokenGroup};
 use sdset::SetBuf;
-
-const WORD_LENGTH_LIMIT: usize = 80;
+use serde::Deserialize;
 
 type Word = Vec<u8>; // TODO make it be a SmallVec
 
 pub struct RawIndexer {
     word_limit: usize, // the maximum number of indexed words
+    word_length_limit: usize, // the maximum length, in bytes, of an indexed word
+    fold_diacritics: bool, // whether the words are also indexed without their diacritics
+    token_policy: TokenPolicy, // how the numbers and the identifiers are tokenized
     stop_words: fst::Set,
     words_doc_indexes: BTreeMap<Word, Vec<DocIndex>>,
     docs_words: HashMap<DocumentId, Vec<Word>>,
+    truncated_tokens: usize,
+    truncated_attributes: usize,
+}
+
+/// A token of a field analyzed outside of the engine.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PreToken {
+    pub token: String,
+    pub position: usize,
+    /// The byte offsets of the token in the original text, they are
+    /// returned as they are in the matches of the search results.
+    pub start: usize,
+    pub end: usize,
 }
 
 pub struct Indexed {
@@ -25,35 +42,82 @@ pub struct Indexed {
 
 impl RawIndexer {
     pub fn new(stop_words: fst::Set) -> RawIndexer {
-        RawIndexer::with_word_limit(stop_words, 1000)
+        RawIndexer::with_limits(stop_words, DEFAULT_MAX_POSITIONS_PER_ATTRIBUTE, DEFAULT_MAX_TOKEN_LENGTH)
     }
 
     pub fn with_word_limit(stop_words: fst::Set, limit: usize) -> RawIndexer {
+        RawIndexer::with_limits(stop_words, limit, DEFAULT_MAX_TOKEN_LENGTH)
+    }
+
+    /// Creates an indexer that only indexes the first `word_limit` positions of each attribute
+    /// and truncates the words longer than `word_length_limit` bytes.
+    pub fn with_limits(stop_words: fst::Set, word_limit: usize, word_length_limit: usize) -> RawIndexer {
         RawIndexer {
-            word_limit: limit,
+            word_limit,
+            word_length_limit,
+            fold_diacritics: DEFAULT_FOLD_DIACRITICS,
+            token_policy: TokenPolicy::default(),
             stop_words,
             words_doc_indexes: BTreeMap::new(),
             docs_words: HashMap::new(),
+            truncated_tokens: 0,
+            truncated_attributes: 0,
+        }
+    }
+
+    /// Creates an empty indexer with the same stop words and limits, the indexers
+    /// of several chunks of documents can be filled in parallel then merged.
+    pub fn fork(&self) -> RawIndexer {
+        let stop_words = fst::Set::from_bytes(self.stop_words.as_fst().to_vec()).unwrap();
+        let mut indexer = RawIndexer::with_limits(stop_words, self.word_limit, self.word_length_limit);
+        indexer.fold_diacritics = self.fold_diacritics;
+        indexer.token_policy = self.token_policy.clone();
+        indexer
+    }
+
+    /// Defines if the words are also indexed without their diacritics.
+    pub fn set_fold_diacritics(&mut self, fold_diacritics: bool) {
+        self.fold_diacritics = fold_diacritics;
+    }
+
+    /// Defines how the compounds, like `3.5mm` or `ISO-9001`, are indexed.
+    pub fn set_token_policy(&mut self, token_policy: TokenPolicy) {
+        self.token_policy = token_policy;
+    }
+
+    /// Adds the words indexed by another indexer to this one.
+    pub fn merge(&mut self, other: RawIndexer) {
+        for (word, indexes) in other.words_doc_indexes {
+            self.words_doc_indexes.entry(word).or_insert_with(Vec::new).extend(indexes);
+        }
+        for (id, words) in other.docs_words {
+            self.docs_words.entry(id).or_insert_with(Vec::new).extend(words);
         }
+        self.truncated_tokens += other.truncated_tokens;
+        self.truncated_attributes += other.truncated_attributes;
+    }
+
+    /// The number of words that were truncated because they were too long.
+    pub fn truncated_tokens(&self) -> usize {
+        self.truncated_tokens
+    }
+
+    /// The number of attributes of which the last words were not indexed.
+    pub fn truncated_attributes(&self) -> usize {
+        self.truncated_attributes
     }
 
     pub fn index_text(&mut self, id: DocumentId, indexed_pos: IndexedPos, text: &str) -> usize {
         let mut number_of_words = 0;
 
-        for token in Tokenizer::new(text) {
-            let must_continue = index_token(
-                token,
-                id,
-                indexed_pos,
-                self.word_limit,
-                &self.stop_words,
-                &mut self.words_doc_indexes,
-                &mut self.docs_words,
-            );
+        for token in policy_tokens(&self.token_policy, text) {
+            let char_length = token.word.chars().count();
+            let must_continue = self.index_token(token, char_length, id, indexed_pos);
 
             number_of_words += 1;
 
             if !must_continue {
+                self.truncated_attributes += 1;
                 break;
             }
         }
@@ -65,22 +129,56 @@ impl RawIndexer {
     where
         I: IntoIterator<Item = &'a str>,
     {
-        let iter = iter.into_iter();
-        for token in SeqTokenizer::new(iter) {
-            let must_continue = index_token(
-                token,
-                id,
-                indexed_pos,
-                self.word_limit,
-                &self.stop_words,
-                &mut self.words_doc_indexes,
-                &mut self.docs_words,
-            );
+        // the texts are separated by a hard separator, like in the sequence tokenizer
+        let hard_space = 8;
+        let mut word_offset = 0;
+        let mut char_offset = 0;
+
+        for text in iter {
+            let tokens = policy_tokens(&self.token_policy, text);
+            let last = tokens.last().map(|t| (t.word_index, t.char_index));
+
+            for token in tokens {
+                let token = Token {
+                    word: token.word,
+                    word_index: token.word_index + word_offset,
+                    char_index: token.char_index + char_offset,
+                };
+                let char_length = token.word.chars().count();
+                let must_continue = self.index_token(token, char_length, id, indexed_pos);
+
+                if !must_continue {
+                    self.truncated_attributes += 1;
+                    return;
+                }
+            }
+
+            if let Some((word_index, char_index)) = last {
+                word_offset += word_index + hard_space;
+                char_offset += char_index + hard_space;
+            }
+        }
+    }
+
+    /// Indexes the tokens of a pre-tokenized field at their own positions, the tokens are
+    /// only normalized like the query words are, they must be ordered by position.
+    pub fn index_pre_tokenized(&mut self, id: DocumentId, indexed_pos: IndexedPos, tokens: &[PreToken]) -> usize {
+        let mut number_of_words = 0;
+
+        for pre_token in tokens {
+            let token = Token { word: &pre_token.token, word_index: pre_token.position, char_index: pre_token.start };
+            let length = pre_token.end.saturating_sub(pre_token.start);
+            let must_continue = self.index_token(token, length, id, indexed_pos);
+
+            number_of_words += 1;
 
             if !must_continue {
+                self.truncated_attributes += 1;
                 break;
             }
         }
+
+        number_of_words
     }
 
     pub fn build(self) -> Indexed {
@@ -105,65 +203,106 @@ impl RawIndexer {
             docs_words,
         }
     }
-}
 
-fn index_token(
-    token: Token,
-    id: DocumentId,
-    indexed_pos: IndexedPos,
-    word_limit: usize,
-    stop_words: &fst::Set,
-    words_doc_indexes: &mut BTreeMap<Word, Vec<DocIndex>>,
-    docs_words: &mut HashMap<DocumentId, Vec<Word>>,
-) -> bool {
-    if token.word_index >= word_limit {
-        return false;
-    }
+    fn index_token(&mut self, token: Token, char_length: usize, id: DocumentId, indexed_pos: IndexedPos) -> bool {
+        if token.word_index >= self.word_limit {
+            return false;
+        }
 
-    let lower = token.word.to_lowercase();
-    let token = Token {
-        word: &lower,
-        ..token
-    };
+        // the positions are computed on the original word to be highlighted in the original text
+        let normalized = normalize_word(token.word);
 
-    if !stop_words.contains(&token.word) {
-        match token_to_docindex(id, indexed_pos, token) {
-            Some(docindex) => {
-                let word = Vec::from(token.word);
-
-                if word.len() <= WORD_LENGTH_LIMIT {
-                    words_doc_indexes
-                        .entry(word.clone())
-                        .or_insert_with(Vec::new)
-                        .push(docindex);
-                    docs_words.entry(id).or_insert_with(Vec::new).push(word);
-
-                    if !lower.contains(is_cjk) {
-                        let unidecoded = deunicode_with_tofu(&lower, "");
-                        if unidecoded != lower && !unidecoded.is_empty() {
-                            let word = Vec::from(unidecoded);
-                            if word.len() <= WORD_LENGTH_LIMIT {
-                                words_doc_indexes
-                                    .entry(word.clone())
-                                    .or_insert_with(Vec::new)
-                                    .push(docindex);
-                                docs_words.entry(id).or_insert_with(Vec::new).push(word);
-                            }
+        if !self.stop_words.contains(&normalized) {
+            match token_to_docindex(id, indexed_pos, token, char_length) {
+                Some(docindex) => {
+                    let word = truncate_word(&normalized, self.word_length_limit);
+                    if word.len() != normalized.len() {
+                        self.truncated_tokens += 1;
+                    }
+                    self.insert_word(id, docindex, word);
+
+                    if self.fold_diacritics {
+                        if let Some(folded) = fold_diacritics(&normalized) {
+                            let word = truncate_word(&folded, self.word_length_limit);
+                            self.insert_word(id, docindex, word);
                         }
                     }
                 }
+                None => return false,
             }
-            None => return false,
+        }
+
+        true
+    }
+
+    fn insert_word(&mut self, id: DocumentId, docindex: DocIndex, word: &str) {
+        let word = Vec::from(word);
+        self.words_doc_indexes
+            .entry(word.clone())
+            .or_insert_with(Vec::new)
+            .push(docindex);
+        self.docs_words.entry(id).or_insert_with(Vec::new).push(word);
+    }
+}
+
+/// Tokenizes the text and applies the token policy to its compounds. The words of a kept
+/// compound take consecutive positions and the following tokens are moved back accordingly,
+/// the compounds are tokenized the same way at search time.
+fn policy_tokens<'a>(policy: &TokenPolicy, text: &'a str) -> Vec<Token<'a>> {
+    let mut tokens = Vec::new();
+    // the number of positions saved by the kept compounds
+    let mut shift = 0;
+
+    for group in group_compounds(text) {
+        match group {
+            TokenGroup::Token(token) => {
+                tokens.push(Token { word_index: token.word_index - shift, ..token });
+            },
+            TokenGroup::Compound(compound) => {
+                let first = compound.tokens[0];
+                let last = compound.tokens[compound.tokens.len() - 1];
+                let position = first.word_index - shift;
+
+                match policy.mode(&compound) {
+                    TokenMode::Split => {
+                        let parts = compound.tokens.iter().map(|t| Token { word_index: t.word_index - shift, ..*t });
+                        tokens.extend(parts);
+                    },
+                    TokenMode::Keep => {
+                        tokens.push(Token { word: compound.word, word_index: position, char_index: first.char_index });
+                        shift += last.word_index - first.word_index;
+                    },
+                    TokenMode::KeepAndSplit => {
+                        tokens.push(Token { word: compound.word, word_index: position, char_index: first.char_index });
+                        let parts = compound.tokens.iter().enumerate().map(|(i, t)| Token { word_index: position + i, ..*t });
+                        tokens.extend(parts);
+                        shift += last.word_index - first.word_index - (compound.tokens.len() - 1);
+                    },
+                }
+            },
         }
     }
 
-    true
+    tokens
 }
 
-fn token_to_docindex(id: DocumentId, indexed_pos: IndexedPos, token: Token) -> Option<DocIndex> {
+/// Truncates the word to the given length in bytes, without splitting a character.
+fn truncate_word(word: &str, max_length: usize) -> &str {
+    if word.len() <= max_length {
+        return word;
+    }
+
+    let mut end = max_length;
+    while !word.is_char_boundary(end) {
+        end -= 1;
+    }
+    &word[..end]
+}
+
+fn token_to_docindex(id: DocumentId, indexed_pos: IndexedPos, token: Token, char_length: usize) -> Option<DocIndex> {
     let word_index = u16::try_from(token.word_index).ok()?;
     let char_index = u16::try_from(token.char_index).ok()?;
-    let char_length = u16::try_from(token.word.chars().count()).ok()?;
+    let char_length = u16::try_from(char_length).ok()?;
 
     let docindex = DocIndex {
         document_id: id,
@@ -269,4 +408,154 @@ mod tests {
             .get(&"🇯🇵".to_owned().into_bytes())
             .is_some());
     }
+
+    #[test]
+    fn long_words_are_truncated() {
+        let mut indexer = RawIndexer::with_limits(fst::Set::default(), 1000, 5);
+
+        let docid = DocumentId(0);
+        let indexed_pos = IndexedPos(0);
+        let text = "abracadabra éléphant";
+        indexer.index_text(docid, indexed_pos, text);
+        assert_eq!(indexer.truncated_tokens(), 2);
+
+        let Indexed {
+            words_doc_indexes, ..
+        } = indexer.build();
+
+        assert!(words_doc_indexes.get(&b"abrac"[..]).is_some());
+        assert!(words_doc_indexes.get(&b"abracadabra"[..]).is_none());
+        // "é" is two bytes long and can't be cut
+        assert!(words_doc_indexes.get(&"élé".to_owned().into_bytes()).is_some());
+        assert!(words_doc_indexes.get(&b"eleph"[..]).is_some());
+    }
+
+    #[test]
+    fn positions_are_limited() {
+        let mut indexer = RawIndexer::with_limits(fst::Set::default(), 2, 80);
+
+        let docid = DocumentId(0);
+        indexer.index_text(docid, IndexedPos(0), "one two three");
+        indexer.index_text(docid, IndexedPos(1), "four five");
+        assert_eq!(indexer.truncated_attributes(), 1);
+
+        let Indexed {
+            words_doc_indexes, ..
+        } = indexer.build();
+
+        assert!(words_doc_indexes.get(&b"two"[..]).is_some());
+        assert!(words_doc_indexes.get(&b"three"[..]).is_none());
+        assert!(words_doc_indexes.get(&b"five"[..]).is_some());
+    }
+
+    #[test]
+    fn forked_indexers_are_merged() {
+        let stop_words = fst::Set::from_iter(vec!["the"]).unwrap();
+        let mut indexer = RawIndexer::with_limits(stop_words, 1000, 5);
+
+        let mut first = indexer.fork();
+        first.index_text(DocumentId(0), IndexedPos(0), "the abracadabra cat");
+        let mut second = indexer.fork();
+        second.index_text(DocumentId(1), IndexedPos(0), "the cat");
+
+        indexer.merge(first);
+        indexer.merge(second);
+        assert_eq!(indexer.truncated_tokens(), 1);
+
+        let Indexed {
+            words_doc_indexes,
+            docs_words,
+        } = indexer.build();
+
+        assert!(words_doc_indexes.get(&b"the"[..]).is_none());
+        assert!(words_doc_indexes.get(&b"abrac"[..]).is_some());
+        assert_eq!(words_doc_indexes.get(&b"cat"[..]).unwrap().len(), 2);
+        assert_eq!(docs_words.len(), 2);
+    }
+
+    #[test]
+    fn pre_tokenized_field() {
+        let stop_words = fst::Set::from_iter(vec!["the"]).unwrap();
+        let mut indexer = RawIndexer::with_limits(stop_words, 3, 80);
+
+        let token = |token: &str, position, start, end| PreToken { token: token.to_string(), position, start, end };
+        let tokens = vec![
+            token("The", 0, 0, 3),
+            token("ST-elevation", 1, 4, 16),
+            // a stem keeps the offsets of the whole word: "infarction"
+            token("infarct", 2, 17, 27),
+            token("acute", 7, 28, 33),
+        ];
+        let number_of_words = indexer.index_pre_tokenized(DocumentId(0), IndexedPos(0), &tokens);
+        assert_eq!(number_of_words, 4);
+        assert_eq!(indexer.truncated_attributes(), 1);
+
+        let Indexed { words_doc_indexes, .. } = indexer.build();
+
+        assert!(words_doc_indexes.get(&b"the"[..]).is_none());
+        assert!(words_doc_indexes.get(&b"acute"[..]).is_none());
+        let matches = words_doc_indexes.get(&b"infarct"[..]).unwrap();
+        assert_eq!((matches[0].word_index, matches[0].char_index, matches[0].char_length), (2, 17, 10));
+        // the tokens are not split by the engine tokenizer
+        assert!(words_doc_indexes.get(&b"st-elevation"[..]).is_some());
+        assert!(words_doc_indexes.get(&b"elevation"[..]).is_none());
+    }
+
+    #[test]
+    fn compounds_follow_the_token_policy() {
+        let text = "ISO-9001 and 3.5mm jacks";
+        let positions = |indexed: &Indexed, word: &str| -> Vec<u16> {
+            indexed.words_doc_indexes.get(word.as_bytes()).map_or(Vec::new(), |m| m.iter().map(|m| m.word_index).collect())
+        };
+
+        // the compounds are split like the rest of the text by default
+        let mut indexer = RawIndexer::new(fst::Set::default());
+        indexer.index_text(DocumentId(0), IndexedPos(0), text);
+        let indexed = indexer.build();
+        assert_eq!(positions(&indexed, "iso-9001"), Vec::<u16>::new());
+        assert_eq!(positions(&indexed, "9001"), vec![1]);
+        assert_eq!(positions(&indexed, "5mm"), vec![11]);
+        assert_eq!(positions(&indexed, "jacks"), vec![12]);
+
+        let mut indexer = RawIndexer::new(fst::Set::default());
+        indexer.set_token_policy(TokenPolicy { numbers: TokenMode::Split, identifiers: TokenMode::Keep });
+        indexer.index_text(DocumentId(0), IndexedPos(0), text);
+        let indexed = indexer.build();
+        assert_eq!(positions(&indexed, "iso-9001"), vec![0]);
+        assert_eq!(positions(&indexed, "iso"), Vec::<u16>::new());
+        assert_eq!(positions(&indexed, "and"), vec![1]);
+        assert_eq!(positions(&indexed, "3.5mm"), vec![2]);
+        assert_eq!(positions(&indexed, "jacks"), vec![3]);
+
+        let mut indexer = RawIndexer::new(fst::Set::default());
+        indexer.set_token_policy(TokenPolicy { numbers: TokenMode::Split, identifiers: TokenMode::KeepAndSplit });
+        indexer.index_text_seq(DocumentId(0), IndexedPos(0), vec!["a 3.5mm jack", "ISO-9001"]);
+        let indexed = indexer.build();
+        assert_eq!(positions(&indexed, "3.5mm"), vec![1]);
+        assert_eq!(positions(&indexed, "3"), vec![1]);
+        assert_eq!(positions(&indexed, "5mm"), vec![2]);
+        assert_eq!(positions(&indexed, "jack"), vec![3]);
+        // the texts of a sequence are separated by a hard separator
+        assert_eq!(positions(&indexed, "iso-9001"), vec![11]);
+        assert_eq!(positions(&indexed, "9001"), vec![12]);
+
+        let matches = indexed.words_doc_indexes.get(&b"3.5mm"[..]).unwrap();
+        assert_eq!((matches[0].char_index, matches[0].char_length), (2, 5));
+    }
+
+    #[test]
+    fn normalized_words() {
+        let mut indexer = RawIndexer::new(fst::Set::default());
+        indexer.set_fold_diacritics(false);
+
+        // the é is decomposed in a e followed by a combining acute accent
+        indexer.index_text(DocumentId(0), IndexedPos(0), "Re\u{301}sume\u{301} \u{fb01}nal");
+
+        let Indexed { words_doc_indexes, .. } = indexer.build();
+
+        let matches = words_doc_indexes.get(&"résumé".to_owned().into_bytes()).unwrap();
+        assert_eq!(matches[0].char_length, 8);
+        assert!(words_doc_indexes.get(&b"final"[..]).is_some());
+        assert!(words_doc_indexes.get(&b"resume"[..]).is_none());
+    }
 }
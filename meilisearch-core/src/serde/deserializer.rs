@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::collections::HashSet;
 use std::io::Cursor;
 use std::{error::Error, fmt};
@@ -55,6 +56,8 @@ pub struct Deserializer<'a> {
     pub documents_fields: DocumentsFields,
     pub schema: &'a Schema,
     pub fields: Option<&'a HashSet<FieldId>>,
+    /// The fields that are not displayed are skipped, unless the document is read internally.
+    pub displayed_only: bool,
 }
 
 impl<'de, 'a, 'b> de::Deserializer<'de> for &'b mut Deserializer<'a> {
@@ -80,9 +83,24 @@ impl<'de, 'a, 'b> de::Deserializer<'de> for &'b mut Deserializer<'a> {
     {
         let mut error = None;
 
-        let iter = self
-            .documents_fields
-            .document_fields(self.reader, self.document_id)?
+        let fields: Box<dyn Iterator<Item = heed::Result<(FieldId, Cow<'a, [u8]>)>> + 'a> = match self.fields {
+            // only the requested fields are read, the others are never copied nor parsed
+            Some(fields) => {
+                let mut fields: Vec<_> = fields.iter().copied().collect();
+                fields.sort_unstable();
+                let (reader, documents_fields, document_id) = (self.reader, self.documents_fields, self.document_id);
+                Box::new(fields.into_iter().filter_map(move |field_id| {
+                    match documents_fields.document_attribute(reader, document_id, field_id) {
+                        Ok(Some(bytes)) => Some(Ok((field_id, bytes))),
+                        Ok(None) => None,
+                        Err(e) => Some(Err(e)),
+                    }
+                }))
+            }
+            None => Box::new(self.documents_fields.document_fields(self.reader, self.document_id)?),
+        };
+
+        let iter = fields
             .filter_map(|result| {
                 let (attr, value) = match result {
                     Ok(value) => value,
@@ -92,10 +110,10 @@ impl<'de, 'a, 'b> de::Deserializer<'de> for &'b mut Deserializer<'a> {
                     }
                 };
 
-                let is_displayed = self.schema.is_displayed(attr);
+                let is_displayed = !self.displayed_only || self.schema.is_displayed(attr);
                 if is_displayed && self.fields.map_or(true, |f| f.contains(&attr)) {
                     if let Some(attribute_name) = self.schema.name(attr) {
-                        let cursor = Cursor::new(value.to_owned());
+                        let cursor = Cursor::new(value.into_owned());
                         let ioread = SerdeJsonIoRead::new(cursor);
                         let value = Value(SerdeJsonDeserializer::new(ioread));
 
@@ -2,15 +2,78 @@ use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::str::FromStr;
 use std::iter::IntoIterator;
 
+use meilisearch_schema::{FieldFlags, FieldType};
+use meilisearch_tokenizer::Compound;
 use serde::{Deserialize, Deserializer, Serialize};
 use once_cell::sync::Lazy;
 
+use crate::normalization::fold_diacritics;
+use crate::query_rules::QueryRule;
+
 use self::RankingRule::*;
 
 pub const DEFAULT_RANKING_RULES: [RankingRule; 6] = [Typo, Words, Proximity, Attribute, WordsPosition, Exactness];
 
+/// The length, in bytes, after which the indexed words are truncated by default.
+pub const DEFAULT_MAX_TOKEN_LENGTH: usize = 80;
+
+/// The number of positions of an attribute that are indexed by default.
+pub const DEFAULT_MAX_POSITIONS_PER_ATTRIBUTE: usize = 1000;
+
+/// The words are used as database keys, which are limited to 511 bytes.
+pub const MAX_TOKEN_LENGTH: usize = 511;
+
+/// The positions of the words are stored on 16 bits.
+pub const MAX_POSITIONS_PER_ATTRIBUTE: usize = u16::MAX as usize + 1;
+
+/// The number of hits that can be reached by paginating the results by default.
+pub const DEFAULT_MAX_TOTAL_HITS: usize = 1000;
+
+/// The words are indexed with and without their diacritics by default.
+pub const DEFAULT_FOLD_DIACRITICS: bool = true;
+
+/// The stop words are neither indexed nor searched by default, even in the phrases.
+pub const DEFAULT_STOP_WORDS_IN_PHRASES: bool = false;
+
+/// The documents fields are stored as raw JSON by default.
+pub const DEFAULT_COMPRESS_DOCUMENTS: bool = false;
+
+/// The maximum length of a language code.
+pub const MAX_LANGUAGE_LENGTH: usize = 35;
+
+/// The synonyms of each language, indexed by language code.
+pub type LocalizedSynonyms = BTreeMap<String, BTreeMap<String, Vec<String>>>;
+
+/// Returns `true` if the string is a valid language code, like `en` or `pt-BR`.
+pub fn is_valid_language(language: &str) -> bool {
+    !language.is_empty()
+        && language.len() <= MAX_LANGUAGE_LENGTH
+        && language.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Expands groups of mutual synonyms into one-way synonyms, where every word
+/// of a group has the other words of the group as alternatives.
+pub fn mutual_synonyms<I>(groups: I) -> BTreeMap<String, Vec<String>>
+where
+    I: IntoIterator<Item = Vec<String>>,
+{
+    let mut synonyms: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for group in groups {
+        for word in &group {
+            let alternatives = group.iter().filter(|w| *w != word).cloned();
+            synonyms.entry(word.clone()).or_default().extend(alternatives);
+        }
+    }
+
+    synonyms
+        .into_iter()
+        .filter(|(_, alternatives)| !alternatives.is_empty())
+        .map(|(word, alternatives)| (word, alternatives.into_iter().collect()))
+        .collect()
+}
+
 static RANKING_RULE_REGEX: Lazy<regex::Regex> = Lazy::new(|| {
-    let regex = regex::Regex::new(r"(asc|desc)\(([a-zA-Z0-9-_]*)\)").unwrap();
+    let regex = regex::Regex::new(r"(asc|desc)\(([a-zA-Z0-9-_]*|[a-zA-Z0-9-_]+(?:\.[a-zA-Z0-9-_]+)+)\)").unwrap();
     regex
 });
 
@@ -33,6 +96,30 @@ pub struct Settings {
     pub accept_new_fields: Option<Option<bool>>,
     #[serde(default, deserialize_with = "deserialize_some")]
     pub attributes_for_faceting: Option<Option<Vec<String>>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub sortable_attributes: Option<Option<Vec<String>>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub document_id_format: Option<Option<DocumentIdFormat>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub max_token_length: Option<Option<usize>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub max_positions_per_attribute: Option<Option<usize>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub max_total_hits: Option<Option<usize>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub search_cutoff_ms: Option<Option<u64>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub typo_tolerance: Option<Option<TypoTolerance>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub prefix_search: Option<Option<PrefixSearch>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub field_types: Option<Option<BTreeMap<String, FieldType>>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub field_flags: Option<Option<BTreeMap<String, FieldFlags>>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub facet_normalization: Option<Option<FacetNormalization>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub token_policy: Option<Option<TokenPolicy>>,
 }
 
 // Any value that is present is considered Some value, including null.
@@ -63,6 +150,24 @@ impl Settings {
             synonyms: settings.synonyms.into(),
             accept_new_fields: settings.accept_new_fields.into(),
             attributes_for_faceting: settings.attributes_for_faceting.into(),
+            sortable_attributes: settings.sortable_attributes.into(),
+            document_id_format: settings.document_id_format.into(),
+            language: UpdateState::Nothing,
+            localized_synonyms: UpdateState::Nothing,
+            max_token_length: settings.max_token_length.into(),
+            max_positions_per_attribute: settings.max_positions_per_attribute.into(),
+            max_total_hits: settings.max_total_hits.into(),
+            search_cutoff_ms: settings.search_cutoff_ms.into(),
+            typo_tolerance: settings.typo_tolerance.into(),
+            prefix_search: settings.prefix_search.into(),
+            field_types: settings.field_types.into(),
+            field_flags: settings.field_flags.into(),
+            facet_normalization: settings.facet_normalization.into(),
+            token_policy: settings.token_policy.into(),
+            fold_diacritics: UpdateState::Nothing,
+            stop_words_in_phrases: UpdateState::Nothing,
+            compress_documents: UpdateState::Nothing,
+            query_rules: UpdateState::Nothing,
         })
     }
 }
@@ -74,6 +179,12 @@ pub enum UpdateState<T> {
     Nothing,
 }
 
+impl<T> Default for UpdateState<T> {
+    fn default() -> UpdateState<T> {
+        UpdateState::Nothing
+    }
+}
+
 impl <T> From<Option<Option<T>>> for UpdateState<T> {
     fn from(opt: Option<Option<T>>) -> UpdateState<T> {
         match opt {
@@ -93,7 +204,7 @@ impl std::fmt::Display for RankingRuleConversionError {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RankingRule {
     Typo,
     Words,
@@ -103,6 +214,8 @@ pub enum RankingRule {
     Exactness,
     Asc(String),
     Desc(String),
+    /// Sorts the documents by their distance to the point given with the search.
+    GeoAsc,
 }
 
 impl std::fmt::Display for RankingRule {
@@ -116,6 +229,7 @@ impl std::fmt::Display for RankingRule {
             RankingRule::Exactness => f.write_str("exactness"),
             RankingRule::Asc(field) => write!(f, "asc({})", field),
             RankingRule::Desc(field) => write!(f, "desc({})", field),
+            RankingRule::GeoAsc => f.write_str("geo:asc"),
         }
     }
 }
@@ -131,6 +245,7 @@ impl FromStr for RankingRule {
             "attribute" => RankingRule::Attribute,
             "wordsPosition" => RankingRule::WordsPosition,
             "exactness" => RankingRule::Exactness,
+            "geo:asc" => RankingRule::GeoAsc,
             _ => {
                 let captures = RANKING_RULE_REGEX.captures(s).ok_or(RankingRuleConversionError)?;
                 match (captures.get(1).map(|m| m.as_str()), captures.get(2)) {
@@ -159,6 +274,214 @@ impl RankingRule {
     }
 }
 
+/// The number of typos allowed for the words of a query, and where typos are not allowed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct TypoTolerance {
+    pub enabled: bool,
+    /// The number of characters from which a word accepts one typo.
+    pub min_word_size_for_one_typo: usize,
+    /// The number of characters from which a word accepts two typos.
+    pub min_word_size_for_two_typos: usize,
+    /// The words that must be matched exactly, without typos and not as prefixes, e.g. SKUs or part numbers.
+    pub disable_on_words: BTreeSet<String>,
+    /// The attributes in which the words must be matched exactly.
+    pub disable_on_attributes: BTreeSet<String>,
+}
+
+impl Default for TypoTolerance {
+    fn default() -> TypoTolerance {
+        TypoTolerance {
+            enabled: true,
+            min_word_size_for_one_typo: 5,
+            min_word_size_for_two_typos: 9,
+            disable_on_words: BTreeSet::new(),
+            disable_on_attributes: BTreeSet::new(),
+        }
+    }
+}
+
+impl TypoTolerance {
+    /// Returns `true` if this lowercased query word must be matched exactly.
+    pub fn is_exact_word(&self, word: &str) -> bool {
+        self.disable_on_words.iter().any(|w| w.to_lowercase() == word)
+    }
+
+    /// The number of typos allowed for this lowercased query word.
+    pub fn allowed_typos(&self, word: &str) -> u8 {
+        if !self.enabled || self.is_exact_word(word) {
+            return 0;
+        }
+
+        let size = word.chars().count();
+        if size >= self.min_word_size_for_two_typos {
+            2
+        } else if size >= self.min_word_size_for_one_typo {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+/// The prefixes are cached under keys of four bytes.
+pub const MAX_PREFIX_LENGTH: usize = 4;
+
+/// How the last word of a query is searched as a prefix, and which prefixes are precomputed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct PrefixSearch {
+    /// Whether the last word of a query matches the words it is a prefix of.
+    pub enabled: bool,
+    /// The length, in bytes, up to which the prefixes postings lists are precomputed.
+    pub max_prefix_length: usize,
+    /// The number of documents from which the postings list of a prefix is precomputed.
+    pub min_prefix_frequency: usize,
+}
+
+impl Default for PrefixSearch {
+    fn default() -> PrefixSearch {
+        PrefixSearch {
+            enabled: true,
+            max_prefix_length: 2,
+            min_prefix_frequency: 0,
+        }
+    }
+}
+
+impl PrefixSearch {
+    /// The length of the longest precomputed prefixes, bounded by the size of the cache keys.
+    pub fn cached_prefix_length(&self) -> usize {
+        self.max_prefix_length.min(MAX_PREFIX_LENGTH)
+    }
+}
+
+/// How the facet values are normalized before being indexed and compared to the facet filters,
+/// the original values are still returned in the facets distribution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct FacetNormalization {
+    pub lowercase: bool,
+    /// Removes the whitespaces around the values.
+    pub trim: bool,
+    pub strip_diacritics: bool,
+}
+
+impl Default for FacetNormalization {
+    fn default() -> FacetNormalization {
+        FacetNormalization {
+            lowercase: true,
+            trim: false,
+            strip_diacritics: false,
+        }
+    }
+}
+
+impl FacetNormalization {
+    pub fn normalize(&self, value: &str) -> String {
+        let value = if self.trim { value.trim() } else { value };
+        let folded = if self.strip_diacritics { fold_diacritics(value) } else { None };
+        let value = folded.unwrap_or_else(|| value.to_string());
+        if self.lowercase { value.to_lowercase() } else { value }
+    }
+}
+
+/// How the words of a compound, like `3.5mm` or `ISO-9001`, are indexed and searched.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TokenMode {
+    /// The compound is split on its separators, like the rest of the text.
+    Split,
+    /// The compound is indexed as a single word, e.g. `iso-9001`.
+    Keep,
+    /// The compound is indexed as a single word and its parts at consecutive positions.
+    KeepAndSplit,
+}
+
+/// How the compounds are tokenized, the numbers written with separators and the identifiers
+/// mixing digits and letters. The same policy is applied to the documents and to the queries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct TokenPolicy {
+    /// The numbers with decimal or thousands separators, e.g. `3.5` or `1,000`.
+    pub numbers: TokenMode,
+    /// The other compounds, e.g. `ISO-9001`, `3.5mm` or `2019-05`.
+    pub identifiers: TokenMode,
+}
+
+impl Default for TokenPolicy {
+    fn default() -> TokenPolicy {
+        TokenPolicy {
+            numbers: TokenMode::Split,
+            identifiers: TokenMode::Split,
+        }
+    }
+}
+
+impl TokenPolicy {
+    /// How this compound is indexed and searched, depending on whether it is a number.
+    pub fn mode(&self, compound: &Compound) -> TokenMode {
+        if compound.is_number() { self.numbers } else { self.identifiers }
+    }
+}
+
+/// The maximum length, in bytes, of a document id whatever its format.
+pub const MAX_DOCUMENT_ID_LENGTH: usize = 512;
+
+/// The format the documents ids of an index must follow.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DocumentIdFormat {
+    /// ASCII alphanumeric characters, hyphens and underscores.
+    Strict,
+    /// Hyphenated UUIDs, e.g. `123e4567-e89b-12d3-a456-426614174000`.
+    Uuid,
+    /// Email addresses.
+    Email,
+    /// Any non-empty UTF-8 string.
+    Any,
+}
+
+impl Default for DocumentIdFormat {
+    fn default() -> DocumentIdFormat {
+        DocumentIdFormat::Strict
+    }
+}
+
+impl DocumentIdFormat {
+    pub fn is_valid(self, id: &str) -> bool {
+        if id.is_empty() || id.len() > MAX_DOCUMENT_ID_LENGTH {
+            return false;
+        }
+
+        match self {
+            DocumentIdFormat::Strict => {
+                id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            },
+            DocumentIdFormat::Uuid => {
+                id.len() == 36 && id.char_indices().all(|(i, c)| match i {
+                    8 | 13 | 18 | 23 => c == '-',
+                    _ => c.is_ascii_hexdigit(),
+                })
+            },
+            DocumentIdFormat::Email => {
+                let mut parts = id.split('@');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(local), Some(domain), None) => {
+                        !local.is_empty()
+                            && !domain.is_empty()
+                            && !domain.starts_with('.')
+                            && !domain.ends_with('.')
+                            && !id.chars().any(|c| c.is_whitespace() || c.is_control())
+                    },
+                    _ => false,
+                }
+            },
+            DocumentIdFormat::Any => true,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SettingsUpdate {
     pub ranking_rules: UpdateState<Vec<RankingRule>>,
@@ -170,6 +493,50 @@ pub struct SettingsUpdate {
     pub synonyms: UpdateState<BTreeMap<String, Vec<String>>>,
     pub accept_new_fields: UpdateState<bool>,
     pub attributes_for_faceting: UpdateState<Vec<String>>,
+    #[serde(default)]
+    pub document_id_format: UpdateState<DocumentIdFormat>,
+    #[serde(default)]
+    pub language: UpdateState<String>,
+    /// Replaces the synonyms of the given languages, an empty set removes the language.
+    #[serde(default)]
+    pub localized_synonyms: UpdateState<LocalizedSynonyms>,
+    #[serde(default)]
+    pub max_token_length: UpdateState<usize>,
+    #[serde(default)]
+    pub max_positions_per_attribute: UpdateState<usize>,
+    #[serde(default)]
+    pub max_total_hits: UpdateState<usize>,
+    /// The default time budget of the searches, the best results found are returned once it is spent.
+    #[serde(default)]
+    pub search_cutoff_ms: UpdateState<u64>,
+    #[serde(default)]
+    pub sortable_attributes: UpdateState<Vec<String>>,
+    #[serde(default)]
+    pub typo_tolerance: UpdateState<TypoTolerance>,
+    #[serde(default)]
+    pub prefix_search: UpdateState<PrefixSearch>,
+    /// Replaces the declared types of the fields, the documents are reindexed.
+    #[serde(default)]
+    pub field_types: UpdateState<BTreeMap<String, FieldType>>,
+    /// Replaces the declared flags of the fields, they win over the settings listing the fields.
+    #[serde(default)]
+    pub field_flags: UpdateState<BTreeMap<String, FieldFlags>>,
+    #[serde(default)]
+    pub facet_normalization: UpdateState<FacetNormalization>,
+    /// Replaces how the numbers and the identifiers are tokenized, the documents are reindexed.
+    #[serde(default)]
+    pub token_policy: UpdateState<TokenPolicy>,
+    #[serde(default)]
+    pub fold_diacritics: UpdateState<bool>,
+    /// Indexes the stop words to search them in the phrases, they are still ignored in the rest of the queries.
+    #[serde(default)]
+    pub stop_words_in_phrases: UpdateState<bool>,
+    /// Compresses the stored documents fields, the fields already stored are rewritten.
+    #[serde(default)]
+    pub compress_documents: UpdateState<bool>,
+    /// Replaces the rules applied to the matching queries, nothing is reindexed.
+    #[serde(default)]
+    pub query_rules: UpdateState<Vec<QueryRule>>,
 }
 
 impl Default for SettingsUpdate {
@@ -184,6 +551,110 @@ impl Default for SettingsUpdate {
             synonyms: UpdateState::Nothing,
             accept_new_fields: UpdateState::Nothing,
             attributes_for_faceting: UpdateState::Nothing,
+            document_id_format: UpdateState::Nothing,
+            language: UpdateState::Nothing,
+            localized_synonyms: UpdateState::Nothing,
+            max_token_length: UpdateState::Nothing,
+            max_positions_per_attribute: UpdateState::Nothing,
+            max_total_hits: UpdateState::Nothing,
+            search_cutoff_ms: UpdateState::Nothing,
+            sortable_attributes: UpdateState::Nothing,
+            typo_tolerance: UpdateState::Nothing,
+            prefix_search: UpdateState::Nothing,
+            field_types: UpdateState::Nothing,
+            field_flags: UpdateState::Nothing,
+            facet_normalization: UpdateState::Nothing,
+            token_policy: UpdateState::Nothing,
+            fold_diacritics: UpdateState::Nothing,
+            stop_words_in_phrases: UpdateState::Nothing,
+            compress_documents: UpdateState::Nothing,
+            query_rules: UpdateState::Nothing,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document_id_formats() {
+        assert!(DocumentIdFormat::Strict.is_valid("product_12-b"));
+        assert!(!DocumentIdFormat::Strict.is_valid("jean@example.com"));
+        assert!(!DocumentIdFormat::Strict.is_valid(""));
+
+        assert!(DocumentIdFormat::Uuid.is_valid("123e4567-e89b-12d3-a456-426614174000"));
+        assert!(!DocumentIdFormat::Uuid.is_valid("123e4567e89b12d3a456426614174000"));
+        assert!(!DocumentIdFormat::Uuid.is_valid("123e4567-e89b-12d3-a456-42661417400z"));
+
+        assert!(DocumentIdFormat::Email.is_valid("jean@example.com"));
+        assert!(!DocumentIdFormat::Email.is_valid("jean@exa@mple.com"));
+        assert!(!DocumentIdFormat::Email.is_valid("jean @example.com"));
+        assert!(!DocumentIdFormat::Email.is_valid("@example.com"));
+
+        assert!(DocumentIdFormat::Any.is_valid("Les Misérables / 1862"));
+        assert!(!DocumentIdFormat::Any.is_valid(&"a".repeat(MAX_DOCUMENT_ID_LENGTH + 1)));
+    }
+
+    #[test]
+    fn language_codes() {
+        assert!(is_valid_language("en"));
+        assert!(is_valid_language("pt-BR"));
+        assert!(!is_valid_language(""));
+        assert!(!is_valid_language("en\0fr"));
+        assert!(!is_valid_language("en fr"));
+    }
+
+    #[test]
+    fn mutual_synonyms_groups() {
+        let groups = vec![
+            vec!["nyc".to_string(), "new york".to_string(), "new york city".to_string()],
+            vec!["nyc".to_string(), "big apple".to_string()],
+            vec!["alone".to_string()],
+        ];
+
+        let synonyms = mutual_synonyms(groups);
+
+        assert_eq!(synonyms["nyc"], vec!["big apple", "new york", "new york city"]);
+        assert_eq!(synonyms["new york"], vec!["new york city", "nyc"]);
+        assert_eq!(synonyms["big apple"], vec!["nyc"]);
+        assert!(!synonyms.contains_key("alone"));
+    }
+
+    #[test]
+    fn geo_ranking_rule() {
+        assert_eq!("geo:asc".parse::<RankingRule>().unwrap(), RankingRule::GeoAsc);
+        assert_eq!(RankingRule::GeoAsc.to_string(), "geo:asc");
+        assert!("geo:desc".parse::<RankingRule>().is_err());
+    }
+
+    #[test]
+    fn facet_values_normalization() {
+        let normalization = FacetNormalization::default();
+        assert_eq!(normalization.normalize(" Horror "), " horror ");
+
+        let normalization = FacetNormalization { trim: true, strip_diacritics: true, ..FacetNormalization::default() };
+        assert_eq!(normalization.normalize(" Comédie "), "comedie");
+
+        let normalization = FacetNormalization { lowercase: false, ..FacetNormalization::default() };
+        assert_eq!(normalization.normalize("Horror"), "Horror");
+    }
+
+    #[test]
+    fn typo_tolerance_allowed_typos() {
+        let typo_tolerance = TypoTolerance::default();
+        assert_eq!(typo_tolerance.allowed_typos("shoe"), 0);
+        assert_eq!(typo_tolerance.allowed_typos("sneaker"), 1);
+        assert_eq!(typo_tolerance.allowed_typos("sneakersss"), 2);
+        assert_eq!(typo_tolerance.allowed_typos("école"), 1);
+
+        let typo_tolerance = TypoTolerance {
+            disable_on_words: vec!["XB-1000".to_string()].into_iter().collect(),
+            ..TypoTolerance::default()
+        };
+        assert_eq!(typo_tolerance.allowed_typos("xb-1000"), 0);
+
+        let typo_tolerance = TypoTolerance { enabled: false, ..TypoTolerance::default() };
+        assert_eq!(typo_tolerance.allowed_typos("sneakersss"), 0);
+    }
+}
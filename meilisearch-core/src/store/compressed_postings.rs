@@ -0,0 +1,165 @@
+//! The compressed representation of the postings lists.
+//!
+//! The matches are grouped by document, each group stores the delta of its document id
+//! with the previous group followed by its matches, the attributes and the word indexes
+//! being delta encoded too. All the numbers are written as LEB128 varints.
+
+use std::convert::TryFrom;
+
+use sdset::{Set, SetBuf};
+use slice_group_by::GroupBy;
+
+use crate::{DocIndex, DocumentId};
+
+/// The first byte of a compressed postings list, the legacy lists start with
+/// their big endian number of documents and therefore always with a zero byte.
+pub const COMPRESSED_TAG: u8 = 1;
+
+pub fn encode(matches: &Set<DocIndex>) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(1 + matches.len() * 4);
+    buffer.push(COMPRESSED_TAG);
+
+    let groups_len = matches.linear_group_by_key(|m| m.document_id).count();
+    write_varint(&mut buffer, groups_len as u64);
+
+    let mut previous_docid = 0;
+    for group in matches.linear_group_by_key(|m| m.document_id) {
+        let docid = group[0].document_id.0;
+        write_varint(&mut buffer, docid - previous_docid);
+        write_varint(&mut buffer, group.len() as u64);
+        previous_docid = docid;
+
+        let mut previous: Option<&DocIndex> = None;
+        for m in group {
+            // the matches are sorted by attribute then by word index
+            let (attribute, word_index) = match previous {
+                Some(p) if p.attribute == m.attribute => (0, m.word_index - p.word_index),
+                Some(p) => (m.attribute - p.attribute, m.word_index),
+                None => (m.attribute, m.word_index),
+            };
+            write_varint(&mut buffer, u64::from(attribute));
+            write_varint(&mut buffer, u64::from(word_index));
+            write_varint(&mut buffer, u64::from(m.char_index));
+            write_varint(&mut buffer, u64::from(m.char_length));
+            previous = Some(m);
+        }
+    }
+
+    buffer
+}
+
+/// Decodes a compressed postings list, the tag byte included.
+pub fn decode(bytes: &[u8]) -> Option<(SetBuf<DocumentId>, SetBuf<DocIndex>)> {
+    let (&tag, mut bytes) = bytes.split_first()?;
+    if tag != COMPRESSED_TAG {
+        return None;
+    }
+
+    let groups_len = read_varint(&mut bytes)? as usize;
+    let mut docids = Vec::with_capacity(groups_len);
+    let mut matches = Vec::new();
+
+    let mut docid = 0;
+    for _ in 0..groups_len {
+        docid += read_varint(&mut bytes)?;
+        let document_id = DocumentId(docid);
+        docids.push(document_id);
+
+        let matches_len = read_varint(&mut bytes)? as usize;
+        let mut previous: Option<DocIndex> = None;
+        for _ in 0..matches_len {
+            let attribute = read_u16(&mut bytes)?;
+            let word_index = read_u16(&mut bytes)?;
+            let (attribute, word_index) = match previous {
+                Some(p) if attribute == 0 => (p.attribute, p.word_index.checked_add(word_index)?),
+                Some(p) => (p.attribute.checked_add(attribute)?, word_index),
+                None => (attribute, word_index),
+            };
+            let char_index = read_u16(&mut bytes)?;
+            let char_length = read_u16(&mut bytes)?;
+
+            let m = DocIndex { document_id, attribute, word_index, char_index, char_length };
+            matches.push(m);
+            previous = Some(m);
+        }
+    }
+
+    if !bytes.is_empty() {
+        return None;
+    }
+
+    Some((SetBuf::new_unchecked(docids), SetBuf::new_unchecked(matches)))
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn read_u16(bytes: &mut &[u8]) -> Option<u16> {
+    read_varint(bytes).and_then(|value| u16::try_from(value).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc_index(document_id: u64, attribute: u16, word_index: u16, char_index: u16) -> DocIndex {
+        DocIndex {
+            document_id: DocumentId(document_id),
+            attribute,
+            word_index,
+            char_index,
+            char_length: 5,
+        }
+    }
+
+    #[test]
+    fn encode_decode() {
+        let matches = SetBuf::from_dirty(vec![
+            doc_index(0, 0, 0, 0),
+            doc_index(0, 0, 3, 18),
+            doc_index(0, 2, 1, 6),
+            doc_index(42, 1, 0, 0),
+            doc_index(300_000, 0, 4, 22),
+            doc_index(300_000, 65_535, 65_535, 65_535),
+        ]);
+
+        let bytes = encode(&matches);
+        let (docids, decoded) = decode(&bytes).unwrap();
+
+        assert_eq!(docids.as_slice(), &[DocumentId(0), DocumentId(42), DocumentId(300_000)]);
+        assert_eq!(decoded.as_slice(), matches.as_slice());
+    }
+
+    #[test]
+    fn encode_decode_empty() {
+        let matches = SetBuf::<DocIndex>::new_unchecked(Vec::new());
+        let (docids, decoded) = decode(&encode(&matches)).unwrap();
+        assert!(docids.is_empty());
+        assert!(decoded.is_empty());
+    }
+
+    #[test]
+    fn reject_truncated_lists() {
+        let matches = SetBuf::from_dirty(vec![doc_index(7, 1, 2, 3), doc_index(9, 0, 0, 0)]);
+        let bytes = encode(&matches);
+        assert!(decode(&bytes[..bytes.len() - 1]).is_none());
+    }
+}
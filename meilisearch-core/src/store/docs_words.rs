@@ -1,4 +1,4 @@
-use super::BEU64;
+use super::{DatabaseUsage, BEU64};
 use crate::database::MainT;
 use crate::DocumentId;
 use heed::types::{ByteSlice, OwnedType};
@@ -31,6 +31,15 @@ impl DocsWords {
         self.docs_words.clear(writer)
     }
 
+    pub fn usage(self, reader: &heed::RoTxn<MainT>) -> ZResult<DatabaseUsage> {
+        let mut usage = DatabaseUsage::default();
+        for result in self.docs_words.iter(reader)? {
+            let (_, bytes) = result?;
+            usage.add_entry(std::mem::size_of::<BEU64>(), bytes.len());
+        }
+        Ok(usage)
+    }
+
     pub fn doc_words(
         self,
         reader: &heed::RoTxn<MainT>,
@@ -1,11 +1,60 @@
+use std::borrow::Cow;
+
 use heed::types::{ByteSlice, OwnedType};
+use heed::{BytesDecode, BytesEncode};
 use crate::database::MainT;
 use heed::Result as ZResult;
 use meilisearch_schema::FieldId;
 
-use super::DocumentFieldStoredKey;
+use super::{DatabaseUsage, DocumentFieldStoredKey};
 use crate::DocumentId;
 
+/// The first byte of a compressed field value, a JSON value never starts with it.
+const COMPRESSED_TAG: u8 = 0;
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// The JSON serialization of a document field, along with whether it must be compressed.
+pub struct FieldValue<'a> {
+    pub json: &'a [u8],
+    pub compress: bool,
+}
+
+/// Compresses the documents fields with zstd when asked to, the values that the
+/// compression doesn't make smaller and the ones written without it are kept as raw JSON.
+pub struct FieldValueCodec;
+
+impl<'a> BytesEncode<'a> for FieldValueCodec {
+    type EItem = FieldValue<'a>;
+
+    fn bytes_encode(item: &'a Self::EItem) -> Option<Cow<'a, [u8]>> {
+        if item.compress {
+            let compressed = zstd::encode_all(item.json, COMPRESSION_LEVEL).ok()?;
+            if compressed.len() < item.json.len() {
+                let mut bytes = Vec::with_capacity(1 + compressed.len());
+                bytes.push(COMPRESSED_TAG);
+                bytes.extend_from_slice(&compressed);
+                return Some(Cow::Owned(bytes));
+            }
+        }
+        Some(Cow::Borrowed(item.json))
+    }
+}
+
+impl<'a> BytesDecode<'a> for FieldValueCodec {
+    type DItem = Cow<'a, [u8]>;
+
+    fn bytes_decode(bytes: &'a [u8]) -> Option<Self::DItem> {
+        match bytes.split_first() {
+            Some((&COMPRESSED_TAG, compressed)) => zstd::decode_all(compressed).ok().map(Cow::Owned),
+            _ => Some(Cow::Borrowed(bytes)),
+        }
+    }
+}
+
+fn decode_field_value(bytes: &[u8]) -> ZResult<Cow<[u8]>> {
+    FieldValueCodec::bytes_decode(bytes).ok_or(heed::Error::Decoding)
+}
+
 #[derive(Copy, Clone)]
 pub struct DocumentsFields {
     pub(crate) documents_fields: heed::Database<OwnedType<DocumentFieldStoredKey>, ByteSlice>,
@@ -18,9 +67,12 @@ impl DocumentsFields {
         document_id: DocumentId,
         field: FieldId,
         value: &[u8],
+        compress: bool,
     ) -> ZResult<()> {
         let key = DocumentFieldStoredKey::new(document_id, field);
-        self.documents_fields.put(writer, &key, value)
+        let value = FieldValue { json: value, compress };
+        let bytes = FieldValueCodec::bytes_encode(&value).ok_or(heed::Error::Encoding)?;
+        self.documents_fields.put(writer, &key, &bytes)
     }
 
     pub fn del_all_document_fields(
@@ -37,14 +89,26 @@ impl DocumentsFields {
         self.documents_fields.clear(writer)
     }
 
+    pub fn usage(self, reader: &heed::RoTxn<MainT>) -> ZResult<DatabaseUsage> {
+        let mut usage = DatabaseUsage::default();
+        for result in self.documents_fields.iter(reader)? {
+            let (_, bytes) = result?;
+            usage.add_entry(std::mem::size_of::<DocumentFieldStoredKey>(), bytes.len());
+        }
+        Ok(usage)
+    }
+
     pub fn document_attribute<'txn>(
         self,
         reader: &'txn heed::RoTxn<MainT>,
         document_id: DocumentId,
         field: FieldId,
-    ) -> ZResult<Option<&'txn [u8]>> {
+    ) -> ZResult<Option<Cow<'txn, [u8]>>> {
         let key = DocumentFieldStoredKey::new(document_id, field);
-        self.documents_fields.get(reader, &key)
+        match self.documents_fields.get(reader, &key)? {
+            Some(bytes) => decode_field_value(bytes).map(Some),
+            None => Ok(None),
+        }
     }
 
     pub fn document_fields<'txn>(
@@ -57,6 +121,23 @@ impl DocumentsFields {
         let iter = self.documents_fields.range(reader, &(start..=end))?;
         Ok(DocumentFieldsIter { iter })
     }
+
+    /// Iterates over the fields of all the documents, ordered by document then by field.
+    pub fn all_documents_fields<'txn>(
+        self,
+        reader: &'txn heed::RoTxn<MainT>,
+    ) -> ZResult<AllDocumentsFieldsIter<'txn>> {
+        let iter = self.documents_fields.iter(reader)?;
+        Ok(AllDocumentsFieldsIter { iter })
+    }
+
+    pub fn documents_ids<'txn>(self, reader: &'txn heed::RoTxn<MainT>) -> ZResult<DocumentsIdsIter<'txn>> {
+        let iter = self.documents_fields.iter(reader)?;
+        Ok(DocumentsIdsIter {
+            last_seen_id: None,
+            iter,
+        })
+    }
 }
 
 pub struct DocumentFieldsIter<'txn> {
@@ -64,16 +145,85 @@ pub struct DocumentFieldsIter<'txn> {
 }
 
 impl<'txn> Iterator for DocumentFieldsIter<'txn> {
-    type Item = ZResult<(FieldId, &'txn [u8])>;
+    type Item = ZResult<(FieldId, Cow<'txn, [u8]>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.iter.next() {
+            Some(Ok((key, bytes))) => {
+                let field_id = FieldId(key.field_id.get());
+                Some(decode_field_value(bytes).map(|bytes| (field_id, bytes)))
+            }
+            Some(Err(e)) => Some(Err(e)),
+            None => None,
+        }
+    }
+}
+
+pub struct AllDocumentsFieldsIter<'txn> {
+    iter: heed::RoIter<'txn, OwnedType<DocumentFieldStoredKey>, ByteSlice>,
+}
+
+impl<'txn> Iterator for AllDocumentsFieldsIter<'txn> {
+    type Item = ZResult<(DocumentId, FieldId, Cow<'txn, [u8]>)>;
 
     fn next(&mut self) -> Option<Self::Item> {
         match self.iter.next() {
             Some(Ok((key, bytes))) => {
+                let docid = DocumentId(key.docid.get());
                 let field_id = FieldId(key.field_id.get());
-                Some(Ok((field_id, bytes)))
+                Some(decode_field_value(bytes).map(|bytes| (docid, field_id, bytes)))
             }
             Some(Err(e)) => Some(Err(e)),
             None => None,
         }
     }
 }
+
+pub struct DocumentsIdsIter<'txn> {
+    last_seen_id: Option<DocumentId>,
+    iter: heed::RoIter<'txn, OwnedType<DocumentFieldStoredKey>, ByteSlice>,
+}
+
+impl Iterator for DocumentsIdsIter<'_> {
+    type Item = ZResult<DocumentId>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for result in &mut self.iter {
+            match result {
+                Ok((key, _)) => {
+                    let document_id = DocumentId(key.docid.get());
+                    if Some(document_id) != self.last_seen_id {
+                        self.last_seen_id = Some(document_id);
+                        return Some(Ok(document_id));
+                    }
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compressed_field_values() {
+        let json = serde_json::to_vec(&"lorem ipsum dolor sit amet ".repeat(20)).unwrap();
+
+        let compressed = FieldValueCodec::bytes_encode(&FieldValue { json: &json, compress: true }).unwrap();
+        assert_eq!(compressed[0], COMPRESSED_TAG);
+        assert!(compressed.len() < json.len());
+        assert_eq!(FieldValueCodec::bytes_decode(&compressed).unwrap().as_ref(), json.as_slice());
+
+        // the raw values are read as they are
+        let raw = FieldValueCodec::bytes_encode(&FieldValue { json: &json, compress: false }).unwrap();
+        assert_eq!(raw.as_ref(), json.as_slice());
+        assert_eq!(FieldValueCodec::bytes_decode(&raw).unwrap().as_ref(), json.as_slice());
+
+        // the compression is skipped when it doesn't make the value smaller
+        let small = FieldValueCodec::bytes_encode(&FieldValue { json: b"12", compress: true }).unwrap();
+        assert_eq!(small.as_ref(), b"12");
+    }
+}
@@ -0,0 +1,70 @@
+use heed::types::{OwnedType, Unit};
+use heed::Result as ZResult;
+use zerocopy::{AsBytes, FromBytes};
+
+use super::BEU64;
+use crate::database::MainT;
+use crate::DocumentId;
+
+/// A document expiration, the keys are ordered by expiration date, then by document.
+#[derive(Debug, Copy, Clone, AsBytes, FromBytes)]
+#[repr(C)]
+pub struct ExpirationKey {
+    expires_at: BEU64,
+    docid: BEU64,
+}
+
+impl ExpirationKey {
+    fn new(expires_at: u64, docid: DocumentId) -> ExpirationKey {
+        ExpirationKey {
+            expires_at: BEU64::new(expires_at),
+            docid: BEU64::new(docid.0),
+        }
+    }
+}
+
+/// The expiration dates of the documents that have an `_expiresAt` attribute.
+#[derive(Copy, Clone)]
+pub struct Expirations {
+    pub(crate) expirations: heed::Database<OwnedType<ExpirationKey>, Unit>,
+}
+
+impl Expirations {
+    pub fn put_document_expiration(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        document_id: DocumentId,
+        expires_at: u64,
+    ) -> ZResult<()> {
+        let key = ExpirationKey::new(expires_at, document_id);
+        self.expirations.put(writer, &key, &())
+    }
+
+    pub fn del_document_expiration(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        document_id: DocumentId,
+        expires_at: u64,
+    ) -> ZResult<bool> {
+        let key = ExpirationKey::new(expires_at, document_id);
+        self.expirations.delete(writer, &key)
+    }
+
+    /// Returns the documents that expire at or before the given date, in seconds since the Unix epoch.
+    pub fn expired_documents(self, reader: &heed::RoTxn<MainT>, now: u64) -> ZResult<Vec<DocumentId>> {
+        let start = ExpirationKey::new(0, DocumentId(u64::min_value()));
+        let end = ExpirationKey::new(now, DocumentId(u64::max_value()));
+
+        let mut document_ids = Vec::new();
+        for result in self.expirations.range(reader, &(start..=end))? {
+            let (key, ()) = result?;
+            document_ids.push(DocumentId(key.docid.get()));
+        }
+
+        Ok(document_ids)
+    }
+
+    pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
+        self.expirations.clear(writer)
+    }
+}
@@ -1,6 +1,6 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
 
+use heed::types::Str;
 use heed::{RwTxn, RoTxn, Result as ZResult, RoRange};
 use sdset::{SetBuf, Set, SetOperation};
 
@@ -8,13 +8,17 @@ use meilisearch_types::DocumentId;
 use meilisearch_schema::FieldId;
 
 use crate::database::MainT;
-use crate::facets::FacetKey;
+use crate::facets::{FacetKey, FacetsMap, NumericFacetKey};
 use super::cow_set::CowSet;
+use super::DatabaseUsage;
 
 /// contains facet info
 #[derive(Clone, Copy)]
 pub struct Facets {
     pub(crate) facets: heed::Database<FacetKey, CowSet<DocumentId>>,
+    pub(crate) numeric_facets: heed::Database<NumericFacetKey, CowSet<DocumentId>>,
+    /// the values as they were in the documents, when they differ from their normalized form
+    pub(crate) originals: heed::Database<FacetKey, Str>,
 }
 
 impl Facets {
@@ -27,32 +31,132 @@ impl Facets {
         self.facets.prefix_iter(reader, &FacetKey::new(field_id, String::new()))
     }
 
+    /// returns the number of distinct values of the given field that are associated to at
+    /// least one document, only the documents in `candidates` are considered when provided
+    /// and the `excluded` documents are never considered.
+    pub fn field_distinct_count(
+        &self,
+        reader: &RoTxn<MainT>,
+        field_id: FieldId,
+        candidates: Option<&Set<DocumentId>>,
+        excluded: &Set<DocumentId>,
+    ) -> ZResult<usize> {
+        let mut count = 0;
+        for result in self.field_document_ids(reader, field_id)? {
+            let (_key, document_ids) = result?;
+            let is_present = match candidates {
+                Some(candidates) => !sdset::duo::OpBuilder::new(document_ids.as_ref(), candidates).intersection().into_set_buf().is_empty(),
+                None => document_ids.iter().any(|id| excluded.binary_search(id).is_err()),
+            };
+            if is_present {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
     pub fn facet_document_ids<'txn>(&self, reader: &'txn RoTxn<MainT>, facet_key: &FacetKey) -> ZResult<Option<Cow<'txn, Set<DocumentId>>>> {
         self.facets.get(reader, &facet_key)
     }
 
+    /// returns the value of the facet as it appeared in the first document that had it.
+    pub fn original_value<'txn>(&self, reader: &'txn RoTxn<MainT>, facet_key: &FacetKey) -> ZResult<Option<&'txn str>> {
+        self.originals.get(reader, facet_key)
+    }
+
+    /// returns the documents whose numeric value of the given field is in the range, bounds included.
+    pub fn numeric_range_document_ids(&self, reader: &RoTxn<MainT>, field_id: FieldId, from: f64, to: f64) -> ZResult<SetBuf<DocumentId>> {
+        let start = NumericFacetKey::new(field_id, from);
+        let end = NumericFacetKey::new(field_id, to);
+        if start > end {
+            return Ok(SetBuf::default());
+        }
+
+        let mut document_ids = Vec::new();
+        for result in self.numeric_facets.range(reader, &(start..=end))? {
+            let (_key, ids) = result?;
+            document_ids.extend_from_slice(&ids);
+        }
+        Ok(SetBuf::from_dirty(document_ids))
+    }
+
     /// updates the facets  store, revmoving the documents from the facets provided in the
     /// `facet_map` argument
-    pub fn remove(&self, writer: &mut RwTxn<MainT>, facet_map: HashMap<FacetKey, Vec<DocumentId>>) -> ZResult<()> {
-        for (key, document_ids) in facet_map {
+    pub fn remove(&self, writer: &mut RwTxn<MainT>, facet_map: FacetsMap) -> ZResult<()> {
+        for (key, document_ids) in facet_map.strings {
             if let Some(old) = self.facets.get(writer, &key)? {
                 let to_remove = SetBuf::from_dirty(document_ids);
                 let new = sdset::duo::OpBuilder::new(old.as_ref(), to_remove.as_set()).difference().into_set_buf();
-                self.facets.put(writer, &key, new.as_set())?;
+                if new.is_empty() {
+                    self.originals.delete(writer, &key)?;
+                    self.facets.delete(writer, &key)?;
+                } else {
+                    self.facets.put(writer, &key, new.as_set())?;
+                }
+            }
+        }
+        for (key, document_ids) in facet_map.numbers {
+            if let Some(old) = self.numeric_facets.get(writer, &key)? {
+                let to_remove = SetBuf::from_dirty(document_ids);
+                let new = sdset::duo::OpBuilder::new(old.as_ref(), to_remove.as_set()).difference().into_set_buf();
+                if new.is_empty() {
+                    self.numeric_facets.delete(writer, &key)?;
+                } else {
+                    self.numeric_facets.put(writer, &key, new.as_set())?;
+                }
             }
         }
         Ok(())
     }
 
-    pub fn add(&self, writer: &mut RwTxn<MainT>, facet_map: HashMap<FacetKey, Vec<DocumentId>>) -> ZResult<()> {
-        for (key, document_ids) in facet_map {
+    pub fn add(&self, writer: &mut RwTxn<MainT>, facet_map: FacetsMap) -> ZResult<()> {
+        for (key, original) in facet_map.originals {
+            if self.originals.get(writer, &key)?.is_none() {
+                self.originals.put(writer, &key, &original)?;
+            }
+        }
+        // a value can already be held by the documents of the previous updates
+        for (key, document_ids) in facet_map.strings {
+            let set = SetBuf::from_dirty(document_ids);
+            let new = match self.facets.get(writer, &key)? {
+                Some(old) => sdset::duo::OpBuilder::new(old.as_ref(), set.as_set()).union().into_set_buf(),
+                None => set,
+            };
+            self.put_facet_document_ids(writer, key, new.as_set())?;
+        }
+        for (key, document_ids) in facet_map.numbers {
             let set = SetBuf::from_dirty(document_ids);
-            self.put_facet_document_ids(writer, key, set.as_set())?;
+            let new = match self.numeric_facets.get(writer, &key)? {
+                Some(old) => sdset::duo::OpBuilder::new(old.as_ref(), set.as_set()).union().into_set_buf(),
+                None => set,
+            };
+            self.numeric_facets.put(writer, &key, new.as_set())?;
         }
         Ok(())
     }
 
     pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
-        self.facets.clear(writer)
+        self.facets.clear(writer)?;
+        self.numeric_facets.clear(writer)?;
+        self.originals.clear(writer)
+    }
+
+    /// The usage of the string and the numeric facets and of the original values.
+    pub fn usage(self, reader: &RoTxn<MainT>) -> ZResult<DatabaseUsage> {
+        let id_size = std::mem::size_of::<DocumentId>();
+        let mut usage = DatabaseUsage::default();
+        for result in self.facets.iter(reader)? {
+            let (key, ids) = result?;
+            usage.add_entry(2 + key.value().len(), ids.len() * id_size);
+        }
+        for result in self.numeric_facets.iter(reader)? {
+            let (_, ids) = result?;
+            usage.add_entry(2 + 8, ids.len() * id_size);
+        }
+        for result in self.originals.iter(reader)? {
+            let (key, original) = result?;
+            usage.add_entry(2 + key.value().len(), original.len());
+        }
+        Ok(usage)
     }
 }
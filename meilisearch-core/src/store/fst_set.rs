@@ -0,0 +1,42 @@
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Deref;
+
+/// An fst set read in place from the memory map of LMDB, its bytes are never copied.
+///
+/// The set borrows the transaction it was read with and is only reachable through
+/// a reference, it can't outlive the pages it reads, use `into_owned` to keep it longer.
+pub struct FstSet<'txn> {
+    set: fst::Set,
+    _marker: PhantomData<&'txn [u8]>,
+}
+
+impl<'txn> FstSet<'txn> {
+    pub(crate) fn new(bytes: &'txn [u8]) -> FstSet<'txn> {
+        // the fst 0.3 sets can only borrow static slices, the lifetime
+        // is enforced by the marker as the set is never given by value
+        let bytes: &'static [u8] = unsafe { mem::transmute(bytes) };
+        let set = fst::Set::from_static_slice(bytes).unwrap();
+        FstSet { set, _marker: PhantomData }
+    }
+
+    /// Copies the bytes of the set, it is no more bound to the transaction.
+    pub fn into_owned(self) -> fst::Set {
+        let bytes = self.set.as_fst().as_bytes().to_vec();
+        fst::Set::from_bytes(bytes).unwrap()
+    }
+}
+
+impl Default for FstSet<'_> {
+    fn default() -> Self {
+        FstSet { set: fst::Set::default(), _marker: PhantomData }
+    }
+}
+
+impl Deref for FstSet<'_> {
+    type Target = fst::Set;
+
+    fn deref(&self) -> &fst::Set {
+        &self.set
+    }
+}
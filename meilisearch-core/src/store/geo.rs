@@ -0,0 +1,39 @@
+use heed::types::OwnedType;
+use heed::Result as ZResult;
+
+use super::BEU64;
+use crate::database::MainT;
+use crate::geo::GeoPoint;
+use crate::DocumentId;
+
+/// The coordinates of the documents that have a `_geo` attribute.
+#[derive(Copy, Clone)]
+pub struct Geo {
+    pub(crate) geo: heed::Database<OwnedType<BEU64>, OwnedType<GeoPoint>>,
+}
+
+impl Geo {
+    pub fn put_document_point(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        document_id: DocumentId,
+        point: GeoPoint,
+    ) -> ZResult<()> {
+        let document_id = BEU64::new(document_id.0);
+        self.geo.put(writer, &document_id, &point)
+    }
+
+    pub fn del_document_point(self, writer: &mut heed::RwTxn<MainT>, document_id: DocumentId) -> ZResult<bool> {
+        let document_id = BEU64::new(document_id.0);
+        self.geo.delete(writer, &document_id)
+    }
+
+    pub fn document_point(self, reader: &heed::RoTxn<MainT>, document_id: DocumentId) -> ZResult<Option<GeoPoint>> {
+        let document_id = BEU64::new(document_id.0);
+        self.geo.get(reader, &document_id)
+    }
+
+    pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
+        self.geo.clear(writer)
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::BTreeSet;
+
+use fst::Streamer;
+use serde::Serialize;
+
+use crate::database::MainT;
+use crate::store::Index;
+use crate::{DocumentId, MResult};
+
+/// The invariants between the stores of an index, a crash in the middle of an update
+/// can leave them broken, a repair rebuilds the stores from the documents fields.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    /// The words of the words sets that have no postings list.
+    pub words_without_postings_list: u64,
+    /// The documents with indexed words or fields counts but without any stored field.
+    pub documents_without_fields: u64,
+    /// The number of documents stored in the documents fields, the deleted ones excluded.
+    pub number_of_documents: u64,
+    /// The number of documents deleted but not yet purged from the stores.
+    pub deleted_documents: u64,
+    /// The number of documents recorded in the main store.
+    pub stored_number_of_documents: u64,
+}
+
+impl IntegrityReport {
+    pub fn is_sound(&self) -> bool {
+        self.words_without_postings_list == 0
+            && self.documents_without_fields == 0
+            && self.number_of_documents == self.stored_number_of_documents
+    }
+}
+
+pub fn check_integrity(reader: &heed::RoTxn<MainT>, index: &Index) -> MResult<IntegrityReport> {
+    let mut report = IntegrityReport::default();
+
+    let words_fst = index.main.words_fst(reader)?;
+    let words_delta_fst = index.main.words_delta_fst(reader)?;
+    for words in words_fst.iter().chain(words_delta_fst.iter()) {
+        let mut stream = words.stream();
+        while let Some(word) = stream.next() {
+            if index.postings_lists.postings_list(reader, word)?.is_none() {
+                report.words_without_postings_list += 1;
+            }
+        }
+    }
+
+    let mut documents_ids = BTreeSet::new();
+    for result in index.documents_fields.documents_ids(reader)? {
+        documents_ids.insert(result?);
+    }
+    let deleted = index.deleted_documents(reader)?;
+    report.deleted_documents = deleted.iter().filter(|id| documents_ids.contains(id)).count() as u64;
+    report.number_of_documents = documents_ids.len() as u64 - report.deleted_documents;
+    report.stored_number_of_documents = index.main.number_of_documents(reader)?;
+
+    let mut orphans = BTreeSet::new();
+    for result in index.docs_words.docs_words.iter(reader)? {
+        let (key, _) = result?;
+        let document_id = DocumentId(key.get());
+        if !documents_ids.contains(&document_id) {
+            orphans.insert(document_id);
+        }
+    }
+    for result in index.documents_fields_counts.documents_ids(reader)? {
+        let document_id = result?;
+        if !documents_ids.contains(&document_id) {
+            orphans.insert(document_id);
+        }
+    }
+    report.documents_without_fields = orphans.len() as u64;
+
+    Ok(report)
+}
@@ -1,37 +1,83 @@
 use std::borrow::Cow;
-use std::sync::Arc;
 use std::collections::HashMap;
+use std::ops::Bound::{Excluded, Included};
 
 use chrono::{DateTime, Utc};
-use heed::types::{ByteSlice, OwnedType, SerdeBincode, Str};
+use heed::types::{ByteSlice, OwnedType, SerdeBincode, SerdeJson, Str};
 use heed::Result as ZResult;
 use meilisearch_schema::{FieldId, Schema};
 use sdset::Set;
+use serde::Serialize;
+use serde_json::Value;
 
 use crate::database::MainT;
-use crate::RankedMap;
-use crate::settings::RankingRule;
+use crate::{DocumentId, RankedMap};
+use crate::query_rules::QueryRule;
+use crate::settings::{DocumentIdFormat, FacetNormalization, LocalizedSynonyms, PrefixSearch, RankingRule, TokenPolicy, TypoTolerance};
 use super::cow_set::CowSet;
+use super::FstSet;
 
 const CREATED_AT_KEY: &str = "created-at";
+const DELETED_DOCUMENTS_KEY: &str = "deleted-documents";
 const ATTRIBUTES_FOR_FACETING: &str = "attributes-for-faceting";
 const RANKING_RULES_KEY: &str = "ranking-rules";
 const DISTINCT_ATTRIBUTE_KEY: &str = "distinct-attribute";
+const DOCUMENT_ID_FORMAT_KEY: &str = "document-id-format";
+const FACET_NORMALIZATION_KEY: &str = "facet-normalization";
 const STOP_WORDS_KEY: &str = "stop-words";
 const SYNONYMS_KEY: &str = "synonyms";
-const CUSTOMS_KEY: &str = "customs";
+const FOLD_DIACRITICS_KEY: &str = "fold-diacritics";
+const STOP_WORDS_IN_PHRASES_KEY: &str = "stop-words-in-phrases";
+const COMPRESS_DOCUMENTS_KEY: &str = "compress-documents";
 const FIELDS_FREQUENCY_KEY: &str = "fields-frequency";
+const LANGUAGE_KEY: &str = "language";
+const LOCALIZED_SYNONYMS_KEY: &str = "localized-synonyms";
+const MAX_POSITIONS_PER_ATTRIBUTE_KEY: &str = "max-positions-per-attribute";
+const MAX_TOKEN_LENGTH_KEY: &str = "max-token-length";
+const MAX_TOTAL_HITS_KEY: &str = "max-total-hits";
+const SEARCH_CUTOFF_MS_KEY: &str = "search-cutoff-ms";
+const METADATA_KEY_PREFIX: &str = "metadata/";
 const NAME_KEY: &str = "name";
 const NUMBER_OF_DOCUMENTS_KEY: &str = "number-of-documents";
+const QUERY_RULES_KEY: &str = "query-rules";
 const RANKED_MAP_KEY: &str = "ranked-map";
 const SCHEMA_KEY: &str = "schema";
+const SORTABLE_ATTRIBUTES_KEY: &str = "sortable-attributes";
+const TOKEN_POLICY_KEY: &str = "token-policy";
+const TYPO_TOLERANCE_KEY: &str = "typo-tolerance";
+const PREFIX_SEARCH_KEY: &str = "prefix-search";
 const UPDATED_AT_KEY: &str = "updated-at";
 const WORDS_KEY: &str = "words";
+const WORDS_DELTA_KEY: &str = "words-delta";
 
 pub type FreqsMap = HashMap<String, usize>;
 type SerdeFreqsMap = SerdeBincode<FreqsMap>;
 type SerdeDatetime = SerdeBincode<DateTime<Utc>>;
 
+/// The size, in bytes, of the biggest structures of an index stored in its main store.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUsage {
+    pub words_fst: usize,
+    pub synonyms_fst: usize,
+    pub stop_words_fst: usize,
+    pub ranked_map: usize,
+}
+
+/// What the index does with a field of its schema.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldCapabilities {
+    pub name: String,
+    pub id: FieldId,
+    pub indexed: bool,
+    pub displayed: bool,
+    pub ranked: bool,
+    pub faceted: bool,
+    /// The number of documents containing the field.
+    pub frequency: usize,
+}
+
 #[derive(Copy, Clone)]
 pub struct Main {
     pub(crate) main: heed::PolyDatabase,
@@ -76,27 +122,25 @@ impl Main {
         self.main.put::<_, Str, ByteSlice>(writer, WORDS_KEY, bytes)
     }
 
-    pub unsafe fn static_words_fst(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<fst::Set>> {
-        match self.main.get::<_, Str, ByteSlice>(reader, WORDS_KEY)? {
-            Some(bytes) => {
-                let bytes: &'static [u8] = std::mem::transmute(bytes);
-                let set = fst::Set::from_static_slice(bytes).unwrap();
-                Ok(Some(set))
-            }
-            None => Ok(None),
-        }
+    pub fn words_fst<'txn>(self, reader: &'txn heed::RoTxn<MainT>) -> ZResult<Option<FstSet<'txn>>> {
+        let bytes = self.main.get::<_, Str, ByteSlice>(reader, WORDS_KEY)?;
+        Ok(bytes.map(FstSet::new))
     }
 
-    pub fn words_fst(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<fst::Set>> {
-        match self.main.get::<_, Str, ByteSlice>(reader, WORDS_KEY)? {
-            Some(bytes) => {
-                let len = bytes.len();
-                let bytes = Arc::new(bytes.to_owned());
-                let fst = fst::raw::Fst::from_shared_bytes(bytes, 0, len).unwrap();
-                Ok(Some(fst::Set::from(fst)))
-            }
-            None => Ok(None),
-        }
+    /// The words added since the words set was last rewritten, they are
+    /// not necessarily missing from it and are searched along with it.
+    pub fn put_words_delta_fst(self, writer: &mut heed::RwTxn<MainT>, fst: &fst::Set) -> ZResult<()> {
+        let bytes = fst.as_fst().as_bytes();
+        self.main.put::<_, Str, ByteSlice>(writer, WORDS_DELTA_KEY, bytes)
+    }
+
+    pub fn words_delta_fst<'txn>(self, reader: &'txn heed::RoTxn<MainT>) -> ZResult<Option<FstSet<'txn>>> {
+        let bytes = self.main.get::<_, Str, ByteSlice>(reader, WORDS_DELTA_KEY)?;
+        Ok(bytes.map(FstSet::new))
+    }
+
+    pub fn delete_words_delta_fst(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, WORDS_DELTA_KEY)
     }
 
     pub fn put_schema(self, writer: &mut heed::RwTxn<MainT>, schema: &Schema) -> ZResult<()> {
@@ -111,6 +155,19 @@ impl Main {
         self.main.delete::<_, Str>(writer, SCHEMA_KEY)
     }
 
+    pub fn storage_usage(self, reader: &heed::RoTxn<MainT>) -> ZResult<StorageUsage> {
+        let size = |key| -> ZResult<usize> {
+            Ok(self.main.get::<_, Str, ByteSlice>(reader, key)?.map_or(0, <[u8]>::len))
+        };
+
+        Ok(StorageUsage {
+            words_fst: size(WORDS_KEY)? + size(WORDS_DELTA_KEY)?,
+            synonyms_fst: size(SYNONYMS_KEY)?,
+            stop_words_fst: size(STOP_WORDS_KEY)?,
+            ranked_map: size(RANKED_MAP_KEY)?,
+        })
+    }
+
     pub fn put_ranked_map(self, writer: &mut heed::RwTxn<MainT>, ranked_map: &RankedMap) -> ZResult<()> {
         self.main.put::<_, Str, SerdeBincode<RankedMap>>(writer, RANKED_MAP_KEY, &ranked_map)
     }
@@ -124,16 +181,21 @@ impl Main {
         self.main.put::<_, Str, ByteSlice>(writer, SYNONYMS_KEY, bytes)
     }
 
-    pub fn synonyms_fst(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<fst::Set>> {
-        match self.main.get::<_, Str, ByteSlice>(reader, SYNONYMS_KEY)? {
-            Some(bytes) => {
-                let len = bytes.len();
-                let bytes = Arc::new(bytes.to_owned());
-                let fst = fst::raw::Fst::from_shared_bytes(bytes, 0, len).unwrap();
-                Ok(Some(fst::Set::from(fst)))
-            }
-            None => Ok(None),
-        }
+    pub fn synonyms_fst<'txn>(self, reader: &'txn heed::RoTxn<MainT>) -> ZResult<Option<FstSet<'txn>>> {
+        let bytes = self.main.get::<_, Str, ByteSlice>(reader, SYNONYMS_KEY)?;
+        Ok(bytes.map(FstSet::new))
+    }
+
+    pub fn localized_synonyms(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<LocalizedSynonyms>> {
+        self.main.get::<_, Str, SerdeBincode<LocalizedSynonyms>>(reader, LOCALIZED_SYNONYMS_KEY)
+    }
+
+    pub fn put_localized_synonyms(self, writer: &mut heed::RwTxn<MainT>, value: &LocalizedSynonyms) -> ZResult<()> {
+        self.main.put::<_, Str, SerdeBincode<LocalizedSynonyms>>(writer, LOCALIZED_SYNONYMS_KEY, value)
+    }
+
+    pub fn delete_localized_synonyms(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, LOCALIZED_SYNONYMS_KEY)
     }
 
     pub fn put_stop_words_fst(self, writer: &mut heed::RwTxn<MainT>, fst: &fst::Set) -> ZResult<()> {
@@ -141,16 +203,9 @@ impl Main {
         self.main.put::<_, Str, ByteSlice>(writer, STOP_WORDS_KEY, bytes)
     }
 
-    pub fn stop_words_fst(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<fst::Set>> {
-        match self.main.get::<_, Str, ByteSlice>(reader, STOP_WORDS_KEY)? {
-            Some(bytes) => {
-                let len = bytes.len();
-                let bytes = Arc::new(bytes.to_owned());
-                let fst = fst::raw::Fst::from_shared_bytes(bytes, 0, len).unwrap();
-                Ok(Some(fst::Set::from(fst)))
-            }
-            None => Ok(None),
-        }
+    pub fn stop_words_fst<'txn>(self, reader: &'txn heed::RoTxn<MainT>) -> ZResult<Option<FstSet<'txn>>> {
+        let bytes = self.main.get::<_, Str, ByteSlice>(reader, STOP_WORDS_KEY)?;
+        Ok(bytes.map(FstSet::new))
     }
 
     pub fn put_number_of_documents<F>(self, writer: &mut heed::RwTxn<MainT>, f: F) -> ZResult<u64>
@@ -192,6 +247,47 @@ impl Main {
         }
     }
 
+    /// Returns the capabilities of every field of the schema, ordered by field id.
+    pub fn fields_capabilities(self, reader: &heed::RoTxn<MainT>) -> ZResult<Vec<FieldCapabilities>> {
+        let schema = match self.schema(reader)? {
+            Some(schema) => schema,
+            None => return Ok(Vec::new()),
+        };
+        let fields_frequency = self.fields_frequency(reader)?.unwrap_or_default();
+        let attributes_for_faceting = self.attributes_for_faceting(reader)?;
+
+        let mut capabilities: Vec<_> = schema
+            .names()
+            .filter_map(|name| schema.id(name).map(|id| (name, id)))
+            .map(|(name, id)| FieldCapabilities {
+                name: name.to_string(),
+                id,
+                indexed: schema.is_indexed(id).is_some(),
+                displayed: schema.is_displayed(id),
+                ranked: schema.is_ranked(id),
+                faceted: attributes_for_faceting.as_ref().map_or(false, |faceted| faceted.contains(&id)),
+                frequency: fields_frequency.get(name).copied().unwrap_or(0),
+            })
+            .collect();
+
+        capabilities.sort_unstable_by_key(|field| field.id);
+
+        Ok(capabilities)
+    }
+
+    /// The documents that are deleted but still in the stores until they are purged.
+    pub fn deleted_documents<'txn>(self, reader: &'txn heed::RoTxn<MainT>) -> ZResult<Option<Cow<'txn, Set<DocumentId>>>> {
+        self.main.get::<_, Str, CowSet<DocumentId>>(reader, DELETED_DOCUMENTS_KEY)
+    }
+
+    pub fn put_deleted_documents(self, writer: &mut heed::RwTxn<MainT>, documents_ids: &Set<DocumentId>) -> ZResult<()> {
+        self.main.put::<_, Str, CowSet<DocumentId>>(writer, DELETED_DOCUMENTS_KEY, documents_ids)
+    }
+
+    pub fn delete_deleted_documents(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, DELETED_DOCUMENTS_KEY)
+    }
+
     pub fn attributes_for_faceting<'txn>(&self, reader: &'txn heed::RoTxn<MainT>) -> ZResult<Option<Cow<'txn, Set<FieldId>>>> {
         self.main.get::<_, Str, CowSet<FieldId>>(reader, ATTRIBUTES_FOR_FACETING)
     }
@@ -204,6 +300,18 @@ impl Main {
         self.main.delete::<_, Str>(writer, ATTRIBUTES_FOR_FACETING)
     }
 
+    pub fn sortable_attributes<'txn>(&self, reader: &'txn heed::RoTxn<MainT>) -> ZResult<Option<Cow<'txn, Set<FieldId>>>> {
+        self.main.get::<_, Str, CowSet<FieldId>>(reader, SORTABLE_ATTRIBUTES_KEY)
+    }
+
+    pub fn put_sortable_attributes(self, writer: &mut heed::RwTxn<MainT>, attributes: &Set<FieldId>) -> ZResult<()> {
+        self.main.put::<_, Str, CowSet<FieldId>>(writer, SORTABLE_ATTRIBUTES_KEY, attributes)
+    }
+
+    pub fn delete_sortable_attributes(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, SORTABLE_ATTRIBUTES_KEY)
+    }
+
     pub fn ranking_rules(&self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<Vec<RankingRule>>> {
         self.main.get::<_, Str, SerdeBincode<Vec<RankingRule>>>(reader, RANKING_RULES_KEY)
     }
@@ -231,12 +339,217 @@ impl Main {
         self.main.delete::<_, Str>(writer, DISTINCT_ATTRIBUTE_KEY)
     }
 
-    pub fn put_customs(self, writer: &mut heed::RwTxn<MainT>, customs: &[u8]) -> ZResult<()> {
-        self.main
-            .put::<_, Str, ByteSlice>(writer, CUSTOMS_KEY, customs)
+    pub fn language(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<String>> {
+        Ok(self.main.get::<_, Str, Str>(reader, LANGUAGE_KEY)?.map(ToOwned::to_owned))
+    }
+
+    pub fn put_language(self, writer: &mut heed::RwTxn<MainT>, value: &str) -> ZResult<()> {
+        self.main.put::<_, Str, Str>(writer, LANGUAGE_KEY, value)
+    }
+
+    pub fn delete_language(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, LANGUAGE_KEY)
+    }
+
+    pub fn document_id_format(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<DocumentIdFormat>> {
+        self.main.get::<_, Str, SerdeBincode<DocumentIdFormat>>(reader, DOCUMENT_ID_FORMAT_KEY)
+    }
+
+    pub fn put_document_id_format(self, writer: &mut heed::RwTxn<MainT>, value: DocumentIdFormat) -> ZResult<()> {
+        self.main.put::<_, Str, SerdeBincode<DocumentIdFormat>>(writer, DOCUMENT_ID_FORMAT_KEY, &value)
+    }
+
+    pub fn delete_document_id_format(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, DOCUMENT_ID_FORMAT_KEY)
+    }
+
+    pub fn typo_tolerance(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<TypoTolerance>> {
+        self.main.get::<_, Str, SerdeBincode<TypoTolerance>>(reader, TYPO_TOLERANCE_KEY)
+    }
+
+    pub fn put_typo_tolerance(self, writer: &mut heed::RwTxn<MainT>, value: &TypoTolerance) -> ZResult<()> {
+        self.main.put::<_, Str, SerdeBincode<TypoTolerance>>(writer, TYPO_TOLERANCE_KEY, value)
+    }
+
+    pub fn delete_typo_tolerance(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, TYPO_TOLERANCE_KEY)
+    }
+
+    pub fn facet_normalization(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<FacetNormalization>> {
+        self.main.get::<_, Str, SerdeBincode<FacetNormalization>>(reader, FACET_NORMALIZATION_KEY)
+    }
+
+    pub fn put_facet_normalization(self, writer: &mut heed::RwTxn<MainT>, value: &FacetNormalization) -> ZResult<()> {
+        self.main.put::<_, Str, SerdeBincode<FacetNormalization>>(writer, FACET_NORMALIZATION_KEY, value)
+    }
+
+    pub fn delete_facet_normalization(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, FACET_NORMALIZATION_KEY)
+    }
+
+    pub fn prefix_search(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<PrefixSearch>> {
+        self.main.get::<_, Str, SerdeBincode<PrefixSearch>>(reader, PREFIX_SEARCH_KEY)
+    }
+
+    pub fn put_prefix_search(self, writer: &mut heed::RwTxn<MainT>, value: &PrefixSearch) -> ZResult<()> {
+        self.main.put::<_, Str, SerdeBincode<PrefixSearch>>(writer, PREFIX_SEARCH_KEY, value)
     }
 
-    pub fn customs<'txn>(self, reader: &'txn heed::RoTxn<MainT>) -> ZResult<Option<&'txn [u8]>> {
-        self.main.get::<_, Str, ByteSlice>(reader, CUSTOMS_KEY)
+    pub fn delete_prefix_search(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, PREFIX_SEARCH_KEY)
     }
+
+    pub fn token_policy(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<TokenPolicy>> {
+        self.main.get::<_, Str, SerdeBincode<TokenPolicy>>(reader, TOKEN_POLICY_KEY)
+    }
+
+    pub fn put_token_policy(self, writer: &mut heed::RwTxn<MainT>, value: &TokenPolicy) -> ZResult<()> {
+        self.main.put::<_, Str, SerdeBincode<TokenPolicy>>(writer, TOKEN_POLICY_KEY, value)
+    }
+
+    pub fn delete_token_policy(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, TOKEN_POLICY_KEY)
+    }
+
+    pub fn fold_diacritics(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<bool>> {
+        self.main.get::<_, Str, SerdeBincode<bool>>(reader, FOLD_DIACRITICS_KEY)
+    }
+
+    pub fn put_fold_diacritics(self, writer: &mut heed::RwTxn<MainT>, value: bool) -> ZResult<()> {
+        self.main.put::<_, Str, SerdeBincode<bool>>(writer, FOLD_DIACRITICS_KEY, &value)
+    }
+
+    pub fn delete_fold_diacritics(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, FOLD_DIACRITICS_KEY)
+    }
+
+    pub fn stop_words_in_phrases(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<bool>> {
+        self.main.get::<_, Str, SerdeBincode<bool>>(reader, STOP_WORDS_IN_PHRASES_KEY)
+    }
+
+    pub fn put_stop_words_in_phrases(self, writer: &mut heed::RwTxn<MainT>, value: bool) -> ZResult<()> {
+        self.main.put::<_, Str, SerdeBincode<bool>>(writer, STOP_WORDS_IN_PHRASES_KEY, &value)
+    }
+
+    pub fn delete_stop_words_in_phrases(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, STOP_WORDS_IN_PHRASES_KEY)
+    }
+
+    pub fn compress_documents(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<bool>> {
+        self.main.get::<_, Str, SerdeBincode<bool>>(reader, COMPRESS_DOCUMENTS_KEY)
+    }
+
+    pub fn put_compress_documents(self, writer: &mut heed::RwTxn<MainT>, value: bool) -> ZResult<()> {
+        self.main.put::<_, Str, SerdeBincode<bool>>(writer, COMPRESS_DOCUMENTS_KEY, &value)
+    }
+
+    pub fn delete_compress_documents(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, COMPRESS_DOCUMENTS_KEY)
+    }
+
+    pub fn max_token_length(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<usize>> {
+        let value = self.main.get::<_, Str, OwnedType<u64>>(reader, MAX_TOKEN_LENGTH_KEY)?;
+        Ok(value.map(|v| v as usize))
+    }
+
+    pub fn put_max_token_length(self, writer: &mut heed::RwTxn<MainT>, value: usize) -> ZResult<()> {
+        self.main.put::<_, Str, OwnedType<u64>>(writer, MAX_TOKEN_LENGTH_KEY, &(value as u64))
+    }
+
+    pub fn delete_max_token_length(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, MAX_TOKEN_LENGTH_KEY)
+    }
+
+    pub fn max_positions_per_attribute(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<usize>> {
+        let value = self.main.get::<_, Str, OwnedType<u64>>(reader, MAX_POSITIONS_PER_ATTRIBUTE_KEY)?;
+        Ok(value.map(|v| v as usize))
+    }
+
+    pub fn put_max_positions_per_attribute(self, writer: &mut heed::RwTxn<MainT>, value: usize) -> ZResult<()> {
+        self.main.put::<_, Str, OwnedType<u64>>(writer, MAX_POSITIONS_PER_ATTRIBUTE_KEY, &(value as u64))
+    }
+
+    pub fn delete_max_positions_per_attribute(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, MAX_POSITIONS_PER_ATTRIBUTE_KEY)
+    }
+
+    pub fn max_total_hits(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<usize>> {
+        let value = self.main.get::<_, Str, OwnedType<u64>>(reader, MAX_TOTAL_HITS_KEY)?;
+        Ok(value.map(|v| v as usize))
+    }
+
+    pub fn put_max_total_hits(self, writer: &mut heed::RwTxn<MainT>, value: usize) -> ZResult<()> {
+        self.main.put::<_, Str, OwnedType<u64>>(writer, MAX_TOTAL_HITS_KEY, &(value as u64))
+    }
+
+    pub fn delete_max_total_hits(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, MAX_TOTAL_HITS_KEY)
+    }
+
+    pub fn search_cutoff_ms(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<u64>> {
+        self.main.get::<_, Str, OwnedType<u64>>(reader, SEARCH_CUTOFF_MS_KEY)
+    }
+
+    pub fn put_search_cutoff_ms(self, writer: &mut heed::RwTxn<MainT>, value: u64) -> ZResult<()> {
+        self.main.put::<_, Str, OwnedType<u64>>(writer, SEARCH_CUTOFF_MS_KEY, &value)
+    }
+
+    pub fn delete_search_cutoff_ms(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, SEARCH_CUTOFF_MS_KEY)
+    }
+
+    pub fn query_rules(self, reader: &heed::RoTxn<MainT>) -> ZResult<Option<Vec<QueryRule>>> {
+        self.main.get::<_, Str, SerdeBincode<Vec<QueryRule>>>(reader, QUERY_RULES_KEY)
+    }
+
+    pub fn put_query_rules(self, writer: &mut heed::RwTxn<MainT>, value: &[QueryRule]) -> ZResult<()> {
+        self.main.put::<_, Str, SerdeBincode<Vec<QueryRule>>>(writer, QUERY_RULES_KEY, &value.to_vec())
+    }
+
+    pub fn delete_query_rules(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<bool> {
+        self.main.delete::<_, Str>(writer, QUERY_RULES_KEY)
+    }
+
+    pub fn put_metadata(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        namespace: &str,
+        key: &str,
+        value: &Value,
+    ) -> ZResult<()> {
+        let key = metadata_key(namespace, key);
+        self.main.put::<_, Str, SerdeJson<Value>>(writer, &key, value)
+    }
+
+    pub fn metadata(self, reader: &heed::RoTxn<MainT>, namespace: &str, key: &str) -> ZResult<Option<Value>> {
+        let key = metadata_key(namespace, key);
+        self.main.get::<_, Str, SerdeJson<Value>>(reader, &key)
+    }
+
+    pub fn delete_metadata(self, writer: &mut heed::RwTxn<MainT>, namespace: &str, key: &str) -> ZResult<bool> {
+        let key = metadata_key(namespace, key);
+        self.main.delete::<_, Str>(writer, &key)
+    }
+
+    /// Returns the metadata of a namespace, ordered by key.
+    pub fn metadata_entries(self, reader: &heed::RoTxn<MainT>, namespace: &str) -> ZResult<Vec<(String, Value)>> {
+        let prefix = metadata_key(namespace, "");
+        // the '/' ending the prefix is followed by '0' in the ascii table
+        let end = format!("{}0", &prefix[..prefix.len() - 1]);
+
+        let range = (Included(prefix.as_str()), Excluded(end.as_str()));
+        let mut entries = Vec::new();
+        for result in self.main.range::<_, Str, SerdeJson<Value>, _>(reader, &range)? {
+            let (key, value) = result?;
+            entries.push((key[prefix.len()..].to_owned(), value));
+        }
+
+        Ok(entries)
+    }
+}
+
+/// The length of the namespace is part of the key, a namespace can therefore
+/// contain any character without ever overlapping another one.
+fn metadata_key(namespace: &str, key: &str) -> String {
+    format!("{}{}:{}/{}", METADATA_KEY_PREFIX, namespace.len(), namespace, key)
 }
@@ -1,50 +1,74 @@
+mod compressed_postings;
 mod cow_set;
 mod docs_words;
 mod prefix_documents_cache;
 mod prefix_postings_lists_cache;
 mod documents_fields;
 mod documents_fields_counts;
+mod fst_set;
+mod integrity;
 mod main;
 mod postings_lists;
 mod synonyms;
 mod updates;
 mod updates_results;
 mod facets;
+mod expirations;
+mod geo;
+mod sortables;
+mod numbers;
+mod presences;
+mod search_analytics;
 
 pub use self::docs_words::DocsWords;
 pub use self::facets::Facets;
+pub use self::fst_set::FstSet;
+pub use self::integrity::IntegrityReport;
+pub use self::expirations::Expirations;
+pub use self::geo::Geo;
+pub use self::sortables::{Sortables, SortableValue};
+pub use self::numbers::{value_numbers, Numbers};
+pub use self::presences::Presences;
+pub use self::search_analytics::{QueryStats, SearchAnalytics, SearchRecord};
 pub use self::prefix_documents_cache::PrefixDocumentsCache;
 pub use self::prefix_postings_lists_cache::PrefixPostingsListsCache;
-pub use self::documents_fields::{DocumentFieldsIter, DocumentsFields};
+pub use self::documents_fields::{AllDocumentsFieldsIter, DocumentFieldsIter, DocumentsFields};
 pub use self::documents_fields_counts::{
     DocumentFieldsCountsIter, DocumentsFieldsCounts, DocumentsIdsIter,
 };
-pub use self::main::Main;
-pub use self::postings_lists::PostingsLists;
+pub use self::main::{FieldCapabilities, FreqsMap, Main, StorageUsage};
+pub use self::postings_lists::{PostingsLists, WordsFrequenciesIter};
 pub use self::synonyms::Synonyms;
-pub use self::updates::Updates;
+pub use self::updates::{UpdateQueueSize, Updates};
 pub use self::updates_results::UpdatesResults;
 
 use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::collections::HashSet;
 use std::convert::TryInto;
+use std::iter::Peekable;
 use std::{mem, ptr};
 
+use chrono::{DateTime, Utc};
 use heed::Result as ZResult;
 use heed::{BytesEncode, BytesDecode};
-use meilisearch_schema::{IndexedPos, FieldId};
+use indexmap::IndexMap;
+use meilisearch_schema::{IndexedPos, FieldId, Schema};
 use sdset::{Set, SetBuf};
 use serde::de::{self, Deserialize};
+use serde::Serialize;
 use zerocopy::{AsBytes, FromBytes};
 
 use crate::criterion::Criteria;
-use crate::database::{MainT, UpdateT};
+use crate::database::{MainT, UpdateQueueLimits, UpdateT, UpdatesRetention};
 use crate::database::{UpdateEvent, UpdateEventsEmitter};
 use crate::serde::Deserializer;
 use crate::settings::SettingsUpdate;
+use crate::suggest::{self, Suggestion};
+use crate::update::MetadataUpdate;
 use crate::{query_builder::QueryBuilder, update, DocIndex, DocumentId, Error, MResult};
 
-type BEU64 = zerocopy::U64<byteorder::BigEndian>;
+pub(crate) type BEU64 = zerocopy::U64<byteorder::BigEndian>;
 pub type BEU16 = zerocopy::U16<byteorder::BigEndian>;
 
 #[derive(Debug, Copy, Clone, AsBytes, FromBytes)]
@@ -91,18 +115,8 @@ impl<'a> BytesEncode<'a> for PostingsCodec {
     type EItem = Postings<'a>;
 
     fn bytes_encode(item: &'a Self::EItem) -> Option<Cow<'a, [u8]>> {
-        let u64_size = mem::size_of::<u64>();
-        let docids_size = item.docids.len() * mem::size_of::<DocumentId>();
-        let matches_size = item.matches.len() * mem::size_of::<DocIndex>();
-
-        let mut buffer = Vec::with_capacity(u64_size + docids_size + matches_size);
-
-        let docids_len = item.docids.len();
-        buffer.extend_from_slice(&docids_len.to_be_bytes());
-        buffer.extend_from_slice(item.docids.as_bytes());
-        buffer.extend_from_slice(item.matches.as_bytes());
-
-        Some(Cow::Owned(buffer))
+        // the documents ids are found again from the matches when decoding
+        Some(Cow::Owned(compressed_postings::encode(&item.matches)))
     }
 }
 
@@ -143,6 +157,13 @@ impl<'a> BytesDecode<'a> for PostingsCodec {
     type DItem = Postings<'a>;
 
     fn bytes_decode(bytes: &'a [u8]) -> Option<Self::DItem> {
+        // the lists written before the compression are still read in place,
+        // they are compressed the next time they are written
+        if bytes.first() == Some(&compressed_postings::COMPRESSED_TAG) {
+            let (docids, matches) = compressed_postings::decode(bytes)?;
+            return Some(Postings { docids: Cow::Owned(docids), matches: Cow::Owned(matches) });
+        }
+
         let u64_size = mem::size_of::<u64>();
         let docid_size = mem::size_of::<DocumentId>();
 
@@ -196,14 +217,164 @@ fn updates_name(name: &str) -> String {
     format!("store-{}-updates", name)
 }
 
+fn updates_queue_size_name(name: &str) -> String {
+    format!("store-{}-updates-queue-size", name)
+}
+
 fn updates_results_name(name: &str) -> String {
     format!("store-{}-updates-results", name)
 }
 
+fn search_analytics_name(name: &str) -> String {
+    format!("store-{}-search-analytics", name)
+}
+
 fn facets_name(name: &str) -> String {
     format!("store-{}-facets", name)
 }
 
+fn numeric_facets_name(name: &str) -> String {
+    format!("store-{}-numeric-facets", name)
+}
+
+fn facet_originals_name(name: &str) -> String {
+    format!("store-{}-facet-originals", name)
+}
+
+fn geo_name(name: &str) -> String {
+    format!("store-{}-geo", name)
+}
+
+fn expirations_name(name: &str) -> String {
+    format!("store-{}-expirations", name)
+}
+
+fn sortables_name(name: &str) -> String {
+    format!("store-{}-sortables", name)
+}
+
+fn numbers_name(name: &str) -> String {
+    format!("store-{}-numbers", name)
+}
+
+fn presences_name(name: &str) -> String {
+    format!("store-{}-presences", name)
+}
+
+/// The number of entries of a database and the size, in bytes, of their keys and
+/// values, the pages of LMDB add their own overhead on top of this size.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseUsage {
+    pub entries: u64,
+    pub size: u64,
+}
+
+impl DatabaseUsage {
+    fn add_entry(&mut self, key_size: usize, value_size: usize) {
+        self.entries += 1;
+        self.size += (key_size + value_size) as u64;
+    }
+}
+
+/// The usage of the biggest databases of an index.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabasesUsage {
+    pub postings_lists: DatabaseUsage,
+    pub docs_words: DatabaseUsage,
+    pub documents_fields: DatabaseUsage,
+    pub facets: DatabaseUsage,
+}
+
+/// The statistics of an index, read from a single transaction of each environment.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexStats {
+    pub number_of_documents: u64,
+    pub is_indexing: bool,
+    pub update_queue: UpdateQueueSize,
+    pub fields_frequency: FreqsMap,
+    pub storage: StorageUsage,
+    pub databases: DatabasesUsage,
+}
+
+/// The order in which the documents of an index are browsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrowseOrder {
+    /// The order of the internal ids, stable but unrelated to the documents ids.
+    InternalId,
+    /// The order of the documents ids, the integers come first in numerical order
+    /// followed by the other ids in lexicographic order.
+    UserId,
+}
+
+impl Default for BrowseOrder {
+    fn default() -> BrowseOrder {
+        BrowseOrder::InternalId
+    }
+}
+
+fn user_ids_order(a: &str, b: &str) -> Ordering {
+    match (a.parse::<i64>(), b.parse::<i64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+/// A lazy iterator over every field of all the documents of an index, the documents are
+/// rebuilt one at a time along with their user id, in the order of their internal ids.
+pub struct AllDocumentsIter<'txn> {
+    fields: Peekable<AllDocumentsFieldsIter<'txn>>,
+    schema: Schema,
+    primary_key: Option<FieldId>,
+    deleted: SetBuf<DocumentId>,
+}
+
+impl AllDocumentsIter<'_> {
+    fn next_document(&mut self) -> MResult<Option<(String, IndexMap<String, serde_json::Value>)>> {
+        let document_id = loop {
+            match self.fields.peek() {
+                Some(Ok((document_id, _, _))) if self.deleted.binary_search(document_id).is_ok() => {
+                    self.fields.next();
+                }
+                Some(Ok((document_id, _, _))) => break *document_id,
+                Some(Err(_)) => return Err(self.fields.next().unwrap().unwrap_err().into()),
+                None => return Ok(None),
+            }
+        };
+
+        let mut user_id = String::new();
+        let mut document = IndexMap::new();
+        while let Some(Ok((id, _, _))) = self.fields.peek() {
+            if *id != document_id {
+                break;
+            }
+
+            let (_, field_id, bytes) = self.fields.next().unwrap()?;
+            let value: serde_json::Value = serde_json::from_slice(&bytes)?;
+            if Some(field_id) == self.primary_key {
+                user_id = update::document_id_to_string(&value).unwrap_or_default();
+            }
+            if let Some(name) = self.schema.name(field_id) {
+                document.insert(name.to_string(), value);
+            }
+        }
+
+        Ok(Some((user_id, document)))
+    }
+}
+
+impl Iterator for AllDocumentsIter<'_> {
+    type Item = MResult<(String, IndexMap<String, serde_json::Value>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_document().transpose()
+    }
+}
+
 #[derive(Clone)]
 pub struct Index {
     pub main: Main,
@@ -211,6 +382,11 @@ pub struct Index {
     pub documents_fields: DocumentsFields,
     pub documents_fields_counts: DocumentsFieldsCounts,
     pub facets: Facets,
+    pub geo: Geo,
+    pub expirations: Expirations,
+    pub sortables: Sortables,
+    pub numbers: Numbers,
+    pub presences: Presences,
     pub synonyms: Synonyms,
     pub docs_words: DocsWords,
     pub prefix_documents_cache: PrefixDocumentsCache,
@@ -218,21 +394,119 @@ pub struct Index {
 
     pub updates: Updates,
     pub updates_results: UpdatesResults,
+    pub search_analytics: SearchAnalytics,
     pub(crate) updates_notifier: UpdateEventsEmitter,
+    pub(crate) progress: update::UpdateProgressEmitter,
 }
 
 impl Index {
+    /// Retrieves the displayed fields of a document, narrowed to the given attributes.
     pub fn document<T: de::DeserializeOwned>(
         &self,
         reader: &heed::RoTxn<MainT>,
         attributes: Option<&HashSet<&str>>,
         document_id: DocumentId,
+    ) -> MResult<Option<T>> {
+        self.document_fields(reader, attributes, document_id, true)
+    }
+
+    /// Retrieves every field of a document, even the ones that are not displayed.
+    ///
+    /// It must not be used to return documents to the users.
+    pub fn full_document<T: de::DeserializeOwned>(
+        &self,
+        reader: &heed::RoTxn<MainT>,
+        document_id: DocumentId,
+    ) -> MResult<Option<T>> {
+        self.document_fields(reader, None, document_id, false)
+    }
+
+    /// Iterates over all the documents, with every field, without keeping them in memory.
+    ///
+    /// It must not be used to return documents to the users, it is meant to export them.
+    pub fn all_documents_iter<'txn>(&self, reader: &'txn heed::RoTxn<MainT>) -> MResult<AllDocumentsIter<'txn>> {
+        let schema = self.main.schema(reader)?.ok_or(Error::SchemaMissing)?;
+        let primary_key = schema.primary_key().and_then(|name| schema.id(name));
+        let fields = self.documents_fields.all_documents_fields(reader)?.peekable();
+        let deleted = self.deleted_documents(reader)?;
+        Ok(AllDocumentsIter { fields, schema, primary_key, deleted })
+    }
+
+    /// Retrieves a page of the displayed documents, narrowed to the given attributes.
+    ///
+    /// Browsing in the order of the documents ids reads the primary key of every document.
+    pub fn browse<T: de::DeserializeOwned>(
+        &self,
+        reader: &heed::RoTxn<MainT>,
+        attributes: Option<&HashSet<&str>>,
+        order: BrowseOrder,
+        offset: usize,
+        limit: usize,
+    ) -> MResult<Vec<T>> {
+        let documents_ids = match order {
+            BrowseOrder::InternalId => {
+                let deleted = self.deleted_documents(reader)?;
+                let iter = self.documents_fields.documents_ids(reader)?
+                    .filter(|result| result.as_ref().map_or(true, |id| deleted.binary_search(id).is_err()))
+                    .skip(offset)
+                    .take(limit);
+                iter.collect::<ZResult<Vec<_>>>()?
+            }
+            BrowseOrder::UserId => {
+                let schema = self.main.schema(reader)?.ok_or(Error::SchemaMissing)?;
+                let primary_key = schema.primary_key().and_then(|name| schema.id(name));
+                let primary_key = match primary_key {
+                    Some(primary_key) => primary_key,
+                    None => return Ok(Vec::new()),
+                };
+
+                let deleted = self.deleted_documents(reader)?;
+                let mut user_ids = Vec::new();
+                for result in self.documents_fields.documents_ids(reader)? {
+                    let document_id = result?;
+                    if deleted.binary_search(&document_id).is_ok() {
+                        continue;
+                    }
+                    let value = self.document_attribute::<serde_json::Value>(reader, document_id, primary_key)?;
+                    let user_id = value.as_ref().and_then(update::document_id_to_string).unwrap_or_default();
+                    user_ids.push((user_id, document_id));
+                }
+
+                user_ids.sort_unstable_by(|(a, _), (b, _)| user_ids_order(a, b));
+                user_ids.into_iter().skip(offset).take(limit).map(|(_, id)| id).collect()
+            }
+        };
+
+        let mut documents = Vec::with_capacity(documents_ids.len());
+        for document_id in documents_ids {
+            if let Some(document) = self.document(reader, attributes, document_id)? {
+                documents.push(document);
+            }
+        }
+
+        Ok(documents)
+    }
+
+    fn document_fields<T: de::DeserializeOwned>(
+        &self,
+        reader: &heed::RoTxn<MainT>,
+        attributes: Option<&HashSet<&str>>,
+        document_id: DocumentId,
+        displayed_only: bool,
     ) -> MResult<Option<T>> {
         let schema = self.main.schema(reader)?;
         let schema = schema.ok_or(Error::SchemaMissing)?;
 
+        if self.is_deleted(reader, document_id)? {
+            return Ok(None);
+        }
+
+        // the nested attributes, e.g. `author.name`, are returned with their top-level field
         let attributes = match attributes {
-            Some(attributes) => Some(attributes.iter().filter_map(|name| schema.id(*name)).collect()),
+            Some(attributes) => Some(attributes.iter().flat_map(|name| {
+                let parents = schema.names().filter(move |parent| update::nested_path(name, parent).is_some());
+                schema.id(*name).into_iter().chain(parents.filter_map(|parent| schema.id(parent)))
+            }).collect()),
             None => None,
         };
 
@@ -242,11 +516,24 @@ impl Index {
             documents_fields: self.documents_fields,
             schema: &schema,
             fields: attributes.as_ref(),
+            displayed_only,
         };
 
         Ok(Option::<T>::deserialize(&mut deserializer)?)
     }
 
+    /// Returns `true` if the document is deleted but not yet purged from the stores.
+    pub fn is_deleted(&self, reader: &heed::RoTxn<MainT>, document_id: DocumentId) -> MResult<bool> {
+        let deleted = self.main.deleted_documents(reader)?;
+        Ok(deleted.map_or(false, |deleted| deleted.binary_search(&document_id).is_ok()))
+    }
+
+    /// The documents deleted but not yet purged from the stores.
+    pub fn deleted_documents(&self, reader: &heed::RoTxn<MainT>) -> MResult<SetBuf<DocumentId>> {
+        let deleted = self.main.deleted_documents(reader)?;
+        Ok(deleted.map(Cow::into_owned).unwrap_or_default())
+    }
+
     pub fn document_attribute<T: de::DeserializeOwned>(
         &self,
         reader: &heed::RoTxn<MainT>,
@@ -257,7 +544,7 @@ impl Index {
             .documents_fields
             .document_attribute(reader, document_id, attribute)?;
         match bytes {
-            Some(bytes) => Ok(Some(serde_json::from_slice(bytes)?)),
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
             None => Ok(None),
         }
     }
@@ -267,7 +554,7 @@ impl Index {
         reader: &'txn heed::RoTxn<MainT>,
         document_id: DocumentId,
         attribute: FieldId,
-    ) -> MResult<Option<&'txn [u8]>> {
+    ) -> MResult<Option<Cow<'txn, [u8]>>> {
         let bytes = self
             .documents_fields
             .document_attribute(reader, document_id, attribute)?;
@@ -277,16 +564,35 @@ impl Index {
         }
     }
 
-    pub fn customs_update(&self, writer: &mut heed::RwTxn<UpdateT>, customs: Vec<u8>) -> ZResult<u64> {
+    pub fn metadata_update(&self, writer: &mut heed::RwTxn<UpdateT>, update: MetadataUpdate) -> MResult<u64> {
         let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
-        update::push_customs_update(writer, self.updates, self.updates_results, customs)
+        update::push_metadata_update(writer, self.updates, self.updates_results, update)
     }
 
-    pub fn settings_update(&self, writer: &mut heed::RwTxn<UpdateT>, update: SettingsUpdate) -> ZResult<u64> {
+    pub fn settings_update(&self, writer: &mut heed::RwTxn<UpdateT>, update: SettingsUpdate) -> MResult<u64> {
         let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
         update::push_settings_update(writer, self.updates, self.updates_results, update)
     }
 
+    /// Enqueues the settings update, or directly registers it as processed when it would
+    /// not change anything and no other update is waiting to be processed.
+    ///
+    /// The main reader must be opened *after* the update writer, this way it sees the
+    /// effects of every update that is no longer in the queue.
+    pub fn settings_update_unless_noop(
+        &self,
+        reader: &heed::RoTxn<MainT>,
+        writer: &mut heed::RwTxn<UpdateT>,
+        update: SettingsUpdate,
+    ) -> MResult<u64> {
+        let queue_is_empty = self.updates.first_update(writer)?.is_none();
+        if queue_is_empty && update::settings_update_is_noop(reader, self, &update)? {
+            let update_id = update::push_noop_settings_update(writer, self.updates, self.updates_results, update)?;
+            return Ok(update_id);
+        }
+        self.settings_update(writer, update)
+    }
+
     pub fn documents_addition<D>(&self) -> update::DocumentsAddition<D> {
         update::DocumentsAddition::new(
             self.updates,
@@ -311,11 +617,158 @@ impl Index {
         )
     }
 
+    pub fn updates_batch(&self) -> update::UpdatesBatch {
+        update::UpdatesBatch::new(
+            self.updates,
+            self.updates_results,
+            self.updates_notifier.clone(),
+        )
+    }
+
     pub fn clear_all(&self, writer: &mut heed::RwTxn<UpdateT>) -> MResult<u64> {
         let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
         update::push_clear_all(writer, self.updates, self.updates_results)
     }
 
+    /// Enqueues an update received from another database under the id it had there,
+    /// returns `false` if the update is already enqueued or processed.
+    pub fn replicate_update(
+        &self,
+        writer: &mut heed::RwTxn<UpdateT>,
+        update_id: u64,
+        update: update::Update,
+    ) -> MResult<bool> {
+        if self.updates.get(writer, update_id)?.is_some()
+            || self.updates_results.update_result(writer, update_id)?.is_some()
+        {
+            return Ok(false);
+        }
+
+        let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
+        self.updates.put_update(writer, update_id, &update)?;
+        Ok(true)
+    }
+
+    /// Returns the words of this index at most `max_distance` typos away from the term,
+    /// the closest and the most frequent first.
+    pub fn suggest(&self, reader: &heed::RoTxn<MainT>, term: &str, max_distance: u8) -> MResult<Vec<Suggestion>> {
+        suggest::suggest(reader, self, term, max_distance)
+    }
+
+    /// Rewrites the query with the suggested words of its terms that match nothing.
+    pub fn suggested_query(&self, reader: &heed::RoTxn<MainT>, query: &str) -> MResult<Option<String>> {
+        suggest::suggested_query(reader, self, query)
+    }
+
+    /// Iterates over the words of the dictionary of this index along with the number
+    /// of documents they appear in, without keeping them in memory.
+    pub fn words_frequencies<'txn>(&self, reader: &'txn heed::RoTxn<MainT>) -> MResult<WordsFrequenciesIter<'txn>> {
+        Ok(self.postings_lists.words_frequencies(reader)?)
+    }
+
+    /// Verifies the invariants between the stores of this index, an index that
+    /// is not sound can be fixed by a repair.
+    pub fn check_integrity(&self, reader: &heed::RoTxn<MainT>) -> MResult<IntegrityReport> {
+        integrity::check_integrity(reader, self)
+    }
+
+    /// Enqueues an update that physically removes the deleted documents from the stores.
+    pub fn purge_deleted_documents(&self, writer: &mut heed::RwTxn<UpdateT>) -> MResult<u64> {
+        let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
+        update::push_documents_purge(writer, self.updates, self.updates_results)
+    }
+
+    /// Enqueues an update that rebuilds the derived stores of this index from its documents.
+    pub fn repair(&self, writer: &mut heed::RwTxn<UpdateT>) -> MResult<u64> {
+        let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
+        update::push_repair(writer, self.updates, self.updates_results)
+    }
+
+    /// Deletes the processed and failed updates enqueued before the given id and
+    /// processed before the given date, returns the number of purged updates and
+    /// the amount of bytes freed in the updates store.
+    pub fn purge_updates(
+        &self,
+        writer: &mut heed::RwTxn<UpdateT>,
+        before_id: Option<u64>,
+        before_date: Option<DateTime<Utc>>,
+    ) -> MResult<(usize, usize)> {
+        let purged = self.updates_results.purge(writer, |result| {
+            before_id.map_or(true, |id| result.update_id < id)
+                && before_date.map_or(true, |date| result.processed_at < date)
+        })?;
+        Ok(purged)
+    }
+
+    /// Removes the update from the queue, it is then reported as canceled.
+    pub fn cancel_update(&self, writer: &mut heed::RwTxn<UpdateT>, update_id: u64) -> MResult<update::CancelOutcome> {
+        update::cancel_update(writer, self.updates, self.updates_results, update_id)
+    }
+
+    /// Deletes the processed and failed updates enqueued before the given id,
+    /// returns the number of deleted updates.
+    pub fn clear_processed_updates(&self, writer: &mut heed::RwTxn<UpdateT>, before_update_id: u64) -> MResult<usize> {
+        let (cleared, _) = self.purge_updates(writer, Some(before_update_id), None)?;
+        Ok(cleared)
+    }
+
+    /// Deletes the oldest updates results that are not retained anymore,
+    /// returns the number of deleted updates.
+    pub fn apply_updates_retention(
+        &self,
+        writer: &mut heed::RwTxn<UpdateT>,
+        retention: UpdatesRetention,
+    ) -> MResult<usize> {
+        let first_kept_id = match retention.max_entries {
+            Some(max_entries) => {
+                let ids = self.updates_results.update_ids(writer)?;
+                ids.len().checked_sub(max_entries).and_then(|n| ids.get(n).copied())
+            }
+            None => None,
+        };
+        let oldest_kept_date = retention.max_age.map(|max_age| Utc::now() - max_age);
+
+        if first_kept_id.is_none() && oldest_kept_date.is_none() {
+            return Ok(0);
+        }
+
+        let (deleted, _) = self.updates_results.purge(writer, |result| {
+            first_kept_id.map_or(false, |id| result.update_id < id)
+                || oldest_kept_date.map_or(false, |date| result.processed_at < date)
+        })?;
+        Ok(deleted)
+    }
+
+    /// Returns the number of enqueued updates and the size of their payloads, the
+    /// updates are refused once one of the limits of the queue is reached.
+    pub fn update_queue_size(&self, reader: &heed::RoTxn<UpdateT>) -> MResult<UpdateQueueSize> {
+        Ok(self.updates.queue_size(reader)?)
+    }
+
+    /// Returns an error if the update queue of the index is full.
+    pub fn check_update_queue(&self, reader: &heed::RoTxn<UpdateT>) -> MResult<()> {
+        self.updates.check_limits(reader, 0)
+    }
+
+    /// Reads the statistics of the index, all the entries of its biggest databases are read.
+    pub fn stats(&self, reader: &heed::RoTxn<MainT>, update_reader: &heed::RoTxn<UpdateT>) -> MResult<IndexStats> {
+        let databases = DatabasesUsage {
+            postings_lists: self.postings_lists.usage(reader)?,
+            docs_words: self.docs_words.usage(reader)?,
+            documents_fields: self.documents_fields.usage(reader)?,
+            facets: self.facets.usage(reader)?,
+        };
+
+        Ok(IndexStats {
+            number_of_documents: self.main.number_of_documents(reader)?,
+            is_indexing: self.current_update_id(update_reader)?.is_some(),
+            update_queue: self.update_queue_size(update_reader)?,
+            fields_frequency: self.main.fields_frequency(reader)?.unwrap_or_default(),
+            storage: self.main.storage_usage(reader)?,
+            databases,
+        })
+    }
+
     pub fn current_update_id(&self, reader: &heed::RoTxn<UpdateT>) -> MResult<Option<u64>> {
         match self.updates.last_update(reader)? {
             Some((id, _)) => Ok(Some(id)),
@@ -323,12 +776,21 @@ impl Index {
         }
     }
 
+    /// The last progress reported by the update being processed, if any.
+    pub fn update_progress(&self) -> Option<update::UpdateProgress> {
+        self.progress.progress()
+    }
+
     pub fn update_status(
         &self,
         reader: &heed::RoTxn<UpdateT>,
         update_id: u64,
     ) -> MResult<Option<update::UpdateStatus>> {
-        update::update_status(reader, self.updates, self.updates_results, update_id)
+        let mut status = update::update_status(reader, self.updates, self.updates_results, update_id)?;
+        if let Some(update::UpdateStatus::Enqueued { content }) = &mut status {
+            content.progress = self.update_progress().filter(|p| p.update_id == update_id);
+        }
+        Ok(status)
     }
 
     pub fn all_updates_status(&self, reader: &heed::RoTxn<UpdateT>) -> MResult<Vec<update::UpdateStatus>> {
@@ -376,6 +838,7 @@ pub fn create(
     update_env: &heed::Env,
     name: &str,
     updates_notifier: UpdateEventsEmitter,
+    update_queue_limits: UpdateQueueLimits,
 ) -> MResult<Index> {
     // create all the store names
     let main_name = main_name(name);
@@ -387,8 +850,17 @@ pub fn create(
     let prefix_documents_cache_name = prefix_documents_cache_name(name);
     let prefix_postings_lists_cache_name = prefix_postings_lists_cache_name(name);
     let updates_name = updates_name(name);
+    let updates_queue_size_name = updates_queue_size_name(name);
     let updates_results_name = updates_results_name(name);
+    let search_analytics_name = search_analytics_name(name);
     let facets_name = facets_name(name);
+    let numeric_facets_name = numeric_facets_name(name);
+    let facet_originals_name = facet_originals_name(name);
+    let geo_name = geo_name(name);
+    let expirations_name = expirations_name(name);
+    let sortables_name = sortables_name(name);
+    let numbers_name = numbers_name(name);
+    let presences_name = presences_name(name);
 
     // open all the stores
     let main = env.create_poly_database(Some(&main_name))?;
@@ -396,12 +868,21 @@ pub fn create(
     let documents_fields = env.create_database(Some(&documents_fields_name))?;
     let documents_fields_counts = env.create_database(Some(&documents_fields_counts_name))?;
     let facets = env.create_database(Some(&facets_name))?;
+    let numeric_facets = env.create_database(Some(&numeric_facets_name))?;
+    let facet_originals = env.create_database(Some(&facet_originals_name))?;
+    let geo = env.create_database(Some(&geo_name))?;
+    let expirations = env.create_database(Some(&expirations_name))?;
+    let sortables = env.create_database(Some(&sortables_name))?;
+    let numbers = env.create_database(Some(&numbers_name))?;
+    let presences = env.create_database(Some(&presences_name))?;
     let synonyms = env.create_database(Some(&synonyms_name))?;
     let docs_words = env.create_database(Some(&docs_words_name))?;
     let prefix_documents_cache = env.create_database(Some(&prefix_documents_cache_name))?;
     let prefix_postings_lists_cache = env.create_database(Some(&prefix_postings_lists_cache_name))?;
     let updates = update_env.create_database(Some(&updates_name))?;
+    let updates_queue_size = update_env.create_database(Some(&updates_queue_size_name))?;
     let updates_results = update_env.create_database(Some(&updates_results_name))?;
+    let search_analytics = update_env.create_database(Some(&search_analytics_name))?;
 
     Ok(Index {
         main: Main { main },
@@ -412,11 +893,18 @@ pub fn create(
         docs_words: DocsWords { docs_words },
         prefix_postings_lists_cache: PrefixPostingsListsCache { prefix_postings_lists_cache },
         prefix_documents_cache: PrefixDocumentsCache { prefix_documents_cache },
-        facets: Facets { facets },
-
-        updates: Updates { updates },
+        facets: Facets { facets, numeric_facets, originals: facet_originals },
+        geo: Geo { geo },
+        expirations: Expirations { expirations },
+        sortables: Sortables { sortables },
+        numbers: Numbers { numbers },
+        presences: Presences { presences },
+
+        updates: Updates { updates, queue_size: updates_queue_size, limits: update_queue_limits },
         updates_results: UpdatesResults { updates_results },
+        search_analytics: SearchAnalytics { searches: search_analytics },
         updates_notifier,
+        progress: update::UpdateProgressEmitter::default(),
     })
 }
 
@@ -425,6 +913,7 @@ pub fn open(
     update_env: &heed::Env,
     name: &str,
     updates_notifier: UpdateEventsEmitter,
+    update_queue_limits: UpdateQueueLimits,
 ) -> MResult<Option<Index>> {
     // create all the store names
     let main_name = main_name(name);
@@ -435,9 +924,18 @@ pub fn open(
     let docs_words_name = docs_words_name(name);
     let prefix_documents_cache_name = prefix_documents_cache_name(name);
     let facets_name = facets_name(name);
+    let numeric_facets_name = numeric_facets_name(name);
+    let facet_originals_name = facet_originals_name(name);
+    let geo_name = geo_name(name);
+    let expirations_name = expirations_name(name);
+    let sortables_name = sortables_name(name);
+    let numbers_name = numbers_name(name);
+    let presences_name = presences_name(name);
     let prefix_postings_lists_cache_name = prefix_postings_lists_cache_name(name);
     let updates_name = updates_name(name);
+    let updates_queue_size_name = updates_queue_size_name(name);
     let updates_results_name = updates_results_name(name);
+    let search_analytics_name = search_analytics_name(name);
 
     // open all the stores
     let main = match env.open_poly_database(Some(&main_name))? {
@@ -472,6 +970,19 @@ pub fn open(
         Some(facets) => facets,
         None => return Ok(None),
     };
+    // the indexes created before the numeric facets existed didn't accept numeric facet
+    // values, the database can safely be created empty
+    let numeric_facets = env.create_database(Some(&numeric_facets_name))?;
+    // the original facet values are only displayed, they are found again
+    // when the facets are reindexed
+    let facet_originals = env.create_database(Some(&facet_originals_name))?;
+    // the same goes for the coordinates, the expirations, the sortable values, the numbers and the fields presences,
+    // the documents indexed before these stores existed are found again after a repair
+    let geo = env.create_database(Some(&geo_name))?;
+    let expirations = env.create_database(Some(&expirations_name))?;
+    let sortables = env.create_database(Some(&sortables_name))?;
+    let numbers = env.create_database(Some(&numbers_name))?;
+    let presences = env.create_database(Some(&presences_name))?;
     let prefix_postings_lists_cache = match env.open_database(Some(&prefix_postings_lists_cache_name))? {
         Some(prefix_postings_lists_cache) => prefix_postings_lists_cache,
         None => return Ok(None),
@@ -480,10 +991,14 @@ pub fn open(
         Some(updates) => updates,
         None => return Ok(None),
     };
+    // the size of the queues enqueued before this store existed is computed once
+    let updates_queue_size = update_env.create_database(Some(&updates_queue_size_name))?;
     let updates_results = match update_env.open_database(Some(&updates_results_name))? {
         Some(updates_results) => updates_results,
         None => return Ok(None),
     };
+    // the searches are only recorded from now on
+    let search_analytics = update_env.create_database(Some(&search_analytics_name))?;
 
     Ok(Some(Index {
         main: Main { main },
@@ -493,14 +1008,124 @@ pub fn open(
         synonyms: Synonyms { synonyms },
         docs_words: DocsWords { docs_words },
         prefix_documents_cache: PrefixDocumentsCache { prefix_documents_cache },
-        facets: Facets { facets },
+        facets: Facets { facets, numeric_facets, originals: facet_originals },
+        geo: Geo { geo },
+        expirations: Expirations { expirations },
+        sortables: Sortables { sortables },
+        numbers: Numbers { numbers },
+        presences: Presences { presences },
         prefix_postings_lists_cache: PrefixPostingsListsCache { prefix_postings_lists_cache },
-        updates: Updates { updates },
+        updates: Updates { updates, queue_size: updates_queue_size, limits: update_queue_limits },
         updates_results: UpdatesResults { updates_results },
+        search_analytics: SearchAnalytics { searches: search_analytics },
         updates_notifier,
+        progress: update::UpdateProgressEmitter::default(),
     }))
 }
 
+/// The number of entries read from a store before they are written into the copy.
+const COPY_CHUNK_SIZE: usize = 10_000;
+
+/// The stores of an index paired with the ones of its copy, they must be opened
+/// before the write transactions as opening a database requires one.
+pub(crate) struct StoresCopy {
+    main: Vec<(heed::PolyDatabase, heed::PolyDatabase)>,
+    updates: Vec<(heed::PolyDatabase, heed::PolyDatabase)>,
+}
+
+impl StoresCopy {
+    /// Pairs the stores named after `source` with the ones named after `destination`,
+    /// the updates and their results are only paired when `with_updates` is set.
+    pub(crate) fn open(
+        env: &heed::Env,
+        update_env: &heed::Env,
+        source: &str,
+        destination: &str,
+        with_updates: bool,
+    ) -> MResult<StoresCopy> {
+        let main_names: &[fn(&str) -> String] = &[
+            main_name,
+            postings_lists_name,
+            documents_fields_name,
+            documents_fields_counts_name,
+            synonyms_name,
+            docs_words_name,
+            prefix_documents_cache_name,
+            prefix_postings_lists_cache_name,
+            facets_name,
+            numeric_facets_name,
+            facet_originals_name,
+            geo_name,
+            expirations_name,
+            sortables_name,
+            numbers_name,
+            presences_name,
+        ];
+        let update_names: &[fn(&str) -> String] = if with_updates {
+            &[updates_name, updates_queue_size_name, updates_results_name]
+        } else {
+            &[]
+        };
+
+        let pair = |env: &heed::Env, name: &fn(&str) -> String| -> MResult<_> {
+            let source = env.create_poly_database(Some(&name(source)))?;
+            let destination = env.create_poly_database(Some(&name(destination)))?;
+            Ok((source, destination))
+        };
+
+        Ok(StoresCopy {
+            main: main_names.iter().map(|name| pair(env, name)).collect::<MResult<_>>()?,
+            updates: update_names.iter().map(|name| pair(update_env, name)).collect::<MResult<_>>()?,
+        })
+    }
+
+    /// Replaces the content of the destination stores by the one of the source stores.
+    pub(crate) fn apply(
+        &self,
+        writer: &mut heed::RwTxn<MainT>,
+        update_writer: &mut heed::RwTxn<UpdateT>,
+    ) -> MResult<()> {
+        for (source, destination) in &self.main {
+            copy_store(writer, *source, *destination)?;
+        }
+        for (source, destination) in &self.updates {
+            copy_store(update_writer, *source, *destination)?;
+        }
+        Ok(())
+    }
+}
+
+fn copy_store<T>(
+    writer: &mut heed::RwTxn<T>,
+    source: heed::PolyDatabase,
+    destination: heed::PolyDatabase,
+) -> ZResult<()> {
+    use std::ops::Bound::{Excluded, Unbounded};
+    use heed::types::ByteSlice;
+
+    destination.clear(writer)?;
+
+    // the entries are copied by chunks as the source can't be read while the copy is written
+    let mut last_key: Option<Vec<u8>> = None;
+    loop {
+        let mut chunk = Vec::with_capacity(COPY_CHUNK_SIZE);
+        let start = last_key.as_deref().map_or(Unbounded, Excluded);
+        for result in source.range::<_, ByteSlice, ByteSlice, _>(writer, &(start, Unbounded))?.take(COPY_CHUNK_SIZE) {
+            let (key, value) = result?;
+            chunk.push((key.to_vec(), value.to_vec()));
+        }
+
+        for (key, value) in &chunk {
+            destination.put::<_, ByteSlice, ByteSlice>(writer, key, value)?;
+        }
+
+        match chunk.pop() {
+            Some((key, _)) => last_key = Some(key),
+            None => return Ok(()),
+        }
+    }
+}
+
 pub fn clear(
     writer: &mut heed::RwTxn<MainT>,
     update_writer: &mut heed::RwTxn<UpdateT>,
@@ -511,11 +1136,17 @@ pub fn clear(
     index.postings_lists.clear(writer)?;
     index.documents_fields.clear(writer)?;
     index.documents_fields_counts.clear(writer)?;
+    index.geo.clear(writer)?;
+    index.expirations.clear(writer)?;
+    index.sortables.clear(writer)?;
+    index.numbers.clear(writer)?;
+    index.presences.clear(writer)?;
     index.synonyms.clear(writer)?;
     index.docs_words.clear(writer)?;
     index.prefix_documents_cache.clear(writer)?;
     index.prefix_postings_lists_cache.clear(writer)?;
     index.updates.clear(update_writer)?;
     index.updates_results.clear(update_writer)?;
+    index.search_analytics.clear(update_writer)?;
     Ok(())
 }
@@ -0,0 +1,95 @@
+use heed::types::{OwnedType, Unit};
+use heed::Result as ZResult;
+use meilisearch_schema::FieldId;
+use sdset::SetBuf;
+use serde_json::Value;
+use zerocopy::{AsBytes, FromBytes};
+
+use super::{BEU16, BEU64};
+use crate::database::MainT;
+use crate::facets::ordered_f64_bits;
+use crate::DocumentId;
+
+/// A number of a document field, the keys are ordered by field, then by value.
+#[derive(Debug, Copy, Clone, AsBytes, FromBytes)]
+#[repr(C)]
+pub struct NumberKey {
+    field_id: BEU16,
+    value: BEU64,
+    docid: BEU64,
+}
+
+impl NumberKey {
+    fn new(field_id: FieldId, value: f64, docid: DocumentId) -> NumberKey {
+        NumberKey {
+            field_id: BEU16::new(field_id.0),
+            value: BEU64::new(ordered_f64_bits(value)),
+            docid: BEU64::new(docid.0),
+        }
+    }
+}
+
+/// Returns the numbers of a value, the numbers of an array are all returned.
+pub fn value_numbers(value: &Value) -> Vec<f64> {
+    match value {
+        Value::Number(number) => number.as_f64().into_iter().collect(),
+        Value::Array(values) => values.iter().filter_map(Value::as_f64).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The numbers of the documents fields, stored in order to be filtered by range.
+#[derive(Copy, Clone)]
+pub struct Numbers {
+    pub(crate) numbers: heed::Database<OwnedType<NumberKey>, Unit>,
+}
+
+impl Numbers {
+    pub fn put_document_number(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        field_id: FieldId,
+        value: f64,
+        document_id: DocumentId,
+    ) -> ZResult<()> {
+        let key = NumberKey::new(field_id, value, document_id);
+        self.numbers.put(writer, &key, &())
+    }
+
+    pub fn del_document_number(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        field_id: FieldId,
+        value: f64,
+        document_id: DocumentId,
+    ) -> ZResult<bool> {
+        let key = NumberKey::new(field_id, value, document_id);
+        self.numbers.delete(writer, &key)
+    }
+
+    /// Returns the documents with a number of the field between `from` and `to`, both included.
+    pub fn range_document_ids(
+        self,
+        reader: &heed::RoTxn<MainT>,
+        field_id: FieldId,
+        from: f64,
+        to: f64,
+    ) -> ZResult<SetBuf<DocumentId>> {
+        let start = NumberKey::new(field_id, from, DocumentId(u64::min_value()));
+        let end = NumberKey::new(field_id, to, DocumentId(u64::max_value()));
+
+        let mut document_ids = Vec::new();
+        if from <= to {
+            for result in self.numbers.range(reader, &(start..=end))? {
+                let (key, ()) = result?;
+                document_ids.push(DocumentId(key.docid.get()));
+            }
+        }
+
+        Ok(SetBuf::from_dirty(document_ids))
+    }
+
+    pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
+        self.numbers.clear(writer)
+    }
+}
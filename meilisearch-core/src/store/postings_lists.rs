@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::str;
 
 use heed::Result as ZResult;
 use heed::types::ByteSlice;
@@ -7,7 +8,7 @@ use slice_group_by::GroupBy;
 
 use crate::database::MainT;
 use crate::DocIndex;
-use crate::store::{Postings, PostingsCodec};
+use crate::store::{compressed_postings, DatabaseUsage, Postings, PostingsCodec};
 
 #[derive(Copy, Clone)]
 pub struct PostingsLists {
@@ -37,6 +38,16 @@ impl PostingsLists {
         self.postings_lists.clear(writer)
     }
 
+    /// The postings lists sizes are the ones of their compressed representation.
+    pub fn usage(self, reader: &heed::RoTxn<MainT>) -> ZResult<DatabaseUsage> {
+        let mut usage = DatabaseUsage::default();
+        for result in self.postings_lists.iter(reader)? {
+            let (word, postings) = result?;
+            usage.add_entry(word.len(), compressed_postings::encode(&postings.matches).len());
+        }
+        Ok(usage)
+    }
+
     pub fn postings_list<'txn>(
         self,
         reader: &'txn heed::RoTxn<MainT>,
@@ -44,4 +55,30 @@ impl PostingsLists {
     ) -> ZResult<Option<Postings<'txn>>> {
         self.postings_lists.get(reader, word)
     }
+
+    /// Iterates over the indexed words in lexicographic order, along with
+    /// the number of documents containing them.
+    pub fn words_frequencies<'txn>(self, reader: &'txn heed::RoTxn<MainT>) -> ZResult<WordsFrequenciesIter<'txn>> {
+        let iter = self.postings_lists.iter(reader)?;
+        Ok(WordsFrequenciesIter { iter })
+    }
+}
+
+pub struct WordsFrequenciesIter<'txn> {
+    iter: heed::RoIter<'txn, ByteSlice, PostingsCodec>,
+}
+
+impl<'txn> Iterator for WordsFrequenciesIter<'txn> {
+    type Item = ZResult<(&'txn str, usize)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.iter.next() {
+            Some(Ok((word, postings))) => match str::from_utf8(word) {
+                Ok(word) => Some(Ok((word, postings.docids.len()))),
+                Err(_) => Some(Err(heed::Error::Decoding)),
+            },
+            Some(Err(e)) => Some(Err(e)),
+            None => None,
+        }
+    }
 }
@@ -0,0 +1,77 @@
+use heed::types::{OwnedType, Unit};
+use heed::Result as ZResult;
+use meilisearch_schema::FieldId;
+use sdset::SetBuf;
+use zerocopy::{AsBytes, FromBytes};
+
+use super::{BEU16, BEU64};
+use crate::database::MainT;
+use crate::DocumentId;
+
+/// A field present in a document, the keys are ordered by field, then by document.
+#[derive(Debug, Copy, Clone, AsBytes, FromBytes)]
+#[repr(C)]
+pub struct PresenceKey {
+    field_id: BEU16,
+    docid: BEU64,
+}
+
+impl PresenceKey {
+    fn new(field_id: FieldId, docid: DocumentId) -> PresenceKey {
+        PresenceKey {
+            field_id: BEU16::new(field_id.0),
+            docid: BEU64::new(docid.0),
+        }
+    }
+}
+
+/// The fields that each document contains, whatever their value, even `null`.
+#[derive(Copy, Clone)]
+pub struct Presences {
+    pub(crate) presences: heed::Database<OwnedType<PresenceKey>, Unit>,
+}
+
+impl Presences {
+    pub fn put_document_field(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        field_id: FieldId,
+        document_id: DocumentId,
+    ) -> ZResult<()> {
+        let key = PresenceKey::new(field_id, document_id);
+        self.presences.put(writer, &key, &())
+    }
+
+    pub fn del_document_field(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        field_id: FieldId,
+        document_id: DocumentId,
+    ) -> ZResult<bool> {
+        let key = PresenceKey::new(field_id, document_id);
+        self.presences.delete(writer, &key)
+    }
+
+    /// Returns the documents that contain the field.
+    pub fn field_document_ids(
+        self,
+        reader: &heed::RoTxn<MainT>,
+        field_id: FieldId,
+    ) -> ZResult<SetBuf<DocumentId>> {
+        let start = PresenceKey::new(field_id, DocumentId(u64::min_value()));
+        let end = PresenceKey::new(field_id, DocumentId(u64::max_value()));
+
+        let mut document_ids = Vec::new();
+        for result in self.presences.range(reader, &(start..=end))? {
+            let (key, ()) = result?;
+            document_ids.push(DocumentId(key.docid.get()));
+        }
+
+        // the keys are ordered by document for the same field
+        Ok(SetBuf::new_unchecked(document_ids))
+    }
+
+    pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
+        self.presences.clear(writer)
+    }
+}
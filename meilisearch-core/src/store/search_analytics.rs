@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use heed::types::{OwnedType, SerdeJson};
+use heed::Result as ZResult;
+use serde::{Deserialize, Serialize};
+
+use super::BEU64;
+use crate::database::UpdateT;
+
+/// A search made on an index, as recorded by the search analytics.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchRecord {
+    pub query: String,
+    pub nb_hits: usize,
+    pub processing_time_ms: usize,
+    pub searched_at: DateTime<Utc>,
+}
+
+/// The searches of a query string aggregated over a time window.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryStats {
+    pub query: String,
+    pub count: usize,
+    pub average_nb_hits: f64,
+    pub average_processing_time_ms: f64,
+    pub last_searched_at: DateTime<Utc>,
+}
+
+/// The last searches made on an index, the oldest ones are deleted
+/// once the capacity of the store is reached.
+#[derive(Copy, Clone)]
+pub struct SearchAnalytics {
+    pub(crate) searches: heed::Database<OwnedType<BEU64>, SerdeJson<SearchRecord>>,
+}
+
+impl SearchAnalytics {
+    /// Records a search, the oldest records are deleted to keep at most `capacity` of them.
+    pub fn record(
+        self,
+        writer: &mut heed::RwTxn<UpdateT>,
+        record: &SearchRecord,
+        capacity: usize,
+    ) -> ZResult<()> {
+        if capacity == 0 {
+            return Ok(());
+        }
+
+        let id = match self.searches.last(writer)? {
+            Some((key, _)) => key.get() + 1,
+            None => 0,
+        };
+        self.searches.put(writer, &BEU64::new(id), record)?;
+
+        // the ids are contiguous, everything before the first kept id is deleted
+        let first_kept_id = (id + 1).saturating_sub(capacity as u64);
+        while let Some((key, _)) = self.searches.first(writer)? {
+            if key.get() >= first_kept_id {
+                break;
+            }
+            self.searches.delete(writer, &key)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the most searched queries since the given date, the most frequent first.
+    pub fn top_queries(
+        self,
+        reader: &heed::RoTxn<UpdateT>,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> ZResult<Vec<QueryStats>> {
+        self.aggregate(reader, since, limit, |_| true)
+    }
+
+    /// Returns the queries that didn't return any hit since the given date, the most frequent first.
+    pub fn zero_result_queries(
+        self,
+        reader: &heed::RoTxn<UpdateT>,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> ZResult<Vec<QueryStats>> {
+        self.aggregate(reader, since, limit, |record| record.nb_hits == 0)
+    }
+
+    fn aggregate<F>(
+        self,
+        reader: &heed::RoTxn<UpdateT>,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+        predicate: F,
+    ) -> ZResult<Vec<QueryStats>>
+    where
+        F: Fn(&SearchRecord) -> bool,
+    {
+        let mut queries: HashMap<String, (usize, usize, usize, DateTime<Utc>)> = HashMap::new();
+
+        // the records are ordered by date, from the oldest to the newest
+        for result in self.searches.iter(reader)? {
+            let (_, record) = result?;
+            if since.map_or(false, |since| record.searched_at < since) || !predicate(&record) {
+                continue;
+            }
+
+            let entry = queries.entry(record.query).or_insert((0, 0, 0, record.searched_at));
+            entry.0 += 1;
+            entry.1 += record.nb_hits;
+            entry.2 += record.processing_time_ms;
+            entry.3 = record.searched_at;
+        }
+
+        let mut stats: Vec<_> = queries
+            .into_iter()
+            .map(|(query, (count, nb_hits, processing_time_ms, last_searched_at))| QueryStats {
+                query,
+                count,
+                average_nb_hits: nb_hits as f64 / count as f64,
+                average_processing_time_ms: processing_time_ms as f64 / count as f64,
+                last_searched_at,
+            })
+            .collect();
+
+        stats.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.query.cmp(&b.query)));
+        stats.truncate(limit);
+
+        Ok(stats)
+    }
+
+    pub fn clear(self, writer: &mut heed::RwTxn<UpdateT>) -> ZResult<()> {
+        self.searches.clear(writer)
+    }
+}
@@ -0,0 +1,71 @@
+use heed::types::{OwnedType, SerdeBincode};
+use heed::Result as ZResult;
+use meilisearch_schema::FieldId;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::DocumentFieldStoredKey;
+use crate::database::MainT;
+use crate::update::value_to_number;
+use crate::{DocumentId, Number};
+
+/// The value of a sortable attribute, numbers are placed before strings.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum SortableValue {
+    Number(Number),
+    String(String),
+}
+
+impl SortableValue {
+    /// Strings are compared case insensitively, arrays, objects and nulls can't be sorted.
+    pub fn from_value(value: &Value) -> Option<SortableValue> {
+        match value {
+            Value::Bool(_) | Value::Number(_) => value_to_number(value).map(SortableValue::Number),
+            Value::String(string) => Some(SortableValue::String(string.to_lowercase())),
+            Value::Null | Value::Array(_) | Value::Object(_) => None,
+        }
+    }
+}
+
+/// The values of the sortable attributes of each document.
+#[derive(Copy, Clone)]
+pub struct Sortables {
+    pub(crate) sortables: heed::Database<OwnedType<DocumentFieldStoredKey>, SerdeBincode<SortableValue>>,
+}
+
+impl Sortables {
+    pub fn put_document_value(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        document_id: DocumentId,
+        field: FieldId,
+        value: &SortableValue,
+    ) -> ZResult<()> {
+        let key = DocumentFieldStoredKey::new(document_id, field);
+        self.sortables.put(writer, &key, value)
+    }
+
+    pub fn del_all_document_values(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        document_id: DocumentId,
+    ) -> ZResult<usize> {
+        let start = DocumentFieldStoredKey::new(document_id, FieldId::min());
+        let end = DocumentFieldStoredKey::new(document_id, FieldId::max());
+        self.sortables.delete_range(writer, &(start..=end))
+    }
+
+    pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
+        self.sortables.clear(writer)
+    }
+
+    pub fn document_value(
+        self,
+        reader: &heed::RoTxn<MainT>,
+        document_id: DocumentId,
+        field: FieldId,
+    ) -> ZResult<Option<SortableValue>> {
+        let key = DocumentFieldStoredKey::new(document_id, field);
+        self.sortables.get(reader, &key)
+    }
+}
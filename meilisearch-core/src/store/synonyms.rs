@@ -23,6 +23,37 @@ impl Synonyms {
         self.synonyms.delete(writer, word)
     }
 
+    pub fn put_localized_synonyms(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        language: &str,
+        word: &[u8],
+        synonyms: &fst::Set,
+    ) -> ZResult<()> {
+        let key = localized_key(language, word);
+        self.put_synonyms(writer, &key, synonyms)
+    }
+
+    pub fn del_localized_synonyms(
+        self,
+        writer: &mut heed::RwTxn<MainT>,
+        language: &str,
+        word: &[u8],
+    ) -> ZResult<bool> {
+        let key = localized_key(language, word);
+        self.del_synonyms(writer, &key)
+    }
+
+    pub fn localized_synonyms(
+        self,
+        reader: &heed::RoTxn<MainT>,
+        language: &str,
+        word: &[u8],
+    ) -> ZResult<Option<fst::Set>> {
+        let key = localized_key(language, word);
+        self.synonyms(reader, &key)
+    }
+
     pub fn clear(self, writer: &mut heed::RwTxn<MainT>) -> ZResult<()> {
         self.synonyms.clear(writer)
     }
@@ -39,3 +70,13 @@ impl Synonyms {
         }
     }
 }
+
+/// The synonyms of a language are stored along the global ones, their key is
+/// prefixed by the language and a nul byte that can't appear in a word.
+fn localized_key(language: &str, word: &[u8]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(language.len() + 1 + word.len());
+    key.extend_from_slice(language.as_bytes());
+    key.push(0);
+    key.extend_from_slice(word);
+    key
+}
@@ -1,19 +1,44 @@
 use super::BEU64;
-use crate::database::UpdateT;
+use crate::database::{UpdateQueueLimits, UpdateT};
+use crate::error::{Error, QueueLimit};
 use crate::update::Update;
-use heed::types::{OwnedType, SerdeJson};
+use crate::MResult;
+use heed::types::{ByteSlice, OwnedType, SerdeBincode, Str};
 use heed::Result as ZResult;
+use serde::{Deserialize, Serialize};
 
+const QUEUE_SIZE_KEY: &str = "queue-size";
+
+/// The updates are stored as raw json, the size of the queue is kept up to date
+/// along with them, the limits are checked without reading the pending updates.
 #[derive(Copy, Clone)]
 pub struct Updates {
-    pub(crate) updates: heed::Database<OwnedType<BEU64>, SerdeJson<Update>>,
+    pub(crate) updates: heed::Database<OwnedType<BEU64>, ByteSlice>,
+    pub(crate) queue_size: heed::Database<Str, SerdeBincode<UpdateQueueSize>>,
+    pub(crate) limits: UpdateQueueLimits,
+}
+
+/// The number of updates waiting to be processed and the size of their payloads.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateQueueSize {
+    pub pending_updates: u64,
+    pub pending_bytes: u64,
+}
+
+fn decode_update(bytes: &[u8]) -> ZResult<Update> {
+    serde_json::from_slice(bytes).map_err(|_| heed::Error::Decoding)
+}
+
+fn encode_update(update: &Update) -> ZResult<Vec<u8>> {
+    serde_json::to_vec(update).map_err(|_| heed::Error::Encoding)
 }
 
 impl Updates {
     // TODO do not trigger deserialize if possible
     pub fn last_update(self, reader: &heed::RoTxn<UpdateT>) -> ZResult<Option<(u64, Update)>> {
         match self.updates.last(reader)? {
-            Some((key, data)) => Ok(Some((key.get(), data))),
+            Some((key, data)) => Ok(Some((key.get(), decode_update(data)?))),
             None => Ok(None),
         }
     }
@@ -21,7 +46,7 @@ impl Updates {
     // TODO do not trigger deserialize if possible
     pub fn first_update(self, reader: &heed::RoTxn<UpdateT>) -> ZResult<Option<(u64, Update)>> {
         match self.updates.first(reader)? {
-            Some((key, data)) => Ok(Some((key.get(), data))),
+            Some((key, data)) => Ok(Some((key.get(), decode_update(data)?))),
             None => Ok(None),
         }
     }
@@ -29,30 +54,131 @@ impl Updates {
     // TODO do not trigger deserialize if possible
     pub fn get(self, reader: &heed::RoTxn<UpdateT>, update_id: u64) -> ZResult<Option<Update>> {
         let update_id = BEU64::new(update_id);
-        self.updates.get(reader, &update_id)
+        match self.updates.get(reader, &update_id)? {
+            Some(data) => decode_update(data).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Iterates over the enqueued updates in the order they will be processed,
+    /// an update is only deserialized when it is reached.
+    pub fn iter<'txn>(
+        self,
+        reader: &'txn heed::RoTxn<UpdateT>,
+    ) -> ZResult<impl Iterator<Item = ZResult<(u64, Update)>> + 'txn> {
+        let iter = self.updates.iter(reader)?;
+        Ok(iter.map(|result| {
+            let (key, data) = result?;
+            Ok((key.get(), decode_update(data)?))
+        }))
+    }
+
+    /// Returns the enqueued updates in the order they will be processed.
+    pub fn enqueued_updates(self, reader: &heed::RoTxn<UpdateT>) -> ZResult<Vec<(u64, Update)>> {
+        self.iter(reader)?.collect()
+    }
+
+    /// Returns the number of enqueued updates and the size of their payloads.
+    pub fn queue_size(self, reader: &heed::RoTxn<UpdateT>) -> ZResult<UpdateQueueSize> {
+        match self.queue_size.get(reader, QUEUE_SIZE_KEY)? {
+            Some(size) => Ok(size),
+            // the queues enqueued before the size was stored are counted once,
+            // the size is stored by the next change of the queue
+            None => {
+                let mut size = UpdateQueueSize::default();
+                for result in self.updates.iter(reader)? {
+                    let (_, data) = result?;
+                    size.pending_updates += 1;
+                    size.pending_bytes += data.len() as u64;
+                }
+                Ok(size)
+            }
+        }
+    }
+
+    /// Writes the update and accounts for it in the size of the queue,
+    /// the update replaces the one enqueued with the same id if any.
+    fn write_update(self, writer: &mut heed::RwTxn<UpdateT>, update_id: u64, bytes: &[u8]) -> ZResult<()> {
+        let mut size = self.queue_size(writer)?;
+        let update_id = BEU64::new(update_id);
+        match self.updates.get(writer, &update_id)? {
+            Some(previous) => size.pending_bytes = size.pending_bytes.saturating_sub(previous.len() as u64),
+            None => size.pending_updates += 1,
+        }
+        size.pending_bytes += bytes.len() as u64;
+
+        self.updates.put(writer, &update_id, bytes)?;
+        self.queue_size.put(writer, QUEUE_SIZE_KEY, &size)
+    }
+
+    /// Returns an error if enqueuing an update of `update_bytes` bytes would
+    /// exceed one of the limits of the queue.
+    pub fn check_limits(self, reader: &heed::RoTxn<UpdateT>, update_bytes: u64) -> MResult<()> {
+        let UpdateQueueLimits { max_pending_updates, max_pending_bytes } = self.limits;
+        if max_pending_updates.is_none() && max_pending_bytes.is_none() {
+            return Ok(());
+        }
+
+        let size = self.queue_size(reader)?;
+        if let Some(max) = max_pending_updates {
+            if size.pending_updates >= max {
+                return Err(Error::UpdateQueueFull(QueueLimit::PendingUpdates(max)));
+            }
+        }
+        if let Some(max) = max_pending_bytes {
+            // an update is always accepted by an empty queue, even if it is bigger than the limit
+            if size.pending_updates > 0 && size.pending_bytes + update_bytes > max {
+                return Err(Error::UpdateQueueFull(QueueLimit::PendingBytes(max)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Enqueues the update if the limits of the queue allow it, the updates
+    /// received from clients must be enqueued with this method.
+    pub fn push_update(
+        self,
+        writer: &mut heed::RwTxn<UpdateT>,
+        update_id: u64,
+        update: &Update,
+    ) -> MResult<()> {
+        let bytes = encode_update(update)?;
+        self.check_limits(writer, bytes.len() as u64)?;
+        self.write_update(writer, update_id, &bytes)?;
+        Ok(())
     }
 
+    /// Enqueues the update regardless of the limits of the queue.
     pub fn put_update(
         self,
         writer: &mut heed::RwTxn<UpdateT>,
         update_id: u64,
         update: &Update,
     ) -> ZResult<()> {
-        // TODO prefer using serde_json?
-        let update_id = BEU64::new(update_id);
-        self.updates.put(writer, &update_id, update)
+        let bytes = encode_update(update)?;
+        self.write_update(writer, update_id, &bytes)
     }
 
     pub fn del_update(self, writer: &mut heed::RwTxn<UpdateT>, update_id: u64) -> ZResult<bool> {
+        let mut size = self.queue_size(writer)?;
         let update_id = BEU64::new(update_id);
-        self.updates.delete(writer, &update_id)
+        let bytes_len = match self.updates.get(writer, &update_id)? {
+            Some(data) => data.len() as u64,
+            None => return Ok(false),
+        };
+        size.pending_updates = size.pending_updates.saturating_sub(1);
+        size.pending_bytes = size.pending_bytes.saturating_sub(bytes_len);
+
+        self.updates.delete(writer, &update_id)?;
+        self.queue_size.put(writer, QUEUE_SIZE_KEY, &size)?;
+        Ok(true)
     }
 
     pub fn pop_front(self, writer: &mut heed::RwTxn<UpdateT>) -> ZResult<Option<(u64, Update)>> {
         match self.first_update(writer)? {
             Some((update_id, update)) => {
-                let key = BEU64::new(update_id);
-                self.updates.delete(writer, &key)?;
+                self.del_update(writer, update_id)?;
                 Ok(Some((update_id, update)))
             }
             None => Ok(None),
@@ -60,6 +186,7 @@ impl Updates {
     }
 
     pub fn clear(self, writer: &mut heed::RwTxn<UpdateT>) -> ZResult<()> {
-        self.updates.clear(writer)
+        self.updates.clear(writer)?;
+        self.queue_size.clear(writer)
     }
 }
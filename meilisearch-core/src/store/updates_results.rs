@@ -39,6 +39,48 @@ impl UpdatesResults {
         self.updates_results.get(reader, &update_id)
     }
 
+    /// Returns the ids of the updates results, from the oldest to the newest.
+    pub fn update_ids(self, reader: &heed::RoTxn<UpdateT>) -> ZResult<Vec<u64>> {
+        let mut ids = Vec::new();
+        for result in self.updates_results.iter(reader)? {
+            let (key, _) = result?;
+            ids.push(key.get());
+        }
+        Ok(ids)
+    }
+
+    /// Deletes the updates results for which the predicate returns `true` and returns
+    /// the number of deleted entries along with the number of bytes they were taking.
+    ///
+    /// The last update result is always kept, it is used to generate the next update id.
+    pub fn purge<F>(self, writer: &mut heed::RwTxn<UpdateT>, mut predicate: F) -> ZResult<(usize, usize)>
+    where
+        F: FnMut(&ProcessedUpdateResult) -> bool,
+    {
+        let last_update_id = match self.last_update(writer)? {
+            Some((id, _)) => id,
+            None => return Ok((0, 0)),
+        };
+
+        let mut to_delete = Vec::new();
+        for result in self.updates_results.iter(writer)? {
+            let (key, update_result) = result?;
+            if key.get() != last_update_id && predicate(&update_result) {
+                // the stored value is the JSON representation of the result
+                let size = serde_json::to_vec(&update_result).map_or(0, |v| v.len());
+                to_delete.push((key, size + std::mem::size_of::<BEU64>()));
+            }
+        }
+
+        let mut freed_bytes = 0;
+        for (key, size) in &to_delete {
+            self.updates_results.delete(writer, key)?;
+            freed_bytes += size;
+        }
+
+        Ok((to_delete.len(), freed_bytes))
+    }
+
     pub fn clear(self, writer: &mut heed::RwTxn<UpdateT>) -> ZResult<()> {
         self.updates_results.clear(writer)
     }
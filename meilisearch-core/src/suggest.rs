@@ -0,0 +1,110 @@
+//! The spelling suggestions, the words of the dictionary of an index that are
+//! the closest to a misspelled term.
+
+use fst::set::OpBuilder;
+use fst::{IntoStreamer, Streamer};
+use levenshtein_automata::Distance;
+use meilisearch_tokenizer::split_query_string;
+use serde::Serialize;
+
+use crate::automaton::{build_dfa, build_prefix_dfa};
+use crate::database::MainT;
+use crate::normalization::normalize_word;
+use crate::{store, MResult};
+
+/// The automata can't accept more typos than this.
+const MAX_DISTANCE: u8 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Suggestion {
+    pub word: String,
+    /// The number of typos between the term and the word.
+    pub distance: u8,
+    /// The number of documents containing the word.
+    pub frequency: usize,
+}
+
+/// Returns the words of the index at most `max_distance` typos away from the term, the closest
+/// first then the most frequent first, the term itself is returned when it is indexed.
+pub fn suggest(
+    reader: &heed::RoTxn<MainT>,
+    index: &store::Index,
+    term: &str,
+    max_distance: u8,
+) -> MResult<Vec<Suggestion>> {
+    let term = normalize_word(term);
+    let words_set = index.main.words_fst(reader)?.unwrap_or_default();
+    let words_delta = index.main.words_delta_fst(reader)?.unwrap_or_default();
+    let dfa = build_dfa(&term, max_distance.min(MAX_DISTANCE));
+
+    let mut suggestions = Vec::new();
+    let mut stream = OpBuilder::new()
+        .add(words_set.search(&dfa))
+        .add(words_delta.search(&dfa))
+        .union();
+
+    while let Some(word) = stream.next() {
+        let distance = match dfa.eval(word) {
+            Distance::Exact(distance) => distance,
+            Distance::AtLeast(_) => continue,
+        };
+
+        // the words of the delta are not necessarily still indexed
+        let frequency = match index.postings_lists.postings_list(reader, word)? {
+            Some(postings) => postings.docids.len(),
+            None => continue,
+        };
+
+        let word = String::from_utf8_lossy(word).into_owned();
+        suggestions.push(Suggestion { word, distance, frequency });
+    }
+
+    suggestions.sort_by(|a, b| {
+        a.distance.cmp(&b.distance)
+            .then(b.frequency.cmp(&a.frequency))
+            .then_with(|| a.word.cmp(&b.word))
+    });
+
+    Ok(suggestions)
+}
+
+/// Rewrites the query by replacing the terms that match no word of the index by their best
+/// suggestion, returns `None` when there is nothing to correct. The last term is not replaced
+/// when it is the prefix of a word as it is searched as a prefix.
+pub fn suggested_query(reader: &heed::RoTxn<MainT>, index: &store::Index, query: &str) -> MResult<Option<String>> {
+    let words_set = index.main.words_fst(reader)?.unwrap_or_default();
+    let words_delta = index.main.words_delta_fst(reader)?.unwrap_or_default();
+    let stop_words = index.main.stop_words_fst(reader)?.unwrap_or_default();
+
+    let is_prefix = |word: &str| {
+        let dfa = build_prefix_dfa(word, 0);
+        words_set.search(&dfa).into_stream().next().is_some()
+            || words_delta.search(&dfa).into_stream().next().is_some()
+    };
+
+    let terms: Vec<_> = split_query_string(query).collect();
+    let mut rewritten = Vec::with_capacity(terms.len());
+    let mut corrected = false;
+
+    for (i, term) in terms.iter().enumerate() {
+        let word = normalize_word(term);
+        let last = i + 1 == terms.len();
+
+        // the stop words are never indexed but they are not misspelled
+        if words_set.contains(&word) || words_delta.contains(&word) || stop_words.contains(&word) || (last && is_prefix(&word)) {
+            rewritten.push(term.to_string());
+            continue;
+        }
+
+        match suggest(reader, index, &word, MAX_DISTANCE)?.into_iter().find(|s| s.distance > 0) {
+            Some(suggestion) => {
+                rewritten.push(suggestion.word);
+                corrected = true;
+            }
+            None => rewritten.push(term.to_string()),
+        }
+    }
+
+    Ok(if corrected { Some(rewritten.join(" ")) } else { None })
+}
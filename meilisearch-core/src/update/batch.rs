@@ -0,0 +1,95 @@
+use crate::database::{UpdateEvent, UpdateEventsEmitter, UpdateT};
+use crate::settings::SettingsUpdate;
+use crate::store;
+use crate::update::documents_addition::documents_to_values;
+use crate::update::{next_update_id, AdditionOptions, Update};
+use crate::{DocumentId, MResult};
+
+/// Several updates enqueued as a single one, they are applied in order inside
+/// the same transaction and none of them is applied if one of them fails.
+pub struct UpdatesBatch {
+    updates_store: store::Updates,
+    updates_results_store: store::UpdatesResults,
+    updates_notifier: UpdateEventsEmitter,
+    updates: Vec<Update>,
+}
+
+impl UpdatesBatch {
+    pub fn new(
+        updates_store: store::Updates,
+        updates_results_store: store::UpdatesResults,
+        updates_notifier: UpdateEventsEmitter,
+    ) -> UpdatesBatch {
+        UpdatesBatch {
+            updates_store,
+            updates_results_store,
+            updates_notifier,
+            updates: Vec::new(),
+        }
+    }
+
+    pub fn add_documents<D: serde::Serialize>(
+        &mut self,
+        documents: Vec<D>,
+        options: AdditionOptions,
+    ) -> MResult<()> {
+        let values = documents_to_values(documents)?;
+        self.updates.push(Update::documents_addition(values, options));
+        Ok(())
+    }
+
+    pub fn update_documents<D: serde::Serialize>(
+        &mut self,
+        documents: Vec<D>,
+        options: AdditionOptions,
+    ) -> MResult<()> {
+        let values = documents_to_values(documents)?;
+        self.updates.push(Update::documents_partial(values, options));
+        Ok(())
+    }
+
+    pub fn delete_documents(&mut self, documents_ids: Vec<DocumentId>) {
+        self.updates.push(Update::documents_deletion(documents_ids));
+    }
+
+    pub fn update_settings(&mut self, settings: SettingsUpdate) {
+        self.updates.push(Update::settings(settings));
+    }
+
+    pub fn clear_all(&mut self) {
+        self.updates.push(Update::clear_all());
+    }
+
+    pub fn len(&self) -> usize {
+        self.updates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.updates.is_empty()
+    }
+
+    pub fn finalize(self, writer: &mut heed::RwTxn<UpdateT>) -> MResult<u64> {
+        let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
+        let update_id = push_batch(
+            writer,
+            self.updates_store,
+            self.updates_results_store,
+            self.updates,
+        )?;
+        Ok(update_id)
+    }
+}
+
+pub fn push_batch(
+    writer: &mut heed::RwTxn<UpdateT>,
+    updates_store: store::Updates,
+    updates_results_store: store::UpdatesResults,
+    updates: Vec<Update>,
+) -> MResult<u64> {
+    let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
+
+    let update = Update::batch(updates);
+    updates_store.push_update(writer, last_update_id, &update)?;
+
+    Ok(last_update_id)
+}
@@ -7,10 +7,17 @@ pub fn apply_clear_all(
     index: &store::Index,
 ) -> MResult<()> {
     index.main.put_words_fst(writer, &fst::Set::default())?;
+    index.main.delete_words_delta_fst(writer)?;
     index.main.put_ranked_map(writer, &RankedMap::default())?;
     index.main.put_number_of_documents(writer, |_| 0)?;
+    index.main.put_fields_frequency(writer, &Default::default())?;
     index.documents_fields.clear(writer)?;
     index.documents_fields_counts.clear(writer)?;
+    index.geo.clear(writer)?;
+    index.expirations.clear(writer)?;
+    index.sortables.clear(writer)?;
+    index.numbers.clear(writer)?;
+    index.presences.clear(writer)?;
     index.postings_lists.clear(writer)?;
     index.docs_words.clear(writer)?;
     index.prefix_documents_cache.clear(writer)?;
@@ -26,7 +33,7 @@ pub fn push_clear_all(
 ) -> MResult<u64> {
     let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
     let update = Update::clear_all();
-    updates_store.put_update(writer, last_update_id, &update)?;
+    updates_store.push_update(writer, last_update_id, &update)?;
 
     Ok(last_update_id)
 }
@@ -0,0 +1,142 @@
+use std::time::Instant;
+
+use chrono::Utc;
+use log::debug;
+
+use crate::database::{MainT, UpdateT};
+use crate::store;
+use crate::update::documents_addition::apply_addition;
+use crate::update::{update_task, DuplicatesPolicy, ProcessedUpdateResult, Update, UpdateData};
+use crate::MResult;
+
+/// The maximum number of documents of the consecutive additions applied at once.
+const COALESCED_DOCUMENTS_LIMIT: usize = 100_000;
+
+/// The number of documents of an addition, `None` if the update is not an addition.
+fn addition_len(update: &Update) -> Option<usize> {
+    match &update.data {
+        UpdateData::DocumentsAddition(documents) | UpdateData::DocumentsPartial(documents) => Some(documents.len()),
+        _ => None,
+    }
+}
+
+/// Whether the given update can be applied along with the first one, they must be additions
+/// of the same kind with the same options. The documents of different additions replace each
+/// other like when they are applied one after the other, which the `KeepLast` policy only does.
+fn can_be_coalesced(first: &Update, update: &Update) -> bool {
+    let same_kind = match (&first.data, &update.data) {
+        (UpdateData::DocumentsAddition(_), UpdateData::DocumentsAddition(_)) => true,
+        (UpdateData::DocumentsPartial(_), UpdateData::DocumentsPartial(_)) => true,
+        _ => false,
+    };
+
+    same_kind
+        && first.addition_options == update.addition_options
+        && first.addition_options.duplicates_policy == DuplicatesPolicy::KeepLast
+}
+
+/// Returns the first enqueued update, followed by the consecutive additions that can be
+/// applied along with it when `coalesce` is set.
+pub fn next_updates(
+    reader: &heed::RoTxn<UpdateT>,
+    updates_store: store::Updates,
+    coalesce: bool,
+) -> MResult<Vec<(u64, Update)>> {
+    let mut updates: Vec<(u64, Update)> = Vec::new();
+    let mut number_of_documents = 0;
+
+    for result in updates_store.iter(reader)? {
+        let (update_id, update) = result?;
+        let update_len = addition_len(&update).unwrap_or_default();
+
+        if let Some((_, first)) = updates.first() {
+            let coalesced = coalesce
+                && can_be_coalesced(first, &update)
+                && number_of_documents + update_len <= COALESCED_DOCUMENTS_LIMIT;
+            if !coalesced {
+                break;
+            }
+        }
+
+        number_of_documents += update_len;
+        updates.push((update_id, update));
+    }
+
+    Ok(updates)
+}
+
+/// Applies the given consecutive updates with a single transaction and returns the result of
+/// each one. Several additions are applied by a single `apply_addition` call, they all fail if
+/// one of them cannot be applied.
+pub fn coalesced_update_task<'a, 'b>(
+    writer: &'a mut heed::RwTxn<'b, MainT>,
+    index: &store::Index,
+    mut updates: Vec<(u64, Update)>,
+) -> MResult<Vec<ProcessedUpdateResult>> {
+    if updates.len() <= 1 {
+        return match updates.pop() {
+            Some((update_id, update)) => update_task(writer, index, update_id, update).map(|status| vec![status]),
+            None => Ok(Vec::new()),
+        };
+    }
+
+    let start = Instant::now();
+
+    let partial = if let UpdateData::DocumentsPartial(_) = updates[0].1.data { true } else { false };
+    let options = updates[0].1.addition_options.clone();
+
+    let mut processed = Vec::with_capacity(updates.len());
+    let mut additions = Vec::with_capacity(updates.len());
+    for (update_id, update) in updates {
+        let update_type = update.data.update_type();
+        let Update { data, enqueued_at, .. } = update;
+        match data {
+            UpdateData::DocumentsAddition(documents) | UpdateData::DocumentsPartial(documents) => {
+                additions.push(documents)
+            }
+            _ => unreachable!("only the documents additions are coalesced"),
+        }
+        processed.push((update_id, update_type, enqueued_at));
+    }
+
+    let update_ids: Vec<_> = processed.iter().map(|(update_id, _, _)| *update_id).collect();
+    debug!("Processing the update numbers {:?} at once", update_ids);
+
+    let result = apply_addition(writer, index, additions, partial, options);
+
+    debug!("Processed the update numbers {:?} {:?}", update_ids, result.as_ref().map(Vec::len));
+
+    // the duration is the one of the whole application, it is shared by the updates
+    let duration = start.elapsed().as_secs_f64();
+    let processed_at = Utc::now();
+    let (reports, error) = match result {
+        Ok(reports) => (reports, None),
+        Err(e) if e.is_map_full() => return Err(e),
+        Err(e) => (Vec::new(), Some(e.to_string())),
+    };
+
+    let mut reports = reports.into_iter();
+    let statuses = processed
+        .into_iter()
+        .map(|(update_id, update_type, enqueued_at)| {
+            let report = reports.next().unwrap_or_default();
+            ProcessedUpdateResult {
+                update_id,
+                update_type,
+                error: error.clone(),
+                duration,
+                enqueued_at,
+                processed_at,
+                settings_diff: None,
+                duplicates: report.duplicates,
+                generated_ids: if report.generated_ids.is_empty() { None } else { Some(report.generated_ids) },
+                inferred_primary_key: report.primary_key,
+                failed_documents: if report.failed_documents.is_empty() { None } else { Some(report.failed_documents) },
+                truncation: report.truncation,
+                interruption: None,
+            }
+        })
+        .collect();
+
+    Ok(statuses)
+}
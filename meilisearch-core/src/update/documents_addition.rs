@@ -1,29 +1,131 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::io::BufRead;
+use std::mem;
 
 use fst::{set::OpBuilder, SetBuilder};
 use indexmap::IndexMap;
-use meilisearch_schema::{Schema, FieldId};
+use meilisearch_schema::{Schema, FieldId, FieldType, IndexedPos};
 use meilisearch_types::DocumentId;
-use sdset::{duo::Union, SetOperation};
-use serde::Deserialize;
+use rayon::prelude::*;
+use sdset::{duo::Union, Set, SetBuf, SetOperation};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use uuid::Uuid;
 
 use crate::database::{MainT, UpdateT};
 use crate::database::{UpdateEvent, UpdateEventsEmitter};
+use crate::expiration::{expiration_from_value, EXPIRES_AT_FIELD};
 use crate::facets;
+use crate::geo::{GeoPoint, GEO_FIELD};
 use crate::raw_indexer::RawIndexer;
 use crate::serde::Deserializer;
-use crate::store::{self, DocumentsFields, DocumentsFieldsCounts};
-use crate::update::helpers::{index_value, value_to_number, extract_document_id};
-use crate::update::{apply_documents_deletion, compute_short_prefixes, next_update_id, Update};
+use crate::settings::{DocumentIdFormat, DEFAULT_FOLD_DIACRITICS, DEFAULT_MAX_POSITIONS_PER_ATTRIBUTE, DEFAULT_MAX_TOKEN_LENGTH};
+use crate::settings::{DEFAULT_COMPRESS_DOCUMENTS, DEFAULT_STOP_WORDS_IN_PHRASES};
+use crate::store::{self, DocumentsFields, DocumentsFieldsCounts, Numbers, Presences, SortableValue};
+use crate::update::helpers::{compute_fields_frequency, document_id_to_string, field_numbers, field_presences, index_pre_tokenized_value, index_value, merge_patch, nested_fields, nested_path, nested_value, typed_value_to_number, value_at_path, value_has_type, values_at_path, extract_document_id, update_fields_frequency};
+use crate::update::{apply_documents_deletion, compact_words_fst, compute_short_prefixes, next_update_id, Update, UpdatePhase};
 use crate::{Error, MResult, RankedMap};
 
+/// The number of documents tokenized by the same thread during an indexation.
+const INDEXING_CHUNK_SIZE: usize = 1000;
+/// The number of documents read at once from the documents fields during a reindexation.
+const REINDEX_BATCH_SIZE: usize = 10_000;
+/// The words delta is merged into the words set once it holds this fraction of its words.
+const WORDS_DELTA_COMPACTION_RATIO: usize = 20;
+/// The number of documents stored in the same update when streaming documents, by default.
+pub const DEFAULT_ADDITION_CHUNK_SIZE: usize = 10_000;
+/// The suffix of the field names a primary key is inferred from, by default.
+pub const DEFAULT_PRIMARY_KEY_SUFFIX: &str = "id";
+
+/// What to do when the same document id appears several times in one addition.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DuplicatesPolicy {
+    /// The whole addition fails.
+    Error,
+    /// The first document with a given id is kept, the following ones are ignored.
+    KeepFirst,
+    /// Each document replaces, or is merged into for partial additions, the previous one.
+    KeepLast,
+}
+
+impl Default for DuplicatesPolicy {
+    fn default() -> DuplicatesPolicy {
+        DuplicatesPolicy::KeepLast
+    }
+}
+
+/// The duplicated document ids found in an addition and how they were handled.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicatesReport {
+    pub policy: DuplicatesPolicy,
+    pub ids: Vec<String>,
+}
+
+/// A document that was skipped by an addition because it could not be indexed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentError {
+    /// The position of the document in the addition.
+    pub position: usize,
+    /// The primary key value of the document, if it has a valid one.
+    pub document_id: Option<String>,
+    pub error: String,
+}
+
+/// The content that was not fully indexed because it exceeded the indexing limits.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TruncationReport {
+    /// The number of words truncated to the maximum token length.
+    pub truncated_tokens: usize,
+    /// The number of attributes of which the words after the maximum position were ignored.
+    pub truncated_attributes: usize,
+}
+
+impl TruncationReport {
+    fn from_indexer(indexer: &RawIndexer) -> Option<TruncationReport> {
+        let report = TruncationReport {
+            truncated_tokens: indexer.truncated_tokens(),
+            truncated_attributes: indexer.truncated_attributes(),
+        };
+        if report == TruncationReport::default() { None } else { Some(report) }
+    }
+}
+
+/// The options of a documents addition, stored along with its documents.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AdditionOptions {
+    pub duplicates_policy: DuplicatesPolicy,
+    /// Generates a UUID for the documents that do not have a primary key value.
+    pub generate_ids: bool,
+    /// The suffix of the field the primary key is inferred from when the index has none,
+    /// `DEFAULT_PRIMARY_KEY_SUFFIX` if not specified.
+    pub primary_key_suffix: Option<String>,
+}
+
+/// What a processed documents addition reports in its update result.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AdditionReport {
+    pub duplicates: Option<DuplicatesReport>,
+    pub generated_ids: Vec<String>,
+    pub primary_key: Option<String>,
+    pub failed_documents: Vec<DocumentError>,
+    pub truncation: Option<TruncationReport>,
+}
+
 pub struct DocumentsAddition<D> {
     updates_store: store::Updates,
     updates_results_store: store::UpdatesResults,
     updates_notifier: UpdateEventsEmitter,
     documents: Vec<D>,
     is_partial: bool,
+    options: AdditionOptions,
+    chunk_size: usize,
 }
 
 impl<D> DocumentsAddition<D> {
@@ -38,6 +140,8 @@ impl<D> DocumentsAddition<D> {
             updates_notifier,
             documents: Vec::new(),
             is_partial: false,
+            options: AdditionOptions::default(),
+            chunk_size: DEFAULT_ADDITION_CHUNK_SIZE,
         }
     }
 
@@ -52,26 +156,77 @@ impl<D> DocumentsAddition<D> {
             updates_notifier,
             documents: Vec::new(),
             is_partial: true,
+            options: AdditionOptions::default(),
+            chunk_size: DEFAULT_ADDITION_CHUNK_SIZE,
         }
     }
 
+    pub fn set_options(&mut self, options: AdditionOptions) {
+        self.options = options;
+    }
+
+    /// Sets the maximum number of documents stored in the same update when streaming documents.
+    pub fn set_chunk_size(&mut self, chunk_size: usize) {
+        self.chunk_size = cmp::max(chunk_size, 1);
+    }
+
     pub fn update_document(&mut self, document: D) {
         self.documents.push(document);
     }
 
-    pub fn finalize(self, writer: &mut heed::RwTxn<UpdateT>) -> MResult<u64>
+    /// Pushes the documents added until now in their own update and returns its id.
+    fn push_chunk(&mut self, writer: &mut heed::RwTxn<UpdateT>) -> MResult<u64>
     where
         D: serde::Serialize,
     {
         let _ = self.updates_notifier.send(UpdateEvent::NewUpdate);
-        let update_id = push_documents_addition(
+        push_documents_addition(
             writer,
             self.updates_store,
             self.updates_results_store,
-            self.documents,
+            mem::take(&mut self.documents),
             self.is_partial,
-        )?;
-        Ok(update_id)
+            self.options.clone(),
+        )
+    }
+
+    pub fn finalize(mut self, writer: &mut heed::RwTxn<UpdateT>) -> MResult<u64>
+    where
+        D: serde::Serialize,
+    {
+        self.push_chunk(writer)
+    }
+}
+
+impl DocumentsAddition<IndexMap<String, Value>> {
+    /// Reads the documents from a reader containing one JSON object per line and pushes
+    /// them in updates of at most `chunk_size` documents, processed one after the other.
+    /// Returns the ids of the updates pushed, the remaining documents are pushed by `finalize`.
+    ///
+    /// The duplicates policy only applies to the documents of the same update.
+    pub fn extend_from_ndjson<R: BufRead>(
+        &mut self,
+        writer: &mut heed::RwTxn<UpdateT>,
+        reader: R,
+    ) -> MResult<Vec<u64>> {
+        let mut update_ids = Vec::new();
+
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let document = serde_json::from_str(&line)?;
+            self.documents.push(document);
+
+            if self.documents.len() >= self.chunk_size {
+                let update_id = self.push_chunk(writer)?;
+                update_ids.push(update_id);
+            }
+        }
+
+        Ok(update_ids)
     }
 }
 
@@ -81,148 +236,431 @@ impl<D> Extend<D> for DocumentsAddition<D> {
     }
 }
 
+/// Converts the documents into the maps of values stored in the updates.
+pub(crate) fn documents_to_values<D: serde::Serialize>(documents: Vec<D>) -> MResult<Vec<IndexMap<String, Value>>> {
+    let mut values = Vec::with_capacity(documents.len());
+    for document in documents {
+        let vec = serde_json::to_vec(&document)?;
+        let value = serde_json::from_slice(&vec)?;
+        values.push(value);
+    }
+    Ok(values)
+}
+
 pub fn push_documents_addition<D: serde::Serialize>(
     writer: &mut heed::RwTxn<UpdateT>,
     updates_store: store::Updates,
     updates_results_store: store::UpdatesResults,
     addition: Vec<D>,
     is_partial: bool,
+    options: AdditionOptions,
 ) -> MResult<u64> {
-    let mut values = Vec::with_capacity(addition.len());
-    for add in addition {
-        let vec = serde_json::to_vec(&add)?;
-        let add = serde_json::from_slice(&vec)?;
-        values.push(add);
-    }
+    let values = documents_to_values(addition)?;
 
     let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
 
     let update = if is_partial {
-        Update::documents_partial(values)
+        Update::documents_partial(values, options)
     } else {
-        Update::documents_addition(values)
+        Update::documents_addition(values, options)
     };
 
-    updates_store.put_update(writer, last_update_id, &update)?;
+    updates_store.push_update(writer, last_update_id, &update)?;
 
     Ok(last_update_id)
 }
 
+/// Stores the field of a document, its words are indexed apart by `index_documents_words`.
 fn index_document(
     writer: &mut heed::RwTxn<MainT>,
     documents_fields: DocumentsFields,
-    documents_fields_counts: DocumentsFieldsCounts,
+    numbers: Numbers,
+    presences: Presences,
     ranked_map: &mut RankedMap,
-    indexer: &mut RawIndexer,
     schema: &Schema,
     field_id: FieldId,
     document_id: DocumentId,
     value: &Value,
+    compress: bool,
 ) -> MResult<()>
 {
     let serialized = serde_json::to_vec(value)?;
-    documents_fields.put_document_field(writer, document_id, field_id, &serialized)?;
+    documents_fields.put_document_field(writer, document_id, field_id, &serialized, compress)?;
 
-    if let Some(indexed_pos) = schema.is_indexed(field_id) {
-        let number_of_words = index_value(indexer, document_id, *indexed_pos, value);
-        if let Some(number_of_words) = number_of_words {
-            documents_fields_counts.put_document_field_count(
-                writer,
-                document_id,
-                *indexed_pos,
-                number_of_words as u16,
-            )?;
-        }
+    for (field_id, number) in field_numbers(schema, field_id, value) {
+        numbers.put_document_number(writer, field_id, number, document_id)?;
+    }
+
+    for field_id in field_presences(schema, field_id, value) {
+        presences.put_document_field(writer, field_id, document_id)?;
     }
 
     if schema.is_ranked(field_id) {
-        let number = value_to_number(value).unwrap_or_default();
+        let number = typed_value_to_number(value, schema.field_type(field_id)).unwrap_or_default();
         ranked_map.insert(document_id, field_id, number);
     }
 
+    // the ranked fields can also be nested in this field, e.g. `ratings.average`
+    for (nested_id, path) in nested_fields(schema, field_id) {
+        if schema.is_ranked(nested_id) {
+            let number = value_at_path(value, path)
+                .and_then(|value| typed_value_to_number(value, schema.field_type(nested_id)))
+                .unwrap_or_default();
+            ranked_map.insert(document_id, nested_id, number);
+        }
+    }
+
     Ok(())
 }
 
+/// Returns the indexed fields of a document field, the field itself
+/// and the indexed fields nested in it, e.g. `author.name`.
+fn document_indexed_fields<'a>(schema: &Schema, field_id: FieldId, value: &'a Value) -> Vec<(IndexedPos, Cow<'a, Value>)> {
+    let mut indexed_fields = Vec::new();
+    if let Some(indexed_pos) = schema.is_indexed(field_id) {
+        indexed_fields.push((*indexed_pos, Cow::Borrowed(value)));
+    }
+
+    for (nested_id, path) in nested_fields(schema, field_id) {
+        if let Some(indexed_pos) = schema.is_indexed(nested_id) {
+            if let Some(value) = nested_value(value, path) {
+                indexed_fields.push((*indexed_pos, Cow::Owned(value)));
+            }
+        }
+    }
+
+    indexed_fields
+}
+
+/// Tokenizes the indexed fields of the documents outside of the write transaction, the chunks
+/// of documents are indexed in parallel by their own indexer, the indexers are then merged
+/// and the fields counts written. The pre-tokenized fields are indexed as they are.
+fn index_documents_words(
+    writer: &mut heed::RwTxn<MainT>,
+    schema: &Schema,
+    documents_fields_counts: DocumentsFieldsCounts,
+    indexer: &mut RawIndexer,
+    documents: &[(DocumentId, Vec<(IndexedPos, Cow<Value>)>)],
+) -> MResult<()>
+{
+    let template = &*indexer;
+    let chunks: Vec<_> = documents
+        .par_chunks(INDEXING_CHUNK_SIZE)
+        .map(|chunk| {
+            let mut chunk_indexer = template.fork();
+            let mut fields_counts = Vec::new();
+            for (document_id, fields) in chunk {
+                for (indexed_pos, value) in fields {
+                    let field_type = schema.indexed_pos_to_field_id(*indexed_pos).and_then(|id| schema.field_type(id));
+                    let number_of_words = match field_type {
+                        Some(FieldType::Tokens) => index_pre_tokenized_value(&mut chunk_indexer, *document_id, *indexed_pos, value),
+                        _ => index_value(&mut chunk_indexer, *document_id, *indexed_pos, value),
+                    };
+                    if let Some(number_of_words) = number_of_words {
+                        fields_counts.push((*document_id, *indexed_pos, number_of_words as u16));
+                    }
+                }
+            }
+            (chunk_indexer, fields_counts)
+        })
+        .collect();
+
+    for (chunk_indexer, fields_counts) in chunks {
+        indexer.merge(chunk_indexer);
+        for (document_id, indexed_pos, number_of_words) in fields_counts {
+            documents_fields_counts.put_document_field_count(writer, document_id, indexed_pos, number_of_words)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Stores the value of the field in the sortables store if it is a sortable attribute.
+fn index_sortable_value(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+    sortable_attributes: Option<&Set<FieldId>>,
+    field_id: FieldId,
+    document_id: DocumentId,
+    value: &Value,
+) -> MResult<()>
+{
+    if sortable_attributes.map_or(false, |attributes| attributes.contains(&field_id)) {
+        if let Some(value) = SortableValue::from_value(value) {
+            index.sortables.put_document_value(writer, document_id, field_id, &value)?;
+        }
+    }
+    Ok(())
+}
+
+fn sortable_attributes(reader: &heed::RoTxn<MainT>, index: &store::Index) -> MResult<Option<SetBuf<FieldId>>> {
+    Ok(index.main.sortable_attributes(reader)?.map(|attributes| attributes.into_owned()))
+}
+
+/// Checks that the document can be indexed and returns its internal id.
+fn check_document(
+    schema: &Schema,
+    primary_key: &str,
+    document: &IndexMap<String, Value>,
+    document_id_format: DocumentIdFormat,
+    attributes_for_facetting: Option<&Set<FieldId>>,
+) -> MResult<DocumentId> {
+    let document_id = extract_document_id(primary_key, document, document_id_format)?;
+
+    if let Some(value) = document.get(GEO_FIELD).filter(|value| !value.is_null()) {
+        GeoPoint::from_value(value)?;
+    }
+
+    if let Some(value) = document.get(EXPIRES_AT_FIELD).filter(|value| !value.is_null()) {
+        expiration_from_value(value)?;
+    }
+
+    if let Some(attributes_for_facetting) = attributes_for_facetting {
+        facets::check_document_facets(schema, document, attributes_for_facetting)?;
+    }
+
+    check_document_field_types(schema, document)?;
+
+    Ok(document_id)
+}
+
+/// Checks that the values of the typed fields, nested ones included, are of their declared type.
+fn check_document_field_types(schema: &Schema, document: &IndexMap<String, Value>) -> MResult<()> {
+    for (name, field_type) in schema.field_types_name() {
+        let values = match document.get(name) {
+            Some(value) => vec![value],
+            // the list of tokens of a nested field must not be traversed like the other arrays
+            None if field_type == FieldType::Tokens => document
+                .iter()
+                .find_map(|(parent, value)| value_at_path(value, nested_path(name, parent)?))
+                .into_iter()
+                .collect(),
+            None => document
+                .iter()
+                .find_map(|(parent, value)| nested_path(name, parent).map(|path| values_at_path(value, path)))
+                .unwrap_or_default(),
+        };
+
+        if !values.into_iter().all(|value| value_has_type(value, field_type)) {
+            return Err(Error::InvalidFieldType { field: name.to_string(), expected: field_type });
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the first field of the document of which the name ends with the suffix, ignoring the case.
+pub fn infer_primary_key<'a>(document: &'a IndexMap<String, Value>, suffix: &str) -> Option<&'a str> {
+    let suffix = suffix.to_lowercase();
+    document.keys().map(String::as_str).find(|key| key.to_lowercase().ends_with(&suffix))
+}
+
+/// Applies the documents of one or several consecutive additions at once, the documents
+/// of an addition replace, or are merged into, the ones of the previous additions.
+/// Returns the report of every addition, the truncation is reported by the first one.
 pub fn apply_addition<'a, 'b>(
     writer: &'a mut heed::RwTxn<'b, MainT>,
     index: &store::Index,
-    new_documents: Vec<IndexMap<String, Value>>,
-    partial: bool
-) -> MResult<()> {
-    let mut documents_additions = HashMap::new();
+    additions: Vec<Vec<IndexMap<String, Value>>>,
+    partial: bool,
+    options: AdditionOptions,
+) -> MResult<Vec<AdditionReport>> {
+    let AdditionOptions { duplicates_policy, generate_ids, primary_key_suffix } = options;
+    let mut documents_additions: HashMap<DocumentId, IndexMap<String, Value>> = HashMap::new();
+    let mut reports = Vec::with_capacity(additions.len());
 
     let mut schema = match index.main.schema(writer)? {
         Some(schema) => schema,
         None => return Err(Error::SchemaMissing),
     };
 
+    // the first addition of an index without primary key chooses it from its first document
+    let mut inferred_primary_key = None;
+    if schema.primary_key().is_none() {
+        let suffix = primary_key_suffix.as_deref().unwrap_or(DEFAULT_PRIMARY_KEY_SUFFIX);
+        let primary_key = additions
+            .iter()
+            .find_map(|documents| documents.first())
+            .and_then(|document| infer_primary_key(document, suffix))
+            .map(ToOwned::to_owned)
+            .or_else(|| if generate_ids { Some(suffix.to_owned()) } else { None })
+            .ok_or(Error::MissingPrimaryKey)?;
+        schema.set_primary_key(&primary_key)?;
+        inferred_primary_key = Some(primary_key);
+    }
+
     let primary_key = schema.primary_key().ok_or(Error::MissingPrimaryKey)?;
+    let document_id_format = index.main.document_id_format(writer)?.unwrap_or_default();
+
+    let attributes_for_facetting = index.main.attributes_for_faceting(writer)?.map(Cow::into_owned);
+    let deleted_documents = index.deleted_documents(writer)?;
+
+    let total_documents = additions.iter().map(Vec::len).sum();
+    let mut extracted_documents = 0;
+    index.progress.emit(UpdatePhase::Extraction, 0, total_documents);
 
     // 1. store documents ids for future deletion
-    for mut document in new_documents {
-        let document_id = extract_document_id(&primary_key, &document)?;
+    for new_documents in additions {
+        let mut duplicates = Vec::new();
+        let mut generated_ids = Vec::new();
+        let mut failed_documents = Vec::new();
+
+        // the duplicates policy only applies to the documents of the same addition
+        let mut addition_ids = HashSet::new();
+
+        for (i, mut document) in new_documents.into_iter().enumerate() {
+            extracted_documents += 1;
+            index.progress.emit_periodically(UpdatePhase::Extraction, extracted_documents, total_documents);
+
+            let mut generated_id = None;
+            if generate_ids && document.get(primary_key).map_or(true, Value::is_null) {
+                let id = Uuid::new_v4().to_hyphenated().to_string();
+                document.insert(primary_key.to_string(), Value::String(id.clone()));
+                generated_id = Some(id);
+            }
 
-        if partial {
-            let mut deserializer = Deserializer {
-                document_id,
-                reader: writer,
-                documents_fields: index.documents_fields,
-                schema: &schema,
-                fields: None,
+            // an invalid document is skipped and reported, the other ones are still added
+            let checked = check_document(
+                &schema,
+                primary_key,
+                &document,
+                document_id_format,
+                attributes_for_facetting.as_deref(),
+            );
+            let document_id = match checked {
+                Ok(document_id) => document_id,
+                Err(error) => {
+                    let document_id = document.get(primary_key).and_then(document_id_to_string);
+                    failed_documents.push(DocumentError { position: i, document_id, error: error.to_string() });
+                    continue;
+                }
             };
+            generated_ids.extend(generated_id);
+
+            if let Some(previous) = documents_additions.get(&document_id) {
+                if !addition_ids.insert(document_id) {
+                    let external_id = document.get(primary_key).and_then(document_id_to_string).unwrap_or_default();
+                    if !duplicates.contains(&external_id) {
+                        duplicates.push(external_id);
+                    }
+
+                    if let DuplicatesPolicy::Error | DuplicatesPolicy::KeepFirst = duplicates_policy {
+                        continue;
+                    }
+                }
 
-            let old_document = Option::<HashMap<String, Value>>::deserialize(&mut deserializer)?;
-            if let Some(old_document) = old_document {
-                for (key, value) in old_document {
-                    document.entry(key).or_insert(value);
+                // a partial document is merged into the previous version found in these additions
+                if partial {
+                    let mut merged = previous.clone();
+                    merge_patch(&mut merged, document);
+                    documents_additions.insert(document_id, merged);
+                    continue;
                 }
+            } else if partial && deleted_documents.binary_search(&document_id).is_err() {
+                // the previous version of the document is merged, unless it was deleted
+                let mut deserializer = Deserializer {
+                    document_id,
+                    reader: writer,
+                    documents_fields: index.documents_fields,
+                    schema: &schema,
+                    fields: None,
+                    displayed_only: false,
+                };
+
+                // the fields set to null are removed from the stored document
+                let old_document = Option::<IndexMap<String, Value>>::deserialize(&mut deserializer)?;
+                let mut merged = old_document.unwrap_or_default();
+                merge_patch(&mut merged, document);
+                document = merged;
             }
+            addition_ids.insert(document_id);
+            documents_additions.insert(document_id, document);
+        }
+
+        if duplicates_policy == DuplicatesPolicy::Error && !duplicates.is_empty() {
+            return Err(Error::DuplicateDocumentIds(duplicates));
         }
-        documents_additions.insert(document_id, document);
+
+        let duplicates = if duplicates.is_empty() {
+            None
+        } else {
+            Some(DuplicatesReport { policy: duplicates_policy, ids: duplicates })
+        };
+
+        reports.push(AdditionReport {
+            duplicates,
+            generated_ids,
+            primary_key: None,
+            failed_documents,
+            truncation: None,
+        });
     }
 
     // 2. remove the documents posting lists
     let number_of_inserted_documents = documents_additions.len();
-    let documents_ids = documents_additions.iter().map(|(id, _)| *id).collect();
-    apply_documents_deletion(writer, index, documents_ids)?;
+    let documents_ids: Vec<_> = documents_additions.iter().map(|(id, _)| *id).collect();
+    apply_documents_deletion(writer, index, documents_ids.clone())?;
 
     let mut ranked_map = match index.main.ranked_map(writer)? {
         Some(ranked_map) => ranked_map,
         None => RankedMap::default(),
     };
 
-    let stop_words = match index.main.stop_words_fst(writer)? {
-        Some(stop_words) => stop_words,
-        None => fst::Set::default(),
-    };
-
     // 3. index the documents fields in the stores
-    if let Some(attributes_for_facetting) = index.main.attributes_for_faceting(writer)? {
-        let facet_map = facets::facet_map_from_docs(&schema, &documents_additions, attributes_for_facetting.as_ref())?;
+    if let Some(attributes_for_facetting) = &attributes_for_facetting {
+        let normalization = index.main.facet_normalization(writer)?.unwrap_or_default();
+        let facet_map = facets::facet_map_from_docs(&schema, &documents_additions, attributes_for_facetting, &normalization)?;
         index.facets.add(writer, facet_map)?;
     }
 
-    let mut indexer = RawIndexer::new(stop_words);
+    let mut indexer = indexer_with_limits(writer, index)?;
+    let sortable_attributes = sortable_attributes(writer, index)?;
+    let compress_documents = index.main.compress_documents(writer)?.unwrap_or(DEFAULT_COMPRESS_DOCUMENTS);
 
     // For each document in this update
-    for (document_id, document) in documents_additions {
+    let mut indexed_documents = Vec::with_capacity(documents_additions.len());
+    for (i, (document_id, document)) in documents_additions.iter().enumerate() {
+        index.progress.emit_periodically(UpdatePhase::Indexing, i + 1, number_of_inserted_documents);
+        let document_id = *document_id;
+
+        // the previous coordinates were removed with the old version of the document
+        if let Some(value) = document.get(GEO_FIELD).filter(|value| !value.is_null()) {
+            let point = GeoPoint::from_value(value)?;
+            index.geo.put_document_point(writer, document_id, point)?;
+        }
+
+        if let Some(value) = document.get(EXPIRES_AT_FIELD).filter(|value| !value.is_null()) {
+            let expires_at = expiration_from_value(value)?;
+            index.expirations.put_document_expiration(writer, document_id, expires_at)?;
+        }
+
         // For each key-value pair in the document.
+        let mut indexed_fields = Vec::new();
         for (attribute, value) in document {
-            let field_id = schema.insert_and_index(&attribute)?;
+            let field_id = schema.insert_and_index(attribute)?;
+            index_sortable_value(writer, index, sortable_attributes.as_deref(), field_id, document_id, value)?;
             index_document(
                 writer,
                 index.documents_fields,
-                index.documents_fields_counts,
+                index.numbers,
+                index.presences,
                 &mut ranked_map,
-                &mut indexer,
                 &schema,
                 field_id,
                 document_id,
-                &value,
+                value,
+                compress_documents,
             )?;
+            indexed_fields.extend(document_indexed_fields(&schema, field_id, value));
         }
+        indexed_documents.push((document_id, indexed_fields));
     }
 
+    index_documents_words(writer, &schema, index.documents_fields_counts, &mut indexer, &indexed_documents)?;
+
+    let truncation = TruncationReport::from_indexer(&indexer);
+
+    index.progress.emit(UpdatePhase::FstMerge, number_of_inserted_documents, number_of_inserted_documents);
     write_documents_addition_index(
         writer,
         index,
@@ -231,28 +669,119 @@ pub fn apply_addition<'a, 'b>(
         indexer,
     )?;
 
+    update_fields_frequency(writer, index, &schema, documents_ids, true)?;
+
     index.main.put_schema(writer, &schema)?;
 
-    Ok(())
+    if let Some(report) = reports.first_mut() {
+        report.primary_key = inferred_primary_key;
+        report.truncation = truncation;
+    }
+
+    Ok(reports)
 }
 
 pub fn apply_documents_partial_addition<'a, 'b>(
     writer: &'a mut heed::RwTxn<'b, MainT>,
     index: &store::Index,
     new_documents: Vec<IndexMap<String, Value>>,
-) -> MResult<()> {
-    apply_addition(writer, index, new_documents, true)
+    options: AdditionOptions,
+) -> MResult<AdditionReport> {
+    let reports = apply_addition(writer, index, vec![new_documents], true, options)?;
+    Ok(reports.into_iter().next().unwrap_or_default())
 }
 
 pub fn apply_documents_addition<'a, 'b>(
     writer: &'a mut heed::RwTxn<'b, MainT>,
     index: &store::Index,
     new_documents: Vec<IndexMap<String, Value>>,
-) -> MResult<()> {
-    apply_addition(writer, index, new_documents, false)
+    options: AdditionOptions,
+) -> MResult<AdditionReport> {
+    let reports = apply_addition(writer, index, vec![new_documents], false, options)?;
+    Ok(reports.into_iter().next().unwrap_or_default())
+}
+
+pub fn reindex_all_documents(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+) -> MResult<Option<TruncationReport>> {
+    let documents_ids_to_reindex = all_documents_ids(writer, index)?;
+    reindex_documents(writer, index, documents_ids_to_reindex)
 }
 
-pub fn reindex_all_documents(writer: &mut heed::RwTxn<MainT>, index: &store::Index) -> MResult<()> {
+/// Rebuilds the facets of all the documents from their stored fields.
+pub fn reindex_facets(writer: &mut heed::RwTxn<MainT>, index: &store::Index) -> MResult<()> {
+    index.facets.clear(writer)?;
+
+    if let Some(attributes_for_facetting) = index.main.attributes_for_faceting(writer)? {
+        let attributes_for_facetting = attributes_for_facetting.into_owned();
+        let documents_ids = all_documents_ids(writer, index)?;
+        let facet_map = facets::facet_map_from_docids(writer, &index, &documents_ids, &attributes_for_facetting)?;
+        index.facets.add(writer, facet_map)?;
+    }
+
+    Ok(())
+}
+
+/// Rebuilds the sortable values of all the documents from their stored fields.
+pub fn reindex_sortables(writer: &mut heed::RwTxn<MainT>, index: &store::Index) -> MResult<()> {
+    index.sortables.clear(writer)?;
+
+    let sortable_attributes = match sortable_attributes(writer, index)? {
+        Some(attributes) => attributes,
+        None => return Ok(()),
+    };
+
+    for document_id in all_documents_ids(writer, index)? {
+        let mut fields = Vec::new();
+        for result in index.documents_fields.document_fields(writer, document_id)? {
+            let (field_id, bytes) = result?;
+            if sortable_attributes.contains(&field_id) {
+                let value: Value = serde_json::from_slice(&bytes)?;
+                fields.push((field_id, value));
+            }
+        }
+
+        for (field_id, value) in fields {
+            index_sortable_value(writer, index, Some(&*sortable_attributes), field_id, document_id, &value)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Rewrites the stored fields of all the documents, compressed or not depending on the settings.
+pub fn rewrite_documents_fields(writer: &mut heed::RwTxn<MainT>, index: &store::Index) -> MResult<()> {
+    let compress_documents = index.main.compress_documents(writer)?.unwrap_or(DEFAULT_COMPRESS_DOCUMENTS);
+
+    for document_id in all_documents_ids(writer, index)? {
+        let mut fields = Vec::new();
+        for result in index.documents_fields.document_fields(writer, document_id)? {
+            let (field_id, bytes) = result?;
+            fields.push((field_id, bytes.into_owned()));
+        }
+
+        for (field_id, bytes) in fields {
+            index.documents_fields.put_document_field(writer, document_id, field_id, &bytes, compress_documents)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn all_documents_ids(reader: &heed::RoTxn<MainT>, index: &store::Index) -> MResult<Vec<DocumentId>> {
+    let mut documents_ids = Vec::new();
+    for result in index.documents_fields_counts.documents_ids(reader)? {
+        documents_ids.push(result?);
+    }
+    Ok(documents_ids)
+}
+
+pub fn reindex_documents(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+    documents_ids_to_reindex: Vec<DocumentId>,
+) -> MResult<Option<TruncationReport>> {
     let schema = match index.main.schema(writer)? {
         Some(schema) => schema,
         None => return Err(Error::SchemaMissing),
@@ -260,59 +789,88 @@ pub fn reindex_all_documents(writer: &mut heed::RwTxn<MainT>, index: &store::Ind
 
     let mut ranked_map = RankedMap::default();
 
-    // 1. retrieve all documents ids
-    let mut documents_ids_to_reindex = Vec::new();
-    for result in index.documents_fields_counts.documents_ids(writer)? {
-        let document_id = result?;
-        documents_ids_to_reindex.push(document_id);
-    }
-
-    // 2. remove the documents posting lists
+    // 1. remove the documents posting lists
     index.main.put_words_fst(writer, &fst::Set::default())?;
+    index.main.delete_words_delta_fst(writer)?;
     index.main.put_ranked_map(writer, &ranked_map)?;
     index.main.put_number_of_documents(writer, |_| 0)?;
     index.facets.clear(writer)?;
+    index.sortables.clear(writer)?;
+    index.numbers.clear(writer)?;
+    index.presences.clear(writer)?;
     index.postings_lists.clear(writer)?;
     index.docs_words.clear(writer)?;
 
-    let stop_words = match index.main.stop_words_fst(writer)? {
-        Some(stop_words) => stop_words,
-        None => fst::Set::default(),
-    };
-
     let number_of_inserted_documents = documents_ids_to_reindex.len();
-    let mut indexer = RawIndexer::new(stop_words);
-    let mut ram_store = HashMap::new();
+    let mut indexer = indexer_with_limits(writer, index)?;
+    let sortable_attributes = sortable_attributes(writer, index)?;
+    let compress_documents = index.main.compress_documents(writer)?.unwrap_or(DEFAULT_COMPRESS_DOCUMENTS);
 
     if let Some(ref attributes_for_facetting) = index.main.attributes_for_faceting(writer)? {
         let facet_map = facets::facet_map_from_docids(writer, &index, &documents_ids_to_reindex, &attributes_for_facetting)?;
         index.facets.add(writer, facet_map)?;
     }
     // ^-- https://github.com/meilisearch/MeiliSearch/pull/631#issuecomment-626624470 --v
-    for document_id in documents_ids_to_reindex {
-        for result in index.documents_fields.document_fields(writer, document_id)? {
-            let (field_id, bytes) = result?;
-            let value: Value = serde_json::from_slice(bytes)?;
-            ram_store.insert((document_id, field_id), value);
+    // the documents are read by batches, not to keep all of them in memory
+    let mut processed_documents = 0;
+    for documents_ids in documents_ids_to_reindex.chunks(REINDEX_BATCH_SIZE) {
+        index.progress.emit(UpdatePhase::Extraction, processed_documents, number_of_inserted_documents);
+        let mut documents = Vec::with_capacity(documents_ids.len());
+        for &document_id in documents_ids {
+            let mut fields = Vec::new();
+            for result in index.documents_fields.document_fields(writer, document_id)? {
+                let (field_id, bytes) = result?;
+                let value: Value = serde_json::from_slice(&bytes)?;
+                fields.push((field_id, value));
+            }
+            documents.push((document_id, fields));
         }
 
-        // For each key-value pair in the document.
-        for ((document_id, field_id), value) in ram_store.drain() {
-            index_document(
-                writer,
-                index.documents_fields,
-                index.documents_fields_counts,
-                &mut ranked_map,
-                &mut indexer,
-                &schema,
-                field_id,
-                document_id,
-                &value,
-            )?;
+        let mut indexed_documents = Vec::with_capacity(documents.len());
+        for (document_id, fields) in &documents {
+            processed_documents += 1;
+            index.progress.emit_periodically(UpdatePhase::Indexing, processed_documents, number_of_inserted_documents);
+            let document_id = *document_id;
+
+            // For each key-value pair in the document.
+            let mut indexed_fields = Vec::new();
+            for (field_id, value) in fields {
+                let field_id = *field_id;
+                if schema.name(field_id) == Some(GEO_FIELD) && !value.is_null() {
+                    let point = GeoPoint::from_value(value)?;
+                    index.geo.put_document_point(writer, document_id, point)?;
+                }
+
+                if schema.name(field_id) == Some(EXPIRES_AT_FIELD) && !value.is_null() {
+                    let expires_at = expiration_from_value(value)?;
+                    index.expirations.put_document_expiration(writer, document_id, expires_at)?;
+                }
+
+                index_sortable_value(writer, index, sortable_attributes.as_deref(), field_id, document_id, value)?;
+                index_document(
+                    writer,
+                    index.documents_fields,
+                    index.numbers,
+                    index.presences,
+                    &mut ranked_map,
+                    &schema,
+                    field_id,
+                    document_id,
+                    value,
+                    compress_documents,
+                )?;
+                indexed_fields.extend(document_indexed_fields(&schema, field_id, value));
+            }
+            indexed_documents.push((document_id, indexed_fields));
         }
+
+        index_documents_words(writer, &schema, index.documents_fields_counts, &mut indexer, &indexed_documents)?;
     }
 
-    // 4. write the new index in the main store
+    let truncation = TruncationReport::from_indexer(&indexer);
+
+    // 2. write the new index in the main store
+    index.progress.emit(UpdatePhase::FstMerge, number_of_inserted_documents, number_of_inserted_documents);
     write_documents_addition_index(
         writer,
         index,
@@ -321,9 +879,35 @@ pub fn reindex_all_documents(writer: &mut heed::RwTxn<MainT>, index: &store::Ind
         indexer,
     )?;
 
+    compute_fields_frequency(writer, index, &schema)?;
+
     index.main.put_schema(writer, &schema)?;
 
-    Ok(())
+    Ok(truncation)
+}
+
+/// Creates an indexer following the indexing limits of the index.
+fn indexer_with_limits(
+    reader: &heed::RoTxn<MainT>,
+    index: &store::Index,
+) -> MResult<RawIndexer> {
+    // the stop words are indexed when they are searched in the phrases,
+    // the indexer keeps the others while the documents are written
+    let stop_words_in_phrases = index.main.stop_words_in_phrases(reader)?.unwrap_or(DEFAULT_STOP_WORDS_IN_PHRASES);
+    let stop_words = match index.main.stop_words_fst(reader)? {
+        Some(stop_words) if !stop_words_in_phrases => stop_words.into_owned(),
+        _ => fst::Set::default(),
+    };
+
+    let max_positions = index.main.max_positions_per_attribute(reader)?.unwrap_or(DEFAULT_MAX_POSITIONS_PER_ATTRIBUTE);
+    let max_token_length = index.main.max_token_length(reader)?.unwrap_or(DEFAULT_MAX_TOKEN_LENGTH);
+    let fold_diacritics = index.main.fold_diacritics(reader)?.unwrap_or(DEFAULT_FOLD_DIACRITICS);
+    let token_policy = index.main.token_policy(reader)?.unwrap_or_default();
+
+    let mut indexer = RawIndexer::with_limits(stop_words, max_positions, max_token_length);
+    indexer.set_fold_diacritics(fold_diacritics);
+    indexer.set_token_policy(token_policy);
+    Ok(indexer)
 }
 
 pub fn write_documents_addition_index(
@@ -356,7 +940,9 @@ pub fn write_documents_addition_index(
         .and_then(fst::Set::from_bytes)
         .unwrap();
 
-    let words = match index.main.words_fst(writer)? {
+    // the new words are gathered in the words delta, the words set is only
+    // rewritten when the delta holds a significant fraction of its words
+    let delta_words = match index.main.words_delta_fst(writer)? {
         Some(words) => {
             let op = OpBuilder::new()
                 .add(words.stream())
@@ -373,7 +959,12 @@ pub fn write_documents_addition_index(
         None => delta_words,
     };
 
-    index.main.put_words_fst(writer, &words)?;
+    let words_len = index.main.words_fst(writer)?.map_or(0, |words| words.len());
+    index.main.put_words_delta_fst(writer, &delta_words)?;
+    if delta_words.len() >= words_len / WORDS_DELTA_COMPACTION_RATIO {
+        compact_words_fst(writer, index)?;
+    }
+
     index.main.put_ranked_map(writer, ranked_map)?;
     index.main.put_number_of_documents(writer, |old| old + number_of_inserted_documents as u64)?;
 
@@ -1,13 +1,17 @@
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
 
 use fst::{SetBuilder, Streamer};
-use sdset::{duo::DifferenceByKey, SetBuf, SetOperation};
+use sdset::{duo::{Difference, DifferenceByKey, Intersection, Union}, SetBuf, SetOperation};
+use serde_json::Value;
 
 use crate::database::{MainT, UpdateT};
 use crate::database::{UpdateEvent, UpdateEventsEmitter};
+use crate::expiration::{expiration_from_value, EXPIRES_AT_FIELD};
 use crate::facets;
 use crate::store;
-use crate::update::{next_update_id, compute_short_prefixes, Update};
+use crate::update::helpers::{field_numbers, field_presences, update_fields_frequency};
+use crate::update::{next_update_id, compact_words_fst, compute_short_prefixes, Update};
 use crate::{DocumentId, Error, MResult, RankedMap};
 
 pub struct DocumentsDeletion {
@@ -62,16 +66,94 @@ pub fn push_documents_deletion(
     let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
 
     let update = Update::documents_deletion(deletion);
-    updates_store.put_update(writer, last_update_id, &update)?;
+    updates_store.push_update(writer, last_update_id, &update)?;
 
     Ok(last_update_id)
 }
 
+/// The number of deleted documents physically removed from the stores at once by a purge.
+const PURGE_BATCH_SIZE: usize = 1000;
+
+pub fn push_documents_purge(
+    writer: &mut heed::RwTxn<UpdateT>,
+    updates_store: store::Updates,
+    updates_results_store: store::UpdatesResults,
+) -> MResult<u64> {
+    let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
+
+    let update = Update::documents_purge();
+    updates_store.push_update(writer, last_update_id, &update)?;
+
+    Ok(last_update_id)
+}
+
+/// Marks the documents as deleted without touching the postings lists, the searches and the
+/// documents retrieval ignore them until they are physically removed by a purge.
+pub fn apply_documents_soft_deletion(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+    deletion: Vec<DocumentId>,
+) -> MResult<()> {
+    let deleted = index.main.deleted_documents(writer)?.map(Cow::into_owned).unwrap_or_default();
+
+    // only the stored documents that are not already deleted are counted
+    let idset = SetBuf::from_dirty(deletion);
+    let mut newly_deleted = Vec::new();
+    for id in Difference::new(&idset, &deleted).into_set_buf() {
+        if index.documents_fields.document_fields(writer, id)?.next().is_some() {
+            newly_deleted.push(id);
+        }
+    }
+
+    if newly_deleted.is_empty() {
+        return Ok(());
+    }
+
+    // the fields counts are kept until the purge, the fields frequency is updated right away
+    if let Some(schema) = index.main.schema(writer)? {
+        update_fields_frequency(writer, index, &schema, newly_deleted.iter().cloned(), false)?;
+    }
+
+    let newly_deleted = SetBuf::new_unchecked(newly_deleted);
+    let deleted = Union::new(&deleted, &newly_deleted).into_set_buf();
+    index.main.put_deleted_documents(writer, &deleted)?;
+    index.main.put_number_of_documents(writer, |old| old - newly_deleted.len() as u64)?;
+
+    Ok(())
+}
+
+/// Physically removes the deleted documents from the stores, by batches, the words
+/// FST and the prefixes are only rebuilt once all the batches are removed.
+pub fn apply_documents_purge(writer: &mut heed::RwTxn<MainT>, index: &store::Index) -> MResult<()> {
+    let deleted = match index.main.deleted_documents(writer)? {
+        Some(deleted) => deleted.into_owned(),
+        None => return Ok(()),
+    };
+
+    let mut removed_words = BTreeSet::new();
+    for batch in deleted.chunks(PURGE_BATCH_SIZE) {
+        removed_words.append(&mut remove_documents(writer, index, batch.to_vec())?);
+    }
+
+    remove_words(writer, index, removed_words)
+}
+
 pub fn apply_documents_deletion(
     writer: &mut heed::RwTxn<MainT>,
     index: &store::Index,
     deletion: Vec<DocumentId>,
 ) -> MResult<()> {
+    let removed_words = remove_documents(writer, index, deletion)?;
+    remove_words(writer, index, removed_words)
+}
+
+/// Removes the documents from all the stores but the words FST and the prefixes,
+/// returns the words that no longer appear in any document.
+fn remove_documents(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+    deletion: Vec<DocumentId>,
+) -> MResult<BTreeSet<Vec<u8>>> {
     let schema = match index.main.schema(writer)? {
         Some(schema) => schema,
         None => return Err(Error::SchemaMissing),
@@ -92,13 +174,46 @@ pub fn apply_documents_deletion(
     let ranked_fields = schema.ranked();
 
     let idset = SetBuf::from_dirty(deletion);
+    let soft_deleted = index.main.deleted_documents(writer)?.map(Cow::into_owned).unwrap_or_default();
+
+    // the fields frequency is computed from the fields counts that we are about to delete,
+    // the soft deleted documents were already removed from it
+    let not_soft_deleted = Difference::new(&idset, &soft_deleted).into_set_buf();
+    update_fields_frequency(writer, index, &schema, not_soft_deleted.iter().cloned(), false)?;
+
     let mut words_document_ids = HashMap::new();
-    for id in idset {
+    for id in idset.iter().cloned() {
         // remove all the ranked attributes from the ranked_map
         for ranked_attr in ranked_fields {
             ranked_map.remove(id, *ranked_attr);
         }
 
+        index.geo.del_document_point(writer, id)?;
+        index.sortables.del_all_document_values(writer, id)?;
+
+        // the numbers and the expirations are keyed by value, they are found again from the fields of the document
+        let mut numbers = Vec::new();
+        let mut presences = Vec::new();
+        let mut expiration = None;
+        for result in index.documents_fields.document_fields(writer, id)? {
+            let (field_id, bytes) = result?;
+            let value: Value = serde_json::from_slice(&bytes)?;
+            numbers.extend(field_numbers(&schema, field_id, &value));
+            presences.extend(field_presences(&schema, field_id, &value));
+            if schema.name(field_id) == Some(EXPIRES_AT_FIELD) {
+                expiration = expiration_from_value(&value).ok();
+            }
+        }
+        if let Some(expires_at) = expiration {
+            index.expirations.del_document_expiration(writer, id, expires_at)?;
+        }
+        for (field_id, number) in numbers {
+            index.numbers.del_document_number(writer, field_id, number, id)?;
+        }
+        for field_id in presences {
+            index.presences.del_document_field(writer, field_id, id)?;
+        }
+
         if let Some(words) = index.docs_words.doc_words(writer, id)? {
             let mut stream = words.stream();
             while let Some(word) = stream.next() {
@@ -111,36 +226,67 @@ pub fn apply_documents_deletion(
         }
     }
 
-    let mut deleted_documents = HashSet::new();
     let mut removed_words = BTreeSet::new();
     for (word, document_ids) in words_document_ids {
         let document_ids = SetBuf::from_dirty(document_ids);
 
         if let Some(postings) = index.postings_lists.postings_list(writer, &word)? {
-            let op = DifferenceByKey::new(&postings.matches, &document_ids, |d| d.document_id, |id| *id);
-            let doc_indexes = op.into_set_buf();
+            // the documents ids are much shorter than the matches, they tell
+            // whether the word remains without computing the matches left
+            let remaining = Difference::new(&postings.docids, &document_ids).into_set_buf();
 
-            if !doc_indexes.is_empty() {
+            if !remaining.is_empty() {
+                let op = DifferenceByKey::new(&postings.matches, &document_ids, |d| d.document_id, |id| *id);
+                let doc_indexes = op.into_set_buf();
                 index.postings_lists.put_postings_list(writer, &word, &doc_indexes)?;
             } else {
                 index.postings_lists.del_postings_list(writer, &word)?;
                 removed_words.insert(word);
             }
         }
+    }
 
-        for id in document_ids {
-            index.documents_fields_counts.del_all_document_fields_counts(writer, id)?;
-            if index.documents_fields.del_all_document_fields(writer, id)? != 0 {
-                deleted_documents.insert(id);
-            }
+    // the documents without any indexed word are removed too
+    let mut deleted_documents = Vec::new();
+    for id in idset.iter().cloned() {
+        index.documents_fields_counts.del_all_document_fields_counts(writer, id)?;
+        if index.documents_fields.del_all_document_fields(writer, id)? != 0 {
+            index.docs_words.del_doc_words(writer, id)?;
+            deleted_documents.push(id);
         }
     }
+    let deleted_documents = SetBuf::new_unchecked(deleted_documents);
 
-    let deleted_documents_len = deleted_documents.len() as u64;
-    for id in deleted_documents {
-        index.docs_words.del_doc_words(writer, id)?;
+    // the soft deleted documents were already removed from the number of documents
+    let mut deleted_documents_len = deleted_documents.len() as u64;
+    if !soft_deleted.is_empty() {
+        let purged = Intersection::new(&soft_deleted, &deleted_documents).into_set_buf();
+        if !purged.is_empty() {
+            deleted_documents_len -= purged.len() as u64;
+            let remaining = Difference::new(&soft_deleted, &purged).into_set_buf();
+            if remaining.is_empty() {
+                index.main.delete_deleted_documents(writer)?;
+            } else {
+                index.main.put_deleted_documents(writer, &remaining)?;
+            }
+        }
     }
 
+    index.main.put_ranked_map(writer, &ranked_map)?;
+    index.main.put_number_of_documents(writer, |old| old - deleted_documents_len)?;
+
+    Ok(removed_words)
+}
+
+/// Removes the words from the words FST and computes the prefixes again.
+fn remove_words(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+    removed_words: BTreeSet<Vec<u8>>,
+) -> MResult<()> {
+    // the removed words can be in the words delta, it is merged first
+    compact_words_fst(writer, index)?;
+
     let removed_words = fst::Set::from_iter(removed_words).unwrap();
     let words = match index.main.words_fst(writer)? {
         Some(words_set) => {
@@ -160,8 +306,6 @@ pub fn apply_documents_deletion(
     };
 
     index.main.put_words_fst(writer, &words)?;
-    index.main.put_ranked_map(writer, &ranked_map)?;
-    index.main.put_number_of_documents(writer, |old| old - deleted_documents_len)?;
 
     compute_short_prefixes(writer, index)?;
 
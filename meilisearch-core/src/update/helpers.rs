@@ -1,16 +1,21 @@
+use std::collections::HashMap;
 use std::fmt::Write as _;
 use std::hash::{Hash, Hasher};
 
+use chrono::{DateTime, NaiveDate};
 use indexmap::IndexMap;
-use meilisearch_schema::IndexedPos;
+use meilisearch_schema::{FieldId, FieldType, IndexedPos, Schema};
 use meilisearch_types::DocumentId;
 use ordered_float::OrderedFloat;
+use serde::Deserialize;
 use serde_json::Value;
 use siphasher::sip::SipHasher;
 
-use crate::raw_indexer::RawIndexer;
+use crate::database::MainT;
+use crate::raw_indexer::{PreToken, RawIndexer};
 use crate::serde::SerializerError;
-use crate::Number;
+use crate::settings::DocumentIdFormat;
+use crate::{store, MResult, Number};
 
 /// Returns the number of words indexed or `None` if the type is unindexable.
 pub fn index_value(
@@ -45,6 +50,29 @@ pub fn index_value(
     }
 }
 
+/// Indexes the value of a field declared as pre-tokenized, returns the number of tokens
+/// indexed or `None` if the value is not a list of tokens.
+pub fn index_pre_tokenized_value(
+    indexer: &mut RawIndexer,
+    document_id: DocumentId,
+    indexed_pos: IndexedPos,
+    value: &Value,
+) -> Option<usize>
+{
+    let tokens = pre_tokens_from_value(value)?;
+    Some(indexer.index_pre_tokenized(document_id, indexed_pos, &tokens))
+}
+
+/// Reads the tokens of a pre-tokenized field, the end of a token can't be before its start.
+pub fn pre_tokens_from_value(value: &Value) -> Option<Vec<PreToken>> {
+    let tokens = Vec::<PreToken>::deserialize(value).ok()?;
+    if tokens.iter().all(|token| token.start <= token.end) {
+        Some(tokens)
+    } else {
+        None
+    }
+}
+
 /// Transforms the JSON Value type into a String.
 pub fn value_to_string(value: &Value) -> String {
     fn internal_value_to_string(string: &mut String, value: &Value) {
@@ -96,10 +124,182 @@ pub fn value_to_number(value: &Value) -> Option<Number> {
     }
 }
 
-/// Validates a string representation to be a correct document id and
-/// returns the hash of the given type, this is the way we produce documents ids.
-pub fn compute_document_id(string: &str) -> Result<DocumentId, SerializerError> {
-    if string.chars().all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '_') {
+/// Parses an RFC 3339 date, or a `YYYY-MM-DD` day at midnight UTC, into a timestamp in seconds.
+pub fn date_to_timestamp(string: &str) -> Option<i64> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(string) {
+        return Some(date.timestamp());
+    }
+    let day = NaiveDate::parse_from_str(string, "%Y-%m-%d").ok()?;
+    Some(day.and_hms(0, 0, 0).timestamp())
+}
+
+/// Returns `true` if the value is of the declared type, null values
+/// and arrays of values of this type are accepted too.
+pub fn value_has_type(value: &Value, field_type: FieldType) -> bool {
+    match (value, field_type) {
+        (Value::Null, _) => true,
+        (value, FieldType::Tokens) => pre_tokens_from_value(value).is_some(),
+        (Value::Array(values), _) => values.iter().all(|value| !value.is_array() && value_has_type(value, field_type)),
+        (Value::String(_), FieldType::String) => true,
+        (Value::Number(_), FieldType::Number) => true,
+        (Value::String(string), FieldType::Date) => date_to_timestamp(string).is_some(),
+        (Value::Number(number), FieldType::Date) => number.is_i64(),
+        (Value::Bool(_), FieldType::Bool) => true,
+        _ => false,
+    }
+}
+
+/// Like `value_to_number` but the dates of the fields declared as such are converted into timestamps.
+pub fn typed_value_to_number(value: &Value, field_type: Option<FieldType>) -> Option<Number> {
+    match (value, field_type) {
+        (Value::String(string), Some(FieldType::Date)) => date_to_timestamp(string).map(Number::Signed),
+        (value, _) => value_to_number(value),
+    }
+}
+
+/// Like `store::value_numbers` but the dates of the fields declared as such are converted into timestamps.
+pub fn typed_value_numbers(value: &Value, field_type: Option<FieldType>) -> Vec<f64> {
+    match (value, field_type) {
+        (Value::String(string), Some(FieldType::Date)) => date_to_timestamp(string).map(|ts| ts as f64).into_iter().collect(),
+        (Value::Array(values), Some(FieldType::Date)) => {
+            values.iter().flat_map(|value| typed_value_numbers(value, field_type)).collect()
+        },
+        (value, _) => store::value_numbers(value),
+    }
+}
+
+/// Returns the value found by following the dotted path in the nested objects.
+pub fn value_at_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, key| match value {
+        Value::Object(object) => object.get(key),
+        _ => None,
+    })
+}
+
+/// Returns the values found by following the dotted path in the nested objects,
+/// the arrays of objects are traversed, `tags.name` gives the names of all the tags.
+pub fn values_at_path<'a>(value: &'a Value, path: &str) -> Vec<&'a Value> {
+    fn collect<'a>(value: &'a Value, keys: &[&str], values: &mut Vec<&'a Value>) {
+        match (keys.split_first(), value) {
+            (_, Value::Array(array)) => array.iter().for_each(|value| collect(value, keys, values)),
+            (None, value) => values.push(value),
+            (Some((key, keys)), Value::Object(object)) => {
+                if let Some(value) = object.get(*key) {
+                    collect(value, keys, values);
+                }
+            },
+            (Some(_), _) => (),
+        }
+    }
+
+    let keys: Vec<_> = path.split('.').collect();
+    let mut values = Vec::new();
+    collect(value, &keys, &mut values);
+    values
+}
+
+/// Returns the value of the dotted path, the values found in the arrays of objects
+/// are gathered in an array, e.g. `{"tags": [{"name": "a"}, {"name": "b"}]}` gives
+/// `["a", "b"]` for `tags.name`.
+pub fn nested_value(value: &Value, path: &str) -> Option<Value> {
+    if let Some(value) = value_at_path(value, path) {
+        return Some(value.clone());
+    }
+
+    let values = values_at_path(value, path);
+    if values.is_empty() {
+        None
+    } else {
+        Some(Value::Array(values.into_iter().cloned().collect()))
+    }
+}
+
+/// Applies a partial document to a document following the JSON merge-patch semantics
+/// (RFC 7396), a `null` removes the field and the objects are merged recursively.
+pub fn merge_patch(document: &mut IndexMap<String, Value>, patch: IndexMap<String, Value>) {
+    for (key, value) in patch {
+        if value.is_null() {
+            document.shift_remove(&key);
+        } else {
+            merge_patch_value(document.entry(key).or_insert(Value::Null), value);
+        }
+    }
+}
+
+fn merge_patch_value(target: &mut Value, patch: Value) {
+    match patch {
+        Value::Object(patch) => {
+            if !target.is_object() {
+                *target = Value::Object(Default::default());
+            }
+            let target = target.as_object_mut().unwrap();
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.remove(&key);
+                } else {
+                    merge_patch_value(target.entry(key).or_insert(Value::Null), value);
+                }
+            }
+        }
+        patch => *target = patch,
+    }
+}
+
+/// Returns the path of the field relative to the given top-level field,
+/// `name` in `author.name` for the `author` field.
+pub fn nested_path<'a>(field: &'a str, parent: &str) -> Option<&'a str> {
+    if field.len() > parent.len() + 1 && field.starts_with(parent) && field.as_bytes()[parent.len()] == b'.' {
+        Some(&field[parent.len() + 1..])
+    } else {
+        None
+    }
+}
+
+/// Returns the fields of the schema nested in the given top-level field,
+/// along with their path relative to it.
+pub fn nested_fields(schema: &Schema, field_id: FieldId) -> Vec<(FieldId, &str)> {
+    let name = match schema.name(field_id) {
+        Some(name) => name,
+        None => return Vec::new(),
+    };
+
+    schema
+        .names()
+        .filter_map(|nested| Some((schema.id(nested)?, nested_path(nested, name)?)))
+        .collect()
+}
+
+/// Returns the numbers of a document field and of the fields nested in it,
+/// these are the numbers stored in the numbers store.
+pub fn field_numbers(schema: &Schema, field_id: FieldId, value: &Value) -> Vec<(FieldId, f64)> {
+    let field_type = schema.field_type(field_id);
+    let mut numbers: Vec<_> = typed_value_numbers(value, field_type).into_iter().map(|number| (field_id, number)).collect();
+    for (nested_id, path) in nested_fields(schema, field_id) {
+        let field_type = schema.field_type(nested_id);
+        for value in values_at_path(value, path) {
+            numbers.extend(typed_value_numbers(value, field_type).into_iter().map(|number| (nested_id, number)));
+        }
+    }
+    numbers
+}
+
+/// Returns the document field and the fields nested in it that are present in the value,
+/// these are the fields stored in the presences store.
+pub fn field_presences(schema: &Schema, field_id: FieldId, value: &Value) -> Vec<FieldId> {
+    let mut fields = vec![field_id];
+    for (nested_id, path) in nested_fields(schema, field_id) {
+        if !values_at_path(value, path).is_empty() {
+            fields.push(nested_id);
+        }
+    }
+    fields
+}
+
+/// Validates a string representation to be a correct document id according to the
+/// format of the index and returns the hash of the given type, this is the way we
+/// produce documents ids, whatever the length of the string the id fits in a `u64`.
+pub fn compute_document_id(string: &str, format: DocumentIdFormat) -> Result<DocumentId, SerializerError> {
+    if format.is_valid(string) {
         let mut s = SipHasher::new();
         string.hash(&mut s);
         Ok(DocumentId(s.finish()))
@@ -108,17 +308,184 @@ pub fn compute_document_id(string: &str) -> Result<DocumentId, SerializerError>
     }
 }
 
+/// Returns the canonical string representation of a primary key value.
+///
+/// Integers are written in decimal, this way `12` and `"12"` refer to the same document.
+/// Floats keep the representation they always had, e.g. `12.0`, the hash of the string
+/// is the stored document id and must not change for the documents already indexed.
+pub fn document_id_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(string) => Some(string.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
 /// Extracts and validates the document id of a document.
-pub fn extract_document_id(primary_key: &str, document: &IndexMap<String, Value>) -> Result<DocumentId, SerializerError> {
+pub fn extract_document_id(
+    primary_key: &str,
+    document: &IndexMap<String, Value>,
+    format: DocumentIdFormat,
+) -> Result<DocumentId, SerializerError> {
     match document.get(primary_key) {
         Some(value) => {
-            let string = match value {
-                Value::Number(number) => number.to_string(),
-                Value::String(string) => string.clone(),
-                _ => return Err(SerializerError::InvalidDocumentIdFormat),
-            };
-            compute_document_id(&string)
+            let string = document_id_to_string(value).ok_or(SerializerError::InvalidDocumentIdFormat)?;
+            compute_document_id(&string, format)
         }
         None => Err(SerializerError::DocumentIdNotFound),
     }
 }
+
+/// Increments, or decrements, the frequency of every indexed field of the given documents,
+/// this must be called while the documents fields counts of these documents are stored.
+pub fn update_fields_frequency(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+    schema: &Schema,
+    documents_ids: impl IntoIterator<Item = DocumentId>,
+    increment: bool,
+) -> MResult<()> {
+    let mut fields_frequency = index.main.fields_frequency(writer)?.unwrap_or_default();
+
+    for document_id in documents_ids {
+        for result in index.documents_fields_counts.document_fields_counts(writer, document_id)? {
+            let (indexed_pos, _) = result?;
+            let name = match schema.indexed_pos_to_field_id(indexed_pos).and_then(|id| schema.name(id)) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            if increment {
+                *fields_frequency.entry(name.to_string()).or_default() += 1;
+            } else if let Some(frequency) = fields_frequency.get_mut(name) {
+                *frequency = frequency.saturating_sub(1);
+                if *frequency == 0 {
+                    fields_frequency.remove(name);
+                }
+            }
+        }
+    }
+
+    index.main.put_fields_frequency(writer, &fields_frequency)?;
+
+    Ok(())
+}
+
+/// Computes the frequency of every indexed field from scratch.
+pub fn compute_fields_frequency(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+    schema: &Schema,
+) -> MResult<()> {
+    let deleted = index.main.deleted_documents(writer)?.map(|set| set.into_owned()).unwrap_or_default();
+
+    let mut fields_frequency = HashMap::<_, usize>::new();
+    for result in index.documents_fields_counts.all_documents_fields_counts(writer)? {
+        let (document_id, indexed_pos, _) = result?;
+        if deleted.binary_search(&document_id).is_ok() {
+            continue;
+        }
+        if let Some(name) = schema.indexed_pos_to_field_id(indexed_pos).and_then(|id| schema.name(id)) {
+            *fields_frequency.entry(name.to_string()).or_default() += 1;
+        }
+    }
+
+    index.main.put_fields_frequency(writer, &fields_frequency)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn canonical_document_ids() {
+        assert_eq!(document_id_to_string(&json!(12)), Some("12".to_string()));
+        assert_eq!(document_id_to_string(&json!(-12)), Some("-12".to_string()));
+        assert_eq!(document_id_to_string(&json!(u64::max_value())), Some(u64::max_value().to_string()));
+        assert_eq!(document_id_to_string(&json!("12")), Some("12".to_string()));
+        assert_eq!(document_id_to_string(&json!(true)), None);
+        assert_eq!(document_id_to_string(&json!(null)), None);
+
+        // floats keep the representation the stored documents ids were computed from
+        assert_eq!(document_id_to_string(&json!(12.0)), Some("12.0".to_string()));
+        assert_eq!(document_id_to_string(&json!(12.5)), Some("12.5".to_string()));
+
+        let number = compute_document_id("12", DocumentIdFormat::Strict).unwrap();
+        let mut document = IndexMap::new();
+        document.insert("id".to_string(), json!(12));
+        assert_eq!(extract_document_id("id", &document, DocumentIdFormat::Strict).unwrap(), number);
+    }
+
+    #[test]
+    fn merge_patch_documents() {
+        let mut document: IndexMap<String, Value> = serde_json::from_value(json!({
+            "id": 1,
+            "title": "Dune",
+            "author": { "name": "Frank Herbert", "born": 1920 },
+            "tags": ["sf", "desert"],
+        })).unwrap();
+
+        let patch: IndexMap<String, Value> = serde_json::from_value(json!({
+            "id": 1,
+            "title": null,
+            "author": { "born": null, "country": "US" },
+            "tags": ["sf"],
+            "year": 1965,
+        })).unwrap();
+
+        merge_patch(&mut document, patch);
+
+        let expected = json!({
+            "id": 1,
+            "author": { "name": "Frank Herbert", "country": "US" },
+            "tags": ["sf"],
+            "year": 1965,
+        });
+        assert_eq!(serde_json::to_value(document).unwrap(), expected);
+    }
+
+    #[test]
+    fn nested_values() {
+        let value = json!({ "ratings": { "average": 4.5, "count": 12 }, "title": "Dune" });
+
+        assert_eq!(value_at_path(&value, "ratings.average"), Some(&json!(4.5)));
+        assert_eq!(value_at_path(&value, "title"), Some(&json!("Dune")));
+        assert_eq!(value_at_path(&value, "ratings.median"), None);
+        assert_eq!(value_at_path(&value, "title.length"), None);
+
+        let value = json!({ "author": { "name": "Frank" }, "tags": [{ "name": "sf" }, { "name": "classic" }, 12] });
+
+        assert_eq!(nested_value(&value, "author.name"), Some(json!("Frank")));
+        assert_eq!(nested_value(&value, "tags.name"), Some(json!(["sf", "classic"])));
+        assert_eq!(nested_value(&value, "tags.year"), None);
+        assert_eq!(nested_path("author.name", "author"), Some("name"));
+        assert_eq!(nested_path("authors.name", "author"), None);
+        assert_eq!(nested_path("author", "author"), None);
+    }
+
+    #[test]
+    fn typed_values() {
+        assert_eq!(date_to_timestamp("2020-09-13T12:26:40Z"), Some(1_600_000_000));
+        assert_eq!(date_to_timestamp("2020-09-13"), Some(1_599_955_200));
+        assert_eq!(date_to_timestamp("13/09/2020"), None);
+
+        assert!(value_has_type(&json!("2020-09-13"), FieldType::Date));
+        assert!(value_has_type(&json!(["2020-09-13", null]), FieldType::Date));
+        assert!(!value_has_type(&json!("tomorrow"), FieldType::Date));
+        assert!(value_has_type(&json!(12.5), FieldType::Number));
+        assert!(!value_has_type(&json!("12.5"), FieldType::Number));
+        assert!(!value_has_type(&json!([[true]]), FieldType::Bool));
+        assert!(value_has_type(&json!([{ "token": "heart", "position": 0, "start": 0, "end": 5 }]), FieldType::Tokens));
+        assert!(!value_has_type(&json!([{ "token": "heart", "position": 0, "start": 5, "end": 0 }]), FieldType::Tokens));
+        assert!(!value_has_type(&json!({ "token": "heart", "position": 0, "start": 0, "end": 5 }), FieldType::Tokens));
+        assert!(!value_has_type(&json!("heart"), FieldType::Tokens));
+
+        let date = typed_value_to_number(&json!("2020-09-13T12:26:40Z"), Some(FieldType::Date));
+        assert_eq!(date, Some(Number::Signed(1_600_000_000)));
+        assert_eq!(typed_value_to_number(&json!("2020-09-13"), None), None);
+        assert_eq!(typed_value_numbers(&json!(["2020-09-13T12:26:40Z", 12]), Some(FieldType::Date)), vec![1_600_000_000.0, 12.0]);
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+
+use heed::Result as ZResult;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::database::{MainT, UpdateT};
+use crate::{store, MResult};
+use crate::update::{next_update_id, Update};
+
+/// The metadata of a namespace to write or delete, the other namespaces are left untouched.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetadataUpdate {
+    pub namespace: String,
+    pub entries: BTreeMap<String, MetadataOperation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MetadataOperation {
+    Put(Value),
+    Delete,
+}
+
+impl MetadataUpdate {
+    pub fn new(namespace: impl Into<String>) -> MetadataUpdate {
+        MetadataUpdate { namespace: namespace.into(), entries: BTreeMap::new() }
+    }
+
+    pub fn put(&mut self, key: impl Into<String>, value: Value) {
+        self.entries.insert(key.into(), MetadataOperation::Put(value));
+    }
+
+    pub fn delete(&mut self, key: impl Into<String>) {
+        self.entries.insert(key.into(), MetadataOperation::Delete);
+    }
+}
+
+pub fn apply_metadata_update(
+    writer: &mut heed::RwTxn<MainT>,
+    main_store: store::Main,
+    update: &MetadataUpdate,
+) -> ZResult<()> {
+    for (key, operation) in &update.entries {
+        match operation {
+            MetadataOperation::Put(value) => main_store.put_metadata(writer, &update.namespace, key, value)?,
+            MetadataOperation::Delete => {
+                main_store.delete_metadata(writer, &update.namespace, key)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn push_metadata_update(
+    writer: &mut heed::RwTxn<UpdateT>,
+    updates_store: store::Updates,
+    updates_results_store: store::UpdatesResults,
+    update: MetadataUpdate,
+) -> MResult<u64> {
+    let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
+
+    let update = Update::metadata(update);
+    updates_store.push_update(writer, last_update_id, &update)?;
+
+    Ok(last_update_id)
+}
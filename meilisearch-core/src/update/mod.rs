@@ -1,30 +1,40 @@
+mod batch;
 mod clear_all;
-mod customs_update;
+mod coalesce;
 mod documents_addition;
 mod documents_deletion;
+mod metadata_update;
+mod progress;
+mod repair;
 mod settings_update;
 mod helpers;
 
+pub use self::batch::{push_batch, UpdatesBatch};
 pub use self::clear_all::{apply_clear_all, push_clear_all};
-pub use self::customs_update::{apply_customs_update, push_customs_update};
-pub use self::documents_addition::{apply_documents_addition, apply_documents_partial_addition, DocumentsAddition};
-pub use self::documents_deletion::{apply_documents_deletion, DocumentsDeletion};
-pub use self::helpers::{index_value, value_to_string, value_to_number, compute_document_id, extract_document_id};
-pub use self::settings_update::{apply_settings_update, push_settings_update};
+pub use self::coalesce::{coalesced_update_task, next_updates};
+pub use self::documents_addition::{apply_documents_addition, apply_documents_partial_addition, AdditionOptions, DEFAULT_ADDITION_CHUNK_SIZE, AdditionReport, DocumentError, DocumentsAddition, DuplicatesPolicy, DEFAULT_PRIMARY_KEY_SUFFIX, infer_primary_key, DuplicatesReport, TruncationReport};
+pub use self::documents_deletion::{apply_documents_deletion, apply_documents_purge, apply_documents_soft_deletion, push_documents_purge, DocumentsDeletion};
+pub use self::metadata_update::{apply_metadata_update, push_metadata_update, MetadataOperation, MetadataUpdate};
+pub use self::progress::{UpdatePhase, UpdateProgress, UpdateProgressEmitter};
+pub use self::repair::{apply_repair, push_repair};
+pub use self::helpers::{index_value, value_to_string, value_to_number, date_to_timestamp, compute_document_id, document_id_to_string, extract_document_id};
+pub use self::helpers::{nested_fields, nested_path, nested_value};
+pub use self::settings_update::{apply_settings_update, push_noop_settings_update, push_settings_update, settings_update_is_noop, ReindexPlan, SettingsDiff};
 
 use std::cmp;
 use std::time::Instant;
 
 use chrono::{DateTime, Utc};
-use fst::{IntoStreamer, Streamer};
+use fst::{set::OpBuilder, SetBuilder, Streamer};
 use heed::Result as ZResult;
 use indexmap::IndexMap;
 use log::debug;
 use sdset::Set;
+use slice_group_by::GroupBy;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::{store, DocumentId, MResult};
+use crate::{store, DocIndex, DocumentId, Error, MResult};
 use crate::database::{MainT, UpdateT};
 use crate::settings::SettingsUpdate;
 
@@ -32,6 +42,8 @@ use crate::settings::SettingsUpdate;
 pub struct Update {
     data: UpdateData,
     enqueued_at: DateTime<Utc>,
+    #[serde(default)]
+    addition_options: AdditionOptions,
 }
 
 impl Update {
@@ -39,27 +51,31 @@ impl Update {
         Update {
             data: UpdateData::ClearAll,
             enqueued_at: Utc::now(),
+            addition_options: AdditionOptions::default(),
         }
     }
 
-    fn customs(data: Vec<u8>) -> Update {
+    fn metadata(update: MetadataUpdate) -> Update {
         Update {
-            data: UpdateData::Customs(data),
+            data: UpdateData::Metadata(update),
             enqueued_at: Utc::now(),
+            addition_options: AdditionOptions::default(),
         }
     }
 
-    fn documents_addition(documents: Vec<IndexMap<String, Value>>) -> Update {
+    fn documents_addition(documents: Vec<IndexMap<String, Value>>, addition_options: AdditionOptions) -> Update {
         Update {
             data: UpdateData::DocumentsAddition(documents),
             enqueued_at: Utc::now(),
+            addition_options,
         }
     }
 
-    fn documents_partial(documents: Vec<IndexMap<String, Value>>) -> Update {
+    fn documents_partial(documents: Vec<IndexMap<String, Value>>, addition_options: AdditionOptions) -> Update {
         Update {
             data: UpdateData::DocumentsPartial(documents),
             enqueued_at: Utc::now(),
+            addition_options,
         }
     }
 
@@ -67,6 +83,15 @@ impl Update {
         Update {
             data: UpdateData::DocumentsDeletion(data),
             enqueued_at: Utc::now(),
+            addition_options: AdditionOptions::default(),
+        }
+    }
+
+    fn documents_purge() -> Update {
+        Update {
+            data: UpdateData::DocumentsPurge,
+            enqueued_at: Utc::now(),
+            addition_options: AdditionOptions::default(),
         }
     }
 
@@ -74,25 +99,86 @@ impl Update {
         Update {
             data: UpdateData::Settings(data),
             enqueued_at: Utc::now(),
+            addition_options: AdditionOptions::default(),
+        }
+    }
+
+    fn repair() -> Update {
+        Update {
+            data: UpdateData::Repair,
+            enqueued_at: Utc::now(),
+            addition_options: AdditionOptions::default(),
+        }
+    }
+
+    fn batch(updates: Vec<Update>) -> Update {
+        Update {
+            data: UpdateData::Batch(updates),
+            enqueued_at: Utc::now(),
+            addition_options: AdditionOptions::default(),
         }
     }
 }
 
+/// An operation applied to the database, in the form it is replayed on a standby database.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum UpdateLogEntry {
+    IndexCreation {
+        index_uid: String,
+    },
+    IndexDeletion {
+        index_uid: String,
+    },
+    IndexesSwap {
+        lhs: String,
+        rhs: String,
+    },
+    IndexRename {
+        old_uid: String,
+        new_uid: String,
+    },
+    IndexCopy {
+        source: String,
+        destination: String,
+        with_updates: bool,
+    },
+    AliasUpdate {
+        alias: String,
+        index_uid: String,
+    },
+    AliasDeletion {
+        alias: String,
+    },
+    Update {
+        index_uid: String,
+        update_id: u64,
+        // the primary key is set outside of the updates, it is needed to replay an addition
+        primary_key: Option<String>,
+        update: Update,
+    },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum UpdateData {
     ClearAll,
-    Customs(Vec<u8>),
+    Metadata(MetadataUpdate),
     DocumentsAddition(Vec<IndexMap<String, Value>>),
     DocumentsPartial(Vec<IndexMap<String, Value>>),
     DocumentsDeletion(Vec<DocumentId>),
-    Settings(SettingsUpdate)
+    DocumentsPurge,
+    Settings(SettingsUpdate),
+    Repair,
+    Batch(Vec<Update>),
 }
 
 impl UpdateData {
     pub fn update_type(&self) -> UpdateType {
         match self {
             UpdateData::ClearAll => UpdateType::ClearAll,
-            UpdateData::Customs(_) => UpdateType::Customs,
+            UpdateData::Metadata(update) => UpdateType::Metadata {
+                namespace: update.namespace.clone(),
+            },
             UpdateData::DocumentsAddition(addition) => UpdateType::DocumentsAddition {
                 number: addition.len(),
             },
@@ -102,9 +188,14 @@ impl UpdateData {
             UpdateData::DocumentsDeletion(deletion) => UpdateType::DocumentsDeletion {
                 number: deletion.len(),
             },
+            UpdateData::DocumentsPurge => UpdateType::DocumentsPurge,
             UpdateData::Settings(update) => UpdateType::Settings {
                 settings: update.clone(),
             },
+            UpdateData::Repair => UpdateType::Repair,
+            UpdateData::Batch(updates) => UpdateType::Batch {
+                updates: updates.iter().map(|update| update.data.update_type()).collect(),
+            },
         }
     }
 }
@@ -113,11 +204,31 @@ impl UpdateData {
 #[serde(tag = "name")]
 pub enum UpdateType {
     ClearAll,
-    Customs,
+    Metadata { namespace: String },
     DocumentsAddition { number: usize },
     DocumentsPartial { number: usize },
     DocumentsDeletion { number: usize },
+    DocumentsPurge,
     Settings { settings: SettingsUpdate },
+    Repair,
+    Batch { updates: Vec<UpdateType> },
+}
+
+impl UpdateType {
+    /// The name of the update type, as serialized in the update statuses.
+    pub fn name(&self) -> &'static str {
+        match self {
+            UpdateType::ClearAll => "ClearAll",
+            UpdateType::Metadata { .. } => "Metadata",
+            UpdateType::DocumentsAddition { .. } => "DocumentsAddition",
+            UpdateType::DocumentsPartial { .. } => "DocumentsPartial",
+            UpdateType::DocumentsDeletion { .. } => "DocumentsDeletion",
+            UpdateType::DocumentsPurge => "DocumentsPurge",
+            UpdateType::Settings { .. } => "Settings",
+            UpdateType::Repair => "Repair",
+            UpdateType::Batch { .. } => "Batch",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -131,6 +242,108 @@ pub struct ProcessedUpdateResult {
     pub duration: f64, // in seconds
     pub enqueued_at: DateTime<Utc>,
     pub processed_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings_diff: Option<SettingsDiff>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicates: Option<DuplicatesReport>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated_ids: Option<Vec<String>>,
+    /// The primary key inferred from the first document added to an index without one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inferred_primary_key: Option<String>,
+    /// The documents skipped by an addition because they could not be indexed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed_documents: Option<Vec<DocumentError>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub truncation: Option<TruncationReport>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interruption: Option<Interruption>,
+}
+
+/// Why an update was removed from the queue without being applied.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Interruption {
+    /// The update was canceled while it was waiting to be processed.
+    Canceled,
+    /// The update was being processed when the database was shut down.
+    Aborted,
+}
+
+impl ProcessedUpdateResult {
+    /// The result of an update that has been interrupted before being applied.
+    pub fn interrupted(update_id: u64, update: &Update, interruption: Interruption) -> ProcessedUpdateResult {
+        let result = ProcessedUpdateResult {
+            update_id,
+            update_type: update.data.update_type(),
+            error: None,
+            duration: 0.0,
+            enqueued_at: update.enqueued_at,
+            processed_at: Utc::now(),
+            settings_diff: None,
+            duplicates: None,
+            generated_ids: None,
+            inferred_primary_key: None,
+            failed_documents: None,
+            truncation: None,
+            interruption: None,
+        };
+        result.interrupt(interruption)
+    }
+
+    /// Marks the update as interrupted, what it reported while being processed is dropped.
+    pub fn interrupt(self, interruption: Interruption) -> ProcessedUpdateResult {
+        let error = match interruption {
+            Interruption::Canceled => "the update was canceled",
+            Interruption::Aborted => "the update was aborted by a shutdown",
+        };
+
+        ProcessedUpdateResult {
+            error: Some(error.to_string()),
+            settings_diff: None,
+            duplicates: None,
+            generated_ids: None,
+            inferred_primary_key: None,
+            failed_documents: None,
+            truncation: None,
+            interruption: Some(interruption),
+            ..self
+        }
+    }
+}
+
+/// What happened to an update that was asked to be canceled.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CancelOutcome {
+    Canceled,
+    /// The update is the first one of the queue, it may already be processed.
+    Processing,
+    /// The update has already been processed or does not exist.
+    NotEnqueued,
+}
+
+/// Removes an enqueued update from the queue and records it as canceled,
+/// the first update of the queue cannot be canceled.
+pub fn cancel_update(
+    writer: &mut heed::RwTxn<UpdateT>,
+    updates_store: store::Updates,
+    updates_results_store: store::UpdatesResults,
+    update_id: u64,
+) -> MResult<CancelOutcome> {
+    let update = match updates_store.get(writer, update_id)? {
+        Some(update) => update,
+        None => return Ok(CancelOutcome::NotEnqueued),
+    };
+
+    if updates_store.first_update(writer)?.map(|(id, _)| id) == Some(update_id) {
+        return Ok(CancelOutcome::Processing);
+    }
+
+    let result = ProcessedUpdateResult::interrupted(update_id, &update, Interruption::Canceled);
+    updates_store.del_update(writer, update_id)?;
+    updates_results_store.put_update_result(writer, update_id, &result)?;
+
+    Ok(CancelOutcome::Canceled)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -140,6 +353,9 @@ pub struct EnqueuedUpdateResult {
     #[serde(rename = "type")]
     pub update_type: UpdateType,
     pub enqueued_at: DateTime<Utc>,
+    /// The progress of the update when it is the one being processed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<UpdateProgress>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -157,6 +373,57 @@ pub enum UpdateStatus {
         #[serde(flatten)]
         content: ProcessedUpdateResult,
     },
+    Canceled {
+        #[serde(flatten)]
+        content: ProcessedUpdateResult,
+    },
+    Aborted {
+        #[serde(flatten)]
+        content: ProcessedUpdateResult,
+    },
+}
+
+impl UpdateStatus {
+    pub fn update_id(&self) -> u64 {
+        match self {
+            UpdateStatus::Enqueued { content } => content.update_id,
+            UpdateStatus::Failed { content }
+            | UpdateStatus::Processed { content }
+            | UpdateStatus::Canceled { content }
+            | UpdateStatus::Aborted { content } => content.update_id,
+        }
+    }
+
+    pub fn update_type(&self) -> &UpdateType {
+        match self {
+            UpdateStatus::Enqueued { content } => &content.update_type,
+            UpdateStatus::Failed { content }
+            | UpdateStatus::Processed { content }
+            | UpdateStatus::Canceled { content }
+            | UpdateStatus::Aborted { content } => &content.update_type,
+        }
+    }
+
+    pub fn enqueued_at(&self) -> DateTime<Utc> {
+        match self {
+            UpdateStatus::Enqueued { content } => content.enqueued_at,
+            UpdateStatus::Failed { content }
+            | UpdateStatus::Processed { content }
+            | UpdateStatus::Canceled { content }
+            | UpdateStatus::Aborted { content } => content.enqueued_at,
+        }
+    }
+
+    /// The name of the status, as serialized in the update statuses.
+    pub fn status(&self) -> &'static str {
+        match self {
+            UpdateStatus::Enqueued { .. } => "enqueued",
+            UpdateStatus::Failed { .. } => "failed",
+            UpdateStatus::Processed { .. } => "processed",
+            UpdateStatus::Canceled { .. } => "canceled",
+            UpdateStatus::Aborted { .. } => "aborted",
+        }
+    }
 }
 
 pub fn update_status(
@@ -166,12 +433,11 @@ pub fn update_status(
     update_id: u64,
 ) -> MResult<Option<UpdateStatus>> {
     match updates_results_store.update_result(update_reader, update_id)? {
-        Some(result) => {
-            if result.error.is_some() {
-                Ok(Some(UpdateStatus::Failed { content: result }))
-            } else {
-                Ok(Some(UpdateStatus::Processed { content: result }))
-            }
+        Some(result) => match result.interruption {
+            Some(Interruption::Canceled) => Ok(Some(UpdateStatus::Canceled { content: result })),
+            Some(Interruption::Aborted) => Ok(Some(UpdateStatus::Aborted { content: result })),
+            None if result.error.is_some() => Ok(Some(UpdateStatus::Failed { content: result })),
+            None => Ok(Some(UpdateStatus::Processed { content: result })),
         },
         None => match updates_store.get(update_reader, update_id)? {
             Some(update) => Ok(Some(UpdateStatus::Enqueued {
@@ -179,6 +445,7 @@ pub fn update_status(
                     update_id,
                     update_type: update.data.update_type(),
                     enqueued_at: update.enqueued_at,
+                    progress: None,
                 },
             })),
             None => Ok(None),
@@ -211,7 +478,10 @@ pub fn update_task<'a, 'b>(
 ) -> MResult<ProcessedUpdateResult> {
     debug!("Processing update number {}", update_id);
 
-    let Update { enqueued_at, data } = update;
+    let Update { enqueued_at, data, addition_options } = update;
+    let mut settings_diff = None;
+    let mut reindex_truncation = None;
+    let mut addition_report = AdditionReport::default();
 
     let (update_type, result, duration) = match data {
         UpdateData::ClearAll => {
@@ -222,11 +492,13 @@ pub fn update_task<'a, 'b>(
 
             (update_type, result, start.elapsed())
         }
-        UpdateData::Customs(customs) => {
+        UpdateData::Metadata(update) => {
             let start = Instant::now();
 
-            let update_type = UpdateType::Customs;
-            let result = apply_customs_update(writer, index.main, &customs).map_err(Into::into);
+            let update_type = UpdateType::Metadata {
+                namespace: update.namespace.clone(),
+            };
+            let result = apply_metadata_update(writer, index.main, &update).map_err(Into::into);
 
             (update_type, result, start.elapsed())
         }
@@ -237,7 +509,8 @@ pub fn update_task<'a, 'b>(
                 number: documents.len(),
             };
 
-            let result = apply_documents_addition(writer, index, documents);
+            let result = apply_documents_addition(writer, index, documents, addition_options)
+                .map(|report| addition_report = report);
 
             (update_type, result, start.elapsed())
         }
@@ -248,7 +521,8 @@ pub fn update_task<'a, 'b>(
                 number: documents.len(),
             };
 
-            let result = apply_documents_partial_addition(writer, index, documents);
+            let result = apply_documents_partial_addition(writer, index, documents, addition_options)
+                .map(|report| addition_report = report);
 
             (update_type, result, start.elapsed())
         }
@@ -259,7 +533,15 @@ pub fn update_task<'a, 'b>(
                 number: documents.len(),
             };
 
-            let result = apply_documents_deletion(writer, index, documents);
+            let result = apply_documents_soft_deletion(writer, index, documents);
+
+            (update_type, result, start.elapsed())
+        }
+        UpdateData::DocumentsPurge => {
+            let start = Instant::now();
+
+            let update_type = UpdateType::DocumentsPurge;
+            let result = apply_documents_purge(writer, index);
 
             (update_type, result, start.elapsed())
         }
@@ -274,7 +556,48 @@ pub fn update_task<'a, 'b>(
                 writer,
                 index,
                 settings,
-            );
+            ).map(|(diff, truncation)| {
+                settings_diff = Some(diff);
+                reindex_truncation = truncation;
+            });
+
+            (update_type, result, start.elapsed())
+        }
+        UpdateData::Repair => {
+            let start = Instant::now();
+
+            let update_type = UpdateType::Repair;
+            let result = apply_repair(writer, index)
+                .map(|truncation| reindex_truncation = truncation);
+
+            (update_type, result, start.elapsed())
+        }
+        UpdateData::Batch(updates) => {
+            let start = Instant::now();
+
+            let mut update_types = Vec::with_capacity(updates.len());
+            let mut result = Ok(());
+
+            // every update of the batch is applied with the same transaction,
+            // the first one to fail makes the whole batch fail
+            for (position, update) in updates.into_iter().enumerate() {
+                let status = update_task(writer, index, update_id, update)?;
+                update_types.push(status.update_type);
+
+                settings_diff = status.settings_diff.or(settings_diff);
+                reindex_truncation = status.truncation.or(reindex_truncation);
+                addition_report.duplicates = status.duplicates.or(addition_report.duplicates);
+                addition_report.generated_ids.extend(status.generated_ids.unwrap_or_default());
+                addition_report.primary_key = status.inferred_primary_key.or(addition_report.primary_key);
+                addition_report.failed_documents.extend(status.failed_documents.unwrap_or_default());
+
+                if let Some(error) = status.error {
+                    result = Err(Error::BatchUpdate { position, error });
+                    break;
+                }
+            }
+
+            let update_type = UpdateType::Batch { updates: update_types };
 
             (update_type, result, start.elapsed())
         }
@@ -285,6 +608,12 @@ pub fn update_task<'a, 'b>(
         update_id, update_type, result
     );
 
+    // the update is not failed when the map is full, it is applied again once the map has grown
+    let result = match result {
+        Err(e) if e.is_map_full() => return Err(e),
+        result => result,
+    };
+
     let status = ProcessedUpdateResult {
         update_id,
         update_type,
@@ -292,26 +621,74 @@ pub fn update_task<'a, 'b>(
         duration: duration.as_secs_f64(),
         enqueued_at,
         processed_at: Utc::now(),
+        settings_diff,
+        duplicates: addition_report.duplicates,
+        generated_ids: if addition_report.generated_ids.is_empty() {
+            None
+        } else {
+            Some(addition_report.generated_ids)
+        },
+        inferred_primary_key: addition_report.primary_key,
+        failed_documents: if addition_report.failed_documents.is_empty() {
+            None
+        } else {
+            Some(addition_report.failed_documents)
+        },
+        truncation: addition_report.truncation.or(reindex_truncation),
+        interruption: None,
     };
 
     Ok(status)
 }
 
+/// Merges the words delta into the words set, the whole words set is rewritten.
+fn compact_words_fst(writer: &mut heed::RwTxn<MainT>, index: &store::Index) -> MResult<()> {
+    let words_fst = match index.main.words_delta_fst(writer)? {
+        Some(delta) => match index.main.words_fst(writer)? {
+            Some(words) => {
+                let op = OpBuilder::new()
+                    .add(words.stream())
+                    .add(delta.stream())
+                    .r#union();
+
+                let mut words_builder = SetBuilder::memory();
+                words_builder.extend_stream(op)?;
+                words_builder.into_inner().and_then(fst::Set::from_bytes)?
+            }
+            None => delta.into_owned(),
+        },
+        None => return Ok(()),
+    };
+
+    index.main.put_words_fst(writer, &words_fst)?;
+    index.main.delete_words_delta_fst(writer)?;
+
+    Ok(())
+}
+
 fn compute_short_prefixes(writer: &mut heed::RwTxn<MainT>, index: &store::Index) -> MResult<()> {
-    // retrieve the words fst to compute all those prefixes
+    // retrieve the words fst to compute all those prefixes, they are
+    // copied as the prefixes are written while they are read
     let words_fst = match index.main.words_fst(writer)? {
-        Some(fst) => fst,
+        Some(fst) => fst.into_owned(),
         None => return Ok(()),
     };
+    let words_delta = match index.main.words_delta_fst(writer)? {
+        Some(fst) => fst.into_owned(),
+        None => fst::Set::default(),
+    };
 
     // clear the prefixes
     let pplc_store = index.prefix_postings_lists_cache;
     pplc_store.clear(writer)?;
 
-    for prefix_len in 1..=2 {
+    let prefix_search = index.main.prefix_search(writer)?.unwrap_or_default();
+    let min_frequency = prefix_search.min_prefix_frequency;
+
+    for prefix_len in 1..=prefix_search.cached_prefix_length() {
         // compute prefixes and store those in the PrefixPostingsListsCache store.
         let mut previous_prefix: Option<([u8; 4], Vec<_>)> = None;
-        let mut stream = words_fst.into_stream();
+        let mut stream = OpBuilder::new().add(&words_fst).add(&words_delta).r#union();
         while let Some(input) = stream.next() {
 
             // We skip the prefixes that are shorter than the current length
@@ -331,12 +708,13 @@ fn compute_short_prefixes(writer: &mut heed::RwTxn<MainT>, index: &store::Index)
                         prev_pl.sort_unstable();
                         prev_pl.dedup();
 
-                        if let Ok(prefix) = std::str::from_utf8(&prev_prefix[..prefix_len]) {
-                            debug!("writing the prefix of {:?} of length {}", prefix, prev_pl.len());
-                        }
-
                         let pls = Set::new_unchecked(&prev_pl);
-                        pplc_store.put_prefix_postings_list(writer, *prev_prefix, &pls)?;
+                        if is_frequent_prefix(pls, min_frequency) {
+                            if let Ok(prefix) = std::str::from_utf8(&prev_prefix[..prefix_len]) {
+                                debug!("writing the prefix of {:?} of length {}", prefix, prev_pl.len());
+                            }
+                            pplc_store.put_prefix_postings_list(writer, *prev_prefix, &pls)?;
+                        }
 
                         *prev_prefix = arr_prefix;
                         prev_pl.clear();
@@ -354,9 +732,17 @@ fn compute_short_prefixes(writer: &mut heed::RwTxn<MainT>, index: &store::Index)
             prev_pl.dedup();
 
             let pls = Set::new_unchecked(&prev_pl);
-            pplc_store.put_prefix_postings_list(writer, prev_prefix, &pls)?;
+            if is_frequent_prefix(pls, min_frequency) {
+                pplc_store.put_prefix_postings_list(writer, prev_prefix, &pls)?;
+            }
         }
     }
 
     Ok(())
 }
+
+/// Returns `true` if the words starting with a prefix appear in enough documents
+/// for its postings list to be precomputed, the others are searched at query time.
+fn is_frequent_prefix(matches: &Set<DocIndex>, min_frequency: usize) -> bool {
+    min_frequency == 0 || matches.linear_group_by_key(|m| m.document_id).count() >= min_frequency
+}
@@ -0,0 +1,94 @@
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+/// The number of documents processed between two progress reports.
+pub const PROGRESS_INTERVAL: usize = 1000;
+
+/// The stage an indexing update is at.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UpdatePhase {
+    /// The documents are read and their ids extracted.
+    Extraction,
+    /// The documents fields are stored and their words tokenized.
+    Indexing,
+    /// The words of the documents are merged into the postings lists and the words fst.
+    FstMerge,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateProgress {
+    pub update_id: u64,
+    pub phase: UpdatePhase,
+    pub processed_documents: usize,
+    pub total_documents: usize,
+}
+
+/// Where the update loop of an index publishes the progress of the update it processes.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateProgressEmitter {
+    progress: Arc<RwLock<Option<UpdateProgress>>>,
+    update_id: Arc<RwLock<Option<u64>>>,
+}
+
+impl UpdateProgressEmitter {
+    pub(crate) fn start(&self, update_id: u64) {
+        *self.update_id.write().unwrap() = Some(update_id);
+        *self.progress.write().unwrap() = None;
+    }
+
+    pub(crate) fn finish(&self) {
+        *self.update_id.write().unwrap() = None;
+        *self.progress.write().unwrap() = None;
+    }
+
+    /// Reports the progress of the update being processed, ignored when called outside of the update loop.
+    pub(crate) fn emit(&self, phase: UpdatePhase, processed_documents: usize, total_documents: usize) {
+        if let Some(update_id) = *self.update_id.read().unwrap() {
+            let progress = UpdateProgress { update_id, phase, processed_documents, total_documents };
+            *self.progress.write().unwrap() = Some(progress);
+        }
+    }
+
+    /// Reports the progress every `PROGRESS_INTERVAL` documents and once all of them are processed.
+    pub(crate) fn emit_periodically(&self, phase: UpdatePhase, processed_documents: usize, total_documents: usize) {
+        if processed_documents % PROGRESS_INTERVAL == 0 || processed_documents == total_documents {
+            self.emit(phase, processed_documents, total_documents);
+        }
+    }
+
+    /// The last progress reported by the update being processed.
+    pub fn progress(&self) -> Option<UpdateProgress> {
+        self.progress.read().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_is_only_reported_while_processing() {
+        let emitter = UpdateProgressEmitter::default();
+        emitter.emit(UpdatePhase::Indexing, 10, 20);
+        assert_eq!(emitter.progress(), None);
+
+        emitter.start(42);
+        emitter.emit_periodically(UpdatePhase::Indexing, 10, 2500);
+        assert_eq!(emitter.progress(), None);
+
+        emitter.emit_periodically(UpdatePhase::Indexing, 1000, 2500);
+        let progress = emitter.progress().unwrap();
+        assert_eq!(progress.update_id, 42);
+        assert_eq!(progress.phase, UpdatePhase::Indexing);
+        assert_eq!(progress.processed_documents, 1000);
+
+        emitter.emit_periodically(UpdatePhase::Indexing, 2500, 2500);
+        assert_eq!(emitter.progress().unwrap().processed_documents, 2500);
+
+        emitter.finish();
+        assert_eq!(emitter.progress(), None);
+    }
+}
@@ -0,0 +1,40 @@
+use crate::database::{MainT, UpdateT};
+use crate::update::documents_addition::{reindex_documents, TruncationReport};
+use crate::update::{next_update_id, Update};
+use crate::{store, MResult};
+
+/// Rebuilds every store derived from the documents fields (postings lists, docs words,
+/// words fst, facets, coordinates, expirations, sortable values, numbers, fields presences, ranked map
+/// and fields counts),
+/// the documents fields are the only store trusted to be sound.
+pub fn apply_repair(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+) -> MResult<Option<TruncationReport>> {
+    let mut documents_ids = Vec::new();
+    for result in index.documents_fields.documents_ids(writer)? {
+        let document_id = result?;
+        documents_ids.push(document_id);
+    }
+
+    index.documents_fields_counts.clear(writer)?;
+    index.geo.clear(writer)?;
+    index.expirations.clear(writer)?;
+    index.sortables.clear(writer)?;
+    index.prefix_documents_cache.clear(writer)?;
+    index.prefix_postings_lists_cache.clear(writer)?;
+
+    reindex_documents(writer, index, documents_ids)
+}
+
+pub fn push_repair(
+    writer: &mut heed::RwTxn<UpdateT>,
+    updates_store: store::Updates,
+    updates_results_store: store::UpdatesResults,
+) -> MResult<u64> {
+    let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
+    let update = Update::repair();
+    updates_store.push_update(writer, last_update_id, &update)?;
+
+    Ok(last_update_id)
+}
@@ -1,14 +1,17 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
+use chrono::Utc;
 use heed::Result as ZResult;
 use fst::{set::OpBuilder, SetBuilder};
 use sdset::SetBuf;
-use meilisearch_schema::Schema;
+use meilisearch_schema::{FieldFlags, FieldId, FieldType, Schema};
+use serde::{Deserialize, Serialize};
 
 use crate::database::{MainT, UpdateT};
-use crate::settings::{UpdateState, SettingsUpdate, RankingRule};
-use crate::update::documents_addition::reindex_all_documents;
-use crate::update::{next_update_id, Update};
+use crate::settings::{UpdateState, SettingsUpdate, RankingRule, DocumentIdFormat, FacetNormalization, LocalizedSynonyms, TypoTolerance};
+use crate::settings::{DEFAULT_COMPRESS_DOCUMENTS, DEFAULT_FOLD_DIACRITICS, DEFAULT_MAX_POSITIONS_PER_ATTRIBUTE, DEFAULT_MAX_TOKEN_LENGTH, DEFAULT_STOP_WORDS_IN_PHRASES};
+use crate::update::documents_addition::{reindex_all_documents, reindex_facets, reindex_sortables, rewrite_documents_fields, TruncationReport};
+use crate::update::{compact_words_fst, compute_short_prefixes, next_update_id, ProcessedUpdateResult, Update, UpdateType};
 use crate::{store, MResult, Error};
 
 pub fn push_settings_update(
@@ -16,21 +19,494 @@ pub fn push_settings_update(
     updates_store: store::Updates,
     updates_results_store: store::UpdatesResults,
     settings: SettingsUpdate,
-) -> ZResult<u64> {
+) -> MResult<u64> {
     let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
 
     let update = Update::settings(settings);
-    updates_store.put_update(writer, last_update_id, &update)?;
+    updates_store.push_update(writer, last_update_id, &update)?;
+
+    Ok(last_update_id)
+}
+
+/// What a processed settings update changed in the index.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsDiff {
+    pub ranking_rules_changed: bool,
+    pub distinct_attribute_changed: bool,
+    pub searchable_attributes_changed: bool,
+    pub displayed_attributes_changed: bool,
+    pub faceting_added: Vec<String>,
+    pub faceting_removed: Vec<String>,
+    pub sortable_attributes_changed: bool,
+    pub stop_words_added: Vec<String>,
+    pub stop_words_removed: Vec<String>,
+    pub synonyms_changed: bool,
+    pub document_id_format_changed: bool,
+    pub language_changed: bool,
+    pub indexing_limits_changed: bool,
+    pub typo_tolerance_changed: bool,
+    pub prefix_search_changed: bool,
+    pub field_types_changed: bool,
+    pub facet_normalization_changed: bool,
+    pub fold_diacritics_changed: bool,
+    pub token_policy_changed: bool,
+    pub stop_words_in_phrases_changed: bool,
+    pub compress_documents_changed: bool,
+    /// The attributes used by the ranking rules, whose values are kept in the ranked map.
+    pub ranked_attributes_changed: bool,
+    pub reindexed: bool,
+    pub facets_reindexed: bool,
+    pub sortables_reindexed: bool,
+    pub documents_fields_rewritten: bool,
+}
+
+/// The parts of the index that must be rebuilt after a settings update.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReindexPlan {
+    /// All the documents are reindexed, their facets and sortable values with them.
+    pub documents: bool,
+    /// Only the facets are rebuilt, from the stored documents fields.
+    pub facets: bool,
+    /// Only the sortable values are rebuilt, from the stored documents fields.
+    pub sortables: bool,
+    /// Only the stored documents fields are rewritten, compressed or not.
+    pub documents_fields: bool,
+}
+
+impl ReindexPlan {
+    /// Decides what must be rebuilt from what a settings update changed, the removed stop
+    /// words must only be indexed when the stop words are not already indexed.
+    pub fn from_diff(diff: &SettingsDiff, stop_words_indexed: bool) -> ReindexPlan {
+        let documents = diff.ranked_attributes_changed
+            || diff.field_types_changed
+            || diff.searchable_attributes_changed
+            || diff.fold_diacritics_changed
+            || diff.token_policy_changed
+            || diff.indexing_limits_changed
+            || diff.stop_words_in_phrases_changed
+            || (!diff.stop_words_removed.is_empty() && !stop_words_indexed);
+
+        if documents {
+            return ReindexPlan { documents, facets: false, sortables: false, documents_fields: false };
+        }
+
+        ReindexPlan {
+            documents,
+            facets: !diff.faceting_added.is_empty()
+                || !diff.faceting_removed.is_empty()
+                || diff.facet_normalization_changed,
+            sortables: diff.sortable_attributes_changed,
+            documents_fields: diff.compress_documents_changed,
+        }
+    }
+}
+
+/// Registers a settings update that would not change anything as an already processed
+/// update, without enqueueing it and therefore without triggering any reindexing.
+pub fn push_noop_settings_update(
+    writer: &mut heed::RwTxn<UpdateT>,
+    updates_store: store::Updates,
+    updates_results_store: store::UpdatesResults,
+    settings: SettingsUpdate,
+) -> ZResult<u64> {
+    let last_update_id = next_update_id(writer, updates_store, updates_results_store)?;
+
+    let now = Utc::now();
+    let result = ProcessedUpdateResult {
+        update_id: last_update_id,
+        update_type: UpdateType::Settings { settings },
+        error: None,
+        duration: 0.0,
+        enqueued_at: now,
+        processed_at: now,
+        settings_diff: Some(SettingsDiff::default()),
+        duplicates: None,
+        generated_ids: None,
+        inferred_primary_key: None,
+        failed_documents: None,
+        truncation: None,
+        interruption: None,
+    };
+    updates_results_store.put_update_result(writer, last_update_id, &result)?;
 
     Ok(last_update_id)
 }
 
+fn current_stop_words(reader: &heed::RoTxn<MainT>, index: &store::Index) -> MResult<BTreeSet<String>> {
+    let stop_words = index.main
+        .stop_words_fst(reader)?
+        .unwrap_or_default()
+        .stream()
+        .into_strs()?;
+    Ok(stop_words.into_iter().collect())
+}
+
+fn current_synonyms(reader: &heed::RoTxn<MainT>, index: &store::Index) -> MResult<BTreeMap<String, Vec<String>>> {
+    let words = index.main.synonyms_fst(reader)?.unwrap_or_default().stream().into_strs()?;
+    let mut synonyms = BTreeMap::new();
+    for word in words {
+        if let Some(alternatives) = index.synonyms.synonyms(reader, word.as_bytes())? {
+            synonyms.insert(word, alternatives.stream().into_strs()?);
+        }
+    }
+    Ok(synonyms)
+}
+
+fn current_localized_synonyms(reader: &heed::RoTxn<MainT>, index: &store::Index) -> MResult<LocalizedSynonyms> {
+    Ok(index.main.localized_synonyms(reader)?.unwrap_or_default())
+}
+
+/// Returns the synonyms as they are stored, with sorted and deduplicated alternatives.
+fn normalize_synonyms(synonyms: &BTreeMap<String, Vec<String>>) -> BTreeMap<String, Vec<String>> {
+    synonyms
+        .iter()
+        .map(|(word, alternatives)| {
+            let alternatives = SetBuf::from_dirty(alternatives.clone()).into_vec();
+            (word.clone(), alternatives)
+        })
+        .collect()
+}
+
+fn current_attributes_for_faceting(
+    reader: &heed::RoTxn<MainT>,
+    index: &store::Index,
+    schema: &Schema,
+) -> MResult<BTreeSet<String>> {
+    let attributes = index.main
+        .attributes_for_faceting(reader)?
+        .map(|ids| ids.iter().filter_map(|id| schema.name(*id)).map(String::from).collect())
+        .unwrap_or_default();
+    Ok(attributes)
+}
+
+fn current_field_types(schema: &Schema) -> BTreeMap<String, FieldType> {
+    schema
+        .field_types_name()
+        .into_iter()
+        .map(|(name, field_type)| (name.to_string(), field_type))
+        .collect()
+}
+
+fn current_field_flags(schema: &Schema) -> BTreeMap<String, FieldFlags> {
+    schema
+        .field_flags_name()
+        .into_iter()
+        .map(|(name, flags)| (name.to_string(), flags))
+        .collect()
+}
+
+fn current_sortable_attributes(
+    reader: &heed::RoTxn<MainT>,
+    index: &store::Index,
+    schema: &Schema,
+) -> MResult<BTreeSet<String>> {
+    let attributes = index.main
+        .sortable_attributes(reader)?
+        .map(|ids| ids.iter().filter_map(|id| schema.name(*id)).map(String::from).collect())
+        .unwrap_or_default();
+    Ok(attributes)
+}
+
+/// Returns `true` if applying these settings would leave the index settings unchanged.
+///
+/// Clearing a setting is never considered a no-op, the default values are not
+/// compared to the current ones.
+pub fn settings_update_is_noop(
+    reader: &heed::RoTxn<MainT>,
+    index: &store::Index,
+    settings: &SettingsUpdate,
+) -> MResult<bool> {
+    let schema = match index.main.schema(reader)? {
+        Some(schema) => schema,
+        None => return Ok(false),
+    };
+
+    match &settings.ranking_rules {
+        UpdateState::Update(rules) => {
+            if index.main.ranking_rules(reader)?.as_ref() != Some(rules) {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.distinct_attribute {
+        UpdateState::Update(attribute) => {
+            if index.main.distinct_attribute(reader)?.as_ref() != Some(attribute) {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.primary_key {
+        UpdateState::Update(primary_key) => {
+            if schema.primary_key() != Some(primary_key.as_str()) {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.searchable_attributes {
+        UpdateState::Update(attributes) => {
+            if schema.indexed_name() != attributes.iter().map(String::as_str).collect::<Vec<_>>() {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.displayed_attributes {
+        UpdateState::Update(attributes) => {
+            if schema.displayed_name() != attributes.iter().map(String::as_str).collect::<HashSet<_>>() {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.stop_words {
+        UpdateState::Update(stop_words) => {
+            if &current_stop_words(reader, index)? != stop_words {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.synonyms {
+        UpdateState::Update(synonyms) => {
+            if current_synonyms(reader, index)? != normalize_synonyms(synonyms) {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.localized_synonyms {
+        UpdateState::Update(localized) => {
+            let current = current_localized_synonyms(reader, index)?;
+            for (language, synonyms) in localized {
+                let current = current.get(language).cloned().unwrap_or_default();
+                if current != normalize_synonyms(synonyms) {
+                    return Ok(false);
+                }
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.language {
+        UpdateState::Update(language) => {
+            if index.main.language(reader)?.as_ref() != Some(language) {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.accept_new_fields {
+        UpdateState::Update(accept) => {
+            if schema.accept_new_fields() != *accept {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.attributes_for_faceting {
+        UpdateState::Update(attributes) => {
+            let attributes: BTreeSet<String> = attributes.iter().cloned().collect();
+            if current_attributes_for_faceting(reader, index, &schema)? != attributes {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.sortable_attributes {
+        UpdateState::Update(attributes) => {
+            let attributes: BTreeSet<String> = attributes.iter().cloned().collect();
+            if current_sortable_attributes(reader, index, &schema)? != attributes {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.field_types {
+        UpdateState::Update(field_types) => {
+            if current_field_types(&schema) != *field_types {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.field_flags {
+        UpdateState::Update(field_flags) => {
+            if current_field_flags(&schema) != *field_flags {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.document_id_format {
+        UpdateState::Update(format) => {
+            if index.main.document_id_format(reader)?.unwrap_or_default() != *format {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.fold_diacritics {
+        UpdateState::Update(fold) => {
+            if index.main.fold_diacritics(reader)?.unwrap_or(DEFAULT_FOLD_DIACRITICS) != *fold {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.token_policy {
+        UpdateState::Update(token_policy) => {
+            if index.main.token_policy(reader)?.unwrap_or_default() != *token_policy {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.stop_words_in_phrases {
+        UpdateState::Update(keep) => {
+            if index.main.stop_words_in_phrases(reader)?.unwrap_or(DEFAULT_STOP_WORDS_IN_PHRASES) != *keep {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.compress_documents {
+        UpdateState::Update(compress) => {
+            if index.main.compress_documents(reader)?.unwrap_or(DEFAULT_COMPRESS_DOCUMENTS) != *compress {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.max_token_length {
+        UpdateState::Update(length) => {
+            if index.main.max_token_length(reader)? != Some(*length) {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.max_positions_per_attribute {
+        UpdateState::Update(positions) => {
+            if index.main.max_positions_per_attribute(reader)? != Some(*positions) {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.max_total_hits {
+        UpdateState::Update(hits) => {
+            if index.main.max_total_hits(reader)? != Some(*hits) {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.search_cutoff_ms {
+        UpdateState::Update(cutoff) => {
+            if index.main.search_cutoff_ms(reader)? != Some(*cutoff) {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.query_rules {
+        UpdateState::Update(rules) => {
+            if index.main.query_rules(reader)?.as_ref() != Some(rules) {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.typo_tolerance {
+        UpdateState::Update(typo_tolerance) => {
+            if index.main.typo_tolerance(reader)?.as_ref() != Some(typo_tolerance) {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.facet_normalization {
+        UpdateState::Update(facet_normalization) => {
+            if index.main.facet_normalization(reader)?.as_ref() != Some(facet_normalization) {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    match &settings.prefix_search {
+        UpdateState::Update(prefix_search) => {
+            if index.main.prefix_search(reader)?.as_ref() != Some(prefix_search) {
+                return Ok(false);
+            }
+        },
+        UpdateState::Clear => return Ok(false),
+        UpdateState::Nothing => (),
+    }
+
+    Ok(true)
+}
+
 pub fn apply_settings_update(
     writer: &mut heed::RwTxn<MainT>,
     index: &store::Index,
     settings: SettingsUpdate,
-) -> MResult<()> {
-    let mut must_reindex = false;
+) -> MResult<(SettingsDiff, Option<TruncationReport>)> {
+    let mut diff = SettingsDiff::default();
 
     let mut schema = match index.main.schema(writer)? {
         Some(schema) => schema,
@@ -42,58 +518,214 @@ pub fn apply_settings_update(
         }
     };
 
+    let old_searchable: Vec<String> = schema.indexed_name().into_iter().map(String::from).collect();
+    let old_displayed: HashSet<String> = schema.displayed_name().into_iter().map(String::from).collect();
+    let old_faceting = current_attributes_for_faceting(writer, index, &schema)?;
+    let old_sortable = current_sortable_attributes(writer, index, &schema)?;
+    let old_ranked = schema.ranked().clone();
+    let old_field_types = current_field_types(&schema);
+
     match settings.ranking_rules {
         UpdateState::Update(v) => {
             let ranked_field: Vec<&str> = v.iter().filter_map(RankingRule::field).collect();
             schema.update_ranked(&ranked_field)?;
-            for name in ranked_field {
+            // the nested fields are stored in their top-level field
+            for name in ranked_field.into_iter().filter(|name| !name.contains('.')) {
                 if schema.accept_new_fields() {
                     schema.set_indexed(name.as_ref())?;
                     schema.set_displayed(name.as_ref())?;
                 }
             }
+            diff.ranking_rules_changed = index.main.ranking_rules(writer)?.as_ref() != Some(&v);
             index.main.put_ranking_rules(writer, &v)?;
-            must_reindex = true;
         },
         UpdateState::Clear => {
+            diff.ranking_rules_changed = index.main.ranking_rules(writer)?.is_some();
             index.main.delete_ranking_rules(writer)?;
             schema.clear_ranked();
-            must_reindex = true;
         },
         UpdateState::Nothing => (),
     }
 
     match settings.distinct_attribute {
         UpdateState::Update(v) => {
+            diff.distinct_attribute_changed = index.main.distinct_attribute(writer)?.as_ref() != Some(&v);
             index.main.put_distinct_attribute(writer, &v)?;
         },
         UpdateState::Clear => {
+            diff.distinct_attribute_changed = index.main.distinct_attribute(writer)?.is_some();
             index.main.delete_distinct_attribute(writer)?;
         },
         UpdateState::Nothing => (),
     }
 
-    match settings.accept_new_fields {
+    // the ids are hashed whatever their format, the stored documents are not affected
+    let old_document_id_format = index.main.document_id_format(writer)?.unwrap_or_default();
+    match settings.document_id_format {
+        UpdateState::Update(format) => {
+            diff.document_id_format_changed = old_document_id_format != format;
+            index.main.put_document_id_format(writer, format)?;
+        },
+        UpdateState::Clear => {
+            diff.document_id_format_changed = old_document_id_format != DocumentIdFormat::default();
+            index.main.delete_document_id_format(writer)?;
+        },
+        UpdateState::Nothing => (),
+    }
+
+    match settings.language {
         UpdateState::Update(v) => {
-            schema.set_accept_new_fields(v);
+            diff.language_changed = index.main.language(writer)?.as_ref() != Some(&v);
+            index.main.put_language(writer, &v)?;
         },
         UpdateState::Clear => {
-            schema.set_accept_new_fields(true);
+            diff.language_changed = index.main.language(writer)?.is_some();
+            index.main.delete_language(writer)?;
         },
         UpdateState::Nothing => (),
     }
 
-    match settings.searchable_attributes.clone() {
+    // the typo tolerance is only used at search time, nothing needs to be reindexed
+    let old_typo_tolerance = index.main.typo_tolerance(writer)?.unwrap_or_default();
+    match settings.typo_tolerance {
+        UpdateState::Update(typo_tolerance) => {
+            diff.typo_tolerance_changed = old_typo_tolerance != typo_tolerance;
+            index.main.put_typo_tolerance(writer, &typo_tolerance)?;
+        },
+        UpdateState::Clear => {
+            diff.typo_tolerance_changed = old_typo_tolerance != TypoTolerance::default();
+            index.main.delete_typo_tolerance(writer)?;
+        },
+        UpdateState::Nothing => (),
+    }
+
+    // the facet values are normalized when indexed, the facets are rebuilt below
+    let old_facet_normalization = index.main.facet_normalization(writer)?.unwrap_or_default();
+    match settings.facet_normalization {
+        UpdateState::Update(facet_normalization) => {
+            diff.facet_normalization_changed = old_facet_normalization != facet_normalization;
+            index.main.put_facet_normalization(writer, &facet_normalization)?;
+        },
+        UpdateState::Clear => {
+            diff.facet_normalization_changed = old_facet_normalization != FacetNormalization::default();
+            index.main.delete_facet_normalization(writer)?;
+        },
+        UpdateState::Nothing => (),
+    }
+
+    // only the cached prefixes depend on the prefix search, they are recomputed below
+    let old_prefix_search = index.main.prefix_search(writer)?.unwrap_or_default();
+    match settings.prefix_search {
+        UpdateState::Update(prefix_search) => index.main.put_prefix_search(writer, &prefix_search)?,
+        UpdateState::Clear => { index.main.delete_prefix_search(writer)?; },
+        UpdateState::Nothing => (),
+    }
+
+    let new_prefix_search = index.main.prefix_search(writer)?.unwrap_or_default();
+    diff.prefix_search_changed = old_prefix_search != new_prefix_search;
+    let must_recompute_prefixes = old_prefix_search.cached_prefix_length() != new_prefix_search.cached_prefix_length()
+        || old_prefix_search.min_prefix_frequency != new_prefix_search.min_prefix_frequency;
+
+    // the maximum number of hits only bounds the search requests
+    match settings.max_total_hits {
+        UpdateState::Update(v) => index.main.put_max_total_hits(writer, v)?,
+        UpdateState::Clear => { index.main.delete_max_total_hits(writer)?; },
+        UpdateState::Nothing => (),
+    }
+
+    // the time budget only bounds the search requests
+    match settings.search_cutoff_ms {
+        UpdateState::Update(v) => index.main.put_search_cutoff_ms(writer, v)?,
+        UpdateState::Clear => { index.main.delete_search_cutoff_ms(writer)?; },
+        UpdateState::Nothing => (),
+    }
+
+    // the query rules are applied by the search requests
+    match settings.query_rules {
+        UpdateState::Update(rules) => index.main.put_query_rules(writer, &rules)?,
+        UpdateState::Clear => { index.main.delete_query_rules(writer)?; },
+        UpdateState::Nothing => (),
+    }
+
+    // the folded words are stored along the original ones, all the documents must be reindexed
+    let old_fold_diacritics = index.main.fold_diacritics(writer)?.unwrap_or(DEFAULT_FOLD_DIACRITICS);
+    match settings.fold_diacritics {
+        UpdateState::Update(v) => index.main.put_fold_diacritics(writer, v)?,
+        UpdateState::Clear => { index.main.delete_fold_diacritics(writer)?; },
+        UpdateState::Nothing => (),
+    }
+
+    let new_fold_diacritics = index.main.fold_diacritics(writer)?.unwrap_or(DEFAULT_FOLD_DIACRITICS);
+    diff.fold_diacritics_changed = old_fold_diacritics != new_fold_diacritics;
+
+    // the compounds are tokenized when indexed, all the documents must be reindexed
+    let old_token_policy = index.main.token_policy(writer)?.unwrap_or_default();
+    match settings.token_policy {
+        UpdateState::Update(token_policy) => index.main.put_token_policy(writer, &token_policy)?,
+        UpdateState::Clear => { index.main.delete_token_policy(writer)?; },
+        UpdateState::Nothing => (),
+    }
+
+    let new_token_policy = index.main.token_policy(writer)?.unwrap_or_default();
+    diff.token_policy_changed = old_token_policy != new_token_policy;
+
+    // the stop words are indexed or not depending on this setting, all the documents must be reindexed
+    let old_stop_words_in_phrases = index.main.stop_words_in_phrases(writer)?.unwrap_or(DEFAULT_STOP_WORDS_IN_PHRASES);
+    match settings.stop_words_in_phrases {
+        UpdateState::Update(v) => index.main.put_stop_words_in_phrases(writer, v)?,
+        UpdateState::Clear => { index.main.delete_stop_words_in_phrases(writer)?; },
+        UpdateState::Nothing => (),
+    }
+
+    let new_stop_words_in_phrases = index.main.stop_words_in_phrases(writer)?.unwrap_or(DEFAULT_STOP_WORDS_IN_PHRASES);
+    diff.stop_words_in_phrases_changed = old_stop_words_in_phrases != new_stop_words_in_phrases;
+
+    // the documents fields are rewritten with the new compression, nothing is reindexed
+    let old_compress_documents = index.main.compress_documents(writer)?.unwrap_or(DEFAULT_COMPRESS_DOCUMENTS);
+    match settings.compress_documents {
+        UpdateState::Update(v) => index.main.put_compress_documents(writer, v)?,
+        UpdateState::Clear => { index.main.delete_compress_documents(writer)?; },
+        UpdateState::Nothing => (),
+    }
+
+    let new_compress_documents = index.main.compress_documents(writer)?.unwrap_or(DEFAULT_COMPRESS_DOCUMENTS);
+    diff.compress_documents_changed = old_compress_documents != new_compress_documents;
+
+    // the limits only apply to the documents indexed from now, all of them must be reindexed
+    let old_max_token_length = index.main.max_token_length(writer)?.unwrap_or(DEFAULT_MAX_TOKEN_LENGTH);
+    match settings.max_token_length {
+        UpdateState::Update(v) => index.main.put_max_token_length(writer, v)?,
+        UpdateState::Clear => { index.main.delete_max_token_length(writer)?; },
+        UpdateState::Nothing => (),
+    }
+
+    let old_max_positions = index.main.max_positions_per_attribute(writer)?.unwrap_or(DEFAULT_MAX_POSITIONS_PER_ATTRIBUTE);
+    match settings.max_positions_per_attribute {
+        UpdateState::Update(v) => index.main.put_max_positions_per_attribute(writer, v)?,
+        UpdateState::Clear => { index.main.delete_max_positions_per_attribute(writer)?; },
+        UpdateState::Nothing => (),
+    }
+
+    let new_max_token_length = index.main.max_token_length(writer)?.unwrap_or(DEFAULT_MAX_TOKEN_LENGTH);
+    let new_max_positions = index.main.max_positions_per_attribute(writer)?.unwrap_or(DEFAULT_MAX_POSITIONS_PER_ATTRIBUTE);
+    diff.indexing_limits_changed = old_max_token_length != new_max_token_length || old_max_positions != new_max_positions;
+
+    match settings.accept_new_fields {
         UpdateState::Update(v) => {
-            schema.update_indexed(v)?;
-            must_reindex = true;
+            schema.set_accept_new_fields(v);
         },
         UpdateState::Clear => {
-            schema.set_all_fields_as_indexed();
-            must_reindex = true;
+            schema.set_accept_new_fields(true);
         },
         UpdateState::Nothing => (),
     }
+
+    // the documents are only reindexed when the searchable attributes or their order change
+    match settings.searchable_attributes.clone() {
+        UpdateState::Update(v) => schema.update_indexed(v)?,
+        UpdateState::Clear => schema.set_all_fields_as_indexed(),
+        UpdateState::Nothing => (),
+    }
     match settings.displayed_attributes.clone() {
         UpdateState::Update(v) => schema.update_displayed(v)?,
         UpdateState::Clear => {
@@ -103,30 +735,52 @@ pub fn apply_settings_update(
     }
 
     match settings.attributes_for_faceting {
-        UpdateState::Update(attrs) => {
-            apply_attributes_for_faceting_update(writer, index, &mut schema, &attrs)?;
-            must_reindex = true;
-        },
-        UpdateState::Clear => {
-            index.main.delete_attributes_for_faceting(writer)?;
-            index.facets.clear(writer)?;
-        },
+        UpdateState::Update(attrs) => apply_attributes_for_faceting_update(writer, index, &mut schema, &attrs)?,
+        UpdateState::Clear => { index.main.delete_attributes_for_faceting(writer)?; },
         UpdateState::Nothing => (),
     }
 
+    match settings.sortable_attributes {
+        UpdateState::Update(attrs) => apply_sortable_attributes_update(writer, index, &mut schema, &attrs)?,
+        UpdateState::Clear => { index.main.delete_sortable_attributes(writer)?; },
+        UpdateState::Nothing => (),
+    }
+
+    // the numbers and the ranked values of the typed fields are converted at indexing time
+    match settings.field_types {
+        UpdateState::Update(field_types) => schema.update_field_types(field_types)?,
+        UpdateState::Clear => schema.clear_field_types(),
+        UpdateState::Nothing => (),
+    }
+
+    // the declared flags win over the settings listing the fields, they are applied last
+    match settings.field_flags {
+        UpdateState::Update(field_flags) => schema.update_field_flags(field_flags)?,
+        UpdateState::Clear => schema.clear_field_flags(),
+        UpdateState::Nothing => schema.apply_field_flags(),
+    }
+    apply_filterable_flags(writer, index, &schema)?;
+
     index.main.put_schema(writer, &schema)?;
 
+    diff.field_types_changed = current_field_types(&schema) != old_field_types;
+    diff.searchable_attributes_changed = schema.indexed_name() != old_searchable.iter().map(String::as_str).collect::<Vec<_>>();
+    diff.displayed_attributes_changed = schema.displayed_name() != old_displayed.iter().map(String::as_str).collect::<HashSet<_>>();
+    diff.ranked_attributes_changed = *schema.ranked() != old_ranked;
+
+    let new_faceting = current_attributes_for_faceting(writer, index, &schema)?;
+    diff.faceting_added = new_faceting.difference(&old_faceting).cloned().collect();
+    diff.faceting_removed = old_faceting.difference(&new_faceting).cloned().collect();
+    diff.sortable_attributes_changed = current_sortable_attributes(writer, index, &schema)? != old_sortable;
+
+    let old_stop_words = current_stop_words(writer, index)?;
+    let old_synonyms = current_synonyms(writer, index)?;
+    let old_localized_synonyms = current_localized_synonyms(writer, index)?;
+
+    // the removed stop words must be indexed, the reindexing is decided with the other changes
     match settings.stop_words {
-        UpdateState::Update(stop_words) => {
-            if apply_stop_words_update(writer, index, stop_words)? {
-                must_reindex = true;
-            }
-        },
-        UpdateState::Clear => {
-            if apply_stop_words_update(writer, index, BTreeSet::new())? {
-                must_reindex = true;
-            }
-        },
+        UpdateState::Update(stop_words) => { apply_stop_words_update(writer, index, stop_words)?; },
+        UpdateState::Clear => { apply_stop_words_update(writer, index, BTreeSet::new())?; },
         UpdateState::Nothing => (),
     }
 
@@ -136,11 +790,52 @@ pub fn apply_settings_update(
         UpdateState::Nothing => (),
     }
 
-    if must_reindex {
-        reindex_all_documents(writer, index)?;
+    match settings.localized_synonyms {
+        UpdateState::Update(localized) => {
+            for (language, synonyms) in localized {
+                apply_localized_synonyms_update(writer, index, &language, synonyms)?;
+            }
+        },
+        UpdateState::Clear => {
+            for language in old_localized_synonyms.keys() {
+                apply_localized_synonyms_update(writer, index, language, BTreeMap::new())?;
+            }
+        },
+        UpdateState::Nothing => (),
     }
 
-    Ok(())
+    let new_stop_words = current_stop_words(writer, index)?;
+    diff.stop_words_added = new_stop_words.difference(&old_stop_words).cloned().collect();
+    diff.stop_words_removed = old_stop_words.difference(&new_stop_words).cloned().collect();
+    diff.synonyms_changed = current_synonyms(writer, index)? != old_synonyms
+        || current_localized_synonyms(writer, index)? != old_localized_synonyms;
+
+    let stop_words_indexed = index.main.stop_words_in_phrases(writer)?.unwrap_or(DEFAULT_STOP_WORDS_IN_PHRASES);
+    let plan = ReindexPlan::from_diff(&diff, stop_words_indexed);
+
+    let truncation = if plan.documents {
+        reindex_all_documents(writer, index)?
+    } else {
+        if plan.facets {
+            reindex_facets(writer, index)?;
+        }
+        if plan.sortables {
+            reindex_sortables(writer, index)?;
+        }
+        if plan.documents_fields {
+            rewrite_documents_fields(writer, index)?;
+        }
+        if must_recompute_prefixes {
+            compute_short_prefixes(writer, index)?;
+        }
+        None
+    };
+    diff.reindexed = plan.documents;
+    diff.facets_reindexed = plan.facets;
+    diff.sortables_reindexed = plan.sortables;
+    diff.documents_fields_rewritten = plan.documents_fields;
+
+    Ok((diff, truncation))
 }
 
 fn apply_attributes_for_faceting_update(
@@ -158,6 +853,54 @@ fn apply_attributes_for_faceting_update(
     Ok(())
 }
 
+/// Adds the fields declared filterable to the attributes for faceting and removes the ones
+/// declared not filterable.
+fn apply_filterable_flags(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+    schema: &Schema,
+) -> MResult<()> {
+    let flags: Vec<(FieldId, bool)> = schema
+        .names()
+        .filter_map(|name| schema.id(name))
+        .filter_map(|id| Some((id, schema.field_flags(id)?.filterable?)))
+        .collect();
+    if flags.is_empty() {
+        return Ok(());
+    }
+
+    let mut attribute_ids: BTreeSet<FieldId> = index.main
+        .attributes_for_faceting(writer)?
+        .map(|ids| ids.iter().copied().collect())
+        .unwrap_or_default();
+    for (id, filterable) in flags {
+        if filterable {
+            attribute_ids.insert(id);
+        } else {
+            attribute_ids.remove(&id);
+        }
+    }
+
+    let attributes_for_faceting = SetBuf::new_unchecked(attribute_ids.into_iter().collect());
+    index.main.put_attributes_for_faceting(writer, &attributes_for_faceting)?;
+    Ok(())
+}
+
+fn apply_sortable_attributes_update(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+    schema: &mut Schema,
+    attributes: &[String]
+    ) -> MResult<()> {
+    let mut attribute_ids = Vec::new();
+    for name in attributes {
+        attribute_ids.push(schema.insert(name)?);
+    }
+    let sortable_attributes = SetBuf::from_dirty(attribute_ids);
+    index.main.put_sortable_attributes(writer, &sortable_attributes)?;
+    Ok(())
+}
+
 pub fn apply_stop_words_update(
     writer: &mut heed::RwTxn<MainT>,
     index: &store::Index,
@@ -174,6 +917,16 @@ pub fn apply_stop_words_update(
         .into_iter()
         .collect();
 
+    // the stop words are already indexed when they are searched in the phrases,
+    // they are only ignored at query time and nothing must be reindexed
+    if index.main.stop_words_in_phrases(writer)?.unwrap_or(DEFAULT_STOP_WORDS_IN_PHRASES) {
+        if old_stop_words != stop_words {
+            let stop_words = fst::Set::from_iter(stop_words)?;
+            index.main.put_stop_words_fst(writer, &stop_words)?;
+        }
+        return Ok(false);
+    }
+
     let deletion: BTreeSet<String> = old_stop_words.difference(&stop_words).cloned().collect();
     let addition: BTreeSet<String> = stop_words.difference(&old_stop_words).cloned().collect();
 
@@ -186,10 +939,13 @@ pub fn apply_stop_words_update(
         apply_stop_words_deletion(writer, index, deletion)?;
     }
 
+    // the stop words can be in the words delta, it is merged first
+    compact_words_fst(writer, index)?;
+
     if let Some(words_fst) = index.main.words_fst(writer)? {
         let stop_words = fst::Set::from_iter(stop_words)?;
         let op = OpBuilder::new()
-            .add(&words_fst)
+            .add(words_fst.stream())
             .add(&stop_words)
             .difference();
 
@@ -227,9 +983,10 @@ fn apply_stop_words_addition(
         .and_then(fst::Set::from_bytes)?;
 
     // we also need to remove all the stop words from the main fst
+    compact_words_fst(writer, index)?;
     if let Some(word_fst) = main_store.words_fst(writer)? {
         let op = OpBuilder::new()
-            .add(&word_fst)
+            .add(word_fst.stream())
             .add(&delta_stop_words)
             .difference();
 
@@ -246,7 +1003,7 @@ fn apply_stop_words_addition(
     let stop_words_fst = main_store.stop_words_fst(writer)?.unwrap_or_default();
 
     let op = OpBuilder::new()
-        .add(&stop_words_fst)
+        .add(stop_words_fst.stream())
         .add(&delta_stop_words)
         .r#union();
 
@@ -282,7 +1039,7 @@ fn apply_stop_words_deletion(
     let stop_words_fst = index.main.stop_words_fst(writer)?.unwrap_or_default();
 
     let op = OpBuilder::new()
-        .add(&stop_words_fst)
+        .add(stop_words_fst.stream())
         .add(&delta_stop_words)
         .difference();
 
@@ -302,19 +1059,17 @@ pub fn apply_synonyms_update(
     let main_store = index.main;
     let synonyms_store = index.synonyms;
 
+    // the synonyms of the languages are stored in the same database,
+    // only the words of the previous global synonyms must be removed
+    let old_words = main_store.synonyms_fst(writer)?.unwrap_or_default().stream().into_strs()?;
+    for word in old_words {
+        synonyms_store.del_synonyms(writer, word.as_bytes())?;
+    }
+
     let mut synonyms_builder = SetBuilder::memory();
-    synonyms_store.clear(writer)?;
-    for (word, alternatives) in synonyms.clone() {
+    for (word, alternatives) in synonyms {
         synonyms_builder.insert(&word)?;
-
-        let alternatives = {
-            let alternatives = SetBuf::from_dirty(alternatives);
-            let mut alternatives_builder = SetBuilder::memory();
-            alternatives_builder.extend_iter(alternatives)?;
-            let bytes = alternatives_builder.into_inner()?;
-            fst::Set::from_bytes(bytes)?
-        };
-
+        let alternatives = alternatives_set(alternatives)?;
         synonyms_store.put_synonyms(writer, word.as_bytes(), &alternatives)?;
     }
 
@@ -326,3 +1081,119 @@ pub fn apply_synonyms_update(
 
     Ok(())
 }
+
+/// Replaces the synonyms of a language, an empty set of synonyms removes the language.
+pub fn apply_localized_synonyms_update(
+    writer: &mut heed::RwTxn<MainT>,
+    index: &store::Index,
+    language: &str,
+    synonyms: BTreeMap<String, Vec<String>>,
+) -> MResult<()> {
+    let mut localized = current_localized_synonyms(writer, index)?;
+
+    if let Some(old_synonyms) = localized.remove(language) {
+        for word in old_synonyms.keys() {
+            index.synonyms.del_localized_synonyms(writer, language, word.as_bytes())?;
+        }
+    }
+
+    if !synonyms.is_empty() {
+        for (word, alternatives) in &synonyms {
+            let alternatives = alternatives_set(alternatives.clone())?;
+            index.synonyms.put_localized_synonyms(writer, language, word.as_bytes(), &alternatives)?;
+        }
+        localized.insert(language.to_string(), normalize_synonyms(&synonyms));
+    }
+
+    if localized.is_empty() {
+        index.main.delete_localized_synonyms(writer)?;
+    } else {
+        index.main.put_localized_synonyms(writer, &localized)?;
+    }
+
+    Ok(())
+}
+
+fn alternatives_set(alternatives: Vec<String>) -> MResult<fst::Set> {
+    let alternatives = SetBuf::from_dirty(alternatives);
+    let mut alternatives_builder = SetBuilder::memory();
+    alternatives_builder.extend_iter(alternatives)?;
+    let bytes = alternatives_builder.into_inner()?;
+    Ok(fst::Set::from_bytes(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reindex_nothing_for_search_settings() {
+        let diff = SettingsDiff {
+            ranking_rules_changed: true,
+            distinct_attribute_changed: true,
+            displayed_attributes_changed: true,
+            synonyms_changed: true,
+            typo_tolerance_changed: true,
+            stop_words_added: vec![String::from("the")],
+            ..SettingsDiff::default()
+        };
+        assert_eq!(ReindexPlan::from_diff(&diff, false), ReindexPlan::default());
+    }
+
+    #[test]
+    fn reindex_only_the_facets_and_sortables() {
+        let diff = SettingsDiff { faceting_removed: vec![String::from("color")], ..SettingsDiff::default() };
+        let plan = ReindexPlan::from_diff(&diff, false);
+        assert_eq!(plan, ReindexPlan { documents: false, facets: true, sortables: false, documents_fields: false });
+
+        let diff = SettingsDiff {
+            faceting_added: vec![String::from("color")],
+            sortable_attributes_changed: true,
+            ..SettingsDiff::default()
+        };
+        let plan = ReindexPlan::from_diff(&diff, false);
+        assert_eq!(plan, ReindexPlan { documents: false, facets: true, sortables: true, documents_fields: false });
+
+        let diff = SettingsDiff { facet_normalization_changed: true, ..SettingsDiff::default() };
+        let plan = ReindexPlan::from_diff(&diff, false);
+        assert_eq!(plan, ReindexPlan { documents: false, facets: true, sortables: false, documents_fields: false });
+    }
+
+    #[test]
+    fn reindex_all_the_documents() {
+        let changes = [
+            SettingsDiff { ranked_attributes_changed: true, ..SettingsDiff::default() },
+            SettingsDiff { field_types_changed: true, ..SettingsDiff::default() },
+            SettingsDiff { searchable_attributes_changed: true, ..SettingsDiff::default() },
+            SettingsDiff { fold_diacritics_changed: true, ..SettingsDiff::default() },
+            SettingsDiff { token_policy_changed: true, ..SettingsDiff::default() },
+            SettingsDiff { indexing_limits_changed: true, ..SettingsDiff::default() },
+            SettingsDiff { stop_words_in_phrases_changed: true, ..SettingsDiff::default() },
+            SettingsDiff { stop_words_removed: vec![String::from("the")], ..SettingsDiff::default() },
+        ];
+
+        for diff in &changes {
+            let diff = SettingsDiff {
+                faceting_added: vec![String::from("color")],
+                compress_documents_changed: true,
+                ..diff.clone()
+            };
+            let plan = ReindexPlan::from_diff(&diff, false);
+            // the facets, the sortables and the documents fields are rebuilt with the documents
+            assert_eq!(plan, ReindexPlan { documents: true, facets: false, sortables: false, documents_fields: false });
+        }
+    }
+
+    #[test]
+    fn rewrite_only_the_documents_fields() {
+        let diff = SettingsDiff { compress_documents_changed: true, ..SettingsDiff::default() };
+        let plan = ReindexPlan::from_diff(&diff, false);
+        assert_eq!(plan, ReindexPlan { documents: false, facets: false, sortables: false, documents_fields: true });
+    }
+
+    #[test]
+    fn removed_stop_words_are_already_indexed() {
+        let diff = SettingsDiff { stop_words_removed: vec![String::from("the")], ..SettingsDiff::default() };
+        assert_eq!(ReindexPlan::from_diff(&diff, true), ReindexPlan::default());
+    }
+}
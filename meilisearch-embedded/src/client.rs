@@ -0,0 +1,80 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use meilisearch_core::{Database, DatabaseOptions};
+
+use crate::error::{Error, Result};
+use crate::index::{Index, DEFAULT_UPDATE_TIMEOUT};
+
+/// A handle on a database, it can be cheaply cloned and shared between threads.
+#[derive(Clone)]
+pub struct Client {
+    db: Arc<Database>,
+    update_timeout: Duration,
+}
+
+impl Client {
+    /// Opens the database stored at the given path, creates it if it doesn't exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Client> {
+        Client::open_with_options(path, DatabaseOptions::default())
+    }
+
+    pub fn open_with_options(path: impl AsRef<Path>, options: DatabaseOptions) -> Result<Client> {
+        let db = Database::open_or_create(path, options)?;
+        Ok(Client { db: Arc::new(db), update_timeout: DEFAULT_UPDATE_TIMEOUT })
+    }
+
+    /// Sets how long the methods of the indexes sending an update wait for it to be processed,
+    /// an `UpdateTimeout` error is returned after that. The default is ten minutes.
+    pub fn set_update_timeout(&mut self, timeout: Duration) {
+        self.update_timeout = timeout;
+    }
+
+    /// Returns the index with this uid, creating it if it doesn't exist yet.
+    pub fn index(&self, uid: &str) -> Result<Index> {
+        match self.get_index(uid) {
+            Some(index) => Ok(index),
+            None => self.create_index(uid, None),
+        }
+    }
+
+    /// Returns the index with this uid if it exists.
+    pub fn get_index(&self, uid: &str) -> Option<Index> {
+        let index = self.db.open_index(uid)?;
+        Some(Index::new(self.db.clone(), uid, index, self.update_timeout))
+    }
+
+    /// Creates an index, the primary key is inferred from the first documents added
+    /// when none is given.
+    pub fn create_index(&self, uid: &str, primary_key: Option<&str>) -> Result<Index> {
+        let valid_uid = !uid.is_empty() && uid.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_uid {
+            return Err(Error::InvalidIndexUid(uid.to_string()));
+        }
+
+        let index = Index::new(self.db.clone(), uid, self.db.create_index(uid)?, self.update_timeout);
+        if let Some(primary_key) = primary_key {
+            index.set_primary_key(primary_key)?;
+        }
+
+        Ok(index)
+    }
+
+    /// Deletes the index and all of its documents, returns `false` if it didn't exist.
+    pub fn delete_index(&self, uid: &str) -> Result<bool> {
+        Ok(self.db.delete_index(uid)?)
+    }
+
+    /// The uids of all the indexes.
+    pub fn indexes(&self) -> Vec<String> {
+        let mut uids = self.db.indexes_uids();
+        uids.sort();
+        uids
+    }
+
+    /// The underlying database, to reach the features the client doesn't expose.
+    pub fn database(&self) -> &Database {
+        &self.db
+    }
+}
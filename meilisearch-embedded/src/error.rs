@@ -0,0 +1,77 @@
+use std::time::Duration;
+use std::{error, fmt};
+
+use meilisearch_core::serde::SerializerError;
+use meilisearch_core::HeedError;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    Core(meilisearch_core::Error),
+    InvalidIndexUid(String),
+    IndexNotFound(String),
+    PrimaryKeyInference,
+    InvalidDocument,
+    InvalidSettings(String),
+    UpdateFailed { update_id: u64, message: String },
+    UpdateNotFound(u64),
+    UpdateTimeout { update_id: u64, timeout: Duration },
+}
+
+impl From<meilisearch_core::Error> for Error {
+    fn from(error: meilisearch_core::Error) -> Error {
+        Error::Core(error)
+    }
+}
+
+impl From<HeedError> for Error {
+    fn from(error: HeedError) -> Error {
+        Error::Core(error.into())
+    }
+}
+
+impl From<meilisearch_schema::Error> for Error {
+    fn from(error: meilisearch_schema::Error) -> Error {
+        Error::Core(error.into())
+    }
+}
+
+impl From<SerializerError> for Error {
+    fn from(error: SerializerError) -> Error {
+        Error::Core(error.into())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Error {
+        Error::Core(error.into())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Error::*;
+        match self {
+            Core(e) => write!(f, "{}", e),
+            InvalidIndexUid(uid) => write!(
+                f,
+                "index uid {:?} is invalid, it can only contain alphanumeric characters, hyphens and underscores",
+                uid
+            ),
+            IndexNotFound(uid) => write!(f, "index {} not found", uid),
+            PrimaryKeyInference => write!(f, "could not infer a primary key"),
+            InvalidDocument => write!(f, "documents must be JSON objects"),
+            InvalidSettings(e) => write!(f, "invalid settings; {}", e),
+            UpdateFailed { update_id, message } => write!(f, "update {} failed; {}", update_id, message),
+            UpdateNotFound(update_id) => write!(f, "update {} not found", update_id),
+            UpdateTimeout { update_id, timeout } => write!(
+                f,
+                "update {} is not processed after {:?}, it will be processed later",
+                update_id, timeout
+            ),
+        }
+    }
+}
+
+impl error::Error for Error {}
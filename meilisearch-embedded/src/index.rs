@@ -0,0 +1,213 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use meilisearch_core::settings::Settings;
+use meilisearch_core::{update, Database, ProcessedUpdateResult, UpdateStatus};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::search::{SearchBuilder, SearchResults};
+use crate::Document;
+
+/// How long the methods sending an update wait for it to be processed by default.
+pub(crate) const DEFAULT_UPDATE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// A handle on an index, the updates it sends are processed before its methods return.
+#[derive(Clone)]
+pub struct Index {
+    db: Arc<Database>,
+    uid: String,
+    update_timeout: Duration,
+    pub(crate) index: meilisearch_core::Index,
+}
+
+impl Index {
+    pub(crate) fn new(db: Arc<Database>, uid: &str, index: meilisearch_core::Index, update_timeout: Duration) -> Index {
+        Index { db, uid: uid.to_string(), update_timeout, index }
+    }
+
+    pub fn uid(&self) -> &str {
+        &self.uid
+    }
+
+    pub fn primary_key(&self) -> Result<Option<String>> {
+        let reader = self.db.main_read_txn()?;
+        let schema = self.index.main.schema(&reader)?;
+        Ok(schema.and_then(|schema| schema.primary_key().map(str::to_string)))
+    }
+
+    /// Sets the primary key of the index, it can't be changed once set.
+    pub fn set_primary_key(&self, primary_key: &str) -> Result<()> {
+        let mut writer = self.db.main_write_txn()?;
+        let mut schema = self
+            .index
+            .main
+            .schema(&writer)?
+            .ok_or(meilisearch_core::Error::SchemaMissing)?;
+        schema.set_primary_key(primary_key)?;
+        self.index.main.put_schema(&mut writer, &schema)?;
+        writer.commit()?;
+        Ok(())
+    }
+
+    /// Adds the documents or replaces the ones that have the same id.
+    pub fn add_documents<D: Serialize>(&self, documents: impl IntoIterator<Item = D>) -> Result<ProcessedUpdateResult> {
+        self.push_documents(documents, false)
+    }
+
+    /// Adds the documents or updates the fields of the ones that have the same id.
+    pub fn update_documents<D: Serialize>(&self, documents: impl IntoIterator<Item = D>) -> Result<ProcessedUpdateResult> {
+        self.push_documents(documents, true)
+    }
+
+    fn push_documents<D: Serialize>(
+        &self,
+        documents: impl IntoIterator<Item = D>,
+        partial: bool,
+    ) -> Result<ProcessedUpdateResult> {
+        let mut document_addition = if partial {
+            self.index.documents_partial_addition()
+        } else {
+            self.index.documents_addition()
+        };
+
+        let mut first = true;
+        for document in documents {
+            let document: Document = match serde_json::to_value(document)? {
+                Value::Object(map) => map.into_iter().collect(),
+                _ => return Err(Error::InvalidDocument),
+            };
+            if first {
+                self.ensure_primary_key(&document)?;
+                first = false;
+            }
+            document_addition.update_document(document);
+        }
+
+        let mut writer = self.db.update_write_txn()?;
+        let update_id = document_addition.finalize(&mut writer)?;
+        writer.commit()?;
+
+        self.wait_update(update_id)
+    }
+
+    /// Infers the primary key from the first document when the index doesn't have one,
+    /// it is the first attribute containing `id`.
+    fn ensure_primary_key(&self, document: &Document) -> Result<()> {
+        if self.primary_key()?.is_none() {
+            let primary_key = document
+                .keys()
+                .find(|key| key.to_lowercase().contains("id"))
+                .ok_or(Error::PrimaryKeyInference)?;
+            self.set_primary_key(primary_key)?;
+        }
+        Ok(())
+    }
+
+    pub fn delete_documents<S: AsRef<str>>(&self, documents_ids: impl IntoIterator<Item = S>) -> Result<ProcessedUpdateResult> {
+        let format = self.index.main.document_id_format(&self.db.main_read_txn()?)?.unwrap_or_default();
+
+        let mut documents_deletion = self.index.documents_deletion();
+        for document_id in documents_ids {
+            let document_id = update::compute_document_id(document_id.as_ref(), format)?;
+            documents_deletion.delete_document_by_id(document_id);
+        }
+
+        let mut writer = self.db.update_write_txn()?;
+        let update_id = documents_deletion.finalize(&mut writer)?;
+        writer.commit()?;
+
+        self.wait_update(update_id)
+    }
+
+    /// Deletes all the documents of the index, the settings are kept.
+    pub fn clear_documents(&self) -> Result<ProcessedUpdateResult> {
+        let mut writer = self.db.update_write_txn()?;
+        let update_id = self.index.clear_all(&mut writer)?;
+        writer.commit()?;
+
+        self.wait_update(update_id)
+    }
+
+    /// Retrieves the displayed attributes of a document.
+    pub fn document<T: DeserializeOwned>(&self, document_id: &str) -> Result<Option<T>> {
+        let reader = self.db.main_read_txn()?;
+        let format = self.index.main.document_id_format(&reader)?.unwrap_or_default();
+        let document_id = update::compute_document_id(document_id, format)?;
+
+        let schema = self
+            .index
+            .main
+            .schema(&reader)?
+            .ok_or(meilisearch_core::Error::SchemaMissing)?;
+        let attributes: HashSet<&str> = schema.displayed_name();
+
+        Ok(self.index.document(&reader, Some(&attributes), document_id)?)
+    }
+
+    /// Updates the settings that are set, the others are left untouched.
+    pub fn update_settings(&self, settings: Settings) -> Result<ProcessedUpdateResult> {
+        let settings = settings.into_update().map_err(|e| Error::InvalidSettings(e.to_string()))?;
+
+        let mut writer = self.db.update_write_txn()?;
+        let update_id = self.index.settings_update(&mut writer, settings)?;
+        writer.commit()?;
+
+        self.wait_update(update_id)
+    }
+
+    /// Searches the documents with the settings of the index.
+    pub fn search(&self, query: &str) -> Result<SearchResults> {
+        self.search_builder(query).execute()
+    }
+
+    pub fn search_builder(&self, query: &str) -> SearchBuilder {
+        SearchBuilder::new(self, query)
+    }
+
+    pub(crate) fn database(&self) -> &Database {
+        &self.db
+    }
+
+    /// Blocks until the update has been processed, a failed update is returned as an error.
+    /// The update is still processed later if it isn't once the timeout is reached.
+    pub(crate) fn wait_update(&self, update_id: u64) -> Result<ProcessedUpdateResult> {
+        // subscribing before reading the status ensures the notification can't be missed
+        let notifications = self.db.subscribe_updates();
+        let deadline = Instant::now() + self.update_timeout;
+
+        loop {
+            let status = {
+                let reader = self.db.update_read_txn()?;
+                self.index.update_status(&reader, update_id)?
+            };
+
+            match status {
+                Some(UpdateStatus::Processed { content }) => return Ok(content),
+                Some(UpdateStatus::Failed { content })
+                | Some(UpdateStatus::Canceled { content })
+                | Some(UpdateStatus::Aborted { content }) => {
+                    let message = content.error.unwrap_or_default();
+                    return Err(Error::UpdateFailed { update_id, message });
+                }
+                Some(_) => (),
+                None => return Err(Error::UpdateNotFound(update_id)),
+            }
+
+            // the result is read again once the update is notified as processed
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                match notifications.recv_timeout(remaining) {
+                    Ok(notification) if notification.index_uid == self.uid && notification.update_id == update_id => {
+                        break
+                    }
+                    Ok(_) => (),
+                    Err(_) => return Err(Error::UpdateTimeout { update_id, timeout: self.update_timeout }),
+                }
+            }
+        }
+    }
+}
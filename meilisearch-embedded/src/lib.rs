@@ -0,0 +1,121 @@
+//! Embeds the MeiliSearch engine in a Rust application, without the HTTP server.
+//!
+//! The transactions and the update loop are hidden, the methods sending an update
+//! return once it has been processed, the documents are then searchable.
+//!
+//! ```no_run
+//! use meilisearch_embedded::Client;
+//! use serde_json::json;
+//!
+//! # fn main() -> meilisearch_embedded::Result<()> {
+//! let client = Client::open("./data.ms")?;
+//! let movies = client.index("movies")?;
+//!
+//! movies.add_documents(vec![
+//!     json!({ "id": 1, "title": "Carol" }),
+//!     json!({ "id": 2, "title": "Wonder Woman" }),
+//! ])?;
+//!
+//! let results = movies.search_builder("wonder").limit(5).execute()?;
+//! assert_eq!(results.hits[0]["title"], "Wonder Woman");
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(test)]
+#[macro_use]
+extern crate assert_matches;
+
+mod client;
+mod error;
+mod index;
+mod search;
+
+pub use self::client::Client;
+pub use self::error::{Error, Result};
+pub use self::index::Index;
+pub use self::search::{SearchBuilder, SearchResults};
+pub use meilisearch_core::settings::Settings;
+pub use meilisearch_core::{DatabaseOptions, GeoPoint, ProcessedUpdateResult};
+
+pub type Document = indexmap::IndexMap<String, serde_json::Value>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn movies(client: &Client) -> Index {
+        let movies = client.index("movies").unwrap();
+        movies
+            .add_documents(vec![
+                json!({ "id": 1, "title": "Carol", "year": 2015 }),
+                json!({ "id": 2, "title": "Wonder Woman", "year": 2017 }),
+                json!({ "id": 3, "title": "Wonder Boys", "year": 2000 }),
+            ])
+            .unwrap();
+        movies
+    }
+
+    #[test]
+    fn add_and_search_documents() {
+        let dir = tempfile::tempdir().unwrap();
+        let client = Client::open(dir.path()).unwrap();
+        let movies = movies(&client);
+
+        assert_eq!(movies.primary_key().unwrap().as_deref(), Some("id"));
+        assert_eq!(client.indexes(), vec!["movies".to_string()]);
+
+        let results = movies.search("wonder").unwrap();
+        assert_eq!(results.nb_hits, 2);
+
+        let results = movies
+            .search_builder("wonder")
+            .filters("year > 2010")
+            .attributes_to_retrieve(&["title"])
+            .execute()
+            .unwrap();
+        assert_eq!(serde_json::to_value(&results.hits).unwrap(), json!([{ "title": "Wonder Woman" }]));
+
+        let document: Option<Document> = movies.document("1").unwrap();
+        assert_eq!(document.unwrap()["title"], "Carol");
+    }
+
+    #[test]
+    fn update_settings_and_delete_documents() {
+        let dir = tempfile::tempdir().unwrap();
+        let client = Client::open(dir.path()).unwrap();
+        let movies = movies(&client);
+
+        let settings: Settings = serde_json::from_value(json!({ "rankingRules": ["desc(year)", "typo"] })).unwrap();
+        movies.update_settings(settings).unwrap();
+
+        let results = movies.search("wonder").unwrap();
+        let titles: Vec<_> = results.hits.iter().map(|hit| hit["title"].clone()).collect();
+        assert_eq!(titles, vec![json!("Wonder Woman"), json!("Wonder Boys")]);
+
+        movies.delete_documents(&["2"]).unwrap();
+        let results = movies.search("wonder").unwrap();
+        assert_eq!(results.nb_hits, 1);
+
+        movies.clear_documents().unwrap();
+        assert_eq!(movies.search("").unwrap().nb_hits, 0);
+    }
+
+    #[test]
+    fn errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let client = Client::open(dir.path()).unwrap();
+
+        assert_matches!(client.index("movies/series"), Err(Error::InvalidIndexUid(_)));
+
+        let movies = client.index("movies").unwrap();
+        assert_matches!(movies.add_documents(vec![json!({ "title": "Carol" })]), Err(Error::PrimaryKeyInference));
+        assert_matches!(movies.add_documents(vec![json!(42)]), Err(Error::InvalidDocument));
+
+        let settings: Settings = serde_json::from_value(json!({ "rankingRules": ["popularity"] })).unwrap();
+        assert_matches!(movies.update_settings(settings), Err(Error::InvalidSettings(_)));
+
+        assert_matches!(movies.wait_update(42), Err(Error::UpdateNotFound(42)));
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::time::Instant;
+
+use log::error;
+use meilisearch_core::criterion::*;
+use meilisearch_core::settings::RankingRule;
+use meilisearch_core::{Filter, GeoPoint, MainT, RankedMap};
+use meilisearch_schema::Schema;
+use serde::Serialize;
+use siphasher::sip::SipHasher;
+
+use crate::error::Result;
+use crate::index::Index;
+use crate::Document;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResults {
+    pub hits: Vec<Document>,
+    pub offset: usize,
+    pub limit: usize,
+    pub nb_hits: usize,
+    pub exhaustive_nb_hits: bool,
+    pub processing_time_ms: usize,
+    pub query: String,
+}
+
+/// A search that follows the ranking rules, the distinct attribute and the displayed
+/// attributes of the index.
+pub struct SearchBuilder<'a> {
+    index: &'a Index,
+    query: String,
+    offset: usize,
+    limit: usize,
+    filters: Option<String>,
+    attributes_to_retrieve: Option<HashSet<String>>,
+    around: Option<GeoPoint>,
+}
+
+impl<'a> SearchBuilder<'a> {
+    pub(crate) fn new(index: &'a Index, query: &str) -> SearchBuilder<'a> {
+        SearchBuilder {
+            index,
+            query: query.to_string(),
+            offset: 0,
+            limit: 20,
+            filters: None,
+            attributes_to_retrieve: None,
+            around: None,
+        }
+    }
+
+    pub fn offset(mut self, value: usize) -> SearchBuilder<'a> {
+        self.offset = value;
+        self
+    }
+
+    pub fn limit(mut self, value: usize) -> SearchBuilder<'a> {
+        self.limit = value;
+        self
+    }
+
+    /// Only returns the documents matching this filter expression, e.g. `year > 2000`.
+    pub fn filters(mut self, value: &str) -> SearchBuilder<'a> {
+        self.filters = Some(value.to_string());
+        self
+    }
+
+    /// Restricts the displayed attributes returned in the hits.
+    pub fn attributes_to_retrieve<S: AsRef<str>>(mut self, attributes: impl IntoIterator<Item = S>) -> SearchBuilder<'a> {
+        let attributes = attributes.into_iter().map(|attr| attr.as_ref().to_string());
+        self.attributes_to_retrieve = Some(attributes.collect());
+        self
+    }
+
+    /// The point from which the distances are computed by the `geo:asc` ranking rule,
+    /// the rule is ignored when it is not set.
+    pub fn around(mut self, point: GeoPoint) -> SearchBuilder<'a> {
+        self.around = Some(point);
+        self
+    }
+
+    pub fn execute(self) -> Result<SearchResults> {
+        let start = Instant::now();
+        let index = &self.index.index;
+        let reader = self.index.database().main_read_txn()?;
+        let reader = &reader;
+
+        let schema = index.main.schema(reader)?.ok_or(meilisearch_core::Error::SchemaMissing)?;
+        let ranked_map = index.main.ranked_map(reader)?.unwrap_or_default();
+
+        let mut query_builder = match criteria(reader, index, &ranked_map, &schema, self.around)? {
+            Some(criteria) => index.query_builder_with_criteria(criteria),
+            None => index.query_builder(),
+        };
+
+        if let Some(filters) = &self.filters {
+            let filter = Filter::parse(filters, &schema)?;
+            query_builder.with_filter(move |id| match filter.test(reader, index, id) {
+                Ok(res) => res,
+                Err(e) => {
+                    log::warn!("unexpected error during filtering: {}", e);
+                    false
+                }
+            });
+        }
+
+        if let Some(field) = index.main.distinct_attribute(reader)? {
+            if let Some(field_id) = schema.id(&field) {
+                query_builder.with_distinct(1, move |id| match index.document_attribute_bytes(reader, id, field_id) {
+                    Ok(Some(bytes)) => {
+                        let mut s = SipHasher::new();
+                        bytes.hash(&mut s);
+                        Some(s.finish())
+                    }
+                    _ => None,
+                });
+            }
+        }
+
+        let result = query_builder.query(reader, &self.query, self.offset..(self.offset + self.limit))?;
+
+        let mut attributes = schema.displayed_name();
+        if let Some(restricted) = &self.attributes_to_retrieve {
+            attributes.retain(|attr| restricted.contains(*attr));
+        }
+
+        let mut hits = Vec::with_capacity(result.documents.len());
+        for document in result.documents {
+            if let Some(hit) = index.document(reader, Some(&attributes), document.id)? {
+                hits.push(hit);
+            }
+        }
+
+        Ok(SearchResults {
+            hits,
+            offset: self.offset,
+            limit: self.limit,
+            nb_hits: result.nb_hits,
+            exhaustive_nb_hits: result.exhaustive_nb_hit,
+            processing_time_ms: start.elapsed().as_millis() as usize,
+            query: self.query,
+        })
+    }
+}
+
+fn criteria<'a>(
+    reader: &heed::RoTxn<MainT>,
+    index: &meilisearch_core::Index,
+    ranked_map: &'a RankedMap,
+    schema: &Schema,
+    around: Option<GeoPoint>,
+) -> Result<Option<Criteria<'a>>> {
+    let ranking_rules = match index.main.ranking_rules(reader)? {
+        Some(ranking_rules) => ranking_rules,
+        None => return Ok(None),
+    };
+
+    let mut builder = CriteriaBuilder::with_capacity(7 + ranking_rules.len());
+    for rule in ranking_rules {
+        match rule {
+            RankingRule::Typo => builder.push(Typo),
+            RankingRule::Words => builder.push(Words),
+            RankingRule::Proximity => builder.push(Proximity),
+            RankingRule::Attribute => builder.push(Attribute),
+            RankingRule::WordsPosition => builder.push(WordsPosition),
+            RankingRule::Exactness => builder.push(Exactness),
+            RankingRule::Asc(field) => match SortByAttr::lower_is_better(ranked_map, schema, &field) {
+                Ok(rule) => builder.push(rule),
+                Err(err) => error!("Error during criteria builder; {:?}", err),
+            },
+            RankingRule::Desc(field) => match SortByAttr::higher_is_better(ranked_map, schema, &field) {
+                Ok(rule) => builder.push(rule),
+                Err(err) => error!("Error during criteria builder; {:?}", err),
+            },
+            RankingRule::GeoAsc => {
+                if let Some(point) = around {
+                    builder.push(GeoDistance::new(index.geo, point));
+                }
+            }
+        }
+    }
+    builder.push(DocumentId);
+
+    Ok(Some(builder.build()))
+}
@@ -0,0 +1,107 @@
+#ifndef MEILISEARCH_H
+#define MEILISEARCH_H
+
+/*
+ * Embeds the MeiliSearch engine, see the documentation of the meilisearch-ffi crate.
+ *
+ * The strings given to the functions are borrowed for the duration of the call, they
+ * must be NUL terminated and valid UTF-8. The strings returned through the out
+ * parameters are owned by the caller and must be released with meili_string_free.
+ * A client is released with meili_client_free, it can be shared between threads.
+ *
+ * Every function returns a MeiliErrorCode, the message describing the last error of
+ * the calling thread is returned by meili_last_error_message.
+ */
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum {
+    MEILI_OK = 0,
+    MEILI_NULL_ARGUMENT = 1,
+    MEILI_INVALID_UTF8 = 2,
+    MEILI_INVALID_JSON = 3,
+    MEILI_INVALID_INDEX_UID = 4,
+    MEILI_INDEX_NOT_FOUND = 5,
+    MEILI_PRIMARY_KEY_INFERENCE = 6,
+    MEILI_INVALID_DOCUMENT = 7,
+    MEILI_INVALID_SETTINGS = 8,
+    MEILI_INVALID_FILTER = 9,
+    MEILI_UPDATE_FAILED = 10,
+    MEILI_INTERNAL = 11,
+    MEILI_PANIC = 12,
+    MEILI_UPDATE_TIMEOUT = 13,
+} MeiliErrorCode;
+
+typedef struct MeiliClient MeiliClient;
+
+/* Opens the database stored at the given path, creates it if it doesn't exist. */
+MeiliErrorCode meili_client_open(const char *path, MeiliClient **out_client);
+
+/* Releases the client, it must not be used afterwards. Does nothing when it is NULL. */
+void meili_client_free(MeiliClient *client);
+
+/*
+ * Adds a JSON array of documents, the index is created if it doesn't exist.
+ * When partial is true only the given fields of the existing documents are updated.
+ * Returns once the documents are searchable, or MEILI_UPDATE_TIMEOUT after ten minutes
+ * in which case the documents are added later.
+ */
+MeiliErrorCode meili_add_documents(const MeiliClient *client,
+                                   const char *index_uid,
+                                   const char *documents_json,
+                                   bool partial,
+                                   uint64_t *out_update_id);
+
+/* Deletes the documents whose ids are given as a JSON array of strings or integers. */
+MeiliErrorCode meili_delete_documents(const MeiliClient *client,
+                                      const char *index_uid,
+                                      const char *documents_ids_json,
+                                      uint64_t *out_update_id);
+
+/* Updates the settings given as a JSON object, the index is created if it doesn't exist. */
+MeiliErrorCode meili_update_settings(const MeiliClient *client,
+                                     const char *index_uid,
+                                     const char *settings_json,
+                                     uint64_t *out_update_id);
+
+/* Deletes the index and its documents, out_deleted is false if it didn't exist. */
+MeiliErrorCode meili_delete_index(const MeiliClient *client,
+                                  const char *index_uid,
+                                  bool *out_deleted);
+
+/* Writes the document as a JSON object, or NULL when there is no document with this id. */
+MeiliErrorCode meili_get_document(const MeiliClient *client,
+                                  const char *index_uid,
+                                  const char *document_id,
+                                  char **out_json);
+
+/*
+ * Searches the index and writes the results as a JSON object. The options are a JSON
+ * object with the optional offset, limit, filters and attributesToRetrieve fields,
+ * they can be NULL.
+ */
+MeiliErrorCode meili_search(const MeiliClient *client,
+                            const char *index_uid,
+                            const char *query,
+                            const char *options_json,
+                            char **out_json);
+
+/* Releases a string returned by the library. Does nothing when it is NULL. */
+void meili_string_free(char *string);
+
+/*
+ * The message of the last error that occurred on the calling thread, NULL if the last
+ * call succeeded. It is owned by the library and valid until the next call on this thread.
+ */
+const char *meili_last_error_message(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MEILISEARCH_H */
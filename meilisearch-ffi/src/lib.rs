@@ -0,0 +1,445 @@
+//! A C ABI over the embedded engine, the declarations are in `include/meilisearch.h`.
+//!
+//! The ownership rules are the following:
+//!  - the strings given to the functions are borrowed for the duration of the call,
+//!    they must be NUL terminated and valid UTF-8;
+//!  - the strings returned through the out parameters are owned by the caller and must
+//!    be released with `meili_string_free`;
+//!  - a client is released with `meili_client_free`, it can be shared between threads;
+//!  - every function returns a `MeiliErrorCode`, the message describing the last error
+//!    of the calling thread is returned by `meili_last_error_message`.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use meilisearch_core::Error as CoreError;
+use meilisearch_embedded::{Client, Error, Index, Settings};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Keep in sync with the `MeiliErrorCode` enum of the header, the values must not change.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeiliErrorCode {
+    Ok = 0,
+    NullArgument = 1,
+    InvalidUtf8 = 2,
+    InvalidJson = 3,
+    InvalidIndexUid = 4,
+    IndexNotFound = 5,
+    PrimaryKeyInference = 6,
+    InvalidDocument = 7,
+    InvalidSettings = 8,
+    InvalidFilter = 9,
+    UpdateFailed = 10,
+    Internal = 11,
+    Panic = 12,
+    UpdateTimeout = 13,
+}
+
+/// An opaque handle on a database.
+pub struct MeiliClient(Client);
+
+struct FfiError {
+    code: MeiliErrorCode,
+    message: String,
+}
+
+impl FfiError {
+    fn new(code: MeiliErrorCode, message: impl ToString) -> FfiError {
+        FfiError { code, message: message.to_string() }
+    }
+}
+
+impl From<Error> for FfiError {
+    fn from(error: Error) -> FfiError {
+        use meilisearch_embedded::Error::*;
+        let code = match &error {
+            Core(CoreError::FilterParseError(_)) | Core(CoreError::FacetError(_)) => MeiliErrorCode::InvalidFilter,
+            Core(CoreError::SerdeJson(_)) => MeiliErrorCode::InvalidJson,
+            Core(_) => MeiliErrorCode::Internal,
+            InvalidIndexUid(_) => MeiliErrorCode::InvalidIndexUid,
+            IndexNotFound(_) => MeiliErrorCode::IndexNotFound,
+            PrimaryKeyInference => MeiliErrorCode::PrimaryKeyInference,
+            InvalidDocument => MeiliErrorCode::InvalidDocument,
+            InvalidSettings(_) => MeiliErrorCode::InvalidSettings,
+            UpdateFailed { .. } => MeiliErrorCode::UpdateFailed,
+            UpdateNotFound(_) => MeiliErrorCode::Internal,
+            UpdateTimeout { .. } => MeiliErrorCode::UpdateTimeout,
+        };
+        FfiError::new(code, error)
+    }
+}
+
+impl From<serde_json::Error> for FfiError {
+    fn from(error: serde_json::Error) -> FfiError {
+        FfiError::new(MeiliErrorCode::InvalidJson, error)
+    }
+}
+
+type FfiResult<T> = Result<T, FfiError>;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+/// Runs the function, records its error message and converts it into a code,
+/// a panic must never unwind across the FFI boundary.
+fn run(f: impl FnOnce() -> FfiResult<()>) -> MeiliErrorCode {
+    let result = panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|_| Err(FfiError::new(MeiliErrorCode::Panic, "the engine panicked")));
+
+    let (code, message) = match result {
+        Ok(()) => (MeiliErrorCode::Ok, None),
+        Err(error) => {
+            // a message can't contain a NUL byte, it would end the C string
+            let message = CString::new(error.message.replace('\0', " ")).ok();
+            (error.code, message)
+        }
+    };
+
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+    code
+}
+
+unsafe fn str_arg<'a>(ptr: *const c_char, name: &str) -> FfiResult<&'a str> {
+    if ptr.is_null() {
+        return Err(FfiError::new(MeiliErrorCode::NullArgument, format!("{} is null", name)));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|e| FfiError::new(MeiliErrorCode::InvalidUtf8, format!("{} is not valid UTF-8; {}", name, e)))
+}
+
+unsafe fn client_arg<'a>(ptr: *const MeiliClient) -> FfiResult<&'a Client> {
+    match ptr.as_ref() {
+        Some(client) => Ok(&client.0),
+        None => Err(FfiError::new(MeiliErrorCode::NullArgument, "client is null")),
+    }
+}
+
+fn check_out<T>(out: *mut T, name: &str) -> FfiResult<()> {
+    if out.is_null() {
+        Err(FfiError::new(MeiliErrorCode::NullArgument, format!("{} is null", name)))
+    } else {
+        Ok(())
+    }
+}
+
+fn open_index(client: &Client, index_uid: &str) -> FfiResult<Index> {
+    client
+        .get_index(index_uid)
+        .ok_or_else(|| Error::IndexNotFound(index_uid.to_string()).into())
+}
+
+fn into_c_string(string: String) -> FfiResult<*mut c_char> {
+    let string = CString::new(string).map_err(|e| FfiError::new(MeiliErrorCode::Internal, e))?;
+    Ok(string.into_raw())
+}
+
+/// Opens the database stored at the given path, creates it if it doesn't exist.
+#[no_mangle]
+pub unsafe extern "C" fn meili_client_open(path: *const c_char, out_client: *mut *mut MeiliClient) -> MeiliErrorCode {
+    run(|| {
+        let path = str_arg(path, "path")?;
+        check_out(out_client, "out_client")?;
+
+        let client = Client::open(path)?;
+        *out_client = Box::into_raw(Box::new(MeiliClient(client)));
+        Ok(())
+    })
+}
+
+/// Releases the client, it must not be used afterwards. Does nothing when it is null.
+#[no_mangle]
+pub unsafe extern "C" fn meili_client_free(client: *mut MeiliClient) {
+    if !client.is_null() {
+        drop(Box::from_raw(client));
+    }
+}
+
+/// Adds a JSON array of documents, the index is created if it doesn't exist.
+/// When `partial` is true only the given fields of the existing documents are updated.
+#[no_mangle]
+pub unsafe extern "C" fn meili_add_documents(
+    client: *const MeiliClient,
+    index_uid: *const c_char,
+    documents_json: *const c_char,
+    partial: bool,
+    out_update_id: *mut u64,
+) -> MeiliErrorCode {
+    run(|| {
+        let client = client_arg(client)?;
+        let index_uid = str_arg(index_uid, "index_uid")?;
+        let documents_json = str_arg(documents_json, "documents_json")?;
+        check_out(out_update_id, "out_update_id")?;
+
+        let documents: Vec<Value> = serde_json::from_str(documents_json)?;
+        let index = client.index(index_uid)?;
+        let result = if partial {
+            index.update_documents(documents)?
+        } else {
+            index.add_documents(documents)?
+        };
+
+        *out_update_id = result.update_id;
+        Ok(())
+    })
+}
+
+/// Deletes the documents whose ids are given as a JSON array of strings or integers.
+#[no_mangle]
+pub unsafe extern "C" fn meili_delete_documents(
+    client: *const MeiliClient,
+    index_uid: *const c_char,
+    documents_ids_json: *const c_char,
+    out_update_id: *mut u64,
+) -> MeiliErrorCode {
+    run(|| {
+        let client = client_arg(client)?;
+        let index_uid = str_arg(index_uid, "index_uid")?;
+        let documents_ids_json = str_arg(documents_ids_json, "documents_ids_json")?;
+        check_out(out_update_id, "out_update_id")?;
+
+        let documents_ids: Vec<Value> = serde_json::from_str(documents_ids_json)?;
+        let documents_ids: Option<Vec<String>> = documents_ids
+            .iter()
+            .map(|id| match id {
+                Value::String(id) => Some(id.clone()),
+                Value::Number(id) => Some(id.to_string()),
+                _ => None,
+            })
+            .collect();
+        let documents_ids = documents_ids
+            .ok_or_else(|| FfiError::new(MeiliErrorCode::InvalidJson, "documents ids must be strings or integers"))?;
+
+        let result = open_index(client, index_uid)?.delete_documents(documents_ids)?;
+
+        *out_update_id = result.update_id;
+        Ok(())
+    })
+}
+
+/// Updates the settings given as a JSON object, the index is created if it doesn't exist.
+#[no_mangle]
+pub unsafe extern "C" fn meili_update_settings(
+    client: *const MeiliClient,
+    index_uid: *const c_char,
+    settings_json: *const c_char,
+    out_update_id: *mut u64,
+) -> MeiliErrorCode {
+    run(|| {
+        let client = client_arg(client)?;
+        let index_uid = str_arg(index_uid, "index_uid")?;
+        let settings_json = str_arg(settings_json, "settings_json")?;
+        check_out(out_update_id, "out_update_id")?;
+
+        let settings: Settings = serde_json::from_str(settings_json)?;
+        let result = client.index(index_uid)?.update_settings(settings)?;
+
+        *out_update_id = result.update_id;
+        Ok(())
+    })
+}
+
+/// Deletes the index and its documents, `out_deleted` is false if it didn't exist.
+#[no_mangle]
+pub unsafe extern "C" fn meili_delete_index(
+    client: *const MeiliClient,
+    index_uid: *const c_char,
+    out_deleted: *mut bool,
+) -> MeiliErrorCode {
+    run(|| {
+        let client = client_arg(client)?;
+        let index_uid = str_arg(index_uid, "index_uid")?;
+        check_out(out_deleted, "out_deleted")?;
+
+        *out_deleted = client.delete_index(index_uid)?;
+        Ok(())
+    })
+}
+
+/// Writes the document as a JSON object, or null when there is no document with this id.
+#[no_mangle]
+pub unsafe extern "C" fn meili_get_document(
+    client: *const MeiliClient,
+    index_uid: *const c_char,
+    document_id: *const c_char,
+    out_json: *mut *mut c_char,
+) -> MeiliErrorCode {
+    run(|| {
+        let client = client_arg(client)?;
+        let index_uid = str_arg(index_uid, "index_uid")?;
+        let document_id = str_arg(document_id, "document_id")?;
+        check_out(out_json, "out_json")?;
+
+        let document: Option<Value> = open_index(client, index_uid)?.document(document_id)?;
+        *out_json = match document {
+            Some(document) => into_c_string(document.to_string())?,
+            None => ptr::null_mut(),
+        };
+        Ok(())
+    })
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct SearchOptions {
+    offset: Option<usize>,
+    limit: Option<usize>,
+    filters: Option<String>,
+    attributes_to_retrieve: Option<Vec<String>>,
+}
+
+/// Searches the index and writes the results as a JSON object, the options are
+/// a JSON object with the `offset`, `limit`, `filters` and `attributesToRetrieve`
+/// optional fields, they can be null.
+#[no_mangle]
+pub unsafe extern "C" fn meili_search(
+    client: *const MeiliClient,
+    index_uid: *const c_char,
+    query: *const c_char,
+    options_json: *const c_char,
+    out_json: *mut *mut c_char,
+) -> MeiliErrorCode {
+    run(|| {
+        let client = client_arg(client)?;
+        let index_uid = str_arg(index_uid, "index_uid")?;
+        let query = str_arg(query, "query")?;
+        let options: SearchOptions = if options_json.is_null() {
+            SearchOptions::default()
+        } else {
+            serde_json::from_str(str_arg(options_json, "options_json")?)?
+        };
+        check_out(out_json, "out_json")?;
+
+        let index = open_index(client, index_uid)?;
+        let mut search = index.search_builder(query);
+        if let Some(offset) = options.offset {
+            search = search.offset(offset);
+        }
+        if let Some(limit) = options.limit {
+            search = search.limit(limit);
+        }
+        if let Some(filters) = &options.filters {
+            search = search.filters(filters);
+        }
+        if let Some(attributes) = options.attributes_to_retrieve {
+            search = search.attributes_to_retrieve(attributes);
+        }
+
+        let results = search.execute()?;
+        *out_json = into_c_string(serde_json::to_string(&results)?)?;
+        Ok(())
+    })
+}
+
+/// Releases a string returned by the library. Does nothing when it is null.
+#[no_mangle]
+pub unsafe extern "C" fn meili_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// The message of the last error that occurred on the calling thread, null if the last
+/// call succeeded. It is owned by the library and valid until the next call on this thread.
+#[no_mangle]
+pub extern "C" fn meili_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| match &*last.borrow() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn c(string: &str) -> CString {
+        CString::new(string).unwrap()
+    }
+
+    unsafe fn take_json(string: *mut c_char) -> Value {
+        let value = serde_json::from_slice(CStr::from_ptr(string).to_bytes()).unwrap();
+        meili_string_free(string);
+        value
+    }
+
+    #[test]
+    fn documents_and_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = c(dir.path().to_str().unwrap());
+
+        unsafe {
+            let mut client = ptr::null_mut();
+            assert_eq!(meili_client_open(path.as_ptr(), &mut client), MeiliErrorCode::Ok);
+
+            let documents = c(r#"[{ "id": 1, "title": "Carol" }, { "id": 2, "title": "Wonder Woman" }]"#);
+            let mut update_id = u64::max_value();
+            let code = meili_add_documents(client, c("movies").as_ptr(), documents.as_ptr(), false, &mut update_id);
+            assert_eq!(code, MeiliErrorCode::Ok);
+            assert_eq!(update_id, 0);
+
+            let mut json = ptr::null_mut();
+            let options = c(r#"{ "limit": 1, "attributesToRetrieve": ["title"] }"#);
+            let code = meili_search(client, c("movies").as_ptr(), c("wonder").as_ptr(), options.as_ptr(), &mut json);
+            assert_eq!(code, MeiliErrorCode::Ok);
+            let results = take_json(json);
+            assert_eq!(results["hits"], json!([{ "title": "Wonder Woman" }]));
+            assert_eq!(results["nbHits"], 1);
+
+            let code = meili_delete_documents(client, c("movies").as_ptr(), c("[2]").as_ptr(), &mut update_id);
+            assert_eq!(code, MeiliErrorCode::Ok);
+
+            let code = meili_get_document(client, c("movies").as_ptr(), c("2").as_ptr(), &mut json);
+            assert_eq!(code, MeiliErrorCode::Ok);
+            assert!(json.is_null());
+
+            let code = meili_get_document(client, c("movies").as_ptr(), c("1").as_ptr(), &mut json);
+            assert_eq!(code, MeiliErrorCode::Ok);
+            assert_eq!(take_json(json), json!({ "id": 1, "title": "Carol" }));
+
+            meili_client_free(client);
+        }
+    }
+
+    #[test]
+    fn error_codes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = c(dir.path().to_str().unwrap());
+
+        unsafe {
+            let mut client = ptr::null_mut();
+            assert_eq!(meili_client_open(path.as_ptr(), &mut client), MeiliErrorCode::Ok);
+            assert!(meili_last_error_message().is_null());
+
+            let mut json = ptr::null_mut();
+            let code = meili_search(client, c("movies").as_ptr(), c("carol").as_ptr(), ptr::null(), &mut json);
+            assert_eq!(code, MeiliErrorCode::IndexNotFound);
+            let message = CStr::from_ptr(meili_last_error_message()).to_str().unwrap();
+            assert_eq!(message, "index movies not found");
+
+            let mut update_id = 0;
+            let code = meili_add_documents(client, c("movies").as_ptr(), c("{").as_ptr(), false, &mut update_id);
+            assert_eq!(code, MeiliErrorCode::InvalidJson);
+
+            let code = meili_add_documents(client, c("movies").as_ptr(), ptr::null(), false, &mut update_id);
+            assert_eq!(code, MeiliErrorCode::NullArgument);
+
+            let code = meili_add_documents(client, c("movies").as_ptr(), c(r#"[{ "title": "Carol" }]"#).as_ptr(), false, &mut update_id);
+            assert_eq!(code, MeiliErrorCode::PrimaryKeyInference);
+
+            let code = meili_search(ptr::null(), c("movies").as_ptr(), c("carol").as_ptr(), ptr::null(), &mut json);
+            assert_eq!(code, MeiliErrorCode::NullArgument);
+
+            meili_client_free(client);
+        }
+    }
+}
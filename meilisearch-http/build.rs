@@ -7,4 +7,7 @@ fn main() {
 
     // Generate the 'cargo:' key output
     generate_cargo_keys(ConstantsFlags::all()).expect("Unable to generate the cargo keys!");
+
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/meilisearch.proto").expect("Unable to compile the protobuf definitions!");
 }
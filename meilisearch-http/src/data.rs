@@ -1,18 +1,27 @@
-use std::collections::HashMap;
 use std::ops::Deref;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 use chrono::{DateTime, Utc};
 use heed::types::{SerdeBincode, Str};
-use log::error;
-use meilisearch_core::{Database, DatabaseOptions, Error as MError, MResult, MainT, UpdateT};
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use meilisearch_core::{Database, DatabaseOptions, Index, MResult, MainT, UpdateQueueLimits, UpdateT, UpdatesRetention};
+use meilisearch_core::store::SearchRecord;
 use sha2::Digest;
 use sysinfo::Pid;
 
+use crate::dump;
+use crate::error::ResponseError;
 use crate::index_update_callback;
 use crate::option::Opt;
+use crate::helpers::uploads;
+use crate::standby::{Shipper, Standby};
+use crate::webhook;
 
 const LAST_UPDATE_KEY: &str = "last-update";
+const SCOPED_KEYS_KEY: &str = "scoped-api-keys";
 
 type SerdeDatetime = SerdeBincode<DateTime<Utc>>;
 
@@ -35,6 +44,15 @@ pub struct DataInner {
     pub db_path: String,
     pub api_keys: ApiKeys,
     pub server_pid: Pid,
+    pub http_payload_size_limit: usize,
+    pub http_payload_document_size_limit: usize,
+    pub import_allow_private_networks: bool,
+    pub search_analytics_capacity: Option<usize>,
+    pub shipper: Option<Arc<Shipper>>,
+    pub standby: Option<Arc<Standby>>,
+    pub enable_graphql: bool,
+    pub snapshot_dir: PathBuf,
+    pub dumps_dir: PathBuf,
 }
 
 #[derive(Clone)]
@@ -61,6 +79,37 @@ impl ApiKeys {
     }
 }
 
+/// An API key restricted to some indexes and to some classes of actions on them,
+/// it is created with the master key and stored in the database.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopedKey {
+    pub key: String,
+    pub description: Option<String>,
+    /// The uids of the indexes the key gives access to, `*` stands for all of them.
+    pub indexes: Vec<String>,
+    pub actions: Vec<KeyAction>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyAction {
+    /// Searching and reading the documents.
+    Search,
+    /// Adding, updating and deleting the documents.
+    DocumentsWrite,
+    /// Reading and updating the settings.
+    SettingsWrite,
+}
+
+impl ScopedKey {
+    pub fn grants(&self, action: KeyAction, index_uid: &str) -> bool {
+        self.actions.contains(&action)
+            && self.indexes.iter().any(|uid| uid == "*" || uid == index_uid)
+    }
+}
+
 impl DataInner {
     pub fn is_indexing(&self, reader: &heed::RoTxn<UpdateT>, index: &str) -> MResult<Option<bool>> {
         match self.db.open_index(&index) {
@@ -69,6 +118,46 @@ impl DataInner {
         }
     }
 
+    /// Refuses the updates while the instance is a standby that has not been promoted.
+    pub fn check_writable(&self) -> Result<(), ResponseError> {
+        match self.standby {
+            Some(ref standby) if !standby.is_promoted() => Err(ResponseError::ReadOnlyStandby),
+            _ => Ok(()),
+        }
+    }
+
+    /// Refuses new updates when the update queue of the index is full.
+    pub fn check_update_queue(&self, reader: &heed::RoTxn<UpdateT>, index: &Index) -> Result<(), ResponseError> {
+        self.check_writable()?;
+        index.check_update_queue(reader)?;
+        Ok(())
+    }
+
+    /// Records the search in the analytics of the index when they are enabled, the search
+    /// is not impacted by a failure to record it.
+    pub fn record_search(&self, index: &Index, nb_hits: usize, processing_time_ms: usize, query: &str) {
+        let capacity = match self.search_analytics_capacity {
+            Some(capacity) if !self.db.is_read_only() => capacity,
+            _ => return,
+        };
+
+        let record = SearchRecord {
+            query: query.trim().to_lowercase(),
+            nb_hits,
+            processing_time_ms,
+            searched_at: Utc::now(),
+        };
+
+        let result = self.db.update_write_txn().and_then(|mut writer| {
+            index.search_analytics.record(&mut writer, &record, capacity)?;
+            writer.commit().map_err(Into::into)
+        });
+
+        if let Err(e) = result {
+            warn!("Impossible to record the search in the analytics; {}", e);
+        }
+    }
+
     pub fn last_update(&self, reader: &heed::RoTxn<MainT>) -> MResult<Option<DateTime<Utc>>> {
         match self
             .db
@@ -87,43 +176,19 @@ impl DataInner {
             .map_err(Into::into)
     }
 
-    pub fn compute_stats(&self, writer: &mut heed::RwTxn<MainT>, index_uid: &str) -> MResult<()> {
-        let index = match self.db.open_index(&index_uid) {
-            Some(index) => index,
-            None => {
-                error!("Impossible to retrieve index {}", index_uid);
-                return Ok(());
-            }
-        };
-
-        let schema = match index.main.schema(&writer)? {
-            Some(schema) => schema,
-            None => return Ok(()),
-        };
-
-        let all_documents_fields = index
-            .documents_fields_counts
-            .all_documents_fields_counts(&writer)?;
-
-        // count fields frequencies
-        let mut fields_frequency = HashMap::<_, usize>::new();
-        for result in all_documents_fields {
-            let (_, attr, _) = result?;
-            if let Some(field_id) = schema.indexed_pos_to_field_id(attr) {
-                *fields_frequency.entry(field_id).or_default() += 1;
-            }
-        }
-
-        // convert attributes to their names
-        let frequency: HashMap<_, _> = fields_frequency
-            .into_iter()
-            .filter_map(|(a, c)| schema.name(a).map(|name| (name.to_string(), c)))
-            .collect();
+    pub fn scoped_keys(&self, reader: &heed::RoTxn<MainT>) -> MResult<Vec<ScopedKey>> {
+        let keys = self
+            .db
+            .common_store()
+            .get::<_, Str, SerdeBincode<Vec<ScopedKey>>>(reader, SCOPED_KEYS_KEY)?;
+        Ok(keys.unwrap_or_default())
+    }
 
-        index
-            .main
-            .put_fields_frequency(writer, &frequency)
-            .map_err(MError::Zlmdb)
+    pub fn put_scoped_keys(&self, writer: &mut heed::RwTxn<MainT>, keys: Vec<ScopedKey>) -> MResult<()> {
+        self.db
+            .common_store()
+            .put::<_, Str, SerdeBincode<Vec<ScopedKey>>>(writer, SCOPED_KEYS_KEY, &keys)
+            .map_err(Into::into)
     }
 }
 
@@ -135,10 +200,71 @@ impl Data {
         let db_opt = DatabaseOptions {
             main_map_size: opt.main_map_size,
             update_map_size: opt.update_map_size,
+            max_main_map_size: opt.max_main_map_size,
+            max_update_map_size: opt.max_update_map_size,
+            updates_retention: UpdatesRetention {
+                max_entries: opt.updates_retention_max_entries,
+                max_age: opt.updates_retention_max_age_sec.map(|sec| chrono::Duration::seconds(sec as i64)),
+            },
+            update_queue_limits: UpdateQueueLimits {
+                max_pending_updates: opt.max_enqueued_updates,
+                max_pending_bytes: opt.max_enqueued_updates_size,
+            },
+            expiration_purge_interval: Duration::from_secs(opt.expiration_purge_interval_sec),
+            read_only: false,
         };
 
+        // the snapshot must be restored before the environments are opened
+        let standby = opt
+            .standby_of
+            .as_ref()
+            .map(|ship_dir| Arc::new(Standby::prepare(ship_dir, &opt.db_path).unwrap()));
+
+        if let Some(ref snapshot_path) = opt.import_snapshot {
+            if Path::new(&opt.db_path).join("main").exists() {
+                warn!("The snapshot {} is not imported, the database already exists", snapshot_path);
+            } else {
+                Database::restore_snapshot(snapshot_path, &opt.db_path).unwrap();
+            }
+        }
+
         let db = Arc::new(Database::open_or_create(opt.db_path, db_opt).unwrap());
 
+        if let Some(ref dump_path) = opt.import_dump {
+            if db.indexes_uids().is_empty() {
+                dump::import_dump(&db, dump_path).unwrap();
+            } else {
+                warn!("The dump {} is not imported, the database already contains indexes", dump_path);
+            }
+        }
+
+        let shipper = opt
+            .standby_ship_dir
+            .as_ref()
+            .map(|ship_dir| Arc::new(Shipper::new(ship_dir).unwrap()));
+
+        if let Some(ref shipper) = shipper {
+            let shipper_clone = shipper.clone();
+            db.set_update_log_callback(Box::new(move |entry| {
+                if let Err(e) = shipper_clone.append(entry) {
+                    error!("Impossible to ship the update log entry; {}", e);
+                }
+            }));
+
+            let interval = Duration::from_secs(opt.standby_snapshot_interval_sec);
+            shipper.clone().spawn_snapshots(db.clone(), interval);
+        }
+
+        if let Some(ref standby) = standby {
+            standby.clone().spawn_replication(db.clone());
+        }
+
+        uploads::spawn_stale_uploads_removal(db_path.clone(), Duration::from_secs(opt.uploads_ttl_sec));
+
+        if let Some(url) = opt.update_webhook_url {
+            webhook::spawn_webhook(&db, url);
+        }
+
         let mut api_keys = ApiKeys {
             master: opt.master_key,
             private: None,
@@ -152,6 +278,15 @@ impl Data {
             db_path,
             api_keys,
             server_pid,
+            http_payload_size_limit: opt.http_payload_size_limit,
+            http_payload_document_size_limit: opt.http_payload_document_size_limit,
+            import_allow_private_networks: opt.import_allow_private_networks,
+            search_analytics_capacity: opt.search_analytics_capacity,
+            shipper,
+            standby,
+            enable_graphql: opt.enable_graphql,
+            snapshot_dir: PathBuf::from(opt.snapshot_dir),
+            dumps_dir: PathBuf::from(opt.dumps_dir),
         };
 
         let data = Data {
@@ -0,0 +1,234 @@
+//! A dump is a gzipped tarball of JSON files independent of the LMDB and bincode formats
+//! of the stores, it moves the indexes to a database created by another version:
+//!  - `metadata.json`: the version of the dump and of MeiliSearch along with the indexes uids,
+//!  - `<index uid>/metadata.json`: the name, primary key and settings of the index,
+//!  - `<index uid>/documents.jsonl`: one document per line,
+//!  - `<index uid>/updates.jsonl`: one enqueued update per line, with the id it is replayed under.
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use indexmap::IndexMap;
+use log::info;
+use meilisearch_core::query_rules::QueryRule;
+use meilisearch_core::settings::{LocalizedSynonyms, Settings, UpdateState};
+use meilisearch_core::update::{self, Update};
+use meilisearch_core::{AdditionOptions, Database, Error, Index, MResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::routes::setting::read_settings;
+
+const DUMP_VERSION: &str = "1";
+const METADATA_FILE: &str = "metadata.json";
+const DOCUMENTS_FILE: &str = "documents.jsonl";
+const UPDATES_FILE: &str = "updates.jsonl";
+/// The number of documents added to an index in one transaction during an import.
+const IMPORT_BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpMetadata {
+    pub dump_version: String,
+    pub db_version: String,
+    pub created_at: DateTime<Utc>,
+    pub indexes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexMetadata {
+    name: String,
+    primary_key: Option<String>,
+    language: Option<String>,
+    localized_synonyms: Option<LocalizedSynonyms>,
+    query_rules: Option<Vec<QueryRule>>,
+    settings: Settings,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DumpedUpdate {
+    update_id: u64,
+    update: Update,
+}
+
+fn invalid_dump(message: impl fmt::Display) -> Error {
+    Error::Io(io::Error::new(io::ErrorKind::InvalidData, message.to_string()))
+}
+
+fn dump_path(dumps_dir: &Path, uid: &str) -> PathBuf {
+    dumps_dir.join(format!("{}.tar.gz", uid))
+}
+
+/// Writes a dump of every index in the dumps directory and returns its uid,
+/// the dump is named `<uid>.tar.gz`.
+pub fn create_dump(db: &Database, dumps_dir: impl AsRef<Path>) -> MResult<String> {
+    let dumps_dir = dumps_dir.as_ref();
+    let uid = Utc::now().format("%Y%m%d-%H%M%S%3f").to_string();
+
+    let tmp_dir = dumps_dir.join(format!("{}.tmp", uid));
+    fs::create_dir_all(&tmp_dir)?;
+    let result = write_dump(db, &tmp_dir).and_then(|()| {
+        let path = dump_path(dumps_dir, &uid);
+        let tmp_path = path.with_extension("tmp.gz");
+
+        let file = File::create(&tmp_path)?;
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        builder.append_dir_all(".", &tmp_dir)?;
+        builder.into_inner()?.finish()?.sync_all()?;
+
+        fs::rename(&tmp_path, &path)?;
+        Ok(())
+    });
+    fs::remove_dir_all(&tmp_dir)?;
+    result?;
+
+    info!("dump {} created in {:?}", uid, dumps_dir);
+
+    Ok(uid)
+}
+
+fn write_dump(db: &Database, dir: &Path) -> MResult<()> {
+    let (reader, update_reader) = db.consistent_read_txns()?;
+
+    let mut indexes_uids = db.indexes_uids();
+    indexes_uids.sort();
+
+    for index_uid in &indexes_uids {
+        let index = match db.open_index(index_uid) {
+            Some(index) => index,
+            None => continue,
+        };
+
+        let index_dir = dir.join(index_uid);
+        fs::create_dir_all(&index_dir)?;
+
+        let schema = index.main.schema(&reader)?;
+        let metadata = IndexMetadata {
+            name: index.main.name(&reader)?.unwrap_or_else(|| index_uid.clone()),
+            primary_key: schema.as_ref().and_then(|schema| schema.primary_key().map(str::to_owned)),
+            language: index.main.language(&reader)?,
+            localized_synonyms: index.main.localized_synonyms(&reader)?,
+            query_rules: index.main.query_rules(&reader)?,
+            settings: read_settings(&index, &reader)?,
+        };
+        fs::write(index_dir.join(METADATA_FILE), serde_json::to_vec(&metadata)?)?;
+
+        let mut documents = BufWriter::new(File::create(index_dir.join(DOCUMENTS_FILE))?);
+        // the indexes without schema don't have any document
+        if schema.is_some() {
+            for result in index.all_documents_iter(&reader)? {
+                let (_user_id, document) = result?;
+                serde_json::to_writer(&mut documents, &document)?;
+                documents.write_all(b"\n")?;
+            }
+        }
+        documents.flush()?;
+
+        let mut updates = BufWriter::new(File::create(index_dir.join(UPDATES_FILE))?);
+        for (update_id, update) in index.updates.enqueued_updates(&update_reader)? {
+            serde_json::to_writer(&mut updates, &DumpedUpdate { update_id, update })?;
+            updates.write_all(b"\n")?;
+        }
+        updates.flush()?;
+    }
+
+    let metadata = DumpMetadata {
+        dump_version: DUMP_VERSION.to_string(),
+        db_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: Utc::now(),
+        indexes: indexes_uids,
+    };
+    fs::write(dir.join(METADATA_FILE), serde_json::to_vec(&metadata)?)?;
+
+    Ok(())
+}
+
+/// Replays a dump into a database that doesn't contain any index, the enqueued updates
+/// of the dump are enqueued again and processed once the import is done.
+pub fn import_dump(db: &Database, path: impl AsRef<Path>) -> MResult<()> {
+    let path = path.as_ref();
+    if !db.indexes_uids().is_empty() {
+        return Err(invalid_dump("a dump can only be imported in a database without indexes"));
+    }
+
+    let tmp_dir = tempfile::tempdir()?;
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(path)?));
+    archive.unpack(tmp_dir.path())?;
+
+    let metadata: DumpMetadata = serde_json::from_slice(&fs::read(tmp_dir.path().join(METADATA_FILE))?)?;
+    if metadata.dump_version != DUMP_VERSION {
+        return Err(invalid_dump(format!("unsupported dump version {}", metadata.dump_version)));
+    }
+
+    for index_uid in &metadata.indexes {
+        let index = db.create_index(index_uid)?;
+        import_index(db, &index, &tmp_dir.path().join(index_uid))?;
+    }
+
+    info!(
+        "dump {:?} created by MeiliSearch {} imported, {} indexes",
+        path,
+        metadata.db_version,
+        metadata.indexes.len()
+    );
+
+    Ok(())
+}
+
+fn import_index(db: &Database, index: &Index, dir: &Path) -> MResult<()> {
+    let metadata: IndexMetadata = serde_json::from_slice(&fs::read(dir.join(METADATA_FILE))?)?;
+
+    let mut settings = metadata.settings.into_update().map_err(invalid_dump)?;
+    settings.language = metadata.language.map_or(UpdateState::Nothing, UpdateState::Update);
+    settings.localized_synonyms = metadata.localized_synonyms.map_or(UpdateState::Nothing, UpdateState::Update);
+
+    let mut writer = db.main_write_txn()?;
+    index.main.put_name(&mut writer, &metadata.name)?;
+    if let Some(primary_key) = metadata.primary_key {
+        let mut schema = index.main.schema(&writer)?.ok_or(Error::SchemaMissing)?;
+        schema.set_primary_key(&primary_key)?;
+        index.main.put_schema(&mut writer, &schema)?;
+    }
+    update::apply_settings_update(&mut writer, index, settings)?;
+    if let Some(query_rules) = metadata.query_rules {
+        index.main.put_query_rules(&mut writer, &query_rules)?;
+    }
+    writer.commit()?;
+
+    let documents = BufReader::new(File::open(dir.join(DOCUMENTS_FILE))?);
+    let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+    for line in documents.lines() {
+        batch.push(serde_json::from_str(&line?)?);
+        if batch.len() == IMPORT_BATCH_SIZE {
+            import_documents(db, index, std::mem::take(&mut batch))?;
+        }
+    }
+    if !batch.is_empty() {
+        import_documents(db, index, batch)?;
+    }
+
+    let updates = BufReader::new(File::open(dir.join(UPDATES_FILE))?);
+    let mut writer = db.update_write_txn()?;
+    for line in updates.lines() {
+        let DumpedUpdate { update_id, update } = serde_json::from_str(&line?)?;
+        index.replicate_update(&mut writer, update_id, update)?;
+    }
+    writer.commit()?;
+
+    Ok(())
+}
+
+fn import_documents(db: &Database, index: &Index, documents: Vec<IndexMap<String, Value>>) -> MResult<()> {
+    let mut writer = db.main_write_txn()?;
+    update::apply_documents_addition(&mut writer, index, documents, AdditionOptions::default())?;
+    writer.commit()?;
+    Ok(())
+}
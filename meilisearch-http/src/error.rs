@@ -6,6 +6,9 @@ use actix_web::http::StatusCode;
 use serde_json::json;
 use actix_web::error::JsonPayloadError;
 
+/// The number of seconds clients are asked to wait when the update queue is full.
+const UPDATE_QUEUE_RETRY_AFTER_SECS: u64 = 5;
+
 #[derive(Debug)]
 pub enum ResponseError {
     BadParameter(String, String),
@@ -28,6 +31,8 @@ pub enum ResponseError {
     UnsupportedMediaType,
     FacetExpression(String),
     FacetCount(String),
+    UpdateQueueFull(meilisearch_core::QueueLimit),
+    ReadOnlyStandby,
 }
 
 pub enum FacetCountError {
@@ -148,13 +153,19 @@ impl fmt::Display for ResponseError {
             Self::PayloadTooLarge => f.write_str("Payload to large"),
             Self::UnsupportedMediaType => f.write_str("Unsupported media type"),
             Self::FacetCount(e) => write!(f, "error with facet count: {}", e),
+            Self::UpdateQueueFull(limit) => write!(f, "the update queue is full ({}), please try again later", limit),
+            Self::ReadOnlyStandby => f.write_str("this instance is a standby, it accepts updates once promoted"),
         }
     }
 }
 
 impl aweb::error::ResponseError for ResponseError {
     fn error_response(&self) -> aweb::HttpResponse {
-        ResponseBuilder::new(self.status_code()).json(json!({
+        let mut builder = ResponseBuilder::new(self.status_code());
+        if let Self::UpdateQueueFull(_) = self {
+            builder.header("Retry-After", UPDATE_QUEUE_RETRY_AFTER_SECS.to_string());
+        }
+        builder.json(json!({
             "message": self.to_string(),
         }))
     }
@@ -178,9 +189,11 @@ impl aweb::error::ResponseError for ResponseError {
             | Self::MissingHeader(_) => StatusCode::UNAUTHORIZED,
             Self::MissingAuthorizationHeader => StatusCode::FORBIDDEN,
             Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            Self::Maintenance => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Maintenance
+            | Self::ReadOnlyStandby => StatusCode::SERVICE_UNAVAILABLE,
             Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
             Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::UpdateQueueFull(_) => StatusCode::TOO_MANY_REQUESTS,
         }
     }
 }
@@ -217,6 +230,9 @@ impl From<meilisearch_core::Error> for ResponseError {
                 ResponseError::FilterParsing(message)
             },
             meilisearch_core::Error::FacetError(e) => ResponseError::FacetExpression(e.to_string()),
+            meilisearch_core::Error::IndexNotFound(uid) => ResponseError::index_not_found(uid),
+            meilisearch_core::Error::AliasConflict(_) => ResponseError::BadRequest(err.to_string()),
+            meilisearch_core::Error::UpdateQueueFull(limit) => ResponseError::UpdateQueueFull(limit),
             _ => ResponseError::Internal(err.to_string()),
         }
     }
@@ -0,0 +1,329 @@
+//! The gRPC API, served alongside the HTTP one when MeiliSearch is compiled with the
+//! `grpc` feature and a gRPC address is given. It shares the authentication, the update
+//! queues limits and the standby restrictions of the HTTP API.
+
+use std::net::SocketAddr;
+
+use actix_web::error::ResponseError as HttpError;
+use actix_web::http::StatusCode;
+use meilisearch_core::settings::Settings;
+use meilisearch_core::{update, Index};
+use serde_json::{json, Map, Value};
+use tonic::transport::Server;
+use tonic::{Code, Request, Response, Status, Streaming};
+
+use crate::error::ResponseError;
+use crate::helpers::csv_documents::Document;
+use crate::helpers::json_stream::DocumentsStream;
+use crate::helpers::Authentication;
+use crate::routes::document::ensure_primary_key;
+use crate::routes::search::SearchQuery;
+use crate::routes::setting::{push_settings, read_settings};
+use crate::Data;
+
+pub mod proto {
+    tonic::include_proto!("meilisearch");
+}
+
+use proto::meili_search_server::{MeiliSearch, MeiliSearchServer};
+use proto::{
+    DeleteDocumentsRequest, DocumentsChunk, IndexRequest, SearchRequest, SearchResponse, SettingsResponse,
+    UpdateResponse, UpdateSettingsRequest, UpdateStatusRequest, UpdateStatusResponse,
+};
+
+const API_KEY_METADATA: &str = "x-meili-api-key";
+
+/// Serves the gRPC API on the given address until the server fails.
+pub async fn serve(data: Data, addr: SocketAddr) -> Result<(), tonic::transport::Error> {
+    Server::builder()
+        .add_service(MeiliSearchServer::new(MeiliSearchService { data }))
+        .serve(addr)
+        .await
+}
+
+fn api_key<T>(request: &Request<T>) -> Option<&str> {
+    request
+        .metadata()
+        .get(API_KEY_METADATA)
+        .and_then(|key| key.to_str().ok())
+}
+
+impl From<ResponseError> for Status {
+    fn from(err: ResponseError) -> Status {
+        let code = match HttpError::status_code(&err) {
+            StatusCode::BAD_REQUEST | StatusCode::UNSUPPORTED_MEDIA_TYPE => Code::InvalidArgument,
+            StatusCode::NOT_FOUND => Code::NotFound,
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Code::Unauthenticated,
+            StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+            StatusCode::PAYLOAD_TOO_LARGE | StatusCode::TOO_MANY_REQUESTS => Code::ResourceExhausted,
+            _ => Code::Internal,
+        };
+        Status::new(code, err.to_string())
+    }
+}
+
+pub struct MeiliSearchService {
+    data: Data,
+}
+
+impl MeiliSearchService {
+    fn authorize<T>(&self, request: &Request<T>, acl: Authentication, index_uid: &str) -> Result<(), ResponseError> {
+        acl.authorize(&self.data, Some(index_uid), api_key(request))
+    }
+
+    fn open_index(&self, index_uid: &str) -> Result<Index, ResponseError> {
+        self.data
+            .db
+            .open_index(index_uid)
+            .ok_or(ResponseError::index_not_found(index_uid))
+    }
+
+    fn search_index(&self, request: SearchRequest) -> Result<SearchResponse, ResponseError> {
+        let index = self.open_index(&request.index_uid)?;
+        let query = search_query(request)?;
+
+        let reader = self.data.db.main_read_txn()?;
+        let result = query.search(&index, &reader)?;
+
+        let hits: Result<Vec<_>, _> = result.hits.iter().map(serde_json::to_vec).collect();
+        let facets = match result.facets {
+            Some(facets) => serde_json::to_vec(&facets).map_err(ResponseError::internal)?,
+            None => Vec::new(),
+        };
+
+        Ok(SearchResponse {
+            hits: hits.map_err(ResponseError::internal)?,
+            offset: result.offset as u64,
+            limit: result.limit as u64,
+            nb_hits: result.nb_hits as u64,
+            exhaustive_nb_hits: result.exhaustive_nb_hits,
+            processing_time_ms: result.processing_time_ms as u64,
+            query: result.query,
+            facets,
+        })
+    }
+
+    fn finalize_addition(
+        &self,
+        index: &Index,
+        addition: update::DocumentsAddition<Document>,
+    ) -> Result<u64, ResponseError> {
+        let mut update_writer = self.data.db.update_write_txn()?;
+        self.data.check_update_queue(&update_writer, index)?;
+        let update_id = addition.finalize(&mut update_writer)?;
+        update_writer.commit()?;
+
+        Ok(update_id)
+    }
+
+    fn delete_documents_by_id(&self, request: DeleteDocumentsRequest) -> Result<u64, ResponseError> {
+        let index = self.open_index(&request.index_uid)?;
+
+        let format = index.main.document_id_format(&self.data.db.main_read_txn()?)?.unwrap_or_default();
+
+        let mut writer = self.data.db.update_write_txn()?;
+        self.data.check_update_queue(&writer, &index)?;
+
+        let mut documents_deletion = index.documents_deletion();
+        for document_id in request.documents_ids {
+            let document_id = update::compute_document_id(&document_id, format).map_err(ResponseError::bad_request)?;
+            documents_deletion.delete_document_by_id(document_id);
+        }
+
+        let update_id = documents_deletion.finalize(&mut writer)?;
+        writer.commit()?;
+
+        Ok(update_id)
+    }
+
+    fn index_settings(&self, request: IndexRequest) -> Result<SettingsResponse, ResponseError> {
+        let index = self.open_index(&request.index_uid)?;
+        let reader = self.data.db.main_read_txn()?;
+        let settings = read_settings(&index, &reader)?;
+
+        Ok(SettingsResponse {
+            settings: serde_json::to_vec(&settings).map_err(ResponseError::internal)?,
+        })
+    }
+
+    fn update_index_settings(&self, request: UpdateSettingsRequest) -> Result<u64, ResponseError> {
+        let index = self.open_index(&request.index_uid)?;
+        let settings: Settings = serde_json::from_slice(&request.settings)
+            .map_err(|e| ResponseError::bad_request(format!("Invalid JSON: {}", e)))?;
+
+        push_settings(&self.data, &index, settings)
+    }
+
+    fn index_update_status(&self, request: UpdateStatusRequest) -> Result<UpdateStatusResponse, ResponseError> {
+        let index = self.open_index(&request.index_uid)?;
+        let reader = self.data.db.update_read_txn()?;
+
+        let status = index
+            .update_status(&reader, request.update_id)?
+            .ok_or_else(|| ResponseError::NotFound(format!("Update {} not found", request.update_id)))?;
+        let details = serde_json::to_value(status).map_err(ResponseError::internal)?;
+
+        Ok(UpdateStatusResponse {
+            status: details["status"].as_str().unwrap_or_default().to_string(),
+            details: serde_json::to_vec(&details).map_err(ResponseError::internal)?,
+        })
+    }
+}
+
+/// Converts the request into the parameters of the HTTP search route, the lists are
+/// given as comma separated attributes except the facets that are JSON encoded.
+fn search_query(request: SearchRequest) -> Result<SearchQuery, ResponseError> {
+    let mut params = Map::new();
+    params.insert("q".to_string(), json!(request.q));
+
+    let numbers = vec![
+        ("offset", request.offset),
+        ("limit", request.limit),
+        ("cropLength", request.crop_length),
+    ];
+    for (name, value) in numbers {
+        if let Some(value) = value {
+            params.insert(name.to_string(), json!(value));
+        }
+    }
+
+    let lists = vec![
+        ("attributesToRetrieve", request.attributes_to_retrieve),
+        ("attributesToCrop", request.attributes_to_crop),
+        ("attributesToHighlight", request.attributes_to_highlight),
+    ];
+    for (name, values) in lists {
+        if !values.is_empty() {
+            params.insert(name.to_string(), json!(values.join(",")));
+        }
+    }
+
+    if !request.filters.is_empty() {
+        params.insert("filters".to_string(), json!(request.filters));
+    }
+    if request.matches {
+        params.insert("matches".to_string(), json!(true));
+    }
+    if !request.facet_filters.is_empty() {
+        params.insert("facetFilters".to_string(), json!(request.facet_filters));
+    }
+    if !request.facets.is_empty() {
+        params.insert("facets".to_string(), json!(json!(request.facets).to_string()));
+    }
+
+    serde_json::from_value(Value::Object(params)).map_err(ResponseError::bad_request)
+}
+
+#[tonic::async_trait]
+impl MeiliSearch for MeiliSearchService {
+    async fn search(&self, request: Request<SearchRequest>) -> Result<Response<SearchResponse>, Status> {
+        self.authorize(&request, Authentication::Public, &request.get_ref().index_uid)?;
+        Ok(Response::new(self.search_index(request.into_inner())?))
+    }
+
+    /// Parses the documents while the chunks are received, like the HTTP route does
+    /// with the payload, the raw chunks are never buffered but the parsed documents are
+    /// kept in memory until the update is enqueued, hence the size limit.
+    async fn add_documents(
+        &self,
+        request: Request<Streaming<DocumentsChunk>>,
+    ) -> Result<Response<UpdateResponse>, Status> {
+        // the index is only known once the first chunk is received
+        let key = api_key(&request).map(ToOwned::to_owned);
+        let mut chunks = request.into_inner();
+
+        let first = chunks
+            .message()
+            .await?
+            .ok_or_else(|| Status::invalid_argument("no documents chunk was sent"))?;
+
+        Authentication::Documents.authorize(&self.data, Some(&first.index_uid), key.as_deref())?;
+
+        let index = self.open_index(&first.index_uid)?;
+        let primary_key = Some(first.primary_key).filter(|primary_key| !primary_key.is_empty());
+
+        let mut document_addition = if first.partial {
+            index.documents_partial_addition()
+        } else {
+            index.documents_addition()
+        };
+
+        // reject the update before receiving the documents if the queue is already full
+        {
+            let reader = self.data.db.update_read_txn().map_err(ResponseError::from)?;
+            self.data.check_update_queue(&reader, &index)?;
+        }
+
+        let mut documents_stream = DocumentsStream::new(self.data.http_payload_document_size_limit);
+        let mut primary_key_checked = false;
+        let mut payload_size = 0;
+        let mut bytes = first.documents;
+
+        loop {
+            payload_size += bytes.len();
+            if payload_size > self.data.http_payload_size_limit {
+                return Err(ResponseError::PayloadTooLarge.into());
+            }
+
+            for document in documents_stream.feed(&bytes)? {
+                if !primary_key_checked {
+                    ensure_primary_key(&self.data, &index, &primary_key, Some(&document), false)?;
+                    primary_key_checked = true;
+                }
+                document_addition.update_document(document);
+            }
+
+            bytes = match chunks.message().await? {
+                // the caller is only authorized on the index of the first chunk
+                Some(chunk) if !chunk.index_uid.is_empty() && chunk.index_uid != first.index_uid => {
+                    return Err(Status::invalid_argument(format!(
+                        "all the documents chunks must target the index {}",
+                        first.index_uid
+                    )));
+                }
+                Some(chunk) => chunk.documents,
+                None => break,
+            };
+        }
+        documents_stream.finish()?;
+
+        if !primary_key_checked {
+            ensure_primary_key(&self.data, &index, &primary_key, None, false)?;
+        }
+
+        let update_id = self.finalize_addition(&index, document_addition)?;
+
+        Ok(Response::new(UpdateResponse { update_id }))
+    }
+
+    async fn delete_documents(
+        &self,
+        request: Request<DeleteDocumentsRequest>,
+    ) -> Result<Response<UpdateResponse>, Status> {
+        self.authorize(&request, Authentication::Documents, &request.get_ref().index_uid)?;
+        let update_id = self.delete_documents_by_id(request.into_inner())?;
+        Ok(Response::new(UpdateResponse { update_id }))
+    }
+
+    async fn get_settings(&self, request: Request<IndexRequest>) -> Result<Response<SettingsResponse>, Status> {
+        self.authorize(&request, Authentication::Settings, &request.get_ref().index_uid)?;
+        Ok(Response::new(self.index_settings(request.into_inner())?))
+    }
+
+    async fn update_settings(
+        &self,
+        request: Request<UpdateSettingsRequest>,
+    ) -> Result<Response<UpdateResponse>, Status> {
+        self.authorize(&request, Authentication::Settings, &request.get_ref().index_uid)?;
+        let update_id = self.update_index_settings(request.into_inner())?;
+        Ok(Response::new(UpdateResponse { update_id }))
+    }
+
+    async fn get_update_status(
+        &self,
+        request: Request<UpdateStatusRequest>,
+    ) -> Result<Response<UpdateStatusResponse>, Status> {
+        self.authorize(&request, Authentication::Updates, &request.get_ref().index_uid)?;
+        Ok(Response::new(self.index_update_status(request.into_inner())?))
+    }
+}
@@ -7,6 +7,7 @@ use actix_service::{Service, Transform};
 use actix_web::{dev::ServiceRequest, dev::ServiceResponse, Error};
 use futures::future::{err, ok, Future, Ready};
 
+use crate::data::{DataInner, KeyAction};
 use crate::error::ResponseError;
 use crate::Data;
 
@@ -15,6 +16,12 @@ pub enum Authentication {
     Public,
     Private,
     Admin,
+    /// The routes writing the documents of an index.
+    Documents,
+    /// The routes reading and writing the settings of an index.
+    Settings,
+    /// The routes following the updates of an index.
+    Updates,
 }
 
 impl<S: 'static, B> Transform<S> for Authentication
@@ -64,39 +71,81 @@ where
         // it means that actix-web has an issue or someone changes the type `Data`.
         let data = req.app_data::<Data>().unwrap();
 
-        if data.api_keys.master.is_none() {
-            return Box::pin(svc.call(req));
+        // a header that is not valid UTF-8 is considered missing
+        let auth_header = req
+            .headers()
+            .get("X-Meili-API-Key")
+            .and_then(|auth| auth.to_str().ok());
+
+        // the scoped keys can only be checked on the routes of an index
+        let index_uid = req.match_info().get("index_uid");
+
+        match self.acl.authorize(&data, index_uid, auth_header) {
+            Ok(()) => Box::pin(svc.call(req)),
+            Err(e) => Box::pin(err(e.into())),
         }
+    }
+}
 
-        let auth_header = match req.headers().get("X-Meili-API-Key") {
-            Some(auth) => match auth.to_str() {
-                Ok(auth) => auth,
-                Err(_) => return Box::pin(err(ResponseError::MissingAuthorizationHeader.into())),
-            },
-            None => {
-                return Box::pin(err(ResponseError::MissingAuthorizationHeader.into()));
-            }
-        };
+impl Authentication {
+    /// Checks that the given API key grants this level of access, any request is
+    /// authorized when no master key is set. The scoped keys are only looked up in
+    /// the database when the key is not one of the master, private or public keys.
+    pub fn authorize(
+        &self,
+        data: &DataInner,
+        index_uid: Option<&str>,
+        key: Option<&str>,
+    ) -> Result<(), ResponseError> {
+        let api_keys = &data.api_keys;
+        if api_keys.master.is_none() {
+            return Ok(());
+        }
 
-        let authenticated = match self.acl {
-            Authentication::Admin => data.api_keys.master.as_deref() == Some(auth_header),
-            Authentication::Private => {
-                data.api_keys.master.as_deref() == Some(auth_header)
-                    || data.api_keys.private.as_deref() == Some(auth_header)
-            }
-            Authentication::Public => {
-                data.api_keys.master.as_deref() == Some(auth_header)
-                    || data.api_keys.private.as_deref() == Some(auth_header)
-                    || data.api_keys.public.as_deref() == Some(auth_header)
-            }
+        let key = key.ok_or(ResponseError::MissingAuthorizationHeader)?;
+
+        let is_master = api_keys.master.as_deref() == Some(key);
+        let is_private = is_master || api_keys.private.as_deref() == Some(key);
+        let is_public = is_private || api_keys.public.as_deref() == Some(key);
+
+        let authenticated = match self {
+            Authentication::Admin => is_master,
+            Authentication::Private
+            | Authentication::Documents
+            | Authentication::Settings
+            | Authentication::Updates => is_private,
+            Authentication::Public => is_public,
         };
 
         if authenticated {
-            Box::pin(svc.call(req))
-        } else {
-            Box::pin(err(
-                ResponseError::InvalidToken(auth_header.to_string()).into()
-            ))
+            return Ok(());
+        }
+
+        let actions = self.scoped_actions();
+        if let Some(index_uid) = index_uid.filter(|_| !actions.is_empty()) {
+            let reader = data.db.main_read_txn()?;
+            let scoped_keys = data.scoped_keys(&reader)?;
+            let granted = scoped_keys
+                .iter()
+                .filter(|scoped| scoped.key == key)
+                .any(|scoped| actions.iter().any(|action| scoped.grants(*action, index_uid)));
+
+            if granted {
+                return Ok(());
+            }
+        }
+
+        Err(ResponseError::InvalidToken(key.to_string()))
+    }
+
+    /// The actions of which one must be granted to a scoped key for it to be accepted.
+    fn scoped_actions(&self) -> &'static [KeyAction] {
+        match self {
+            Authentication::Public => &[KeyAction::Search],
+            Authentication::Documents => &[KeyAction::DocumentsWrite],
+            Authentication::Settings => &[KeyAction::SettingsWrite],
+            Authentication::Updates => &[KeyAction::DocumentsWrite, KeyAction::SettingsWrite],
+            Authentication::Private | Authentication::Admin => &[],
         }
     }
 }
@@ -0,0 +1,168 @@
+use std::io::Read;
+use std::str::FromStr;
+
+use indexmap::IndexMap;
+use serde_json::{Number, Value};
+
+use crate::error::ResponseError;
+
+pub type Document = IndexMap<String, Value>;
+
+pub const DEFAULT_CSV_DELIMITER: u8 = b',';
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ColumnType {
+    String,
+    Number,
+    Boolean,
+}
+
+impl FromStr for ColumnType {
+    type Err = ResponseError;
+
+    fn from_str(s: &str) -> Result<ColumnType, ResponseError> {
+        match s.trim() {
+            "string" => Ok(ColumnType::String),
+            "number" => Ok(ColumnType::Number),
+            "boolean" => Ok(ColumnType::Boolean),
+            other => Err(ResponseError::bad_request(format!(
+                "unknown column type `{}`, expected one of string, number or boolean",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Column {
+    name: String,
+    kind: Option<ColumnType>,
+}
+
+/// A header is either a plain `name` or a typed `name:type` column.
+fn parse_header(header: &str) -> Result<Column, ResponseError> {
+    match header.rfind(':') {
+        Some(pos) => {
+            let name = header[..pos].trim().to_string();
+            let kind = header[pos + 1..].parse()?;
+            Ok(Column { name, kind: Some(kind) })
+        }
+        None => {
+            let name = header.trim().to_string();
+            Ok(Column { name, kind: None })
+        }
+    }
+}
+
+/// Infers the type of an untyped column from its non-empty fields: a number if they all
+/// are numbers, a boolean if they all are booleans and a string otherwise.
+fn infer_column_type(records: &[csv::StringRecord], index: usize) -> ColumnType {
+    let mut fields = records
+        .iter()
+        .filter_map(|record| record.get(index))
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .peekable();
+
+    if fields.peek().is_none() {
+        return ColumnType::String;
+    }
+
+    let mut is_number = true;
+    let mut is_boolean = true;
+    for field in fields {
+        is_number &= Number::from_str(field).is_ok();
+        is_boolean &= field.eq_ignore_ascii_case("true") || field.eq_ignore_ascii_case("false");
+        if !is_number && !is_boolean {
+            return ColumnType::String;
+        }
+    }
+
+    if is_number { ColumnType::Number } else { ColumnType::Boolean }
+}
+
+fn parse_field(column: &Column, kind: ColumnType, field: &str) -> Result<Value, ResponseError> {
+    match kind {
+        ColumnType::String => Ok(Value::String(field.to_string())),
+        ColumnType::Number => {
+            let field = field.trim();
+            if field.is_empty() {
+                return Ok(Value::Null);
+            }
+            Number::from_str(field).map(Value::Number).map_err(|_| {
+                ResponseError::bad_request(format!(
+                    "impossible to parse `{}` as a number in column `{}`",
+                    field, column.name
+                ))
+            })
+        }
+        ColumnType::Boolean => {
+            let field = field.trim();
+            if field.is_empty() {
+                return Ok(Value::Null);
+            }
+            match field.to_lowercase().as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                _ => Err(ResponseError::bad_request(format!(
+                    "impossible to parse `{}` as a boolean in column `{}`",
+                    field, column.name
+                ))),
+            }
+        }
+    }
+}
+
+/// Converts the CSV delimiter given by the user into the byte expected by the csv reader.
+pub fn csv_delimiter(delimiter: Option<char>) -> Result<u8, ResponseError> {
+    match delimiter {
+        Some(c) if c.is_ascii() && c != '"' && c != '\n' && c != '\r' => Ok(c as u8),
+        Some(c) => Err(ResponseError::bad_parameter(
+            "csvDelimiter",
+            format!("`{}` is not a valid delimiter, it must be a single ASCII character", c),
+        )),
+        None => Ok(DEFAULT_CSV_DELIMITER),
+    }
+}
+
+/// Reads the CSV rows as documents. Untyped columns are considered as strings unless
+/// `infer_types` is set, in which case their type is inferred from their fields.
+pub fn read_csv_documents<R: Read>(reader: R, delimiter: u8, infer_types: bool) -> Result<Vec<Document>, ResponseError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_reader(reader);
+
+    let columns = reader
+        .headers()
+        .map_err(|e| ResponseError::bad_request(format!("Invalid CSV: {}", e)))?
+        .iter()
+        .map(parse_header)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let records = reader
+        .records()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ResponseError::bad_request(format!("Invalid CSV: {}", e)))?;
+
+    let kinds: Vec<_> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| match column.kind {
+            Some(kind) => kind,
+            None if infer_types => infer_column_type(&records, i),
+            None => ColumnType::String,
+        })
+        .collect();
+
+    let mut documents = Vec::with_capacity(records.len());
+    for record in records {
+        let mut document = Document::with_capacity(columns.len());
+        for ((column, kind), field) in columns.iter().zip(&kinds).zip(record.iter()) {
+            let value = parse_field(column, *kind, field)?;
+            document.insert(column.name.clone(), value);
+        }
+        documents.push(document);
+    }
+
+    Ok(documents)
+}
@@ -0,0 +1,353 @@
+use std::io::{self, BufRead, BufReader, Read};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, ToSocketAddrs};
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use url::{Host, Url};
+
+use crate::error::ResponseError;
+use crate::helpers::csv_documents::{read_csv_documents, Document};
+use crate::helpers::json_stream::DocumentsStream;
+
+/// The maximum number of bytes fetched from a remote documents file.
+pub const MAX_IMPORT_SIZE: u64 = 100 * 1024 * 1024;
+
+const CONNECT_TIMEOUT_MS: u64 = 10_000;
+const READ_TIMEOUT_MS: u64 = 60_000;
+const MAX_REDIRECTS: usize = 5;
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Json,
+    Ndjson,
+    Csv,
+}
+
+impl ImportFormat {
+    /// Guesses the format of a remote file from its content type, then from its extension.
+    pub fn infer(url: &str, content_type: Option<&str>) -> Option<ImportFormat> {
+        let content_type = content_type.map(|c| c.split(';').next().unwrap_or("").trim());
+        match content_type {
+            Some("application/json") => return Some(ImportFormat::Json),
+            Some("application/x-ndjson") | Some("application/ndjson") => return Some(ImportFormat::Ndjson),
+            Some("text/csv") => return Some(ImportFormat::Csv),
+            _ => (),
+        }
+
+        let path = url.split(|c| c == '?' || c == '#').next().unwrap_or(url);
+        let extension = path.rsplit('.').next().unwrap_or("").to_lowercase();
+        match extension.as_str() {
+            "json" => Some(ImportFormat::Json),
+            "ndjson" | "jsonl" => Some(ImportFormat::Ndjson),
+            "csv" => Some(ImportFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// A documents file the server fetches from a remote location.
+pub struct RemoteImport {
+    pub url: String,
+    pub format: Option<ImportFormat>,
+    pub sha256: Option<String>,
+    pub csv_delimiter: u8,
+    pub infer_types: bool,
+    pub max_size: u64,
+    pub max_document_size: usize,
+    /// Allows the url to target loopback, private and link-local addresses.
+    pub allow_private_networks: bool,
+}
+
+impl RemoteImport {
+    /// Downloads the file and reads its documents while it is received, the file itself is
+    /// never entirely kept in memory. The checksum is verified once the file has been read.
+    ///
+    /// This function is blocking and must not be called on the actix executor.
+    pub fn fetch_documents(&self) -> Result<Vec<Document>, ResponseError> {
+        let (url, response) = self.get()?;
+
+        let declared_size = response.header("Content-Length").and_then(|l| l.parse::<u64>().ok());
+        if declared_size.map_or(false, |size| size > self.max_size) {
+            return Err(ResponseError::PayloadTooLarge);
+        }
+
+        let format = self
+            .format
+            .or_else(|| ImportFormat::infer(url.as_str(), response.header("Content-Type")))
+            .ok_or_else(|| {
+                ResponseError::bad_parameter(
+                    "format",
+                    "impossible to infer the format of the file, it must be one of json, ndjson or csv",
+                )
+            })?;
+
+        let mut reader = CheckedReader {
+            inner: response.into_reader(),
+            read: 0,
+            max_size: self.max_size,
+            hasher: Sha256::new(),
+        };
+        let result = read_documents(
+            &mut reader,
+            format,
+            self.csv_delimiter,
+            self.infer_types,
+            self.max_document_size,
+        );
+
+        // payloads can lie about their size, the reader stops once the limit is crossed
+        if reader.read > self.max_size {
+            return Err(ResponseError::PayloadTooLarge);
+        }
+        let documents = result?;
+
+        if let Some(expected) = &self.sha256 {
+            let checksum = format!("{:x}", reader.hasher.result());
+            if !checksum.eq_ignore_ascii_case(expected.trim()) {
+                return Err(ResponseError::bad_parameter(
+                    "sha256",
+                    format!("checksum mismatch, the fetched file has a checksum of {}", checksum),
+                ));
+            }
+        }
+
+        Ok(documents)
+    }
+
+    /// Sends the request, redirections are followed by hand to check every url they lead to.
+    fn get(&self) -> Result<(Url, ureq::Response), ResponseError> {
+        let mut url = Url::parse(&self.url)
+            .map_err(|e| ResponseError::bad_parameter("url", format!("invalid url: {}", e)))?;
+
+        for _ in 0..=MAX_REDIRECTS {
+            check_target(&url, self.allow_private_networks)?;
+
+            let response = ureq::get(url.as_str())
+                .timeout_connect(CONNECT_TIMEOUT_MS)
+                .timeout_read(READ_TIMEOUT_MS)
+                .redirects(0)
+                .call();
+
+            if let Some(err) = response.synthetic_error() {
+                return Err(ResponseError::bad_request(format!("impossible to fetch {}: {}", url, err)));
+            }
+
+            if (300..400).contains(&response.status()) {
+                let location = response.header("Location").ok_or_else(|| {
+                    ResponseError::bad_request(format!("impossible to fetch {}: redirection without location", url))
+                })?;
+                url = url.join(location).map_err(|e| {
+                    ResponseError::bad_request(format!("impossible to fetch {}: invalid redirection: {}", url, e))
+                })?;
+                continue;
+            }
+
+            if !response.ok() {
+                return Err(ResponseError::bad_request(format!(
+                    "impossible to fetch {}: the server responded with status {}",
+                    url,
+                    response.status()
+                )));
+            }
+
+            return Ok((url, response));
+        }
+
+        Err(ResponseError::bad_request(format!(
+            "impossible to fetch {}: too many redirections",
+            self.url
+        )))
+    }
+}
+
+/// Refuses the urls that are not http(s) and, unless allowed, the ones that resolve to an
+/// address of the server itself or of its private networks.
+///
+/// The host is resolved again when connecting, this check doesn't protect
+/// against a DNS server answering a different address the second time.
+fn check_target(url: &Url, allow_private_networks: bool) -> Result<(), ResponseError> {
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(ResponseError::bad_parameter("url", "only http and https urls are supported"));
+    }
+    if allow_private_networks {
+        return Ok(());
+    }
+
+    let addresses: Vec<IpAddr> = match url.host() {
+        Some(Host::Ipv4(ip)) => vec![IpAddr::V4(ip)],
+        Some(Host::Ipv6(ip)) => vec![IpAddr::V6(ip)],
+        Some(Host::Domain(domain)) => {
+            let port = url.port_or_known_default().unwrap_or(80);
+            (domain, port)
+                .to_socket_addrs()
+                .map_err(|e| ResponseError::bad_request(format!("impossible to resolve {}: {}", domain, e)))?
+                .map(|address| address.ip())
+                .collect()
+        }
+        None => return Err(ResponseError::bad_parameter("url", "the url must have a host")),
+    };
+
+    if addresses.iter().all(is_public_address) {
+        Ok(())
+    } else {
+        Err(ResponseError::bad_parameter(
+            "url",
+            format!("{} is not a public address, the server can only import from public addresses", url),
+        ))
+    }
+}
+
+fn is_public_address(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => is_public_ipv6(ip),
+    }
+}
+
+fn is_public_ipv4(ip: &Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    let shared = a == 100 && (b & 0b1100_0000) == 64; // 100.64.0.0/10
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || a == 0
+        || shared)
+}
+
+fn is_public_ipv6(ip: &Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    match segments {
+        // IPv4-mapped addresses
+        [0, 0, 0, 0, 0, 0xffff, ..] => ip.to_ipv4().map_or(false, |ip| is_public_ipv4(&ip)),
+        _ => {
+            let unique_local = (segments[0] & 0xfe00) == 0xfc00; // fc00::/7
+            let link_local = (segments[0] & 0xffc0) == 0xfe80; // fe80::/10
+            !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || unique_local || link_local)
+        }
+    }
+}
+
+/// Counts and hashes the bytes read, and fails once more than `max_size` bytes are read.
+struct CheckedReader<R> {
+    inner: R,
+    read: u64,
+    max_size: u64,
+    hasher: Sha256,
+}
+
+impl<R: Read> Read for CheckedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        self.read += count as u64;
+        if self.read > self.max_size {
+            return Err(io::Error::new(io::ErrorKind::Other, "the file is too large"));
+        }
+        self.hasher.input(&buf[..count]);
+        Ok(count)
+    }
+}
+
+fn read_error(error: io::Error) -> ResponseError {
+    ResponseError::bad_request(format!("impossible to read the documents: {}", error))
+}
+
+/// Reads a JSON array of documents in chunks, the documents are parsed as soon as they are read.
+fn read_json_documents<R: Read>(mut reader: R, max_document_size: usize) -> Result<Vec<Document>, ResponseError> {
+    let mut stream = DocumentsStream::new(max_document_size);
+    let mut documents = Vec::new();
+    let mut buffer = vec![0; READ_BUFFER_SIZE];
+
+    loop {
+        let count = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(count) => count,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(read_error(e)),
+        };
+        documents.extend(stream.feed(&buffer[..count])?);
+    }
+    stream.finish()?;
+
+    Ok(documents)
+}
+
+/// Reads newline delimited JSON documents, empty lines are ignored.
+fn read_ndjson_documents<R: Read>(reader: R) -> Result<Vec<Document>, ResponseError> {
+    let mut documents = Vec::new();
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line.map_err(read_error)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let document = serde_json::from_str(&line).map_err(|e| {
+            ResponseError::bad_request(format!("Invalid NDJSON at line {}: {}", i + 1, e))
+        })?;
+        documents.push(document);
+    }
+    Ok(documents)
+}
+
+pub fn read_documents<R: Read>(
+    reader: R,
+    format: ImportFormat,
+    csv_delimiter: u8,
+    infer_types: bool,
+    max_document_size: usize,
+) -> Result<Vec<Document>, ResponseError> {
+    match format {
+        ImportFormat::Json => read_json_documents(reader, max_document_size),
+        ImportFormat::Ndjson => read_ndjson_documents(reader),
+        ImportFormat::Csv => read_csv_documents(reader, csv_delimiter, infer_types),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn public_addresses() {
+        let public = ["93.184.216.34", "2606:2800:220:1:248:1893:25c8:1946", "::ffff:93.184.216.34"];
+        for ip in &public {
+            assert!(is_public_address(&ip.parse().unwrap()), "{}", ip);
+        }
+
+        let private = [
+            "127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1",
+            "0.0.0.0", "::1", "::", "fd00::1", "fe80::1", "::ffff:127.0.0.1",
+        ];
+        for ip in &private {
+            assert!(!is_public_address(&ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn read_documents_in_every_format() {
+        let json = &br#"[{"id": 1}, {"id": 2}]"#[..];
+        assert_eq!(read_documents(json, ImportFormat::Json, b',', false, 1024).unwrap().len(), 2);
+
+        let ndjson = &b"{\"id\": 1}\n\n{\"id\": 2}\n"[..];
+        assert_eq!(read_documents(ndjson, ImportFormat::Ndjson, b',', false, 1024).unwrap().len(), 2);
+
+        let csv = &b"id,title\n1,a\n2,b\n"[..];
+        assert_eq!(read_documents(csv, ImportFormat::Csv, b',', false, 1024).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn checked_reader_stops_after_the_limit() {
+        let mut reader = CheckedReader {
+            inner: &b"0123456789"[..],
+            read: 0,
+            max_size: 4,
+            hasher: Sha256::new(),
+        };
+        assert!(io::copy(&mut reader, &mut io::sink()).is_err());
+        assert!(reader.read > reader.max_size);
+    }
+}
@@ -0,0 +1,537 @@
+//! A parser for the subset of the GraphQL query language understood by the GraphQL route:
+//! query operations made of fields, aliases, arguments and variables. Fragments, directives,
+//! mutations and subscriptions are rejected.
+//!
+//! The endpoint is hand-written, it is not a complete GraphQL server: the introspection
+//! fields (`__schema`, `__type`) are not supported, only `__typename` is. The clients and
+//! tools relying on introspection can't discover the schema by themselves, it must be
+//! fetched in the SDL format from the `/graphql/schema` route.
+
+use std::fmt;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputValue {
+    Null,
+    Int(i64),
+    Float(f64),
+    String(String),
+    Boolean(bool),
+    Enum(String),
+    List(Vec<InputValue>),
+    Object(Vec<(String, InputValue)>),
+    Variable(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub alias: Option<String>,
+    pub name: String,
+    pub arguments: Vec<(String, InputValue)>,
+    pub selection: Vec<Field>,
+}
+
+impl Field {
+    /// The key under which the field appears in the response.
+    pub fn response_key(&self) -> &str {
+        self.alias.as_ref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariableDefinition {
+    pub name: String,
+    pub default: Option<InputValue>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+    pub name: Option<String>,
+    pub variables: Vec<VariableDefinition>,
+    pub selection: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub offset: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "syntax error at offset {}: {}", self.offset, self.message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Punctuator(char),
+    Spread,
+    Name(String),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Punctuator(c) => write!(f, "\"{}\"", c),
+            Token::Spread => f.write_str("\"...\""),
+            Token::Name(name) => write!(f, "name \"{}\"", name),
+            Token::Int(i) => write!(f, "int {}", i),
+            Token::Float(x) => write!(f, "float {}", x),
+            Token::String(s) => write!(f, "string {:?}", s),
+        }
+    }
+}
+
+fn error<T>(message: impl ToString, offset: usize) -> Result<T, ParseError> {
+    Err(ParseError { message: message.to_string(), offset })
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some(&(offset, c)) = chars.peek() {
+        match c {
+            // commas are insignificant in GraphQL, like white spaces
+            ' ' | '\t' | '\n' | '\r' | ',' | '\u{feff}' => {
+                chars.next();
+            }
+            '#' => {
+                for (_, c) in &mut chars {
+                    if c == '\n' || c == '\r' {
+                        break;
+                    }
+                }
+            }
+            '{' | '}' | '(' | ')' | '[' | ']' | ':' | '$' | '!' | '=' | '@' => {
+                chars.next();
+                tokens.push((offset, Token::Punctuator(c)));
+            }
+            '.' => {
+                if source[offset..].starts_with("...") {
+                    chars.nth(2);
+                    tokens.push((offset, Token::Spread));
+                } else {
+                    return error("unexpected character \".\"", offset);
+                }
+            }
+            '"' => {
+                if source[offset..].starts_with("\"\"\"") {
+                    return error("block strings are not supported", offset);
+                }
+                chars.next();
+                tokens.push((offset, Token::String(string(&mut chars, offset)?)));
+            }
+            '-' | '0'..='9' => tokens.push((offset, number(source, &mut chars)?)),
+            c if c == '_' || c.is_ascii_alphabetic() => {
+                let mut end = offset;
+                while let Some(&(i, c)) = chars.peek() {
+                    if c == '_' || c.is_ascii_alphanumeric() {
+                        end = i + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push((offset, Token::Name(source[offset..end].to_string())));
+            }
+            c => return error(format!("unexpected character {:?}", c), offset),
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn string(chars: &mut Peekable<CharIndices>, start: usize) -> Result<String, ParseError> {
+    let mut value = String::new();
+    loop {
+        match chars.next() {
+            Some((_, '"')) => return Ok(value),
+            Some((offset, '\\')) => match chars.next() {
+                Some((_, '"')) => value.push('"'),
+                Some((_, '\\')) => value.push('\\'),
+                Some((_, '/')) => value.push('/'),
+                Some((_, 'b')) => value.push('\u{8}'),
+                Some((_, 'f')) => value.push('\u{c}'),
+                Some((_, 'n')) => value.push('\n'),
+                Some((_, 'r')) => value.push('\r'),
+                Some((_, 't')) => value.push('\t'),
+                Some((_, 'u')) => {
+                    let code: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                    match u32::from_str_radix(&code, 16).ok().and_then(std::char::from_u32) {
+                        Some(c) if code.len() == 4 => value.push(c),
+                        _ => return error(format!("invalid unicode escape \"\\u{}\"", code), offset),
+                    }
+                }
+                _ => return error("invalid escape sequence", offset),
+            },
+            Some((offset, '\n')) | Some((offset, '\r')) => return error("unterminated string", offset),
+            Some((_, c)) => value.push(c),
+            None => return error("unterminated string", start),
+        }
+    }
+}
+
+fn number(source: &str, chars: &mut Peekable<CharIndices>) -> Result<Token, ParseError> {
+    let (start, _) = chars.next().unwrap();
+    let mut end = start + 1;
+    let mut is_float = false;
+
+    while let Some(&(i, c)) = chars.peek() {
+        match c {
+            '0'..='9' => (),
+            '.' | 'e' | 'E' => is_float = true,
+            '+' | '-' if is_float => (),
+            _ => break,
+        }
+        end = i + 1;
+        chars.next();
+    }
+
+    let text = &source[start..end];
+    let token = if is_float {
+        text.parse().map(Token::Float).ok()
+    } else {
+        text.parse().map(Token::Int).ok()
+    };
+
+    match token {
+        Some(token) => Ok(token),
+        None => error(format!("invalid number \"{}\"", text), start),
+    }
+}
+
+/// The maximum nesting of the selection sets, lists, objects and types of a document,
+/// the parser is recursive and must not overflow the stack.
+const MAX_DEPTH: usize = 32;
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens.get(self.position).map_or(self.end, |(offset, _)| *offset)
+    }
+
+    fn advance(&mut self) -> Result<Token, ParseError> {
+        match self.tokens.get(self.position) {
+            Some((_, token)) => {
+                self.position += 1;
+                Ok(token.clone())
+            }
+            None => error("unexpected end of document", self.end),
+        }
+    }
+
+    fn is_punctuator(&self, c: char) -> bool {
+        self.peek() == Some(&Token::Punctuator(c))
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), ParseError> {
+        let offset = self.offset();
+        match self.advance()? {
+            Token::Punctuator(p) if p == c => Ok(()),
+            token => error(format!("expected \"{}\", found {}", c, token), offset),
+        }
+    }
+
+    fn check_depth(&self, depth: usize) -> Result<(), ParseError> {
+        if depth > MAX_DEPTH {
+            error(format!("the document can't be nested more than {} levels deep", MAX_DEPTH), self.offset())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn name(&mut self) -> Result<String, ParseError> {
+        let offset = self.offset();
+        match self.advance()? {
+            Token::Name(name) => Ok(name),
+            token => error(format!("expected a name, found {}", token), offset),
+        }
+    }
+
+    fn document(&mut self) -> Result<Vec<Operation>, ParseError> {
+        let mut operations = Vec::new();
+        while self.peek().is_some() {
+            operations.push(self.operation()?);
+        }
+        if operations.is_empty() {
+            return error("the document does not contain any operation", self.end);
+        }
+        Ok(operations)
+    }
+
+    fn operation(&mut self) -> Result<Operation, ParseError> {
+        let offset = self.offset();
+        let mut name = None;
+        let mut variables = Vec::new();
+
+        match self.peek() {
+            Some(Token::Punctuator('{')) => (),
+            Some(Token::Name(keyword)) if keyword == "query" => {
+                self.advance()?;
+                if let Some(Token::Name(_)) = self.peek() {
+                    name = Some(self.name()?);
+                }
+                if self.is_punctuator('(') {
+                    variables = self.variable_definitions()?;
+                }
+            }
+            Some(Token::Name(keyword)) if keyword == "mutation" || keyword == "subscription" => {
+                return error(format!("{} operations are not supported", keyword), offset);
+            }
+            Some(Token::Name(keyword)) if keyword == "fragment" => {
+                return error("fragments are not supported", offset);
+            }
+            _ => {
+                let token = self.advance()?;
+                return error(format!("expected an operation, found {}", token), offset);
+            }
+        }
+
+        if self.is_punctuator('@') {
+            return error("directives are not supported", self.offset());
+        }
+
+        let selection = self.selection_set(1)?;
+
+        Ok(Operation { name, variables, selection })
+    }
+
+    fn variable_definitions(&mut self) -> Result<Vec<VariableDefinition>, ParseError> {
+        self.expect('(')?;
+        let mut definitions = Vec::new();
+        while !self.is_punctuator(')') {
+            self.expect('$')?;
+            let name = self.name()?;
+            self.expect(':')?;
+            self.type_reference(1)?;
+            let default = if self.is_punctuator('=') {
+                self.advance()?;
+                Some(self.value(true, 1)?)
+            } else {
+                None
+            };
+            definitions.push(VariableDefinition { name, default });
+        }
+        self.expect(')')?;
+        Ok(definitions)
+    }
+
+    /// The types of the variables are parsed but not checked, the values are checked
+    /// when the arguments are converted.
+    fn type_reference(&mut self, depth: usize) -> Result<(), ParseError> {
+        self.check_depth(depth)?;
+        if self.is_punctuator('[') {
+            self.advance()?;
+            self.type_reference(depth + 1)?;
+            self.expect(']')?;
+        } else {
+            self.name()?;
+        }
+        if self.is_punctuator('!') {
+            self.advance()?;
+        }
+        Ok(())
+    }
+
+    fn selection_set(&mut self, depth: usize) -> Result<Vec<Field>, ParseError> {
+        self.check_depth(depth)?;
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        while !self.is_punctuator('}') {
+            if self.peek() == Some(&Token::Spread) {
+                return error("fragments are not supported", self.offset());
+            }
+            fields.push(self.field(depth)?);
+        }
+        self.expect('}')?;
+        if fields.is_empty() {
+            return error("a selection set can't be empty", self.offset());
+        }
+        Ok(fields)
+    }
+
+    fn field(&mut self, depth: usize) -> Result<Field, ParseError> {
+        let mut alias = None;
+        let mut name = self.name()?;
+        if self.is_punctuator(':') {
+            self.advance()?;
+            alias = Some(name);
+            name = self.name()?;
+        }
+
+        let mut arguments = Vec::new();
+        if self.is_punctuator('(') {
+            self.advance()?;
+            while !self.is_punctuator(')') {
+                let name = self.name()?;
+                self.expect(':')?;
+                arguments.push((name, self.value(false, 1)?));
+            }
+            self.expect(')')?;
+        }
+
+        if self.is_punctuator('@') {
+            return error("directives are not supported", self.offset());
+        }
+
+        let selection = if self.is_punctuator('{') {
+            self.selection_set(depth + 1)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Field { alias, name, arguments, selection })
+    }
+
+    fn value(&mut self, constant: bool, depth: usize) -> Result<InputValue, ParseError> {
+        self.check_depth(depth)?;
+        let offset = self.offset();
+        match self.advance()? {
+            Token::Punctuator('$') if !constant => Ok(InputValue::Variable(self.name()?)),
+            Token::Int(i) => Ok(InputValue::Int(i)),
+            Token::Float(x) => Ok(InputValue::Float(x)),
+            Token::String(s) => Ok(InputValue::String(s)),
+            Token::Name(name) => match name.as_str() {
+                "true" => Ok(InputValue::Boolean(true)),
+                "false" => Ok(InputValue::Boolean(false)),
+                "null" => Ok(InputValue::Null),
+                _ => Ok(InputValue::Enum(name)),
+            },
+            Token::Punctuator('[') => {
+                let mut values = Vec::new();
+                while !self.is_punctuator(']') {
+                    values.push(self.value(constant, depth + 1)?);
+                }
+                self.advance()?;
+                Ok(InputValue::List(values))
+            }
+            Token::Punctuator('{') => {
+                let mut fields = Vec::new();
+                while !self.is_punctuator('}') {
+                    let name = self.name()?;
+                    self.expect(':')?;
+                    fields.push((name, self.value(constant, depth + 1)?));
+                }
+                self.advance()?;
+                Ok(InputValue::Object(fields))
+            }
+            token => error(format!("expected a value, found {}", token), offset),
+        }
+    }
+}
+
+/// Parses a GraphQL document into its operations.
+pub fn parse_document(source: &str) -> Result<Vec<Operation>, ParseError> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser { tokens, position: 0, end: source.len() };
+    parser.document()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, selection: Vec<Field>) -> Field {
+        Field { alias: None, name: name.to_string(), arguments: Vec::new(), selection }
+    }
+
+    #[test]
+    fn shorthand_query() {
+        let operations = parse_document("{ movies { hits { title } nbHits } }").unwrap();
+        let expected = Operation {
+            name: None,
+            variables: Vec::new(),
+            selection: vec![field(
+                "movies",
+                vec![field("hits", vec![field("title", Vec::new())]), field("nbHits", Vec::new())],
+            )],
+        };
+        assert_eq!(operations, vec![expected]);
+    }
+
+    #[test]
+    fn named_query_with_variables_and_arguments() {
+        let source = r#"
+            # search the movies
+            query Search($q: String! = "carol", $limit: Int) {
+                found: movies(q: $q, limit: $limit, offset: 2, facets: ["genre"], facetFilters: { a: 1.5 }) {
+                    nbHits
+                }
+            }
+        "#;
+        let operations = parse_document(source).unwrap();
+        assert_eq!(operations.len(), 1);
+
+        let operation = &operations[0];
+        assert_eq!(operation.name.as_deref(), Some("Search"));
+        assert_eq!(
+            operation.variables,
+            vec![
+                VariableDefinition { name: "q".to_string(), default: Some(InputValue::String("carol".to_string())) },
+                VariableDefinition { name: "limit".to_string(), default: None },
+            ]
+        );
+
+        let field = &operation.selection[0];
+        assert_eq!(field.response_key(), "found");
+        assert_eq!(field.name, "movies");
+        assert_eq!(
+            field.arguments,
+            vec![
+                ("q".to_string(), InputValue::Variable("q".to_string())),
+                ("limit".to_string(), InputValue::Variable("limit".to_string())),
+                ("offset".to_string(), InputValue::Int(2)),
+                ("facets".to_string(), InputValue::List(vec![InputValue::String("genre".to_string())])),
+                ("facetFilters".to_string(), InputValue::Object(vec![("a".to_string(), InputValue::Float(1.5))])),
+            ]
+        );
+    }
+
+    #[test]
+    fn string_escapes() {
+        let operations = parse_document(r#"{ movies(q: "a\"b\u00e9\n") { nbHits } }"#).unwrap();
+        let (_, value) = &operations[0].selection[0].arguments[0];
+        assert_eq!(value, &InputValue::String("a\"bé\n".to_string()));
+    }
+
+    #[test]
+    fn unsupported_constructs() {
+        assert!(parse_document("mutation { addMovie }").is_err());
+        assert!(parse_document("{ movies { ...MovieFields } }").is_err());
+        assert!(parse_document("{ movies @skip(if: true) { nbHits } }").is_err());
+        assert!(parse_document("{ movies { } }").is_err());
+        assert!(parse_document("{ movies(q: \"unterminated) { nbHits } }").is_err());
+        assert!(parse_document("").is_err());
+    }
+
+    #[test]
+    fn deeply_nested_documents() {
+        let nested = |depth: usize| format!("{{ movies{} }}", "{ a ".repeat(depth - 1) + &" }".repeat(depth - 1));
+        assert!(parse_document(&nested(MAX_DEPTH)).is_ok());
+        assert!(parse_document(&nested(MAX_DEPTH + 1)).is_err());
+
+        let list = |depth: usize| format!("{{ movies(q: {}1{}) {{ nbHits }} }}", "[".repeat(depth), "]".repeat(depth));
+        assert!(parse_document(&list(MAX_DEPTH - 1)).is_ok());
+        assert!(parse_document(&list(MAX_DEPTH)).is_err());
+
+        // the stack must not overflow, whatever the nesting
+        assert!(parse_document(&"{ a ".repeat(100_000)).is_err());
+        assert!(parse_document(&format!("{{ movies(q: {}) }}", "{ a: ".repeat(100_000))).is_err());
+        assert!(parse_document(&format!("query($a: {}) {{ a }}", "[".repeat(100_000))).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use crate::error::ResponseError;
+use crate::helpers::csv_documents::Document;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum State {
+    /// Waiting for the opening bracket of the array.
+    Start,
+    /// Waiting for a document, or for the end of the array if `first` is `true`.
+    BeforeDocument { first: bool },
+    /// Reading the bytes of a document.
+    InDocument,
+    /// Waiting for a comma or the end of the array.
+    AfterDocument,
+    /// The array has been closed.
+    End,
+}
+
+/// Splits a JSON array of documents received in arbitrary chunks, documents are parsed
+/// and returned as soon as they are complete, the whole payload is never buffered.
+pub struct DocumentsStream {
+    state: State,
+    buffer: Vec<u8>,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    max_document_size: usize,
+}
+
+impl DocumentsStream {
+    pub fn new(max_document_size: usize) -> DocumentsStream {
+        DocumentsStream {
+            state: State::Start,
+            buffer: Vec::new(),
+            depth: 0,
+            in_string: false,
+            escaped: false,
+            max_document_size,
+        }
+    }
+
+    /// Feeds a chunk of the payload and returns the documents it completes.
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<Vec<Document>, ResponseError> {
+        let mut documents = Vec::new();
+
+        for &byte in bytes {
+            match self.state {
+                State::InDocument => {
+                    self.buffer.push(byte);
+                    if self.buffer.len() > self.max_document_size {
+                        return Err(ResponseError::bad_request(format!(
+                            "a document exceeds the maximum size of {} bytes",
+                            self.max_document_size
+                        )));
+                    }
+
+                    if self.in_string {
+                        if self.escaped {
+                            self.escaped = false;
+                        } else if byte == b'\\' {
+                            self.escaped = true;
+                        } else if byte == b'"' {
+                            self.in_string = false;
+                        }
+                        continue;
+                    }
+
+                    match byte {
+                        b'"' => self.in_string = true,
+                        b'{' | b'[' => self.depth += 1,
+                        b'}' | b']' => self.depth -= 1,
+                        _ => (),
+                    }
+
+                    if self.depth == 0 {
+                        let document = serde_json::from_slice(&self.buffer)
+                            .map_err(|e| ResponseError::bad_request(format!("Invalid JSON document: {}", e)))?;
+                        documents.push(document);
+                        self.buffer.clear();
+                        self.state = State::AfterDocument;
+                    }
+                }
+                _ if byte.is_ascii_whitespace() => (),
+                State::Start if byte == b'[' => self.state = State::BeforeDocument { first: true },
+                State::BeforeDocument { .. } if byte == b'{' => {
+                    self.buffer.push(byte);
+                    self.depth = 1;
+                    self.state = State::InDocument;
+                }
+                State::BeforeDocument { first: true } if byte == b']' => self.state = State::End,
+                State::AfterDocument if byte == b',' => self.state = State::BeforeDocument { first: false },
+                State::AfterDocument if byte == b']' => self.state = State::End,
+                _ => {
+                    return Err(ResponseError::bad_request(format!(
+                        "Invalid JSON: unexpected character `{}`, the payload must be an array of documents",
+                        byte as char
+                    )))
+                }
+            }
+        }
+
+        Ok(documents)
+    }
+
+    /// Must be called once the whole payload has been fed.
+    pub fn finish(&self) -> Result<(), ResponseError> {
+        if self.state == State::End {
+            Ok(())
+        } else {
+            Err(ResponseError::bad_request("Invalid JSON: unexpected end of the array of documents"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_documents_in_chunks() {
+        let payload = br#" [ {"id": 1, "title": "a } \" ]"}, {"id": 2, "tags": [{}, []]} ] "#;
+
+        let mut stream = DocumentsStream::new(1024);
+        let mut documents = Vec::new();
+        for chunk in payload.chunks(3) {
+            documents.extend(stream.feed(chunk).unwrap());
+        }
+        stream.finish().unwrap();
+
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[0]["title"], "a } \" ]");
+        assert_eq!(documents[1]["id"], 2);
+    }
+
+    #[test]
+    fn empty_array() {
+        let mut stream = DocumentsStream::new(1024);
+        assert!(stream.feed(b"[]").unwrap().is_empty());
+        stream.finish().unwrap();
+    }
+
+    #[test]
+    fn invalid_payloads() {
+        for payload in &[&b"{}"[..], b"[{},]", b"[1]", b"[{}] {}", b"[{} {}]"] {
+            let mut stream = DocumentsStream::new(1024);
+            assert!(stream.feed(payload).is_err(), "{:?}", payload);
+        }
+
+        let mut stream = DocumentsStream::new(1024);
+        stream.feed(b"[{}").unwrap();
+        assert!(stream.finish().is_err());
+    }
+
+    #[test]
+    fn document_too_large() {
+        let mut stream = DocumentsStream::new(8);
+        assert!(stream.feed(br#"[{"id": 1}, {"id": 123456}]"#).is_err());
+    }
+}
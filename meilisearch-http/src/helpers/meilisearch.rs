@@ -1,17 +1,19 @@
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use indexmap::IndexMap;
 use log::error;
 use meilisearch_core::Filter;
-use meilisearch_core::facets::FacetFilter;
+use meilisearch_core::facets::{FacetFilter, FacetStats};
 use meilisearch_core::criterion::*;
-use meilisearch_core::settings::RankingRule;
-use meilisearch_core::{Highlight, Index, MainT, RankedMap};
+use meilisearch_core::settings::{RankingRule, DEFAULT_RANKING_RULES};
+use meilisearch_core::query_rules::matching_actions;
+use meilisearch_core::{update, GeoPoint, Highlight, Index, MainT, MatchingStrategy, RankedMap, SearchProfile};
 use meilisearch_schema::{FieldId, Schema};
-use meilisearch_tokenizer::is_cjk;
+use meilisearch_tokenizer::is_unspaced;
+use sdset::Set;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use siphasher::sip::SipHasher;
@@ -31,12 +33,31 @@ impl IndexSearchExt for Index {
             offset: 0,
             limit: 20,
             attributes_to_crop: None,
+            crop_marker: None,
             attributes_to_retrieve: None,
+            attributes_to_search_in: None,
+            attribute_weights: None,
             attributes_to_highlight: None,
             filters: None,
             matches: false,
+            matches_unit: MatchesUnit::default(),
             facet_filters: None,
             facets: None,
+            global_facets: false,
+            language: None,
+            ranking_rules: None,
+            sort: None,
+            around: None,
+            matching_strategy: MatchingStrategy::default(),
+            exhaustive_nb_hits: false,
+            time_budget: None,
+            distinct: None,
+            distinct_size: 1,
+            max_values_per_facet: None,
+            facet_values_order: FacetValuesOrder::default(),
+            profile: false,
+            ranking_score_details: false,
+            suggest_query: false,
         }
     }
 }
@@ -47,12 +68,31 @@ pub struct SearchBuilder<'a> {
     offset: usize,
     limit: usize,
     attributes_to_crop: Option<HashMap<String, usize>>,
+    crop_marker: Option<String>,
     attributes_to_retrieve: Option<HashSet<String>>,
-    attributes_to_highlight: Option<HashSet<String>>,
+    attributes_to_search_in: Option<Vec<String>>,
+    attribute_weights: Option<Vec<(String, f64)>>,
+    attributes_to_highlight: Option<HashMap<String, HighlightTags>>,
     filters: Option<String>,
     matches: bool,
+    matches_unit: MatchesUnit,
     facet_filters: Option<FacetFilter>,
-    facets: Option<Vec<(FieldId, String)>>
+    facets: Option<Vec<(FieldId, String)>>,
+    global_facets: bool,
+    language: Option<String>,
+    ranking_rules: Option<Vec<RankingRule>>,
+    sort: Option<Vec<SortRule>>,
+    around: Option<GeoPoint>,
+    matching_strategy: MatchingStrategy,
+    exhaustive_nb_hits: bool,
+    time_budget: Option<Duration>,
+    distinct: Option<String>,
+    distinct_size: usize,
+    max_values_per_facet: Option<usize>,
+    facet_values_order: FacetValuesOrder,
+    profile: bool,
+    ranking_score_details: bool,
+    suggest_query: bool,
 }
 
 impl<'a> SearchBuilder<'a> {
@@ -71,6 +111,11 @@ impl<'a> SearchBuilder<'a> {
         self
     }
 
+    pub fn crop_marker(&mut self, value: String) -> &SearchBuilder {
+        self.crop_marker = Some(value);
+        self
+    }
+
     pub fn attributes_to_retrieve(&mut self, value: HashSet<String>) -> &SearchBuilder {
         self.attributes_to_retrieve = Some(value);
         self
@@ -82,7 +127,17 @@ impl<'a> SearchBuilder<'a> {
         self
     }
 
-    pub fn attributes_to_highlight(&mut self, value: HashSet<String>) -> &SearchBuilder {
+    pub fn attributes_to_search_in(&mut self, value: Vec<String>) -> &SearchBuilder {
+        self.attributes_to_search_in = Some(value);
+        self
+    }
+
+    pub fn attribute_weights(&mut self, value: Vec<(String, f64)>) -> &SearchBuilder {
+        self.attribute_weights = Some(value);
+        self
+    }
+
+    pub fn attributes_to_highlight(&mut self, value: HashMap<String, HighlightTags>) -> &SearchBuilder {
         self.attributes_to_highlight = Some(value);
         self
     }
@@ -102,11 +157,97 @@ impl<'a> SearchBuilder<'a> {
         self
     }
 
+    pub fn matches_unit(&mut self, value: MatchesUnit) -> &SearchBuilder {
+        self.matches_unit = value;
+        self
+    }
+
     pub fn add_facets(&mut self, facets: Vec<(FieldId, String)>) -> &SearchBuilder {
         self.facets = Some(facets);
         self
     }
 
+    pub fn global_facets(&mut self, value: bool) -> &SearchBuilder {
+        self.global_facets = value;
+        self
+    }
+
+    pub fn language(&mut self, value: String) -> &SearchBuilder {
+        self.language = Some(value);
+        self
+    }
+
+    /// Overrides the ranking rules of the index for this search only.
+    pub fn ranking_rules(&mut self, value: Vec<RankingRule>) -> &SearchBuilder {
+        self.ranking_rules = Some(value);
+        self
+    }
+
+    /// Sorts the documents by the given sortable attributes before applying the ranking rules.
+    pub fn sort(&mut self, value: Vec<SortRule>) -> &SearchBuilder {
+        self.sort = Some(value);
+        self
+    }
+
+    /// The point from which the distances are computed by the `geo:asc` ranking rule.
+    pub fn around(&mut self, value: GeoPoint) -> &SearchBuilder {
+        self.around = Some(value);
+        self
+    }
+
+    pub fn matching_strategy(&mut self, value: MatchingStrategy) -> &SearchBuilder {
+        self.matching_strategy = value;
+        self
+    }
+
+    pub fn exhaustive_nb_hits(&mut self, value: bool) -> &SearchBuilder {
+        self.exhaustive_nb_hits = value;
+        self
+    }
+
+    /// Overrides the search cutoff of the index.
+    pub fn time_budget(&mut self, value: Duration) -> &SearchBuilder {
+        self.time_budget = Some(value);
+        self
+    }
+
+    pub fn distinct(&mut self, value: String) -> &SearchBuilder {
+        self.distinct = Some(value);
+        self
+    }
+
+    pub fn distinct_size(&mut self, value: usize) -> &SearchBuilder {
+        self.distinct_size = value;
+        self
+    }
+
+    pub fn max_values_per_facet(&mut self, value: usize) -> &SearchBuilder {
+        self.max_values_per_facet = Some(value);
+        self
+    }
+
+    pub fn facet_values_order(&mut self, value: FacetValuesOrder) -> &SearchBuilder {
+        self.facet_values_order = value;
+        self
+    }
+
+    pub fn profile(&mut self, value: bool) -> &SearchBuilder {
+        self.profile = value;
+        self
+    }
+
+    /// Attaches the values compared by each ranking rule to the hits.
+    pub fn ranking_score_details(&mut self, value: bool) -> &SearchBuilder {
+        self.ranking_score_details = value;
+        self
+    }
+
+    /// Attaches the query rewritten with the suggestions of its misspelled terms to the result.
+    pub fn suggest_query(&mut self, value: bool) -> &SearchBuilder {
+        self.suggest_query = value;
+        self
+    }
+
     pub fn search(self, reader: &heed::RoTxn<MainT>) -> Result<SearchResult, ResponseError> {
         let schema = self
             .index
@@ -116,13 +257,33 @@ impl<'a> SearchBuilder<'a> {
 
         let ranked_map = self.index.main.ranked_map(reader)?.unwrap_or_default();
 
+        let query_rules = self.index.main.query_rules(reader)?.unwrap_or_default();
+        let actions = matching_actions(&query_rules, &self.query);
+
+        // the filters forced by the query rules are combined with the user ones
+        let filters = self.filters.iter().chain(&actions.filters).map(|f| format!("({})", f));
+        let filters = filters.collect::<Vec<_>>().join(" AND ");
+
         // Change criteria
         let mut query_builder = match self.get_criteria(reader, &ranked_map, &schema)? {
             Some(criteria) => self.index.query_builder_with_criteria(criteria),
             None => self.index.query_builder(),
         };
 
-        if let Some(filter_expression) = &self.filters {
+        let document_id_format = self.index.main.document_id_format(reader)?.unwrap_or_default();
+
+        if !actions.pinned.is_empty() {
+            let pinned = actions.pinned.iter().filter_map(|id| update::compute_document_id(id, document_id_format).ok());
+            query_builder.set_pinned_documents(pinned.collect());
+        }
+
+        if !actions.hidden.is_empty() {
+            let hidden = actions.hidden.iter().filter_map(|id| update::compute_document_id(id, document_id_format).ok());
+            query_builder.set_hidden_documents(hidden.collect());
+        }
+
+        if !filters.is_empty() {
+            let filter_expression = &filters;
             let filter = Filter::parse(filter_expression, &schema)?;
             let index = &self.index;
             query_builder.with_filter(move |id| {
@@ -138,29 +299,55 @@ impl<'a> SearchBuilder<'a> {
             });
         }
 
-        if let Some(field) = self.index.main.distinct_attribute(reader)? {
-            if let Some(field_id) = schema.id(&field) {
-                let index = &self.index;
-                query_builder.with_distinct(1, move |id| {
-                    match index.document_attribute_bytes(reader, id, field_id) {
-                        Ok(Some(bytes)) => {
-                            let mut s = SipHasher::new();
-                            bytes.hash(&mut s);
-                            Some(s.finish())
-                        }
-                        _ => None,
+        // the distinct attribute of the query replaces the one of the settings
+        let distinct_field_id = match &self.distinct {
+            Some(field) => match schema.id(field) {
+                Some(field_id) => Some(field_id),
+                None => return Err(ResponseError::bad_parameter("distinct", format!("unknown attribute {}", field))),
+            },
+            None => self.index.main.distinct_attribute(reader)?.and_then(|field| schema.id(&field)),
+        };
+
+        if let Some(field_id) = distinct_field_id {
+            let index = &self.index;
+            query_builder.with_distinct(self.distinct_size, move |id| {
+                match index.document_attribute_bytes(reader, id, field_id) {
+                    Ok(Some(bytes)) => {
+                        let mut s = SipHasher::new();
+                        bytes.hash(&mut s);
+                        Some(s.finish())
                     }
-                });
-            }
+                    _ => None,
+                }
+            });
+        }
+
+        if let Some(attributes) = &self.attributes_to_search_in {
+            let attributes: Vec<_> = attributes.iter().map(String::as_str).collect();
+            query_builder.restrict_searchable_attributes(&schema, &attributes);
+        }
+
+        if let Some(weights) = &self.attribute_weights {
+            let weights: Vec<_> = weights.iter().map(|(attr, weight)| (attr.as_str(), *weight)).collect();
+            query_builder.set_attribute_weights(&schema, &weights);
         }
 
         query_builder.set_facet_filter(self.facet_filters);
         query_builder.set_facets(self.facets);
+        query_builder.set_global_facet_count(self.global_facets);
+        query_builder.set_language(self.language);
+        query_builder.set_matching_strategy(self.matching_strategy);
+        query_builder.set_exhaustive_nb_hits(self.exhaustive_nb_hits);
+        query_builder.set_ranking_score_details(self.ranking_score_details);
+        if let Some(time_budget) = self.time_budget {
+            query_builder.with_time_budget(time_budget);
+        }
 
         let start = Instant::now();
         let result = query_builder.query(reader, &self.query, self.offset..(self.offset + self.limit));
         let search_result = result.map_err(ResponseError::search_documents)?;
         let time_ms = start.elapsed().as_millis() as usize;
+        let before_formatting = Instant::now();
 
         let mut all_attributes: HashSet<&str> = HashSet::new();
         let mut all_formatted: HashSet<&str> = HashSet::new();
@@ -170,7 +357,7 @@ impl<'a> SearchBuilder<'a> {
                 all_attributes.extend(to_retrieve.iter().map(String::as_str));
 
                 if let Some(to_highlight) = &self.attributes_to_highlight {
-                    all_formatted.extend(to_highlight.iter().map(String::as_str));
+                    all_formatted.extend(to_highlight.keys().map(String::as_str));
                 }
 
                 if let Some(to_crop) = &self.attributes_to_crop {
@@ -208,47 +395,94 @@ impl<'a> SearchBuilder<'a> {
 
             // Crops fields if needed
             if let Some(fields) = &self.attributes_to_crop {
-                crop_document(&mut formatted, &mut matches, &schema, fields);
+                let marker = self.crop_marker.as_deref().unwrap_or("");
+                crop_document(&mut formatted, &mut matches, &schema, fields, marker);
+            }
+
+            let mut matches_info = if self.matches {
+                Some(calculate_matches(&matches, self.attributes_to_retrieve.clone(), &schema))
+            } else {
+                None
+            };
+
+            // The positions must be converted against the text they refer to,
+            // which is the cropped one for the cropped attributes.
+            if let (Some(matches_info), MatchesUnit::Bytes) = (&mut matches_info, self.matches_unit) {
+                for (attribute, positions) in matches_info.iter_mut() {
+                    let text = match &self.attributes_to_crop {
+                        Some(to_crop) if to_crop.contains_key(attribute) => formatted.get(attribute),
+                        _ => document.get(attribute),
+                    };
+                    if let Some(Value::String(text)) = text {
+                        chars_to_bytes_positions(text, positions);
+                    }
+                }
             }
 
             // Transform to readable matches
             if let Some(attributes_to_highlight) = &self.attributes_to_highlight {
                 let matches = calculate_matches(
                     &matches,
-                    self.attributes_to_highlight.clone(),
+                    Some(attributes_to_highlight.keys().cloned().collect()),
                     &schema,
                 );
                 formatted = calculate_highlights(&formatted, &matches, attributes_to_highlight);
             }
 
-            let matches_info = if self.matches {
-                Some(calculate_matches(&matches, self.attributes_to_retrieve.clone(), &schema))
-            } else {
-                None
-            };
-
             if let Some(attributes_to_retrieve) = &self.attributes_to_retrieve {
-                document.retain(|key, _| attributes_to_retrieve.contains(&key.to_string()))
+                // the nested attributes are retrieved with their top-level field
+                document.retain(|key, _| {
+                    attributes_to_retrieve.iter().any(|name| name == key || update::nested_path(name, key).is_some())
+                })
             }
 
             let hit = SearchHit {
                 document,
                 formatted,
                 matches_info,
+                collapsed: distinct_field_id.map(|_| doc.collapsed),
+                ranking_score_details: doc.ranking_score_details,
             };
 
             hits.push(hit);
         }
 
+        let profile = if self.profile {
+            Some(SearchProfileReport {
+                search: search_result.profile,
+                formatting_ms: before_formatting.elapsed().as_secs_f64() * 1000.0,
+            })
+        } else {
+            None
+        };
+
+        let suggested_query = if self.suggest_query {
+            self.index.suggested_query(reader, &self.query)?
+        } else {
+            None
+        };
+
         let results = SearchResult {
             hits,
             offset: self.offset,
             limit: self.limit,
             nb_hits: search_result.nb_hits,
             exhaustive_nb_hits: search_result.exhaustive_nb_hit,
+            degraded: search_result.degraded,
             processing_time_ms: time_ms,
             query: self.query.to_string(),
-            facets: search_result.facets,
+            facets: search_result.facets.map(|facets| {
+                facets
+                    .into_iter()
+                    .map(|(name, values)| {
+                        let values = sort_facet_values(values, self.facet_values_order, self.max_values_per_facet);
+                        (name, values)
+                    })
+                    .collect()
+            }),
+            facets_stats: search_result.facets_stats,
+            profile,
+            suggested_query,
         };
 
         Ok(results)
@@ -260,10 +494,34 @@ impl<'a> SearchBuilder<'a> {
         ranked_map: &'a RankedMap,
         schema: &Schema,
     ) -> Result<Option<Criteria<'a>>, ResponseError> {
-        let ranking_rules = self.index.main.ranking_rules(reader)?;
+        // an invalid rule given with the search is reported to the user instead of being ignored
+        let (ranking_rules, overridden) = match &self.ranking_rules {
+            Some(rules) => (Some(rules.clone()), true),
+            None => (self.index.main.ranking_rules(reader)?, false),
+        };
+
+        // a sort requires the ranking rules to be applied after it, the default ones if needed
+        let ranking_rules = match (ranking_rules, &self.sort) {
+            (Some(ranking_rules), _) => Some(ranking_rules),
+            (None, Some(_)) => Some(DEFAULT_RANKING_RULES.to_vec()),
+            (None, None) => None,
+        };
 
         if let Some(ranking_rules) = ranking_rules {
-            let mut builder = CriteriaBuilder::with_capacity(7 + ranking_rules.len());
+            let sort = self.sort.as_deref().unwrap_or_default();
+            let mut builder = CriteriaBuilder::with_capacity(7 + sort.len() + ranking_rules.len());
+
+            if !sort.is_empty() {
+                let sortable_attributes = self.index.main.sortable_attributes(reader)?;
+                let sortable_attributes = sortable_attributes.as_deref().unwrap_or_else(|| Set::new_unchecked(&[]));
+                for rule in sort {
+                    match SortBy::new(self.index.sortables, schema, sortable_attributes, rule) {
+                        Ok(criterion) => builder.push(criterion),
+                        Err(err) => return Err(ResponseError::bad_parameter("sort", err)),
+                    }
+                }
+            }
+
             for rule in ranking_rules {
                 match rule {
                     RankingRule::Typo => builder.push(Typo),
@@ -275,15 +533,29 @@ impl<'a> SearchBuilder<'a> {
                     RankingRule::Asc(field) => {
                         match SortByAttr::lower_is_better(&ranked_map, &schema, &field) {
                             Ok(rule) => builder.push(rule),
+                            Err(err) if overridden => {
+                                return Err(ResponseError::bad_parameter("rankingRules", format!("{}: {}", field, err)))
+                            }
                             Err(err) => error!("Error during criteria builder; {:?}", err),
                         }
                     }
                     RankingRule::Desc(field) => {
                         match SortByAttr::higher_is_better(&ranked_map, &schema, &field) {
                             Ok(rule) => builder.push(rule),
+                            Err(err) if overridden => {
+                                return Err(ResponseError::bad_parameter("rankingRules", format!("{}: {}", field, err)))
+                            }
                             Err(err) => error!("Error during criteria builder; {:?}", err),
                         }
                     }
+                    RankingRule::GeoAsc => match self.around {
+                        Some(point) => builder.push(GeoDistance::new(self.index.geo, point)),
+                        None if overridden => {
+                            return Err(ResponseError::bad_parameter("rankingRules", "geo:asc requires the aroundLatLng parameter"))
+                        }
+                        // without a point to sort around the rule is ignored
+                        None => (),
+                    },
                 }
             }
             builder.push(DocumentId);
@@ -309,9 +581,75 @@ impl Ord for MatchPosition {
     }
 }
 
+/// The unit in which the positions of the matches are expressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatchesUnit {
+    /// the positions are counted in unicode characters
+    Chars,
+    /// the positions are counted in bytes of the UTF-8 encoded text
+    Bytes,
+}
+
+impl Default for MatchesUnit {
+    fn default() -> MatchesUnit {
+        MatchesUnit::Chars
+    }
+}
+
 pub type HighlightInfos = HashMap<String, Value>;
+
+/// The tags surrounding the highlighted parts of an attribute.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct HighlightTags {
+    pub pre_tag: String,
+    pub post_tag: String,
+}
+
+impl Default for HighlightTags {
+    fn default() -> HighlightTags {
+        HighlightTags {
+            pre_tag: "<em>".to_string(),
+            post_tag: "</em>".to_string(),
+        }
+    }
+}
+
+/// The order of the values in the facets distribution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FacetValuesOrder {
+    /// the values with the most documents first
+    Count,
+    /// the values in alphabetical order
+    Alpha,
+}
+
+impl Default for FacetValuesOrder {
+    fn default() -> FacetValuesOrder {
+        FacetValuesOrder::Count
+    }
+}
+
+/// Sorts the values of a facet and only keeps the first `limit` ones.
+fn sort_facet_values(
+    values: HashMap<String, usize>,
+    order: FacetValuesOrder,
+    limit: Option<usize>,
+) -> IndexMap<String, usize> {
+    let mut values: Vec<_> = values.into_iter().collect();
+    match order {
+        FacetValuesOrder::Count => values.sort_unstable_by(|(av, ac), (bv, bc)| bc.cmp(ac).then_with(|| av.cmp(bv))),
+        FacetValuesOrder::Alpha => values.sort_unstable_by(|(av, _), (bv, _)| av.cmp(bv)),
+    }
+    if let Some(limit) = limit {
+        values.truncate(limit);
+    }
+    values.into_iter().collect()
+}
+
 pub type MatchesInfos = HashMap<String, Vec<MatchPosition>>;
-// pub type RankingInfos = HashMap<String, u64>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchHit {
@@ -321,6 +659,10 @@ pub struct SearchHit {
     pub formatted: IndexMap<String, Value>,
     #[serde(rename = "_matchesInfo", skip_serializing_if = "Option::is_none")]
     pub matches_info: Option<MatchesInfos>,
+    #[serde(rename = "_collapsed", skip_serializing_if = "Option::is_none")]
+    pub collapsed: Option<usize>,
+    #[serde(rename = "_rankingScoreDetails", skip_serializing_if = "Option::is_none")]
+    pub ranking_score_details: Option<Vec<CriterionDetails>>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -331,14 +673,31 @@ pub struct SearchResult {
     pub limit: usize,
     pub nb_hits: usize,
     pub exhaustive_nb_hits: bool,
+    /// The search cutoff was reached, the hits are the best ones found so far.
+    pub degraded: bool,
     pub processing_time_ms: usize,
     pub query: String,
-    pub facets: Option<HashMap<String, HashMap<String, usize>>>,
+    pub facets: Option<HashMap<String, IndexMap<String, usize>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facets_stats: Option<HashMap<String, FacetStats>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<SearchProfileReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggested_query: Option<String>,
+}
+
+/// The timings of the search stages followed by the time spent formatting the hits.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchProfileReport {
+    #[serde(flatten)]
+    pub search: SearchProfile,
+    pub formatting_ms: f64,
 }
 
 /// returns the start index and the length on the crop.
 fn aligned_crop(text: &str, match_index: usize, context: usize) -> (usize, usize) {
-    let is_word_component = |c: &char| c.is_alphanumeric() && !is_cjk(*c);
+    let is_word_component = |c: &char| c.is_alphanumeric() && !is_unspaced(*c);
 
     let word_end_index = |mut index| {
         if text.chars().nth(index - 1).map_or(false, |c| is_word_component(&c)) {
@@ -364,10 +723,13 @@ fn aligned_crop(text: &str, match_index: usize, context: usize) -> (usize, usize
     (start, end - start)
 }
 
+/// Crops the text around the first match and surrounds it with the marker
+/// on the sides where some text has been removed.
 fn crop_text(
     text: &str,
     matches: impl IntoIterator<Item = Highlight>,
     context: usize,
+    marker: &str,
 ) -> (String, Vec<Highlight>) {
     let mut matches = matches.into_iter().peekable();
 
@@ -375,7 +737,7 @@ fn crop_text(
     let (start, count) = aligned_crop(text, char_index, context);
 
     // TODO do something about double allocation
-    let text = text
+    let mut cropped = text
         .chars()
         .skip(start)
         .take(count)
@@ -383,16 +745,28 @@ fn crop_text(
         .trim()
         .to_string();
 
+    // the matches are shifted by the marker that is prepended to the text
+    let mut shift = 0;
+    if !marker.is_empty() {
+        if start > 0 {
+            cropped.insert_str(0, marker);
+            shift = marker.chars().count();
+        }
+        if start + count < text.chars().count() {
+            cropped.push_str(marker);
+        }
+    }
+
     // update matches index to match the new cropped text
     let matches = matches
         .take_while(|m| (m.char_index as usize) + (m.char_length as usize) <= start + count)
         .map(|m| Highlight {
-            char_index: m.char_index - start as u16,
+            char_index: m.char_index - start as u16 + shift as u16,
             ..m
         })
         .collect();
 
-    (text, matches)
+    (cropped, matches)
 }
 
 fn crop_document(
@@ -400,6 +774,7 @@ fn crop_document(
     matches: &mut Vec<Highlight>,
     schema: &Schema,
     fields: &HashMap<String, usize>,
+    marker: &str,
 ) {
     matches.sort_unstable_by_key(|m| (m.char_index, m.char_length));
 
@@ -416,7 +791,7 @@ fn crop_document(
 
         if let Some(Value::String(ref mut original_text)) = document.get_mut(field) {
             let (cropped_text, cropped_matches) =
-                crop_text(original_text, selected_matches, *length);
+                crop_text(original_text, selected_matches, *length, marker);
 
             *original_text = cropped_text;
 
@@ -464,15 +839,28 @@ fn calculate_matches(
     matches_result
 }
 
+/// Converts the positions counted in characters into positions counted in bytes.
+fn chars_to_bytes_positions(text: &str, positions: &mut [MatchPosition]) {
+    let offsets: Vec<usize> = text.char_indices().map(|(i, _)| i).chain(Some(text.len())).collect();
+    let byte_offset = |index: usize| offsets.get(index).copied().unwrap_or(text.len());
+
+    for position in positions {
+        let start = byte_offset(position.start);
+        let end = byte_offset(position.start + position.length);
+        position.start = start;
+        position.length = end - start;
+    }
+}
+
 fn calculate_highlights(
     document: &IndexMap<String, Value>,
     matches: &MatchesInfos,
-    attributes_to_highlight: &HashSet<String>,
+    attributes_to_highlight: &HashMap<String, HighlightTags>,
 ) -> IndexMap<String, Value> {
     let mut highlight_result = document.clone();
 
     for (attribute, matches) in matches.iter() {
-        if attributes_to_highlight.contains(attribute) {
+        if let Some(tags) = attributes_to_highlight.get(attribute) {
             if let Some(Value::String(value)) = document.get(attribute) {
                 let value: Vec<_> = value.chars().collect();
                 let mut highlighted_value = String::new();
@@ -488,9 +876,9 @@ fn calculate_highlights(
                     let highlighted = value.get(m.start..(m.start + m.length));
                     if let (Some(before), Some(highlighted)) = (before, highlighted) {
                         highlighted_value.extend(before);
-                        highlighted_value.push_str("<em>");
+                        highlighted_value.push_str(&tags.pre_tag);
                         highlighted_value.extend(highlighted);
-                        highlighted_value.push_str("</em>");
+                        highlighted_value.push_str(&tags.post_tag);
                         index = m.start + m.length;
                     } else {
                         error!("value: {:?}; index: {:?}, match: {:?}", value, index, m);
@@ -540,6 +928,36 @@ mod tests {
         assert_eq!("の", cropped);
     }
 
+    #[test]
+    fn crop_text_with_marker() {
+        let text = "the quick brown fox jumps over the lazy dog";
+        let matches = vec![Highlight { attribute: 0, char_index: 16, char_length: 3 }];
+
+        let (cropped, matches) = crop_text(text, matches, 6, "…");
+        assert_eq!("…brown fox jumps…", cropped);
+        assert_eq!(matches[0].char_index, 7);
+        assert_eq!("fox", cropped.chars().skip(7).take(3).collect::<String>());
+
+        // nothing is removed so no marker is added
+        let matches = vec![Highlight { attribute: 0, char_index: 4, char_length: 5 }];
+        let (cropped, _) = crop_text(text, matches, 100, "…");
+        assert_eq!(text, cropped);
+    }
+
+    #[test]
+    fn chars_to_bytes_positions() {
+        let text = "l'été à Paris";
+        let mut positions = vec![
+            MatchPosition { start: 2, length: 3 },
+            MatchPosition { start: 8, length: 5 },
+        ];
+
+        super::chars_to_bytes_positions(text, &mut positions);
+
+        assert_eq!(&text[positions[0].start..][..positions[0].length], "été");
+        assert_eq!(&text[positions[1].start..][..positions[1].length], "Paris");
+    }
+
     #[test]
     fn calculate_matches() {
         let mut matches = Vec::new();
@@ -577,9 +995,9 @@ mod tests {
         }"#;
 
         let document: IndexMap<String, Value> = serde_json::from_str(data).unwrap();
-        let mut attributes_to_highlight = HashSet::new();
-        attributes_to_highlight.insert("title".to_string());
-        attributes_to_highlight.insert("description".to_string());
+        let mut attributes_to_highlight = HashMap::new();
+        attributes_to_highlight.insert("title".to_string(), HighlightTags::default());
+        attributes_to_highlight.insert("description".to_string(), HighlightTags::default());
 
         let mut matches = HashMap::new();
 
@@ -615,8 +1033,8 @@ mod tests {
         }"#;
 
         let document: IndexMap<String, Value> = serde_json::from_str(data).unwrap();
-        let mut attributes_to_highlight = HashSet::new();
-        attributes_to_highlight.insert("title".to_string());
+        let mut attributes_to_highlight = HashMap::new();
+        attributes_to_highlight.insert("title".to_string(), HighlightTags::default());
 
         let mut matches = HashMap::new();
 
@@ -641,4 +1059,30 @@ mod tests {
 
         assert_eq!(result, result_expected);
     }
+
+    #[test]
+    fn highlight_with_custom_tags() {
+        let data = r#"{
+            "title": "Fondation",
+            "description": "Seldon crée la Fondation."
+        }"#;
+
+        let document: IndexMap<String, Value> = serde_json::from_str(data).unwrap();
+        let mut attributes_to_highlight = HashMap::new();
+        let tags = HighlightTags { pre_tag: "<b>".to_string(), post_tag: "</b>".to_string() };
+        attributes_to_highlight.insert("title".to_string(), tags);
+
+        let mut matches = HashMap::new();
+        matches.insert("title".to_string(), vec![MatchPosition { start: 0, length: 9 }]);
+        matches.insert("description".to_string(), vec![MatchPosition { start: 15, length: 9 }]);
+
+        let result = super::calculate_highlights(&document, &matches, &attributes_to_highlight);
+
+        // the description is not highlighted
+        let mut result_expected = IndexMap::new();
+        result_expected.insert("title".to_string(), Value::String("<b>Fondation</b>".to_string()));
+        result_expected.insert("description".to_string(), Value::String("Seldon crée la Fondation.".to_string()));
+
+        assert_eq!(result, result_expected);
+    }
 }
@@ -1,6 +1,11 @@
 pub mod authentication;
+pub mod csv_documents;
+pub mod documents_import;
+pub mod graphql;
+pub mod json_stream;
 pub mod meilisearch;
 pub mod normalize_slashes;
+pub mod uploads;
 
 pub use authentication::Authentication;
 pub use normalize_slashes::NormalizeSlashes;
@@ -0,0 +1,269 @@
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use log::{error, info};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+
+use crate::error::ResponseError;
+
+/// The maximum number of chunks an upload can be made of.
+pub const MAX_CHUNKS: u32 = 10_000;
+
+/// The maximum interval between two removals of the stale uploads.
+const STALE_UPLOADS_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+const UPLOAD_ID_LEN: usize = 16;
+
+/// Chunks of a multi-part upload are stored on disk, in a directory dedicated to
+/// the upload, until the upload is completed or aborted.
+pub struct Upload {
+    path: PathBuf,
+}
+
+fn uploads_path(db_path: impl AsRef<Path>) -> PathBuf {
+    db_path.as_ref().join("uploads")
+}
+
+fn index_uploads_path(db_path: impl AsRef<Path>, index_uid: &str) -> Result<PathBuf, ResponseError> {
+    // the index uid is part of the path, it must not be able to escape the uploads directory
+    let valid_uid = !index_uid.is_empty()
+        && index_uid.chars().all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '_');
+    if !valid_uid {
+        return Err(ResponseError::InvalidIndexUid);
+    }
+    Ok(uploads_path(db_path).join(index_uid))
+}
+
+impl Upload {
+    fn path(db_path: &str, index_uid: &str, upload_id: &str) -> Result<PathBuf, ResponseError> {
+        let index_path = index_uploads_path(db_path, index_uid)?;
+
+        let valid = upload_id.len() == UPLOAD_ID_LEN && upload_id.chars().all(|c| c.is_ascii_alphanumeric());
+        if !valid {
+            return Err(ResponseError::not_found(format!("Upload {}", upload_id)));
+        }
+        Ok(index_path.join(upload_id))
+    }
+
+    pub fn create(db_path: &str, index_uid: &str) -> Result<(String, Upload), ResponseError> {
+        let upload_id: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(UPLOAD_ID_LEN)
+            .collect();
+
+        let path = Upload::path(db_path, index_uid, &upload_id)?;
+        fs::create_dir_all(&path).map_err(ResponseError::internal)?;
+
+        Ok((upload_id, Upload { path }))
+    }
+
+    pub fn open(db_path: &str, index_uid: &str, upload_id: &str) -> Result<Upload, ResponseError> {
+        let path = Upload::path(db_path, index_uid, upload_id)?;
+        if path.is_dir() {
+            Ok(Upload { path })
+        } else {
+            Err(ResponseError::not_found(format!("Upload {}", upload_id)))
+        }
+    }
+
+    /// Writes a chunk, replacing any chunk previously uploaded with the same number,
+    /// this way a client can safely retry a chunk that failed to be uploaded.
+    ///
+    /// The chunk is refused if the chunks of the upload would exceed `max_size` bytes together.
+    pub fn write_chunk(&self, number: u32, bytes: &[u8], max_size: usize) -> Result<(), ResponseError> {
+        if number >= MAX_CHUNKS {
+            return Err(ResponseError::bad_parameter(
+                "chunk",
+                format!("chunk numbers must be lower than {}", MAX_CHUNKS),
+            ));
+        }
+
+        let others_size: u64 = self
+            .chunks()?
+            .into_iter()
+            .filter(|n| *n != number)
+            .map(|n| self.chunk_size(n))
+            .sum::<Result<u64, ResponseError>>()?;
+        if others_size + bytes.len() as u64 > max_size as u64 {
+            return Err(ResponseError::PayloadTooLarge);
+        }
+
+        // the chunk is first written to a temporary file to never keep a partial chunk
+        let tmp_path = self.path.join(format!("{}.tmp", number));
+        fs::write(&tmp_path, bytes).map_err(ResponseError::internal)?;
+        fs::rename(&tmp_path, self.path.join(number.to_string())).map_err(ResponseError::internal)?;
+
+        Ok(())
+    }
+
+    fn chunk_size(&self, number: u32) -> Result<u64, ResponseError> {
+        match fs::metadata(self.path.join(number.to_string())) {
+            Ok(metadata) => Ok(metadata.len()),
+            // the chunk may have been removed by a concurrent abort
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(ResponseError::internal(e)),
+        }
+    }
+
+    /// Returns the numbers of the chunks received, in ascending order.
+    pub fn chunks(&self) -> Result<Vec<u32>, ResponseError> {
+        let mut chunks = Vec::new();
+        for entry in fs::read_dir(&self.path).map_err(ResponseError::internal)? {
+            let entry = entry.map_err(ResponseError::internal)?;
+            if let Some(number) = entry.file_name().to_str().and_then(|n| n.parse().ok()) {
+                chunks.push(number);
+            }
+        }
+        chunks.sort_unstable();
+        Ok(chunks)
+    }
+
+    /// Returns a reader of all the chunks one after the other, they must be numbered
+    /// from zero without any gap and must not exceed `max_size` bytes together.
+    pub fn reader(&self, max_size: usize) -> Result<ChunksReader, ResponseError> {
+        let chunks = self.chunks()?;
+        if chunks.is_empty() {
+            return Err(ResponseError::bad_request("the upload does not contain any chunk"));
+        }
+        if let Some(missing) = (0..).zip(&chunks).find(|(i, n)| i != *n).map(|(i, _)| i) {
+            return Err(ResponseError::bad_request(format!("the chunk {} is missing", missing)));
+        }
+
+        let size: u64 = chunks.iter().map(|n| self.chunk_size(*n)).sum::<Result<u64, ResponseError>>()?;
+        if size > max_size as u64 {
+            return Err(ResponseError::PayloadTooLarge);
+        }
+
+        let paths: Vec<_> = chunks.iter().map(|n| self.path.join(n.to_string())).collect();
+        Ok(ChunksReader {
+            paths: paths.into_iter(),
+            current: None,
+        })
+    }
+
+    pub fn remove(self) -> Result<(), ResponseError> {
+        remove_dir(&self.path).map_err(ResponseError::internal)
+    }
+
+    /// Removes all the uploads of an index, used when the index is deleted.
+    pub fn remove_all(db_path: &str, index_uid: &str) -> Result<(), ResponseError> {
+        let path = index_uploads_path(db_path, index_uid)?;
+        remove_dir(&path).map_err(ResponseError::internal)
+    }
+
+    /// Removes the uploads that didn't receive any chunk for `ttl`,
+    /// returns the number of uploads removed.
+    pub fn remove_stale(db_path: &str, ttl: Duration) -> io::Result<usize> {
+        let uploads_path = uploads_path(db_path);
+        let indexes = match fs::read_dir(&uploads_path) {
+            Ok(indexes) => indexes,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+
+        let mut removed = 0;
+        for index in indexes {
+            let index_path = index?.path();
+            for upload in fs::read_dir(&index_path)? {
+                let upload = upload?;
+                // writing a chunk creates and renames a file, this updates the directory mtime
+                let age = upload.metadata()?.modified()?.elapsed().unwrap_or_default();
+                if age >= ttl {
+                    remove_dir(&upload.path())?;
+                    removed += 1;
+                }
+            }
+            // the directory of the index is only removed once empty
+            let _ = fs::remove_dir(&index_path);
+        }
+
+        Ok(removed)
+    }
+}
+
+fn remove_dir(path: &Path) -> io::Result<()> {
+    match fs::remove_dir_all(path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Regularly removes the uploads that didn't receive any chunk for `ttl`.
+pub fn spawn_stale_uploads_removal(db_path: String, ttl: Duration) {
+    let interval = ttl.min(STALE_UPLOADS_INTERVAL).max(Duration::from_secs(1));
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        match Upload::remove_stale(&db_path, ttl) {
+            Ok(0) => (),
+            Ok(removed) => info!("{} stale uploads removed", removed),
+            Err(e) => error!("impossible to remove the stale uploads; {}", e),
+        }
+    });
+}
+
+/// Reads the chunks of an upload one after the other, a chunk file is only
+/// opened once the previous one has been entirely read.
+pub struct ChunksReader {
+    paths: std::vec::IntoIter<PathBuf>,
+    current: Option<File>,
+}
+
+impl Read for ChunksReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(file) = &mut self.current {
+                let count = file.read(buf)?;
+                if count != 0 || buf.is_empty() {
+                    return Ok(count);
+                }
+            }
+
+            match self.paths.next() {
+                Some(path) => self.current = Some(File::open(path)?),
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_chunks_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().to_str().unwrap();
+
+        let (_id, upload) = Upload::create(db_path, "books").unwrap();
+        upload.write_chunk(1, b"world", 16).unwrap();
+        upload.write_chunk(0, b"hello ", 16).unwrap();
+        // replacing a chunk only counts its new size
+        upload.write_chunk(0, b"hello ", 16).unwrap();
+        assert!(upload.write_chunk(2, b"!!!!!!", 16).is_err());
+
+        let mut content = String::new();
+        upload.reader(16).unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "hello world");
+        assert!(upload.reader(8).is_err());
+    }
+
+    #[test]
+    fn remove_stale_uploads() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().to_str().unwrap();
+
+        let (id, upload) = Upload::create(db_path, "books").unwrap();
+        upload.write_chunk(0, b"[]", 16).unwrap();
+
+        assert_eq!(Upload::remove_stale(db_path, Duration::from_secs(3600)).unwrap(), 0);
+        assert!(Upload::open(db_path, "books", &id).is_ok());
+
+        assert_eq!(Upload::remove_stale(db_path, Duration::from_secs(0)).unwrap(), 1);
+        assert!(Upload::open(db_path, "books", &id).is_err());
+    }
+}
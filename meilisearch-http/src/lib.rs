@@ -1,11 +1,16 @@
 #![allow(clippy::or_fun_call)]
 
 pub mod data;
+pub mod dump;
 pub mod error;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 pub mod helpers;
 pub mod models;
 pub mod option;
 pub mod routes;
+pub mod standby;
+pub mod webhook;
 
 pub use self::data::Data;
 use self::error::json_error_handler;
@@ -35,17 +40,30 @@ pub fn create_app(
                 .content_type(|_mime| true) // Accept all mime types
                 .error_handler(|err, _req| json_error_handler(err).into()),
         )
+        .app_data(web::PayloadConfig::new(1024 * 1024 * 10)) // Raw payload Limit of 10Mb
         .service(routes::load_html)
         .service(routes::load_css)
+        .configure(routes::alias::services)
         .configure(routes::document::services)
+        .configure(routes::upload::services)
+        .configure(routes::batch::services)
         .configure(routes::index::services)
+        .configure(routes::facet::services)
+        .configure(routes::query_rule::services)
         .configure(routes::search::services)
+        .configure(routes::graphql::services)
         .configure(routes::setting::services)
         .configure(routes::stop_words::services)
         .configure(routes::synonym::services)
         .configure(routes::health::services)
         .configure(routes::stats::services)
+        .configure(routes::compaction::services)
+        .configure(routes::dump::services)
+        .configure(routes::snapshot::services)
+        .configure(routes::standby::services)
         .configure(routes::key::services)
+        .configure(routes::words::services)
+        .configure(routes::analytics::services)
 }
 
 pub fn index_update_callback(index_uid: &str, data: &Data, status: ProcessedUpdateResult) {
@@ -63,10 +81,6 @@ pub fn index_update_callback(index_uid: &str, data: &Data, status: ProcessedUpda
             }
         };
 
-        if let Err(e) = data.compute_stats(&mut writer, &index_uid) {
-            error!("Impossible to compute stats; {}", e)
-        }
-
         if let Err(e) = data.set_last_update(&mut writer) {
             error!("Impossible to update last_update; {}", e)
         }
@@ -60,8 +60,28 @@ async fn main() -> Result<(), MainError> {
         index_update_callback(name, &data_cloned, status);
     }));
 
+    #[cfg(feature = "grpc")]
+    if let Some(ref grpc_addr) = opt.grpc_addr {
+        let addr = grpc_addr
+            .parse()
+            .map_err(|_| format!("Invalid gRPC address: {}", grpc_addr))?;
+        let data = data.clone();
+        actix_rt::spawn(async move {
+            if let Err(e) = meilisearch_http::grpc::serve(data, addr).await {
+                log::error!("The gRPC server stopped; {}", e);
+            }
+        });
+    }
+
+    #[cfg(not(feature = "grpc"))]
+    if opt.grpc_addr.is_some() {
+        return Err("MeiliSearch must be compiled with the grpc feature to serve the gRPC API".into());
+    }
+
     print_launch_resume(&opt, &data);
 
+    let db = data.db.clone();
+
     HttpServer::new(move || {
         create_app(&data)
             .wrap(
@@ -78,6 +98,9 @@ async fn main() -> Result<(), MainError> {
     .run()
     .await?;
 
+    // the updates being processed are not applied when the server stops
+    db.shutdown()?;
+
     Ok(())
 }
 
@@ -97,6 +120,9 @@ pub fn print_launch_resume(opt: &Opt, data: &Data) {
 
     eprintln!("Database path:\t\t{:?}", opt.db_path);
     eprintln!("Server listening on:\t{:?}", opt.http_addr);
+    if let Some(ref grpc_addr) = opt.grpc_addr {
+        eprintln!("gRPC listening on:\t{:?}", grpc_addr);
+    }
     eprintln!("Environment:\t\t{:?}", opt.env);
     eprintln!("Commit SHA:\t\t{:?}", env!("VERGEN_SHA").to_string());
     eprintln!(
@@ -34,4 +34,99 @@ pub struct Opt {
     /// The maximum size, in bytes, of the update lmdb database directory
     #[structopt(long, env = "MEILI_UPDATE_MAP_SIZE", default_value = "107374182400")] // 100GB
     pub update_map_size: usize,
+
+    /// The maximum size, in bytes, the main lmdb map grows to when it is full, it never grows if not set
+    #[structopt(long, env = "MEILI_MAX_MAIN_MAP_SIZE")]
+    pub max_main_map_size: Option<usize>,
+
+    /// The maximum size, in bytes, the update lmdb map grows to when it is full, it never grows if not set
+    #[structopt(long, env = "MEILI_MAX_UPDATE_MAP_SIZE")]
+    pub max_update_map_size: Option<usize>,
+
+    /// The maximum size, in bytes, of a whole payload sent to the documents addition routes,
+    /// the documents are kept in memory until the update is enqueued.
+    #[structopt(long, env = "MEILI_HTTP_PAYLOAD_SIZE_LIMIT", default_value = "104857600")] // 100MB
+    pub http_payload_size_limit: usize,
+
+    /// The maximum size, in bytes, of a single document sent to the documents addition routes.
+    #[structopt(long, env = "MEILI_HTTP_PAYLOAD_DOCUMENT_SIZE_LIMIT", default_value = "10485760")] // 10MB
+    pub http_payload_document_size_limit: usize,
+
+    /// The maximum size, in bytes, of the payloads of the updates an index can have waiting
+    /// to be processed, new updates are rejected until the queue is drained.
+    #[structopt(long, env = "MEILI_MAX_ENQUEUED_UPDATES_SIZE")]
+    pub max_enqueued_updates_size: Option<u64>,
+
+    /// The maximum number of updates an index can have waiting to be processed,
+    /// new updates are rejected until the queue is drained.
+    #[structopt(long, env = "MEILI_MAX_ENQUEUED_UPDATES")]
+    pub max_enqueued_updates: Option<u64>,
+
+    /// The maximum number of processed updates kept by each index, the oldest ones are deleted.
+    #[structopt(long, env = "MEILI_UPDATES_RETENTION_MAX_ENTRIES")]
+    pub updates_retention_max_entries: Option<usize>,
+
+    /// The number of seconds the processed updates are kept, the older ones are deleted.
+    #[structopt(long, env = "MEILI_UPDATES_RETENTION_MAX_AGE_SEC")]
+    pub updates_retention_max_age_sec: Option<u64>,
+
+    /// The number of seconds between two deletions of the documents of which the `_expiresAt` date is passed.
+    #[structopt(long, env = "MEILI_EXPIRATION_PURGE_INTERVAL_SEC", default_value = "60")]
+    pub expiration_purge_interval_sec: u64,
+
+    /// The number of searches recorded by each index for the search analytics, the oldest
+    /// ones are forgotten first, the searches are not recorded when it is not set.
+    #[structopt(long, env = "MEILI_SEARCH_ANALYTICS_CAPACITY")]
+    pub search_analytics_capacity: Option<usize>,
+
+    /// The url to which a notification is posted every time an update has been processed.
+    #[structopt(long, env = "MEILI_UPDATE_WEBHOOK_URL")]
+    pub update_webhook_url: Option<String>,
+
+    /// The directory where the snapshots and the update log are shipped for a standby to follow.
+    #[structopt(long, env = "MEILI_STANDBY_SHIP_DIR")]
+    pub standby_ship_dir: Option<String>,
+
+    /// The interval, in seconds, between two snapshots shipped to the standby directory.
+    #[structopt(long, env = "MEILI_STANDBY_SNAPSHOT_INTERVAL_SEC", default_value = "3600")]
+    pub standby_snapshot_interval_sec: u64,
+
+    /// Runs as a standby of the primary shipping to this directory, updates are refused
+    /// until the standby is promoted.
+    #[structopt(long, env = "MEILI_STANDBY_OF", conflicts_with = "standby_ship_dir")]
+    pub standby_of: Option<String>,
+
+    /// The directory where the snapshots are created.
+    #[structopt(long, env = "MEILI_SNAPSHOT_DIR", default_value = "snapshots/")]
+    pub snapshot_dir: String,
+
+    /// Starts from the environments of a snapshot when the database doesn't exist yet.
+    #[structopt(long, env = "MEILI_IMPORT_SNAPSHOT", conflicts_with = "standby_of")]
+    pub import_snapshot: Option<String>,
+
+    /// The directory where the dumps are created.
+    #[structopt(long, env = "MEILI_DUMPS_DIR", default_value = "dumps/")]
+    pub dumps_dir: String,
+
+    /// Imports the indexes of a dump when the database doesn't contain any index yet.
+    #[structopt(long, env = "MEILI_IMPORT_DUMP")]
+    pub import_dump: Option<String>,
+
+    /// The number of seconds a chunked upload is kept without receiving any chunk, it is removed after.
+    #[structopt(long, env = "MEILI_UPLOADS_TTL_SEC", default_value = "86400")]
+    pub uploads_ttl_sec: u64,
+
+    /// Allows the documents import to fetch files from loopback, private and link-local
+    /// addresses, by default only public addresses can be imported from.
+    #[structopt(long, env = "MEILI_IMPORT_ALLOW_PRIVATE_NETWORKS")]
+    pub import_allow_private_networks: bool,
+
+    /// Serves the search and the documents of the indexes through a GraphQL endpoint,
+    /// it supports a subset of GraphQL and can't be introspected, see `/graphql/schema`.
+    #[structopt(long, env = "MEILI_ENABLE_GRAPHQL")]
+    pub enable_graphql: bool,
+
+    /// The address on which the gRPC server listens, requires the `grpc` feature.
+    #[structopt(long, env = "MEILI_GRPC_ADDR")]
+    pub grpc_addr: Option<String>,
 }
@@ -0,0 +1,92 @@
+use actix_web::{web, HttpResponse};
+use actix_web_macros::{delete, get, post};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ResponseError;
+use crate::helpers::Authentication;
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(list_aliases)
+        .service(update_alias)
+        .service(delete_alias)
+        .service(swap_indexes);
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct Alias {
+    alias: String,
+    index_uid: String,
+}
+
+#[derive(Deserialize)]
+struct AliasParam {
+    alias: String,
+}
+
+#[get("/aliases", wrap = "Authentication::Private")]
+async fn list_aliases(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    let aliases: Vec<_> = data
+        .db
+        .aliases()
+        .into_iter()
+        .map(|(alias, index_uid)| Alias { alias, index_uid })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(aliases))
+}
+
+/// Creates an alias or moves it to another index, the requests
+/// on the alias are then routed to this index.
+#[post("/aliases", wrap = "Authentication::Private")]
+async fn update_alias(
+    data: web::Data<Data>,
+    body: web::Json<Alias>,
+) -> Result<HttpResponse, ResponseError> {
+    data.check_writable()?;
+
+    let is_valid = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    if body.alias.is_empty() || !body.alias.chars().all(is_valid) {
+        return Err(ResponseError::InvalidIndexUid);
+    }
+
+    data.db.put_alias(&body.alias, &body.index_uid)?;
+
+    Ok(HttpResponse::Ok().json(body.into_inner()))
+}
+
+#[delete("/aliases/{alias}", wrap = "Authentication::Private")]
+async fn delete_alias(
+    data: web::Data<Data>,
+    path: web::Path<AliasParam>,
+) -> Result<HttpResponse, ResponseError> {
+    data.check_writable()?;
+
+    if !data.db.delete_alias(&path.alias)? {
+        return Err(ResponseError::NotFound(format!("alias {}", path.alias)));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct SwapIndexesRequest {
+    indexes: (String, String),
+}
+
+/// Exchanges two indexes at once, a new version of an index
+/// can be built aside and then swapped with the served one.
+#[post("/swap-indexes", wrap = "Authentication::Private")]
+async fn swap_indexes(
+    data: web::Data<Data>,
+    body: web::Json<SwapIndexesRequest>,
+) -> Result<HttpResponse, ResponseError> {
+    data.check_writable()?;
+
+    let (lhs, rhs) = &body.indexes;
+    data.db.swap_indexes(lhs, rhs)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
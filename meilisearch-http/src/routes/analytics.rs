@@ -0,0 +1,64 @@
+use actix_web::web;
+use actix_web::HttpResponse;
+use actix_web_macros::get;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::error::ResponseError;
+use crate::helpers::Authentication;
+use crate::routes::IndexParam;
+use crate::Data;
+
+/// The number of queries returned by default.
+const DEFAULT_QUERIES_LIMIT: usize = 20;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_top_queries).service(get_zero_result_queries);
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct AnalyticsQuery {
+    since: Option<DateTime<Utc>>,
+    limit: Option<usize>,
+}
+
+/// Returns the most searched queries of the index, the searches are only
+/// recorded when the search analytics are enabled.
+#[get("/indexes/{index_uid}/analytics/top-queries", wrap = "Authentication::Private")]
+async fn get_top_queries(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    params: web::Query<AnalyticsQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let reader = data.db.update_read_txn()?;
+    let limit = params.limit.unwrap_or(DEFAULT_QUERIES_LIMIT);
+    let queries = index.search_analytics.top_queries(&reader, params.since, limit)?;
+
+    Ok(HttpResponse::Ok().json(queries))
+}
+
+/// Returns the most searched queries of the index that returned no hit, they
+/// often are the ones missing synonyms or stop words.
+#[get("/indexes/{index_uid}/analytics/zero-result-queries", wrap = "Authentication::Private")]
+async fn get_zero_result_queries(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    params: web::Query<AnalyticsQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let reader = data.db.update_read_txn()?;
+    let limit = params.limit.unwrap_or(DEFAULT_QUERIES_LIMIT);
+    let queries = index.search_analytics.zero_result_queries(&reader, params.since, limit)?;
+
+    Ok(HttpResponse::Ok().json(queries))
+}
@@ -0,0 +1,134 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web_macros::post;
+use indexmap::IndexMap;
+use meilisearch_core::settings::Settings;
+use meilisearch_core::{update, DuplicatesPolicy};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::error::ResponseError;
+use crate::helpers::Authentication;
+use crate::routes::document::{addition_options, ensure_primary_key};
+use crate::routes::setting::{check_indexing_limits, check_typo_tolerance};
+use crate::routes::{IndexParam, IndexUpdateResponse};
+use crate::Data;
+
+type Document = IndexMap<String, Value>;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(push_batch);
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+enum BatchOperation {
+    #[serde(rename_all = "camelCase")]
+    AddDocuments {
+        documents: Vec<Document>,
+        duplicates: Option<DuplicatesPolicy>,
+        generate_ids: Option<bool>,
+    },
+    #[serde(rename_all = "camelCase")]
+    UpdateDocuments {
+        documents: Vec<Document>,
+        duplicates: Option<DuplicatesPolicy>,
+        generate_ids: Option<bool>,
+    },
+    DeleteDocuments {
+        ids: Vec<Value>,
+    },
+    UpdateSettings {
+        settings: Settings,
+    },
+    ClearDocuments,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct BatchQuery {
+    primary_key: Option<String>,
+}
+
+/// Enqueues several operations as a single update, they are applied atomically:
+/// searches never see the index with only a part of them applied.
+///
+/// The route requires the documents access, a batch updating the settings
+/// also requires the settings access.
+#[post("/indexes/{index_uid}/batch", wrap = "Authentication::Documents")]
+async fn push_batch(
+    data: web::Data<Data>,
+    req: HttpRequest,
+    path: web::Path<IndexParam>,
+    params: web::Query<BatchQuery>,
+    body: web::Json<Vec<BatchOperation>>,
+) -> Result<HttpResponse, ResponseError> {
+    let operations = body.into_inner();
+    if operations.is_empty() {
+        return Err(ResponseError::bad_request("a batch must contain at least one operation"));
+    }
+
+    let updates_settings = operations
+        .iter()
+        .any(|operation| matches!(operation, BatchOperation::UpdateSettings { .. }));
+    if updates_settings {
+        // a header that is not valid UTF-8 is considered missing
+        let key = req.headers().get("X-Meili-API-Key").and_then(|key| key.to_str().ok());
+        Authentication::Settings.authorize(&data, Some(&path.index_uid), key)?;
+    }
+
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    // the primary key is inferred from the first document added by the batch
+    let first_addition = operations.iter().find_map(|operation| match operation {
+        BatchOperation::AddDocuments { documents, generate_ids, .. }
+        | BatchOperation::UpdateDocuments { documents, generate_ids, .. } => {
+            Some((documents.first(), generate_ids.unwrap_or(false)))
+        }
+        _ => None,
+    });
+    if let Some((first_document, generate_ids)) = first_addition {
+        ensure_primary_key(&data, &index, &params.primary_key, first_document, generate_ids)?;
+    }
+
+    let format = index.main.document_id_format(&data.db.main_read_txn()?)?.unwrap_or_default();
+    let mut batch = index.updates_batch();
+
+    for operation in operations {
+        match operation {
+            BatchOperation::AddDocuments { documents, duplicates, generate_ids } => {
+                batch.add_documents(documents, addition_options(duplicates, generate_ids))?;
+            }
+            BatchOperation::UpdateDocuments { documents, duplicates, generate_ids } => {
+                batch.update_documents(documents, addition_options(duplicates, generate_ids))?;
+            }
+            BatchOperation::DeleteDocuments { ids } => {
+                let mut documents_ids = Vec::with_capacity(ids.len());
+                for document_id in ids {
+                    let document_id = update::document_id_to_string(&document_id)
+                        .ok_or_else(|| ResponseError::bad_request("documents ids must be strings or numbers"))?;
+                    let document_id = update::compute_document_id(&document_id, format)
+                        .map_err(ResponseError::bad_request)?;
+                    documents_ids.push(document_id);
+                }
+                batch.delete_documents(documents_ids);
+            }
+            BatchOperation::UpdateSettings { settings } => {
+                let settings = settings.into_update().map_err(ResponseError::bad_request)?;
+                check_indexing_limits(&settings)?;
+                check_typo_tolerance(&settings)?;
+                batch.update_settings(settings);
+            }
+            BatchOperation::ClearDocuments => batch.clear_all(),
+        }
+    }
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = batch.finalize(&mut writer)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
@@ -0,0 +1,33 @@
+use actix_web::{web, HttpResponse};
+use actix_web_macros::{get, post};
+use serde::Serialize;
+
+use crate::error::ResponseError;
+use crate::helpers::Authentication;
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_compaction).service(schedule_compaction);
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CompactionResponse {
+    scheduled: bool,
+}
+
+#[get("/compaction", wrap = "Authentication::Private")]
+async fn get_compaction(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    let scheduled = data.db.is_compaction_scheduled();
+
+    Ok(HttpResponse::Ok().json(CompactionResponse { scheduled }))
+}
+
+/// The environments are compacted the next time MeiliSearch starts,
+/// before the database is opened.
+#[post("/compaction", wrap = "Authentication::Private")]
+async fn schedule_compaction(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    data.db.schedule_compaction()?;
+
+    Ok(HttpResponse::Accepted().json(CompactionResponse { scheduled: true }))
+}
@@ -1,13 +1,21 @@
-use std::collections::{BTreeSet, HashSet};
+use std::collections::HashSet;
 
-use actix_web::{web, HttpResponse};
+use actix_http::encoding::Decoder;
+use actix_web::error::BlockingError;
+use actix_web::{web, HttpRequest, HttpResponse};
 use actix_web_macros::{delete, get, post, put};
+use futures::StreamExt;
 use indexmap::IndexMap;
-use meilisearch_core::{update, Error};
-use serde::Deserialize;
+use meilisearch_core::store::BrowseOrder;
+use meilisearch_core::{update, AdditionOptions, DuplicatesPolicy, Filter, Index};
+use sdset::SetOperation;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::error::ResponseError;
+use crate::helpers::csv_documents::{csv_delimiter, read_csv_documents};
+use crate::helpers::documents_import::{ImportFormat, RemoteImport, MAX_IMPORT_SIZE};
+use crate::helpers::json_stream::DocumentsStream;
 use crate::helpers::Authentication;
 use crate::routes::{IndexParam, IndexUpdateResponse};
 use crate::Data;
@@ -21,12 +29,18 @@ struct DocumentParam {
 }
 
 pub fn services(cfg: &mut web::ServiceConfig) {
-    cfg.service(get_document)
+    cfg.service(count_documents)
+        .service(get_document)
         .service(delete_document)
         .service(get_all_documents)
         .service(add_documents)
         .service(update_documents)
+        .service(add_csv_documents)
+        .service(update_csv_documents)
+        .service(import_documents)
+        .service(import_partial_documents)
         .service(delete_documents)
+        .service(purge_deleted_documents)
         .service(clear_all_documents);
 }
 
@@ -43,8 +57,9 @@ async fn get_document(
         .open_index(&path.index_uid)
         .ok_or(ResponseError::index_not_found(&path.index_uid))?;
 
-    let document_id = update::compute_document_id(&path.document_id).map_err(Error::Serializer)?;
     let reader = data.db.main_read_txn()?;
+    let format = index.main.document_id_format(&reader)?.unwrap_or_default();
+    let document_id = update::compute_document_id(&path.document_id, format).map_err(ResponseError::bad_request)?;
 
     let response: Document = index
         .document(&reader, None, document_id)?
@@ -55,7 +70,7 @@ async fn get_document(
 
 #[delete(
     "/indexes/{index_uid}/documents/{document_id}",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Documents"
 )]
 async fn delete_document(
     data: web::Data<Data>,
@@ -66,9 +81,11 @@ async fn delete_document(
         .open_index(&path.index_uid)
         .ok_or(ResponseError::index_not_found(&path.index_uid))?;
 
-    let document_id = update::compute_document_id(&path.document_id).map_err(Error::Serializer)?;
+    let format = index.main.document_id_format(&data.db.main_read_txn()?)?.unwrap_or_default();
+    let document_id = update::compute_document_id(&path.document_id, format).map_err(ResponseError::bad_request)?;
 
     let mut update_writer = data.db.update_write_txn()?;
+    data.check_update_queue(&update_writer, &index)?;
 
     let mut documents_deletion = index.documents_deletion();
     documents_deletion.delete_document_by_id(document_id);
@@ -86,6 +103,7 @@ struct BrowseQuery {
     offset: Option<usize>,
     limit: Option<usize>,
     attributes_to_retrieve: Option<String>,
+    sort_by_id: Option<bool>,
 }
 
 #[get("/indexes/{index_uid}/documents", wrap = "Authentication::Public")]
@@ -102,55 +120,42 @@ async fn get_all_documents(
     let offset = params.offset.unwrap_or(0);
     let limit = params.limit.unwrap_or(20);
 
-    let reader = data.db.main_read_txn()?;
-
-    let documents_ids: Result<BTreeSet<_>, _> = index
-        .documents_fields_counts
-        .documents_ids(&reader)?
-        .skip(offset)
-        .take(limit)
-        .collect();
+    let order = if params.sort_by_id.unwrap_or(false) {
+        BrowseOrder::UserId
+    } else {
+        BrowseOrder::InternalId
+    };
 
-    let documents_ids = documents_ids?;
+    let reader = data.db.main_read_txn()?;
 
     let attributes: Option<HashSet<&str>> = params
         .attributes_to_retrieve
         .as_ref()
         .map(|a| a.split(',').collect());
 
-    let mut response = Vec::new();
-    for document_id in documents_ids {
-        if let Ok(Some(document)) =
-            index.document::<Document>(&reader, attributes.as_ref(), document_id)
-        {
-            response.push(document);
-        }
-    }
+    let response: Vec<Document> = index.browse(&reader, attributes.as_ref(), order, offset, limit)?;
 
     Ok(HttpResponse::Ok().json(response))
 }
 
-fn find_primary_key(document: &IndexMap<String, Value>) -> Option<String> {
-    for key in document.keys() {
-        if key.to_lowercase().contains("id") {
-            return Some(key.to_string());
-        }
-    }
-    None
-}
-
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
-struct UpdateDocumentsQuery {
-    primary_key: Option<String>,
+struct CountQuery {
+    #[serde(alias = "filter")]
+    filters: Option<String>,
 }
 
-async fn update_multiple_documents(
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CountResponse {
+    number_of_documents: u64,
+}
+
+#[get("/indexes/{index_uid}/documents/count", wrap = "Authentication::Public")]
+async fn count_documents(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
-    params: web::Query<UpdateDocumentsQuery>,
-    body: web::Json<Vec<Document>>,
-    is_partial: bool,
+    params: web::Query<CountQuery>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
         .db
@@ -159,69 +164,307 @@ async fn update_multiple_documents(
 
     let reader = data.db.main_read_txn()?;
 
+    let number_of_documents = match &params.filters {
+        Some(filters) => {
+            let schema = index
+                .main
+                .schema(&reader)?
+                .ok_or(ResponseError::internal("missing schema"))?;
+            let filter = Filter::parse(filters, &schema)?;
+            let candidates = filter.candidates(&reader, &index)?;
+            let deleted = index.deleted_documents(&reader)?;
+            sdset::duo::Difference::new(&candidates, &deleted).into_set_buf().len() as u64
+        }
+        None => index.main.number_of_documents(&reader)?,
+    };
+
+    Ok(HttpResponse::Ok().json(CountResponse { number_of_documents }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct UpdateDocumentsQuery {
+    primary_key: Option<String>,
+    duplicates: Option<DuplicatesPolicy>,
+    generate_ids: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct UpdateCsvDocumentsQuery {
+    primary_key: Option<String>,
+    csv_delimiter: Option<char>,
+    infer_types: Option<bool>,
+    duplicates: Option<DuplicatesPolicy>,
+    generate_ids: Option<bool>,
+}
+
+pub(crate) fn addition_options(duplicates: Option<DuplicatesPolicy>, generate_ids: Option<bool>) -> AdditionOptions {
+    AdditionOptions {
+        duplicates_policy: duplicates.unwrap_or_default(),
+        generate_ids: generate_ids.unwrap_or(false),
+        primary_key_suffix: None,
+    }
+}
+
+/// Sets the primary key of the index if it doesn't have one yet and one is given by the
+/// user. Otherwise the update infers it from its first document, the addition is rejected
+/// right away when it cannot, unless the ids are generated.
+pub(crate) fn ensure_primary_key(
+    data: &Data,
+    index: &Index,
+    primary_key: &Option<String>,
+    first_document: Option<&Document>,
+    generate_ids: bool,
+) -> Result<(), ResponseError> {
+    let reader = data.db.main_read_txn()?;
+
     let mut schema = index
         .main
         .schema(&reader)?
         .ok_or(ResponseError::internal("Impossible to retrieve the schema"))?;
 
     if schema.primary_key().is_none() {
-        let id = match &params.primary_key {
-            Some(id) => id.to_string(),
-            None => body
-                .first()
-                .and_then(find_primary_key)
-                .ok_or(ResponseError::bad_request("Could not infer a primary key"))?,
-        };
-
-        let mut writer = data.db.main_write_txn()?;
-
-        schema
-            .set_primary_key(&id)
-            .map_err(ResponseError::bad_request)?;
-        index.main.put_schema(&mut writer, &schema)?;
-        writer.commit()?;
+        match primary_key {
+            Some(id) => {
+                let mut writer = data.db.main_write_txn()?;
+
+                schema
+                    .set_primary_key(id)
+                    .map_err(ResponseError::bad_request)?;
+                index.main.put_schema(&mut writer, &schema)?;
+                writer.commit()?;
+            }
+            None => {
+                let inferable = first_document
+                    .and_then(|document| update::infer_primary_key(document, update::DEFAULT_PRIMARY_KEY_SUFFIX))
+                    .is_some();
+                if !inferable && !generate_ids {
+                    return Err(ResponseError::bad_request("Could not infer a primary key"));
+                }
+            }
+        }
     }
 
+    Ok(())
+}
+
+pub(crate) async fn update_multiple_documents(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    primary_key: Option<String>,
+    documents: Vec<Document>,
+    is_partial: bool,
+    options: AdditionOptions,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    ensure_primary_key(&data, &index, &primary_key, documents.first(), options.generate_ids)?;
+
     let mut document_addition = if is_partial {
         index.documents_partial_addition()
     } else {
         index.documents_addition()
     };
+    document_addition.set_options(options);
 
-    for document in body.into_inner() {
+    for document in documents {
         document_addition.update_document(document);
     }
 
     let mut update_writer = data.db.update_write_txn()?;
+    data.check_update_queue(&update_writer, &index)?;
     let update_id = document_addition.finalize(&mut update_writer)?;
     update_writer.commit()?;
 
     Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
 }
 
-#[post("/indexes/{index_uid}/documents", wrap = "Authentication::Private")]
+/// Parses the documents while the payload is received and hands them to the
+/// documents addition one by one, the raw payload is never buffered but the parsed
+/// documents are kept in memory until the update is enqueued, hence the size limit.
+async fn stream_multiple_documents(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    primary_key: Option<String>,
+    req: HttpRequest,
+    payload: web::Payload,
+    is_partial: bool,
+    options: AdditionOptions,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let generate_ids = options.generate_ids;
+    let mut document_addition = if is_partial {
+        index.documents_partial_addition()
+    } else {
+        index.documents_addition()
+    };
+    document_addition.set_options(options);
+
+    // reject the update before receiving the payload if the queue is already full
+    data.check_update_queue(&data.db.update_read_txn()?, &index)?;
+
+    // compressed payloads are not decoded by the raw payload extractor
+    let mut payload = Decoder::from_headers(payload.into_inner(), req.headers());
+    let mut documents_stream = DocumentsStream::new(data.http_payload_document_size_limit);
+    let mut primary_key_checked = false;
+    let mut payload_size = 0;
+
+    while let Some(bytes) = payload.next().await {
+        let bytes = bytes.map_err(ResponseError::bad_request)?;
+        // the decoded size is limited, a small compressed payload can expand a lot
+        payload_size += bytes.len();
+        if payload_size > data.http_payload_size_limit {
+            return Err(ResponseError::PayloadTooLarge);
+        }
+        for document in documents_stream.feed(&bytes)? {
+            if !primary_key_checked {
+                ensure_primary_key(&data, &index, &primary_key, Some(&document), generate_ids)?;
+                primary_key_checked = true;
+            }
+            document_addition.update_document(document);
+        }
+    }
+    documents_stream.finish()?;
+
+    if !primary_key_checked {
+        ensure_primary_key(&data, &index, &primary_key, None, generate_ids)?;
+    }
+
+    let mut update_writer = data.db.update_write_txn()?;
+    data.check_update_queue(&update_writer, &index)?;
+    let update_id = document_addition.finalize(&mut update_writer)?;
+    update_writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[post("/indexes/{index_uid}/documents", wrap = "Authentication::Documents")]
 async fn add_documents(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
     params: web::Query<UpdateDocumentsQuery>,
-    body: web::Json<Vec<Document>>,
+    req: HttpRequest,
+    payload: web::Payload,
 ) -> Result<HttpResponse, ResponseError> {
-    update_multiple_documents(data, path, params, body, false).await
+    let params = params.into_inner();
+    let options = addition_options(params.duplicates, params.generate_ids);
+    stream_multiple_documents(data, path, params.primary_key, req, payload, false, options).await
 }
 
-#[put("/indexes/{index_uid}/documents", wrap = "Authentication::Private")]
+#[put("/indexes/{index_uid}/documents", wrap = "Authentication::Documents")]
 async fn update_documents(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
     params: web::Query<UpdateDocumentsQuery>,
-    body: web::Json<Vec<Document>>,
+    req: HttpRequest,
+    payload: web::Payload,
+) -> Result<HttpResponse, ResponseError> {
+    let params = params.into_inner();
+    let options = addition_options(params.duplicates, params.generate_ids);
+    stream_multiple_documents(data, path, params.primary_key, req, payload, true, options).await
+}
+
+#[post("/indexes/{index_uid}/documents/csv", wrap = "Authentication::Documents")]
+async fn add_csv_documents(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    params: web::Query<UpdateCsvDocumentsQuery>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ResponseError> {
+    let params = params.into_inner();
+    let delimiter = csv_delimiter(params.csv_delimiter)?;
+    let documents = read_csv_documents(&body[..], delimiter, params.infer_types.unwrap_or(false))?;
+    let options = addition_options(params.duplicates, params.generate_ids);
+    update_multiple_documents(data, path, params.primary_key, documents, false, options).await
+}
+
+#[put("/indexes/{index_uid}/documents/csv", wrap = "Authentication::Documents")]
+async fn update_csv_documents(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    params: web::Query<UpdateCsvDocumentsQuery>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ResponseError> {
+    let params = params.into_inner();
+    let delimiter = csv_delimiter(params.csv_delimiter)?;
+    let documents = read_csv_documents(&body[..], delimiter, params.infer_types.unwrap_or(false))?;
+    let options = addition_options(params.duplicates, params.generate_ids);
+    update_multiple_documents(data, path, params.primary_key, documents, true, options).await
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ImportDocumentsRequest {
+    url: String,
+    format: Option<ImportFormat>,
+    sha256: Option<String>,
+    primary_key: Option<String>,
+    csv_delimiter: Option<char>,
+    infer_types: Option<bool>,
+    duplicates: Option<DuplicatesPolicy>,
+    generate_ids: Option<bool>,
+}
+
+async fn import_remote_documents(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: ImportDocumentsRequest,
+    is_partial: bool,
+) -> Result<HttpResponse, ResponseError> {
+    if data.db.open_index(&path.index_uid).is_none() {
+        return Err(ResponseError::index_not_found(&path.index_uid));
+    }
+
+    let import = RemoteImport {
+        url: body.url,
+        format: body.format,
+        sha256: body.sha256,
+        csv_delimiter: csv_delimiter(body.csv_delimiter)?,
+        infer_types: body.infer_types.unwrap_or(false),
+        max_size: MAX_IMPORT_SIZE,
+        max_document_size: data.http_payload_document_size_limit,
+        allow_private_networks: data.import_allow_private_networks,
+    };
+    let documents = web::block(move || import.fetch_documents())
+        .await
+        .map_err(|e| match e {
+            BlockingError::Error(e) => e,
+            BlockingError::Canceled => ResponseError::internal("the document import was canceled"),
+        })?;
+
+    let options = addition_options(body.duplicates, body.generate_ids);
+    update_multiple_documents(data, path, body.primary_key, documents, is_partial, options).await
+}
+
+#[post("/indexes/{index_uid}/documents/import", wrap = "Authentication::Documents")]
+async fn import_documents(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<ImportDocumentsRequest>,
 ) -> Result<HttpResponse, ResponseError> {
-    update_multiple_documents(data, path, params, body, true).await
+    import_remote_documents(data, path, body.into_inner(), false).await
+}
+
+#[put("/indexes/{index_uid}/documents/import", wrap = "Authentication::Documents")]
+async fn import_partial_documents(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<ImportDocumentsRequest>,
+) -> Result<HttpResponse, ResponseError> {
+    import_remote_documents(data, path, body.into_inner(), true).await
 }
 
 #[post(
     "/indexes/{index_uid}/documents/delete-batch",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Documents"
 )]
 async fn delete_documents(
     data: web::Data<Data>,
@@ -233,13 +476,17 @@ async fn delete_documents(
         .open_index(&path.index_uid)
         .ok_or(ResponseError::index_not_found(&path.index_uid))?;
 
+    let format = index.main.document_id_format(&data.db.main_read_txn()?)?.unwrap_or_default();
+
     let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
 
     let mut documents_deletion = index.documents_deletion();
 
     for document_id in body.into_inner() {
-        let document_id = update::value_to_string(&document_id);
-        let document_id = update::compute_document_id(&document_id).map_err(Error::Serializer)?;
+        let document_id = update::document_id_to_string(&document_id)
+            .ok_or_else(|| ResponseError::bad_request("documents ids must be strings or numbers"))?;
+        let document_id = update::compute_document_id(&document_id, format).map_err(ResponseError::bad_request)?;
         documents_deletion.delete_document_by_id(document_id);
     }
 
@@ -250,7 +497,30 @@ async fn delete_documents(
     Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
 }
 
-#[delete("/indexes/{index_uid}/documents", wrap = "Authentication::Private")]
+#[post(
+    "/indexes/{index_uid}/documents/purge",
+    wrap = "Authentication::Documents"
+)]
+async fn purge_deleted_documents(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+
+    let update_id = index.purge_deleted_documents(&mut writer)?;
+
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete("/indexes/{index_uid}/documents", wrap = "Authentication::Documents")]
 async fn clear_all_documents(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
@@ -261,6 +531,7 @@ async fn clear_all_documents(
         .ok_or(ResponseError::index_not_found(&path.index_uid))?;
 
     let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
 
     let update_id = index.clear_all(&mut writer)?;
 
@@ -0,0 +1,31 @@
+use actix_web::{web, HttpResponse};
+use actix_web_macros::post;
+use serde::Serialize;
+
+use crate::dump;
+use crate::error::ResponseError;
+use crate::helpers::Authentication;
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(create_dump);
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DumpResponse {
+    uid: String,
+}
+
+/// The dump is written in the dumps directory as `<uid>.tar.gz`,
+/// it can be imported by starting MeiliSearch with `--import-dump`.
+#[post("/dumps", wrap = "Authentication::Private")]
+async fn create_dump(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    let db = data.db.clone();
+    let dumps_dir = data.dumps_dir.clone();
+    let uid = web::block(move || dump::create_dump(&db, &dumps_dir))
+        .await
+        .map_err(ResponseError::internal)?;
+
+    Ok(HttpResponse::Created().json(DumpResponse { uid }))
+}
@@ -0,0 +1,89 @@
+use actix_web::{web, HttpResponse};
+use actix_web_macros::get;
+use meilisearch_core::Filter;
+use sdset::SetOperation;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{FacetCountError, ResponseError};
+use crate::helpers::Authentication;
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_distinct_count);
+}
+
+#[derive(Deserialize)]
+struct FacetParam {
+    index_uid: String,
+    attribute: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct DistinctCountQuery {
+    #[serde(alias = "filter")]
+    filters: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DistinctCountResponse {
+    attribute: String,
+    distinct_count: usize,
+}
+
+#[get(
+    "/indexes/{index_uid}/facets/{attribute}/distinct-count",
+    wrap = "Authentication::Public"
+)]
+async fn get_distinct_count(
+    data: web::Data<Data>,
+    path: web::Path<FacetParam>,
+    params: web::Query<DistinctCountQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let reader = data.db.main_read_txn()?;
+
+    let schema = index
+        .main
+        .schema(&reader)?
+        .ok_or(ResponseError::internal("missing schema"))?;
+
+    let attributes_for_faceting = index
+        .main
+        .attributes_for_faceting(&reader)?
+        .ok_or(FacetCountError::NoFacetSet)?;
+
+    let field_id = schema
+        .id(&path.attribute)
+        .filter(|id| attributes_for_faceting.contains(id))
+        .ok_or_else(|| FacetCountError::AttributeNotSet(path.attribute.clone()))?;
+
+    // the deleted documents are still in the facets until they are purged
+    let deleted = index.deleted_documents(&reader)?;
+    let filter = match &params.filters {
+        Some(filters) => Some(Filter::parse(filters, &schema)?),
+        None => None,
+    };
+
+    let candidates = match filter {
+        Some(filter) => {
+            let candidates = filter.candidates(&reader, &index)?;
+            Some(sdset::duo::Difference::new(&candidates, &deleted).into_set_buf())
+        }
+        None => None,
+    };
+
+    let distinct_count = index
+        .facets
+        .field_distinct_count(&reader, field_id, candidates.as_deref(), &deleted)?;
+
+    Ok(HttpResponse::Ok().json(DistinctCountResponse {
+        attribute: path.attribute.clone(),
+        distinct_count,
+    }))
+}
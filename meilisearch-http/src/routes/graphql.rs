@@ -0,0 +1,524 @@
+use std::collections::HashSet;
+
+use actix_web::{web, HttpResponse};
+use actix_web_macros::{get, post};
+use indexmap::IndexMap;
+use meilisearch_core::{update, Index, MainT};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::error::ResponseError;
+use crate::helpers::graphql::{parse_document, Field, InputValue, Operation};
+use crate::helpers::Authentication;
+use crate::routes::search::SearchQuery;
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(graphql_query).service(graphql_schema);
+}
+
+/// The arguments of the generated search fields, they are the parameters of the search route.
+const SEARCH_ARGUMENTS: &[(&str, &str)] = &[
+    ("q", "String"),
+    ("offset", "Int"),
+    ("limit", "Int"),
+    ("attributesToSearchIn", "[String!]"),
+    ("attributeWeights", "[String!]"),
+    ("attributesToCrop", "[String!]"),
+    ("cropLength", "Int"),
+    ("cropMarker", "String"),
+    ("attributesToHighlight", "[String!]"),
+    ("highlightPreTag", "String"),
+    ("highlightPostTag", "String"),
+    ("highlightTags", "JSON"),
+    ("filters", "String"),
+    ("matches", "Boolean"),
+    ("matchesUnit", "String"),
+    ("facetFilters", "JSON"),
+    ("facets", "[String!]"),
+    ("globalFacets", "Boolean"),
+    ("maxValuesPerFacet", "Int"),
+    ("sortFacetValuesBy", "String"),
+    ("language", "String"),
+    ("rankingRules", "[String!]"),
+    ("matchingStrategy", "String"),
+    ("exhaustiveNbHits", "Boolean"),
+    ("searchCutoffMs", "Int"),
+    ("distinct", "String"),
+    ("distinctSize", "Int"),
+    ("profile", "Boolean"),
+    ("showRankingScoreDetails", "Boolean"),
+    ("suggestQuery", "Boolean"),
+];
+
+/// The list arguments the search route expects as comma separated attributes.
+const COMMA_SEPARATED_ARGUMENTS: &[&str] = &["attributesToSearchIn", "attributeWeights", "attributesToCrop", "attributesToHighlight"];
+
+/// The arguments the search route expects as JSON encoded strings.
+const JSON_ENCODED_ARGUMENTS: &[&str] = &["highlightTags", "facetFilters", "facets", "rankingRules"];
+
+const SEARCH_RESULT_FIELDS: &[(&str, &str)] = &[
+    ("offset", "Int!"),
+    ("limit", "Int!"),
+    ("nbHits", "Int!"),
+    ("exhaustiveNbHits", "Boolean!"),
+    ("degraded", "Boolean!"),
+    ("processingTimeMs", "Int!"),
+    ("query", "String!"),
+    ("facets", "JSON"),
+    ("profile", "JSON"),
+    ("suggestedQuery", "String"),
+];
+
+/// The built-in types of the schema, an index can't generate a type with one of these names.
+const RESERVED_TYPE_NAMES: &[&str] = &["Query", "JSON", "String", "Int", "Float", "Boolean", "ID"];
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct GraphQLRequest {
+    query: String,
+    variables: Option<Map<String, Value>>,
+    operation_name: Option<String>,
+}
+
+/// The GraphQL types and root fields generated from an index.
+struct IndexType {
+    index: Index,
+    field_name: String,
+    type_name: String,
+    /// The displayed attributes that are valid GraphQL names.
+    fields: Vec<String>,
+}
+
+impl IndexType {
+    fn search_result_name(&self) -> String {
+        format!("{}SearchResult", self.type_name)
+    }
+
+    fn document_field_name(&self) -> String {
+        format!("{}Document", self.field_name)
+    }
+
+    /// The root fields and the types this index adds to the schema.
+    fn generated_names(&self) -> [String; 4] {
+        [
+            self.field_name.clone(),
+            self.document_field_name(),
+            self.type_name.clone(),
+            self.search_result_name(),
+        ]
+    }
+}
+
+/// Converts an index uid, made of alphanumeric characters, hyphens and underscores,
+/// into a GraphQL type name in PascalCase.
+fn type_name(uid: &str) -> String {
+    let mut name = String::new();
+    for part in uid.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            name.push(first.to_ascii_uppercase());
+            name.push_str(chars.as_str());
+        }
+    }
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert_str(0, "Index");
+    }
+    name
+}
+
+fn field_name(uid: &str) -> String {
+    let name = type_name(uid);
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+        None => name,
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    let valid_start = chars.next().map_or(false, |c| c == '_' || c.is_ascii_alphabetic());
+    valid_start && chars.all(|c| c == '_' || c.is_ascii_alphanumeric()) && !name.starts_with("__")
+}
+
+fn index_types(data: &Data, reader: &heed::RoTxn<MainT>) -> Result<Vec<IndexType>, ResponseError> {
+    let mut uids = data.db.indexes_uids();
+    uids.sort();
+
+    let mut used_names: HashSet<String> = RESERVED_TYPE_NAMES.iter().map(|name| name.to_string()).collect();
+    let mut types = Vec::with_capacity(uids.len());
+    for uid in uids {
+        let index = match data.db.open_index(&uid) {
+            Some(index) => index,
+            None => continue,
+        };
+        let schema = match index.main.schema(reader)? {
+            Some(schema) => schema,
+            None => continue,
+        };
+
+        let mut fields: Vec<String> = schema
+            .displayed_name()
+            .into_iter()
+            .filter(|name| is_valid_name(name))
+            .map(str::to_string)
+            .collect();
+        fields.sort();
+
+        let mut index_type = IndexType {
+            index,
+            field_name: field_name(&uid),
+            type_name: type_name(&uid),
+            fields,
+        };
+
+        // different uids can be converted into the same names, e.g. `my-index` and `my_index`,
+        // or into the names generated for another index, e.g. `x-document` and `x`, the uids
+        // are sorted so the suffixes are stable as long as the indexes don't change
+        let mut suffix = 1;
+        while index_type.generated_names().iter().any(|name| used_names.contains(name)) {
+            suffix += 1;
+            index_type.field_name = format!("{}_{}", field_name(&uid), suffix);
+            index_type.type_name = format!("{}_{}", type_name(&uid), suffix);
+        }
+        used_names.extend(index_type.generated_names().iter().cloned());
+
+        types.push(index_type);
+    }
+
+    Ok(types)
+}
+
+fn schema_sdl(types: &[IndexType]) -> String {
+    let arguments: Vec<String> = SEARCH_ARGUMENTS
+        .iter()
+        .map(|(name, kind)| format!("{}: {}", name, kind))
+        .collect();
+    let arguments = arguments.join(", ");
+
+    let mut sdl = String::from("scalar JSON\n\ntype Query {\n");
+    for t in types {
+        sdl.push_str(&format!("  {}({}): {}!\n", t.field_name, arguments, t.search_result_name()));
+        sdl.push_str(&format!("  {}(id: ID!): {}\n", t.document_field_name(), t.type_name));
+    }
+    sdl.push_str("}\n");
+
+    for t in types {
+        sdl.push_str(&format!("\ntype {} {{\n", t.search_result_name()));
+        sdl.push_str(&format!("  hits: [{}!]!\n", t.type_name));
+        for (name, kind) in SEARCH_RESULT_FIELDS {
+            sdl.push_str(&format!("  {}: {}\n", name, kind));
+        }
+        sdl.push_str("}\n");
+
+        sdl.push_str(&format!("\ntype {} {{\n", t.type_name));
+        for name in &t.fields {
+            sdl.push_str(&format!("  {}: JSON\n", name));
+        }
+        sdl.push_str(&format!("  _formatted: {}\n", t.type_name));
+        sdl.push_str("  _matchesInfo: JSON\n");
+        sdl.push_str("  _rankingScoreDetails: JSON\n}\n");
+    }
+
+    sdl
+}
+
+#[get("/graphql/schema", wrap = "Authentication::Public")]
+async fn graphql_schema(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    if !data.enable_graphql {
+        return Err(ResponseError::not_found("GraphQL endpoint"));
+    }
+
+    let reader = data.db.main_read_txn()?;
+    let types = index_types(&data, &reader)?;
+
+    Ok(HttpResponse::Ok().json(json!({ "schema": schema_sdl(&types) })))
+}
+
+/// Executes a GraphQL query, the errors are reported in the `errors` field of the response
+/// as the GraphQL clients expect, a single error fails the whole query.
+#[post("/graphql", wrap = "Authentication::Public")]
+async fn graphql_query(
+    data: web::Data<Data>,
+    body: web::Json<GraphQLRequest>,
+) -> Result<HttpResponse, ResponseError> {
+    if !data.enable_graphql {
+        return Err(ResponseError::not_found("GraphQL endpoint"));
+    }
+
+    let reader = data.db.main_read_txn()?;
+    let types = index_types(&data, &reader)?;
+
+    let response = match execute(&reader, &types, &body) {
+        Ok(data) => json!({ "data": data }),
+        Err(message) => json!({ "data": null, "errors": [{ "message": message }] }),
+    };
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+fn execute(reader: &heed::RoTxn<MainT>, types: &[IndexType], request: &GraphQLRequest) -> Result<Value, String> {
+    let operations = parse_document(&request.query).map_err(|e| e.to_string())?;
+    let operation = select_operation(operations, request.operation_name.as_deref())?;
+    let variables = resolve_variables(&operation, request.variables.as_ref())?;
+
+    let mut result = Map::new();
+    for field in &operation.selection {
+        let value = resolve_root_field(reader, types, field, &variables)?;
+        result.insert(field.response_key().to_string(), value);
+    }
+
+    Ok(Value::Object(result))
+}
+
+fn select_operation(mut operations: Vec<Operation>, name: Option<&str>) -> Result<Operation, String> {
+    match name {
+        Some(name) => operations
+            .into_iter()
+            .find(|operation| operation.name.as_deref() == Some(name))
+            .ok_or_else(|| format!("Unknown operation named \"{}\"", name)),
+        None if operations.len() == 1 => Ok(operations.remove(0)),
+        None => Err("Must provide operation name if query contains multiple operations".to_string()),
+    }
+}
+
+fn resolve_variables(operation: &Operation, provided: Option<&Map<String, Value>>) -> Result<Map<String, Value>, String> {
+    let mut variables = Map::new();
+    for definition in &operation.variables {
+        let value = match provided.and_then(|provided| provided.get(&definition.name)) {
+            Some(value) => value.clone(),
+            None => match &definition.default {
+                Some(default) => input_to_json(default, &Map::new())?,
+                None => Value::Null,
+            },
+        };
+        variables.insert(definition.name.clone(), value);
+    }
+    Ok(variables)
+}
+
+fn input_to_json(value: &InputValue, variables: &Map<String, Value>) -> Result<Value, String> {
+    let value = match value {
+        InputValue::Null => Value::Null,
+        InputValue::Int(i) => json!(i),
+        InputValue::Float(x) => json!(x),
+        InputValue::String(s) | InputValue::Enum(s) => Value::String(s.clone()),
+        InputValue::Boolean(b) => Value::Bool(*b),
+        InputValue::List(values) => {
+            let values: Result<Vec<_>, _> = values.iter().map(|v| input_to_json(v, variables)).collect();
+            Value::Array(values?)
+        }
+        InputValue::Object(fields) => {
+            let mut object = Map::new();
+            for (name, value) in fields {
+                object.insert(name.clone(), input_to_json(value, variables)?);
+            }
+            Value::Object(object)
+        }
+        InputValue::Variable(name) => variables
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("Variable \"${}\" is not defined", name))?,
+    };
+    Ok(value)
+}
+
+fn no_arguments(field: &Field, parent_type: &str) -> Result<(), String> {
+    match field.arguments.first() {
+        Some((name, _)) => Err(format!("Unknown argument \"{}\" on field \"{}.{}\"", name, parent_type, field.name)),
+        None => Ok(()),
+    }
+}
+
+fn require_selection(field: &Field, kind: &str) -> Result<(), String> {
+    if field.selection.is_empty() {
+        return Err(format!("Field \"{}\" of type \"{}\" must have a selection of subfields", field.name, kind));
+    }
+    Ok(())
+}
+
+fn forbid_selection(field: &Field, kind: &str) -> Result<(), String> {
+    if !field.selection.is_empty() {
+        return Err(format!("Field \"{}\" must not have a selection since type \"{}\" has no subfields", field.name, kind));
+    }
+    Ok(())
+}
+
+fn resolve_root_field(
+    reader: &heed::RoTxn<MainT>,
+    types: &[IndexType],
+    field: &Field,
+    variables: &Map<String, Value>,
+) -> Result<Value, String> {
+    if field.name == "__typename" {
+        return Ok(json!("Query"));
+    }
+    if field.name.starts_with("__") {
+        return Err("Introspection is not supported, the schema is described by the /graphql/schema route".to_string());
+    }
+
+    for t in types {
+        if field.name == t.field_name {
+            return resolve_search(reader, t, field, variables);
+        }
+        if field.name == t.document_field_name() {
+            return resolve_document(reader, t, field, variables);
+        }
+    }
+
+    Err(format!("Cannot query field \"{}\" on type \"Query\"", field.name))
+}
+
+fn resolve_search(
+    reader: &heed::RoTxn<MainT>,
+    t: &IndexType,
+    field: &Field,
+    variables: &Map<String, Value>,
+) -> Result<Value, String> {
+    require_selection(field, &format!("{}!", t.search_result_name()))?;
+
+    let mut params = Map::new();
+    for (name, value) in &field.arguments {
+        if !SEARCH_ARGUMENTS.iter().any(|(argument, _)| *argument == name.as_str()) {
+            return Err(format!("Unknown argument \"{}\" on field \"Query.{}\"", name, field.name));
+        }
+
+        let value = match input_to_json(value, variables)? {
+            Value::Null => continue,
+            Value::Array(values) if COMMA_SEPARATED_ARGUMENTS.contains(&name.as_str()) => {
+                let values: Vec<String> = values
+                    .iter()
+                    .map(|v| v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string()))
+                    .collect();
+                Value::String(values.join(","))
+            }
+            value if JSON_ENCODED_ARGUMENTS.contains(&name.as_str()) && !value.is_string() => {
+                Value::String(value.to_string())
+            }
+            value => value,
+        };
+        params.insert(name.clone(), value);
+    }
+
+    if !params.contains_key("q") {
+        params.insert("q".to_string(), json!(""));
+    }
+
+    // only the attributes selected in the hits are retrieved
+    let mut attributes = Vec::new();
+    let mut hits_selected = false;
+    for hits in field.selection.iter().filter(|f| f.name == "hits") {
+        hits_selected = true;
+        let formatted = hits.selection.iter().filter(|f| f.name == "_formatted").flat_map(|f| &f.selection);
+        for attribute in hits.selection.iter().chain(formatted) {
+            if t.fields.contains(&attribute.name) && !attributes.contains(&attribute.name) {
+                attributes.push(attribute.name.clone());
+            }
+        }
+    }
+    if hits_selected {
+        params.insert("attributesToRetrieve".to_string(), Value::String(attributes.join(",")));
+    }
+
+    let query: SearchQuery = serde_json::from_value(Value::Object(params))
+        .map_err(|e| format!("Invalid arguments on field \"Query.{}\": {}", field.name, e))?;
+    let result = query.search(&t.index, reader).map_err(|e| e.to_string())?;
+    let result = serde_json::to_value(result).map_err(|e| e.to_string())?;
+
+    let type_name = t.search_result_name();
+    let mut object = Map::new();
+    for sub in &field.selection {
+        no_arguments(sub, &type_name)?;
+        let value = match sub.name.as_str() {
+            "__typename" => json!(type_name),
+            "hits" => {
+                require_selection(sub, &format!("[{}!]!", t.type_name))?;
+                let hits = match result.get("hits") {
+                    Some(Value::Array(hits)) => hits.as_slice(),
+                    _ => &[][..],
+                };
+                let hits: Result<Vec<_>, _> = hits.iter().map(|hit| project_document(t, hit, &sub.selection)).collect();
+                Value::Array(hits?)
+            }
+            name => match SEARCH_RESULT_FIELDS.iter().find(|(field, _)| *field == name) {
+                Some((_, kind)) => {
+                    forbid_selection(sub, kind)?;
+                    result.get(name).cloned().unwrap_or(Value::Null)
+                }
+                None => return Err(format!("Cannot query field \"{}\" on type \"{}\"", name, type_name)),
+            },
+        };
+        object.insert(sub.response_key().to_string(), value);
+    }
+
+    Ok(Value::Object(object))
+}
+
+fn resolve_document(
+    reader: &heed::RoTxn<MainT>,
+    t: &IndexType,
+    field: &Field,
+    variables: &Map<String, Value>,
+) -> Result<Value, String> {
+    require_selection(field, &t.type_name)?;
+
+    let mut id = None;
+    for (name, value) in &field.arguments {
+        if name != "id" {
+            return Err(format!("Unknown argument \"{}\" on field \"Query.{}\"", name, field.name));
+        }
+        id = match input_to_json(value, variables)? {
+            Value::String(id) => Some(id),
+            Value::Number(id) => Some(id.to_string()),
+            _ => None,
+        };
+    }
+    let id = id.ok_or_else(|| format!("Field \"Query.{}\" argument \"id\" of type \"ID!\" is required", field.name))?;
+
+    let format = t.index.main.document_id_format(reader).map_err(|e| e.to_string())?.unwrap_or_default();
+    let document_id = update::compute_document_id(&id, format).map_err(|e| e.to_string())?;
+
+    let document: Option<IndexMap<String, Value>> = t.index.document(reader, None, document_id).map_err(|e| e.to_string())?;
+    match document {
+        Some(document) => {
+            let document = serde_json::to_value(document).map_err(|e| e.to_string())?;
+            project_document(t, &document, &field.selection)
+        }
+        None => Ok(Value::Null),
+    }
+}
+
+fn project_document(t: &IndexType, document: &Value, selection: &[Field]) -> Result<Value, String> {
+    if document.is_null() {
+        return Ok(Value::Null);
+    }
+
+    let mut object = Map::new();
+    for sub in selection {
+        no_arguments(sub, &t.type_name)?;
+        let value = match sub.name.as_str() {
+            "__typename" => json!(t.type_name),
+            "_formatted" => {
+                require_selection(sub, &t.type_name)?;
+                project_document(t, &document["_formatted"], &sub.selection)?
+            }
+            "_matchesInfo" => {
+                forbid_selection(sub, "JSON")?;
+                document["_matchesInfo"].clone()
+            }
+            "_rankingScoreDetails" => {
+                forbid_selection(sub, "JSON")?;
+                document["_rankingScoreDetails"].clone()
+            }
+            name if t.fields.iter().any(|field| field == name) => {
+                forbid_selection(sub, "JSON")?;
+                document[name].clone()
+            }
+            name => return Err(format!("Cannot query field \"{}\" on type \"{}\"", name, t.type_name)),
+        };
+        object.insert(sub.response_key().to_string(), value);
+    }
+
+    Ok(Value::Object(object))
+}
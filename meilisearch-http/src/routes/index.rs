@@ -1,13 +1,18 @@
+use std::time::{Duration, Instant};
+
 use actix_web::{web, HttpResponse};
 use actix_web_macros::{delete, get, post, put};
 use chrono::{DateTime, Utc};
 use log::error;
+use meilisearch_core::store::IntegrityReport;
+use meilisearch_core::update::CancelOutcome;
 use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
 
 use crate::error::ResponseError;
+use crate::helpers::uploads::Upload;
 use crate::helpers::Authentication;
-use crate::routes::IndexParam;
+use crate::routes::{IndexParam, IndexUpdateResponse};
 use crate::Data;
 
 pub fn services(cfg: &mut web::ServiceConfig) {
@@ -16,8 +21,16 @@ pub fn services(cfg: &mut web::ServiceConfig) {
         .service(create_index)
         .service(update_index)
         .service(delete_index)
+        .service(get_updates_status_batch)
         .service(get_update_status)
-        .service(get_all_updates_status);
+        .service(cancel_update)
+        .service(get_all_updates_status)
+        .service(purge_updates)
+        .service(get_fields)
+        .service(check_index_integrity)
+        .service(repair_index)
+        .service(copy_index)
+        .service(rename_index);
 }
 
 fn generate_uid() -> String {
@@ -151,6 +164,8 @@ async fn create_index(
     data: web::Data<Data>,
     body: web::Json<IndexCreateRequest>,
 ) -> Result<HttpResponse, ResponseError> {
+    data.check_writable()?;
+
     if let (None, None) = (body.name.clone(), body.uid.clone()) {
         return Err(ResponseError::bad_request(
             "Index creation must have an uid",
@@ -239,6 +254,8 @@ async fn update_index(
     path: web::Path<IndexParam>,
     body: web::Json<IndexCreateRequest>,
 ) -> Result<HttpResponse, ResponseError> {
+    data.check_writable()?;
+
     let index = data
         .db
         .open_index(&path.index_uid)
@@ -309,7 +326,9 @@ async fn delete_index(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
 ) -> Result<HttpResponse, ResponseError> {
+    data.check_writable()?;
     data.db.delete_index(&path.index_uid)?;
+    Upload::remove_all(&data.db_path, &path.index_uid)?;
 
     Ok(HttpResponse::NoContent().finish())
 }
@@ -320,9 +339,74 @@ struct UpdateParam {
     update_id: u64,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct UpdatesBatchQuery {
+    ids: String,
+    wait_for: Option<bool>,
+    timeout_ms: Option<u64>,
+}
+
+const DEFAULT_WAIT_FOR_TIMEOUT_MS: u64 = 30_000;
+const MAX_WAIT_FOR_TIMEOUT_MS: u64 = 120_000;
+const WAIT_FOR_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[get(
+    "/indexes/{index_uid}/updates/batch",
+    wrap = "Authentication::Updates"
+)]
+async fn get_updates_status_batch(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    params: web::Query<UpdatesBatchQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let update_ids = params
+        .ids
+        .split(',')
+        .map(|id| id.trim().parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ResponseError::bad_parameter("ids", e))?;
+
+    let timeout = params.timeout_ms.unwrap_or(DEFAULT_WAIT_FOR_TIMEOUT_MS);
+    let timeout = Duration::from_millis(timeout.min(MAX_WAIT_FOR_TIMEOUT_MS));
+    let wait_for = params.wait_for.unwrap_or(false);
+    let start = Instant::now();
+
+    loop {
+        let reader = data.db.update_read_txn()?;
+
+        let mut statuses = Vec::with_capacity(update_ids.len());
+        for &update_id in &update_ids {
+            match index.update_status(&reader, update_id)? {
+                Some(status) => statuses.push(status),
+                None => {
+                    return Err(ResponseError::NotFound(format!(
+                        "Update {} not found",
+                        update_id
+                    )))
+                }
+            }
+        }
+
+        reader.abort();
+
+        let all_terminal = statuses.iter().all(|s| s.status() != "enqueued");
+        if !wait_for || all_terminal || start.elapsed() >= timeout {
+            return Ok(HttpResponse::Ok().json(statuses));
+        }
+
+        actix_rt::time::delay_for(WAIT_FOR_POLL_INTERVAL).await;
+    }
+}
+
 #[get(
     "/indexes/{index_uid}/updates/{update_id}",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Updates"
 )]
 async fn get_update_status(
     data: web::Data<Data>,
@@ -346,19 +430,322 @@ async fn get_update_status(
     }
 }
 
-#[get("/indexes/{index_uid}/updates", wrap = "Authentication::Private")]
+/// Cancels an enqueued update, the update that is the next one to be processed
+/// or that is being processed cannot be canceled.
+#[delete(
+    "/indexes/{index_uid}/updates/{update_id}",
+    wrap = "Authentication::Updates"
+)]
+async fn cancel_update(
+    data: web::Data<Data>,
+    path: web::Path<UpdateParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let mut writer = data.db.update_write_txn()?;
+
+    match index.cancel_update(&mut writer, path.update_id)? {
+        CancelOutcome::Canceled => (),
+        CancelOutcome::Processing => {
+            return Err(ResponseError::bad_request(format!(
+                "Update {} is being processed and cannot be canceled",
+                path.update_id
+            )))
+        }
+        CancelOutcome::NotEnqueued => {
+            return Err(ResponseError::NotFound(format!(
+                "Update {} is not enqueued",
+                path.update_id
+            )))
+        }
+    }
+
+    let status = index.update_status(&writer, path.update_id)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Ok().json(status))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct UpdatesQuery {
+    status: Option<String>,
+    #[serde(rename = "type")]
+    update_type: Option<String>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
+const UPDATE_STATUSES: &[&str] = &["enqueued", "processing", "processed", "failed", "canceled", "aborted"];
+
+#[get("/indexes/{index_uid}/updates", wrap = "Authentication::Updates")]
 async fn get_all_updates_status(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
+    params: web::Query<UpdatesQuery>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
         .db
         .open_index(&path.index_uid)
         .ok_or(ResponseError::index_not_found(&path.index_uid))?;
 
+    let statuses: Option<Vec<&str>> = params.status.as_ref().map(|s| s.split(',').map(str::trim).collect());
+    if let Some(statuses) = &statuses {
+        if let Some(status) = statuses.iter().find(|s| !UPDATE_STATUSES.contains(*s)) {
+            return Err(ResponseError::bad_parameter(
+                "status",
+                format!("unknown status `{}`, expected one of {}", status, UPDATE_STATUSES.join(", ")),
+            ));
+        }
+    }
+
+    let types: Option<Vec<&str>> = params.update_type.as_ref().map(|s| s.split(',').map(str::trim).collect());
+
     let reader = data.db.update_read_txn()?;
 
-    let response = index.all_updates_status(&reader)?;
+    // the update being processed is the oldest one that is still enqueued
+    let processing_id = index.updates.first_update(&reader)?.map(|(id, _)| id);
+
+    let response: Vec<_> = index
+        .all_updates_status(&reader)?
+        .into_iter()
+        .filter(|update| match &statuses {
+            Some(statuses) => statuses.iter().any(|&s| match s {
+                "processing" => Some(update.update_id()) == processing_id,
+                s => s == update.status(),
+            }),
+            None => true,
+        })
+        .filter(|update| match &types {
+            Some(types) => types.iter().any(|t| t.eq_ignore_ascii_case(update.update_type().name())),
+            None => true,
+        })
+        .filter(|update| params.from.map_or(true, |from| update.enqueued_at() >= from))
+        .filter(|update| params.to.map_or(true, |to| update.enqueued_at() <= to))
+        .skip(params.offset.unwrap_or(0))
+        .take(params.limit.unwrap_or(usize::max_value()))
+        .collect();
 
     Ok(HttpResponse::Ok().json(response))
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct PurgeUpdatesQuery {
+    before_id: Option<u64>,
+    before_date: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PurgeUpdatesResponse {
+    purged_updates: usize,
+    freed_bytes: usize,
+}
+
+#[delete("/indexes/{index_uid}/updates", wrap = "Authentication::Updates")]
+async fn purge_updates(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    params: web::Query<PurgeUpdatesQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    if params.before_id.is_none() && params.before_date.is_none() {
+        return Err(ResponseError::bad_request(
+            "at least one of beforeId or beforeDate must be specified",
+        ));
+    }
+
+    let mut writer = data.db.update_write_txn()?;
+    let (purged_updates, freed_bytes) =
+        index.purge_updates(&mut writer, params.before_id, params.before_date)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Ok().json(PurgeUpdatesResponse {
+        purged_updates,
+        freed_bytes,
+    }))
+}
+
+/// Returns, for every field of the schema, what the index does with it and
+/// the number of documents containing it.
+#[get("/indexes/{index_uid}/fields", wrap = "Authentication::Private")]
+async fn get_fields(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let reader = data.db.main_read_txn()?;
+    let fields = index.main.fields_capabilities(&reader)?;
+
+    Ok(HttpResponse::Ok().json(fields))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IntegrityResponse {
+    is_sound: bool,
+    #[serde(flatten)]
+    report: IntegrityReport,
+}
+
+#[get("/indexes/{index_uid}/integrity", wrap = "Authentication::Private")]
+async fn check_index_integrity(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let reader = data.db.main_read_txn()?;
+    let report = index.check_integrity(&reader)?;
+
+    Ok(HttpResponse::Ok().json(IntegrityResponse {
+        is_sound: report.is_sound(),
+        report,
+    }))
+}
+
+#[post("/indexes/{index_uid}/repair", wrap = "Authentication::Private")]
+async fn repair_index(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+
+    let update_id = index.repair(&mut writer)?;
+
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct CopyIndexRequest {
+    uid: String,
+    with_updates: Option<bool>,
+}
+
+/// Creates a new index with the documents and the settings of this one, the settings
+/// can then be tried on the copy without sending the documents again.
+#[post("/indexes/{index_uid}/copy", wrap = "Authentication::Private")]
+async fn copy_index(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<CopyIndexRequest>,
+) -> Result<HttpResponse, ResponseError> {
+    data.check_writable()?;
+
+    if !body.uid.chars().all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '_') {
+        return Err(ResponseError::InvalidIndexUid);
+    }
+
+    let with_updates = body.with_updates.unwrap_or(false);
+    let index = match data.db.copy_index(&path.index_uid, &body.uid, with_updates) {
+        Ok(index) => index,
+        Err(meilisearch_core::Error::IndexNotFound(uid)) => return Err(ResponseError::index_not_found(uid)),
+        Err(e) => return Err(ResponseError::create_index(e)),
+    };
+
+    let reader = data.db.main_read_txn()?;
+    let created_at = index
+        .main
+        .created_at(&reader)?
+        .ok_or(ResponseError::internal("Impossible to read created at"))?;
+    let updated_at = index
+        .main
+        .updated_at(&reader)?
+        .ok_or(ResponseError::internal("Impossible to read updated at"))?;
+    let primary_key = index
+        .main
+        .schema(&reader)?
+        .and_then(|schema| schema.primary_key().map(str::to_owned));
+
+    Ok(HttpResponse::Created().json(IndexResponse {
+        name: body.uid.clone(),
+        uid: body.uid.clone(),
+        created_at,
+        updated_at,
+        primary_key,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct RenameIndexRequest {
+    uid: String,
+}
+
+/// Gives another uid to the index, its documents, settings and updates are kept
+/// and its aliases now refer to the new uid.
+#[post("/indexes/{index_uid}/rename", wrap = "Authentication::Private")]
+async fn rename_index(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<RenameIndexRequest>,
+) -> Result<HttpResponse, ResponseError> {
+    data.check_writable()?;
+
+    if body.uid.is_empty() || !body.uid.chars().all(|x| x.is_ascii_alphanumeric() || x == '-' || x == '_') {
+        return Err(ResponseError::InvalidIndexUid);
+    }
+
+    match data.db.rename_index(&path.index_uid, &body.uid) {
+        Ok(()) => (),
+        Err(e @ meilisearch_core::Error::IndexAlreadyExists) => return Err(ResponseError::BadRequest(e.to_string())),
+        Err(e) => return Err(e.into()),
+    }
+
+    let index = data
+        .db
+        .open_index(&body.uid)
+        .ok_or(ResponseError::index_not_found(&body.uid))?;
+
+    let reader = data.db.main_read_txn()?;
+    let name = index
+        .main
+        .name(&reader)?
+        .ok_or(ResponseError::internal("Impossible to read the name"))?;
+    let created_at = index
+        .main
+        .created_at(&reader)?
+        .ok_or(ResponseError::internal("Impossible to read created at"))?;
+    let updated_at = index
+        .main
+        .updated_at(&reader)?
+        .ok_or(ResponseError::internal("Impossible to read updated at"))?;
+    let primary_key = index
+        .main
+        .schema(&reader)?
+        .and_then(|schema| schema.primary_key().map(str::to_owned));
+
+    Ok(HttpResponse::Ok().json(IndexResponse {
+        name,
+        uid: body.uid.clone(),
+        created_at,
+        updated_at,
+        primary_key,
+    }))
+}
@@ -1,13 +1,23 @@
 use actix_web::web;
 use actix_web::HttpResponse;
-use actix_web_macros::get;
-use serde::Serialize;
+use actix_web_macros::{delete, get, post};
+use chrono::Utc;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
+use crate::data::{KeyAction, ScopedKey};
+use crate::error::ResponseError;
 use crate::helpers::Authentication;
 use crate::Data;
 
+const SCOPED_KEY_LEN: usize = 32;
+
 pub fn services(cfg: &mut web::ServiceConfig) {
-    cfg.service(list);
+    cfg.service(list)
+        .service(list_scoped_keys)
+        .service(create_scoped_key)
+        .service(delete_scoped_key);
 }
 
 #[derive(Serialize)]
@@ -24,3 +34,85 @@ async fn list(data: web::Data<Data>) -> HttpResponse {
         public: api_keys.public,
     })
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ScopedKeyRequest {
+    description: Option<String>,
+    indexes: Vec<String>,
+    actions: Vec<KeyAction>,
+}
+
+#[derive(Deserialize)]
+struct ScopedKeyParam {
+    key: String,
+}
+
+#[get("/keys/scoped", wrap = "Authentication::Admin")]
+async fn list_scoped_keys(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    let reader = data.db.main_read_txn()?;
+    let keys = data.scoped_keys(&reader)?;
+
+    Ok(HttpResponse::Ok().json(keys))
+}
+
+/// Generates a key that only gives access to the given actions on the given indexes,
+/// multi-tenant deployments can then hand it out instead of the private key.
+#[post("/keys/scoped", wrap = "Authentication::Admin")]
+async fn create_scoped_key(
+    data: web::Data<Data>,
+    body: web::Json<ScopedKeyRequest>,
+) -> Result<HttpResponse, ResponseError> {
+    data.check_writable()?;
+
+    let body = body.into_inner();
+    if body.indexes.is_empty() {
+        return Err(ResponseError::bad_request("a scoped key must give access to at least one index"));
+    }
+    if body.actions.is_empty() {
+        return Err(ResponseError::bad_request("a scoped key must grant at least one action"));
+    }
+
+    let key: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(SCOPED_KEY_LEN)
+        .collect();
+
+    let scoped_key = ScopedKey {
+        key,
+        description: body.description,
+        indexes: body.indexes,
+        actions: body.actions,
+        created_at: Utc::now(),
+    };
+
+    let mut writer = data.db.main_write_txn()?;
+    let mut keys = data.scoped_keys(&writer)?;
+    keys.push(scoped_key.clone());
+    data.put_scoped_keys(&mut writer, keys)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Created().json(scoped_key))
+}
+
+#[delete("/keys/scoped/{key}", wrap = "Authentication::Admin")]
+async fn delete_scoped_key(
+    data: web::Data<Data>,
+    path: web::Path<ScopedKeyParam>,
+) -> Result<HttpResponse, ResponseError> {
+    data.check_writable()?;
+
+    let mut writer = data.db.main_write_txn()?;
+    let mut keys = data.scoped_keys(&writer)?;
+    let len = keys.len();
+    keys.retain(|scoped| scoped.key != path.key);
+
+    if keys.len() == len {
+        return Err(ResponseError::NotFound(format!("scoped key {}", path.key)));
+    }
+
+    data.put_scoped_keys(&mut writer, keys)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -1,15 +1,27 @@
 use actix_web::{get, HttpResponse};
 use serde::{Deserialize, Serialize};
 
+pub mod alias;
+pub mod analytics;
+pub mod batch;
+pub mod compaction;
 pub mod document;
+pub mod dump;
+pub mod facet;
+pub mod graphql;
 pub mod health;
 pub mod index;
 pub mod key;
+pub mod query_rule;
 pub mod search;
 pub mod setting;
+pub mod snapshot;
+pub mod standby;
 pub mod stats;
 pub mod stop_words;
 pub mod synonym;
+pub mod upload;
+pub mod words;
 
 #[derive(Deserialize)]
 pub struct IndexParam {
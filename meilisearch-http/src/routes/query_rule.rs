@@ -0,0 +1,89 @@
+use actix_web::{web, HttpResponse};
+use actix_web_macros::{delete, get, post};
+use meilisearch_core::query_rules::QueryRule;
+use meilisearch_core::settings::{SettingsUpdate, UpdateState};
+use meilisearch_core::Filter;
+
+use crate::error::ResponseError;
+use crate::helpers::Authentication;
+use crate::routes::{IndexParam, IndexUpdateResponse};
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get).service(update).service(delete);
+}
+
+#[get("/indexes/{index_uid}/query-rules", wrap = "Authentication::Settings")]
+async fn get(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let reader = data.db.main_read_txn()?;
+
+    let query_rules = index.main.query_rules(&reader)?.unwrap_or_default();
+
+    Ok(HttpResponse::Ok().json(query_rules))
+}
+
+#[post("/indexes/{index_uid}/query-rules", wrap = "Authentication::Settings")]
+async fn update(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Vec<QueryRule>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let query_rules = body.into_inner();
+
+    let reader = data.db.main_read_txn()?;
+
+    // we make sure that the forced filters are valid before enqueuing them
+    if let Some(schema) = index.main.schema(&reader)? {
+        for filters in query_rules.iter().filter_map(|r| r.filters.as_ref()) {
+            Filter::parse(filters, &schema)?;
+        }
+    }
+
+    let settings = SettingsUpdate {
+        query_rules: UpdateState::Update(query_rules),
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete("/indexes/{index_uid}/query-rules", wrap = "Authentication::Settings")]
+async fn delete(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        query_rules: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = index.settings_update(&mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
@@ -1,167 +1,389 @@
 use std::collections::{HashSet, HashMap};
+use std::time::{Duration, Instant};
 
+use indexmap::IndexMap;
 use log::warn;
 use actix_web::web;
 use actix_web::HttpResponse;
 use actix_web_macros::get;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::error::{ResponseError, FacetCountError};
-use crate::helpers::meilisearch::IndexSearchExt;
+use crate::helpers::meilisearch::{FacetValuesOrder, HighlightTags, IndexSearchExt, MatchesUnit, SearchResult};
 use crate::helpers::Authentication;
 use crate::routes::IndexParam;
 use crate::Data;
 
+use meilisearch_core::criterion::SortRule;
 use meilisearch_core::facets::FacetFilter;
+use meilisearch_core::{GeoPoint, Index, MainT, MatchingStrategy};
+use meilisearch_core::settings::{is_valid_language, RankingRule};
 use meilisearch_schema::{Schema, FieldId};
 
 pub fn services(cfg: &mut web::ServiceConfig) {
-    cfg.service(search_with_url_query);
+    cfg.service(search_with_url_query).service(multi_search_with_url_query);
 }
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
-struct SearchQuery {
+pub(crate) struct SearchQuery {
+    /// An empty or missing query returns all the documents, ordered by the ranking rules.
+    #[serde(default)]
     q: String,
     offset: Option<usize>,
     limit: Option<usize>,
     attributes_to_retrieve: Option<String>,
+    attributes_to_search_in: Option<String>,
+    attribute_weights: Option<String>,
     attributes_to_crop: Option<String>,
     crop_length: Option<usize>,
+    crop_marker: Option<String>,
     attributes_to_highlight: Option<String>,
+    highlight_pre_tag: Option<String>,
+    highlight_post_tag: Option<String>,
+    highlight_tags: Option<String>,
     filters: Option<String>,
     matches: Option<bool>,
+    matches_unit: Option<MatchesUnit>,
     facet_filters: Option<String>,
     facets: Option<String>,
+    global_facets: Option<bool>,
+    max_values_per_facet: Option<usize>,
+    sort_facet_values_by: Option<FacetValuesOrder>,
+    language: Option<String>,
+    ranking_rules: Option<String>,
+    sort: Option<String>,
+    around_lat_lng: Option<String>,
+    matching_strategy: Option<MatchingStrategy>,
+    exhaustive_nb_hits: Option<bool>,
+    search_cutoff_ms: Option<u64>,
+    distinct: Option<String>,
+    distinct_size: Option<usize>,
+    profile: Option<bool>,
+    show_ranking_score_details: Option<bool>,
+    suggest_query: Option<bool>,
 }
 
-#[get("/indexes/{index_uid}/search", wrap = "Authentication::Public")]
-async fn search_with_url_query(
-    data: web::Data<Data>,
-    path: web::Path<IndexParam>,
-    params: web::Query<SearchQuery>,
-) -> Result<HttpResponse, ResponseError> {
-    let index = data
-        .db
-        .open_index(&path.index_uid)
-        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
-
-    let reader = data.db.main_read_txn()?;
+impl SearchQuery {
+    pub(crate) fn search(&self, index: &Index, reader: &heed::RoTxn<MainT>) -> Result<SearchResult, ResponseError> {
+        let schema = index
+            .main
+            .schema(reader)?
+            .ok_or(ResponseError::internal("Impossible to retrieve the schema"))?;
 
-    let schema = index
-        .main
-        .schema(&reader)?
-        .ok_or(ResponseError::internal("Impossible to retrieve the schema"))?;
+        let mut search_builder = index.new_search(self.q.clone());
 
-    let mut search_builder = index.new_search(params.q.clone());
-
-    if let Some(offset) = params.offset {
-        search_builder.offset(offset);
-    }
-    if let Some(limit) = params.limit {
-        search_builder.limit(limit);
-    }
+        if let Some(offset) = self.offset {
+            search_builder.offset(offset);
+        }
+        if let Some(limit) = self.limit {
+            search_builder.limit(limit);
+        }
 
-    let available_attributes = schema.displayed_name();
-    let mut restricted_attributes: HashSet<&str>;
-    match &params.attributes_to_retrieve {
-        Some(attributes_to_retrieve) => {
-            let attributes_to_retrieve: HashSet<&str> = attributes_to_retrieve.split(',').collect();
-            if attributes_to_retrieve.contains("*") {
-                restricted_attributes = available_attributes.clone();
-            } else {
-                restricted_attributes = HashSet::new();
-                for attr in attributes_to_retrieve {
-                    if available_attributes.contains(attr) {
-                        restricted_attributes.insert(attr);
-                        search_builder.add_retrievable_field(attr.to_string());
-                    } else {
-                        warn!("The attributes {:?} present in attributesToCrop parameter doesn't exist", attr);
+        let available_attributes = schema.displayed_name();
+        let mut restricted_attributes: HashSet<&str>;
+        match &self.attributes_to_retrieve {
+            Some(attributes_to_retrieve) => {
+                let attributes_to_retrieve: HashSet<&str> = attributes_to_retrieve.split(',').collect();
+                if attributes_to_retrieve.contains("*") {
+                    restricted_attributes = available_attributes.clone();
+                } else {
+                    restricted_attributes = HashSet::new();
+                    for attr in attributes_to_retrieve {
+                        if available_attributes.contains(attr) {
+                            restricted_attributes.insert(attr);
+                            search_builder.add_retrievable_field(attr.to_string());
+                        } else {
+                            warn!("The attributes {:?} present in attributesToCrop parameter doesn't exist", attr);
+                        }
                     }
                 }
+            },
+            None => {
+                restricted_attributes = available_attributes.clone();
             }
-        },
-        None => {
-            restricted_attributes = available_attributes.clone();
         }
-    }
 
-    if let Some(ref facet_filters) = params.facet_filters {
-        match index.main.attributes_for_faceting(&reader)? {
-            Some(ref attrs) => { search_builder.add_facet_filters(FacetFilter::from_str(facet_filters, &schema, attrs)?); },
-            None => return Err(ResponseError::FacetExpression("can't filter on facets, as no facet is set".to_string()))
+        if let Some(ref facet_filters) = self.facet_filters {
+            match index.main.attributes_for_faceting(reader)? {
+                Some(ref attrs) => {
+                    let normalization = index.main.facet_normalization(reader)?.unwrap_or_default();
+                    search_builder.add_facet_filters(FacetFilter::from_str(facet_filters, &schema, attrs, &normalization)?);
+                },
+                None => return Err(ResponseError::FacetExpression("can't filter on facets, as no facet is set".to_string()))
+            }
         }
-    }
 
-    if let Some(facets) = &params.facets {
-        match index.main.attributes_for_faceting(&reader)? {
-            Some(ref attrs) => {
-                let field_ids = prepare_facet_list(&facets, &schema, attrs)?;
-                search_builder.add_facets(field_ids);
-            },
-            None => return Err(FacetCountError::NoFacetSet.into())
+        if let Some(facets) = &self.facets {
+            match index.main.attributes_for_faceting(reader)? {
+                Some(ref attrs) => {
+                    let field_ids = prepare_facet_list(&facets, &schema, attrs)?;
+                    search_builder.add_facets(field_ids);
+                },
+                None => return Err(FacetCountError::NoFacetSet.into())
+            }
         }
-    }
 
-    if let Some(attributes_to_crop) = &params.attributes_to_crop {
-        let default_length = params.crop_length.unwrap_or(200);
-        let mut final_attributes: HashMap<String, usize> = HashMap::new();
+        if let Some(global_facets) = self.global_facets {
+            search_builder.global_facets(global_facets);
+        }
+
+        if let Some(max_values_per_facet) = self.max_values_per_facet {
+            search_builder.max_values_per_facet(max_values_per_facet);
+        }
+
+        if let Some(sort_facet_values_by) = self.sort_facet_values_by {
+            search_builder.facet_values_order(sort_facet_values_by);
+        }
 
-        for attribute in attributes_to_crop.split(',') {
-            let mut attribute = attribute.split(':');
-            let attr = attribute.next();
-            let length = attribute.next().and_then(|s| s.parse().ok()).unwrap_or(default_length);
-            match attr {
-                Some("*") => {
+        if let Some(attributes_to_crop) = &self.attributes_to_crop {
+            let default_length = self.crop_length.unwrap_or(200);
+            let mut final_attributes: HashMap<String, usize> = HashMap::new();
+
+            for attribute in attributes_to_crop.split(',') {
+                let mut attribute = attribute.split(':');
+                let attr = attribute.next();
+                let length = attribute.next().and_then(|s| s.parse().ok()).unwrap_or(default_length);
+                match attr {
+                    Some("*") => {
+                        for attr in &restricted_attributes {
+                            final_attributes.insert(attr.to_string(), length);
+                        }
+                    },
+                    Some(attr) => {
+                        if available_attributes.contains(attr) {
+                            final_attributes.insert(attr.to_string(), length);
+                        } else {
+                            warn!("The attributes {:?} present in attributesToCrop parameter doesn't exist", attr);
+                        }
+                    },
+                    None => (),
+                }
+            }
+
+            search_builder.attributes_to_crop(final_attributes);
+
+            if let Some(crop_marker) = &self.crop_marker {
+                search_builder.crop_marker(crop_marker.clone());
+            }
+        }
+
+        if let Some(attributes_to_highlight) = &self.attributes_to_highlight {
+            let default_tags = HighlightTags {
+                pre_tag: self.highlight_pre_tag.clone().unwrap_or_else(|| HighlightTags::default().pre_tag),
+                post_tag: self.highlight_post_tag.clone().unwrap_or_else(|| HighlightTags::default().post_tag),
+            };
+
+            let mut final_attributes: HashMap<String, HighlightTags> = HashMap::new();
+            for attribute in attributes_to_highlight.split(',') {
+                if attribute == "*" {
                     for attr in &restricted_attributes {
-                        final_attributes.insert(attr.to_string(), length);
+                        final_attributes.insert(attr.to_string(), default_tags.clone());
                     }
-                },
-                Some(attr) => {
-                    if available_attributes.contains(attr) {
-                        final_attributes.insert(attr.to_string(), length);
+                } else {
+                    if available_attributes.contains(attribute) {
+                        final_attributes.insert(attribute.to_string(), default_tags.clone());
                     } else {
-                        warn!("The attributes {:?} present in attributesToCrop parameter doesn't exist", attr);
+                        warn!("The attributes {:?} present in attributesToHighlight parameter doesn't exist", attribute);
                     }
-                },
-                None => (),
+                }
             }
+
+            if let Some(highlight_tags) = &self.highlight_tags {
+                for (attribute, tags) in prepare_highlight_tags(highlight_tags)? {
+                    match tags {
+                        Some(tags) => {
+                            if let Some(attribute_tags) = final_attributes.get_mut(&attribute) {
+                                *attribute_tags = tags;
+                            }
+                        },
+                        None => { final_attributes.remove(&attribute); },
+                    }
+                }
+            }
+
+            search_builder.attributes_to_highlight(final_attributes);
         }
 
-        search_builder.attributes_to_crop(final_attributes);
-    }
+        if let Some(language) = &self.language {
+            if !is_valid_language(language) {
+                return Err(ResponseError::bad_parameter("language", "invalid language code"));
+            }
+            search_builder.language(language.to_string());
+        }
 
-    if let Some(attributes_to_highlight) = &params.attributes_to_highlight {
-        let mut final_attributes: HashSet<String> = HashSet::new();
-        for attribute in attributes_to_highlight.split(',') {
-            if attribute == "*" {
-                for attr in &restricted_attributes {
-                    final_attributes.insert(attr.to_string());
-                }
-            } else {
-                if available_attributes.contains(attribute) {
-                    final_attributes.insert(attribute.to_string());
-                } else {
-                    warn!("The attributes {:?} present in attributesToHighlight parameter doesn't exist", attribute);
-                }
+        if let Some(ranking_rules) = &self.ranking_rules {
+            search_builder.ranking_rules(prepare_ranking_rules(ranking_rules)?);
+        }
+
+        if let Some(sort) = &self.sort {
+            search_builder.sort(prepare_sort(sort)?);
+        }
+
+        if let Some(around_lat_lng) = &self.around_lat_lng {
+            search_builder.around(prepare_around_lat_lng(around_lat_lng)?);
+        }
+
+        if let Some(attributes_to_search_in) = &self.attributes_to_search_in {
+            let attributes = attributes_to_search_in.split(',').map(str::to_string).collect();
+            search_builder.attributes_to_search_in(attributes);
+        }
+
+        if let Some(attribute_weights) = &self.attribute_weights {
+            search_builder.attribute_weights(prepare_attribute_weights(attribute_weights)?);
+        }
+
+        if let Some(matching_strategy) = self.matching_strategy {
+            search_builder.matching_strategy(matching_strategy);
+        }
+
+        if let Some(exhaustive_nb_hits) = self.exhaustive_nb_hits {
+            search_builder.exhaustive_nb_hits(exhaustive_nb_hits);
+        }
+
+        if let Some(search_cutoff_ms) = self.search_cutoff_ms {
+            search_builder.time_budget(Duration::from_millis(search_cutoff_ms));
+        }
+
+        if let Some(distinct) = &self.distinct {
+            search_builder.distinct(distinct.to_string());
+        }
+
+        if let Some(distinct_size) = self.distinct_size {
+            if distinct_size == 0 {
+                return Err(ResponseError::bad_parameter("distinctSize", "must be greater than 0"));
             }
+            search_builder.distinct_size(distinct_size);
         }
 
-        search_builder.attributes_to_highlight(final_attributes);
-    }
+        if let Some(profile) = self.profile {
+            search_builder.profile(profile);
+        }
 
-    if let Some(filters) = &params.filters {
-        search_builder.filters(filters.to_string());
-    }
+        if let Some(show_ranking_score_details) = self.show_ranking_score_details {
+            search_builder.ranking_score_details(show_ranking_score_details);
+        }
 
-    if let Some(matches) = params.matches {
-        if matches {
-            search_builder.get_matches();
+        if let Some(suggest_query) = self.suggest_query {
+            search_builder.suggest_query(suggest_query);
         }
+
+        if let Some(filters) = &self.filters {
+            search_builder.filters(filters.to_string());
+        }
+
+        if let Some(matches) = self.matches {
+            if matches {
+                search_builder.get_matches();
+            }
+        }
+
+        if let Some(matches_unit) = self.matches_unit {
+            search_builder.matches_unit(matches_unit);
+        }
+
+        search_builder.search(reader)
+    }
+}
+
+#[get("/indexes/{index_uid}/search", wrap = "Authentication::Public")]
+async fn search_with_url_query(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    params: web::Query<SearchQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let reader = data.db.main_read_txn()?;
+    let result = params.search(&index, &reader)?;
+    reader.abort();
+
+    data.record_search(&index, result.nb_hits, result.processing_time_ms, &result.query);
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub(crate) struct MultiSearchQuery {
+    index_uids: String,
+    q: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MultiSearchResult {
+    hits: Vec<IndexMap<String, Value>>,
+    offset: usize,
+    limit: usize,
+    nb_hits: usize,
+    exhaustive_nb_hits: bool,
+    processing_time_ms: usize,
+    query: String,
+}
+
+/// Searches several indexes at once, the hits are merged by relevancy
+/// and tagged with the uid of the index they come from.
+#[get("/multi-search", wrap = "Authentication::Public")]
+async fn multi_search_with_url_query(
+    data: web::Data<Data>,
+    params: web::Query<MultiSearchQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    let indexes_uids: Vec<&str> = params.index_uids.split(',').collect();
+    let offset = params.offset.unwrap_or(0);
+    let limit = params.limit.unwrap_or(20);
+
+    let start = Instant::now();
+    let result = data.db.multi_search(&indexes_uids, &params.q, offset..offset + limit)?;
+    let processing_time_ms = start.elapsed().as_millis() as usize;
+
+    let reader = data.db.main_read_txn()?;
+    let mut hits = Vec::with_capacity(result.hits.len());
+    for hit in result.hits {
+        let index = data
+            .db
+            .open_index(&hit.index_uid)
+            .ok_or(ResponseError::index_not_found(&hit.index_uid))?;
+        let document_id = hit.document.id;
+        let mut document: IndexMap<String, Value> = index
+            .document(&reader, None, document_id)
+            .map_err(|e| ResponseError::retrieve_document(document_id.0, e))?
+            .ok_or(ResponseError::document_not_found(document_id.0))?;
+        document.insert("_indexUid".to_string(), Value::String(hit.index_uid));
+        hits.push(document);
     }
 
-    Ok(HttpResponse::Ok().json(search_builder.search(&reader)?))
+    Ok(HttpResponse::Ok().json(MultiSearchResult {
+        hits,
+        offset,
+        limit,
+        nb_hits: result.nb_hits,
+        exhaustive_nb_hits: result.exhaustive_nb_hits,
+        processing_time_ms,
+        query: params.q.clone(),
+    }))
+}
+
+/// Parses the per-attribute highlight configuration, a JSON object associating attributes
+/// to their `preTag` and `postTag`, or to `false` to disable their highlighting.
+fn prepare_highlight_tags(highlight_tags: &str) -> Result<Vec<(String, Option<HighlightTags>)>, ResponseError> {
+    let attributes: HashMap<String, Value> = serde_json::from_str(highlight_tags)
+        .map_err(|e| ResponseError::bad_parameter("highlightTags", e))?;
+    let mut result = Vec::with_capacity(attributes.len());
+    for (attribute, tags) in attributes {
+        let tags = match tags {
+            Value::Bool(false) | Value::Null => None,
+            tags => Some(serde_json::from_value(tags).map_err(|e| ResponseError::bad_parameter("highlightTags", e))?),
+        };
+        result.push((attribute, tags));
+    }
+    Ok(result)
 }
 
 /// Parses the incoming string into an array of attributes for which to return a count. It returns
@@ -200,3 +422,51 @@ fn prepare_facet_list(facets: &str, schema: &Schema, facet_attrs: &[FieldId]) ->
         bad_val => return Err(FacetCountError::unexpected_token(bad_val, &["[String]"]))
     }
 }
+
+/// Parses the ranking rules overriding the ones of the index, a JSON array of rules
+/// following the same syntax as the ranking rules setting.
+fn prepare_ranking_rules(ranking_rules: &str) -> Result<Vec<RankingRule>, ResponseError> {
+    let rules: Vec<String> = serde_json::from_str(ranking_rules)
+        .map_err(|e| ResponseError::bad_parameter("rankingRules", e))?;
+    RankingRule::from_iter(rules).map_err(|e| ResponseError::bad_parameter("rankingRules", e))
+}
+
+/// Parses the sort rules, a JSON array of `attribute:asc` or `attribute:desc` rules
+/// applied in order before the ranking rules.
+fn prepare_sort(sort: &str) -> Result<Vec<SortRule>, ResponseError> {
+    let rules: Vec<String> = serde_json::from_str(sort)
+        .map_err(|e| ResponseError::bad_parameter("sort", e))?;
+    rules
+        .iter()
+        .map(|rule| rule.parse().map_err(|e| ResponseError::bad_parameter("sort", e)))
+        .collect()
+}
+
+/// Parses the weights given to the attributes at query time, `attribute:weight` pairs
+/// separated by commas, the weights must be positive numbers.
+fn prepare_attribute_weights(attribute_weights: &str) -> Result<Vec<(String, f64)>, ResponseError> {
+    attribute_weights
+        .split(',')
+        .map(|pair| {
+            let mut pair = pair.splitn(2, ':');
+            match (pair.next(), pair.next().map(|w| w.trim().parse::<f64>())) {
+                (Some(attr), Some(Ok(weight))) if weight.is_finite() && weight > 0.0 => {
+                    Ok((attr.trim().to_string(), weight))
+                }
+                _ => Err(ResponseError::bad_parameter("attributeWeights", "expected attribute:weight pairs with positive weights")),
+            }
+        })
+        .collect()
+}
+
+/// Parses the point used by the `geo:asc` ranking rule, a latitude and a longitude
+/// separated by a comma.
+fn prepare_around_lat_lng(around_lat_lng: &str) -> Result<GeoPoint, ResponseError> {
+    let mut coordinates = around_lat_lng.split(',').map(|c| c.trim().parse::<f64>());
+    match (coordinates.next(), coordinates.next(), coordinates.next()) {
+        (Some(Ok(lat)), Some(Ok(lng)), None) => {
+            GeoPoint::new(lat, lng).map_err(|e| ResponseError::bad_parameter("aroundLatLng", e))
+        }
+        _ => Err(ResponseError::bad_parameter("aroundLatLng", "expected a latitude and a longitude separated by a comma")),
+    }
+}
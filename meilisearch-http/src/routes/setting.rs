@@ -1,6 +1,12 @@
 use actix_web::{web, HttpResponse};
 use actix_web_macros::{delete, get, post};
-use meilisearch_core::settings::{Settings, SettingsUpdate, UpdateState, DEFAULT_RANKING_RULES};
+use meilisearch_core::settings::{
+    is_valid_language, DocumentIdFormat, FacetNormalization, PrefixSearch, Settings, SettingsUpdate, TokenPolicy, TypoTolerance, UpdateState,
+    DEFAULT_RANKING_RULES, DEFAULT_COMPRESS_DOCUMENTS, DEFAULT_FOLD_DIACRITICS, DEFAULT_MAX_POSITIONS_PER_ATTRIBUTE, DEFAULT_MAX_TOKEN_LENGTH,
+    DEFAULT_MAX_TOTAL_HITS, DEFAULT_STOP_WORDS_IN_PHRASES, MAX_POSITIONS_PER_ATTRIBUTE, MAX_PREFIX_LENGTH, MAX_TOKEN_LENGTH,
+};
+use meilisearch_core::{Index, MResult, MainT};
+use meilisearch_schema::{FieldFlags, FieldType};
 use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 use crate::error::ResponseError;
@@ -25,10 +31,55 @@ pub fn services(cfg: &mut web::ServiceConfig) {
         .service(update_displayed)
         .service(delete_displayed)
         .service(get_accept_new_fields)
-        .service(update_accept_new_fields);
+        .service(update_accept_new_fields)
+        .service(get_document_id_format)
+        .service(update_document_id_format)
+        .service(delete_document_id_format)
+        .service(get_language)
+        .service(update_language)
+        .service(delete_language)
+        .service(get_fold_diacritics)
+        .service(update_fold_diacritics)
+        .service(delete_fold_diacritics)
+        .service(get_stop_words_in_phrases)
+        .service(update_stop_words_in_phrases)
+        .service(delete_stop_words_in_phrases)
+        .service(get_compress_documents)
+        .service(update_compress_documents)
+        .service(delete_compress_documents)
+        .service(get_max_token_length)
+        .service(update_max_token_length)
+        .service(delete_max_token_length)
+        .service(get_max_positions_per_attribute)
+        .service(update_max_positions_per_attribute)
+        .service(delete_max_positions_per_attribute)
+        .service(get_max_total_hits)
+        .service(update_max_total_hits)
+        .service(delete_max_total_hits)
+        .service(get_search_cutoff_ms)
+        .service(update_search_cutoff_ms)
+        .service(delete_search_cutoff_ms)
+        .service(get_typo_tolerance)
+        .service(update_typo_tolerance)
+        .service(delete_typo_tolerance)
+        .service(get_prefix_search)
+        .service(update_prefix_search)
+        .service(delete_prefix_search)
+        .service(get_field_types)
+        .service(update_field_types)
+        .service(delete_field_types)
+        .service(get_field_flags)
+        .service(update_field_flags)
+        .service(delete_field_flags)
+        .service(get_facet_normalization)
+        .service(update_facet_normalization)
+        .service(delete_facet_normalization)
+        .service(get_token_policy)
+        .service(update_token_policy)
+        .service(delete_token_policy);
 }
 
-#[post("/indexes/{index_uid}/settings", wrap = "Authentication::Private")]
+#[post("/indexes/{index_uid}/settings", wrap = "Authentication::Settings")]
 async fn update_all(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
@@ -39,18 +90,26 @@ async fn update_all(
         .open_index(&path.index_uid)
         .ok_or(ResponseError::index_not_found(&path.index_uid))?;
 
+    let update_id = push_settings(&data, &index, body.into_inner())?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+/// Enqueues an update of all the settings of the index, returns the id of the update.
+pub(crate) fn push_settings(data: &Data, index: &Index, settings: Settings) -> Result<u64, ResponseError> {
     let mut writer = data.db.update_write_txn()?;
-    let settings = body
-        .into_inner()
-        .into_update()
-        .map_err(ResponseError::bad_request)?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    data.check_update_queue(&writer, index)?;
+    let settings = settings.into_update().map_err(ResponseError::bad_request)?;
+    check_indexing_limits(&settings)?;
+    check_typo_tolerance(&settings)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
     writer.commit()?;
 
-    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+    Ok(update_id)
 }
 
-#[get("/indexes/{index_uid}/settings", wrap = "Authentication::Private")]
+#[get("/indexes/{index_uid}/settings", wrap = "Authentication::Settings")]
 async fn get_all(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
@@ -61,18 +120,24 @@ async fn get_all(
         .ok_or(ResponseError::index_not_found(&path.index_uid))?;
 
     let reader = data.db.main_read_txn()?;
+    let settings = read_settings(&index, &reader)?;
 
-    let stop_words_fst = index.main.stop_words_fst(&reader)?;
+    Ok(HttpResponse::Ok().json(settings))
+}
+
+/// Reads all the settings of the index, the ones that are not set hold their default value.
+pub(crate) fn read_settings(index: &Index, reader: &heed::RoTxn<MainT>) -> MResult<Settings> {
+    let stop_words_fst = index.main.stop_words_fst(reader)?;
     let stop_words = stop_words_fst.unwrap_or_default().stream().into_strs()?;
     let stop_words: BTreeSet<String> = stop_words.into_iter().collect();
 
-    let synonyms_fst = index.main.synonyms_fst(&reader)?.unwrap_or_default();
+    let synonyms_fst = index.main.synonyms_fst(reader)?.unwrap_or_default();
     let synonyms_list = synonyms_fst.stream().into_strs()?;
 
     let mut synonyms = BTreeMap::new();
     let index_synonyms = &index.synonyms;
     for synonym in synonyms_list {
-        let alternative_list = index_synonyms.synonyms(&reader, synonym.as_bytes())?;
+        let alternative_list = index_synonyms.synonyms(reader, synonym.as_bytes())?;
         if let Some(list) = alternative_list {
             let list = list.stream().into_strs()?;
             synonyms.insert(synonym, list);
@@ -81,17 +146,17 @@ async fn get_all(
 
     let ranking_rules = index
         .main
-        .ranking_rules(&reader)?
+        .ranking_rules(reader)?
         .unwrap_or(DEFAULT_RANKING_RULES.to_vec())
         .into_iter()
         .map(|r| r.to_string())
         .collect();
 
-    let distinct_attribute = index.main.distinct_attribute(&reader)?;
+    let distinct_attribute = index.main.distinct_attribute(reader)?;
 
-    let schema = index.main.schema(&reader)?;
+    let schema = index.main.schema(reader)?;
 
-    let attributes_for_faceting = match (&schema, &index.main.attributes_for_faceting(&reader)?) {
+    let attributes_for_faceting = match (&schema, &index.main.attributes_for_faceting(reader)?) {
         (Some(schema), Some(attrs)) => {
             Some(attrs
                 .iter()
@@ -102,6 +167,17 @@ async fn get_all(
         _ => None,
     };
 
+    let sortable_attributes = match (&schema, &index.main.sortable_attributes(reader)?) {
+        (Some(schema), Some(attrs)) => {
+            Some(attrs
+                .iter()
+                .filter_map(|&id| schema.name(id))
+                .map(str::to_string)
+                .collect())
+        }
+        _ => None,
+    };
+
     let searchable_attributes = schema.clone().map(|s| {
         s.indexed_name()
             .iter()
@@ -118,7 +194,32 @@ async fn get_all(
 
     let accept_new_fields = schema.map(|s| s.accept_new_fields());
 
-    let settings = Settings {
+    let document_id_format = index.main.document_id_format(reader)?.unwrap_or_default();
+    let max_token_length = index.main.max_token_length(reader)?.unwrap_or(DEFAULT_MAX_TOKEN_LENGTH);
+    let max_positions_per_attribute = index
+        .main
+        .max_positions_per_attribute(reader)?
+        .unwrap_or(DEFAULT_MAX_POSITIONS_PER_ATTRIBUTE);
+    let max_total_hits = index.main.max_total_hits(reader)?.unwrap_or(DEFAULT_MAX_TOTAL_HITS);
+    let search_cutoff_ms = index.main.search_cutoff_ms(reader)?;
+    let typo_tolerance = index.main.typo_tolerance(reader)?.unwrap_or_default();
+    let prefix_search = index.main.prefix_search(reader)?.unwrap_or_default();
+    let facet_normalization = index.main.facet_normalization(reader)?.unwrap_or_default();
+    let token_policy = index.main.token_policy(reader)?.unwrap_or_default();
+    let field_types = schema.as_ref().map(|s| {
+        s.field_types_name()
+            .into_iter()
+            .map(|(name, field_type)| (name.to_string(), field_type))
+            .collect::<BTreeMap<String, FieldType>>()
+    });
+    let field_flags = schema.as_ref().map(|s| {
+        s.field_flags_name()
+            .into_iter()
+            .map(|(name, flags)| (name.to_string(), flags))
+            .collect::<BTreeMap<String, FieldFlags>>()
+    });
+
+    Ok(Settings {
         ranking_rules: Some(Some(ranking_rules)),
         distinct_attribute: Some(distinct_attribute),
         searchable_attributes: Some(searchable_attributes),
@@ -127,12 +228,22 @@ async fn get_all(
         synonyms: Some(Some(synonyms)),
         accept_new_fields: Some(accept_new_fields),
         attributes_for_faceting: Some(attributes_for_faceting),
-    };
-
-    Ok(HttpResponse::Ok().json(settings))
+        sortable_attributes: Some(sortable_attributes),
+        document_id_format: Some(Some(document_id_format)),
+        max_token_length: Some(Some(max_token_length)),
+        max_positions_per_attribute: Some(Some(max_positions_per_attribute)),
+        max_total_hits: Some(Some(max_total_hits)),
+        search_cutoff_ms: Some(search_cutoff_ms),
+        typo_tolerance: Some(Some(typo_tolerance)),
+        prefix_search: Some(Some(prefix_search)),
+        field_types: Some(field_types),
+        field_flags: Some(field_flags),
+        facet_normalization: Some(Some(facet_normalization)),
+        token_policy: Some(Some(token_policy)),
+    })
 }
 
-#[delete("/indexes/{index_uid}/settings", wrap = "Authentication::Private")]
+#[delete("/indexes/{index_uid}/settings", wrap = "Authentication::Settings")]
 async fn delete_all(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
@@ -142,6 +253,7 @@ async fn delete_all(
         .open_index(&path.index_uid)
         .ok_or(ResponseError::index_not_found(&path.index_uid))?;
     let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
 
     let settings = SettingsUpdate {
         ranking_rules: UpdateState::Clear,
@@ -153,6 +265,24 @@ async fn delete_all(
         synonyms: UpdateState::Clear,
         accept_new_fields: UpdateState::Clear,
         attributes_for_faceting: UpdateState::Clear,
+        document_id_format: UpdateState::Clear,
+        language: UpdateState::Clear,
+        localized_synonyms: UpdateState::Clear,
+        max_token_length: UpdateState::Clear,
+        max_positions_per_attribute: UpdateState::Clear,
+        max_total_hits: UpdateState::Clear,
+        search_cutoff_ms: UpdateState::Clear,
+        sortable_attributes: UpdateState::Clear,
+        typo_tolerance: UpdateState::Clear,
+        prefix_search: UpdateState::Clear,
+        field_types: UpdateState::Clear,
+        field_flags: UpdateState::Clear,
+        facet_normalization: UpdateState::Clear,
+        token_policy: UpdateState::Clear,
+        fold_diacritics: UpdateState::Clear,
+        stop_words_in_phrases: UpdateState::Clear,
+        compress_documents: UpdateState::Clear,
+        query_rules: UpdateState::Nothing,
     };
 
     let update_id = index.settings_update(&mut writer, settings)?;
@@ -163,7 +293,7 @@ async fn delete_all(
 
 #[get(
     "/indexes/{index_uid}/settings/ranking-rules",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn get_rules(
     data: web::Data<Data>,
@@ -188,7 +318,7 @@ async fn get_rules(
 
 #[post(
     "/indexes/{index_uid}/settings/ranking-rules",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn update_rules(
     data: web::Data<Data>,
@@ -206,8 +336,10 @@ async fn update_rules(
     };
 
     let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
     let settings = settings.into_update().map_err(ResponseError::bad_request)?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
     writer.commit()?;
 
     Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
@@ -215,7 +347,7 @@ async fn update_rules(
 
 #[delete(
     "/indexes/{index_uid}/settings/ranking-rules",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn delete_rules(
     data: web::Data<Data>,
@@ -226,6 +358,7 @@ async fn delete_rules(
         .open_index(&path.index_uid)
         .ok_or(ResponseError::index_not_found(&path.index_uid))?;
     let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
 
     let settings = SettingsUpdate {
         ranking_rules: UpdateState::Clear,
@@ -241,7 +374,7 @@ async fn delete_rules(
 
 #[get(
     "/indexes/{index_uid}/settings/distinct-attribute",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn get_distinct(
     data: web::Data<Data>,
@@ -259,7 +392,7 @@ async fn get_distinct(
 
 #[post(
     "/indexes/{index_uid}/settings/distinct-attribute",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn update_distinct(
     data: web::Data<Data>,
@@ -277,8 +410,10 @@ async fn update_distinct(
     };
 
     let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
     let settings = settings.into_update().map_err(ResponseError::bad_request)?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
     writer.commit()?;
 
     Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
@@ -286,7 +421,7 @@ async fn update_distinct(
 
 #[delete(
     "/indexes/{index_uid}/settings/distinct-attribute",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn delete_distinct(
     data: web::Data<Data>,
@@ -297,6 +432,7 @@ async fn delete_distinct(
         .open_index(&path.index_uid)
         .ok_or(ResponseError::index_not_found(&path.index_uid))?;
     let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
 
     let settings = SettingsUpdate {
         distinct_attribute: UpdateState::Clear,
@@ -312,7 +448,7 @@ async fn delete_distinct(
 
 #[get(
     "/indexes/{index_uid}/settings/searchable-attributes",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn get_searchable(
     data: web::Data<Data>,
@@ -332,7 +468,7 @@ async fn get_searchable(
 
 #[post(
     "/indexes/{index_uid}/settings/searchable-attributes",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn update_searchable(
     data: web::Data<Data>,
@@ -350,8 +486,10 @@ async fn update_searchable(
     };
 
     let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
     let settings = settings.into_update().map_err(ResponseError::bad_request)?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
     writer.commit()?;
 
     Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
@@ -359,7 +497,7 @@ async fn update_searchable(
 
 #[delete(
     "/indexes/{index_uid}/settings/searchable-attributes",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn delete_searchable(
     data: web::Data<Data>,
@@ -376,6 +514,7 @@ async fn delete_searchable(
     };
 
     let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
     let update_id = index.settings_update(&mut writer, settings)?;
     writer.commit()?;
 
@@ -384,7 +523,7 @@ async fn delete_searchable(
 
 #[get(
     "/indexes/{index_uid}/settings/displayed-attributes",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn get_displayed(
     data: web::Data<Data>,
@@ -406,7 +545,7 @@ async fn get_displayed(
 
 #[post(
     "/indexes/{index_uid}/settings/displayed-attributes",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn update_displayed(
     data: web::Data<Data>,
@@ -424,8 +563,10 @@ async fn update_displayed(
     };
 
     let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
     let settings = settings.into_update().map_err(ResponseError::bad_request)?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
     writer.commit()?;
 
     Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
@@ -433,7 +574,7 @@ async fn update_displayed(
 
 #[delete(
     "/indexes/{index_uid}/settings/displayed-attributes",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn delete_displayed(
     data: web::Data<Data>,
@@ -450,6 +591,7 @@ async fn delete_displayed(
     };
 
     let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
     let update_id = index.settings_update(&mut writer, settings)?;
     writer.commit()?;
 
@@ -458,7 +600,7 @@ async fn delete_displayed(
 
 #[get(
     "/indexes/{index_uid}/settings/accept-new-fields",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn get_accept_new_fields(
     data: web::Data<Data>,
@@ -479,7 +621,7 @@ async fn get_accept_new_fields(
 
 #[post(
     "/indexes/{index_uid}/settings/accept-new-fields",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn update_accept_new_fields(
     data: web::Data<Data>,
@@ -497,7 +639,1179 @@ async fn update_accept_new_fields(
     };
 
     let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let settings = settings.into_update().map_err(ResponseError::bad_request)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/document-id-format",
+    wrap = "Authentication::Settings"
+)]
+async fn get_document_id_format(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+
+    let document_id_format = index.main.document_id_format(&reader)?.unwrap_or_default();
+
+    Ok(HttpResponse::Ok().json(document_id_format))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/document-id-format",
+    wrap = "Authentication::Settings"
+)]
+async fn update_document_id_format(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<DocumentIdFormat>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        document_id_format: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
     let settings = settings.into_update().map_err(ResponseError::bad_request)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/document-id-format",
+    wrap = "Authentication::Settings"
+)]
+async fn delete_document_id_format(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        document_id_format: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = index.settings_update(&mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/language",
+    wrap = "Authentication::Settings"
+)]
+async fn get_language(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+
+    let language = index.main.language(&reader)?;
+
+    Ok(HttpResponse::Ok().json(language))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/language",
+    wrap = "Authentication::Settings"
+)]
+async fn update_language(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<String>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let language = body.into_inner();
+    if !is_valid_language(&language) {
+        return Err(ResponseError::bad_parameter("language", "invalid language code"));
+    }
+
+    let settings = SettingsUpdate {
+        language: UpdateState::Update(language),
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/language",
+    wrap = "Authentication::Settings"
+)]
+async fn delete_language(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        language: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = index.settings_update(&mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/fold-diacritics",
+    wrap = "Authentication::Settings"
+)]
+async fn get_fold_diacritics(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+
+    let fold_diacritics = index.main.fold_diacritics(&reader)?.unwrap_or(DEFAULT_FOLD_DIACRITICS);
+
+    Ok(HttpResponse::Ok().json(fold_diacritics))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/fold-diacritics",
+    wrap = "Authentication::Settings"
+)]
+async fn update_fold_diacritics(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<bool>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        fold_diacritics: UpdateState::Update(body.into_inner()),
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/fold-diacritics",
+    wrap = "Authentication::Settings"
+)]
+async fn delete_fold_diacritics(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        fold_diacritics: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = index.settings_update(&mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/stop-words-in-phrases",
+    wrap = "Authentication::Settings"
+)]
+async fn get_stop_words_in_phrases(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+
+    let stop_words_in_phrases = index.main.stop_words_in_phrases(&reader)?.unwrap_or(DEFAULT_STOP_WORDS_IN_PHRASES);
+
+    Ok(HttpResponse::Ok().json(stop_words_in_phrases))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/stop-words-in-phrases",
+    wrap = "Authentication::Settings"
+)]
+async fn update_stop_words_in_phrases(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<bool>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        stop_words_in_phrases: UpdateState::Update(body.into_inner()),
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/stop-words-in-phrases",
+    wrap = "Authentication::Settings"
+)]
+async fn delete_stop_words_in_phrases(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        stop_words_in_phrases: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = index.settings_update(&mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/compress-documents",
+    wrap = "Authentication::Settings"
+)]
+async fn get_compress_documents(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+
+    let compress_documents = index.main.compress_documents(&reader)?.unwrap_or(DEFAULT_COMPRESS_DOCUMENTS);
+
+    Ok(HttpResponse::Ok().json(compress_documents))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/compress-documents",
+    wrap = "Authentication::Settings"
+)]
+async fn update_compress_documents(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<bool>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        compress_documents: UpdateState::Update(body.into_inner()),
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/compress-documents",
+    wrap = "Authentication::Settings"
+)]
+async fn delete_compress_documents(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        compress_documents: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = index.settings_update(&mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+/// Rejects the indexing limits that can't be honored by the index.
+pub(crate) fn check_indexing_limits(settings: &SettingsUpdate) -> Result<(), ResponseError> {
+    if let UpdateState::Update(length) = settings.max_token_length {
+        if length == 0 || length > MAX_TOKEN_LENGTH {
+            let message = format!("must be between 1 and {}", MAX_TOKEN_LENGTH);
+            return Err(ResponseError::bad_parameter("maxTokenLength", message));
+        }
+    }
+
+    if let UpdateState::Update(positions) = settings.max_positions_per_attribute {
+        if positions == 0 || positions > MAX_POSITIONS_PER_ATTRIBUTE {
+            let message = format!("must be between 1 and {}", MAX_POSITIONS_PER_ATTRIBUTE);
+            return Err(ResponseError::bad_parameter("maxPositionsPerAttribute", message));
+        }
+    }
+
+    if let UpdateState::Update(prefix_search) = &settings.prefix_search {
+        if prefix_search.max_prefix_length > MAX_PREFIX_LENGTH {
+            let message = format!("maxPrefixLength must be between 0 and {}", MAX_PREFIX_LENGTH);
+            return Err(ResponseError::bad_parameter("prefixSearch", message));
+        }
+    }
+
+    Ok(())
+}
+
+/// Rejects a typo tolerance allowing two typos on words too short to accept one.
+pub(crate) fn check_typo_tolerance(settings: &SettingsUpdate) -> Result<(), ResponseError> {
+    if let UpdateState::Update(typo_tolerance) = &settings.typo_tolerance {
+        if typo_tolerance.min_word_size_for_one_typo > typo_tolerance.min_word_size_for_two_typos {
+            let message = "minWordSizeForOneTypo must be lower than or equal to minWordSizeForTwoTypos";
+            return Err(ResponseError::bad_parameter("typoTolerance", message));
+        }
+    }
+
+    Ok(())
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/max-token-length",
+    wrap = "Authentication::Settings"
+)]
+async fn get_max_token_length(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+
+    let max_token_length = index.main.max_token_length(&reader)?.unwrap_or(DEFAULT_MAX_TOKEN_LENGTH);
+
+    Ok(HttpResponse::Ok().json(max_token_length))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/max-token-length",
+    wrap = "Authentication::Settings"
+)]
+async fn update_max_token_length(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<usize>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        max_token_length: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let settings = settings.into_update().map_err(ResponseError::bad_request)?;
+    check_indexing_limits(&settings)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/max-token-length",
+    wrap = "Authentication::Settings"
+)]
+async fn delete_max_token_length(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        max_token_length: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = index.settings_update(&mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/max-positions-per-attribute",
+    wrap = "Authentication::Settings"
+)]
+async fn get_max_positions_per_attribute(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+
+    let max_positions_per_attribute = index
+        .main
+        .max_positions_per_attribute(&reader)?
+        .unwrap_or(DEFAULT_MAX_POSITIONS_PER_ATTRIBUTE);
+
+    Ok(HttpResponse::Ok().json(max_positions_per_attribute))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/max-positions-per-attribute",
+    wrap = "Authentication::Settings"
+)]
+async fn update_max_positions_per_attribute(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<usize>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        max_positions_per_attribute: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let settings = settings.into_update().map_err(ResponseError::bad_request)?;
+    check_indexing_limits(&settings)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/max-positions-per-attribute",
+    wrap = "Authentication::Settings"
+)]
+async fn delete_max_positions_per_attribute(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        max_positions_per_attribute: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = index.settings_update(&mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/max-total-hits",
+    wrap = "Authentication::Settings"
+)]
+async fn get_max_total_hits(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+
+    let max_total_hits = index.main.max_total_hits(&reader)?.unwrap_or(DEFAULT_MAX_TOTAL_HITS);
+
+    Ok(HttpResponse::Ok().json(max_total_hits))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/max-total-hits",
+    wrap = "Authentication::Settings"
+)]
+async fn update_max_total_hits(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<usize>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        max_total_hits: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let settings = settings.into_update().map_err(ResponseError::bad_request)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/max-total-hits",
+    wrap = "Authentication::Settings"
+)]
+async fn delete_max_total_hits(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        max_total_hits: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = index.settings_update(&mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/search-cutoff-ms",
+    wrap = "Authentication::Settings"
+)]
+async fn get_search_cutoff_ms(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+
+    let search_cutoff_ms = index.main.search_cutoff_ms(&reader)?;
+
+    Ok(HttpResponse::Ok().json(search_cutoff_ms))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/search-cutoff-ms",
+    wrap = "Authentication::Settings"
+)]
+async fn update_search_cutoff_ms(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<u64>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        search_cutoff_ms: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let settings = settings.into_update().map_err(ResponseError::bad_request)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/search-cutoff-ms",
+    wrap = "Authentication::Settings"
+)]
+async fn delete_search_cutoff_ms(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        search_cutoff_ms: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = index.settings_update(&mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/typo-tolerance",
+    wrap = "Authentication::Settings"
+)]
+async fn get_typo_tolerance(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+
+    let typo_tolerance = index.main.typo_tolerance(&reader)?.unwrap_or_default();
+
+    Ok(HttpResponse::Ok().json(typo_tolerance))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/typo-tolerance",
+    wrap = "Authentication::Settings"
+)]
+async fn update_typo_tolerance(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<TypoTolerance>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        typo_tolerance: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let settings = settings.into_update().map_err(ResponseError::bad_request)?;
+    check_typo_tolerance(&settings)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/typo-tolerance",
+    wrap = "Authentication::Settings"
+)]
+async fn delete_typo_tolerance(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        typo_tolerance: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = index.settings_update(&mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/prefix-search",
+    wrap = "Authentication::Settings"
+)]
+async fn get_prefix_search(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+
+    let prefix_search = index.main.prefix_search(&reader)?.unwrap_or_default();
+
+    Ok(HttpResponse::Ok().json(prefix_search))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/prefix-search",
+    wrap = "Authentication::Settings"
+)]
+async fn update_prefix_search(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<PrefixSearch>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        prefix_search: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let settings = settings.into_update().map_err(ResponseError::bad_request)?;
+    check_indexing_limits(&settings)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/prefix-search",
+    wrap = "Authentication::Settings"
+)]
+async fn delete_prefix_search(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        prefix_search: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = index.settings_update(&mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/field-types",
+    wrap = "Authentication::Settings"
+)]
+async fn get_field_types(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+
+    let field_types: BTreeMap<String, FieldType> = match index.main.schema(&reader)? {
+        Some(schema) => schema
+            .field_types_name()
+            .into_iter()
+            .map(|(name, field_type)| (name.to_string(), field_type))
+            .collect(),
+        None => BTreeMap::new(),
+    };
+
+    Ok(HttpResponse::Ok().json(field_types))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/field-types",
+    wrap = "Authentication::Settings"
+)]
+async fn update_field_types(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<BTreeMap<String, FieldType>>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        field_types: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let settings = settings.into_update().map_err(ResponseError::bad_request)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/field-types",
+    wrap = "Authentication::Settings"
+)]
+async fn delete_field_types(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        field_types: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = index.settings_update(&mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/field-flags",
+    wrap = "Authentication::Settings"
+)]
+async fn get_field_flags(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+
+    let field_flags: BTreeMap<String, FieldFlags> = match index.main.schema(&reader)? {
+        Some(schema) => schema
+            .field_flags_name()
+            .into_iter()
+            .map(|(name, flags)| (name.to_string(), flags))
+            .collect(),
+        None => BTreeMap::new(),
+    };
+
+    Ok(HttpResponse::Ok().json(field_flags))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/field-flags",
+    wrap = "Authentication::Settings"
+)]
+async fn update_field_flags(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<BTreeMap<String, FieldFlags>>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        field_flags: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let settings = settings.into_update().map_err(ResponseError::bad_request)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/field-flags",
+    wrap = "Authentication::Settings"
+)]
+async fn delete_field_flags(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        field_flags: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = index.settings_update(&mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/facet-normalization",
+    wrap = "Authentication::Settings"
+)]
+async fn get_facet_normalization(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+
+    let facet_normalization = index.main.facet_normalization(&reader)?.unwrap_or_default();
+
+    Ok(HttpResponse::Ok().json(facet_normalization))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/facet-normalization",
+    wrap = "Authentication::Settings"
+)]
+async fn update_facet_normalization(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<FacetNormalization>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        facet_normalization: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let settings = settings.into_update().map_err(ResponseError::bad_request)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/facet-normalization",
+    wrap = "Authentication::Settings"
+)]
+async fn delete_facet_normalization(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        facet_normalization: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = index.settings_update(&mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/token-policy",
+    wrap = "Authentication::Settings"
+)]
+async fn get_token_policy(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let reader = data.db.main_read_txn()?;
+
+    let token_policy = index.main.token_policy(&reader)?.unwrap_or_default();
+
+    Ok(HttpResponse::Ok().json(token_policy))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/token-policy",
+    wrap = "Authentication::Settings"
+)]
+async fn update_token_policy(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    body: web::Json<Option<TokenPolicy>>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = Settings {
+        token_policy: Some(body.into_inner()),
+        ..Settings::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let settings = settings.into_update().map_err(ResponseError::bad_request)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/token-policy",
+    wrap = "Authentication::Settings"
+)]
+async fn delete_token_policy(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let settings = SettingsUpdate {
+        token_policy: UpdateState::Clear,
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
     let update_id = index.settings_update(&mut writer, settings)?;
     writer.commit()?;
 
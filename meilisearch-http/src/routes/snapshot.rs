@@ -0,0 +1,35 @@
+use actix_web::{web, HttpResponse};
+use actix_web_macros::post;
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::error::ResponseError;
+use crate::helpers::Authentication;
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(create_snapshot);
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotResponse {
+    uid: String,
+}
+
+/// The snapshot is written in the `<uid>` directory of the snapshots directory,
+/// MeiliSearch can be started from it with `--import-snapshot`.
+#[post("/snapshots", wrap = "Authentication::Private")]
+async fn create_snapshot(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    let uid = Utc::now().format("%Y%m%d-%H%M%S%3f").to_string();
+    let db = data.db.clone();
+    let path = data.snapshot_dir.join(&uid);
+    web::block(move || {
+        std::fs::create_dir_all(&path)?;
+        db.snapshot(&path)
+    })
+    .await
+    .map_err(ResponseError::internal)?;
+
+    Ok(HttpResponse::Created().json(SnapshotResponse { uid }))
+}
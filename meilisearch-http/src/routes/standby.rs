@@ -0,0 +1,78 @@
+use actix_web::{web, HttpResponse};
+use actix_web_macros::{get, post};
+use serde::Serialize;
+
+use crate::error::ResponseError;
+use crate::helpers::Authentication;
+use crate::standby::SnapshotManifest;
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_standby)
+        .service(ship_snapshot)
+        .service(promote_standby);
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StandbyResponse {
+    role: &'static str,
+    sequence: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_snapshot: Option<SnapshotManifest>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn standby_response(data: &Data) -> StandbyResponse {
+    match (&data.shipper, &data.standby) {
+        (Some(shipper), _) => StandbyResponse {
+            role: "primary",
+            sequence: Some(shipper.sequence()),
+            last_snapshot: shipper.last_snapshot(),
+            error: None,
+        },
+        (None, Some(standby)) => StandbyResponse {
+            role: if standby.is_promoted() { "promoted" } else { "standby" },
+            sequence: Some(standby.applied_sequence()),
+            last_snapshot: None,
+            error: standby.error(),
+        },
+        (None, None) => StandbyResponse {
+            role: "standalone",
+            sequence: None,
+            last_snapshot: None,
+            error: None,
+        },
+    }
+}
+
+#[get("/standby", wrap = "Authentication::Private")]
+async fn get_standby(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    Ok(HttpResponse::Ok().json(standby_response(&data)))
+}
+
+/// Ships a snapshot right away instead of waiting for the next interval.
+#[post("/standby/snapshot", wrap = "Authentication::Private")]
+async fn ship_snapshot(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    let shipper = data
+        .shipper
+        .as_ref()
+        .ok_or(ResponseError::bad_request("no standby shipping directory is configured"))?;
+
+    let manifest = shipper.ship_snapshot(&data.db)?;
+
+    Ok(HttpResponse::Ok().json(manifest))
+}
+
+#[post("/standby/promote", wrap = "Authentication::Private")]
+async fn promote_standby(data: web::Data<Data>) -> Result<HttpResponse, ResponseError> {
+    let standby = data
+        .standby
+        .as_ref()
+        .ok_or(ResponseError::bad_request("this instance is not a standby"))?;
+
+    standby.promote();
+
+    Ok(HttpResponse::Ok().json(standby_response(&data)))
+}
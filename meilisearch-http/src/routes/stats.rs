@@ -5,6 +5,8 @@ use actix_web::HttpResponse;
 use actix_web_macros::get;
 use chrono::{DateTime, Utc};
 use log::error;
+use meilisearch_core::store::IndexStats;
+use meilisearch_core::EnvironmentUsage;
 use pretty_bytes::converter::convert;
 use serde::Serialize;
 use sysinfo::{NetworkExt, ProcessExt, ProcessorExt, System, SystemExt};
@@ -23,14 +25,6 @@ pub fn services(cfg: &mut web::ServiceConfig) {
         .service(get_sys_info_pretty);
 }
 
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct IndexStatsResponse {
-    number_of_documents: u64,
-    is_indexing: bool,
-    fields_frequency: HashMap<String, usize>,
-}
-
 #[get("/indexes/{index_uid}/stats", wrap = "Authentication::Private")]
 async fn index_stats(
     data: web::Data<Data>,
@@ -42,32 +36,27 @@ async fn index_stats(
         .ok_or(ResponseError::index_not_found(&path.index_uid))?;
 
     let reader = data.db.main_read_txn()?;
-
-    let number_of_documents = index.main.number_of_documents(&reader)?;
-
-    let fields_frequency = index.main.fields_frequency(&reader)?.unwrap_or_default();
-
     let update_reader = data.db.update_read_txn()?;
 
-    let is_indexing =
-        data.is_indexing(&update_reader, &path.index_uid)?
-            .ok_or(ResponseError::internal(
-                "Impossible to know if the database is indexing",
-            ))?;
+    let stats = index.stats(&reader, &update_reader)?;
 
-    Ok(HttpResponse::Ok().json(IndexStatsResponse {
-        number_of_documents,
-        is_indexing,
-        fields_frequency,
-    }))
+    Ok(HttpResponse::Ok().json(stats))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EnvironmentsStats {
+    main: EnvironmentUsage,
+    update: EnvironmentUsage,
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct StatsResult {
     database_size: u64,
+    environments: EnvironmentsStats,
     last_update: Option<DateTime<Utc>>,
-    indexes: HashMap<String, IndexStatsResponse>,
+    indexes: HashMap<String, IndexStats>,
 }
 
 #[get("/stats", wrap = "Authentication::Private")]
@@ -82,20 +71,8 @@ async fn get_stats(data: web::Data<Data>) -> Result<HttpResponse, ResponseError>
         let index = data.db.open_index(&index_uid);
         match index {
             Some(index) => {
-                let number_of_documents = index.main.number_of_documents(&reader)?;
-
-                let fields_frequency = index.main.fields_frequency(&reader)?.unwrap_or_default();
-
-                let is_indexing = data.is_indexing(&update_reader, &index_uid)?.ok_or(
-                    ResponseError::internal("Impossible to know if the database is indexing"),
-                )?;
-
-                let response = IndexStatsResponse {
-                    number_of_documents,
-                    is_indexing,
-                    fields_frequency,
-                };
-                index_list.insert(index_uid, response);
+                let stats = index.stats(&reader, &update_reader)?;
+                index_list.insert(index_uid, stats);
             }
             None => error!(
                 "Index {:?} is referenced in the indexes list but cannot be found",
@@ -111,10 +88,16 @@ async fn get_stats(data: web::Data<Data>) -> Result<HttpResponse, ResponseError>
         .filter(|metadata| metadata.is_file())
         .fold(0, |acc, m| acc + m.len());
 
+    let environments = EnvironmentsStats {
+        main: data.db.main_environment_usage()?,
+        update: data.db.update_environment_usage()?,
+    };
+
     let last_update = data.last_update(&reader)?;
 
     Ok(HttpResponse::Ok().json(StatsResult {
         database_size,
+        environments,
         last_update,
         indexes: index_list,
     }))
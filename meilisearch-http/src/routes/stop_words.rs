@@ -14,7 +14,7 @@ pub fn services(cfg: &mut web::ServiceConfig) {
 
 #[get(
     "/indexes/{index_uid}/settings/stop-words",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn get(
     data: web::Data<Data>,
@@ -33,7 +33,7 @@ async fn get(
 
 #[post(
     "/indexes/{index_uid}/settings/stop-words",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn update(
     data: web::Data<Data>,
@@ -51,7 +51,9 @@ async fn update(
     };
 
     let mut writer = data.db.update_write_txn()?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    data.check_update_queue(&writer, &index)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
     writer.commit()?;
 
     Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
@@ -59,7 +61,7 @@ async fn update(
 
 #[delete(
     "/indexes/{index_uid}/settings/stop-words",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn delete(
     data: web::Data<Data>,
@@ -76,6 +78,7 @@ async fn delete(
     };
 
     let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
     let update_id = index.settings_update(&mut writer, settings)?;
     writer.commit()?;
 
@@ -3,7 +3,8 @@ use std::collections::BTreeMap;
 use actix_web::{web, HttpResponse};
 use actix_web_macros::{delete, get, post};
 use indexmap::IndexMap;
-use meilisearch_core::settings::{SettingsUpdate, UpdateState};
+use meilisearch_core::settings::{is_valid_language, mutual_synonyms, SettingsUpdate, UpdateState};
+use serde::Deserialize;
 
 use crate::error::ResponseError;
 use crate::helpers::Authentication;
@@ -11,12 +12,51 @@ use crate::routes::{IndexParam, IndexUpdateResponse};
 use crate::Data;
 
 pub fn services(cfg: &mut web::ServiceConfig) {
-    cfg.service(get).service(update).service(delete);
+    cfg.service(get)
+        .service(update)
+        .service(delete)
+        .service(get_localized)
+        .service(update_localized)
+        .service(delete_localized);
+}
+
+#[derive(Deserialize)]
+struct LanguageParam {
+    index_uid: String,
+    language: String,
+}
+
+impl LanguageParam {
+    fn language(&self) -> Result<&str, ResponseError> {
+        if is_valid_language(&self.language) {
+            Ok(&self.language)
+        } else {
+            Err(ResponseError::bad_parameter("language", "invalid language code"))
+        }
+    }
+}
+
+/// The synonyms are either given as one-way synonyms, indexed by word,
+/// or as groups of words that are all synonyms of each other.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SynonymsBody {
+    OneWay(BTreeMap<String, Vec<String>>),
+    Mutual(Vec<Vec<String>>),
+}
+
+impl SynonymsBody {
+    fn into_synonyms(self) -> BTreeMap<String, Vec<String>> {
+        match self {
+            SynonymsBody::OneWay(synonyms) => synonyms,
+            SynonymsBody::Mutual(groups) => mutual_synonyms(groups),
+        }
+    }
 }
 
 #[get(
     "/indexes/{index_uid}/settings/synonyms",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn get(
     data: web::Data<Data>,
@@ -48,12 +88,12 @@ async fn get(
 
 #[post(
     "/indexes/{index_uid}/settings/synonyms",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn update(
     data: web::Data<Data>,
     path: web::Path<IndexParam>,
-    body: web::Json<BTreeMap<String, Vec<String>>>,
+    body: web::Json<SynonymsBody>,
 ) -> Result<HttpResponse, ResponseError> {
     let index = data
         .db
@@ -61,12 +101,14 @@ async fn update(
         .ok_or(ResponseError::index_not_found(&path.index_uid))?;
 
     let settings = SettingsUpdate {
-        synonyms: UpdateState::Update(body.into_inner()),
+        synonyms: UpdateState::Update(body.into_inner().into_synonyms()),
         ..SettingsUpdate::default()
     };
 
     let mut writer = data.db.update_write_txn()?;
-    let update_id = index.settings_update(&mut writer, settings)?;
+    data.check_update_queue(&writer, &index)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
     writer.commit()?;
 
     Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
@@ -74,7 +116,7 @@ async fn update(
 
 #[delete(
     "/indexes/{index_uid}/settings/synonyms",
-    wrap = "Authentication::Private"
+    wrap = "Authentication::Settings"
 )]
 async fn delete(
     data: web::Data<Data>,
@@ -91,6 +133,96 @@ async fn delete(
     };
 
     let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let update_id = index.settings_update(&mut writer, settings)?;
+
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[get(
+    "/indexes/{index_uid}/settings/synonyms/{language}",
+    wrap = "Authentication::Settings"
+)]
+async fn get_localized(
+    data: web::Data<Data>,
+    path: web::Path<LanguageParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let language = path.language()?;
+
+    let reader = data.db.main_read_txn()?;
+
+    let synonyms = index
+        .main
+        .localized_synonyms(&reader)?
+        .and_then(|mut localized| localized.remove(language))
+        .unwrap_or_default();
+
+    Ok(HttpResponse::Ok().json(synonyms))
+}
+
+#[post(
+    "/indexes/{index_uid}/settings/synonyms/{language}",
+    wrap = "Authentication::Settings"
+)]
+async fn update_localized(
+    data: web::Data<Data>,
+    path: web::Path<LanguageParam>,
+    body: web::Json<SynonymsBody>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let language = path.language()?.to_string();
+
+    let mut localized = BTreeMap::new();
+    localized.insert(language, body.into_inner().into_synonyms());
+
+    let settings = SettingsUpdate {
+        localized_synonyms: UpdateState::Update(localized),
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
+    let reader = data.db.main_read_txn()?;
+    let update_id = index.settings_update_unless_noop(&reader, &mut writer, settings)?;
+    writer.commit()?;
+
+    Ok(HttpResponse::Accepted().json(IndexUpdateResponse::with_id(update_id)))
+}
+
+#[delete(
+    "/indexes/{index_uid}/settings/synonyms/{language}",
+    wrap = "Authentication::Settings"
+)]
+async fn delete_localized(
+    data: web::Data<Data>,
+    path: web::Path<LanguageParam>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+    let language = path.language()?.to_string();
+
+    // an empty set of synonyms removes the language
+    let mut localized = BTreeMap::new();
+    localized.insert(language, BTreeMap::new());
+
+    let settings = SettingsUpdate {
+        localized_synonyms: UpdateState::Update(localized),
+        ..SettingsUpdate::default()
+    };
+
+    let mut writer = data.db.update_write_txn()?;
+    data.check_update_queue(&writer, &index)?;
     let update_id = index.settings_update(&mut writer, settings)?;
 
     writer.commit()?;
@@ -0,0 +1,173 @@
+use actix_web::error::BlockingError;
+use actix_web::{web, HttpResponse};
+use actix_web_macros::{delete, get, post, put};
+use meilisearch_core::DuplicatesPolicy;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ResponseError;
+use crate::helpers::csv_documents::csv_delimiter;
+use crate::helpers::documents_import::{self, ImportFormat};
+use crate::helpers::uploads::Upload;
+use crate::helpers::Authentication;
+use crate::routes::document::{addition_options, update_multiple_documents};
+use crate::routes::IndexParam;
+use crate::Data;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(create_upload)
+        .service(get_upload)
+        .service(upload_chunk)
+        .service(complete_upload)
+        .service(abort_upload);
+}
+
+#[derive(Deserialize)]
+struct UploadParam {
+    index_uid: String,
+    upload_id: String,
+}
+
+#[derive(Deserialize)]
+struct ChunkParam {
+    index_uid: String,
+    upload_id: String,
+    chunk: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadResponse {
+    upload_id: String,
+    chunks: Vec<u32>,
+}
+
+fn check_index(data: &Data, index_uid: &str) -> Result<(), ResponseError> {
+    match data.db.open_index(index_uid) {
+        Some(_) => Ok(()),
+        None => Err(ResponseError::index_not_found(index_uid)),
+    }
+}
+
+#[post("/indexes/{index_uid}/documents/uploads", wrap = "Authentication::Documents")]
+async fn create_upload(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+) -> Result<HttpResponse, ResponseError> {
+    check_index(&data, &path.index_uid)?;
+    let (upload_id, _upload) = Upload::create(&data.db_path, &path.index_uid)?;
+
+    Ok(HttpResponse::Created().json(UploadResponse {
+        upload_id,
+        chunks: Vec::new(),
+    }))
+}
+
+#[get(
+    "/indexes/{index_uid}/documents/uploads/{upload_id}",
+    wrap = "Authentication::Documents"
+)]
+async fn get_upload(
+    data: web::Data<Data>,
+    path: web::Path<UploadParam>,
+) -> Result<HttpResponse, ResponseError> {
+    check_index(&data, &path.index_uid)?;
+    let upload = Upload::open(&data.db_path, &path.index_uid, &path.upload_id)?;
+
+    Ok(HttpResponse::Ok().json(UploadResponse {
+        upload_id: path.upload_id.clone(),
+        chunks: upload.chunks()?,
+    }))
+}
+
+#[put(
+    "/indexes/{index_uid}/documents/uploads/{upload_id}/chunks/{chunk}",
+    wrap = "Authentication::Documents"
+)]
+async fn upload_chunk(
+    data: web::Data<Data>,
+    path: web::Path<ChunkParam>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ResponseError> {
+    check_index(&data, &path.index_uid)?;
+    let upload = Upload::open(&data.db_path, &path.index_uid, &path.upload_id)?;
+    upload.write_chunk(path.chunk, &body, data.http_payload_size_limit)?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct CompleteUploadQuery {
+    format: ImportFormat,
+    primary_key: Option<String>,
+    csv_delimiter: Option<char>,
+    infer_types: Option<bool>,
+    partial: Option<bool>,
+    duplicates: Option<DuplicatesPolicy>,
+    generate_ids: Option<bool>,
+}
+
+#[post(
+    "/indexes/{index_uid}/documents/uploads/{upload_id}/complete",
+    wrap = "Authentication::Documents"
+)]
+async fn complete_upload(
+    data: web::Data<Data>,
+    path: web::Path<UploadParam>,
+    params: web::Query<CompleteUploadQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    let params = params.into_inner();
+    let delimiter = csv_delimiter(params.csv_delimiter)?;
+
+    check_index(&data, &path.index_uid)?;
+    let upload = Upload::open(&data.db_path, &path.index_uid, &path.upload_id)?;
+    let reader = upload.reader(data.http_payload_size_limit)?;
+
+    // the chunks are read and parsed on the blocking thread pool
+    let format = params.format;
+    let infer_types = params.infer_types.unwrap_or(false);
+    let max_document_size = data.http_payload_document_size_limit;
+    let documents = web::block(move || {
+        documents_import::read_documents(reader, format, delimiter, infer_types, max_document_size)
+    })
+    .await
+    .map_err(|e| match e {
+        BlockingError::Error(e) => e,
+        BlockingError::Canceled => ResponseError::internal("the upload completion was canceled"),
+    })?;
+
+    let index_path = web::Path::from(IndexParam {
+        index_uid: path.index_uid.clone(),
+    });
+    let is_partial = params.partial.unwrap_or(false);
+    let response = update_multiple_documents(
+        data,
+        index_path,
+        params.primary_key,
+        documents,
+        is_partial,
+        addition_options(params.duplicates, params.generate_ids),
+    )
+    .await?;
+
+    // the chunks are only removed once the documents are enqueued,
+    // the client can retry the completion until then
+    upload.remove()?;
+
+    Ok(response)
+}
+
+#[delete(
+    "/indexes/{index_uid}/documents/uploads/{upload_id}",
+    wrap = "Authentication::Documents"
+)]
+async fn abort_upload(
+    data: web::Data<Data>,
+    path: web::Path<UploadParam>,
+) -> Result<HttpResponse, ResponseError> {
+    check_index(&data, &path.index_uid)?;
+    let upload = Upload::open(&data.db_path, &path.index_uid, &path.upload_id)?;
+    upload.remove()?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,99 @@
+use actix_web::web;
+use actix_web::HttpResponse;
+use actix_web_macros::get;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ResponseError;
+use crate::helpers::Authentication;
+use crate::routes::IndexParam;
+use crate::Data;
+
+/// The number of suggestions returned by default.
+const DEFAULT_SUGGESTIONS_LIMIT: usize = 10;
+
+pub fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_words).service(get_suggestions);
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct WordsQuery {
+    prefix: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WordFrequency<'a> {
+    word: &'a str,
+    frequency: usize,
+}
+
+/// Returns the words of the index in lexicographic order along with the number of documents
+/// containing them, all the words are returned when no limit is given.
+#[get("/indexes/{index_uid}/words", wrap = "Authentication::Private")]
+async fn get_words(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    params: web::Query<WordsQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let reader = data.db.main_read_txn()?;
+
+    let prefix = params.prefix.as_deref().unwrap_or("");
+    let offset = params.offset.unwrap_or(0);
+    let limit = params.limit.unwrap_or(usize::max_value());
+
+    let mut words = Vec::new();
+    for result in index.words_frequencies(&reader)? {
+        let (word, frequency) = result?;
+        if word.starts_with(prefix) {
+            words.push(WordFrequency { word, frequency });
+        } else if word > prefix {
+            // the words are ordered, there is no more word with this prefix
+            break;
+        }
+    }
+
+    let words: Vec<_> = words.into_iter().skip(offset).take(limit).collect();
+
+    Ok(HttpResponse::Ok().json(words))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct SuggestQuery {
+    q: String,
+    max_distance: Option<u8>,
+    limit: Option<usize>,
+}
+
+/// Returns the words of the index the closest to the term, the most frequent first.
+#[get("/indexes/{index_uid}/suggest", wrap = "Authentication::Public")]
+async fn get_suggestions(
+    data: web::Data<Data>,
+    path: web::Path<IndexParam>,
+    params: web::Query<SuggestQuery>,
+) -> Result<HttpResponse, ResponseError> {
+    let index = data
+        .db
+        .open_index(&path.index_uid)
+        .ok_or(ResponseError::index_not_found(&path.index_uid))?;
+
+    let max_distance = params.max_distance.unwrap_or(2);
+    if max_distance > 2 {
+        return Err(ResponseError::bad_parameter("maxDistance", "must be lower than or equal to 2"));
+    }
+    let limit = params.limit.unwrap_or(DEFAULT_SUGGESTIONS_LIMIT);
+
+    let reader = data.db.main_read_txn()?;
+    let mut suggestions = index.suggest(&reader, &params.q, max_distance)?;
+    suggestions.truncate(limit);
+
+    Ok(HttpResponse::Ok().json(suggestions))
+}
@@ -0,0 +1,360 @@
+//! A warm standby follows a primary through a shipping directory, an NFS mount or a
+//! directory synchronized between the two hosts, in which the primary writes:
+//!  - `snapshot/`: a consistent copy of its environments along with a `manifest.json`,
+//!  - `log/`: one file per operation applied since, named after its sequence number.
+//!
+//! The standby restores the snapshot and replays the log under the same update ids,
+//! an operation found both in the snapshot and in the log is therefore only applied once.
+//! The changes made outside of the update queues, like the index names or the query rules,
+//! only reach the standby with the next snapshot.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use log::{error, info};
+use meilisearch_core::{Database, Error, MResult, UpdateLogEntry};
+use serde::{Deserialize, Serialize};
+
+const SNAPSHOT_DIR: &str = "snapshot";
+const LOG_DIR: &str = "log";
+const MANIFEST_FILE: &str = "manifest.json";
+/// The file, in the database directory of a standby, storing the last replicated sequence.
+const APPLIED_SEQUENCE_FILE: &str = "standby-sequence";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotManifest {
+    /// The operations logged up to this sequence are contained in the snapshot.
+    pub sequence: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Writes the snapshots and the update log of a primary to the shipping directory.
+pub struct Shipper {
+    dir: PathBuf,
+    sequence: Mutex<u64>,
+    last_snapshot: Mutex<Option<SnapshotManifest>>,
+    // two snapshots can't be shipped at the same time, they share the temporary directory
+    shipping: Mutex<()>,
+}
+
+impl Shipper {
+    pub fn new(dir: impl AsRef<Path>) -> MResult<Shipper> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(dir.join(LOG_DIR))?;
+
+        let last_snapshot = read_manifest(&dir)?;
+        let logged = log_sequences(&dir)?.into_iter().max();
+        let sequence = last_snapshot.as_ref().map(|m| m.sequence).max(logged).unwrap_or(0);
+
+        Ok(Shipper {
+            dir,
+            sequence: Mutex::new(sequence),
+            last_snapshot: Mutex::new(last_snapshot),
+            shipping: Mutex::new(()),
+        })
+    }
+
+    /// Appends an operation to the log, the entry is written to a temporary file
+    /// renamed once complete so that a standby never reads it partially.
+    pub fn append(&self, entry: &UpdateLogEntry) -> MResult<u64> {
+        let mut sequence = self.sequence.lock().unwrap();
+        let next = *sequence + 1;
+
+        let path = log_entry_path(&self.dir, next);
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_vec(entry)?)?;
+        fs::rename(&tmp_path, &path)?;
+
+        *sequence = next;
+        Ok(next)
+    }
+
+    pub fn sequence(&self) -> u64 {
+        *self.sequence.lock().unwrap()
+    }
+
+    pub fn last_snapshot(&self) -> Option<SnapshotManifest> {
+        self.last_snapshot.lock().unwrap().clone()
+    }
+
+    /// Ships a snapshot of the database then removes the log entries it contains.
+    pub fn ship_snapshot(&self, db: &Database) -> MResult<SnapshotManifest> {
+        let _shipping = self.shipping.lock().unwrap();
+
+        // operations logged during the copy can be both in the snapshot and in the log,
+        // which is harmless, reading the sequence after the copy could lose some of them
+        let sequence = self.sequence();
+
+        let tmp_dir = self.dir.join("snapshot.tmp");
+        if tmp_dir.exists() {
+            fs::remove_dir_all(&tmp_dir)?;
+        }
+        fs::create_dir_all(&tmp_dir)?;
+        db.snapshot(&tmp_dir)?;
+
+        let manifest = SnapshotManifest { sequence, created_at: Utc::now() };
+        fs::write(tmp_dir.join(MANIFEST_FILE), serde_json::to_vec(&manifest)?)?;
+
+        let snapshot_dir = self.dir.join(SNAPSHOT_DIR);
+        let old_dir = self.dir.join("snapshot.old");
+        if snapshot_dir.exists() {
+            if old_dir.exists() {
+                fs::remove_dir_all(&old_dir)?;
+            }
+            fs::rename(&snapshot_dir, &old_dir)?;
+        }
+        fs::rename(&tmp_dir, &snapshot_dir)?;
+        if old_dir.exists() {
+            fs::remove_dir_all(&old_dir)?;
+        }
+
+        for logged in log_sequences(&self.dir)? {
+            if logged <= sequence {
+                fs::remove_file(log_entry_path(&self.dir, logged))?;
+            }
+        }
+
+        info!("snapshot shipped to {:?} at sequence {}", self.dir, sequence);
+        *self.last_snapshot.lock().unwrap() = Some(manifest.clone());
+
+        Ok(manifest)
+    }
+
+    /// Ships a snapshot at every interval, the first one right away.
+    pub fn spawn_snapshots(self: Arc<Self>, db: Arc<Database>, interval: Duration) {
+        thread::spawn(move || loop {
+            if let Err(e) = self.ship_snapshot(&db) {
+                error!("impossible to ship the snapshot; {}", e);
+            }
+            thread::sleep(interval);
+        });
+    }
+}
+
+/// The replication state of a standby.
+pub struct Standby {
+    ship_dir: PathBuf,
+    db_path: PathBuf,
+    applied_sequence: AtomicU64,
+    promoted: AtomicBool,
+    error: Mutex<Option<String>>,
+}
+
+impl Standby {
+    /// Restores the last shipped snapshot in the database directory when the standby is new
+    /// or when the log entries it still has to replay were removed by a newer snapshot.
+    pub fn prepare(ship_dir: impl AsRef<Path>, db_path: impl AsRef<Path>) -> MResult<Standby> {
+        let ship_dir = ship_dir.as_ref().to_path_buf();
+        let db_path = db_path.as_ref().to_path_buf();
+
+        let applied = read_applied_sequence(&db_path)?;
+        if applied.is_none() && db_path.join("main").exists() {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::Other,
+                "the database is not a standby, it can not be replaced by a snapshot",
+            )));
+        }
+
+        let manifest = read_manifest(&ship_dir)?;
+        let sequence = match (applied, manifest) {
+            (None, Some(manifest)) => restore_snapshot(&ship_dir, &db_path, manifest)?,
+            (Some(applied), Some(manifest))
+                if applied < manifest.sequence && !log_entry_path(&ship_dir, applied + 1).exists() =>
+            {
+                restore_snapshot(&ship_dir, &db_path, manifest)?
+            }
+            (applied, _) => applied.unwrap_or(0),
+        };
+
+        write_applied_sequence(&db_path, sequence)?;
+
+        Ok(Standby {
+            ship_dir,
+            db_path,
+            applied_sequence: AtomicU64::new(sequence),
+            promoted: AtomicBool::new(false),
+            error: Mutex::new(None),
+        })
+    }
+
+    pub fn applied_sequence(&self) -> u64 {
+        self.applied_sequence.load(Ordering::SeqCst)
+    }
+
+    pub fn is_promoted(&self) -> bool {
+        self.promoted.load(Ordering::SeqCst)
+    }
+
+    /// Stops the replication, the instance accepts updates from now on.
+    pub fn promote(&self) {
+        self.promoted.store(true, Ordering::SeqCst);
+    }
+
+    /// The error that stopped the replication, if any.
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    /// Replays the shipped log entries as they arrive until the standby is promoted.
+    pub fn spawn_replication(self: Arc<Self>, db: Arc<Database>) {
+        thread::spawn(move || {
+            while !self.is_promoted() {
+                if let Err(e) = self.replicate(&db) {
+                    error!("standby replication stopped; {}", e);
+                    *self.error.lock().unwrap() = Some(e.to_string());
+                    return;
+                }
+                thread::sleep(Duration::from_secs(1));
+            }
+            info!("standby promoted, replication stopped at sequence {}", self.applied_sequence());
+        });
+    }
+
+    fn replicate(&self, db: &Database) -> MResult<()> {
+        while !self.is_promoted() {
+            let next = self.applied_sequence() + 1;
+            let bytes = match fs::read(log_entry_path(&self.ship_dir, next)) {
+                Ok(bytes) => bytes,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    if log_sequences(&self.ship_dir)?.into_iter().any(|s| s > next) {
+                        return Err(Error::Io(io::Error::new(
+                            io::ErrorKind::Other,
+                            "log entries were removed before being replayed, \
+                             restart the standby to restore the last snapshot",
+                        )));
+                    }
+                    return Ok(());
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            let entry: UpdateLogEntry = serde_json::from_slice(&bytes)?;
+            apply_entry(db, entry)?;
+
+            write_applied_sequence(&self.db_path, next)?;
+            self.applied_sequence.store(next, Ordering::SeqCst);
+        }
+
+        Ok(())
+    }
+}
+
+fn apply_entry(db: &Database, entry: UpdateLogEntry) -> MResult<()> {
+    match entry {
+        UpdateLogEntry::IndexCreation { index_uid } => {
+            if db.open_index(&index_uid).is_none() {
+                db.create_index(&index_uid)?;
+            }
+        }
+        UpdateLogEntry::IndexDeletion { index_uid } => {
+            db.delete_index(&index_uid)?;
+        }
+        UpdateLogEntry::IndexesSwap { lhs, rhs } => {
+            db.swap_indexes(&lhs, &rhs)?;
+        }
+        UpdateLogEntry::IndexRename { old_uid, new_uid } => {
+            db.rename_index(&old_uid, &new_uid)?;
+        }
+        UpdateLogEntry::IndexCopy { source, destination, with_updates } => {
+            // the copy is logged after the creation of the empty destination index
+            db.delete_index(&destination)?;
+            db.copy_index(&source, &destination, with_updates)?;
+        }
+        UpdateLogEntry::AliasUpdate { alias, index_uid } => {
+            db.put_alias(&alias, &index_uid)?;
+        }
+        UpdateLogEntry::AliasDeletion { alias } => {
+            db.delete_alias(&alias)?;
+        }
+        UpdateLogEntry::Update { index_uid, update_id, primary_key, update } => {
+            let index = match db.open_index(&index_uid) {
+                Some(index) => index,
+                None => db.create_index(&index_uid)?,
+            };
+
+            if let Some(primary_key) = primary_key {
+                let mut writer = db.main_write_txn()?;
+                let mut schema = index.main.schema(&writer)?.ok_or(Error::SchemaMissing)?;
+                if schema.primary_key().is_none() {
+                    schema.set_primary_key(&primary_key)?;
+                    index.main.put_schema(&mut writer, &schema)?;
+                }
+                writer.commit()?;
+            }
+
+            let mut writer = db.update_write_txn()?;
+            index.replicate_update(&mut writer, update_id, update)?;
+            writer.commit()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces the environments of the database directory by the ones of the shipped snapshot.
+fn restore_snapshot(ship_dir: &Path, db_path: &Path, manifest: SnapshotManifest) -> MResult<u64> {
+    Database::restore_snapshot(ship_dir.join(SNAPSHOT_DIR), db_path)?;
+
+    info!("standby restored from the snapshot at sequence {}", manifest.sequence);
+
+    Ok(manifest.sequence)
+}
+
+fn read_manifest(ship_dir: &Path) -> MResult<Option<SnapshotManifest>> {
+    match fs::read(ship_dir.join(SNAPSHOT_DIR).join(MANIFEST_FILE)) {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn read_applied_sequence(db_path: &Path) -> MResult<Option<u64>> {
+    match fs::read_to_string(db_path.join(APPLIED_SEQUENCE_FILE)) {
+        Ok(content) => content
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|e| Error::Io(io::Error::new(io::ErrorKind::InvalidData, e))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn write_applied_sequence(db_path: &Path, sequence: u64) -> MResult<()> {
+    fs::create_dir_all(db_path)?;
+    let path = db_path.join(APPLIED_SEQUENCE_FILE);
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, sequence.to_string())?;
+    fs::rename(&tmp_path, &path)?;
+    Ok(())
+}
+
+fn log_entry_path(ship_dir: &Path, sequence: u64) -> PathBuf {
+    ship_dir.join(LOG_DIR).join(format!("{:020}.json", sequence))
+}
+
+fn log_sequences(ship_dir: &Path) -> MResult<Vec<u64>> {
+    let entries = match fs::read_dir(ship_dir.join(LOG_DIR)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut sequences = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().map_or(false, |ext| ext == "json") {
+            if let Some(sequence) = path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.parse().ok()) {
+                sequences.push(sequence);
+            }
+        }
+    }
+    Ok(sequences)
+}
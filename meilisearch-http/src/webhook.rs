@@ -0,0 +1,47 @@
+//! Delivers the notifications of the processed updates to an HTTP webhook.
+
+use std::thread;
+
+use log::{error, warn};
+use meilisearch_core::{Database, UpdateNotification};
+
+/// The time, in milliseconds, a webhook has to accept the connection and to respond.
+const TIMEOUT_MS: u64 = 10_000;
+
+/// Posts the notification of every update processed to the url, in the order the updates are
+/// processed, from a dedicated thread to never slow the update loops down.
+pub fn spawn_webhook(db: &Database, url: String) {
+    let notifications = db.subscribe_updates();
+    thread::spawn(move || {
+        for notification in notifications {
+            post_notification(&url, &notification);
+        }
+    });
+}
+
+fn post_notification(url: &str, notification: &UpdateNotification) {
+    let body = match serde_json::to_string(notification) {
+        Ok(body) => body,
+        Err(e) => {
+            error!("Impossible to serialize the update notification; {}", e);
+            return;
+        }
+    };
+
+    let response = ureq::post(url)
+        .set("Content-Type", "application/json")
+        .timeout_connect(TIMEOUT_MS)
+        .timeout_read(TIMEOUT_MS)
+        .send_string(&body);
+
+    if let Some(err) = response.synthetic_error() {
+        warn!("Impossible to notify the update {} to {}; {}", notification.update_id, url, err);
+    } else if !response.ok() {
+        warn!(
+            "The webhook {} responded with status {} to the update {} notification",
+            url,
+            response.status(),
+            notification.update_id,
+        );
+    }
+}
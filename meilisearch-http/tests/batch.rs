@@ -0,0 +1,88 @@
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn batch_deletes_and_adds_documents_at_once() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "Carol" },
+        { "id": 2, "title": "Wonder Woman" },
+    ])).await;
+
+    let body = json!([
+        { "type": "deleteDocuments", "ids": [1] },
+        { "type": "addDocuments", "documents": [{ "id": 3, "title": "Life of Pi" }] },
+        { "type": "updateSettings", "settings": { "displayedAttributes": ["id", "title"] } },
+    ]);
+    let (response, status_code) = server.post_request("/indexes/movies/batch", body).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    let (response, status_code) = server.get_update_status(update_id).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["status"], "processed");
+    assert_eq!(response["type"]["name"], "Batch");
+    assert_eq!(response["type"]["updates"].as_array().unwrap().len(), 3);
+
+    let (_response, status_code) = server.get_document(1).await;
+    assert_eq!(status_code, 404);
+    let (response, status_code) = server.get_document(3).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!({ "id": 3, "title": "Life of Pi" }));
+}
+
+#[actix_rt::test]
+async fn failed_batch_is_not_applied() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    server.add_or_replace_multiple_documents(json!([
+        { "id": 1, "title": "Carol" },
+        { "id": 2, "title": "Wonder Woman" },
+    ])).await;
+
+    // the second addition contains the same document id twice and refuses duplicates
+    let body = json!([
+        { "type": "deleteDocuments", "ids": [1, 2] },
+        {
+            "type": "addDocuments",
+            "documents": [{ "id": 3, "title": "Life of Pi" }, { "id": 3, "title": "Shazam" }],
+            "duplicates": "error",
+        },
+    ]);
+    let (response, status_code) = server.post_request("/indexes/movies/batch", body).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    let (response, status_code) = server.get_update_status(update_id).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["status"], "failed");
+
+    let (_response, status_code) = server.get_document(1).await;
+    assert_eq!(status_code, 200);
+    let (_response, status_code) = server.get_document(2).await;
+    assert_eq!(status_code, 200);
+}
+
+#[actix_rt::test]
+async fn empty_batch_is_rejected() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let (_response, status_code) = server.post_request("/indexes/movies/batch", json!([])).await;
+    assert_eq!(status_code, 400);
+}
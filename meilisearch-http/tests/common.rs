@@ -1,9 +1,12 @@
 #![allow(dead_code)]
 
 use serde_json::{json, Value};
+use std::io::Write;
 use std::time::Duration;
 
 use actix_web::{http::StatusCode, test};
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use meilisearch_core::DatabaseOptions;
 use meilisearch_http::data::Data;
 use meilisearch_http::option::Opt;
@@ -14,15 +17,20 @@ use tokio::time::delay_for;
 pub struct Server {
     uid: String,
     data: Data,
+    api_key: Option<String>,
 }
 
 impl Server {
     pub fn with_uid(uid: &str) -> Server {
+        Server::with_options(uid, |_| ())
+    }
+
+    pub fn with_options(uid: &str, configure: impl FnOnce(&mut Opt)) -> Server {
         let tmp_dir = TempDir::new("meilisearch").unwrap();
 
         let default_db_options = DatabaseOptions::default();
 
-        let opt = Opt {
+        let mut opt = Opt {
             db_path: tmp_dir.path().to_str().unwrap().to_string(),
             http_addr: "127.0.0.1:7700".to_owned(),
             master_key: None,
@@ -30,13 +38,53 @@ impl Server {
             no_analytics: true,
             main_map_size: default_db_options.main_map_size,
             update_map_size: default_db_options.update_map_size,
+            max_main_map_size: None,
+            max_update_map_size: None,
+            http_payload_size_limit: 100 * 1024 * 1024,
+            http_payload_document_size_limit: 10 * 1024 * 1024,
+            max_enqueued_updates_size: None,
+            max_enqueued_updates: None,
+            updates_retention_max_entries: None,
+            updates_retention_max_age_sec: None,
+            expiration_purge_interval_sec: 60,
+            search_analytics_capacity: None,
+            update_webhook_url: None,
+            standby_ship_dir: None,
+            standby_snapshot_interval_sec: 3600,
+            standby_of: None,
+            snapshot_dir: tmp_dir.path().join("snapshots").to_str().unwrap().to_string(),
+            import_snapshot: None,
+            dumps_dir: tmp_dir.path().join("dumps").to_str().unwrap().to_string(),
+            import_dump: None,
+            uploads_ttl_sec: 86400,
+            import_allow_private_networks: false,
+            enable_graphql: false,
+            grpc_addr: None,
         };
+        configure(&mut opt);
 
         let data = Data::new(opt.clone());
 
         Server {
             uid: uid.to_string(),
             data,
+            api_key: None,
+        }
+    }
+
+    pub fn data(&self) -> &Data {
+        &self.data
+    }
+
+    /// Sends the given API key along with all the following requests.
+    pub fn set_api_key(&mut self, api_key: Option<&str>) {
+        self.api_key = api_key.map(ToOwned::to_owned);
+    }
+
+    fn request(&self, request: test::TestRequest) -> test::TestRequest {
+        match self.api_key {
+            Some(ref api_key) => request.header("X-Meili-API-Key", api_key.as_str()),
+            None => request,
         }
     }
 
@@ -113,7 +161,7 @@ impl Server {
             let (response, status_code) = self.get_update_status(update_id).await;
             assert_eq!(status_code, 200);
 
-            if response["status"] == "processed" || response["status"] == "error" {
+            if response["status"] == "processed" || response["status"] == "failed" {
                 eprintln!("{:#?}", response);
                 return;
             }
@@ -129,7 +177,7 @@ impl Server {
 
         let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
 
-        let req = test::TestRequest::get().uri(url).to_request();
+        let req = self.request(test::TestRequest::get()).uri(url).to_request();
         let res = test::call_service(&mut app, req).await;
         let status_code = res.status().clone();
 
@@ -143,7 +191,7 @@ impl Server {
 
         let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
 
-        let req = test::TestRequest::post()
+        let req = self.request(test::TestRequest::post())
             .uri(url)
             .set_json(&body)
             .to_request();
@@ -155,6 +203,63 @@ impl Server {
         (response, status_code)
     }
 
+    pub async fn post_raw_request(&mut self, url: &str, body: &'static str) -> (Value, StatusCode) {
+        eprintln!("post_raw_request: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
+
+        let req = self.request(test::TestRequest::post())
+            .uri(url)
+            .set_payload(body)
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+
+        let body = test::read_body(res).await;
+        let response = serde_json::from_slice(&body).unwrap_or_default();
+        (response, status_code)
+    }
+
+    pub async fn post_gzip_request(&mut self, url: &str, body: Value) -> (Value, StatusCode) {
+        eprintln!("post_gzip_request: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(body.to_string().as_bytes()).unwrap();
+        let payload = encoder.finish().unwrap();
+
+        let req = self.request(test::TestRequest::post())
+            .uri(url)
+            .header("Content-Type", "application/json")
+            .header("Content-Encoding", "gzip")
+            .set_payload(payload)
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+
+        let body = test::read_body(res).await;
+        let response = serde_json::from_slice(&body).unwrap_or_default();
+        (response, status_code)
+    }
+
+    pub async fn put_raw_request(&mut self, url: &str, body: &'static str) -> (Value, StatusCode) {
+        eprintln!("put_raw_request: {}", url);
+
+        let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
+
+        let req = self.request(test::TestRequest::put())
+            .uri(url)
+            .set_payload(body)
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        let status_code = res.status().clone();
+
+        let body = test::read_body(res).await;
+        let response = serde_json::from_slice(&body).unwrap_or_default();
+        (response, status_code)
+    }
+
     pub async fn post_request_async(&mut self, url: &str, body: Value) -> (Value, StatusCode) {
         eprintln!("post_request_async: {}", url);
 
@@ -172,7 +277,7 @@ impl Server {
 
         let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
 
-        let req = test::TestRequest::put()
+        let req = self.request(test::TestRequest::put())
             .uri(url)
             .set_json(&body)
             .to_request();
@@ -200,7 +305,7 @@ impl Server {
 
         let mut app = test::init_service(meilisearch_http::create_app(&self.data).wrap(NormalizeSlashes)).await;
 
-        let req = test::TestRequest::delete().uri(url).to_request();
+        let req = self.request(test::TestRequest::delete()).uri(url).to_request();
         let res = test::call_service(&mut app, req).await;
         let status_code = res.status().clone();
 
@@ -231,7 +336,7 @@ impl Server {
     }
 
     pub async fn search_multi_index(&mut self, query: &str) -> (Value, StatusCode) {
-        let url = format!("/indexes/search?{}", query);
+        let url = format!("/multi-search?{}", query);
         self.get_request(&url).await
     }
 
@@ -441,6 +546,46 @@ impl Server {
         self.delete_request_async(&url).await
     }
 
+    pub async fn get_typo_tolerance(&mut self) -> (Value, StatusCode) {
+        let url = format!("/indexes/{}/settings/typo-tolerance", self.uid);
+        self.get_request(&url).await
+    }
+
+    pub async fn update_typo_tolerance(&mut self, body: Value) {
+        let url = format!("/indexes/{}/settings/typo-tolerance", self.uid);
+        self.post_request_async(&url, body).await;
+    }
+
+    pub async fn update_typo_tolerance_sync(&mut self, body: Value) -> (Value, StatusCode) {
+        let url = format!("/indexes/{}/settings/typo-tolerance", self.uid);
+        self.post_request(&url, body).await
+    }
+
+    pub async fn delete_typo_tolerance(&mut self) -> (Value, StatusCode) {
+        let url = format!("/indexes/{}/settings/typo-tolerance", self.uid);
+        self.delete_request_async(&url).await
+    }
+
+    pub async fn get_prefix_search(&mut self) -> (Value, StatusCode) {
+        let url = format!("/indexes/{}/settings/prefix-search", self.uid);
+        self.get_request(&url).await
+    }
+
+    pub async fn update_prefix_search(&mut self, body: Value) {
+        let url = format!("/indexes/{}/settings/prefix-search", self.uid);
+        self.post_request_async(&url, body).await;
+    }
+
+    pub async fn update_prefix_search_sync(&mut self, body: Value) -> (Value, StatusCode) {
+        let url = format!("/indexes/{}/settings/prefix-search", self.uid);
+        self.post_request(&url, body).await
+    }
+
+    pub async fn delete_prefix_search(&mut self) -> (Value, StatusCode) {
+        let url = format!("/indexes/{}/settings/prefix-search", self.uid);
+        self.delete_request_async(&url).await
+    }
+
     pub async fn get_index_stats(&mut self) -> (Value, StatusCode) {
         let url = format!("/indexes/{}/stats", self.uid);
         self.get_request(&url).await
@@ -1,4 +1,9 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
 use serde_json::json;
+use sha2::Digest;
 
 mod common;
 
@@ -175,3 +180,629 @@ async fn check_add_documents_with_nested_sequence() {
     assert_eq!(status_code, 200);
     assert_eq!(response["hits"], body);
 }
+
+#[actix_rt::test]
+async fn add_csv_documents_with_delimiter_and_typed_headers() {
+    let mut server = common::Server::with_uid("books");
+
+    let body = json!({ "uid": "books" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = "id:number;title;price:number;available:boolean\n\
+                1;Alice in Wonderland;12.5;true\n\
+                2;Le Petit Prince;8;false\n";
+
+    let url = "/indexes/books/documents/csv?primaryKey=id&csvDelimiter=;";
+    let (response, status_code) = server.post_raw_request(&url, body).await;
+    eprintln!("{:#?}", response);
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    let (response, status_code) = server.get_update_status(update_id).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["status"], "processed");
+
+    let (response, status_code) = server.get_document(2).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!({
+        "id": 2,
+        "title": "Le Petit Prince",
+        "price": 8,
+        "available": false,
+    }));
+}
+
+#[actix_rt::test]
+async fn add_csv_documents_with_inferred_types() {
+    let mut server = common::Server::with_uid("books");
+
+    let body = json!({ "uid": "books" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = "id,title,price,available,isbn:string\n\
+                1,Alice in Wonderland,12.5,true,9780141439761\n\
+                2,Le Petit Prince,,FALSE,9782070612758\n";
+
+    let url = "/indexes/books/documents/csv?primaryKey=id&inferTypes=true";
+    let (response, status_code) = server.post_raw_request(&url, body).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    let (response, status_code) = server.get_update_status(update_id).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["status"], "processed");
+
+    let (response, status_code) = server.get_document(2).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!({
+        "id": 2,
+        "title": "Le Petit Prince",
+        "price": null,
+        "available": false,
+        "isbn": "9782070612758",
+    }));
+}
+
+#[actix_rt::test]
+async fn add_csv_documents_with_unknown_column_type() {
+    let mut server = common::Server::with_uid("books");
+
+    let body = json!({ "uid": "books" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = "id:number,title:text\n1,Alice in Wonderland\n";
+
+    let url = "/indexes/books/documents/csv?primaryKey=id";
+    let (_response, status_code) = server.post_raw_request(&url, body).await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn import_documents_from_invalid_url() {
+    let mut server = common::Server::with_uid("books");
+
+    let body = json!({ "uid": "books" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!({ "url": "file:///etc/passwd" });
+    let (_response, status_code) = server.post_request("/indexes/books/documents/import", body).await;
+    assert_eq!(status_code, 400);
+
+    let body = json!({ "url": "https://example.com/books.json", "format": "xml" });
+    let (_response, status_code) = server.post_request("/indexes/books/documents/import", body).await;
+    assert_eq!(status_code, 400);
+
+    let body = json!({ "url": "https://example.com/books.json" });
+    let (_response, status_code) = server.post_request("/indexes/unknown/documents/import", body).await;
+    assert_eq!(status_code, 404);
+}
+
+/// Serves a single response with the given body on a local port and returns its url.
+fn serve_once(path: &str, content_type: &'static str, body: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 4096];
+        let _ = stream.read(&mut request);
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            content_type,
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).unwrap();
+    });
+    format!("http://{}{}", address, path)
+}
+
+#[actix_rt::test]
+async fn import_documents_from_a_private_network_is_refused() {
+    let mut server = common::Server::with_uid("books");
+
+    let body = json!({ "uid": "books" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let urls = &[
+        "http://127.0.0.1:7700/books.json",
+        "http://localhost/books.json",
+        "http://[::1]/books.json",
+        "http://169.254.169.254/latest/meta-data",
+        "http://10.0.0.1/books.json",
+    ];
+    for url in urls {
+        let body = json!({ "url": url });
+        let (_response, status_code) = server.post_request("/indexes/books/documents/import", body).await;
+        assert_eq!(status_code, 400, "{}", url);
+    }
+}
+
+#[actix_rt::test]
+async fn import_documents_from_a_local_server() {
+    let mut server = common::Server::with_options("books", |opt| opt.import_allow_private_networks = true);
+
+    let body = json!({ "uid": "books" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let file = r#"[{"id": 1, "title": "Alice in Wonderland"}, {"id": 2, "title": "Le Petit Prince"}]"#;
+    let sha256 = format!("{:x}", sha2::Sha256::digest(file.as_bytes()));
+
+    // the checksum is verified once the file is read
+    let url = serve_once("/books", "application/json", file);
+    let body = json!({ "url": url, "sha256": format!("{:x}", sha2::Sha256::digest(b"other")) });
+    let (_response, status_code) = server.post_request("/indexes/books/documents/import", body).await;
+    assert_eq!(status_code, 400);
+
+    // the format is inferred from the content type
+    let url = serve_once("/books", "application/json", file);
+    let body = json!({ "url": url, "sha256": sha256, "primaryKey": "id" });
+    let (response, status_code) = server.post_request("/indexes/books/documents/import", body).await;
+    assert_eq!(status_code, 202);
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+
+    let (response, status_code) = server.get_document(2).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["title"], "Le Petit Prince");
+
+    let url = serve_once("/books.ndjson", "application/octet-stream", "{\"id\": 3, \"title\": \"Dune\"}\n\n");
+    let body = json!({ "url": url });
+    let (response, status_code) = server.post_request("/indexes/books/documents/import", body).await;
+    assert_eq!(status_code, 202);
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+
+    let (response, status_code) = server.get_document(3).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["title"], "Dune");
+}
+
+#[actix_rt::test]
+async fn add_documents_with_a_chunked_upload() {
+    let mut server = common::Server::with_uid("books");
+
+    let body = json!({ "uid": "books" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let (response, status_code) = server.post_request("/indexes/books/documents/uploads", json!(null)).await;
+    assert_eq!(status_code, 201);
+    let upload_id = response["uploadId"].as_str().unwrap().to_string();
+    let upload_url = format!("/indexes/books/documents/uploads/{}", upload_id);
+
+    let url = format!("{}/chunks/1", upload_url);
+    let (_response, status_code) = server.put_raw_request(&url, "{\"id\": 2, \"title\": \"Le Petit Prince\"}]").await;
+    assert_eq!(status_code, 204);
+
+    // the first chunk is missing
+    let url = format!("{}/complete?format=json&primaryKey=id", upload_url);
+    let (_response, status_code) = server.post_request(&url, json!(null)).await;
+    assert_eq!(status_code, 400);
+
+    let url = format!("{}/chunks/0", upload_url);
+    let (_response, status_code) = server.put_raw_request(&url, "[{\"id\": 1, \"title\": \"Alice in Wonderland\"},").await;
+    assert_eq!(status_code, 204);
+
+    let (response, status_code) = server.get_request(&upload_url).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["chunks"], json!([0, 1]));
+
+    let url = format!("{}/complete?format=json&primaryKey=id", upload_url);
+    let (response, status_code) = server.post_request(&url, json!(null)).await;
+    assert_eq!(status_code, 202);
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+
+    let (response, status_code) = server.get_document(2).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["title"], "Le Petit Prince");
+
+    // the upload no longer exists once completed
+    let (_response, status_code) = server.get_request(&upload_url).await;
+    assert_eq!(status_code, 404);
+}
+
+#[actix_rt::test]
+async fn chunked_upload_requires_an_existing_index() {
+    let mut server = common::Server::with_uid("books");
+
+    let (_response, status_code) = server.post_request("/indexes/unknown/documents/uploads", json!(null)).await;
+    assert_eq!(status_code, 404);
+
+    let (_response, status_code) = server.get_request("/indexes/unknown/documents/uploads/0123456789abcdef").await;
+    assert_eq!(status_code, 404);
+
+    // the index uid must not be able to escape the uploads directory
+    let (_response, status_code) = server.post_request("/indexes/../documents/uploads", json!(null)).await;
+    assert_ne!(status_code, 201);
+}
+
+#[actix_rt::test]
+async fn chunked_upload_is_limited_to_the_payload_size() {
+    let mut server = common::Server::with_options("books", |opt| opt.http_payload_size_limit = 64);
+
+    let body = json!({ "uid": "books" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let (response, status_code) = server.post_request("/indexes/books/documents/uploads", json!(null)).await;
+    assert_eq!(status_code, 201);
+    let upload_url = format!("/indexes/books/documents/uploads/{}", response["uploadId"].as_str().unwrap());
+
+    let url = format!("{}/chunks/0", upload_url);
+    let (_response, status_code) = server.put_raw_request(&url, "[{\"id\": 1, \"title\": \"Alice in Wonderland\"},").await;
+    assert_eq!(status_code, 204);
+
+    // the chunks are limited together, not one by one
+    let url = format!("{}/chunks/1", upload_url);
+    let (_response, status_code) = server.put_raw_request(&url, "{\"id\": 2, \"title\": \"Le Petit Prince\"}]").await;
+    assert_eq!(status_code, 413);
+
+    let (response, status_code) = server.get_request(&upload_url).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["chunks"], json!([0]));
+}
+
+#[actix_rt::test]
+async fn deleting_the_index_removes_its_uploads() {
+    let mut server = common::Server::with_uid("books");
+
+    let body = json!({ "uid": "books" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let (response, status_code) = server.post_request("/indexes/books/documents/uploads", json!(null)).await;
+    assert_eq!(status_code, 201);
+    let upload_url = format!("/indexes/books/documents/uploads/{}", response["uploadId"].as_str().unwrap());
+
+    let (_response, status_code) = server.delete_index().await;
+    assert_eq!(status_code, 204);
+
+    let body = json!({ "uid": "books" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let (_response, status_code) = server.get_request(&upload_url).await;
+    assert_eq!(status_code, 404);
+}
+
+#[actix_rt::test]
+async fn add_gzip_compressed_documents() {
+    let mut server = common::Server::with_uid("books");
+
+    let body = json!({ "uid": "books", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "Alice in Wonderland" },
+        { "id": 2, "title": "Le Petit Prince" },
+    ]);
+    let (response, status_code) = server.post_gzip_request("/indexes/books/documents", body).await;
+    assert_eq!(status_code, 202);
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+
+    let body = json!({ "searchableAttributes": ["title"] });
+    let (response, status_code) = server.post_gzip_request("/indexes/books/settings", body).await;
+    assert_eq!(status_code, 202);
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+
+    let (response, status_code) = server.get_document(2).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["title"], "Le Petit Prince");
+
+    let (response, _status_code) = server.get_searchable_attributes().await;
+    assert_eq!(response, json!(["title"]));
+}
+
+#[actix_rt::test]
+async fn add_documents_with_an_invalid_payload() {
+    let mut server = common::Server::with_uid("books");
+
+    let body = json!({ "uid": "books", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!({ "id": 1, "title": "Alice in Wonderland" });
+    let (_response, status_code) = server.post_request("/indexes/books/documents", body).await;
+    assert_eq!(status_code, 400);
+
+    let (_response, status_code) = server.post_raw_request("/indexes/books/documents", "[{\"id\": 1},").await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn add_documents_with_a_payload_too_large() {
+    let mut server = common::Server::with_options("books", |opt| opt.http_payload_size_limit = 32);
+
+    let body = json!({ "uid": "books", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "Alice in Wonderland" },
+        { "id": 2, "title": "Le Petit Prince" },
+    ]);
+    let (_response, status_code) = server.post_request("/indexes/books/documents", body).await;
+    assert_eq!(status_code, 413);
+
+    let (_response, status_code) = server.post_raw_request("/indexes/books/documents", "[{\"id\": 1}]").await;
+    assert_eq!(status_code, 202);
+}
+
+#[actix_rt::test]
+async fn add_documents_with_duplicated_ids() {
+    let mut server = common::Server::with_uid("books");
+
+    let body = json!({ "uid": "books", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "Alice in Wonderland" },
+        { "id": 1, "title": "Le Petit Prince" },
+    ]);
+
+    // by default the last document wins
+    let (response, status_code) = server.post_request("/indexes/books/documents", body.clone()).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["duplicates"], json!({ "policy": "keepLast", "ids": ["1"] }));
+    let (response, _status_code) = server.get_document(1).await;
+    assert_eq!(response["title"], "Le Petit Prince");
+
+    let (response, status_code) = server.post_request("/indexes/books/documents?duplicates=keepFirst", body.clone()).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["duplicates"], json!({ "policy": "keepFirst", "ids": ["1"] }));
+    let (response, _status_code) = server.get_document(1).await;
+    assert_eq!(response["title"], "Alice in Wonderland");
+
+    let (response, status_code) = server.post_request("/indexes/books/documents?duplicates=error", body).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["status"], "failed");
+    let (response, _status_code) = server.get_document(1).await;
+    assert_eq!(response["title"], "Alice in Wonderland");
+
+    let body = json!([{ "id": 2, "title": "Le Petit Prince" }]);
+    let (response, status_code) = server.post_request("/indexes/books/documents", body).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert!(response.get("duplicates").is_none());
+}
+
+#[actix_rt::test]
+async fn add_documents_with_a_relaxed_document_id_format() {
+    let mut server = common::Server::with_uid("users");
+
+    let body = json!({ "uid": "users", "primaryKey": "email" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([{ "email": "jean@example.com", "name": "Jean" }]);
+
+    // the ids are strictly checked by default, the invalid documents are skipped
+    let (response, status_code) = server.post_request("/indexes/users/documents", body.clone()).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["status"], "processed");
+    assert_eq!(response["failedDocuments"][0]["position"], 0);
+    assert_eq!(response["failedDocuments"][0]["documentId"], "jean@example.com");
+
+    let (response, status_code) = server
+        .post_request("/indexes/users/settings/document-id-format", json!("email"))
+        .await;
+    assert_eq!(status_code, 202);
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+
+    let (response, _status_code) = server.get_request("/indexes/users/settings/document-id-format").await;
+    assert_eq!(response, json!("email"));
+
+    let (response, status_code) = server.post_request("/indexes/users/documents", body).await;
+    assert_eq!(status_code, 202);
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+
+    let (response, status_code) = server.get_request("/indexes/users/documents/jean@example.com").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["name"], "Jean");
+
+    let (_response, status_code) = server.get_request("/indexes/users/documents/not-an-email").await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn add_documents_with_generated_ids() {
+    let mut server = common::Server::with_uid("logs");
+
+    let body = json!({ "uid": "logs" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "level": "info", "message": "server started" },
+        { "level": "error", "message": "connection lost" },
+    ]);
+    let (response, status_code) = server.post_request("/indexes/logs/documents?generateIds=true", body).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["status"], "processed");
+    let generated_ids = response["generatedIds"].as_array().unwrap().clone();
+    assert_eq!(generated_ids.len(), 2);
+
+    let (response, _status_code) = server.get_request("/indexes/logs").await;
+    assert_eq!(response["primaryKey"], "id");
+
+    let url = format!("/indexes/logs/documents/{}", generated_ids[1].as_str().unwrap());
+    let (response, status_code) = server.get_request(&url).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["message"], "connection lost");
+    assert_eq!(response["id"], generated_ids[1]);
+}
+
+#[actix_rt::test]
+async fn report_the_inferred_primary_key() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "title": "Carol", "movieId": 1 },
+        { "title": "Wall-E", "movieId": 2 },
+    ]);
+    let (response, status_code) = server.add_or_replace_multiple_documents_sync(body).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["status"], "processed");
+    assert_eq!(response["inferredPrimaryKey"], "movieId");
+
+    let (response, _status_code) = server.get_index().await;
+    assert_eq!(response["primaryKey"], "movieId");
+
+    // the following additions use the primary key chosen by the first one
+    let body = json!([{ "title": "Shazam", "movieId": 3 }]);
+    let (response, _status_code) = server.add_or_replace_multiple_documents_sync(body).await;
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["status"], "processed");
+    assert!(response.get("inferredPrimaryKey").is_none());
+}
+
+#[actix_rt::test]
+async fn browse_documents_sorted_by_id() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 10, "title": "Carol", "year": 2015 },
+        { "id": 2, "title": "Wall-E", "year": 2008 },
+        { "id": "b", "title": "Shazam", "year": 2019 },
+        { "id": 9, "title": "Up", "year": 2009 },
+        { "id": "a", "title": "Heat", "year": 1995 },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let (response, status_code) = server.get_request("/indexes/movies/documents?sortById=true").await;
+    assert_eq!(status_code, 200);
+    let ids: Vec<_> = response.as_array().unwrap().iter().map(|d| d["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(2), json!(9), json!(10), json!("a"), json!("b")]);
+
+    let url = "/indexes/movies/documents?sortById=true&offset=1&limit=2&attributesToRetrieve=title";
+    let (response, status_code) = server.get_request(url).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!([{ "title": "Up" }, { "title": "Carol" }]));
+
+    let (response, status_code) = server.get_all_documents().await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response.as_array().unwrap().len(), 5);
+}
+
+#[actix_rt::test]
+async fn partial_update_follows_merge_patch() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([{
+        "id": 1,
+        "title": "Carol",
+        "overview": "a glamorous stranger",
+        "director": { "name": "Todd Haynes", "born": 1961 },
+    }]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let body = json!([{
+        "id": 1,
+        "overview": null,
+        "director": { "born": null, "country": "US" },
+    }]);
+    server.add_or_update_multiple_documents(body).await;
+
+    let (response, status_code) = server.get_document(1).await;
+    assert_eq!(status_code, 200);
+    let expected = json!({
+        "id": 1,
+        "title": "Carol",
+        "director": { "name": "Todd Haynes", "country": "US" },
+    });
+    assert_eq!(response, expected);
+
+    // the words of the removed field are not searchable anymore
+    let (response, _status_code) = server.search("q=glamorous").await;
+    assert_eq!(response["hits"].as_array().unwrap().len(), 0);
+    let (response, _status_code) = server.search("q=carol").await;
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+}
+
+#[actix_rt::test]
+async fn documents_are_read_compressed_or_not() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let overview = "a long overview, repeated to be worth compressing. ".repeat(10);
+    let first = json!({ "id": 1, "title": "Carol", "overview": overview });
+    server.add_or_replace_multiple_documents(json!([first])).await;
+
+    let (response, status_code) = server.get_request("/indexes/movies/settings/compress-documents").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!(false));
+
+    // the fields already stored are rewritten compressed, the documents are not reindexed
+    let (response, _status_code) = server.post_request("/indexes/movies/settings/compress-documents", json!(true)).await;
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["settingsDiff"]["documentsFieldsRewritten"], json!(true));
+    assert_eq!(response["settingsDiff"]["reindexed"], json!(false));
+
+    let second = json!({ "id": 2, "title": "Carol", "overview": overview });
+    server.add_or_replace_multiple_documents(json!([second])).await;
+
+    let (response, _status_code) = server.get_document(1).await;
+    assert_eq!(response, first);
+    let (response, _status_code) = server.get_document(2).await;
+    assert_eq!(response, second);
+    let (response, _status_code) = server.search("q=carol&filters=id%20%3D%202").await;
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+
+    server.delete_request_async("/indexes/movies/settings/compress-documents").await;
+    let (response, _status_code) = server.get_document(2).await;
+    assert_eq!(response, second);
+}
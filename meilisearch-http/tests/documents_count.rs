@@ -0,0 +1,94 @@
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn count_all_documents() {
+    let mut server = common::Server::test_server().await;
+
+    let (response, status_code) = server.get_request("/indexes/test/documents/count").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!({ "numberOfDocuments": 77 }));
+}
+
+#[actix_rt::test]
+async fn count_documents_with_filters() {
+    let mut server = common::Server::test_server().await;
+
+    let (response, status_code) = server.get_request("/indexes/test/documents/count?filters=color%3Dgreen").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!({ "numberOfDocuments": 25 }));
+
+    let (response, status_code) = server.get_request("/indexes/test/documents/count?filter=age%20%3E%2030").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!({ "numberOfDocuments": 41 }));
+}
+
+#[actix_rt::test]
+async fn count_documents_with_has_filter() {
+    let mut server = common::Server::with_uid("items");
+
+    let body = json!({ "uid": "items", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "discount": 10 },
+        { "id": 2 },
+        { "id": 3, "discount": null },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let (response, status_code) = server.get_request("/indexes/items/documents/count?filters=HAS%20discount").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!({ "numberOfDocuments": 1 }));
+
+    let (response, status_code) = server.get_request("/indexes/items/documents/count?filters=NOT%20HAS%20discount").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!({ "numberOfDocuments": 2 }));
+}
+
+#[actix_rt::test]
+async fn count_documents_with_in_exists_and_is_null_filters() {
+    let mut server = common::Server::with_uid("items");
+
+    let body = json!({ "uid": "items", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "color": "red", "discount": 10 },
+        { "id": 2, "color": "dark blue" },
+        { "id": 3, "color": "green", "discount": null },
+        { "id": 4, "color": "red" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let count = |response: serde_json::Value| response["numberOfDocuments"].as_u64().unwrap();
+
+    // id IN [1, 3, 5]
+    let (response, status_code) = server.get_request("/indexes/items/documents/count?filters=id%20IN%20%5B1%2C%203%2C%205%5D").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(count(response), 2);
+
+    // color IN ['dark blue', green]
+    let (response, status_code) = server.get_request("/indexes/items/documents/count?filters=color%20IN%20%5B'dark%20blue'%2C%20green%5D").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(count(response), 2);
+
+    // the null values exist
+    let (response, status_code) = server.get_request("/indexes/items/documents/count?filters=discount%20EXISTS").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(count(response), 2);
+
+    let (response, status_code) = server.get_request("/indexes/items/documents/count?filters=discount%20IS%20NULL").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(count(response), 1);
+
+    // the presences are removed along with the documents
+    server.delete_document(1).await;
+
+    let (response, status_code) = server.get_request("/indexes/items/documents/count?filters=NOT%20discount%20EXISTS").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(count(response), 2);
+}
@@ -29,3 +29,59 @@ async fn delete_batch() {
     let (_response, status_code) = server.get_document(419704).await;
     assert_eq!(status_code, 404);
 }
+
+#[actix_rt::test]
+async fn fields_frequency_follows_additions_and_deletions() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = serde_json::json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = serde_json::json!([
+        { "id": 1, "title": "Carol", "genre": "drama" },
+        { "id": 2, "title": "Wonder Woman" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let (response, status_code) = server.get_request("/indexes/movies/stats").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["fieldsFrequency"], serde_json::json!({ "id": 2, "title": 2, "genre": 1 }));
+
+    // replacing a document must not count its fields twice
+    let body = serde_json::json!([{ "id": 2, "title": "Wonder Woman", "genre": "action" }]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let (response, _status_code) = server.get_request("/indexes/movies/stats").await;
+    assert_eq!(response["fieldsFrequency"], serde_json::json!({ "id": 2, "title": 2, "genre": 2 }));
+
+    let (response, _status_code) = server.delete_request("/indexes/movies/documents/1").await;
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+
+    let (response, _status_code) = server.get_request("/indexes/movies/stats").await;
+    assert_eq!(response["fieldsFrequency"], serde_json::json!({ "id": 1, "title": 1, "genre": 1 }));
+}
+
+#[actix_rt::test]
+async fn deleted_documents_are_purged() {
+    let mut server = common::Server::with_uid("movies");
+    server.populate_movies().await;
+
+    server.delete_document(419704).await;
+
+    let (_response, status_code) = server.get_document(419704).await;
+    assert_eq!(status_code, 404);
+
+    let (response, status_code) = server.post_request("/indexes/movies/documents/purge", serde_json::json!(null)).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    let (response, status_code) = server.get_request(&format!("/indexes/movies/updates/{}", update_id)).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["status"], "processed");
+    assert_eq!(response["type"]["name"], "DocumentsPurge");
+
+    let (_response, status_code) = server.get_document(419704).await;
+    assert_eq!(status_code, 404);
+}
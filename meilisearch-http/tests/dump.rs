@@ -0,0 +1,55 @@
+use serde_json::json;
+use tempdir::TempDir;
+
+mod common;
+
+#[actix_rt::test]
+async fn dump_is_imported_in_a_new_database() {
+    let dumps_dir = TempDir::new("dumps").unwrap();
+    let dumps_path = dumps_dir.path().to_str().unwrap().to_string();
+
+    let path = dumps_path.clone();
+    let mut server = common::Server::with_options("movies", |opt| opt.dumps_dir = path);
+
+    server.create_index(json!({ "uid": "movies", "name": "Movies", "primaryKey": "id" })).await;
+    server
+        .update_all_settings(json!({
+            "rankingRules": ["typo", "words", "proximity", "attribute", "wordsPosition", "exactness", "desc(year)"],
+            "stopWords": ["the"],
+            "attributesForFaceting": ["genre"],
+        }))
+        .await;
+    server
+        .add_or_replace_multiple_documents(json!([
+            { "id": 1, "title": "Carol", "genre": "romance", "year": 2015 },
+            { "id": 2, "title": "Wonder Woman", "genre": "action", "year": 2017 },
+        ]))
+        .await;
+    let (settings, _status_code) = server.get_all_settings().await;
+
+    let (response, status_code) = server.post_request("/dumps", json!(null)).await;
+    assert_eq!(status_code, 201);
+    let uid = response["uid"].as_str().unwrap().to_string();
+
+    let dump_path = dumps_dir.path().join(format!("{}.tar.gz", uid));
+    assert!(dump_path.exists());
+
+    let path = dump_path.to_str().unwrap().to_string();
+    let mut imported = common::Server::with_options("movies", |opt| opt.import_dump = Some(path));
+
+    let (response, status_code) = imported.get_request("/indexes/movies").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["name"], "Movies");
+    assert_eq!(response["primaryKey"], "id");
+
+    let (response, status_code) = imported.get_document(2).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!({ "id": 2, "title": "Wonder Woman", "genre": "action", "year": 2017 }));
+
+    let (response, _status_code) = imported.get_all_settings().await;
+    assert_eq!(response, settings);
+
+    let (response, status_code) = imported.get_request("/indexes/movies/search?q=wonder").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+}
@@ -0,0 +1,83 @@
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn facet_distinct_count() {
+    let mut server = common::Server::test_server().await;
+
+    let body = json!({
+        "attributesForFaceting": ["color", "gender"]
+    });
+    server.update_all_settings(body).await;
+
+    let (response, status_code) = server.get_request("/indexes/test/facets/color/distinct-count").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!({ "attribute": "color", "distinctCount": 3 }));
+
+    let (response, status_code) = server.get_request("/indexes/test/facets/gender/distinct-count").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!({ "attribute": "gender", "distinctCount": 2 }));
+
+    let url = "/indexes/test/facets/color/distinct-count?filters=gender%3Dmale%20AND%20age%20%3E%2039";
+    let (response, status_code) = server.get_request(url).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!({ "attribute": "color", "distinctCount": 1 }));
+}
+
+#[actix_rt::test]
+async fn facet_distinct_count_on_non_facet_attribute() {
+    let mut server = common::Server::test_server().await;
+
+    let body = json!({
+        "attributesForFaceting": ["color"]
+    });
+    server.update_all_settings(body).await;
+
+    let (_response, status_code) = server.get_request("/indexes/test/facets/name/distinct-count").await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn facet_values_are_normalized() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    server.create_index(body).await;
+
+    let body = json!({
+        "attributesForFaceting": ["genre"],
+        "facetNormalization": { "lowercase": true, "trim": true, "stripDiacritics": true },
+    });
+    server.update_all_settings(body).await;
+
+    let body = json!([
+        { "id": 1, "title": "a scream", "genre": "Horror " },
+        { "id": 2, "title": "a laugh", "genre": "Comédie" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    // facetFilters=["genre:horror"]
+    let (response, status_code) = server.search("q=a&facetFilters=%5B%22genre%3Ahorror%22%5D").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+    assert_eq!(response["hits"][0]["id"], 1);
+
+    // facetFilters=["genre:COMEDIE"]
+    let (response, status_code) = server.search("q=a&facetFilters=%5B%22genre%3ACOMEDIE%22%5D").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+    assert_eq!(response["hits"][0]["id"], 2);
+
+    // the distribution shows the values as they are in the documents
+    let (response, status_code) = server.search("q=a&facets=%5B%22genre%22%5D").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["facets"]["genre"], json!({ "Horror ": 1, "Comédie": 1 }));
+
+    // without trimming, the value no longer matches
+    let body = json!({ "lowercase": true, "trim": false, "stripDiacritics": true });
+    server.post_request_async("/indexes/movies/settings/facet-normalization", body).await;
+    let (response, status_code) = server.search("q=a&facetFilters=%5B%22genre%3Ahorror%22%5D").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 0);
+}
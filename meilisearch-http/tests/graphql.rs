@@ -0,0 +1,115 @@
+use assert_json_diff::assert_json_eq;
+use serde_json::json;
+
+mod common;
+
+async fn graphql_server() -> common::Server {
+    let mut server = common::Server::with_options("movies", |opt| opt.enable_graphql = true);
+
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server
+        .add_or_replace_multiple_documents(json!([
+            { "id": 1, "title": "Carol", "genre": "romance" },
+            { "id": 2, "title": "Wonder Woman", "genre": "action" },
+            { "id": 3, "title": "Mad Max", "genre": "action" },
+        ]))
+        .await;
+
+    server
+}
+
+#[actix_rt::test]
+async fn graphql_search_and_document() {
+    let mut server = graphql_server().await;
+
+    let body = json!({
+        "query": r#"
+            query Search($q: String!) {
+                found: movies(q: $q, limit: 1) {
+                    __typename
+                    nbHits
+                    hits { title }
+                }
+                moviesDocument(id: 3) { id title }
+                missing: moviesDocument(id: "42") { id }
+            }
+        "#,
+        "variables": { "q": "carol" },
+    });
+
+    let (response, status_code) = server.post_request("/graphql", body).await;
+    assert_eq!(status_code, 200);
+
+    let expected = json!({
+        "data": {
+            "found": {
+                "__typename": "MoviesSearchResult",
+                "nbHits": 1,
+                "hits": [{ "title": "Carol" }],
+            },
+            "moviesDocument": { "id": 3, "title": "Mad Max" },
+            "missing": null,
+        }
+    });
+    assert_json_eq!(expected, response, ordered: false);
+}
+
+#[actix_rt::test]
+async fn graphql_errors() {
+    let mut server = graphql_server().await;
+
+    let (response, status_code) = server.post_request("/graphql", json!({ "query": "{ movies { hits { rating } } }" })).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["data"], json!(null));
+    assert_eq!(response["errors"][0]["message"], "Cannot query field \"rating\" on type \"Movies\"");
+
+    let (response, _) = server.post_request("/graphql", json!({ "query": "mutation { movies }" })).await;
+    assert!(response["errors"][0]["message"].as_str().unwrap().contains("mutation operations are not supported"));
+
+    let (response, _) = server.post_request("/graphql", json!({ "query": "{ movies(q: $q) { nbHits } }" })).await;
+    assert_eq!(response["errors"][0]["message"], "Variable \"$q\" is not defined");
+
+    let (response, _) = server.post_request("/graphql", json!({ "query": "{ __schema { types { name } } }" })).await;
+    assert!(response["errors"][0]["message"].as_str().unwrap().starts_with("Introspection is not supported"));
+}
+
+#[actix_rt::test]
+async fn graphql_schema() {
+    let mut server = graphql_server().await;
+
+    let (response, status_code) = server.get_request("/graphql/schema").await;
+    assert_eq!(status_code, 200);
+
+    let schema = response["schema"].as_str().unwrap();
+    assert!(schema.contains("  moviesDocument(id: ID!): Movies\n"));
+    assert!(schema.contains("type MoviesSearchResult {\n  hits: [Movies!]!\n"));
+    assert!(schema.contains("type Movies {\n  genre: JSON\n  id: JSON\n  title: JSON\n"));
+}
+
+#[actix_rt::test]
+async fn graphql_colliding_names() {
+    let mut server = graphql_server().await;
+
+    server.create_index(json!({ "uid": "my-index" })).await;
+    server.create_index(json!({ "uid": "my_index" })).await;
+    server.create_index(json!({ "uid": "movies-document" })).await;
+    server.create_index(json!({ "uid": "query" })).await;
+
+    let (response, status_code) = server.get_request("/graphql/schema").await;
+    assert_eq!(status_code, 200);
+
+    let schema = response["schema"].as_str().unwrap();
+    assert!(schema.contains("  moviesDocument(id: ID!): Movies\n"));
+    assert!(schema.contains("  moviesDocument_2Document(id: ID!): MoviesDocument_2\n"));
+    assert!(schema.contains("  myIndexDocument(id: ID!): MyIndex\n"));
+    assert!(schema.contains("  myIndex_2Document(id: ID!): MyIndex_2\n"));
+    assert!(schema.contains("  query_2Document(id: ID!): Query_2\n"));
+}
+
+#[actix_rt::test]
+async fn graphql_disabled() {
+    let mut server = common::Server::with_uid("movies");
+
+    let (_response, status_code) = server.post_request("/graphql", json!({ "query": "{ __typename }" })).await;
+    assert_eq!(status_code, 404);
+}
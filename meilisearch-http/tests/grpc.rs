@@ -0,0 +1,148 @@
+#![cfg(feature = "grpc")]
+
+use std::net::{SocketAddr, TcpListener};
+use std::time::Duration;
+
+use meilisearch_http::grpc::proto::meili_search_client::MeiliSearchClient;
+use meilisearch_http::grpc::proto::{
+    DocumentsChunk, IndexRequest, SearchRequest, UpdateSettingsRequest, UpdateStatusRequest,
+};
+use serde_json::{json, Value};
+use tokio::time::delay_for;
+use tonic::transport::Channel;
+use tonic::{Code, Request};
+
+mod common;
+
+async fn grpc_client(server: &common::Server) -> MeiliSearchClient<Channel> {
+    let addr: SocketAddr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    actix_rt::spawn({
+        let data = server.data().clone();
+        async move { meilisearch_http::grpc::serve(data, addr).await.unwrap() }
+    });
+
+    for _ in 0..30 {
+        if let Ok(client) = MeiliSearchClient::connect(format!("http://{}", addr)).await {
+            return client;
+        }
+        delay_for(Duration::from_millis(100)).await;
+    }
+    panic!("the gRPC server did not start");
+}
+
+async fn wait_update(client: &mut MeiliSearchClient<Channel>, index_uid: &str, update_id: u64) -> Value {
+    loop {
+        let request = UpdateStatusRequest { index_uid: index_uid.to_string(), update_id };
+        let response = client.get_update_status(request).await.unwrap().into_inner();
+        if response.status == "processed" || response.status == "failed" {
+            return serde_json::from_slice(&response.details).unwrap();
+        }
+        delay_for(Duration::from_millis(100)).await;
+    }
+}
+
+#[actix_rt::test]
+async fn grpc_documents_settings_and_search() {
+    let mut server = common::Server::with_uid("movies");
+    server.create_index(json!({ "uid": "movies" })).await;
+    let mut client = grpc_client(&server).await;
+
+    // the documents array is split in the middle of a document
+    let chunks = vec![
+        DocumentsChunk {
+            index_uid: "movies".to_string(),
+            primary_key: "id".to_string(),
+            partial: false,
+            documents: br#"[{ "id": 1, "title": "Carol", "genre": "ro"#.to_vec(),
+        },
+        DocumentsChunk {
+            documents: br#"mance" }, { "id": 2, "title": "Wonder Woman", "genre": "action" }]"#.to_vec(),
+            ..Default::default()
+        },
+    ];
+    let response = client.add_documents(Request::new(futures::stream::iter(chunks))).await.unwrap();
+    let details = wait_update(&mut client, "movies", response.into_inner().update_id).await;
+    assert_eq!(details["status"], "processed", "{}", details);
+
+    let request = UpdateSettingsRequest {
+        index_uid: "movies".to_string(),
+        settings: serde_json::to_vec(&json!({ "attributesForFaceting": ["genre"] })).unwrap(),
+    };
+    let response = client.update_settings(request).await.unwrap();
+    let details = wait_update(&mut client, "movies", response.into_inner().update_id).await;
+    assert_eq!(details["status"], "processed", "{}", details);
+
+    let request = IndexRequest { index_uid: "movies".to_string() };
+    let response = client.get_settings(request).await.unwrap().into_inner();
+    let settings: Value = serde_json::from_slice(&response.settings).unwrap();
+    assert_eq!(settings["attributesForFaceting"], json!(["genre"]));
+
+    let request = SearchRequest {
+        index_uid: "movies".to_string(),
+        q: "carol".to_string(),
+        limit: Some(1),
+        attributes_to_retrieve: vec!["title".to_string()],
+        facets: vec!["genre".to_string()],
+        ..Default::default()
+    };
+    let response = client.search(request).await.unwrap().into_inner();
+    assert_eq!(response.nb_hits, 1);
+    assert_eq!(response.limit, 1);
+    let hit: Value = serde_json::from_slice(&response.hits[0]).unwrap();
+    assert_eq!(hit, json!({ "title": "Carol" }));
+    let facets: Value = serde_json::from_slice(&response.facets).unwrap();
+    assert_eq!(facets, json!({ "genre": { "romance": 1 } }));
+}
+
+#[actix_rt::test]
+async fn grpc_errors() {
+    let server = common::Server::with_options("movies", |opt| opt.master_key = Some("masterKey".to_string()));
+    let mut client = grpc_client(&server).await;
+
+    let request = SearchRequest { index_uid: "movies".to_string(), ..Default::default() };
+    let status = client.search(request).await.unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+
+    let mut request = Request::new(SearchRequest { index_uid: "movies".to_string(), ..Default::default() });
+    request.metadata_mut().insert("x-meili-api-key", "masterKey".parse().unwrap());
+    let status = client.search(request).await.unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[actix_rt::test]
+async fn grpc_invalid_documents_chunks() {
+    let mut server = common::Server::with_options("movies", |opt| opt.http_payload_size_limit = 64);
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.create_index(json!({ "uid": "books", "primaryKey": "id" })).await;
+    let mut client = grpc_client(&server).await;
+
+    // the chunks following the first one can't target another index
+    let chunks = vec![
+        DocumentsChunk {
+            index_uid: "movies".to_string(),
+            documents: br#"[{ "id": 1 },"#.to_vec(),
+            ..Default::default()
+        },
+        DocumentsChunk {
+            index_uid: "books".to_string(),
+            documents: br#"{ "id": 2 }]"#.to_vec(),
+            ..Default::default()
+        },
+    ];
+    let status = client.add_documents(Request::new(futures::stream::iter(chunks))).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let chunks = vec![
+        DocumentsChunk {
+            index_uid: "movies".to_string(),
+            documents: br#"[{ "id": 1, "title": "Carol" },"#.to_vec(),
+            ..Default::default()
+        },
+        DocumentsChunk {
+            documents: br#"{ "id": 2, "title": "Wonder Woman" }]"#.to_vec(),
+            ..Default::default()
+        },
+    ];
+    let status = client.add_documents(Request::new(futures::stream::iter(chunks))).await.unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+}
@@ -36,3 +36,19 @@ async fn test_healthyness() {
     let (_response, status_code) = server.get_health().await;
     assert_eq!(status_code, 200);
 }
+
+#[actix_rt::test]
+async fn schedule_compaction() {
+    let mut server = common::Server::with_uid("movies");
+
+    let (response, status_code) = server.get_request("/compaction").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!({ "scheduled": false }));
+
+    let (response, status_code) = server.post_request("/compaction", json!(null)).await;
+    assert_eq!(status_code, 202);
+    assert_eq!(response, json!({ "scheduled": true }));
+
+    let (response, _status_code) = server.get_request("/compaction").await;
+    assert_eq!(response, json!({ "scheduled": true }));
+}
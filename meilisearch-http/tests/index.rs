@@ -680,3 +680,458 @@ async fn check_first_update_should_bring_up_processed_status_after_first_docs_ad
     assert_eq!(status_code, 200);
     assert_eq!(response[0]["status"], "processed");
 }
+
+#[actix_rt::test]
+async fn filter_and_paginate_updates_status() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({
+        "uid": "movies",
+        "primaryKey": "id",
+    });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([{ "id": 1, "title": "Carol" }, { "id": 2, "title": "Wonder Woman" }]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let body = json!({ "rankingRules": ["typo", "words"] });
+    server.update_all_settings(body).await;
+
+    let body = json!([{ "id": 3, "title": "Mad Max" }]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let (response, status_code) = server.get_request("/indexes/movies/updates?type=DocumentsAddition").await;
+    assert_eq!(status_code, 200);
+    let updates = response.as_array().unwrap();
+    assert_eq!(updates.len(), 2);
+    assert!(updates.iter().all(|u| u["type"]["name"] == "DocumentsAddition"));
+
+    let (response, status_code) = server.get_request("/indexes/movies/updates?status=processed&offset=1&limit=1").await;
+    assert_eq!(status_code, 200);
+    let updates = response.as_array().unwrap();
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0]["updateId"], 1);
+    assert_eq!(updates[0]["type"]["name"], "Settings");
+
+    let (response, status_code) = server.get_request("/indexes/movies/updates?status=enqueued,failed").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!([]));
+
+    let (response, status_code) = server.get_request("/indexes/movies/updates?from=2100-01-01T00:00:00Z").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!([]));
+
+    let (_response, status_code) = server.get_request("/indexes/movies/updates?status=unknown").await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn get_updates_status_batch() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({
+        "uid": "movies",
+        "primaryKey": "id",
+    });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([{ "id": 1, "title": "Carol" }]);
+    let (response, status_code) = server.post_request("/indexes/movies/documents", body).await;
+    assert_eq!(status_code, 202);
+    let first_update_id = response["updateId"].as_u64().unwrap();
+
+    let body = json!([{ "id": 2, "title": "Wonder Woman" }]);
+    let (response, status_code) = server.post_request("/indexes/movies/documents", body).await;
+    assert_eq!(status_code, 202);
+    let second_update_id = response["updateId"].as_u64().unwrap();
+
+    let url = format!(
+        "/indexes/movies/updates/batch?ids={},{}&waitFor=true",
+        second_update_id, first_update_id
+    );
+    let (response, status_code) = server.get_request(&url).await;
+    assert_eq!(status_code, 200);
+    let statuses = response.as_array().unwrap();
+    assert_eq!(statuses.len(), 2);
+    assert_eq!(statuses[0]["updateId"], second_update_id);
+    assert_eq!(statuses[1]["updateId"], first_update_id);
+    assert!(statuses.iter().all(|s| s["status"] == "processed"));
+
+    let (_response, status_code) = server.get_request("/indexes/movies/updates/batch?ids=0,42").await;
+    assert_eq!(status_code, 404);
+
+    let (_response, status_code) = server.get_request("/indexes/movies/updates/batch?ids=0,abc").await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn purge_updates_history() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({
+        "uid": "movies",
+        "primaryKey": "id",
+    });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    for id in 0..3 {
+        let body = json!([{ "id": id, "title": "Carol" }]);
+        server.add_or_replace_multiple_documents(body).await;
+    }
+
+    let (_response, status_code) = server.delete_request("/indexes/movies/updates").await;
+    assert_eq!(status_code, 400);
+
+    let (response, status_code) = server.delete_request("/indexes/movies/updates?beforeId=1").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["purgedUpdates"], 1);
+    assert!(response["freedBytes"].as_u64().unwrap() > 0);
+
+    let (response, status_code) = server.get_all_updates_status().await;
+    assert_eq!(status_code, 200);
+    let ids: Vec<_> = response.as_array().unwrap().iter().map(|u| u["updateId"].clone()).collect();
+    assert_eq!(ids, vec![json!(1), json!(2)]);
+
+    // the last update is always kept to never reuse update ids
+    let (response, status_code) = server.delete_request("/indexes/movies/updates?beforeDate=2100-01-01T00:00:00Z").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["purgedUpdates"], 1);
+
+    let (response, _status_code) = server.get_all_updates_status().await;
+    assert_eq!(response.as_array().unwrap().len(), 1);
+    assert_eq!(response[0]["updateId"], 2);
+}
+
+#[actix_rt::test]
+async fn reject_updates_when_the_queue_is_full() {
+    let mut server = common::Server::with_options("movies", |opt| opt.max_enqueued_updates = Some(0));
+
+    let body = json!({
+        "uid": "movies",
+        "primaryKey": "id",
+    });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([{ "id": 1, "title": "Carol" }]);
+    let (response, status_code) = server.post_request("/indexes/movies/documents", body).await;
+    assert_eq!(status_code, 429);
+    assert!(response["message"].as_str().unwrap().contains("update queue is full"));
+
+    let (response, status_code) = server.get_all_updates_status().await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!([]));
+}
+
+#[actix_rt::test]
+async fn storage_usage_in_stats() {
+    let mut server = common::Server::test_server().await;
+
+    let (response, status_code) = server.get_index_stats().await;
+    assert_eq!(status_code, 200);
+    assert!(response["storage"]["wordsFst"].as_u64().unwrap() > 0);
+    assert!(response["storage"]["rankedMap"].as_u64().is_some());
+    assert_eq!(response["storage"]["synonymsFst"], 0);
+
+    let (response, status_code) = server.get_request("/stats").await;
+    assert_eq!(status_code, 200);
+    let main = &response["environments"]["main"];
+    assert!(main["size"].as_u64().unwrap() > 0);
+    assert!(main["size"].as_u64().unwrap() <= main["mapSize"].as_u64().unwrap());
+    assert_eq!(main["maxMapSize"], Value::Null);
+    assert!(response["environments"]["update"]["size"].as_u64().unwrap() > 0);
+    assert!(response["indexes"]["test"]["storage"]["wordsFst"].as_u64().unwrap() > 0);
+}
+
+#[actix_rt::test]
+async fn databases_usage_in_stats() {
+    let mut server = common::Server::test_server().await;
+
+    let (response, status_code) = server.get_index_stats().await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["isIndexing"], false);
+    assert_eq!(response["updateQueue"], json!({ "pendingUpdates": 0, "pendingBytes": 0 }));
+    let databases = &response["databases"];
+    for name in &["postingsLists", "docsWords", "documentsFields"] {
+        assert!(databases[name]["entries"].as_u64().unwrap() > 0);
+        assert!(databases[name]["size"].as_u64().unwrap() > databases[name]["entries"].as_u64().unwrap());
+    }
+    assert_eq!(
+        databases["docsWords"]["entries"],
+        response["numberOfDocuments"],
+    );
+    assert!(databases["facets"]["entries"].as_u64().is_some());
+
+    let (response, status_code) = server.get_request("/stats").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["indexes"]["test"]["databases"], *databases);
+}
+
+#[actix_rt::test]
+async fn export_words_frequencies() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "Carol Carpenter" },
+        { "id": 2, "title": "Carol" },
+        { "id": 3, "title": "Heat" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let (response, status_code) = server.get_request("/indexes/movies/words").await;
+    assert_eq!(status_code, 200);
+    let words = response.as_array().unwrap();
+    assert!(words.contains(&json!({ "word": "heat", "frequency": 1 })));
+    let mut sorted = words.clone();
+    sorted.sort_by_key(|w| w["word"].as_str().unwrap().to_string());
+    assert_eq!(*words, sorted);
+
+    let (response, status_code) = server.get_request("/indexes/movies/words?prefix=car").await;
+    assert_eq!(status_code, 200);
+    let expected = json!([
+        { "word": "carol", "frequency": 2 },
+        { "word": "carpenter", "frequency": 1 },
+    ]);
+    assert_eq!(response, expected);
+
+    let (response, status_code) = server.get_request("/indexes/movies/words?prefix=car&offset=1&limit=1").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!([{ "word": "carpenter", "frequency": 1 }]));
+}
+
+#[actix_rt::test]
+async fn repair_index_rebuilds_derived_stores() {
+    let mut server = common::Server::test_server().await;
+
+    let (stats_before, _) = server.get_index_stats().await;
+    let (search_before, _) = server.search("q=exercitation").await;
+
+    let (response, status_code) = server.post_request_async("/indexes/test/repair", json!(null)).await;
+    assert_eq!(status_code, 202);
+
+    let update_id = response["updateId"].as_u64().unwrap();
+    let (response, status_code) = server.get_update_status(update_id).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["status"], "processed");
+    assert_eq!(response["type"]["name"], "Repair");
+
+    let (stats_after, _) = server.get_index_stats().await;
+    assert_eq!(stats_before["numberOfDocuments"], stats_after["numberOfDocuments"]);
+    assert_eq!(stats_before["fieldsFrequency"], stats_after["fieldsFrequency"]);
+
+    let (search_after, _) = server.search("q=exercitation").await;
+    assert_eq!(search_before["hits"], search_after["hits"]);
+    assert_eq!(search_before["nbHits"], search_after["nbHits"]);
+}
+
+#[actix_rt::test]
+async fn check_index_integrity() {
+    let mut server = common::Server::test_server().await;
+
+    let (response, status_code) = server.get_request("/indexes/test/integrity").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["isSound"], true);
+    assert_eq!(response["wordsWithoutPostingsList"], 0);
+    assert_eq!(response["documentsWithoutFields"], 0);
+    assert_eq!(response["numberOfDocuments"], response["storedNumberOfDocuments"]);
+
+    let (_response, status_code) = server.get_request("/indexes/unknown/integrity").await;
+    assert_eq!(status_code, 404);
+}
+
+#[actix_rt::test]
+async fn repair_unknown_index() {
+    let mut server = common::Server::with_uid("movies");
+
+    let (_response, status_code) = server.post_request("/indexes/movies/repair", json!(null)).await;
+    assert_eq!(status_code, 404);
+}
+
+#[actix_rt::test]
+async fn swap_indexes_and_use_aliases() {
+    let mut server = common::Server::with_uid("products");
+
+    for uid in &["products", "products_new"] {
+        let body = json!({ "uid": uid, "primaryKey": "id" });
+        let (_response, status_code) = server.create_index(body).await;
+        assert_eq!(status_code, 201);
+    }
+
+    let body = json!([{ "id": 1, "title": "old product" }]);
+    server.post_request_async("/indexes/products/documents", body).await;
+
+    let body = json!([{ "id": 2, "title": "new product" }]);
+    server.post_request_async("/indexes/products_new/documents", body).await;
+
+    // 1 - Swap the indexes, the uids now serve the other documents
+
+    let body = json!({ "indexes": ["products", "products_new"] });
+    let (_response, status_code) = server.post_request("/swap-indexes", body).await;
+    assert_eq!(status_code, 204);
+
+    let (response, status_code) = server.get_request("/indexes/products/search?q=product").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"][0]["id"], 2);
+
+    let (response, status_code) = server.get_request("/indexes/products_new/search?q=product").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"][0]["id"], 1);
+
+    let body = json!({ "indexes": ["products", "unknown"] });
+    let (_response, status_code) = server.post_request("/swap-indexes", body).await;
+    assert_eq!(status_code, 404);
+
+    // 2 - Route the requests through an alias
+
+    let body = json!({ "alias": "shop", "indexUid": "products" });
+    let (_response, status_code) = server.post_request("/aliases", body).await;
+    assert_eq!(status_code, 200);
+
+    let (response, status_code) = server.get_request("/indexes/shop/search?q=product").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"][0]["id"], 2);
+
+    let (response, status_code) = server.get_request("/aliases").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!([{ "alias": "shop", "indexUid": "products" }]));
+
+    let body = json!({ "alias": "products_new", "indexUid": "products" });
+    let (_response, status_code) = server.post_request("/aliases", body).await;
+    assert_eq!(status_code, 400);
+
+    let (_response, status_code) = server.delete_request("/aliases/shop").await;
+    assert_eq!(status_code, 204);
+
+    let (_response, status_code) = server.delete_request("/aliases/shop").await;
+    assert_eq!(status_code, 404);
+}
+
+#[actix_rt::test]
+async fn copy_index() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "Carol" },
+        { "id": 2, "title": "Heat" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let (response, status_code) = server.post_request("/indexes/movies/copy", json!({ "uid": "movies-copy" })).await;
+    assert_eq!(status_code, 201);
+    assert_eq!(response["uid"], json!("movies-copy"));
+    assert_eq!(response["primaryKey"], json!("id"));
+
+    let (response, status_code) = server.get_request("/indexes/movies-copy/search?q=heat").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"], json!([{ "id": 2, "title": "Heat" }]));
+
+    let (response, status_code) = server.get_request("/indexes/movies-copy/updates").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!([]));
+
+    let (_response, status_code) = server.post_request("/indexes/movies/copy", json!({ "uid": "movies-copy" })).await;
+    assert_eq!(status_code, 400);
+
+    let (_response, status_code) = server.post_request("/indexes/unknown/copy", json!({ "uid": "other" })).await;
+    assert_eq!(status_code, 404);
+}
+
+#[actix_rt::test]
+async fn rename_index_uid() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "Carol" },
+        { "id": 2, "title": "Heat" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let body = json!({ "alias": "films", "indexUid": "movies" });
+    let (_response, status_code) = server.post_request("/aliases", body).await;
+    assert_eq!(status_code, 200);
+
+    let (response, status_code) = server.post_request("/indexes/movies/rename", json!({ "uid": "cinema" })).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["uid"], json!("cinema"));
+    assert_eq!(response["name"], json!("cinema"));
+    assert_eq!(response["primaryKey"], json!("id"));
+
+    let (response, status_code) = server.get_request("/indexes/cinema/search?q=heat").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"], json!([{ "id": 2, "title": "Heat" }]));
+
+    let (_response, status_code) = server.get_request("/indexes/movies").await;
+    assert_eq!(status_code, 404);
+
+    // the updates and the aliases follow the index
+    let (response, status_code) = server.get_request("/indexes/cinema/updates").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response.as_array().unwrap().len(), 1);
+
+    let (response, status_code) = server.get_request("/aliases").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!([{ "alias": "films", "indexUid": "cinema" }]));
+
+    // the old uid can be used by a new index
+    let body = json!({ "uid": "movies" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let (response, status_code) = server.get_request("/indexes/movies/documents").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!([]));
+
+    let (_response, status_code) = server.post_request("/indexes/cinema/rename", json!({ "uid": "movies" })).await;
+    assert_eq!(status_code, 400);
+
+    let (_response, status_code) = server.post_request("/indexes/unknown/rename", json!({ "uid": "other" })).await;
+    assert_eq!(status_code, 404);
+}
+
+#[actix_rt::test]
+async fn fields_capabilities() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "Carol", "genre": "drama" },
+        { "id": 2, "title": "Heat" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    server.update_all_settings(json!({
+        "attributesForFaceting": ["genre"],
+        "displayedAttributes": ["id", "title"],
+    })).await;
+
+    let (response, status_code) = server.get_request("/indexes/movies/fields").await;
+    assert_eq!(status_code, 200);
+
+    let expected = json!([
+        { "name": "id", "id": 0, "indexed": true, "displayed": true, "ranked": false, "faceted": false, "frequency": 2 },
+        { "name": "title", "id": 1, "indexed": true, "displayed": true, "ranked": false, "faceted": false, "frequency": 2 },
+        { "name": "genre", "id": 2, "indexed": true, "displayed": false, "ranked": false, "faceted": true, "frequency": 1 },
+    ]);
+    assert_json_eq!(expected, response, ordered: true);
+
+    let (_response, status_code) = server.get_request("/indexes/unknown/fields").await;
+    assert_eq!(status_code, 404);
+}
@@ -0,0 +1,100 @@
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn scoped_keys() {
+    let mut server = common::Server::with_options("movies", |opt| opt.master_key = Some("masterKey".to_string()));
+    server.set_api_key(Some("masterKey"));
+
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server.create_index(json!({ "uid": "series", "primaryKey": "id" })).await;
+    server.add_or_replace_multiple_documents(json!([{ "id": 1, "title": "Carol" }])).await;
+
+    // only the master key manages the scoped keys
+    let body = json!({ "description": "movies tenant", "indexes": ["movies"], "actions": ["search", "documents-write"] });
+    let (response, status_code) = server.post_request("/keys/scoped", body).await;
+    assert_eq!(status_code, 201);
+    assert_eq!(response["description"], "movies tenant");
+    assert_eq!(response["indexes"], json!(["movies"]));
+    assert_eq!(response["actions"], json!(["search", "documents-write"]));
+    let key = response["key"].as_str().unwrap().to_string();
+
+    let body = json!({ "indexes": [], "actions": ["search"] });
+    let (_response, status_code) = server.post_request("/keys/scoped", body).await;
+    assert_eq!(status_code, 400);
+
+    let body = json!({ "indexes": ["movies"], "actions": ["everything"] });
+    let (_response, status_code) = server.post_request("/keys/scoped", body).await;
+    assert_eq!(status_code, 400);
+
+    let (response, status_code) = server.get_request("/keys/scoped").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response.as_array().unwrap().len(), 1);
+    assert_eq!(response[0]["key"], key.as_str());
+
+    server.set_api_key(Some(&key));
+
+    // the key grants its actions on its indexes only
+    let (response, status_code) = server.search("q=carol").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"][0]["title"], "Carol");
+
+    let (response, status_code) = server.post_request("/indexes/movies/documents", json!([{ "id": 2, "title": "Ran" }])).await;
+    assert_eq!(status_code, 202);
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+
+    // a batch updating the settings also requires the settings access
+    let body = json!([{ "type": "addDocuments", "documents": [{ "id": 3, "title": "Her" }] }]);
+    let (_response, status_code) = server.post_request("/indexes/movies/batch", body).await;
+    assert_eq!(status_code, 202);
+
+    let body = json!([
+        { "type": "addDocuments", "documents": [{ "id": 4, "title": "Heat" }] },
+        { "type": "updateSettings", "settings": { "searchableAttributes": ["title"] } },
+    ]);
+    let (_response, status_code) = server.post_request("/indexes/movies/batch", body).await;
+    assert_eq!(status_code, 401);
+
+    let (_response, status_code) = server.get_request("/indexes/series/search?q=carol").await;
+    assert_eq!(status_code, 401);
+
+    let (_response, status_code) = server.get_request("/indexes/movies/settings").await;
+    assert_eq!(status_code, 401);
+
+    let (_response, status_code) = server.delete_request("/indexes/movies").await;
+    assert_eq!(status_code, 401);
+
+    let (_response, status_code) = server.get_request("/indexes").await;
+    assert_eq!(status_code, 401);
+
+    let (_response, status_code) = server.get_request("/keys/scoped").await;
+    assert_eq!(status_code, 401);
+
+    // a settings key given for all the indexes
+    server.set_api_key(Some("masterKey"));
+    let body = json!({ "indexes": ["*"], "actions": ["settings-write"] });
+    let (response, status_code) = server.post_request("/keys/scoped", body).await;
+    assert_eq!(status_code, 201);
+    let settings_key = response["key"].as_str().unwrap().to_string();
+
+    server.set_api_key(Some(&settings_key));
+
+    let (_response, status_code) = server.get_request("/indexes/series/settings").await;
+    assert_eq!(status_code, 200);
+
+    let (_response, status_code) = server.get_request("/indexes/series/search?q=carol").await;
+    assert_eq!(status_code, 401);
+
+    // a deleted key is refused
+    server.set_api_key(Some("masterKey"));
+    let (_response, status_code) = server.delete_request(&format!("/keys/scoped/{}", key)).await;
+    assert_eq!(status_code, 204);
+
+    let (_response, status_code) = server.delete_request(&format!("/keys/scoped/{}", key)).await;
+    assert_eq!(status_code, 404);
+
+    server.set_api_key(Some(&key));
+    let (_response, status_code) = server.search("q=carol").await;
+    assert_eq!(status_code, 401);
+}
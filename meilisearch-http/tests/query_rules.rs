@@ -0,0 +1,96 @@
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn query_rules_pin_and_hide_documents() {
+    let mut server = common::Server::test_server().await;
+
+    let body = json!([{
+        "condition": { "exact": "Lorem" },
+        "pinned": ["76", "42"],
+        "hidden": ["0"],
+    }]);
+    let (response, status_code) = server.post_request("/indexes/test/query-rules", body.clone()).await;
+    assert_eq!(status_code, 202);
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+
+    let (response, status_code) = server.get_request("/indexes/test/query-rules").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response[0]["condition"], json!({ "exact": "Lorem" }));
+    assert_eq!(response[0]["pinned"], json!(["76", "42"]));
+
+    let (response, status_code) = server.search("q=lorem&attributesToRetrieve=id").await;
+    assert_eq!(status_code, 200);
+    let hits = response["hits"].as_array().unwrap();
+    assert_eq!(hits[0]["id"], 76);
+    assert_eq!(hits[1]["id"], 42);
+    assert!(hits.iter().all(|hit| hit["id"] != 0));
+    assert_eq!(hits.iter().filter(|hit| hit["id"] == 76).count(), 1);
+
+    // the rule is not applied to other queries
+    let (response, status_code) = server.search("q=dolor&attributesToRetrieve=id").await;
+    assert_eq!(status_code, 200);
+    assert_ne!(response["hits"][0]["id"], 76);
+
+    let (response, status_code) = server.delete_request("/indexes/test/query-rules").await;
+    assert_eq!(status_code, 202);
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+
+    let (response, _status_code) = server.get_request("/indexes/test/query-rules").await;
+    assert_eq!(response, json!([]));
+}
+
+#[actix_rt::test]
+async fn query_rules_force_filters() {
+    let mut server = common::Server::test_server().await;
+
+    let body = json!([{
+        "condition": { "pattern": "lor*" },
+        "filters": "color = green",
+    }]);
+    let (response, status_code) = server.post_request("/indexes/test/query-rules", body).await;
+    assert_eq!(status_code, 202);
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+
+    let (response, status_code) = server.search("q=lorem&attributesToRetrieve=color").await;
+    assert_eq!(status_code, 200);
+    let hits = response["hits"].as_array().unwrap();
+    assert!(!hits.is_empty());
+    assert!(hits.iter().all(|hit| hit["color"] == "green"));
+
+    // invalid filters are rejected
+    let body = json!([{
+        "condition": { "exact": "lorem" },
+        "filters": "unknown = green",
+    }]);
+    let (_response, status_code) = server.post_request("/indexes/test/query-rules", body).await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn query_rules_pinned_documents_are_filtered_and_counted_once() {
+    let mut server = common::Server::test_server().await;
+
+    let query = "q=lorem&filters=color%20%3D%20green&exhaustiveNbHits=true&attributesToRetrieve=id";
+    let (response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 200);
+    let nb_hits = response["nbHits"].as_u64().unwrap();
+
+    // 42 is brown, 0 does not match the query and 76 is already a hit
+    let body = json!([{
+        "condition": { "exact": "lorem" },
+        "pinned": ["42", "0", "76"],
+    }]);
+    let (response, status_code) = server.post_request("/indexes/test/query-rules", body).await;
+    assert_eq!(status_code, 202);
+    server.wait_update_id(response["updateId"].as_u64().unwrap()).await;
+
+    let (response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 200);
+    let hits = response["hits"].as_array().unwrap();
+    assert_eq!(hits[0]["id"], 0);
+    assert_eq!(hits[1]["id"], 76);
+    assert!(hits.iter().all(|hit| hit["id"] != 42));
+    assert_eq!(response["nbHits"], nb_hits + 1);
+}
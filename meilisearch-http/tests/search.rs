@@ -1251,6 +1251,59 @@ async fn test_faceted_search_valid() {
                 .unwrap() == "green")));
 }
 
+#[actix_rt::test]
+async fn test_faceted_search_range() {
+    let mut server = common::Server::test_server().await;
+
+    let body = json!({
+        "attributesForFaceting": ["color", "age"]
+    });
+    server.update_all_settings(body).await;
+
+    // ["age:30 TO 35"]
+    let query = "q=a&facetFilters=%5B%22age%3A30%20TO%2035%22%5D";
+    let (response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 200);
+    let hits = response["hits"].as_array().unwrap();
+    assert!(!hits.is_empty());
+    assert!(hits.iter().all(|hit| {
+        let age = hit["age"].as_u64().unwrap();
+        age >= 30 && age <= 35
+    }));
+
+    // ["color:green", "age:30 TO 35"]
+    let query = "q=a&facetFilters=%5B%22color%3Agreen%22,%20%22age%3A30%20TO%2035%22%5D";
+    let (response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 200);
+    assert!(response["hits"].as_array().unwrap().iter().all(|hit| {
+        let age = hit["age"].as_u64().unwrap();
+        hit["color"] == "green" && age >= 30 && age <= 35
+    }));
+
+    // [["age:20 TO 21", "age:39 TO 40"]]
+    let query = "q=a&facetFilters=%5B%5B%22age%3A20%20TO%2021%22,%20%22age%3A39%20TO%2040%22%5D%5D";
+    let (response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 200);
+    assert!(response["hits"].as_array().unwrap().iter().all(|hit| {
+        let age = hit["age"].as_u64().unwrap();
+        age == 20 || age == 21 || age == 39 || age == 40
+    }));
+
+    // an exact numeric value is still matched: ["age:36"]
+    let query = "q=a&facetFilters=%5B%22age%3A36%22%5D";
+    let (response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 200);
+    let hits = response["hits"].as_array().unwrap();
+    assert!(!hits.is_empty());
+    assert!(hits.iter().all(|hit| hit["age"] == 36));
+
+    // ["age:50 TO 40"]
+    let query = "q=a&facetFilters=%5B%22age%3A50%20TO%2040%22%5D";
+    let (response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 200);
+    assert!(response["hits"].as_array().unwrap().is_empty());
+}
+
 #[actix_rt::test]
 async fn test_faceted_search_invalid() {
     let mut server = common::Server::test_server().await;
@@ -1344,6 +1397,119 @@ async fn test_facet_count() {
     assert_eq!(status_code, 400);
 }
 
+#[actix_rt::test]
+async fn test_global_facet_count() {
+    let mut server = common::Server::test_server().await;
+
+    let body = json!({
+        "attributesForFaceting": ["color"]
+    });
+    server.update_all_settings(body).await;
+
+    // the counts are computed over the whole index, whatever the query is
+    let query = "q=a&facets=%5B%22color%22%5D&facetFilters=%5B%22color%3Ablue%22%5D&globalFacets=true";
+    let (response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 200);
+    let color = response["facets"]["color"].as_object().unwrap();
+    assert_eq!(color["green"], 25);
+    assert_eq!(color["blue"], 28);
+    assert_eq!(color["brown"], 24);
+}
+
+#[actix_rt::test]
+async fn test_disjunctive_facet_count() {
+    let mut server = common::Server::test_server().await;
+
+    let body = json!({
+        "attributesForFaceting": ["color", "gender"]
+    });
+    server.update_all_settings(body).await;
+
+    // the filter on color is ignored when counting the colors, but not the genders
+    let query = "q=a&facets=%5B%22color%22,%20%22gender%22%5D&facetFilters=%5B%22color%3Ablue%22%5D";
+    let (response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 200);
+    let color = response["facets"]["color"].as_object().unwrap();
+    assert_ne!(color["green"], 0);
+    assert_ne!(color["brown"], 0);
+    let gender = response["facets"]["gender"].as_object().unwrap();
+    let blue_hits = response["nbHits"].as_u64().unwrap();
+    let gender_count = gender.values().map(|v| v.as_u64().unwrap()).sum::<u64>();
+    assert_eq!(gender_count, blue_hits);
+
+    // the filter on gender restricts the counts of the colors
+    let query = "q=&facets=%5B%22color%22%5D&facetFilters=%5B%22color%3Ablue%22,%20%22gender%3Amale%22%5D";
+    let (response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 13);
+    assert_eq!(response["facets"]["color"], json!({ "green": 13, "blue": 13, "brown": 11 }));
+
+    // no document matches the filter but the counts of each facet still ignore its own clause
+    let query = "q=&facets=%5B%22color%22,%20%22gender%22%5D&facetFilters=%5B%22color%3Ablue%22,%20%22gender%3Aunknown%22%5D";
+    let (response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 0);
+    assert_eq!(response["hits"], json!([]));
+    assert_eq!(response["facets"]["color"], json!({ "green": 0, "blue": 0, "brown": 0 }));
+    assert_eq!(response["facets"]["gender"], json!({ "female": 15, "male": 13 }));
+}
+
+#[actix_rt::test]
+async fn test_facet_count_with_filters() {
+    let mut server = common::Server::with_uid("shoes");
+
+    let body = json!({ "uid": "shoes", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!({ "attributesForFaceting": ["color"] });
+    server.update_all_settings(body).await;
+
+    let body = json!([
+        { "id": 1, "title": "shoe", "color": "red", "price": 10 },
+        { "id": 2, "title": "shoe", "color": "blue", "price": 20 },
+        { "id": 3, "title": "shoe", "color": "blue", "price": 30 },
+        { "id": 4, "title": "shoe", "color": "green", "price": 40 },
+        { "id": 5, "title": "boot", "color": "green", "price": 50 },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    // only the documents matching the query and the filters are counted
+    let query = "q=shoe&facets=%5B%22color%22%5D&filters=price%20%3E%2015";
+    let (response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["facets"]["color"], json!({ "red": 0, "blue": 2, "green": 1 }));
+}
+
+#[actix_rt::test]
+async fn test_facet_stats() {
+    let mut server = common::Server::with_uid("shoes");
+
+    let body = json!({ "uid": "shoes", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!({ "attributesForFaceting": ["color", "price"] });
+    server.update_all_settings(body).await;
+
+    let body = json!([
+        { "id": 1, "title": "shoe", "color": "red", "price": 10 },
+        { "id": 2, "title": "shoe", "color": "blue", "price": 20 },
+        { "id": 3, "title": "shoe", "color": "blue", "price": 20 },
+        { "id": 4, "title": "shoe", "color": "green", "price": 50 },
+        { "id": 5, "title": "boot", "color": "green", "price": 80 },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    // the stats only concern the documents matching the query
+    let query = "q=shoe&facets=%5B%22color%22,%22price%22%5D";
+    let (response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["facetsStats"]["price"], json!({ "min": 10.0, "max": 50.0, "sum": 100.0, "avg": 25.0 }));
+    // the attributes without numbers have no stats
+    assert!(response["facetsStats"].get("color").is_none());
+}
+
 #[actix_rt::test]
 async fn highlight_cropped_text() {
     let mut server = common::Server::with_uid("test");
@@ -1407,3 +1573,1071 @@ async fn highlight_cropped_text() {
         .unwrap()
         , &Value::String(expected_response.to_owned()));
 }
+
+#[actix_rt::test]
+async fn search_with_per_attribute_highlight_tags() {
+    let mut server = common::Server::with_uid("movies");
+    server.populate_movies().await;
+
+    // highlightTags={"overview":false}
+    let query = "q=captain&limit=1&attributesToHighlight=title,overview&highlightPreTag=__&highlightPostTag=__&highlightTags=%7B%22overview%22%3Afalse%7D";
+    let (response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 200);
+
+    let formatted = &response["hits"][0]["_formatted"];
+    assert_eq!(formatted["title"], "__Captain__ Marvel");
+    assert!(!formatted["overview"].as_str().unwrap().contains("__"));
+
+    // highlightTags={"overview":{"preTag":"[","postTag":"]"}}
+    let query = "q=captain&limit=1&attributesToHighlight=*&highlightTags=%7B%22overview%22%3A%7B%22preTag%22%3A%22%5B%22%2C%22postTag%22%3A%22%5D%22%7D%7D";
+    let (response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 200);
+
+    let formatted = &response["hits"][0]["_formatted"];
+    assert_eq!(formatted["title"], "<em>Captain</em> Marvel");
+    assert!(formatted["overview"].as_str().unwrap().contains("[Captain] Marvel"));
+
+    let query = "q=captain&attributesToHighlight=*&highlightTags=%7B%22overview%22%3A1%7D";
+    let (_response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn search_with_ranking_rules_override() {
+    let mut server = common::Server::with_uid("shoes");
+
+    let body = json!({ "uid": "shoes", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        "typo",
+        "words",
+        "proximity",
+        "attribute",
+        "wordsPosition",
+        "exactness",
+        "asc(price)"
+    ]);
+    server.update_ranking_rules(body).await;
+
+    let body = json!([
+        { "id": 1, "title": "running shoe", "price": 10 },
+        { "id": 2, "title": "running shoe", "price": 30 },
+        { "id": 3, "title": "running shoe", "price": 20 },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let hits_ids = |response: &Value| -> Vec<u64> {
+        response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["id"].as_u64().unwrap())
+            .collect()
+    };
+
+    let (response, status_code) = server.search("q=shoe").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![1, 3, 2]);
+
+    // rankingRules=["desc(price)"]
+    let (response, status_code) = server.search("q=shoe&rankingRules=%5B%22desc(price)%22%5D").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![2, 3, 1]);
+
+    // the override doesn't change the settings of the index
+    let (response, _status_code) = server.get_ranking_rules().await;
+    assert_eq!(response.as_array().unwrap().last().unwrap(), "asc(price)");
+
+    // rankingRules=["sort(price)"]
+    let (_response, status_code) = server.search("q=shoe&rankingRules=%5B%22sort(price)%22%5D").await;
+    assert_eq!(status_code, 400);
+
+    // rankingRules=["desc(title)"], the attribute is not registered for ranking
+    let (_response, status_code) = server.search("q=shoe&rankingRules=%5B%22desc(title)%22%5D").await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn search_with_ranking_rules_on_nested_fields() {
+    let mut server = common::Server::with_uid("books");
+
+    let body = json!({ "uid": "books", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!(["typo", "words", "desc(ratings.average)"]);
+    server.update_ranking_rules(body).await;
+
+    let body = json!([
+        { "id": 1, "title": "dune", "ratings": { "average": 3.5 } },
+        { "id": 2, "title": "dune messiah", "ratings": { "average": 4.5 } },
+        { "id": 3, "title": "dune children", "ratings": { "average": 4.0 } },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let hits_ids = |response: &Value| -> Vec<u64> {
+        response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["id"].as_u64().unwrap())
+            .collect()
+    };
+
+    let (response, status_code) = server.search("q=dune").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![2, 3, 1]);
+
+    // the nested field is not added to the searchable attributes
+    let (response, _status_code) = server.get_searchable_attributes().await;
+    assert!(!response.as_array().unwrap().contains(&json!("ratings.average")));
+}
+
+#[actix_rt::test]
+async fn search_on_nested_fields() {
+    let mut server = common::Server::with_uid("books");
+
+    let body = json!({ "uid": "books", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!({
+        "searchableAttributes": ["title", "author.name"],
+        "attributesForFaceting": ["tags.name"],
+    });
+    server.update_all_settings(body).await;
+
+    let body = json!([
+        {
+            "id": 1,
+            "title": "the dune saga",
+            "author": { "name": "Frank Herbert", "born": 1920 },
+            "tags": [{ "name": "classic" }, { "name": "desert" }],
+        },
+        {
+            "id": 2,
+            "title": "the foundation",
+            "author": { "name": "Isaac Asimov", "born": 1920 },
+            "tags": [{ "name": "classic" }, { "name": "empire" }],
+        },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let hits_ids = |response: &Value| -> Vec<u64> {
+        response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["id"].as_u64().unwrap())
+            .collect()
+    };
+
+    let (response, status_code) = server.search("q=herbert").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![1]);
+
+    // the other nested fields are not searchable
+    let (response, _status_code) = server.search("q=1920").await;
+    assert!(hits_ids(&response).is_empty());
+
+    let (response, status_code) = server.search("q=the&facetFilters=%5B%22tags.name%3Aempire%22%5D").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![2]);
+
+    let (response, status_code) = server.search("q=the&filters=author.name%3D%22Frank%20Herbert%22").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![1]);
+
+    // the documents are returned as they were sent
+    let (response, status_code) = server.search("q=herbert").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"][0]["author"]["name"], json!("Frank Herbert"));
+    assert_eq!(response["hits"][0]["tags"][1]["name"], json!("desert"));
+}
+
+#[actix_rt::test]
+async fn search_with_attributes_to_search_in() {
+    let mut server = common::Server::with_uid("books");
+
+    let body = json!({ "uid": "books", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "the winter garden", "overview": "a story of the desert" },
+        { "id": 2, "title": "desert winds", "overview": "a winter in the south" },
+        { "id": 3, "title": "the garden", "overview": "a summer in the desert" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let hits_ids = |response: &Value| -> Vec<u64> {
+        let mut ids: Vec<_> = response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["id"].as_u64().unwrap())
+            .collect();
+        ids.sort_unstable();
+        ids
+    };
+
+    let (response, status_code) = server.search("q=desert").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![1, 2, 3]);
+
+    let (response, status_code) = server.search("q=desert&attributesToSearchIn=title").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![2]);
+    assert_eq!(response["nbHits"], json!(1));
+
+    // all the query words must be found in the restricted attributes
+    let (response, status_code) = server.search("q=winter%20desert&attributesToSearchIn=overview").await;
+    assert_eq!(status_code, 200);
+    assert!(hits_ids(&response).is_empty());
+
+    let (response, status_code) = server.search("q=desert&attributesToSearchIn=overview,title").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![1, 2, 3]);
+}
+
+#[actix_rt::test]
+async fn search_with_matching_strategy() {
+    let mut server = common::Server::with_uid("shoes");
+
+    let body = json!({ "uid": "shoes", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "red running shoe" },
+        { "id": 2, "title": "blue running shoe" },
+        { "id": 3, "title": "red boots" },
+        { "id": 4, "title": "blue running sandal" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let hits_ids = |response: &Value| -> Vec<u64> {
+        response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["id"].as_u64().unwrap())
+            .collect()
+    };
+
+    let (response, status_code) = server.search("q=red%20running%20shoe").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![1]);
+
+    let (response, status_code) = server.search("q=red%20running%20shoe&matchingStrategy=all").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![1]);
+
+    // the documents matching all the words stay first, then "shoe" and "running" are dropped
+    let (response, status_code) = server.search("q=red%20running%20shoe&matchingStrategy=last").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 2);
+    assert_eq!(hits_ids(&response), vec![1, 3]);
+
+    let (response, status_code) = server.search("q=running%20red&matchingStrategy=last").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 3);
+    assert_eq!(hits_ids(&response)[0], 1);
+
+    // "running" is in more documents than "red", it is dropped first
+    let (response, status_code) = server.search("q=running%20red&matchingStrategy=frequency").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 2);
+    assert_eq!(hits_ids(&response), vec![1, 3]);
+
+    let (_response, status_code) = server.search("q=red%20running%20shoe&matchingStrategy=any").await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn search_with_exhaustive_nb_hits_and_max_total_hits() {
+    let mut server = common::Server::with_uid("shoes");
+
+    let body = json!({ "uid": "shoes", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "red shoe", "color": "red" },
+        { "id": 2, "title": "blue shoe", "color": "blue" },
+        { "id": 3, "title": "green shoe", "color": "green" },
+        { "id": 4, "title": "red sneaker shoe", "color": "red" },
+        { "id": 5, "title": "black shoe", "color": "black" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let (response, status_code) = server.search("q=shoe").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 5);
+    assert_eq!(response["exhaustiveNbHits"], true);
+
+    // the filter is only applied to the ranked documents, the count is estimated
+    let (response, status_code) = server.search("q=shoe&filters=color%20%3D%20red&limit=1").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+    assert_eq!(response["exhaustiveNbHits"], false);
+
+    let (response, status_code) = server.search("q=shoe&filters=color%20%3D%20red&limit=1&exhaustiveNbHits=true").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+    assert_eq!(response["nbHits"], 2);
+    assert_eq!(response["exhaustiveNbHits"], true);
+
+    let (response, _status_code) = server.get_request("/indexes/shoes/settings/max-total-hits").await;
+    assert_eq!(response, json!(1000));
+
+    server.post_request_async("/indexes/shoes/settings/max-total-hits", json!(3)).await;
+
+    // the hits after the maximum number of hits can't be reached by paginating
+    let (response, status_code) = server.search("q=shoe&offset=2&limit=2").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+    assert_eq!(response["nbHits"], 5);
+
+    let (response, status_code) = server.search("q=shoe&offset=3").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"], json!([]));
+
+    server.delete_request_async("/indexes/shoes/settings/max-total-hits").await;
+
+    let (response, status_code) = server.search("q=shoe&offset=3").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 2);
+}
+
+#[actix_rt::test]
+async fn search_with_search_cutoff() {
+    let mut server = common::Server::with_uid("shoes");
+
+    let body = json!({ "uid": "shoes", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "red shoe" },
+        { "id": 2, "title": "blue shoe" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let (response, status_code) = server.search("q=shoe").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["degraded"], false);
+
+    // the budget is spent before the documents are sorted, the hits are still returned
+    let (response, status_code) = server.search("q=shoe&searchCutoffMs=0").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["degraded"], true);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 2);
+
+    let (response, _status_code) = server.get_request("/indexes/shoes/settings/search-cutoff-ms").await;
+    assert_eq!(response, json!(null));
+
+    server.post_request_async("/indexes/shoes/settings/search-cutoff-ms", json!(0)).await;
+
+    let (response, _status_code) = server.get_request("/indexes/shoes/settings/search-cutoff-ms").await;
+    assert_eq!(response, json!(0));
+
+    let (response, status_code) = server.search("q=shoe").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["degraded"], true);
+
+    // the budget of the query overrides the one of the index
+    let (response, status_code) = server.search("q=shoe&searchCutoffMs=60000").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["degraded"], false);
+
+    server.delete_request_async("/indexes/shoes/settings/search-cutoff-ms").await;
+
+    let (response, status_code) = server.search("q=shoe").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["degraded"], false);
+}
+
+#[actix_rt::test]
+async fn search_with_query_distinct_attribute() {
+    let mut server = common::Server::with_uid("shoes");
+
+    let body = json!({ "uid": "shoes", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "running shoe", "product": "runner", "size": 40 },
+        { "id": 2, "title": "running shoe", "product": "runner", "size": 41 },
+        { "id": 3, "title": "running shoe", "product": "runner", "size": 42 },
+        { "id": 4, "title": "walking shoe", "product": "walker", "size": 40 },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let hits_collapsed = |response: &Value| -> Vec<(u64, u64)> {
+        let mut hits: Vec<_> = response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| (hit["id"].as_u64().unwrap(), hit["_collapsed"].as_u64().unwrap()))
+            .collect();
+        hits.sort_unstable();
+        hits
+    };
+
+    let (response, status_code) = server.search("q=shoe").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 4);
+    assert!(response["hits"][0].get("_collapsed").is_none());
+
+    let (response, status_code) = server.search("q=shoe&distinct=product").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 2);
+    let hits = hits_collapsed(&response);
+    assert_eq!(hits.len(), 2);
+    assert_eq!(hits[1], (4, 0));
+    assert_eq!(hits[0].1, 2);
+
+    let (response, status_code) = server.search("q=shoe&distinct=product&distinctSize=2").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 3);
+    let hits = hits_collapsed(&response);
+    assert_eq!(hits.len(), 3);
+    assert_eq!(hits[2], (4, 0));
+    assert_eq!(hits[0].1, 1);
+    assert_eq!(hits[1].1, 1);
+
+    // the distinct attribute of the query replaces the one of the settings
+    server.update_distinct_attribute(json!("size")).await;
+
+    let (response, status_code) = server.search("q=shoe").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 3);
+
+    let (response, status_code) = server.search("q=shoe&distinct=product").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 2);
+
+    let (_response, status_code) = server.search("q=shoe&distinct=unknown").await;
+    assert_eq!(status_code, 400);
+    let (_response, status_code) = server.search("q=shoe&distinct=product&distinctSize=0").await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn search_on_multiple_indexes() {
+    let mut server = common::Server::with_uid("shoes");
+
+    for uid in &["shoes", "boots"] {
+        let body = json!({ "uid": uid, "primaryKey": "id" });
+        let (_response, status_code) = server.create_index(body).await;
+        assert_eq!(status_code, 201);
+    }
+
+    let body = json!([
+        { "id": 1, "title": "red running shoe" },
+        { "id": 2, "title": "blue shoe" },
+    ]);
+    server.post_request_async("/indexes/shoes/documents", body).await;
+
+    let body = json!([
+        { "id": 1, "title": "red boot" },
+        { "id": 2, "title": "red running boot" },
+    ]);
+    server.post_request_async("/indexes/boots/documents", body).await;
+
+    let hits = |response: &Value| -> Vec<(String, u64)> {
+        response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| (hit["_indexUid"].as_str().unwrap().to_string(), hit["id"].as_u64().unwrap()))
+            .collect()
+    };
+
+    // the documents matching both words come first, whatever their index
+    let (response, status_code) = server.search_multi_index("indexUids=shoes,boots&q=red%20running").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 2);
+    assert_eq!(hits(&response), vec![("shoes".to_string(), 1), ("boots".to_string(), 2)]);
+
+    let (response, status_code) = server.search_multi_index("indexUids=boots,shoes&q=red&offset=1&limit=1").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 3);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+
+    let (_response, status_code) = server.search_multi_index("indexUids=shoes,unknown&q=red").await;
+    assert_eq!(status_code, 404);
+}
+
+#[actix_rt::test]
+async fn test_facet_values_limit_and_order() {
+    let mut server = common::Server::with_uid("shoes");
+
+    let body = json!({ "uid": "shoes", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!({ "attributesForFaceting": ["color"] });
+    server.update_all_settings(body).await;
+
+    let body = json!([
+        { "id": 1, "title": "shoe", "color": "red" },
+        { "id": 2, "title": "shoe", "color": "blue" },
+        { "id": 3, "title": "shoe", "color": "blue" },
+        { "id": 4, "title": "shoe", "color": "green" },
+        { "id": 5, "title": "shoe", "color": "green" },
+        { "id": 6, "title": "shoe", "color": "green" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let color_values = |response: &Value| -> Vec<(String, u64)> {
+        response["facets"]["color"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(value, count)| (value.clone(), count.as_u64().unwrap()))
+            .collect()
+    };
+
+    // the values are sorted by count by default
+    let (response, status_code) = server.search("q=shoe&facets=%5B%22color%22%5D").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(
+        color_values(&response),
+        vec![("green".to_string(), 3), ("blue".to_string(), 2), ("red".to_string(), 1)]
+    );
+
+    let (response, status_code) = server.search("q=shoe&facets=%5B%22color%22%5D&maxValuesPerFacet=2").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(color_values(&response), vec![("green".to_string(), 3), ("blue".to_string(), 2)]);
+
+    let query = "q=shoe&facets=%5B%22color%22%5D&maxValuesPerFacet=2&sortFacetValuesBy=alpha";
+    let (response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(color_values(&response), vec![("blue".to_string(), 2), ("green".to_string(), 3)]);
+
+    let query = "q=shoe&facets=%5B%22color%22%5D&sortFacetValuesBy=random";
+    let (_response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn search_with_profile() {
+    let mut server = common::Server::test_server().await;
+
+    let (response, status_code) = server.search("q=exercitation").await;
+    assert_eq!(status_code, 200);
+    assert!(response.get("profile").is_none());
+
+    let (response, status_code) = server.search("q=exercitation&profile=true").await;
+    assert_eq!(status_code, 200);
+
+    let profile = &response["profile"];
+    let nb_hits = response["nbHits"].as_u64().unwrap();
+    assert!(profile["queryTreeMs"].is_f64());
+    assert!(profile["postingsFetchMs"].is_f64());
+    assert!(profile["formattingMs"].is_f64());
+    assert_eq!(profile["candidates"].as_u64().unwrap(), nb_hits);
+    assert_eq!(profile["facetFilteredCandidates"].as_u64().unwrap(), nb_hits);
+
+    let criteria = profile["criteria"].as_array().unwrap();
+    assert_eq!(criteria[0]["name"], "typo");
+    assert_eq!(criteria[0]["sortedDocuments"].as_u64().unwrap(), nb_hits);
+}
+
+#[actix_rt::test]
+async fn placeholder_search_orders_by_ranking_rules() {
+    let mut server = common::Server::with_uid("products");
+
+    let body = json!({ "uid": "products", "primaryKey": "id" });
+    server.create_index(body).await;
+
+    let body = json!({
+        "rankingRules": ["typo", "words", "desc(price)"],
+        "attributesForFaceting": ["color"],
+    });
+    server.update_all_settings(body).await;
+
+    let body = json!([
+        { "id": 1, "title": "shirt", "color": "red", "price": 10 },
+        { "id": 2, "title": "trousers", "color": "blue", "price": 30 },
+        { "id": 3, "title": "hat", "color": "red", "price": 20 },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let (response, status_code) = server.search("q=").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 3);
+    let ids: Vec<_> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(2), json!(3), json!(1)]);
+
+    // the query parameter can be omitted
+    let (response, status_code) = server.search("limit=2").await;
+    assert_eq!(status_code, 200);
+    let ids: Vec<_> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(2), json!(3)]);
+
+    // facetFilters=["color:red"]
+    let (response, status_code) = server.search("q=&facetFilters=%5B%22color%3Ared%22%5D").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["nbHits"], 2);
+    let ids: Vec<_> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(3), json!(1)]);
+
+    // filters=price < 25
+    let (response, status_code) = server.search("q=&filters=price%20%3C%2025").await;
+    assert_eq!(status_code, 200);
+    let ids: Vec<_> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(3), json!(1)]);
+
+    // the deleted documents are not returned
+    server.delete_document(2).await;
+    let (response, status_code) = server.search("q=").await;
+    assert_eq!(status_code, 200);
+    let ids: Vec<_> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(3), json!(1)]);
+}
+
+#[actix_rt::test]
+async fn search_with_ranking_score_details() {
+    let mut server = common::Server::with_uid("shoes");
+
+    let body = json!({ "uid": "shoes", "primaryKey": "id" });
+    server.create_index(body).await;
+
+    let body = json!(["typo", "words", "desc(price)"]);
+    server.update_ranking_rules(body).await;
+
+    let body = json!([
+        { "id": 1, "title": "running shoe", "price": 10 },
+        { "id": 2, "title": "running shoe", "price": 20 },
+        { "id": 3, "title": "shoe", "price": 30 },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let (response, status_code) = server.search("q=running%20shoe").await;
+    assert_eq!(status_code, 200);
+    assert!(response["hits"][0].get("_rankingScoreDetails").is_none());
+
+    let (response, status_code) = server.search("q=running%20shoe&showRankingScoreDetails=true").await;
+    assert_eq!(status_code, 200);
+
+    let hits = response["hits"].as_array().unwrap();
+    let ids: Vec<_> = hits.iter().map(|hit| hit["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(2), json!(1)]);
+
+    let details = hits[0]["_rankingScoreDetails"].as_array().unwrap();
+    assert_eq!(details[0], json!({ "name": "typo", "value": 0 }));
+    assert_eq!(details[1], json!({ "name": "words", "value": 2 }));
+    assert_eq!(details[2], json!({ "name": "sort by attribute", "value": 20 }));
+    assert_eq!(details[3]["name"], "stable document id");
+
+    let details = hits[1]["_rankingScoreDetails"].as_array().unwrap();
+    assert_eq!(details[2], json!({ "name": "sort by attribute", "value": 10 }));
+}
+
+#[actix_rt::test]
+async fn search_with_geo_radius_and_geo_ranking_rule() {
+    let mut server = common::Server::with_uid("restaurants");
+
+    let body = json!({ "uid": "restaurants", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!(["geo:asc", "typo", "words", "proximity", "attribute", "wordsPosition", "exactness"]);
+    server.update_ranking_rules(body).await;
+
+    let body = json!([
+        { "id": 1, "name": "restaurant in Lyon", "_geo": { "lat": 45.7640, "lng": 4.8357 } },
+        { "id": 2, "name": "restaurant in Paris", "_geo": { "lat": 48.8566, "lng": 2.3522 } },
+        { "id": 3, "name": "restaurant in Versailles", "_geo": { "lat": 48.8049, "lng": 2.1204 } },
+        { "id": 4, "name": "restaurant somewhere" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let hits_ids = |response: &Value| -> Vec<u64> {
+        response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["id"].as_u64().unwrap())
+            .collect()
+    };
+
+    // around the Eiffel Tower
+    let (response, status_code) = server.search("q=restaurant&aroundLatLng=48.8584,2.2945").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![2, 3, 1, 4]);
+    assert_eq!(response["hits"][0]["_geo"], json!({ "lat": 48.8566, "lng": 2.3522 }));
+
+    // around Brest, documents at most 20km away from the Eiffel Tower
+    let query = "q=restaurant&aroundLatLng=48.39,-4.49&filters=_geoRadius(48.8584%2C%202.2945%2C%2020000)";
+    let (response, status_code) = server.search(query).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![3, 2]);
+
+    let (_response, status_code) = server.search("q=restaurant&aroundLatLng=48.8584").await;
+    assert_eq!(status_code, 400);
+
+    let (_response, status_code) = server.search("q=restaurant&filters=_geoRadius(98%2C%202.2945%2C%2020000)").await;
+    assert_eq!(status_code, 400);
+
+    // rankingRules=["geo:asc"] without a point to sort around
+    let (_response, status_code) = server.search("q=restaurant&rankingRules=%5B%22geo:asc%22%5D").await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn search_with_sort() {
+    let mut server = common::Server::with_uid("shoes");
+
+    let body = json!({ "uid": "shoes", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!({ "sortableAttributes": ["price", "brand"] });
+    server.update_all_settings(body).await;
+
+    let body = json!([
+        { "id": 1, "name": "running shoe", "brand": "Zephyr", "price": 80 },
+        { "id": 2, "name": "walking shoe", "brand": "adidas", "price": 120 },
+        { "id": 3, "name": "hiking shoe", "brand": "Merrell" },
+        { "id": 4, "name": "trail shoe", "brand": "Asics", "price": 95.5 },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let hits_ids = |response: &Value| -> Vec<u64> {
+        response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["id"].as_u64().unwrap())
+            .collect()
+    };
+
+    // sort=["price:desc"], the documents without a price come last
+    let (response, status_code) = server.search("q=shoe&sort=%5B%22price:desc%22%5D").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![2, 4, 1, 3]);
+
+    // sort=["brand:asc"], strings are compared case insensitively
+    let (response, status_code) = server.search("q=shoe&sort=%5B%22brand:asc%22%5D").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![2, 4, 3, 1]);
+
+    let (response, status_code) = server.get_all_settings().await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["sortableAttributes"], json!(["price", "brand"]));
+
+    // sort=["name:asc"], name is not sortable
+    let (_response, status_code) = server.search("q=shoe&sort=%5B%22name:asc%22%5D").await;
+    assert_eq!(status_code, 400);
+
+    // sort=["price:up"]
+    let (_response, status_code) = server.search("q=shoe&sort=%5B%22price:up%22%5D").await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn search_with_typo_tolerance() {
+    let mut server = common::Server::with_uid("parts");
+
+    let body = json!({ "uid": "parts", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "zorbatex gasket", "sku": "kombilux" },
+        { "id": 2, "title": "kombilax valve", "sku": "zorbatex" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let hits_ids = |response: &Value| -> Vec<u64> {
+        let mut ids: Vec<_> = response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["id"].as_u64().unwrap())
+            .collect();
+        ids.sort_unstable();
+        ids
+    };
+
+    let (response, _status_code) = server.search("q=zorbatix").await;
+    assert_eq!(hits_ids(&response), vec![1, 2]);
+
+    server.update_typo_tolerance(json!({ "disableOnAttributes": ["sku"] })).await;
+    let (response, _status_code) = server.search("q=zorbatix").await;
+    assert_eq!(hits_ids(&response), vec![1]);
+    let (response, _status_code) = server.search("q=zorbatex").await;
+    assert_eq!(hits_ids(&response), vec![1, 2]);
+
+    server.update_typo_tolerance(json!({ "disableOnWords": ["Zorbatix"] })).await;
+    let (response, _status_code) = server.search("q=zorbatix").await;
+    assert_eq!(hits_ids(&response), Vec::<u64>::new());
+
+    // the exact words are not matched as prefixes either
+    let (response, _status_code) = server.search("q=zorba").await;
+    assert_eq!(hits_ids(&response), vec![1, 2]);
+    server.update_typo_tolerance(json!({ "disableOnWords": ["Zorba"] })).await;
+    let (response, _status_code) = server.search("q=zorba").await;
+    assert_eq!(hits_ids(&response), Vec::<u64>::new());
+
+    server.update_typo_tolerance(json!({ "minWordSizeForOneTypo": 9, "minWordSizeForTwoTypos": 12 })).await;
+    let (response, _status_code) = server.search("q=zorbatix").await;
+    assert_eq!(hits_ids(&response), Vec::<u64>::new());
+
+    server.update_typo_tolerance(json!({ "enabled": false })).await;
+    let (response, _status_code) = server.search("q=kombilox").await;
+    assert_eq!(hits_ids(&response), Vec::<u64>::new());
+
+    let (response, status_code) = server.get_typo_tolerance().await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["enabled"], json!(false));
+
+    server.delete_typo_tolerance().await;
+    let (response, _status_code) = server.search("q=kombilox").await;
+    assert_eq!(hits_ids(&response), vec![1, 2]);
+
+    let body = json!({ "minWordSizeForOneTypo": 10, "minWordSizeForTwoTypos": 9 });
+    let (_response, status_code) = server.update_typo_tolerance_sync(body).await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn search_with_prefix_search() {
+    let mut server = common::Server::with_uid("parts");
+
+    let body = json!({ "uid": "parts", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "zorbatex gasket" },
+        { "id": 2, "title": "kombilax valve" },
+        { "id": 3, "title": "zo ring" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let hits_ids = |response: &Value| -> Vec<u64> {
+        let mut ids: Vec<_> = response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["id"].as_u64().unwrap())
+            .collect();
+        ids.sort_unstable();
+        ids
+    };
+
+    let (response, _status_code) = server.search("q=zo").await;
+    assert_eq!(hits_ids(&response), vec![1, 3]);
+
+    // the prefixes that are not cached are searched in the words
+    server.update_prefix_search(json!({ "minPrefixFrequency": 5 })).await;
+    let (response, _status_code) = server.search("q=zo").await;
+    assert_eq!(hits_ids(&response), vec![1, 3]);
+
+    server.update_prefix_search(json!({ "maxPrefixLength": 0 })).await;
+    let (response, _status_code) = server.search("q=z").await;
+    assert_eq!(hits_ids(&response), vec![1, 3]);
+
+    server.update_prefix_search(json!({ "enabled": false })).await;
+    let (response, _status_code) = server.search("q=zorba").await;
+    assert_eq!(hits_ids(&response), Vec::<u64>::new());
+    let (response, _status_code) = server.search("q=zo").await;
+    assert_eq!(hits_ids(&response), vec![3]);
+
+    let (response, status_code) = server.get_prefix_search().await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!({ "enabled": false, "maxPrefixLength": 2, "minPrefixFrequency": 0 }));
+
+    server.delete_prefix_search().await;
+    let (response, _status_code) = server.search("q=zorba").await;
+    assert_eq!(hits_ids(&response), vec![1]);
+
+    let (_response, status_code) = server.update_prefix_search_sync(json!({ "maxPrefixLength": 5 })).await;
+    assert_eq!(status_code, 400);
+}
+
+#[actix_rt::test]
+async fn search_with_split_and_concatenated_words() {
+    let mut server = common::Server::with_uid("places");
+
+    let body = json!({ "uid": "places", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "new york pizza" },
+        { "id": 2, "title": "newyork bagels" },
+        { "id": 3, "title": "database tuning" },
+        { "id": 4, "title": "data base design" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let hits_ids = |response: &Value| -> Vec<u64> {
+        response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["id"].as_u64().unwrap())
+            .collect()
+    };
+
+    // the derived words are ranked after the words as they were typed
+    let (response, status_code) = server.search("q=newyork").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![2, 1]);
+
+    let (response, status_code) = server.search("q=data%20base").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![4, 3]);
+}
+
+#[actix_rt::test]
+async fn search_with_quoted_phrase() {
+    let mut server = common::Server::with_uid("books");
+
+    let body = json!({ "uid": "books", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "The Lord of the Rings" },
+        { "id": 2, "title": "Rings of the Lord" },
+        { "id": 3, "title": "Lord Rings collection" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let hits_ids = |response: &Value| -> Vec<u64> {
+        let mut ids: Vec<_> = response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["id"].as_u64().unwrap())
+            .collect();
+        ids.sort_unstable();
+        ids
+    };
+
+    let (response, status_code) = server.search("q=lord%20rings").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(hits_ids(&response), vec![1, 2, 3]);
+
+    let (response, _status_code) = server.search("q=%22lord%20of%20the%20rings%22").await;
+    assert_eq!(hits_ids(&response), vec![1]);
+
+    let (response, _status_code) = server.search("q=%22lord%20rings%22").await;
+    assert_eq!(hits_ids(&response), vec![3]);
+
+    // an unclosed quote ends with the query
+    let (response, _status_code) = server.search("q=collection%20%22lord%20rings").await;
+    assert_eq!(hits_ids(&response), vec![3]);
+
+    // the stop words are not indexed but still separate the words of a phrase
+    server.update_stop_words(json!(["of", "the"])).await;
+
+    let (response, _status_code) = server.search("q=%22lord%20of%20the%20rings%22").await;
+    assert_eq!(hits_ids(&response), vec![1]);
+
+    let (response, _status_code) = server.search("q=%22lord%20rings%22").await;
+    assert_eq!(hits_ids(&response), vec![3]);
+}
+
+// Search with matches expressed in bytes
+// q: Captain
+// limit: 1
+// attributesToRetrieve: [overview]
+// matches: true
+// matchesUnit: bytes
+#[actix_rt::test]
+async fn search_with_matches_in_bytes() {
+    let mut server = common::Server::with_uid("movies");
+    server.populate_movies().await;
+
+    let query = "q=captain&limit=1&attributesToRetrieve=overview&matches=true&matchesUnit=bytes";
+
+    let (response, _status_code) = server.search(query).await;
+    let hit = &response["hits"][0];
+
+    // the overview contains a multi-bytes apostrophe before the match
+    let expected = json!([{ "start": 188, "length": 7 }]);
+    assert_json_eq!(expected, hit["_matchesInfo"]["overview"].clone(), ordered: false);
+
+    let overview = hit["overview"].as_str().unwrap();
+    assert_eq!(&overview[188..195], "Captain");
+}
+
+#[actix_rt::test]
+async fn spelling_suggestions() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "Carol Carpenter" },
+        { "id": 2, "title": "Carol" },
+        { "id": 3, "title": "Coral Reef" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let (response, status_code) = server.get_request("/indexes/movies/suggest?q=carl&maxDistance=1").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!([{ "word": "carol", "distance": 1, "frequency": 2 }]));
+
+    // the closest words come first, then the most frequent ones
+    let (response, status_code) = server.get_request("/indexes/movies/suggest?q=corol").await;
+    assert_eq!(status_code, 200);
+    let words: Vec<_> = response.as_array().unwrap().iter().map(|s| s["word"].clone()).collect();
+    assert_eq!(words[..2], [json!("carol"), json!("coral")]);
+
+    let (_response, status_code) = server.get_request("/indexes/movies/suggest?q=carl&maxDistance=3").await;
+    assert_eq!(status_code, 400);
+
+    let (response, _status_code) = server.search("q=corol%20rif&suggestQuery=true").await;
+    assert_eq!(response["suggestedQuery"], json!("carol reef"));
+
+    // the last term is searched as a prefix, it is not misspelled
+    let (response, _status_code) = server.search("q=carol%20carp&suggestQuery=true").await;
+    assert!(response.get("suggestedQuery").is_none());
+
+    let (response, _status_code) = server.search("q=corol%20rif").await;
+    assert!(response.get("suggestedQuery").is_none());
+}
+
+#[actix_rt::test]
+async fn attribute_weights_at_query_time() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "shoe", "overview": "a red one" },
+        { "id": 2, "title": "a red one", "overview": "shoe" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let (response, status_code) = server.search("q=shoe&attributeWeights=title:3").await;
+    assert_eq!(status_code, 200);
+    let ids: Vec<_> = response["hits"].as_array().unwrap().iter().map(|h| h["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(1), json!(2)]);
+
+    let (response, status_code) = server.search("q=shoe&attributeWeights=title:1,overview:3").await;
+    assert_eq!(status_code, 200);
+    let ids: Vec<_> = response["hits"].as_array().unwrap().iter().map(|h| h["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(2), json!(1)]);
+
+    let (_response, status_code) = server.search("q=shoe&attributeWeights=title:-1").await;
+    assert_eq!(status_code, 400);
+
+    let (_response, status_code) = server.search("q=shoe&attributeWeights=title").await;
+    assert_eq!(status_code, 400);
+}
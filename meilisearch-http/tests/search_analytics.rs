@@ -0,0 +1,64 @@
+use serde_json::json;
+
+mod common;
+
+#[actix_rt::test]
+async fn top_and_zero_result_queries() {
+    let mut server = common::Server::with_options("movies", |opt| opt.search_analytics_capacity = Some(4));
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "Carol" },
+        { "id": 2, "title": "Coral Reef" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    server.search("q=carol").await;
+    server.search("q=Carol").await;
+    server.search("q=reef").await;
+    server.search("q=unicorn").await;
+
+    let (response, status_code) = server.get_request("/indexes/movies/analytics/top-queries").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response[0]["query"], json!("carol"));
+    assert_eq!(response[0]["count"], json!(2));
+    assert_eq!(response.as_array().unwrap().len(), 3);
+
+    let (response, status_code) = server.get_request("/indexes/movies/analytics/zero-result-queries").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!([{
+        "query": "unicorn",
+        "count": 1,
+        "averageNbHits": 0.0,
+        "averageProcessingTimeMs": response[0]["averageProcessingTimeMs"],
+        "lastSearchedAt": response[0]["lastSearchedAt"],
+    }]));
+
+    // the oldest searches are forgotten once the capacity is reached
+    server.search("q=unicorn").await;
+    server.search("q=unicorn").await;
+
+    let (response, _status_code) = server.get_request("/indexes/movies/analytics/top-queries?limit=1").await;
+    assert_eq!(response.as_array().unwrap().len(), 1);
+    assert_eq!(response[0]["query"], json!("unicorn"));
+    assert_eq!(response[0]["count"], json!(3));
+
+    let (response, _status_code) = server.get_request("/indexes/movies/analytics/top-queries?since=2200-01-01T00:00:00Z").await;
+    assert_eq!(response, json!([]));
+}
+
+#[actix_rt::test]
+async fn searches_are_not_recorded_by_default() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    server.create_index(body).await;
+    server.search("q=carol").await;
+
+    let (response, status_code) = server.get_request("/indexes/movies/analytics/top-queries").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!([]));
+}
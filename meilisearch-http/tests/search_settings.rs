@@ -677,3 +677,216 @@ async fn search_with_settings_searchable_attributes_2() {
     let (response, _status_code) = server.search(query).await;
     assert_json_eq!(expect, response["hits"].clone(), ordered: false);
 }
+
+#[actix_rt::test]
+async fn search_with_settings_localized_synonyms() {
+    let mut server = common::Server::with_uid("cars");
+
+    let body = json!({ "uid": "cars", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "red car" },
+        { "id": 2, "title": "voiture rouge" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let body = json!({ "voiture": ["car"] });
+    server.post_request_async("/indexes/cars/settings/synonyms/fr", body).await;
+
+    let (response, status_code) = server.get_request("/indexes/cars/settings/synonyms/fr").await;
+    assert_eq!(status_code, 200);
+    assert_json_eq!(json!({ "voiture": ["car"] }), response, ordered: false);
+
+    let hits_ids = |response: &serde_json::Value| -> Vec<u64> {
+        let mut ids: Vec<_> = response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["id"].as_u64().unwrap())
+            .collect();
+        ids.sort_unstable();
+        ids
+    };
+
+    // the synonyms of a language are only used for queries in this language
+    let (response, _status_code) = server.search("q=voiture").await;
+    assert_eq!(hits_ids(&response), vec![2]);
+
+    let (response, _status_code) = server.search("q=voiture&language=fr").await;
+    assert_eq!(hits_ids(&response), vec![1, 2]);
+
+    let (response, _status_code) = server.search("q=voiture&language=en").await;
+    assert_eq!(hits_ids(&response), vec![2]);
+
+    // the language of the index is used when the query doesn't specify one
+    server.post_request_async("/indexes/cars/settings/language", json!("fr")).await;
+
+    let (response, _status_code) = server.search("q=voiture").await;
+    assert_eq!(hits_ids(&response), vec![1, 2]);
+
+    let (response, _status_code) = server.search("q=voiture&language=en").await;
+    assert_eq!(hits_ids(&response), vec![2]);
+
+    server.delete_request_async("/indexes/cars/settings/synonyms/fr").await;
+
+    let (response, _status_code) = server.search("q=voiture").await;
+    assert_eq!(hits_ids(&response), vec![2]);
+
+    let (response, status_code) = server.search("q=voiture&language=f%20r").await;
+    assert_eq!(status_code, 400);
+    assert!(response["message"].as_str().is_some());
+}
+
+#[actix_rt::test]
+async fn search_with_settings_mutual_synonyms() {
+    let mut server = common::Server::with_uid("cities");
+
+    let body = json!({ "uid": "cities", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "NYC subway" },
+        { "id": 2, "title": "new york subway" },
+        { "id": 3, "title": "Paris subway" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let body = json!([["nyc", "new york"]]);
+    server.update_synonyms(body).await;
+
+    let (response, status_code) = server.get_synonyms().await;
+    assert_eq!(status_code, 200);
+    let expected = json!({ "new york": ["nyc"], "nyc": ["new york"] });
+    assert_json_eq!(expected, response, ordered: false);
+
+    let hits_ids = |response: &serde_json::Value| -> Vec<u64> {
+        let mut ids: Vec<_> = response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["id"].as_u64().unwrap())
+            .collect();
+        ids.sort_unstable();
+        ids
+    };
+
+    let (response, _status_code) = server.search("q=nyc").await;
+    assert_eq!(hits_ids(&response), vec![1, 2]);
+
+    let (response, _status_code) = server.search("q=new%20york").await;
+    assert_eq!(hits_ids(&response), vec![1, 2]);
+}
+
+#[actix_rt::test]
+async fn search_with_settings_fold_diacritics() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "Déjà vu" },
+        { "id": 2, "title": "Deja vu" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let hits_ids = |response: &serde_json::Value| -> Vec<u64> {
+        let mut ids: Vec<_> = response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["id"].as_u64().unwrap())
+            .collect();
+        ids.sort_unstable();
+        ids
+    };
+
+    let (response, status_code) = server.get_request("/indexes/movies/settings/fold-diacritics").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!(true));
+
+    // the words match whatever their diacritics
+    let (response, _status_code) = server.search("q=d%C3%A9j%C3%A0").await;
+    assert_eq!(hits_ids(&response), vec![1, 2]);
+
+    let (response, _status_code) = server.search("q=deja").await;
+    assert_eq!(hits_ids(&response), vec![1, 2]);
+
+    server.post_request_async("/indexes/movies/settings/fold-diacritics", json!(false)).await;
+
+    let (response, _status_code) = server.search("q=d%C3%A9j%C3%A0").await;
+    assert_eq!(hits_ids(&response), vec![1]);
+
+    let (response, _status_code) = server.search("q=deja").await;
+    assert_eq!(hits_ids(&response), vec![2]);
+
+    server.delete_request_async("/indexes/movies/settings/fold-diacritics").await;
+
+    let (response, _status_code) = server.search("q=deja").await;
+    assert_eq!(hits_ids(&response), vec![1, 2]);
+}
+
+#[actix_rt::test]
+async fn search_with_settings_token_policy() {
+    let mut server = common::Server::with_uid("products");
+
+    let body = json!({ "uid": "products", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "ISO-9001 certified" },
+        { "id": 2, "title": "A guide to ISO 9001" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let hits_ids = |response: &serde_json::Value| -> Vec<u64> {
+        let mut ids: Vec<_> = response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["id"].as_u64().unwrap())
+            .collect();
+        ids.sort_unstable();
+        ids
+    };
+
+    let (response, status_code) = server.get_request("/indexes/products/settings/token-policy").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!({ "numbers": "split", "identifiers": "split" }));
+
+    // the compounds are split like the rest of the text by default
+    let (response, _status_code) = server.search("q=ISO-9001").await;
+    assert_eq!(hits_ids(&response), vec![1, 2]);
+
+    let body = json!({ "identifiers": "keep" });
+    server.post_request_async("/indexes/products/settings/token-policy", body).await;
+
+    let (response, _status_code) = server.search("q=ISO-9001").await;
+    assert_eq!(hits_ids(&response), vec![1]);
+
+    let (response, _status_code) = server.search("q=9001").await;
+    assert_eq!(hits_ids(&response), vec![2]);
+
+    let body = json!({ "identifiers": "keepAndSplit" });
+    server.post_request_async("/indexes/products/settings/token-policy", body).await;
+
+    let (response, _status_code) = server.search("q=ISO-9001").await;
+    assert_eq!(hits_ids(&response), vec![1, 2]);
+
+    let (response, _status_code) = server.search("q=9001").await;
+    assert_eq!(hits_ids(&response), vec![1, 2]);
+
+    let (response, _status_code) = server.search("q=%22ISO-9001%20certified%22").await;
+    assert_eq!(hits_ids(&response), vec![1]);
+
+    server.delete_request_async("/indexes/products/settings/token-policy").await;
+
+    let (response, status_code) = server.get_all_settings().await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["tokenPolicy"], json!({ "numbers": "split", "identifiers": "split" }));
+}
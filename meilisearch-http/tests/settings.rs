@@ -49,6 +49,28 @@ async fn write_all_and_delete() {
         },
         "attributesForFaceting": ["title"],
         "acceptNewFields": false,
+        "documentIdFormat": "strict",
+        "maxTokenLength": 80,
+        "maxPositionsPerAttribute": 1000,
+        "maxTotalHits": 1000,
+        "searchCutoffMs": null,
+        "sortableAttributes": null,
+        "typoTolerance": {
+            "enabled": true,
+            "minWordSizeForOneTypo": 5,
+            "minWordSizeForTwoTypos": 9,
+            "disableOnWords": [],
+            "disableOnAttributes": [],
+        },
+        "prefixSearch": {
+            "enabled": true,
+            "maxPrefixLength": 2,
+            "minPrefixFrequency": 0,
+        },
+        "fieldTypes": {},
+        "fieldFlags": {},
+        "facetNormalization": { "lowercase": true, "trim": false, "stripDiacritics": false },
+        "tokenPolicy": { "numbers": "split", "identifiers": "split" },
     });
 
 
@@ -122,6 +144,28 @@ async fn write_all_and_delete() {
         "synonyms": {},
         "attributesForFaceting": null,
         "acceptNewFields": true,
+        "documentIdFormat": "strict",
+        "maxTokenLength": 80,
+        "maxPositionsPerAttribute": 1000,
+        "maxTotalHits": 1000,
+        "searchCutoffMs": null,
+        "sortableAttributes": null,
+        "typoTolerance": {
+            "enabled": true,
+            "minWordSizeForOneTypo": 5,
+            "minWordSizeForTwoTypos": 9,
+            "disableOnWords": [],
+            "disableOnAttributes": [],
+        },
+        "prefixSearch": {
+            "enabled": true,
+            "maxPrefixLength": 2,
+            "minPrefixFrequency": 0,
+        },
+        "fieldTypes": {},
+        "fieldFlags": {},
+        "facetNormalization": { "lowercase": true, "trim": false, "stripDiacritics": false },
+        "tokenPolicy": { "numbers": "split", "identifiers": "split" },
     });
 
     assert_json_eq!(expect, response, ordered: false);
@@ -173,6 +217,28 @@ async fn write_all_and_update() {
         },
         "attributesForFaceting": ["title"],
         "acceptNewFields": false,
+        "documentIdFormat": "strict",
+        "maxTokenLength": 80,
+        "maxPositionsPerAttribute": 1000,
+        "maxTotalHits": 1000,
+        "searchCutoffMs": null,
+        "sortableAttributes": null,
+        "typoTolerance": {
+            "enabled": true,
+            "minWordSizeForOneTypo": 5,
+            "minWordSizeForTwoTypos": 9,
+            "disableOnWords": [],
+            "disableOnAttributes": [],
+        },
+        "prefixSearch": {
+            "enabled": true,
+            "maxPrefixLength": 2,
+            "minPrefixFrequency": 0,
+        },
+        "fieldTypes": {},
+        "fieldFlags": {},
+        "facetNormalization": { "lowercase": true, "trim": false, "stripDiacritics": false },
+        "tokenPolicy": { "numbers": "split", "identifiers": "split" },
     });
 
     server.update_all_settings(body.clone()).await;
@@ -215,6 +281,28 @@ async fn write_all_and_update() {
         },
         "attributesForFaceting": ["title"],
         "acceptNewFields": false,
+        "documentIdFormat": "strict",
+        "maxTokenLength": 80,
+        "maxPositionsPerAttribute": 1000,
+        "maxTotalHits": 1000,
+        "searchCutoffMs": null,
+        "sortableAttributes": null,
+        "typoTolerance": {
+            "enabled": true,
+            "minWordSizeForOneTypo": 5,
+            "minWordSizeForTwoTypos": 9,
+            "disableOnWords": [],
+            "disableOnAttributes": [],
+        },
+        "prefixSearch": {
+            "enabled": true,
+            "maxPrefixLength": 2,
+            "minPrefixFrequency": 0,
+        },
+        "fieldTypes": {},
+        "fieldFlags": {},
+        "facetNormalization": { "lowercase": true, "trim": false, "stripDiacritics": false },
+        "tokenPolicy": { "numbers": "split", "identifiers": "split" },
     });
 
     server.update_all_settings(body).await;
@@ -252,7 +340,29 @@ async fn write_all_and_update() {
             "logan": ["wolverine", "xmen"],
         },
         "attributesForFaceting": ["title"],
-        "acceptNewFields": false
+        "acceptNewFields": false,
+        "documentIdFormat": "strict",
+        "maxTokenLength": 80,
+        "maxPositionsPerAttribute": 1000,
+        "maxTotalHits": 1000,
+        "searchCutoffMs": null,
+        "sortableAttributes": null,
+        "typoTolerance": {
+            "enabled": true,
+            "minWordSizeForOneTypo": 5,
+            "minWordSizeForTwoTypos": 9,
+            "disableOnWords": [],
+            "disableOnAttributes": [],
+        },
+        "prefixSearch": {
+            "enabled": true,
+            "maxPrefixLength": 2,
+            "minPrefixFrequency": 0,
+        },
+        "fieldTypes": {},
+        "fieldFlags": {},
+        "facetNormalization": { "lowercase": true, "trim": false, "stripDiacritics": false },
+        "tokenPolicy": { "numbers": "split", "identifiers": "split" },
     });
 
     assert_json_eq!(expected, response, ordered: false);
@@ -284,6 +394,28 @@ async fn test_default_settings() {
         "synonyms": {},
         "attributesForFaceting": null,
         "acceptNewFields": true,
+        "documentIdFormat": "strict",
+        "maxTokenLength": 80,
+        "maxPositionsPerAttribute": 1000,
+        "maxTotalHits": 1000,
+        "searchCutoffMs": null,
+        "sortableAttributes": null,
+        "typoTolerance": {
+            "enabled": true,
+            "minWordSizeForOneTypo": 5,
+            "minWordSizeForTwoTypos": 9,
+            "disableOnWords": [],
+            "disableOnAttributes": [],
+        },
+        "prefixSearch": {
+            "enabled": true,
+            "maxPrefixLength": 2,
+            "minPrefixFrequency": 0,
+        },
+        "fieldTypes": {},
+        "fieldFlags": {},
+        "facetNormalization": { "lowercase": true, "trim": false, "stripDiacritics": false },
+        "tokenPolicy": { "numbers": "split", "identifiers": "split" },
     });
 
     let (response, _status_code) = server.get_all_settings().await;
@@ -322,6 +454,28 @@ async fn test_default_settings_2() {
         "synonyms": {},
         "attributesForFaceting": null,
         "acceptNewFields": true,
+        "documentIdFormat": "strict",
+        "maxTokenLength": 80,
+        "maxPositionsPerAttribute": 1000,
+        "maxTotalHits": 1000,
+        "searchCutoffMs": null,
+        "sortableAttributes": null,
+        "typoTolerance": {
+            "enabled": true,
+            "minWordSizeForOneTypo": 5,
+            "minWordSizeForTwoTypos": 9,
+            "disableOnWords": [],
+            "disableOnAttributes": [],
+        },
+        "prefixSearch": {
+            "enabled": true,
+            "maxPrefixLength": 2,
+            "minPrefixFrequency": 0,
+        },
+        "fieldTypes": {},
+        "fieldFlags": {},
+        "facetNormalization": { "lowercase": true, "trim": false, "stripDiacritics": false },
+        "tokenPolicy": { "numbers": "split", "identifiers": "split" },
     });
 
     let (response, _status_code) = server.get_all_settings().await;
@@ -385,6 +539,28 @@ async fn write_setting_and_update_partial() {
             "logan": ["wolverine"],
         },
         "acceptNewFields": false,
+        "documentIdFormat": "strict",
+        "maxTokenLength": 80,
+        "maxPositionsPerAttribute": 1000,
+        "maxTotalHits": 1000,
+        "searchCutoffMs": null,
+        "sortableAttributes": null,
+        "typoTolerance": {
+            "enabled": true,
+            "minWordSizeForOneTypo": 5,
+            "minWordSizeForTwoTypos": 9,
+            "disableOnWords": [],
+            "disableOnAttributes": [],
+        },
+        "prefixSearch": {
+            "enabled": true,
+            "maxPrefixLength": 2,
+            "minPrefixFrequency": 0,
+        },
+        "fieldTypes": {},
+        "fieldFlags": {},
+        "facetNormalization": { "lowercase": true, "trim": false, "stripDiacritics": false },
+        "tokenPolicy": { "numbers": "split", "identifiers": "split" },
     });
 
     server.update_all_settings(body.clone()).await;
@@ -430,9 +606,366 @@ async fn write_setting_and_update_partial() {
         },
         "attributesForFaceting": null,
         "acceptNewFields": false,
+        "documentIdFormat": "strict",
+        "maxTokenLength": 80,
+        "maxPositionsPerAttribute": 1000,
+        "maxTotalHits": 1000,
+        "searchCutoffMs": null,
+        "sortableAttributes": null,
+        "typoTolerance": {
+            "enabled": true,
+            "minWordSizeForOneTypo": 5,
+            "minWordSizeForTwoTypos": 9,
+            "disableOnWords": [],
+            "disableOnAttributes": [],
+        },
+        "prefixSearch": {
+            "enabled": true,
+            "maxPrefixLength": 2,
+            "minPrefixFrequency": 0,
+        },
+        "fieldTypes": {},
+        "fieldFlags": {},
+        "facetNormalization": { "lowercase": true, "trim": false, "stripDiacritics": false },
+        "tokenPolicy": { "numbers": "split", "identifiers": "split" },
     });
 
     let (response, _status_code) = server.get_all_settings().await;
 
     assert_json_eq!(expected, response, ordered: false);
 }
+
+#[actix_rt::test]
+async fn unchanged_settings_are_not_enqueued() {
+    let mut server = common::Server::test_server().await;
+
+    let body = json!(["le", "la", "les"]);
+    let (_response, status_code) = server.post_request_async("/indexes/test/settings/stop-words", body.clone()).await;
+    assert_eq!(status_code, 202);
+
+    // the very same stop words are directly marked as processed
+    let (response, status_code) = server.post_request("/indexes/test/settings/stop-words", body).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+
+    let (response, status_code) = server.get_update_status(update_id).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["status"], "processed");
+    assert_eq!(response["duration"], 0.0);
+
+    // a different list of stop words is enqueued as usual
+    let body = json!(["le", "la"]);
+    let (response, status_code) = server.post_request("/indexes/test/settings/stop-words", body).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    let (response, _status_code) = server.get_request("/indexes/test/settings/stop-words").await;
+    assert_eq!(response, json!(["la", "le"]));
+}
+
+#[actix_rt::test]
+async fn settings_diff_in_update_result() {
+    let mut server = common::Server::test_server().await;
+
+    let body = json!({
+        "attributesForFaceting": ["color", "gender"],
+        "stopWords": ["the"],
+    });
+    let (response, _status_code) = server.post_request_async("/indexes/test/settings", body).await;
+    let update_id = response["updateId"].as_u64().unwrap();
+
+    let (response, status_code) = server.get_update_status(update_id).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["status"], "processed");
+
+    let diff = &response["settingsDiff"];
+    assert_eq!(diff["rankingRulesChanged"], false);
+    assert_eq!(diff["stopWordsAdded"], json!(["the"]));
+    assert_eq!(diff["stopWordsRemoved"], json!([]));
+    assert_eq!(diff["reindexed"], false);
+    assert_eq!(diff["facetsReindexed"], true);
+    assert!(diff["facetingAdded"].as_array().unwrap().contains(&json!("gender")));
+
+    let body = json!({ "attributesForFaceting": null });
+    let (response, _status_code) = server.post_request_async("/indexes/test/settings", body).await;
+    let update_id = response["updateId"].as_u64().unwrap();
+
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    let diff = &response["settingsDiff"];
+    assert_eq!(diff["facetingAdded"], json!([]));
+    assert_eq!(diff["facetingRemoved"], json!(["color", "gender"]));
+    assert_eq!(diff["reindexed"], false);
+    assert_eq!(diff["facetsReindexed"], true);
+}
+
+#[actix_rt::test]
+async fn indexing_limits_truncate_documents() {
+    let mut server = common::Server::with_uid("test");
+
+    let body = json!({ "uid": "test", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let (response, _status_code) = server.get_request("/indexes/test/settings/max-token-length").await;
+    assert_eq!(response, json!(80));
+    let (response, _status_code) = server.get_request("/indexes/test/settings/max-positions-per-attribute").await;
+    assert_eq!(response, json!(1000));
+
+    let (_response, status_code) = server.post_request("/indexes/test/settings/max-token-length", json!(0)).await;
+    assert_eq!(status_code, 400);
+    let (_response, status_code) = server.post_request("/indexes/test/settings/max-token-length", json!(512)).await;
+    assert_eq!(status_code, 400);
+    let (_response, status_code) = server.post_request("/indexes/test/settings/max-positions-per-attribute", json!(0)).await;
+    assert_eq!(status_code, 400);
+
+    server.post_request_async("/indexes/test/settings/max-token-length", json!(5)).await;
+    server.post_request_async("/indexes/test/settings/max-positions-per-attribute", json!(2)).await;
+
+    let body = json!([{ "id": 1, "title": "abracadabra magic", "description": "one two three" }]);
+    let (response, _status_code) = server.post_request_async("/indexes/test/documents", body).await;
+    let update_id = response["updateId"].as_u64().unwrap();
+
+    let (response, status_code) = server.get_update_status(update_id).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["status"], "processed");
+    assert_eq!(response["truncation"], json!({ "truncatedTokens": 1, "truncatedAttributes": 1 }));
+
+    // the truncated word is found from its indexed part, the words after the limit are not
+    let (response, _status_code) = server.search("q=abrac").await;
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+    let (response, _status_code) = server.search("q=three").await;
+    assert_eq!(response["hits"].as_array().unwrap().len(), 0);
+
+    // removing the limits reindexes the documents
+    let (response, _status_code) = server.delete_request_async("/indexes/test/settings/max-positions-per-attribute").await;
+    let update_id = response["updateId"].as_u64().unwrap();
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["settingsDiff"]["indexingLimitsChanged"], true);
+    assert_eq!(response["settingsDiff"]["reindexed"], true);
+    assert_eq!(response["truncation"], json!({ "truncatedTokens": 1, "truncatedAttributes": 0 }));
+
+    let (response, _status_code) = server.search("q=three").await;
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+}
+
+#[actix_rt::test]
+async fn unchanged_searchable_attributes_do_not_reindex() {
+    let mut server = common::Server::with_uid("test");
+
+    let body = json!({ "uid": "test", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([{ "id": 1, "title": "carol", "description": "a movie" }]);
+    server.post_request_async("/indexes/test/documents", body).await;
+
+    let body = json!({ "searchableAttributes": ["description", "title"] });
+    let (response, _status_code) = server.post_request_async("/indexes/test/settings", body).await;
+    let update_id = response["updateId"].as_u64().unwrap();
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["settingsDiff"]["searchableAttributesChanged"], true);
+    assert_eq!(response["settingsDiff"]["reindexed"], true);
+
+    let body = json!({
+        "searchableAttributes": ["description", "title"],
+        "displayedAttributes": ["title"],
+    });
+    let (response, _status_code) = server.post_request_async("/indexes/test/settings", body).await;
+    let update_id = response["updateId"].as_u64().unwrap();
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["settingsDiff"]["searchableAttributesChanged"], false);
+    assert_eq!(response["settingsDiff"]["reindexed"], false);
+}
+
+#[actix_rt::test]
+async fn settings_only_reindex_what_they_change() {
+    let mut server = common::Server::with_uid("test");
+
+    let body = json!({ "uid": "test", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "blue shoe", "price": 30 },
+        { "id": 2, "title": "red shoe", "price": 10 },
+    ]);
+    server.post_request_async("/indexes/test/documents", body).await;
+
+    // reordering the ranking rules doesn't change the ranked attributes
+    let body = json!({ "rankingRules": ["words", "typo", "proximity", "attribute", "wordsPosition", "exactness"] });
+    let (response, _status_code) = server.post_request_async("/indexes/test/settings", body).await;
+    let update_id = response["updateId"].as_u64().unwrap();
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["settingsDiff"]["rankingRulesChanged"], true);
+    assert_eq!(response["settingsDiff"]["reindexed"], false);
+
+    // the sortable values are rebuilt from the stored documents
+    let body = json!({ "sortableAttributes": ["price"] });
+    let (response, _status_code) = server.post_request_async("/indexes/test/settings", body).await;
+    let update_id = response["updateId"].as_u64().unwrap();
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["settingsDiff"]["reindexed"], false);
+    assert_eq!(response["settingsDiff"]["sortablesReindexed"], true);
+
+    // sort=["price:asc"]
+    let (response, status_code) = server.search("q=shoe&sort=%5B%22price:asc%22%5D").await;
+    assert_eq!(status_code, 200);
+    let ids: Vec<_> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(2), json!(1)]);
+
+    // a new ranked attribute must be read from all the documents
+    let body = json!({ "rankingRules": ["typo", "words", "desc(price)"] });
+    let (response, _status_code) = server.post_request_async("/indexes/test/settings", body).await;
+    let update_id = response["updateId"].as_u64().unwrap();
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["settingsDiff"]["rankedAttributesChanged"], true);
+    assert_eq!(response["settingsDiff"]["reindexed"], true);
+}
+
+#[actix_rt::test]
+async fn field_types_rank_and_filter_dates() {
+    let mut server = common::Server::with_uid("events");
+
+    let body = json!({ "uid": "events", "primaryKey": "id" });
+    server.create_index(body).await;
+
+    let body = json!({
+        "rankingRules": ["desc(date)", "typo", "words"],
+        "fieldTypes": { "date": "date" },
+    });
+    server.update_all_settings(body).await;
+
+    let (response, status_code) = server.get_request("/indexes/events/settings/field-types").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!({ "date": "date" }));
+
+    let body = json!([
+        { "id": 1, "title": "concert", "date": "2020-06-01" },
+        { "id": 2, "title": "concert", "date": "2021-01-15T20:00:00Z" },
+        { "id": 3, "title": "concert", "date": "2019-12-31" },
+        { "id": 4, "title": "concert", "date": "tomorrow" },
+    ]);
+    let (response, status_code) = server.post_request("/indexes/events/documents", body).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    // the date that can't be parsed is reported, the other documents are added
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["status"], "processed");
+    assert_eq!(response["failedDocuments"][0]["position"], 3);
+    assert_eq!(response["failedDocuments"][0]["documentId"], "4");
+
+    let (response, status_code) = server.search("q=concert").await;
+    assert_eq!(status_code, 200);
+    let ids: Vec<_> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(2), json!(1), json!(3)]);
+
+    // filters=date > "2020-01-01"
+    let (response, status_code) = server.search("q=concert&filters=date%20%3E%20%222020-01-01%22").await;
+    assert_eq!(status_code, 200);
+    let ids: Vec<_> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(2), json!(1)]);
+
+    let (response, _status_code) = server.delete_request_async("/indexes/events/settings/field-types").await;
+    let update_id = response["updateId"].as_u64().unwrap();
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["settingsDiff"]["fieldTypesChanged"], true);
+    assert_eq!(response["settingsDiff"]["reindexed"], true);
+
+    let (response, _status_code) = server.get_request("/indexes/events/settings/field-types").await;
+    assert_eq!(response, json!({}));
+}
+
+#[actix_rt::test]
+async fn field_flags_filterable_but_not_searchable() {
+    let mut server = common::Server::with_uid("items");
+
+    let body = json!({ "uid": "items", "primaryKey": "id" });
+    server.create_index(body).await;
+
+    let body = json!({
+        "fieldFlags": { "tag": { "searchable": false, "filterable": true } },
+    });
+    server.update_all_settings(body).await;
+
+    let body = json!([
+        { "id": 1, "title": "red shirt", "tag": "promo" },
+        { "id": 2, "title": "blue shirt", "tag": "regular" },
+        { "id": 3, "title": "promo shirt", "tag": "regular" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+
+    let (response, status_code) = server.get_request("/indexes/items/settings/field-flags").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["tag"]["searchable"], false);
+    assert_eq!(response["tag"]["filterable"], true);
+
+    let (response, _status_code) = server.get_all_settings().await;
+    assert!(!response["searchableAttributes"].as_array().unwrap().contains(&json!("tag")));
+    assert!(response["attributesForFaceting"].as_array().unwrap().contains(&json!("tag")));
+
+    // the tag is not searched
+    let (response, status_code) = server.search("q=promo").await;
+    assert_eq!(status_code, 200);
+    let ids: Vec<_> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(3)]);
+
+    // facetFilters=["tag:promo"]
+    let (response, status_code) = server.search("q=shirt&facetFilters=%5B%22tag%3Apromo%22%5D").await;
+    assert_eq!(status_code, 200);
+    let ids: Vec<_> = response["hits"].as_array().unwrap().iter().map(|hit| hit["id"].clone()).collect();
+    assert_eq!(ids, vec![json!(1)]);
+
+    server.delete_request_async("/indexes/items/settings/field-flags").await;
+
+    let (response, _status_code) = server.get_request("/indexes/items/settings/field-flags").await;
+    assert_eq!(response, json!({}));
+}
+
+#[actix_rt::test]
+async fn field_types_index_pre_tokenized_fields() {
+    let mut server = common::Server::with_uid("reports");
+
+    let body = json!({ "uid": "reports", "primaryKey": "id" });
+    server.create_index(body).await;
+
+    let body = json!({ "fieldTypes": { "analysis": "tokens" } });
+    server.update_all_settings(body).await;
+
+    // "acute myocardial infarction", stemmed by an external analyzer
+    let body = json!([
+        {
+            "id": 1,
+            "analysis": [
+                { "token": "acute", "position": 0, "start": 0, "end": 5 },
+                { "token": "myocardial", "position": 1, "start": 6, "end": 16 },
+                { "token": "infarct", "position": 2, "start": 17, "end": 27 },
+            ],
+        },
+        { "id": 2, "analysis": "acute infarction" },
+        { "id": 3, "analysis": [{ "token": "infarct", "position": 0, "start": 9, "end": 2 }] },
+    ]);
+    let (response, status_code) = server.post_request("/indexes/reports/documents", body).await;
+    assert_eq!(status_code, 202);
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+
+    // the documents that are not a list of valid tokens are reported
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["status"], "processed");
+    let failed: Vec<_> = response["failedDocuments"].as_array().unwrap().iter().map(|f| f["documentId"].clone()).collect();
+    assert_eq!(failed, vec![json!("2"), json!("3")]);
+
+    // the matches are the byte offsets given with the tokens
+    let (response, status_code) = server.search("q=infarct&matches=true").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["hits"].as_array().unwrap().len(), 1);
+    assert_eq!(response["hits"][0]["id"], 1);
+    assert_eq!(response["hits"][0]["_matchesInfo"]["analysis"], json!([{ "start": 17, "length": 10 }]));
+
+    // the query words are only normalized, the tokens are not stemmed again
+    let (response, _status_code) = server.search("q=infarction").await;
+    assert_eq!(response["hits"].as_array().unwrap().len(), 0);
+}
@@ -343,6 +343,28 @@ async fn accept_new_fields_does_not_take_into_account_the_primary_key() {
         "synonyms": {},
         "attributesForFaceting": null,
         "acceptNewFields": false,
+        "documentIdFormat": "strict",
+        "maxTokenLength": 80,
+        "maxPositionsPerAttribute": 1000,
+        "maxTotalHits": 1000,
+        "searchCutoffMs": null,
+        "sortableAttributes": null,
+        "typoTolerance": {
+            "enabled": true,
+            "minWordSizeForOneTypo": 5,
+            "minWordSizeForTwoTypos": 9,
+            "disableOnWords": [],
+            "disableOnAttributes": [],
+        },
+        "prefixSearch": {
+            "enabled": true,
+            "maxPrefixLength": 2,
+            "minPrefixFrequency": 0,
+        },
+        "fieldTypes": {},
+        "fieldFlags": {},
+        "facetNormalization": { "lowercase": true, "trim": false, "stripDiacritics": false },
+        "tokenPolicy": { "numbers": "split", "identifiers": "split" },
     });
 
     assert_json_eq!(response, expected, ordered: false);
@@ -61,3 +61,66 @@ async fn add_documents_and_stop_words() {
 
     // assert!(!response["hits"].as_array().unwrap().is_empty());
 }
+
+#[actix_rt::test]
+async fn search_stop_words_in_phrases() {
+    let mut server = common::Server::with_uid("movies");
+
+    let body = json!({ "uid": "movies", "primaryKey": "id" });
+    let (_response, status_code) = server.create_index(body).await;
+    assert_eq!(status_code, 201);
+
+    let body = json!([
+        { "id": 1, "title": "the who live" },
+        { "id": 2, "title": "who live there" },
+    ]);
+    server.add_or_replace_multiple_documents(body).await;
+    server.update_stop_words(json!(["the"])).await;
+
+    let hits_ids = |response: &serde_json::Value| -> Vec<u64> {
+        let mut ids: Vec<_> = response["hits"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["id"].as_u64().unwrap())
+            .collect();
+        ids.sort_unstable();
+        ids
+    };
+
+    // the stop words are ignored, even in the phrases where they only take a position
+    let (response, _status_code) = server.search("q=the%20who").await;
+    assert_eq!(hits_ids(&response), vec![1, 2]);
+    let (response, _status_code) = server.search("q=%22the%20who%22").await;
+    assert_eq!(hits_ids(&response), vec![1]);
+    let (response, _status_code) = server.search("q=%22the%22").await;
+    assert_eq!(hits_ids(&response), Vec::<u64>::new());
+
+    server.post_request_async("/indexes/movies/settings/stop-words-in-phrases", json!(true)).await;
+    let (response, status_code) = server.get_request("/indexes/movies/settings/stop-words-in-phrases").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!(true));
+
+    // the stop words are only searched in the phrases
+    let (response, _status_code) = server.search("q=the%20who").await;
+    assert_eq!(hits_ids(&response), vec![1, 2]);
+    let (response, _status_code) = server.search("q=%22the%20who%22").await;
+    assert_eq!(hits_ids(&response), vec![1]);
+    let (response, _status_code) = server.search("q=%22the%22").await;
+    assert_eq!(hits_ids(&response), vec![1]);
+
+    // the stop words are already indexed, changing them doesn't require a reindexing
+    let (response, _status_code) = server.post_request("/indexes/movies/settings/stop-words", json!(["live"])).await;
+    let update_id = response["updateId"].as_u64().unwrap();
+    server.wait_update_id(update_id).await;
+    let (response, _status_code) = server.get_update_status(update_id).await;
+    assert_eq!(response["settingsDiff"]["reindexed"], json!(false));
+    let (response, _status_code) = server.search("q=%22who%20live%20there%22").await;
+    assert_eq!(hits_ids(&response), vec![2]);
+
+    server.delete_request_async("/indexes/movies/settings/stop-words-in-phrases").await;
+    let (response, _status_code) = server.search("q=%22live%22").await;
+    assert_eq!(hits_ids(&response), Vec::<u64>::new());
+    let (response, _status_code) = server.search("q=who").await;
+    assert_eq!(hits_ids(&response), vec![1, 2]);
+}
@@ -0,0 +1,38 @@
+use serde_json::json;
+use tempdir::TempDir;
+
+mod common;
+
+#[actix_rt::test]
+async fn server_starts_from_a_snapshot() {
+    let snapshot_dir = TempDir::new("snapshots").unwrap();
+    let snapshot_path = snapshot_dir.path().to_str().unwrap().to_string();
+
+    let path = snapshot_path.clone();
+    let mut server = common::Server::with_options("movies", |opt| opt.snapshot_dir = path);
+
+    server.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    server
+        .add_or_replace_multiple_documents(json!([
+            { "id": 1, "title": "Carol" },
+            { "id": 2, "title": "Wonder Woman" },
+        ]))
+        .await;
+
+    let (response, status_code) = server.post_request("/snapshots", json!(null)).await;
+    assert_eq!(status_code, 201);
+    let uid = response["uid"].as_str().unwrap().to_string();
+
+    // the snapshot is taken while the server keeps accepting updates
+    server.add_or_replace_multiple_documents(json!([{ "id": 3, "title": "Her" }])).await;
+
+    let path = snapshot_dir.path().join(uid).to_str().unwrap().to_string();
+    let mut restored = common::Server::with_options("movies", |opt| opt.import_snapshot = Some(path));
+
+    let (response, status_code) = restored.get_document(2).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response, json!({ "id": 2, "title": "Wonder Woman" }));
+
+    let (_response, status_code) = restored.get_document(3).await;
+    assert_eq!(status_code, 404);
+}
@@ -0,0 +1,72 @@
+use serde_json::json;
+use std::time::Duration;
+use tempdir::TempDir;
+use tokio::time::delay_for;
+
+mod common;
+
+#[actix_rt::test]
+async fn standby_follows_primary_until_promoted() {
+    let ship_dir = TempDir::new("standby").unwrap();
+    let ship_path = ship_dir.path().to_str().unwrap().to_string();
+
+    let path = ship_path.clone();
+    let mut primary = common::Server::with_options("movies", |opt| opt.standby_ship_dir = Some(path));
+
+    let (response, status_code) = primary.post_request("/standby/snapshot", json!(null)).await;
+    assert_eq!(status_code, 200);
+    assert!(response["sequence"].is_u64());
+
+    primary.create_index(json!({ "uid": "movies", "primaryKey": "id" })).await;
+    primary
+        .add_or_replace_multiple_documents(json!([
+            { "id": 1, "title": "Carol" },
+            { "id": 2, "title": "Wonder Woman" },
+        ]))
+        .await;
+
+    let (response, status_code) = primary.get_request("/standby").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["role"], "primary");
+
+    let path = ship_path.clone();
+    let mut standby = common::Server::with_options("movies", |opt| opt.standby_of = Some(path));
+
+    let mut replicated = false;
+    for _ in 0..30 {
+        let (_response, status_code) = standby.get_request("/indexes/movies/documents/2").await;
+        if status_code == 200 {
+            replicated = true;
+            break;
+        }
+        delay_for(Duration::from_secs(1)).await;
+    }
+    assert!(replicated);
+
+    let (response, status_code) = standby.get_request("/standby").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["role"], "standby");
+
+    // the standby refuses the updates until it is promoted
+    let (_response, status_code) = standby.create_index(json!({ "uid": "series" })).await;
+    assert_eq!(status_code, 503);
+
+    let (response, status_code) = standby.post_request("/standby/promote", json!(null)).await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["role"], "promoted");
+
+    let (_response, status_code) = standby.create_index(json!({ "uid": "series" })).await;
+    assert_eq!(status_code, 201);
+}
+
+#[actix_rt::test]
+async fn promote_without_standby() {
+    let mut server = common::Server::with_uid("movies");
+
+    let (response, status_code) = server.get_request("/standby").await;
+    assert_eq!(status_code, 200);
+    assert_eq!(response["role"], "standalone");
+
+    let (_response, status_code) = server.post_request("/standby/promote", json!(null)).await;
+    assert_eq!(status_code, 400);
+}
@@ -5,6 +5,8 @@ mod schema;
 pub use error::{Error, SResult};
 pub use fields_map::FieldsMap;
 pub use schema::Schema;
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 use zerocopy::{AsBytes, FromBytes};
 
@@ -37,6 +39,51 @@ impl Into<u16> for IndexedPos {
     }
 }
 
+/// The type declared for a field, the values of the documents must be of this type.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Number,
+    /// An RFC 3339 date, or a `YYYY-MM-DD` day, ranked and filtered as a timestamp.
+    Date,
+    Bool,
+    /// A field analyzed outside of the engine, a list of `{ token, position, start, end }`
+    /// objects that are indexed as they are instead of being tokenized.
+    Tokens,
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldType::String => f.write_str("string"),
+            FieldType::Number => f.write_str("number"),
+            FieldType::Date => f.write_str("date"),
+            FieldType::Bool => f.write_str("bool"),
+            FieldType::Tokens => f.write_str("tokens"),
+        }
+    }
+}
+
+/// The flags declared for a field, they win over the settings listing the fields.
+/// A flag that is not declared leaves the field as the other settings decide.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct FieldFlags {
+    /// Whether the words of the field are indexed and searched.
+    #[serde(default)]
+    pub searchable: Option<bool>,
+    /// Whether the field is faceted, any field can be filtered on but only the faceted ones
+    /// can be used in the facet filters and counted.
+    #[serde(default)]
+    pub filterable: Option<bool>,
+    #[serde(default)]
+    pub displayed: Option<bool>,
+    /// Whether the values of the field are kept in the ranked map to sort by them.
+    #[serde(default)]
+    pub ranked: Option<bool>,
+}
+
 #[derive(Debug, Copy, Clone, Default, PartialOrd, Ord, PartialEq, Eq, Hash)]
 #[derive(Serialize, Deserialize)]
 #[derive(AsBytes, FromBytes)]
@@ -1,4 +1,4 @@
-use crate::{FieldsMap, FieldId, SResult, Error, IndexedPos};
+use crate::{FieldsMap, FieldId, FieldFlags, FieldType, SResult, Error, IndexedPos};
 use serde::{Serialize, Deserialize};
 use std::collections::{HashMap, HashSet};
 
@@ -14,6 +14,10 @@ pub struct Schema {
     indexed_map: HashMap<FieldId, IndexedPos>,
 
     accept_new_fields: bool,
+
+    field_types: HashMap<FieldId, FieldType>,
+
+    field_flags: HashMap<FieldId, FieldFlags>,
 }
 
 impl Schema {
@@ -26,6 +30,8 @@ impl Schema {
             indexed: Vec::new(),
             indexed_map: HashMap::new(),
             accept_new_fields: true,
+            field_types: HashMap::new(),
+            field_flags: HashMap::new(),
         }
     }
 
@@ -49,6 +55,8 @@ impl Schema {
             indexed,
             indexed_map,
             accept_new_fields: true,
+            field_types: HashMap::new(),
+            field_flags: HashMap::new(),
         }
     }
 
@@ -91,18 +99,24 @@ impl Schema {
         self.fields_map.insert(name)
     }
 
+    /// Inserts the field, a new field is indexed and displayed when the new fields are
+    /// accepted, unless its declared flags decide otherwise.
     pub fn insert_and_index(&mut self, name: &str) -> SResult<FieldId> {
         match self.fields_map.id(name) {
             Some(id) => {
                 Ok(id)
             }
             None => {
-                if self.accept_new_fields {
+                let id = if self.accept_new_fields {
                     self.set_indexed(name)?;
-                    self.set_displayed(name)
+                    self.set_displayed(name)?
                 } else {
-                    self.fields_map.insert(name)
+                    self.fields_map.insert(name)?
+                };
+                if let Some(flags) = self.field_flags.get(&id).copied() {
+                    self.apply_flags(id, flags);
                 }
+                Ok(id)
             }
         }
     }
@@ -249,4 +263,130 @@ impl Schema {
     pub fn set_accept_new_fields(&mut self, value: bool) {
         self.accept_new_fields = value;
     }
+
+    pub fn field_type(&self, id: FieldId) -> Option<FieldType> {
+        self.field_types.get(&id).copied()
+    }
+
+    pub fn field_types_name(&self) -> HashMap<&str, FieldType> {
+        self.field_types
+            .iter()
+            .filter_map(|(id, field_type)| Some((self.name(*id)?, *field_type)))
+            .collect()
+    }
+
+    /// Replaces the declared types, the fields that are not listed are no longer typed.
+    pub fn update_field_types<S: AsRef<str>>(&mut self, data: impl IntoIterator<Item = (S, FieldType)>) -> SResult<()> {
+        self.field_types.clear();
+        for (name, field_type) in data {
+            let id = self.insert(name.as_ref())?;
+            self.field_types.insert(id, field_type);
+        }
+        Ok(())
+    }
+
+    pub fn clear_field_types(&mut self) {
+        self.field_types.clear();
+    }
+
+    pub fn field_flags(&self, id: FieldId) -> Option<FieldFlags> {
+        self.field_flags.get(&id).copied()
+    }
+
+    pub fn field_flags_name(&self) -> HashMap<&str, FieldFlags> {
+        self.field_flags
+            .iter()
+            .filter_map(|(id, flags)| Some((self.name(*id)?, *flags)))
+            .collect()
+    }
+
+    /// Replaces the declared flags, the fields that are not listed no longer have flags
+    /// and keep their current state. The new fields are inserted like the fields of a document.
+    pub fn update_field_flags<S: AsRef<str>>(&mut self, data: impl IntoIterator<Item = (S, FieldFlags)>) -> SResult<()> {
+        self.field_flags.clear();
+        for (name, flags) in data {
+            let id = self.insert_and_index(name.as_ref())?;
+            self.field_flags.insert(id, flags);
+        }
+        self.apply_field_flags();
+        Ok(())
+    }
+
+    pub fn clear_field_flags(&mut self) {
+        self.field_flags.clear();
+    }
+
+    /// Makes the searchable, displayed and ranked fields follow the declared flags,
+    /// it must be called once the settings listing the fields have been changed.
+    pub fn apply_field_flags(&mut self) {
+        let flags: Vec<_> = self.field_flags.iter().map(|(id, flags)| (*id, *flags)).collect();
+        for (id, flags) in flags {
+            self.apply_flags(id, flags);
+        }
+    }
+
+    fn apply_flags(&mut self, id: FieldId, flags: FieldFlags) {
+        match flags.searchable {
+            Some(true) if !self.indexed_map.contains_key(&id) => {
+                let pos = self.indexed.len() as u16;
+                self.indexed.push(id);
+                self.indexed_map.insert(id, pos.into());
+            }
+            Some(false) if self.indexed_map.contains_key(&id) => {
+                // the positions of the following fields change
+                self.indexed.retain(|x| *x != id);
+                self.indexed_map = self.indexed.iter().enumerate().map(|(pos, id)| (*id, (pos as u16).into())).collect();
+            }
+            _ => (),
+        }
+
+        match flags.displayed {
+            Some(true) => { self.displayed.insert(id); }
+            Some(false) => { self.displayed.remove(&id); }
+            None => (),
+        }
+
+        match flags.ranked {
+            Some(true) => { self.ranked.insert(id); }
+            Some(false) => { self.ranked.remove(&id); }
+            None => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declared_flags_win() {
+        let mut schema = Schema::with_primary_key("id");
+        schema.insert_and_index("title").unwrap();
+
+        let flags = FieldFlags { searchable: Some(false), filterable: Some(true), ..FieldFlags::default() };
+        schema.update_field_flags(vec![("internal_tag", flags)]).unwrap();
+
+        let id = schema.id("internal_tag").unwrap();
+        assert!(schema.is_indexed(id).is_none());
+        assert!(schema.is_displayed(id));
+        assert_eq!(schema.field_flags(id), Some(flags));
+
+        // the settings listing all the fields do not override the flags
+        schema.set_all_fields_as_indexed();
+        schema.apply_field_flags();
+        assert_eq!(schema.indexed_name(), vec!["id", "title"]);
+
+        let title = schema.id("title").unwrap();
+        let flags = FieldFlags { searchable: Some(false), ..FieldFlags::default() };
+        schema.update_field_flags(vec![("id", flags)]).unwrap();
+        assert_eq!(schema.is_indexed(title), Some(&IndexedPos(0)));
+
+        // a new field with flags is inserted as declared
+        schema.clear_field_flags();
+        let flags = FieldFlags { displayed: Some(false), ..FieldFlags::default() };
+        schema.update_field_flags(vec![("secret", flags)]).unwrap();
+        let id = schema.id("secret").unwrap();
+        assert!(schema.is_indexed(id).is_some());
+        assert!(!schema.is_displayed(id));
+    }
 }
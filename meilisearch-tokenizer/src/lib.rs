@@ -2,6 +2,7 @@ use self::SeparatorCategory::*;
 use deunicode::deunicode_char;
 use slice_group_by::StrGroupBy;
 use std::iter::Peekable;
+use std::ops::Range;
 
 pub fn is_cjk(c: char) -> bool {
     (c >= '\u{1100}' && c <= '\u{11ff}')  // Hangul Jamo
@@ -22,6 +23,27 @@ pub fn is_cjk(c: char) -> bool {
         || (c >= '\u{ff00}' && c <= '\u{ffef}') // Full-width roman characters and half-width katakana
 }
 
+/// Returns `true` if the character belongs to a script that doesn't separate
+/// its words by spaces, the texts of these scripts are segmented by characters.
+pub fn is_unspaced(c: char) -> bool {
+    is_cjk(c)
+        || (c >= '\u{0e00}' && c <= '\u{0e7f}') // Thai
+        || (c >= '\u{0e80}' && c <= '\u{0eff}') // Lao
+        || (c >= '\u{1000}' && c <= '\u{109f}') // Myanmar
+        || (c >= '\u{1780}' && c <= '\u{17ff}') // Khmer
+}
+
+/// The vowels and tone marks of the Thai and Lao scripts are combined
+/// with the previous character, they can't be a word on their own.
+fn is_combining_mark(c: char) -> bool {
+    c == '\u{0e31}'
+        || (c >= '\u{0e34}' && c <= '\u{0e3a}')
+        || (c >= '\u{0e47}' && c <= '\u{0e4e}')
+        || c == '\u{0eb1}'
+        || (c >= '\u{0eb4}' && c <= '\u{0ebc}')
+        || (c >= '\u{0ec8}' && c <= '\u{0ecd}')
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum SeparatorCategory {
     Soft,
@@ -63,15 +85,15 @@ fn classify_separator(c: char) -> Option<SeparatorCategory> {
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum CharCategory {
     Separator(SeparatorCategory),
-    Cjk,
+    Unspaced,
     Other,
 }
 
 fn classify_char(c: char) -> CharCategory {
     if let Some(category) = classify_separator(c) {
         CharCategory::Separator(category)
-    } else if is_cjk(c) {
-        CharCategory::Cjk
+    } else if is_unspaced(c) {
+        CharCategory::Unspaced
     } else {
         CharCategory::Other
     }
@@ -83,7 +105,8 @@ fn is_str_word(s: &str) -> bool {
 
 fn same_group_category(a: char, b: char) -> bool {
     match (classify_char(a), classify_char(b)) {
-        (CharCategory::Cjk, _) | (_, CharCategory::Cjk) => false,
+        (CharCategory::Unspaced, CharCategory::Unspaced) => is_combining_mark(b),
+        (CharCategory::Unspaced, _) | (_, CharCategory::Unspaced) => false,
         (CharCategory::Separator(_), CharCategory::Separator(_)) => true,
         (a, b) => a == b,
     }
@@ -169,6 +192,81 @@ impl<'a> Iterator for Tokenizer<'a> {
     }
 }
 
+/// A word made of tokens joined by single separators, at least one of them containing a digit,
+/// like `2019-05`, `3.5mm` or `ISO-9001`. The dots and the commas only join digits, they
+/// otherwise end a sentence or separate the items of a list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Compound<'a> {
+    pub word: &'a str,
+    pub tokens: Vec<Token<'a>>,
+}
+
+impl Compound<'_> {
+    /// Returns `true` if the compound is a number written with separators, like `3.5` or `1,000`.
+    pub fn is_number(&self) -> bool {
+        self.word.chars().all(|c| c.is_ascii_digit() || c == '.' || c == ',')
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenGroup<'a> {
+    Token(Token<'a>),
+    Compound(Compound<'a>),
+}
+
+/// Tokenizes the text and groups the tokens that form a compound.
+pub fn group_compounds(text: &str) -> Vec<TokenGroup> {
+    let mut groups = Vec::new();
+    let mut run: Vec<Token> = Vec::new();
+
+    for token in Tokenizer::new(text) {
+        let joined = run.last().map_or(false, |last| is_joined(text, last, &token));
+        if !joined {
+            flush_compound(text, &mut run, &mut groups);
+        }
+        run.push(token);
+    }
+
+    flush_compound(text, &mut run, &mut groups);
+    groups
+}
+
+fn flush_compound<'a>(text: &'a str, run: &mut Vec<Token<'a>>, groups: &mut Vec<TokenGroup<'a>>) {
+    let has_digit = run.iter().any(|t| t.word.chars().any(|c| c.is_ascii_digit()));
+    match (run.first(), run.last()) {
+        (Some(first), Some(last)) if run.len() > 1 && has_digit => {
+            let start = byte_range(text, first.word).start;
+            let end = byte_range(text, last.word).end;
+            let tokens = std::mem::replace(run, Vec::new());
+            groups.push(TokenGroup::Compound(Compound { word: &text[start..end], tokens }));
+        }
+        _ => groups.extend(run.drain(..).map(TokenGroup::Token)),
+    }
+}
+
+/// The range of bytes of a word in the text it was extracted from.
+fn byte_range(text: &str, word: &str) -> Range<usize> {
+    let start = word.as_ptr() as usize - text.as_ptr() as usize;
+    start..start + word.len()
+}
+
+fn is_joined(text: &str, left: &Token, right: &Token) -> bool {
+    let is_part = |word: &str| word.chars().all(|c| c.is_alphanumeric() && !is_unspaced(c));
+    if !is_part(left.word) || !is_part(right.word) {
+        return false;
+    }
+
+    let between = &text[byte_range(text, left.word).end..byte_range(text, right.word).start];
+    let ends_with_digit = left.word.chars().last().map_or(false, |c| c.is_ascii_digit());
+    let starts_with_digit = right.word.chars().next().map_or(false, |c| c.is_ascii_digit());
+
+    match between {
+        "-" | "_" | "/" => true,
+        "." | "," => ends_with_digit && starts_with_digit,
+        _ => false,
+    }
+}
+
 pub struct SeqTokenizer<'a, I>
 where
     I: Iterator<Item = &'a str>,
@@ -508,4 +606,77 @@ mod tests {
         );
         assert_eq!(tokenizer.next(), None);
     }
+
+    #[test]
+    fn compounds() {
+        let groups = group_compounds("ISO-9001 compliant, 3.5mm jack. Well-known 2019");
+        let words: Vec<_> = groups
+            .iter()
+            .map(|group| match group {
+                TokenGroup::Token(token) => (token.word, false),
+                TokenGroup::Compound(compound) => (compound.word, true),
+            })
+            .collect();
+
+        assert_eq!(
+            words,
+            vec![
+                ("ISO-9001", true),
+                ("compliant", false),
+                ("3.5mm", true),
+                ("jack", false),
+                ("Well", false),
+                ("known", false),
+                ("2019", false),
+            ]
+        );
+
+        match &groups[2] {
+            TokenGroup::Compound(compound) => {
+                assert!(!compound.is_number());
+                let parts: Vec<_> = compound.tokens.iter().map(|t| t.word).collect();
+                assert_eq!(parts, vec!["3", "5mm"]);
+            }
+            group => panic!("unexpected group {:?}", group),
+        }
+
+        // the dots and the commas only join digits
+        let groups = group_compounds("1,000.50 and v2.a, 3,b");
+        assert_eq!(groups.len(), 6);
+        match &groups[0] {
+            TokenGroup::Compound(compound) => assert!(compound.is_number()),
+            group => panic!("unexpected group {:?}", group),
+        }
+    }
+
+    #[test]
+    fn unspaced_scripts() {
+        let mut tokenizer = Tokenizer::new("hello กิน");
+
+        assert_eq!(
+            tokenizer.next(),
+            Some(Token {
+                word: "hello",
+                word_index: 0,
+                char_index: 0
+            })
+        );
+        assert_eq!(
+            tokenizer.next(),
+            Some(Token {
+                word: "กิ",
+                word_index: 1,
+                char_index: 6
+            })
+        );
+        assert_eq!(
+            tokenizer.next(),
+            Some(Token {
+                word: "น",
+                word_index: 2,
+                char_index: 8
+            })
+        );
+        assert_eq!(tokenizer.next(), None);
+    }
 }